@@ -0,0 +1,351 @@
+//! Results of a backtest run and their CSV export
+//!
+//! A `BacktestResult` holds the equity curve, with the drawdown from the
+//! running peak at each point, and the round-trip trades. Both export to CSV
+//! files with stable headers for charting. `bootstrap_metrics` puts
+//! confidence intervals on Sharpe, CAGR and max drawdown by block-resampling
+//! the bar returns of the equity curve.
+
+use crate::rng::RngSource;
+use crate::stats::{max_drawdown, sharpe_ratio};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::info;
+use std::fs;
+use std::path::Path;
+
+/// Column header for `BacktestResult::export_equity_curve_csv`
+pub const EQUITY_CSV_HEADER: &str = "timestamp,equity,drawdown";
+
+/// Column header for `BacktestResult::export_trades_csv`
+pub const BACKTEST_TRADE_CSV_HEADER: &str =
+    "entry_time,exit_time,entry_price,exit_price,return,pnl";
+
+/// Bars per year used to annualize bootstrapped metrics (daily bars)
+const PERIODS_PER_YEAR: f64 = 252.0;
+
+/// Consecutive returns drawn together, keeping short-range autocorrelation
+const BOOTSTRAP_BLOCK_LENGTH: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquityPoint {
+    pub timestamp: DateTime<Utc>,
+    pub equity: f64,
+    pub drawdown: f64, // Fraction below the running peak, 0 at a new high
+}
+
+/// One round trip, entered and exited at bar closes
+#[derive(Debug, Clone, PartialEq)]
+pub struct BacktestTrade {
+    pub entry_time: DateTime<Utc>,
+    pub exit_time: DateTime<Utc>,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    pub pnl: f64,
+}
+
+impl BacktestTrade {
+    pub fn return_pct(&self) -> f64 {
+        self.exit_price / self.entry_price - 1.0
+    }
+}
+
+/// 5th, 50th and 95th percentiles of a bootstrapped metric
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfidenceInterval {
+    pub p5: f64,
+    pub p50: f64,
+    pub p95: f64,
+}
+
+impl ConfidenceInterval {
+    fn from_samples(mut samples: Vec<f64>) -> Self {
+        samples.sort_by(f64::total_cmp);
+        let percentile = |p: f64| samples[(p * (samples.len() - 1) as f64).round() as usize];
+        Self {
+            p5: percentile(0.05),
+            p50: percentile(0.50),
+            p95: percentile(0.95),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BootstrapMetrics {
+    pub resamples: usize,
+    pub sharpe: ConfidenceInterval, // Annualized, no risk-free rate
+    pub cagr: ConfidenceInterval,   // Compound annual growth rate
+    pub max_drawdown: ConfidenceInterval, // Fraction below the running peak
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BacktestResult {
+    pub equity_curve: Vec<EquityPoint>,
+    pub trades: Vec<BacktestTrade>,
+    peak_equity: f64,
+}
+
+impl BacktestResult {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an equity point, measuring its drawdown from the peak so far
+    pub fn record_equity(&mut self, timestamp: DateTime<Utc>, equity: f64) {
+        self.peak_equity = self.peak_equity.max(equity);
+        let drawdown = if self.peak_equity > 0.0 {
+            1.0 - equity / self.peak_equity
+        } else {
+            0.0
+        };
+        self.equity_curve.push(EquityPoint {
+            timestamp,
+            equity,
+            drawdown,
+        });
+    }
+
+    pub fn record_trade(&mut self, trade: BacktestTrade) {
+        self.trades.push(trade);
+    }
+
+    pub fn max_drawdown(&self) -> f64 {
+        self.equity_curve
+            .iter()
+            .map(|point| point.drawdown)
+            .fold(0.0, f64::max)
+    }
+
+    /// Bar-over-bar returns of the equity curve
+    pub fn returns(&self) -> Vec<f64> {
+        self.equity_curve
+            .windows(2)
+            .filter(|pair| pair[0].equity > 0.0)
+            .map(|pair| pair[1].equity / pair[0].equity - 1.0)
+            .collect()
+    }
+
+    /// Confidence intervals from `n_resamples` circular block bootstraps
+    ///
+    /// Each resample stitches randomly placed blocks of the bar returns into
+    /// a series as long as the original. None with no resamples or fewer
+    /// than two returns.
+    pub fn bootstrap_metrics(
+        &self,
+        n_resamples: usize,
+        rng: &RngSource,
+    ) -> Option<BootstrapMetrics> {
+        let returns = self.returns();
+        if n_resamples == 0 || returns.len() < 2 {
+            return None;
+        }
+
+        let mut sharpes = Vec::with_capacity(n_resamples);
+        let mut cagrs = Vec::with_capacity(n_resamples);
+        let mut drawdowns = Vec::with_capacity(n_resamples);
+        for _ in 0..n_resamples {
+            let mut sample = Vec::with_capacity(returns.len());
+            while sample.len() < returns.len() {
+                let start = (rng.uniform() * returns.len() as f64) as usize;
+                sample.extend(
+                    (start..start + BOOTSTRAP_BLOCK_LENGTH)
+                        .map(|i| returns[i % returns.len()])
+                        .take(returns.len() - sample.len()),
+                );
+            }
+
+            let mut equity = vec![1.0];
+            for r in &sample {
+                equity.push(equity[equity.len() - 1] * (1.0 + r));
+            }
+            let years = sample.len() as f64 / PERIODS_PER_YEAR;
+            sharpes.push(sharpe_ratio(&sample, 0.0, PERIODS_PER_YEAR).unwrap_or(0.0));
+            cagrs.push(equity[equity.len() - 1].max(0.0).powf(1.0 / years) - 1.0);
+            drawdowns.push(max_drawdown(&equity).unwrap_or(0.0));
+        }
+
+        Some(BootstrapMetrics {
+            resamples: n_resamples,
+            sharpe: ConfidenceInterval::from_samples(sharpes),
+            cagr: ConfidenceInterval::from_samples(cagrs),
+            max_drawdown: ConfidenceInterval::from_samples(drawdowns),
+        })
+    }
+
+    /// Write the equity curve to a CSV file, one row per bar
+    pub fn export_equity_curve_csv(&self, path: &Path) -> Result<()> {
+        let mut csv = String::from(EQUITY_CSV_HEADER);
+        csv.push('\n');
+        for point in &self.equity_curve {
+            let fields = [
+                point.timestamp.to_rfc3339(),
+                point.equity.to_string(),
+                point.drawdown.to_string(),
+            ];
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+
+        fs::write(path, csv)?;
+        info!(
+            "Exported {} equity points to {}",
+            self.equity_curve.len(),
+            path.display()
+        );
+        Ok(())
+    }
+
+    /// Write the round-trip trades to a CSV file in entry order
+    pub fn export_trades_csv(&self, path: &Path) -> Result<()> {
+        let mut csv = String::from(BACKTEST_TRADE_CSV_HEADER);
+        csv.push('\n');
+        for trade in &self.trades {
+            let fields = [
+                trade.entry_time.to_rfc3339(),
+                trade.exit_time.to_rfc3339(),
+                trade.entry_price.to_string(),
+                trade.exit_price.to_string(),
+                trade.return_pct().to_string(),
+                trade.pnl.to_string(),
+            ];
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+
+        fs::write(path, csv)?;
+        info!(
+            "Exported {} backtest trades to {}",
+            self.trades.len(),
+            path.display()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signals::SignalWeights;
+    use crate::walk_forward::ParameterSet;
+    use crate::walk_forward::tests::daily_backtest;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn test_drawdown_from_running_peak() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap();
+        let mut result = BacktestResult::new();
+        for (day, equity) in [100.0, 110.0, 99.0, 120.0].into_iter().enumerate() {
+            result.record_equity(start + Duration::days(day as i64), equity);
+        }
+
+        let drawdowns: Vec<f64> = result.equity_curve.iter().map(|p| p.drawdown).collect();
+        assert_eq!(drawdowns[..2], [0.0, 0.0]);
+        assert!((drawdowns[2] - 0.1).abs() < 1e-12);
+        assert_eq!(drawdowns[3], 0.0);
+        assert!((result.max_drawdown() - 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_export_backtest_csvs() {
+        // Rallies, sells off, then rallies again: two round trips
+        let mut prices = vec![100.0];
+        for i in 1..30 {
+            let r = if (10..18).contains(&i) { -0.02 } else { 0.01 };
+            prices.push(prices[i - 1] * (1.0 + r));
+        }
+        let params = ParameterSet {
+            lookback_period: 3,
+            momentum_threshold: 0.0,
+            signal_weights: SignalWeights {
+                momentum: 1.0,
+                breakout: 0.0,
+                ..SignalWeights::default()
+            },
+        };
+        let result = daily_backtest(prices)
+            .run(&params, 0..30, 10_000.0)
+            .unwrap();
+        assert_eq!(result.equity_curve.len(), 30);
+        assert_eq!(result.trades.len(), 2);
+
+        let dir = std::env::temp_dir();
+        let equity_path = dir.join(format!("equity_{}.csv", std::process::id()));
+        let trades_path = dir.join(format!("backtest_trades_{}.csv", std::process::id()));
+        result.export_equity_curve_csv(&equity_path).unwrap();
+        result.export_trades_csv(&trades_path).unwrap();
+
+        let parse_time = |field: &str| DateTime::parse_from_rfc3339(field).unwrap();
+        let equity = fs::read_to_string(&equity_path).unwrap();
+        let mut lines = equity.lines();
+        assert_eq!(lines.next(), Some(EQUITY_CSV_HEADER));
+        let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+        assert_eq!(rows.len(), result.equity_curve.len());
+        assert!(rows.iter().all(|row| row.len() == 3));
+        assert!(
+            rows.windows(2)
+                .all(|pair| parse_time(pair[0][0]) < parse_time(pair[1][0]))
+        );
+        assert_eq!(rows[0][1], "10000");
+        let max_drawdown = rows
+            .iter()
+            .map(|row| row[2].parse::<f64>().unwrap())
+            .fold(0.0, f64::max);
+        assert_eq!(max_drawdown, result.max_drawdown());
+        assert!(max_drawdown > 0.0);
+
+        let trades = fs::read_to_string(&trades_path).unwrap();
+        let mut lines = trades.lines();
+        assert_eq!(lines.next(), Some(BACKTEST_TRADE_CSV_HEADER));
+        let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|row| row.len() == 6));
+        assert!(
+            rows.iter()
+                .all(|row| parse_time(row[0]) < parse_time(row[1]))
+        );
+        assert!(parse_time(rows[0][1]) <= parse_time(rows[1][0]));
+
+        let _ = fs::remove_file(equity_path);
+        let _ = fs::remove_file(trades_path);
+    }
+
+    #[test]
+    fn test_bootstrap_median_sharpe_near_point_estimate() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap();
+        let noise = RngSource::from_seed(1);
+        let mut result = BacktestResult::new();
+        let mut equity = 10_000.0;
+        result.record_equity(start, equity);
+        for day in 1..=504 {
+            equity *= 1.0 + 0.0008 + 0.01 * noise.standard_normal();
+            result.record_equity(start + Duration::days(day), equity);
+        }
+
+        let returns = result.returns();
+        assert_eq!(returns.len(), 504);
+        let point_sharpe = sharpe_ratio(&returns, 0.0, PERIODS_PER_YEAR).unwrap();
+
+        let metrics = result
+            .bootstrap_metrics(1000, &RngSource::from_seed(42))
+            .unwrap();
+        assert_eq!(metrics.resamples, 1000);
+        assert!((metrics.sharpe.p50 - point_sharpe).abs() < 0.25);
+        for interval in [metrics.sharpe, metrics.cagr, metrics.max_drawdown] {
+            assert!(interval.p5 < interval.p50 && interval.p50 < interval.p95);
+        }
+        assert!(metrics.max_drawdown.p5 > 0.0);
+
+        // Same seed, same intervals
+        let again = result
+            .bootstrap_metrics(1000, &RngSource::from_seed(42))
+            .unwrap();
+        assert_eq!(again, metrics);
+
+        assert!(
+            BacktestResult::new()
+                .bootstrap_metrics(100, &noise)
+                .is_none()
+        );
+        assert!(result.bootstrap_metrics(0, &noise).is_none());
+    }
+}
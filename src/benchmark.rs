@@ -0,0 +1,87 @@
+//! Benchmark price series for relative performance (e.g. beta and alpha
+//! versus SPY)
+
+use crate::market_data::MarketDataHandler;
+use chrono::{DateTime, Utc};
+
+/// Price series of a reference instrument
+#[derive(Debug, Clone)]
+pub struct Benchmark {
+    pub symbol: String,
+    prices: Vec<(DateTime<Utc>, f64)>,
+}
+
+impl Benchmark {
+    pub fn new(symbol: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            prices: Vec::new(),
+        }
+    }
+
+    /// Copy the benchmark's price history out of the market data handler
+    pub fn from_market_data(symbol: &str, market_data: &MarketDataHandler) -> Option<Self> {
+        let history = market_data.get_price_history(symbol)?;
+        let mut benchmark = Self::new(symbol);
+        for &(timestamp, price) in &history.prices {
+            benchmark.add_price(timestamp, price);
+        }
+        Some(benchmark)
+    }
+
+    /// Append a price, ignoring samples that are not newer than the last one
+    pub fn add_price(&mut self, timestamp: DateTime<Utc>, price: f64) {
+        if self.prices.last().is_none_or(|(last, _)| timestamp > *last) {
+            self.prices.push((timestamp, price));
+        }
+    }
+
+    pub fn prices(&self) -> &[(DateTime<Utc>, f64)] {
+        &self.prices
+    }
+
+    /// Period-over-period returns, oldest first
+    pub fn returns(&self) -> Vec<f64> {
+        self.prices
+            .windows(2)
+            .filter(|pair| pair[0].1 > 0.0)
+            .map(|pair| pair[1].1 / pair[0].1 - 1.0)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_returns_from_market_data() {
+        let mut handler = MarketDataHandler::new();
+        handler.register_symbol(1, "SPY".to_string());
+        let start = time::OffsetDateTime::now_utc() - time::Duration::days(3);
+        for (day, price) in [100.0, 110.0, 99.0].into_iter().enumerate() {
+            handler.add_historical_price("SPY", start + time::Duration::days(day as i64), price);
+        }
+
+        let benchmark = Benchmark::from_market_data("SPY", &handler).unwrap();
+        let returns = benchmark.returns();
+        assert_eq!(returns.len(), 2);
+        assert!((returns[0] - 0.1).abs() < 1e-12);
+        assert!((returns[1] + 0.1).abs() < 1e-12);
+
+        assert!(Benchmark::from_market_data("QQQ", &handler).is_none());
+    }
+
+    #[test]
+    fn test_out_of_order_prices_ignored() {
+        let now = Utc::now();
+        let mut benchmark = Benchmark::new("SPY");
+        benchmark.add_price(now, 100.0);
+        benchmark.add_price(now - Duration::days(1), 90.0);
+        benchmark.add_price(now + Duration::days(1), 105.0);
+
+        assert_eq!(benchmark.prices().len(), 2);
+        assert!((benchmark.returns()[0] - 0.05).abs() < 1e-12);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::market_data::{MarketDataHandler, TimeFrame};
+use crate::market_data::{AggregationMethod, MarketDataHandler, TimeFrame};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
@@ -47,9 +47,10 @@ pub enum VolatilityRegime {
 
 #[derive(Debug, Clone)]
 pub struct BollingerCalculator {
-    pub period: usize,          // Moving average period (default 20)
-    pub std_multiplier: f64,    // Standard deviation multiplier (default 2.0)
-    pub squeeze_threshold: f64, // Bandwidth threshold for squeeze detection
+    pub period: usize,                  // Moving average period (default 20)
+    pub std_multiplier: f64,            // Standard deviation multiplier (default 2.0)
+    pub squeeze_threshold: f64,         // Bandwidth threshold for squeeze detection
+    pub aggregation: AggregationMethod, // How timeframe signals combine into the composite
 }
 
 impl Default for BollingerCalculator {
@@ -64,6 +65,7 @@ impl BollingerCalculator {
             period: 20,
             std_multiplier: 2.0,
             squeeze_threshold: 0.1, // 10% bandwidth threshold
+            aggregation: AggregationMethod::default(),
         }
     }
 
@@ -72,9 +74,15 @@ impl BollingerCalculator {
             period,
             std_multiplier,
             squeeze_threshold,
+            aggregation: AggregationMethod::default(),
         }
     }
 
+    pub fn with_aggregation(mut self, aggregation: AggregationMethod) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
+
     /// Calculate Bollinger Bands for a single timeframe
     pub fn calculate_bollinger_bands(&self, prices: &[f64]) -> Option<BollingerBands> {
         if prices.len() < self.period {
@@ -258,8 +266,9 @@ impl BollingerCalculator {
             return None;
         }
 
-        // Calculate composite signal as weighted average
-        let composite_signal = signal_strengths.iter().sum::<f64>() / signal_strengths.len() as f64;
+        // Combine timeframes with equal weight
+        let weighted: Vec<(f64, f64)> = signal_strengths.iter().map(|&s| (s, 1.0)).collect();
+        let composite_signal = self.aggregation.aggregate(&weighted)?;
 
         // Find dominant signal (strongest absolute value)
         let dominant_signal = timeframe_signals
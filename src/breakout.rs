@@ -1,4 +1,4 @@
-use crate::market_data::{MarketDataHandler, TimeFrame};
+use crate::market_data::{AggregationMethod, MarketDataHandler, TimeFrame};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
@@ -38,6 +38,8 @@ pub struct BreakoutCalculator {
     pub volatility_multiplier: f64,
     /// Lookback periods for breakout detection
     pub lookback_periods: Vec<usize>,
+    /// How timeframe signals combine into the composite
+    pub aggregation: AggregationMethod,
 }
 
 impl Default for BreakoutCalculator {
@@ -52,6 +54,7 @@ impl BreakoutCalculator {
             min_breakout_threshold: 0.01,            // 1% minimum breakout
             volatility_multiplier: 1.5,              // 1.5x volatility for breakout threshold
             lookback_periods: vec![10, 20, 50, 100], // Common breakout periods
+            aggregation: AggregationMethod::default(),
         }
     }
 
@@ -64,9 +67,15 @@ impl BreakoutCalculator {
             min_breakout_threshold: min_threshold,
             volatility_multiplier: vol_multiplier,
             lookback_periods,
+            aggregation: AggregationMethod::default(),
         }
     }
 
+    pub fn with_aggregation(mut self, aggregation: AggregationMethod) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
+
     /// Calculate breakout signal for a single timeframe
     pub fn calculate_breakout_signal(
         &self,
@@ -184,8 +193,9 @@ impl BreakoutCalculator {
             return None;
         }
 
-        // Calculate composite signal as weighted average
-        let composite_signal = signal_strengths.iter().sum::<f64>() / signal_strengths.len() as f64;
+        // Combine timeframes with equal weight
+        let weighted: Vec<(f64, f64)> = signal_strengths.iter().map(|&s| (s, 1.0)).collect();
+        let composite_signal = self.aggregation.aggregate(&weighted)?;
 
         // Find strongest signal
         let strongest_signal = timeframe_signals
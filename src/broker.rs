@@ -0,0 +1,1146 @@
+//! Order routing over either the live TWS connection or a local fill simulator
+//!
+//! `SimulatedBroker` runs the full live pipeline against real market data but
+//! fills orders locally at the latest price adjusted by the cost model, so the
+//! portfolio sync sees modeled positions and cash instead of IBKR's.
+//! `MockBroker` records orders without touching any market data, for tests
+//! that drive the trading cycle deterministically.
+
+use crate::connection::{AccountPosition, TwsClient};
+use crate::costs::CostModel;
+use crate::market_data::{MarketDataEvent, MarketDataHandler};
+use crate::order_types::{BracketLevels, EnhancedOrderBuilder, OrderAction};
+use crate::orders::{Order, OrderSignal, OrderStatus};
+use crate::portfolio::Portfolio;
+use crate::security_types::{SecurityInfo, SecurityType};
+use anyhow::{Result, anyhow};
+use ibapi::contracts::Contract;
+use log::{error, info, warn};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tokio::sync::{Mutex, mpsc};
+
+const SIMULATED_FIRST_ORDER_ID: i32 = 2_000_000;
+
+/// Time the broker gets to confirm a cancel before a replacement is given up
+const CANCEL_CONFIRM_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time between status checks while waiting for a cancel to be confirmed
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The order, account and subscription calls the trading loop makes
+pub trait Broker {
+    fn place_order(&self, signal: &OrderSignal) -> impl Future<Output = Result<i32>>;
+
+    fn get_positions(&self) -> impl Future<Output = Result<Vec<AccountPosition>>>;
+
+    fn get_account_summary(&self) -> impl Future<Output = Result<HashMap<String, f64>>>;
+
+    fn subscribe_realtime_data(
+        &self,
+        symbol: &str,
+        req_id: i32,
+        tx: mpsc::Sender<MarketDataEvent>,
+    ) -> impl Future<Output = Result<()>>;
+
+    fn unsubscribe_realtime_data(&self, req_id: i32) -> impl Future<Output = Result<()>>;
+
+    /// Enter with a limit order at the signal's price plus linked take-profit
+    /// and stop legs
+    ///
+    /// Returns broker IDs as `[parent, take_profit, stop_loss]`.
+    fn place_bracket_order(
+        &self,
+        signal: &OrderSignal,
+        levels: &BracketLevels,
+    ) -> impl Future<Output = Result<Vec<i32>>>;
+
+    fn cancel_order(&self, order_id: i32) -> impl Future<Output = Result<()>>;
+
+    /// Cancel every working order on the account
+    fn cancel_all_orders(&self) -> impl Future<Output = Result<()>>;
+
+    /// Close every position in `portfolio` with an offsetting market order
+    ///
+    /// Keeps going past failed symbols so as much as possible is closed, then
+    /// reports them. Returns the order IDs placed.
+    fn flatten_all_positions(
+        &self,
+        portfolio: &Portfolio,
+    ) -> impl Future<Output = Result<Vec<i32>>> {
+        let signals = flatten_signals(portfolio);
+        async move {
+            let mut order_ids = Vec::with_capacity(signals.len());
+            let mut failed = Vec::new();
+            for signal in signals {
+                match self.place_order(&signal).await {
+                    Ok(order_id) => {
+                        warn!(
+                            "Flatten: {} {} {} (order #{})",
+                            signal.action, signal.quantity, signal.symbol, order_id
+                        );
+                        order_ids.push(order_id);
+                    }
+                    Err(e) => {
+                        error!("Flatten: failed to close {}: {}", signal.symbol, e);
+                        failed.push(signal.symbol);
+                    }
+                }
+            }
+            if !failed.is_empty() {
+                return Err(anyhow!(
+                    "Failed to flatten {:?} after placing orders {:?}",
+                    failed,
+                    order_ids
+                ));
+            }
+            Ok(order_ids)
+        }
+    }
+
+    /// Cancel all working orders, then flatten every position
+    ///
+    /// Flattening goes ahead even when the cancel request fails.
+    fn emergency_stop(&self, portfolio: &Portfolio) -> impl Future<Output = Result<Vec<i32>>> {
+        async move {
+            warn!("EMERGENCY STOP: cancelling all orders and flattening positions");
+            if let Err(e) = self.cancel_all_orders().await {
+                error!("Emergency stop: failed to cancel open orders: {}", e);
+            }
+            self.flatten_all_positions(portfolio).await
+        }
+    }
+
+    /// Current status of a previously placed order
+    fn order_status(&self, order_id: i32) -> impl Future<Output = Result<OrderStatus>>;
+
+    /// The broker's status for every order it knows about, by broker order ID
+    fn poll_order_statuses(&self) -> impl Future<Output = Result<HashMap<i32, OrderStatus>>>;
+
+    /// Cancel a working order and, once the cancel is confirmed, place
+    /// `signal` in its place
+    ///
+    /// Returns the replacement's broker ID, or None when the order filled
+    /// before the cancel took effect.
+    fn replace_order(
+        &self,
+        order_id: i32,
+        signal: &OrderSignal,
+    ) -> impl Future<Output = Result<Option<i32>>> {
+        replace_after_cancel(self, order_id, signal)
+    }
+
+    /// Rest a take-profit and a stop that close an open position, where a
+    /// fill on one cancels the other
+    ///
+    /// `quantity` is the signed position. Returns broker IDs as
+    /// `[take_profit, stop_loss]`.
+    fn place_protective_orders(
+        &self,
+        symbol: &str,
+        quantity: f64,
+        levels: &BracketLevels,
+    ) -> impl Future<Output = Result<Vec<i32>>>;
+
+    /// Buy or sell about `notional` worth of `symbol` at market
+    ///
+    /// See `notional_order_signal` for how the quantity is sized.
+    fn place_notional_order(
+        &self,
+        market_data: &MarketDataHandler,
+        symbol: &str,
+        notional: f64,
+        action: OrderAction,
+    ) -> impl Future<Output = Result<i32>> {
+        let signal = notional_order_signal(market_data, symbol, notional, action);
+        async move { self.place_order(&signal?).await }
+    }
+
+    /// Place a previously created order at market
+    fn place_order_from_order(&self, order: &Order) -> impl Future<Output = Result<i32>> {
+        let signal = OrderSignal {
+            symbol: order.symbol.clone(),
+            action: order.action.clone(),
+            quantity: order.quantity,
+            price: 0.0, // Market price will be used
+            order_type: order.order_type.clone(),
+            limit_price: order.limit_price,
+            reason: format!("Order #{}", order.id),
+            security_info: order.security_info.clone(),
+        };
+        async move { self.place_order(&signal).await }
+    }
+}
+
+impl Broker for TwsClient {
+    async fn place_order(&self, signal: &OrderSignal) -> Result<i32> {
+        TwsClient::place_order(self, signal).await
+    }
+
+    async fn get_positions(&self) -> Result<Vec<AccountPosition>> {
+        TwsClient::get_positions(self).await
+    }
+
+    async fn get_account_summary(&self) -> Result<HashMap<String, f64>> {
+        TwsClient::get_account_summary(self).await
+    }
+
+    async fn place_order_from_order(&self, order: &Order) -> Result<i32> {
+        TwsClient::place_order_from_order(self, order).await
+    }
+
+    async fn subscribe_realtime_data(
+        &self,
+        symbol: &str,
+        req_id: i32,
+        tx: mpsc::Sender<MarketDataEvent>,
+    ) -> Result<()> {
+        TwsClient::subscribe_realtime_data(self, symbol, req_id, tx).await
+    }
+
+    async fn unsubscribe_realtime_data(&self, req_id: i32) -> Result<()> {
+        TwsClient::unsubscribe_realtime_data(self, req_id).await
+    }
+
+    async fn place_bracket_order(
+        &self,
+        signal: &OrderSignal,
+        levels: &BracketLevels,
+    ) -> Result<Vec<i32>> {
+        TwsClient::place_bracket_order(
+            self,
+            &signal.symbol,
+            signed_quantity(signal),
+            entry_price(signal),
+            levels.take_profit,
+            levels.stop_loss,
+        )
+        .await
+    }
+
+    async fn cancel_order(&self, order_id: i32) -> Result<()> {
+        TwsClient::cancel_order(self, order_id).await
+    }
+
+    async fn cancel_all_orders(&self) -> Result<()> {
+        TwsClient::cancel_all_orders(self)
+    }
+
+    async fn order_status(&self, order_id: i32) -> Result<OrderStatus> {
+        TwsClient::order_status(self, order_id).await
+    }
+
+    async fn poll_order_statuses(&self) -> Result<HashMap<i32, OrderStatus>> {
+        TwsClient::poll_order_statuses(self).await
+    }
+
+    async fn replace_order(&self, order_id: i32, signal: &OrderSignal) -> Result<Option<i32>> {
+        TwsClient::replace_order(self, order_id, signal).await
+    }
+
+    async fn place_protective_orders(
+        &self,
+        symbol: &str,
+        quantity: f64,
+        levels: &BracketLevels,
+    ) -> Result<Vec<i32>> {
+        TwsClient::place_oco_exit(self, symbol, quantity, levels.take_profit, levels.stop_loss)
+            .await
+    }
+}
+
+/// Cancel `order_id`, wait for the broker to confirm it stopped working, then
+/// place `signal` for whatever was left unfilled
+///
+/// A fill racing the cancel would otherwise leave both orders filled. If the
+/// order fills completely nothing is placed; if a partial fill is seen the
+/// replacement shrinks to the remaining quantity. Fails, placing nothing, when
+/// the cancel is not confirmed within `CANCEL_CONFIRM_TIMEOUT`.
+pub(crate) async fn replace_after_cancel<B: Broker + ?Sized>(
+    broker: &B,
+    order_id: i32,
+    signal: &OrderSignal,
+) -> Result<Option<i32>> {
+    broker.cancel_order(order_id).await?;
+
+    let deadline = tokio::time::Instant::now() + CANCEL_CONFIRM_TIMEOUT;
+    let mut remaining = None;
+    loop {
+        match broker.order_status(order_id).await? {
+            OrderStatus::Filled => {
+                info!(
+                    "Order #{} filled before its cancel, not replacing it",
+                    order_id
+                );
+                return Ok(None);
+            }
+            OrderStatus::PartiallyFilled { remaining_qty, .. } => remaining = Some(remaining_qty),
+            status if !status.is_active() => break,
+            _ => {}
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(anyhow!(
+                "Cancel of order #{} not confirmed within {:?}, replacement not placed",
+                order_id,
+                CANCEL_CONFIRM_TIMEOUT
+            ));
+        }
+        tokio::time::sleep(CANCEL_POLL_INTERVAL).await;
+    }
+
+    let mut replacement = signal.clone();
+    if let Some(remaining) = remaining {
+        replacement.quantity = replacement.quantity.min(remaining);
+    }
+    let replacement_id = broker.place_order(&replacement).await?;
+    info!("Replaced order #{} with #{}", order_id, replacement_id);
+    Ok(Some(replacement_id))
+}
+
+/// Offsetting market orders for every open position, by symbol
+fn flatten_signals(portfolio: &Portfolio) -> Vec<OrderSignal> {
+    let mut positions: Vec<_> = portfolio
+        .positions()
+        .values()
+        .filter(|position| position.quantity != 0.0)
+        .collect();
+    positions.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+
+    positions
+        .into_iter()
+        .map(|position| OrderSignal {
+            symbol: position.symbol.clone(),
+            action: if position.quantity > 0.0 {
+                "SELL"
+            } else {
+                "BUY"
+            }
+            .to_string(),
+            quantity: position.quantity.abs(),
+            price: position.current_price,
+            order_type: "MKT".to_string(),
+            limit_price: None,
+            reason: "EMERGENCY FLATTEN".to_string(),
+            security_info: position.security_info.clone().unwrap_or_else(|| {
+                SecurityInfo::new_stock(
+                    position.symbol.clone(),
+                    "SMART".to_string(),
+                    "USD".to_string(),
+                )
+            }),
+        })
+        .collect()
+}
+
+fn signed_quantity(signal: &OrderSignal) -> f64 {
+    if signal.action == "SELL" {
+        -signal.quantity
+    } else {
+        signal.quantity
+    }
+}
+
+/// Limit price of a bracket's parent order
+fn entry_price(signal: &OrderSignal) -> f64 {
+    signal.limit_price.unwrap_or(signal.price)
+}
+
+/// Live or simulated broker selected at startup
+#[derive(Clone)]
+pub enum BrokerHandle {
+    Live(Arc<TwsClient>),
+    Simulated(Arc<SimulatedBroker>),
+}
+
+impl Broker for BrokerHandle {
+    async fn place_order(&self, signal: &OrderSignal) -> Result<i32> {
+        match self {
+            BrokerHandle::Live(client) => Broker::place_order(client.as_ref(), signal).await,
+            BrokerHandle::Simulated(broker) => broker.place_order(signal).await,
+        }
+    }
+
+    async fn get_positions(&self) -> Result<Vec<AccountPosition>> {
+        match self {
+            BrokerHandle::Live(client) => Broker::get_positions(client.as_ref()).await,
+            BrokerHandle::Simulated(broker) => broker.get_positions().await,
+        }
+    }
+
+    async fn get_account_summary(&self) -> Result<HashMap<String, f64>> {
+        match self {
+            BrokerHandle::Live(client) => Broker::get_account_summary(client.as_ref()).await,
+            BrokerHandle::Simulated(broker) => broker.get_account_summary().await,
+        }
+    }
+
+    async fn place_order_from_order(&self, order: &Order) -> Result<i32> {
+        match self {
+            BrokerHandle::Live(client) => {
+                Broker::place_order_from_order(client.as_ref(), order).await
+            }
+            BrokerHandle::Simulated(broker) => broker.place_order_from_order(order).await,
+        }
+    }
+
+    async fn subscribe_realtime_data(
+        &self,
+        symbol: &str,
+        req_id: i32,
+        tx: mpsc::Sender<MarketDataEvent>,
+    ) -> Result<()> {
+        match self {
+            BrokerHandle::Live(client) => {
+                Broker::subscribe_realtime_data(client.as_ref(), symbol, req_id, tx).await
+            }
+            BrokerHandle::Simulated(broker) => {
+                broker.subscribe_realtime_data(symbol, req_id, tx).await
+            }
+        }
+    }
+
+    async fn unsubscribe_realtime_data(&self, req_id: i32) -> Result<()> {
+        match self {
+            BrokerHandle::Live(client) => {
+                Broker::unsubscribe_realtime_data(client.as_ref(), req_id).await
+            }
+            BrokerHandle::Simulated(broker) => broker.unsubscribe_realtime_data(req_id).await,
+        }
+    }
+
+    async fn place_bracket_order(
+        &self,
+        signal: &OrderSignal,
+        levels: &BracketLevels,
+    ) -> Result<Vec<i32>> {
+        match self {
+            BrokerHandle::Live(client) => {
+                Broker::place_bracket_order(client.as_ref(), signal, levels).await
+            }
+            BrokerHandle::Simulated(broker) => broker.place_bracket_order(signal, levels).await,
+        }
+    }
+
+    async fn cancel_order(&self, order_id: i32) -> Result<()> {
+        match self {
+            BrokerHandle::Live(client) => Broker::cancel_order(client.as_ref(), order_id).await,
+            BrokerHandle::Simulated(broker) => broker.cancel_order(order_id).await,
+        }
+    }
+
+    async fn cancel_all_orders(&self) -> Result<()> {
+        match self {
+            BrokerHandle::Live(client) => Broker::cancel_all_orders(client.as_ref()).await,
+            BrokerHandle::Simulated(broker) => broker.cancel_all_orders().await,
+        }
+    }
+
+    async fn order_status(&self, order_id: i32) -> Result<OrderStatus> {
+        match self {
+            BrokerHandle::Live(client) => Broker::order_status(client.as_ref(), order_id).await,
+            BrokerHandle::Simulated(broker) => broker.order_status(order_id).await,
+        }
+    }
+
+    async fn poll_order_statuses(&self) -> Result<HashMap<i32, OrderStatus>> {
+        match self {
+            BrokerHandle::Live(client) => Broker::poll_order_statuses(client.as_ref()).await,
+            BrokerHandle::Simulated(broker) => broker.poll_order_statuses().await,
+        }
+    }
+
+    async fn replace_order(&self, order_id: i32, signal: &OrderSignal) -> Result<Option<i32>> {
+        match self {
+            BrokerHandle::Live(client) => {
+                Broker::replace_order(client.as_ref(), order_id, signal).await
+            }
+            BrokerHandle::Simulated(broker) => broker.replace_order(order_id, signal).await,
+        }
+    }
+
+    async fn place_protective_orders(
+        &self,
+        symbol: &str,
+        quantity: f64,
+        levels: &BracketLevels,
+    ) -> Result<Vec<i32>> {
+        match self {
+            BrokerHandle::Live(client) => {
+                Broker::place_protective_orders(client.as_ref(), symbol, quantity, levels).await
+            }
+            BrokerHandle::Simulated(broker) => {
+                broker
+                    .place_protective_orders(symbol, quantity, levels)
+                    .await
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SimulatedPosition {
+    quantity: f64,
+    average_cost: f64,
+    security_info: SecurityInfo,
+}
+
+impl SimulatedPosition {
+    fn multiplier(&self) -> f64 {
+        contract_multiplier(&self.security_info)
+    }
+
+    fn unrealized_pnl(&self, price: f64) -> f64 {
+        self.quantity * (price - self.average_cost) * self.multiplier()
+    }
+}
+
+#[derive(Debug, Default)]
+struct SimulatedAccount {
+    cash: f64,
+    realized_pnl: f64,
+    positions: HashMap<String, SimulatedPosition>,
+}
+
+/// Fills orders locally at the latest market price plus modeled costs
+pub struct SimulatedBroker {
+    market_data: Arc<Mutex<MarketDataHandler>>,
+    cost_model: CostModel,
+    account: Mutex<SimulatedAccount>,
+    next_order_id: AtomicI32,
+}
+
+impl SimulatedBroker {
+    pub fn new(
+        market_data: Arc<Mutex<MarketDataHandler>>,
+        cost_model: CostModel,
+        starting_cash: f64,
+    ) -> Self {
+        Self {
+            market_data,
+            cost_model,
+            account: Mutex::new(SimulatedAccount {
+                cash: starting_cash,
+                ..SimulatedAccount::default()
+            }),
+            next_order_id: AtomicI32::new(SIMULATED_FIRST_ORDER_ID),
+        }
+    }
+
+    async fn mark_prices(&self, symbols: impl Iterator<Item = &String>) -> HashMap<String, f64> {
+        let handler = self.market_data.lock().await;
+        symbols
+            .filter_map(|symbol| Some((symbol.clone(), latest_price(&handler, symbol)?)))
+            .collect()
+    }
+}
+
+/// Latest traded price, falling back to the last historical close
+fn latest_price(handler: &MarketDataHandler, symbol: &str) -> Option<f64> {
+    handler
+        .get_market_data(symbol)
+        .map(|data| data.last_price)
+        .filter(|price| *price > 0.0)
+        .or_else(|| {
+            handler
+                .get_price_history(symbol)?
+                .prices
+                .last()
+                .map(|(_, price)| *price)
+        })
+}
+
+/// Market order for about `notional` worth of `symbol` at its latest price
+///
+/// Quantity follows `SecurityInfo::quantity_for_notional`; symbols without
+/// registered security info are treated as stock. Fails when there is no
+/// usable price or the notional does not buy a single unit.
+pub fn notional_order_signal(
+    market_data: &MarketDataHandler,
+    symbol: &str,
+    notional: f64,
+    action: OrderAction,
+) -> Result<OrderSignal> {
+    let price = latest_price(market_data, symbol)
+        .filter(|price| price.is_finite() && *price > 0.0)
+        .ok_or_else(|| anyhow!("No price for {}, cannot size a notional order", symbol))?;
+    let security_info = market_data
+        .get_security_info(symbol)
+        .cloned()
+        .unwrap_or_else(|| {
+            SecurityInfo::new_stock(symbol.to_string(), "SMART".to_string(), "USD".to_string())
+        });
+
+    let quantity = security_info.quantity_for_notional(notional, price);
+    if quantity < 1.0 {
+        return Err(anyhow!(
+            "${:.2} of {} at {} is less than one unit",
+            notional,
+            symbol,
+            price
+        ));
+    }
+
+    Ok(OrderSignal {
+        symbol: symbol.to_string(),
+        action: match action {
+            OrderAction::Buy => "BUY",
+            OrderAction::Sell => "SELL",
+        }
+        .to_string(),
+        quantity,
+        price,
+        order_type: "MKT".to_string(),
+        limit_price: None,
+        reason: format!("Notional order for ${:.2}", notional),
+        security_info,
+    })
+}
+
+fn contract_multiplier(security_info: &SecurityInfo) -> f64 {
+    match (&security_info.security_type, &security_info.contract_specs) {
+        (SecurityType::Future, Some(specs)) => specs.multiplier,
+        _ => 1.0,
+    }
+}
+
+fn simulated_contract(position: &SimulatedPosition, symbol: &str) -> Contract {
+    let mut contract = match position.security_info.security_type {
+        SecurityType::Future => Contract::futures(symbol),
+        _ => Contract::stock(symbol),
+    };
+    contract.exchange = position.security_info.exchange.clone();
+    contract.currency = position.security_info.currency.clone();
+    contract
+}
+
+impl Broker for SimulatedBroker {
+    async fn place_order(&self, signal: &OrderSignal) -> Result<i32> {
+        let market_price = {
+            let handler = self.market_data.lock().await;
+            latest_price(&handler, &signal.symbol)
+        }
+        .or((signal.price > 0.0).then_some(signal.price))
+        .ok_or_else(|| anyhow!("No market price to simulate fill for {}", signal.symbol))?;
+
+        let quantity = if signal.action == "SELL" {
+            -signal.quantity
+        } else {
+            signal.quantity
+        };
+        let security_type = &signal.security_info.security_type;
+        let fill =
+            self.cost_model
+                .simulated_fill(&signal.symbol, security_type, quantity, market_price);
+        let multiplier = contract_multiplier(&signal.security_info);
+
+        let mut account = self.account.lock().await;
+        let position = account
+            .positions
+            .entry(signal.symbol.clone())
+            .or_insert_with(|| SimulatedPosition {
+                quantity: 0.0,
+                average_cost: 0.0,
+                security_info: signal.security_info.clone(),
+            });
+
+        // Portion of the fill that closes existing exposure realizes P&L
+        let closed = if position.quantity * quantity < 0.0 {
+            quantity.abs().min(position.quantity.abs()) * position.quantity.signum()
+        } else {
+            0.0
+        };
+        let realized = closed * (fill.fill_price - position.average_cost) * multiplier;
+        let opened = quantity + closed;
+        let remaining = position.quantity - closed;
+        if opened != 0.0 {
+            position.average_cost = (remaining * position.average_cost + opened * fill.fill_price)
+                / (remaining + opened);
+        }
+        position.quantity = remaining + opened;
+        if position.quantity == 0.0 {
+            account.positions.remove(&signal.symbol);
+        }
+
+        // Futures only move cash by realized P&L; other instruments pay notional
+        let cash_change = match security_type {
+            SecurityType::Future => realized,
+            _ => -quantity * fill.fill_price,
+        };
+        account.cash += cash_change - fill.commission;
+        account.realized_pnl += realized;
+
+        let order_id = self.next_order_id.fetch_add(1, Ordering::SeqCst);
+        info!(
+            "Simulated fill #{}: {} {} {} @ {:.4} (commission ${:.2})",
+            order_id,
+            signal.action,
+            signal.quantity,
+            signal.symbol,
+            fill.fill_price,
+            fill.commission
+        );
+        Ok(order_id)
+    }
+
+    async fn get_positions(&self) -> Result<Vec<AccountPosition>> {
+        let account = self.account.lock().await;
+        Ok(account
+            .positions
+            .iter()
+            .map(|(symbol, position)| AccountPosition {
+                account: "SIMULATED".to_string(),
+                symbol: symbol.clone(),
+                position: position.quantity,
+                avg_cost: position.average_cost,
+                contract: simulated_contract(position, symbol),
+            })
+            .collect())
+    }
+
+    async fn get_account_summary(&self) -> Result<HashMap<String, f64>> {
+        let account = self.account.lock().await;
+        let prices = self.mark_prices(account.positions.keys()).await;
+
+        let mut unrealized_pnl = 0.0;
+        let mut position_value = 0.0;
+        for (symbol, position) in &account.positions {
+            let price = prices.get(symbol).copied().unwrap_or(position.average_cost);
+            let pnl = position.unrealized_pnl(price);
+            unrealized_pnl += pnl;
+            // Futures carry no notional in cash, only their open P&L
+            position_value += match position.security_info.security_type {
+                SecurityType::Future => pnl,
+                _ => position.quantity * price,
+            };
+        }
+        let net_liquidation = account.cash + position_value;
+
+        Ok(HashMap::from([
+            ("net_liquidation".to_string(), net_liquidation),
+            ("cash".to_string(), account.cash),
+            ("unrealized_pnl".to_string(), unrealized_pnl),
+            ("realized_pnl".to_string(), account.realized_pnl),
+            ("available_funds".to_string(), net_liquidation),
+            ("buying_power".to_string(), account.cash),
+        ]))
+    }
+
+    /// Prices arrive through the shared market data handler, so this only
+    /// registers the symbol
+    async fn subscribe_realtime_data(
+        &self,
+        symbol: &str,
+        req_id: i32,
+        _tx: mpsc::Sender<MarketDataEvent>,
+    ) -> Result<()> {
+        let mut handler = self.market_data.lock().await;
+        handler.register_symbol(req_id, symbol.to_string());
+        Ok(())
+    }
+
+    async fn unsubscribe_realtime_data(&self, _req_id: i32) -> Result<()> {
+        Ok(())
+    }
+
+    /// Fills the entry immediately; resting exit legs are not simulated, so
+    /// only the parent ID is returned
+    async fn place_bracket_order(
+        &self,
+        signal: &OrderSignal,
+        levels: &BracketLevels,
+    ) -> Result<Vec<i32>> {
+        info!(
+            "Simulated bracket for {}: take-profit {:.4} and stop {:.4} are not simulated",
+            signal.symbol, levels.take_profit, levels.stop_loss
+        );
+        Ok(vec![self.place_order(signal).await?])
+    }
+
+    async fn cancel_order(&self, _order_id: i32) -> Result<()> {
+        Ok(())
+    }
+
+    /// Orders fill immediately, so none are ever working
+    async fn cancel_all_orders(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Orders fill as soon as they are placed
+    async fn order_status(&self, _order_id: i32) -> Result<OrderStatus> {
+        Ok(OrderStatus::Filled)
+    }
+
+    /// Filled orders are not tracked by ID, so there is nothing to report
+    async fn poll_order_statuses(&self) -> Result<HashMap<i32, OrderStatus>> {
+        Ok(HashMap::new())
+    }
+
+    async fn place_protective_orders(
+        &self,
+        symbol: &str,
+        _quantity: f64,
+        levels: &BracketLevels,
+    ) -> Result<Vec<i32>> {
+        info!(
+            "Simulated protective orders for {}: take-profit {:.4} and stop {:.4} are not simulated",
+            symbol, levels.take_profit, levels.stop_loss
+        );
+        Ok(Vec::new())
+    }
+}
+
+/// In-memory broker that records every call and returns canned account data
+#[derive(Default)]
+pub struct MockBroker {
+    positions: StdMutex<Vec<AccountPosition>>,
+    account_summary: StdMutex<HashMap<String, f64>>,
+    placed: StdMutex<Vec<OrderSignal>>,
+    brackets: StdMutex<Vec<(i32, ibapi::orders::Order)>>,
+    cancelled: StdMutex<Vec<i32>>,
+    statuses: StdMutex<HashMap<i32, OrderStatus>>,
+    cancel_all_requests: AtomicI32,
+    protective: StdMutex<Vec<(String, f64, BracketLevels)>>,
+    subscriptions: StdMutex<Vec<(String, i32)>>,
+    next_order_id: AtomicI32,
+}
+
+impl MockBroker {
+    pub fn new(account_summary: HashMap<String, f64>) -> Self {
+        Self {
+            account_summary: StdMutex::new(account_summary),
+            next_order_id: AtomicI32::new(1),
+            ..Self::default()
+        }
+    }
+
+    /// Positions returned by subsequent `get_positions` calls
+    pub fn set_positions(&self, positions: Vec<AccountPosition>) {
+        *self.positions.lock().unwrap() = positions;
+    }
+
+    /// Every order placed so far, in submission order
+    pub fn placed_orders(&self) -> Vec<OrderSignal> {
+        self.placed.lock().unwrap().clone()
+    }
+
+    /// Linked `(order_id, order)` legs of every bracket placed so far
+    pub fn bracket_orders(&self) -> Vec<(i32, ibapi::orders::Order)> {
+        self.brackets.lock().unwrap().clone()
+    }
+
+    /// Order IDs cancelled so far
+    pub fn cancelled_orders(&self) -> Vec<i32> {
+        self.cancelled.lock().unwrap().clone()
+    }
+
+    /// Number of `cancel_all_orders` calls so far
+    pub fn cancel_all_requests(&self) -> i32 {
+        self.cancel_all_requests.load(Ordering::SeqCst)
+    }
+
+    /// Status reported for `order_id`; orders without one read as submitted
+    pub fn set_order_status(&self, order_id: i32, status: OrderStatus) {
+        self.statuses.lock().unwrap().insert(order_id, status);
+    }
+
+    /// `(symbol, position, levels)` of every protective pair placed so far
+    pub fn protective_orders(&self) -> Vec<(String, f64, BracketLevels)> {
+        self.protective.lock().unwrap().clone()
+    }
+
+    /// Active `(symbol, req_id)` subscriptions
+    pub fn subscriptions(&self) -> Vec<(String, i32)> {
+        self.subscriptions.lock().unwrap().clone()
+    }
+}
+
+impl Broker for MockBroker {
+    async fn place_order(&self, signal: &OrderSignal) -> Result<i32> {
+        self.placed.lock().unwrap().push(signal.clone());
+        Ok(self.next_order_id.fetch_add(1, Ordering::SeqCst))
+    }
+
+    async fn get_positions(&self) -> Result<Vec<AccountPosition>> {
+        Ok(self.positions.lock().unwrap().clone())
+    }
+
+    async fn get_account_summary(&self) -> Result<HashMap<String, f64>> {
+        Ok(self.account_summary.lock().unwrap().clone())
+    }
+
+    async fn subscribe_realtime_data(
+        &self,
+        symbol: &str,
+        req_id: i32,
+        _tx: mpsc::Sender<MarketDataEvent>,
+    ) -> Result<()> {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .push((symbol.to_string(), req_id));
+        Ok(())
+    }
+
+    async fn unsubscribe_realtime_data(&self, req_id: i32) -> Result<()> {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .retain(|(_, id)| *id != req_id);
+        Ok(())
+    }
+
+    async fn place_bracket_order(
+        &self,
+        signal: &OrderSignal,
+        levels: &BracketLevels,
+    ) -> Result<Vec<i32>> {
+        let action = if signal.action == "SELL" {
+            OrderAction::Sell
+        } else {
+            OrderAction::Buy
+        };
+        let orders = EnhancedOrderBuilder::bracket_order(
+            action,
+            signal.quantity,
+            entry_price(signal),
+            levels.take_profit,
+            levels.stop_loss,
+        );
+        let mut brackets = self.brackets.lock().unwrap();
+        crate::connection::submit_bracket(
+            orders,
+            || self.next_order_id.fetch_add(1, Ordering::SeqCst),
+            |order_id, order| {
+                brackets.push((order_id, order.clone()));
+                Ok(())
+            },
+        )
+    }
+
+    /// A working order reads as cancelled afterwards; a filled one stays filled
+    async fn cancel_order(&self, order_id: i32) -> Result<()> {
+        self.cancelled.lock().unwrap().push(order_id);
+        let mut statuses = self.statuses.lock().unwrap();
+        let status = statuses.entry(order_id).or_insert(OrderStatus::Submitted);
+        if status.is_active() {
+            *status = OrderStatus::Cancelled;
+        }
+        Ok(())
+    }
+
+    async fn cancel_all_orders(&self) -> Result<()> {
+        self.cancel_all_requests.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn order_status(&self, order_id: i32) -> Result<OrderStatus> {
+        Ok(self
+            .statuses
+            .lock()
+            .unwrap()
+            .get(&order_id)
+            .cloned()
+            .unwrap_or(OrderStatus::Submitted))
+    }
+
+    /// Only orders given a status with `set_order_status`
+    async fn poll_order_statuses(&self) -> Result<HashMap<i32, OrderStatus>> {
+        Ok(self.statuses.lock().unwrap().clone())
+    }
+
+    async fn place_protective_orders(
+        &self,
+        symbol: &str,
+        quantity: f64,
+        levels: &BracketLevels,
+    ) -> Result<Vec<i32>> {
+        self.protective
+            .lock()
+            .unwrap()
+            .push((symbol.to_string(), quantity, *levels));
+        Ok(vec![
+            self.next_order_id.fetch_add(1, Ordering::SeqCst),
+            self.next_order_id.fetch_add(1, Ordering::SeqCst),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::costs::CostModelConfig;
+
+    fn broker_with_price(symbol: &str, price: f64) -> SimulatedBroker {
+        let mut handler = MarketDataHandler::new();
+        handler.register_symbol(1, symbol.to_string());
+        handler.update_realtime_data(symbol, price, 100);
+        SimulatedBroker::new(
+            Arc::new(Mutex::new(handler)),
+            CostModel::new(CostModelConfig::default()),
+            100_000.0,
+        )
+    }
+
+    fn signal(action: &str, quantity: f64) -> OrderSignal {
+        OrderSignal {
+            symbol: "AAPL".to_string(),
+            action: action.to_string(),
+            quantity,
+            price: 0.0,
+            order_type: "MKT".to_string(),
+            limit_price: None,
+            reason: "test".to_string(),
+            security_info: SecurityInfo::new_stock(
+                "AAPL".to_string(),
+                "SMART".to_string(),
+                "USD".to_string(),
+            ),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_buy_creates_position_and_spends_cash() {
+        let broker = broker_with_price("AAPL", 150.0);
+        let expected = CostModel::new(CostModelConfig::default()).expected_fill(
+            "AAPL",
+            &SecurityType::Stock,
+            100.0,
+            150.0,
+        );
+
+        let order_id = broker.place_order(&signal("BUY", 100.0)).await.unwrap();
+        assert_eq!(order_id, SIMULATED_FIRST_ORDER_ID);
+
+        let positions = broker.get_positions().await.unwrap();
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].symbol, "AAPL");
+        assert_eq!(positions[0].position, 100.0);
+        assert!((positions[0].avg_cost - expected.fill_price).abs() < 1e-9);
+        assert!(expected.fill_price > 150.0); // Slippage against the buyer
+
+        let summary = broker.get_account_summary().await.unwrap();
+        let expected_cash = 100_000.0 - 100.0 * expected.fill_price - expected.commission;
+        assert!((summary["cash"] - expected_cash).abs() < 1e-9);
+        assert!(summary["cash"] < 100_000.0 - 15_000.0);
+        // Marked at 150, the position is worth less than its slipped cost
+        assert!(summary["unrealized_pnl"] < 0.0);
+        assert!((summary["net_liquidation"] - (expected_cash + 15_000.0)).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_sell_closes_position_and_realizes_pnl() {
+        let broker = broker_with_price("AAPL", 150.0);
+        broker.place_order(&signal("BUY", 100.0)).await.unwrap();
+        broker
+            .market_data
+            .lock()
+            .await
+            .update_realtime_data("AAPL", 160.0, 100);
+        broker.place_order(&signal("SELL", 100.0)).await.unwrap();
+
+        assert!(broker.get_positions().await.unwrap().is_empty());
+        let summary = broker.get_account_summary().await.unwrap();
+        assert!(summary["cash"] > 100_900.0);
+        assert!((summary["net_liquidation"] - summary["cash"]).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_emergency_stop_offsets_every_position() {
+        let broker = MockBroker::new(HashMap::new());
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", 100.0, 150.0);
+        portfolio.update_position("TSLA", -20.0, 250.0);
+        portfolio.update_position("MSFT", 10.0, 400.0);
+        portfolio.update_position("MSFT", -10.0, 410.0); // Closed, nothing to flatten
+
+        let order_ids = broker.emergency_stop(&portfolio).await.unwrap();
+
+        assert_eq!(broker.cancel_all_requests(), 1);
+        assert_eq!(order_ids, vec![1, 2]);
+        let placed: Vec<(String, String, f64, String)> = broker
+            .placed_orders()
+            .into_iter()
+            .map(|order| (order.symbol, order.action, order.quantity, order.order_type))
+            .collect();
+        assert_eq!(
+            placed,
+            vec![
+                (
+                    "AAPL".to_string(),
+                    "SELL".to_string(),
+                    100.0,
+                    "MKT".to_string()
+                ),
+                (
+                    "TSLA".to_string(),
+                    "BUY".to_string(),
+                    20.0,
+                    "MKT".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_notional_order_sizes_from_latest_price() {
+        let broker = MockBroker::new(HashMap::new());
+        let mut handler = MarketDataHandler::new();
+        handler.register_symbol(1, "AAPL".to_string());
+        handler.update_realtime_data("AAPL", 100.0, 100);
+
+        let order_id = broker
+            .place_notional_order(&handler, "AAPL", 10_000.0, OrderAction::Buy)
+            .await
+            .unwrap();
+
+        assert_eq!(order_id, 1);
+        let placed = broker.placed_orders();
+        assert_eq!(placed.len(), 1);
+        assert_eq!(placed[0].action, "BUY");
+        assert_eq!(placed[0].quantity, 100.0);
+        assert_eq!(placed[0].order_type, "MKT");
+
+        // Partial shares round down
+        let signal = notional_order_signal(&handler, "AAPL", 10_050.0, OrderAction::Sell).unwrap();
+        assert_eq!((signal.action.as_str(), signal.quantity), ("SELL", 100.0));
+
+        // No price and less than one share are refused before reaching the broker
+        handler.register_symbol(2, "MSFT".to_string());
+        let no_price = broker
+            .place_notional_order(&handler, "MSFT", 10_000.0, OrderAction::Buy)
+            .await
+            .unwrap_err();
+        assert!(no_price.to_string().contains("No price for MSFT"));
+        assert!(notional_order_signal(&handler, "AAPL", 50.0, OrderAction::Buy).is_err());
+        assert_eq!(broker.placed_orders().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_replace_waits_for_cancel_and_skips_filled_orders() {
+        let broker = MockBroker::new(HashMap::new());
+        let limit = OrderSignal {
+            order_type: "LMT".to_string(),
+            limit_price: Some(100.0),
+            ..signal("BUY", 10.0)
+        };
+        let original = broker.place_order(&limit).await.unwrap();
+
+        // Cancel confirmed: the replacement goes out
+        let replacement = broker.replace_order(original, &limit).await.unwrap();
+        assert_eq!(replacement, Some(2));
+        assert_eq!(broker.cancelled_orders(), vec![original]);
+        assert_eq!(
+            broker.order_status(original).await.unwrap(),
+            OrderStatus::Cancelled
+        );
+
+        // The order filled before the cancel landed: nothing more is placed
+        broker.set_order_status(2, OrderStatus::Filled);
+        assert_eq!(broker.replace_order(2, &limit).await.unwrap(), None);
+        assert_eq!(broker.placed_orders().len(), 2);
+        assert_eq!(broker.order_status(2).await.unwrap(), OrderStatus::Filled);
+    }
+}
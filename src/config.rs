@@ -1,8 +1,19 @@
+use crate::costs::{CostModelConfig, InstrumentCosts};
 use crate::futures_utils::get_front_month_contract;
-use crate::security_types::SecurityType;
-use anyhow::Result;
+use crate::journal::JournalConfig;
+use crate::market_data::{
+    AggregationMethod, DEFAULT_MAX_TICK_DEVIATION, DataGapConfig, ReturnMode,
+    ReturnOutlierThresholds, TimeFrame, VolatilityEstimator,
+};
+use crate::order_types::BracketLevels;
+use crate::schedule::Schedule;
+use crate::security_types::{FuturesContract, SecurityInfo, SecurityType};
+use crate::signals::core::SignalWeights;
+use anyhow::{Result, anyhow, bail};
+use ibapi::market_data::realtime::BarSize as RealtimeBarSize;
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,6 +21,100 @@ pub struct TradingConfig {
     pub tws_config: TwsConfig,
     pub strategy_config: StrategyConfig,
     pub risk_config: RiskConfig,
+    #[serde(default)]
+    pub cost_model: CostModelConfig,
+    #[serde(default)]
+    pub journal: JournalConfig,
+    #[serde(default)]
+    pub status_server: StatusServerConfig,
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
+    /// Registered name of the strategy the trading loop runs
+    #[serde(default = "default_strategy")]
+    pub strategy: String,
+    /// Spread to trade; required by the pairs strategy
+    #[serde(default)]
+    pub pairs: Option<PairsConfig>,
+    /// Capital fraction per strategy name; empty runs the strategy on the
+    /// whole account
+    #[serde(default)]
+    pub strategy_allocations: HashMap<String, f64>,
+}
+
+fn default_strategy() -> String {
+    "momentum".to_string() // See `strategy::StrategyRegistry` for the others
+}
+
+/// Two co-moving symbols traded on the z-score of their price ratio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairsConfig {
+    pub symbol_a: String,
+    pub symbol_b: String,
+    #[serde(default = "default_pairs_lookback")]
+    pub lookback: usize,
+    #[serde(default = "default_pairs_entry_z")]
+    pub entry_z: f64,
+    #[serde(default = "default_pairs_exit_z")]
+    pub exit_z: f64,
+    #[serde(default = "default_pairs_leg_notional")]
+    pub leg_notional: f64,
+}
+
+fn default_pairs_lookback() -> usize {
+    60 // Bars in the ratio's mean and standard deviation
+}
+
+fn default_pairs_entry_z() -> f64 {
+    2.0
+}
+
+fn default_pairs_exit_z() -> f64 {
+    0.5
+}
+
+fn default_pairs_leg_notional() -> f64 {
+    10_000.0 // Dollars on each side of the spread
+}
+
+/// HTTP status endpoint settings (requires the `status-server` feature)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusServerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_status_bind_address")]
+    pub bind_address: String,
+    #[serde(default = "default_status_port")]
+    pub port: u16,
+}
+
+impl Default for StatusServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: default_status_bind_address(),
+            port: default_status_port(),
+        }
+    }
+}
+
+fn default_status_bind_address() -> String {
+    "127.0.0.1".to_string() // Local access only
+}
+
+fn default_status_port() -> u16 {
+    8080
+}
+
+/// Steps taken on a graceful (ctrl-c) shutdown
+///
+/// Real-time subscriptions are always cancelled and the journal synced; the
+/// rest is opt-in.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShutdownConfig {
+    #[serde(default)]
+    pub cancel_orders_on_exit: bool, // Cancel working orders before disconnecting
+    #[serde(default)]
+    pub price_history_path: Option<String>, // Save price history here on exit and reload it at startup
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,12 +122,106 @@ pub struct TwsConfig {
     pub host: String,
     pub port: u16,
     pub client_id: i32,
+    #[serde(default)]
+    pub dry_run: bool, // Log orders instead of submitting them
+    #[serde(default)]
+    pub simulate_fills: bool, // Fill orders locally against live prices
+    #[serde(default = "default_simulated_starting_cash")]
+    pub simulated_starting_cash: f64, // Account cash the simulated broker starts with
+    #[serde(default = "default_client_id_attempts")]
+    pub client_id_attempts: u32, // Ids tried from client_id upward while TWS reports them in use
+    #[serde(default = "default_max_orders_per_second")]
+    pub max_orders_per_second: f64, // Pacing for order submissions
+    #[serde(default = "default_realtime_bar_seconds")]
+    pub realtime_bar_seconds: u32, // Length of real-time bars fed to the strategy
+}
+
+/// Environment variables that take precedence over `tws_config` in the file
+pub const TWS_HOST_ENV: &str = "TWS_HOST";
+pub const TWS_PORT_ENV: &str = "TWS_PORT";
+pub const TWS_CLIENT_ID_ENV: &str = "TWS_CLIENT_ID";
+
+impl TwsConfig {
+    /// Override connection settings from `TWS_HOST`, `TWS_PORT` and
+    /// `TWS_CLIENT_ID`; empty variables are ignored
+    pub fn apply_env_overrides(&mut self) -> Result<()> {
+        self.apply_overrides(|name| std::env::var(name).ok())
+    }
+
+    /// Override connection settings from variables resolved by `lookup`
+    pub fn apply_overrides(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<()> {
+        let lookup = |name: &str| lookup(name).filter(|value| !value.trim().is_empty());
+
+        if let Some(host) = lookup(TWS_HOST_ENV) {
+            info!("tws_config.host overridden by {}", TWS_HOST_ENV);
+            self.host = host.trim().to_string();
+        }
+        if let Some(port) = lookup(TWS_PORT_ENV) {
+            self.port = port.trim().parse().map_err(|_| {
+                anyhow!(
+                    "{} must be a port number (0-65535), got {:?}",
+                    TWS_PORT_ENV,
+                    port
+                )
+            })?;
+            info!("tws_config.port overridden by {}", TWS_PORT_ENV);
+        }
+        if let Some(client_id) = lookup(TWS_CLIENT_ID_ENV) {
+            self.client_id = client_id.trim().parse().map_err(|_| {
+                anyhow!(
+                    "{} must be a 32-bit integer, got {:?}",
+                    TWS_CLIENT_ID_ENV,
+                    client_id
+                )
+            })?;
+            info!("tws_config.client_id overridden by {}", TWS_CLIENT_ID_ENV);
+        }
+        Ok(())
+    }
+}
+
+/// Longest real-time bar built from IBKR's 5-second bars
+const MAX_REALTIME_BAR_SECONDS: u32 = 3600;
+
+/// How real-time bars of a configured length are obtained from IBKR
+///
+/// IBKR only streams 5-second real-time bars, so longer bars are built by
+/// merging `bars_per_update` consecutive ones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RealtimeBarSpec {
+    pub bar_size: RealtimeBarSize,
+    pub bars_per_update: u32,
+}
+
+impl RealtimeBarSpec {
+    /// Spec for bars of `seconds`, a multiple of 5 up to an hour
+    pub fn from_seconds(seconds: u32) -> Result<Self> {
+        const NATIVE_SECONDS: u32 = 5;
+        if seconds == 0
+            || !seconds.is_multiple_of(NATIVE_SECONDS)
+            || seconds > MAX_REALTIME_BAR_SECONDS
+        {
+            bail!(
+                "{}s is not supported: IBKR streams {}-second bars, so use a multiple of {} up to {}",
+                seconds,
+                NATIVE_SECONDS,
+                NATIVE_SECONDS,
+                MAX_REALTIME_BAR_SECONDS
+            );
+        }
+        Ok(Self {
+            bar_size: RealtimeBarSize::Sec5,
+            bars_per_update: seconds / NATIVE_SECONDS,
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StrategyConfig {
     pub securities: Vec<SecurityConfig>,
     pub lookback_period: usize,
+    #[serde(default)]
+    pub lookback_periods: HashMap<TimeFrame, usize>, // Per-timeframe overrides of lookback_period
     pub momentum_threshold: f64,
     pub position_size: f64,
     pub rebalance_frequency_minutes: u64,
@@ -34,6 +233,64 @@ pub struct StrategyConfig {
     pub use_limit_orders: bool,
     #[serde(default = "default_limit_order_offset")]
     pub limit_order_offset: f64,
+    #[serde(default)]
+    pub volatility_estimator: VolatilityEstimator,
+    #[serde(default = "default_max_data_age_seconds")]
+    pub max_data_age_seconds: u64,
+    #[serde(default)]
+    pub data_gaps: DataGapConfig,
+    #[serde(default)]
+    pub return_outliers: ReturnOutlierThresholds, // Per-type cap on returns kept in momentum statistics
+    #[serde(default)]
+    pub signal_smoothing: f64, // EWMA weight on the prior cycle's score (0 = off)
+    #[serde(default)]
+    pub exit_hysteresis: f64, // Held positions exit below momentum_threshold minus this
+    #[serde(default = "default_futures_roll_window_days")]
+    pub futures_roll_window_days: i64, // Warn that a futures position is due to roll this many days before expiry
+    #[serde(default = "default_max_tick_deviation")]
+    pub max_tick_deviation: f64, // Real-time prices further than this fraction from the last are rejected
+    #[serde(default)]
+    pub use_volume_weighted_momentum: bool, // Weight each period's return by its volume
+    #[serde(default)]
+    pub bracket_orders: Option<BracketConfig>, // Attach take-profit and stop legs to entries
+    #[serde(default)]
+    pub order_chase: Option<OrderChaseConfig>, // Reprice unfilled limit orders toward the market
+    #[serde(default = "default_max_positions")]
+    pub max_positions: usize, // Concurrent names held
+    #[serde(default)]
+    pub rotation_margin: f64, // Score a new name must beat the weakest holding by to replace it
+    #[serde(default)]
+    pub risk_free_rate: f64, // Annual rate subtracted in annualized Sharpe ratios
+    #[serde(default)]
+    pub trading_calendar: Option<TradingCalendar>, // Overrides the per-security-type calendar
+    #[serde(default = "default_signal_quality_threshold")]
+    pub signal_quality_threshold: f64, // Minimum combined signal strength to enter (Carver scale)
+    #[serde(default = "default_signal_consensus_threshold")]
+    pub signal_consensus_threshold: f64, // Minimum fraction of signals agreeing to enter
+    #[serde(default)]
+    pub min_timeframe_consensus: f64, // Fraction of momentum timeframes agreeing in sign to enter (0 = off)
+    #[serde(default)]
+    pub rebalance_schedule: Option<Schedule>, // Replaces rebalance_frequency_minutes when set
+    #[serde(default = "default_signal_weights")]
+    pub signal_weights: SignalWeights, // Composite weights, normalized to sum to 1
+    #[serde(default)]
+    pub fractional_shares: bool, // Stock sizes may include fractions of a share
+    #[serde(default)]
+    pub share_rounding: ShareRounding, // How stock sizes snap to tradable quantities
+    #[serde(default)]
+    pub rank_normalization: bool, // Score names by percentile rank within the universe
+    #[serde(default)]
+    pub sizing_mode: SizingMode, // How forex sizes snap to tradable quantities
+    #[serde(default)]
+    pub min_bars_for_signals: Option<usize>, // History each symbol needs before trading starts
+    #[serde(default = "default_max_warmup_seconds")]
+    pub max_warmup_seconds: u64, // Start trading anyway once the warm-up takes this long
+    #[serde(default)]
+    pub max_spread_bps: Option<f64>, // Skip entries quoted wider than this (None = off)
+    #[serde(default)]
+    pub min_average_volume: f64, // Skip entries averaging less daily volume over the lookback (0 = off)
+    #[serde(default)]
+    pub aggregation_method: AggregationMethod, // How timeframe signals combine into a composite
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +302,114 @@ pub struct SecurityConfig {
     pub currency: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub futures_specs: Option<FuturesSpecs>,
+    #[serde(default)]
+    pub extended_hours_data: bool, // Include pre- and post-market bars in history and real-time data
+    #[serde(default)]
+    pub outside_rth_orders: bool, // Let orders fill outside regular trading hours
+    #[serde(default)]
+    pub return_mode: ReturnMode, // Absolute for instruments that trade at or through zero
+    #[serde(default = "default_trade")]
+    pub trade: bool, // False for data-only benchmarks (SPY, VIX): subscribed but never signalled
+    #[serde(default)]
+    pub commission_per_unit: Option<f64>, // Replaces the cost model's per-share/contract rate
+    #[serde(default)]
+    pub commission_min: Option<f64>, // Replaces the cost model's minimum per order
+    #[serde(default)]
+    pub multiplier: Option<f64>, // Futures only: replaces futures_specs.multiplier in cost and margin math
+}
+
+impl SecurityConfig {
+    /// Symbol plus contract month for futures, telling apart two months of
+    /// one underlying
+    pub fn key(&self) -> SecurityKey {
+        SecurityKey {
+            symbol: self.symbol.clone(),
+            contract_month: self
+                .futures_specs
+                .as_ref()
+                .map(|specs| specs.contract_month.clone()),
+        }
+    }
+
+    /// Multiplier from price to notional: the override, else the futures
+    /// contract's, else None (1x)
+    pub fn contract_multiplier(&self) -> Option<f64> {
+        self.multiplier
+            .or(self.futures_specs.as_ref().map(|specs| specs.multiplier))
+    }
+
+    /// Instrument details for the portfolio, market data and margin code
+    pub fn security_info(&self) -> SecurityInfo {
+        match self.security_type {
+            SecurityType::Future => {
+                let contract = self
+                    .futures_specs
+                    .as_ref()
+                    .map(|specs| FuturesContract {
+                        underlying: specs.underlying.clone(),
+                        expiry: specs.expiry.clone(),
+                        multiplier: self.multiplier.unwrap_or(specs.multiplier),
+                        tick_size: specs.tick_size,
+                        contract_month: specs.contract_month.clone(),
+                    })
+                    .unwrap_or_default();
+                SecurityInfo::new_future(
+                    self.symbol.clone(),
+                    self.exchange.clone(),
+                    self.currency.clone(),
+                    contract,
+                )
+            }
+            SecurityType::Forex => SecurityInfo::new_forex(
+                self.symbol.clone(),
+                self.exchange.clone(),
+                self.currency.clone(),
+            ),
+            SecurityType::Stock => SecurityInfo::new_stock(
+                self.symbol.clone(),
+                self.exchange.clone(),
+                self.currency.clone(),
+            ),
+        }
+    }
+}
+
+/// Identity of a configured instrument
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SecurityKey {
+    pub symbol: String,
+    pub contract_month: Option<String>, // Futures only
+}
+
+impl std::fmt::Display for SecurityKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.contract_month {
+            Some(contract_month) => write!(f, "{} {}", self.symbol, contract_month),
+            None => write!(f, "{}", self.symbol),
+        }
+    }
+}
+
+/// Name market data, positions and orders use for `security`
+///
+/// The bare symbol, unless another entry in `securities` shares it (e.g.
+/// two contract months of one future for a calendar position); then the
+/// symbol and contract month, so the two are tracked independently.
+///
+/// Note the id depends on the other entries: adding a second month of a
+/// future renames the first from "ES" to "ES 202403", and anything saved
+/// under the old name (price history, journal entries) no longer matches it.
+/// A lone future keeps its bare symbol across rolls.
+pub fn security_id(securities: &[SecurityConfig], security: &SecurityConfig) -> String {
+    let key = security.key();
+    let shared = securities
+        .iter()
+        .any(|other| other.symbol == security.symbol && other.key() != key);
+    if shared {
+        key.to_string()
+    } else {
+        security.symbol.clone()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,12 +421,320 @@ pub struct FuturesSpecs {
     pub contract_month: String,
 }
 
+/// Builds a `SecurityConfig` with per-type exchange and currency defaults
+///
+/// ```ignore
+/// let eurusd = SecurityConfigBuilder::forex("EUR.USD").build()?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct SecurityConfigBuilder {
+    symbol: String,
+    security_type: SecurityType,
+    exchange: String,
+    currency: String,
+    futures_specs: Option<FuturesSpecs>,
+    extended_hours_data: bool,
+    outside_rth_orders: bool,
+    return_mode: ReturnMode,
+    trade: bool,
+    commission_per_unit: Option<f64>,
+    commission_min: Option<f64>,
+    multiplier: Option<f64>,
+}
+
+impl SecurityConfigBuilder {
+    /// Stock routed through SMART, quoted in USD
+    pub fn stock(symbol: &str) -> Self {
+        Self::new(symbol, SecurityType::Stock, "SMART", "USD")
+    }
+
+    /// Futures contract on CME, quoted in USD; requires `futures_specs`
+    pub fn future(symbol: &str) -> Self {
+        Self::new(symbol, SecurityType::Future, "CME", "USD")
+    }
+
+    /// Currency pair on IDEALPRO, quoted in the pair's quote currency
+    /// (`EUR.USD` -> USD)
+    pub fn forex(symbol: &str) -> Self {
+        let currency = symbol
+            .split_once('.')
+            .map_or("USD", |(_, quote)| quote)
+            .to_string();
+        Self::new(symbol, SecurityType::Forex, "IDEALPRO", &currency)
+    }
+
+    fn new(symbol: &str, security_type: SecurityType, exchange: &str, currency: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            security_type,
+            exchange: exchange.to_string(),
+            currency: currency.to_string(),
+            futures_specs: None,
+            extended_hours_data: false,
+            outside_rth_orders: false,
+            return_mode: ReturnMode::Simple,
+            trade: true,
+            commission_per_unit: None,
+            commission_min: None,
+            multiplier: None,
+        }
+    }
+
+    pub fn exchange(mut self, exchange: &str) -> Self {
+        self.exchange = exchange.to_string();
+        self
+    }
+
+    pub fn currency(mut self, currency: &str) -> Self {
+        self.currency = currency.to_string();
+        self
+    }
+
+    pub fn futures_specs(mut self, futures_specs: FuturesSpecs) -> Self {
+        self.futures_specs = Some(futures_specs);
+        self
+    }
+
+    pub fn extended_hours_data(mut self, extended_hours_data: bool) -> Self {
+        self.extended_hours_data = extended_hours_data;
+        self
+    }
+
+    pub fn outside_rth_orders(mut self, outside_rth_orders: bool) -> Self {
+        self.outside_rth_orders = outside_rth_orders;
+        self
+    }
+
+    pub fn return_mode(mut self, return_mode: ReturnMode) -> Self {
+        self.return_mode = return_mode;
+        self
+    }
+
+    /// Commission terms for this security instead of its type's defaults
+    pub fn commission(mut self, per_unit: f64, min: f64) -> Self {
+        self.commission_per_unit = Some(per_unit);
+        self.commission_min = Some(min);
+        self
+    }
+
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = Some(multiplier);
+        self
+    }
+
+    /// Subscribe for data only, e.g. a benchmark index
+    pub fn data_only(mut self) -> Self {
+        self.trade = false;
+        self
+    }
+
+    pub fn build(self) -> Result<SecurityConfig> {
+        if self.symbol.trim().is_empty() {
+            bail!("security symbol must not be empty");
+        }
+        match (&self.security_type, &self.futures_specs) {
+            (SecurityType::Future, None) => {
+                bail!("{}: futures security requires futures_specs", self.symbol)
+            }
+            (SecurityType::Stock | SecurityType::Forex, Some(_)) => {
+                bail!("{}: futures_specs only apply to futures", self.symbol)
+            }
+            _ => {}
+        }
+        if self.multiplier.is_some() && self.security_type != SecurityType::Future {
+            bail!("{}: multiplier applies to futures only", self.symbol);
+        }
+
+        Ok(SecurityConfig {
+            symbol: self.symbol,
+            security_type: self.security_type,
+            exchange: self.exchange,
+            currency: self.currency,
+            futures_specs: self.futures_specs,
+            extended_hours_data: self.extended_hours_data,
+            outside_rth_orders: self.outside_rth_orders,
+            return_mode: self.return_mode,
+            trade: self.trade,
+            commission_per_unit: self.commission_per_unit,
+            commission_min: self.commission_min,
+            multiplier: self.multiplier,
+        })
+    }
+}
+
+/// Distance of a bracket leg from the entry price
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BracketOffset {
+    Fraction(f64),    // Fraction of the entry price
+    AtrMultiple(f64), // Multiple of the Average True Range
+}
+
+impl BracketOffset {
+    fn distance(self, entry_price: f64, atr: Option<f64>) -> Option<f64> {
+        match self {
+            BracketOffset::Fraction(fraction) => Some(entry_price * fraction),
+            BracketOffset::AtrMultiple(multiple) => atr.map(|atr| atr * multiple),
+        }
+    }
+}
+
+/// Protective take-profit and stop-loss legs submitted with every entry
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BracketConfig {
+    pub take_profit: BracketOffset,
+    pub stop_loss: BracketOffset,
+    #[serde(default = "default_bracket_atr_period")]
+    pub atr_period: usize,
+}
+
+impl BracketConfig {
+    /// Leg prices for an entry at `entry_price`
+    ///
+    /// Returns None when an ATR offset is configured but `atr` is unknown.
+    pub fn levels(
+        &self,
+        entry_price: f64,
+        is_long: bool,
+        atr: Option<f64>,
+    ) -> Option<BracketLevels> {
+        let profit = self.take_profit.distance(entry_price, atr)?;
+        let loss = self.stop_loss.distance(entry_price, atr)?;
+        let direction = if is_long { 1.0 } else { -1.0 };
+        Some(BracketLevels {
+            take_profit: entry_price + direction * profit,
+            stop_loss: entry_price - direction * loss,
+        })
+    }
+}
+
+fn default_bracket_atr_period() -> usize {
+    14
+}
+
+/// Reprice unfilled limit entries toward the market (see
+/// `execution::OrderChaser`)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OrderChaseConfig {
+    pub timeout_seconds: u64, // Time each price gets before repricing
+    #[serde(default = "default_chase_poll_seconds")]
+    pub poll_interval_seconds: u64, // Time between order status checks
+    pub step: f64,            // Reprice step as a fraction of the original limit
+    pub max_chase: f64,       // Max fractional distance from the original limit
+    #[serde(default)]
+    pub convert_to_market: bool, // Send a market order once the chase is exhausted
+}
+
+fn default_chase_poll_seconds() -> u64 {
+    5
+}
+
+/// Trading days per year used to annualize returns and volatility
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TradingCalendar {
+    Equities252,   // Exchange-traded markets
+    Continuous365, // Markets trading every day
+    Custom(f64),
+}
+
+impl TradingCalendar {
+    pub fn trading_days(self) -> f64 {
+        match self {
+            TradingCalendar::Equities252 => 252.0,
+            TradingCalendar::Continuous365 => 365.0,
+            TradingCalendar::Custom(days) => days,
+        }
+    }
+
+    /// 365 days for forex, 252 for everything else
+    pub fn for_security_type(security_type: &SecurityType) -> Self {
+        match security_type {
+            SecurityType::Forex => TradingCalendar::Continuous365,
+            SecurityType::Stock | SecurityType::Future => TradingCalendar::Equities252,
+        }
+    }
+}
+
+/// Exposure cut applied to new position sizes while in drawdown
+///
+/// Scales linearly from 100% at no drawdown to `min_scale` at
+/// `max_drawdown`, and stays at `min_scale` beyond it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DrawdownScaling {
+    pub max_drawdown: f64, // Drawdown fraction at which the floor is reached
+    pub min_scale: f64,    // Fraction of normal size kept at the floor
+}
+
+impl DrawdownScaling {
+    /// Position size multiplier for a fractional `drawdown`
+    pub fn scale(&self, drawdown: f64) -> f64 {
+        let progress = (drawdown / self.max_drawdown).clamp(0.0, 1.0);
+        1.0 - progress * (1.0 - self.min_scale)
+    }
+}
+
+/// Hard de-risk on a deep drawdown
+///
+/// Once drawdown passes `trigger_drawdown` positions are cut until
+/// `cash_floor` of equity is in cash, and entries stay blocked until equity
+/// climbs `trigger_drawdown - reentry_drawdown` off its trough.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DrawdownDerisking {
+    pub trigger_drawdown: f64, // Drawdown fraction that starts liquidating
+    pub reentry_drawdown: f64, // Entries resume after a recovery of trigger less this off the trough
+    pub cash_floor: f64,       // Fraction of equity held in cash while de-risked
+}
+
+/// How a stock position size snaps to a tradable quantity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ShareRounding {
+    Floor, // Toward zero, never above the target size
+    #[default]
+    Round,
+    Ceil, // Away from zero
+}
+
+impl ShareRounding {
+    /// Snap the magnitude of `quantity` to a multiple of `increment`
+    pub fn apply(self, quantity: f64, increment: f64) -> f64 {
+        let units = quantity.abs() / increment;
+        let units = match self {
+            ShareRounding::Floor => units.floor(),
+            ShareRounding::Round => units.round(),
+            ShareRounding::Ceil => units.ceil(),
+        };
+        quantity.signum() * units * increment
+    }
+}
+
+/// How a forex position size turns into an order quantity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum SizingMode {
+    #[default]
+    Lots, // Snap to standard, mini or micro lots, at least one lot
+    Notional {
+        min_notional: f64,
+    }, // Whole base units hitting the target notional, at least min_notional
+}
+
+/// How stop-loss prices are placed relative to entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum StopLossMethod {
+    #[default]
+    Percentage, // Fixed stop_loss_percentage from entry
+    Atr {
+        period: usize,
+        multiple: f64,
+    }, // Entry -/+ multiple x Average True Range
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RiskConfig {
     pub max_position_size: f64,
     pub max_portfolio_exposure: f64,
     pub stop_loss_percentage: f64,
     pub take_profit_percentage: f64,
+    #[serde(default)]
+    pub stop_loss_method: StopLossMethod,
     #[serde(default = "default_max_margin_utilization")]
     pub max_margin_utilization: f64,
     #[serde(default = "default_min_excess_liquidity")]
@@ -85,15 +758,11 @@ pub struct RiskConfig {
     pub correlation_lookback_days: usize,
     #[serde(default = "default_min_positions_for_erc")]
     pub min_positions_for_erc: usize,
+    #[serde(default = "default_max_cluster_exposure")]
+    pub max_cluster_exposure: f64,
     // Transaction Cost Configuration
     #[serde(default = "default_enable_transaction_cost_optimization")]
     pub enable_transaction_cost_optimization: bool,
-    #[serde(default = "default_stock_commission")]
-    pub stock_commission: f64,
-    #[serde(default = "default_futures_commission")]
-    pub futures_commission: f64,
-    #[serde(default = "default_forex_commission")]
-    pub forex_commission: f64,
     #[serde(default = "default_max_acceptable_cost_bps")]
     pub max_acceptable_cost_bps: f64,
     // Position Inertia Configuration
@@ -105,6 +774,35 @@ pub struct RiskConfig {
     pub min_position_change_value: f64,
     #[serde(default = "default_max_position_change_pct")]
     pub max_position_change_pct: f64,
+    // Circuit Breaker Configuration
+    #[serde(default = "default_max_daily_loss")]
+    pub max_daily_loss: f64,
+    #[serde(default = "default_halt_reset_hour_utc")]
+    pub halt_reset_hour_utc: u32,
+    // Anti-churn: positions younger than this are not exited (0 = off)
+    #[serde(default)]
+    pub min_holding_period_minutes: u64,
+    // No re-entry into a symbol for this long after a risk-reduction exit (0 = off)
+    #[serde(default)]
+    pub reentry_cooldown_minutes: u64,
+    // Shrink new positions while the account is in drawdown (None = off)
+    #[serde(default)]
+    pub drawdown_scaling: Option<DrawdownScaling>,
+    // Move to cash and pause entries on a deep drawdown (None = off)
+    #[serde(default)]
+    pub drawdown_derisking: Option<DrawdownDerisking>,
+    // Rest stop_loss/take_profit OCO orders against positions confirmed by sync
+    #[serde(default)]
+    pub auto_protective_orders: bool,
+    // Stocks that may be sold short; short-opening orders in any other stock are rejected. The pairs strategy needs both stock legs listed
+    #[serde(default)]
+    pub shortable_symbols: Vec<String>,
+    // Largest short position in a single stock, in shares (0 = no limit)
+    #[serde(default)]
+    pub max_short_quantity: f64,
+    // New orders for a symbol wait until its last order resolves; one unresolved this long is cancelled (0 = off)
+    #[serde(default = "default_working_order_timeout_seconds")]
+    pub working_order_timeout_seconds: u64,
 }
 
 impl Default for RiskConfig {
@@ -114,6 +812,7 @@ impl Default for RiskConfig {
             max_portfolio_exposure: 1.0,  // 100% max exposure
             stop_loss_percentage: 0.02,   // 2% stop loss
             take_profit_percentage: 0.04, // 4% take profit
+            stop_loss_method: StopLossMethod::default(),
             max_margin_utilization: default_max_margin_utilization(),
             min_excess_liquidity: default_min_excess_liquidity(),
             futures_position_limit: default_futures_position_limit(),
@@ -125,15 +824,23 @@ impl Default for RiskConfig {
             max_correlation_exposure: default_max_correlation_exposure(),
             correlation_lookback_days: default_correlation_lookback_days(),
             min_positions_for_erc: default_min_positions_for_erc(),
+            max_cluster_exposure: default_max_cluster_exposure(),
             enable_transaction_cost_optimization: default_enable_transaction_cost_optimization(),
-            stock_commission: default_stock_commission(),
-            futures_commission: default_futures_commission(),
-            forex_commission: default_forex_commission(),
             max_acceptable_cost_bps: default_max_acceptable_cost_bps(),
             enable_position_inertia: default_enable_position_inertia(),
             inertia_multiplier: default_inertia_multiplier(),
             min_position_change_value: default_min_position_change_value(),
             max_position_change_pct: default_max_position_change_pct(),
+            max_daily_loss: default_max_daily_loss(),
+            halt_reset_hour_utc: default_halt_reset_hour_utc(),
+            min_holding_period_minutes: 0,
+            reentry_cooldown_minutes: 0,
+            drawdown_scaling: None,
+            drawdown_derisking: None,
+            auto_protective_orders: false,
+            shortable_symbols: Vec::new(),
+            max_short_quantity: 0.0,
+            working_order_timeout_seconds: default_working_order_timeout_seconds(),
         }
     }
 }
@@ -162,6 +869,10 @@ fn default_volatility_halflife() -> f64 {
     32.0 // 32-day half-life for EWMA
 }
 
+fn default_trade() -> bool {
+    true
+}
+
 fn default_use_limit_orders() -> bool {
     true // Prefer limit orders for better execution prices
 }
@@ -170,6 +881,60 @@ fn default_limit_order_offset() -> f64 {
     0.01 // 1% offset from current price for limit orders
 }
 
+fn default_client_id_attempts() -> u32 {
+    1 // Only the configured client_id
+}
+
+fn default_max_orders_per_second() -> f64 {
+    40.0 // Headroom under IBKR's 50 messages per second
+}
+
+fn default_simulated_starting_cash() -> f64 {
+    100_000.0
+}
+
+fn default_realtime_bar_seconds() -> u32 {
+    5 // IBKR's native real-time bar
+}
+
+fn default_max_data_age_seconds() -> u64 {
+    300 // Treat market data older than 5 minutes as stale
+}
+
+fn default_max_warmup_seconds() -> u64 {
+    60
+}
+
+fn default_max_tick_deviation() -> f64 {
+    DEFAULT_MAX_TICK_DEVIATION
+}
+
+fn default_signal_weights() -> SignalWeights {
+    SignalWeights {
+        momentum: 0.5,
+        breakout: 0.3,
+        carry: 0.0,          // No carry signal generated yet
+        mean_reversion: 0.2, // Bollinger signals
+        ..SignalWeights::default()
+    }
+}
+
+fn default_signal_quality_threshold() -> f64 {
+    1.0 // Out of the +/-20 signal range
+}
+
+fn default_signal_consensus_threshold() -> f64 {
+    2.0 / 3.0 // Two thirds of signals pointing the same way
+}
+
+fn default_max_positions() -> usize {
+    5 // Hold the top five names
+}
+
+fn default_futures_roll_window_days() -> i64 {
+    5 // Roll futures positions within 5 calendar days of expiry
+}
+
 // Risk Budgeting Configuration Defaults
 fn default_enable_risk_budgeting() -> bool {
     true // Enable risk budgeting by default
@@ -195,23 +960,15 @@ fn default_min_positions_for_erc() -> usize {
     3 // Minimum 3 positions required for Equal Risk Contribution
 }
 
+fn default_max_cluster_exposure() -> f64 {
+    0.40 // Maximum 40% of portfolio value in one correlation cluster
+}
+
 // Transaction Cost Configuration Defaults
 fn default_enable_transaction_cost_optimization() -> bool {
     true // Enable transaction cost optimization by default
 }
 
-fn default_stock_commission() -> f64 {
-    1.00 // $1.00 per stock trade
-}
-
-fn default_futures_commission() -> f64 {
-    2.50 // $2.50 per futures contract
-}
-
-fn default_forex_commission() -> f64 {
-    0.50 // $0.50 per forex trade
-}
-
 fn default_max_acceptable_cost_bps() -> f64 {
     15.0 // 15 basis points maximum acceptable transaction cost
 }
@@ -233,6 +990,19 @@ fn default_max_position_change_pct() -> f64 {
     0.50 // 50% maximum position change per rebalance
 }
 
+// Circuit Breaker Configuration Defaults
+fn default_max_daily_loss() -> f64 {
+    0.03 // Halt new entries after a 3% intraday loss
+}
+
+fn default_halt_reset_hour_utc() -> u32 {
+    0 // Trading day rolls over at midnight UTC
+}
+
+fn default_working_order_timeout_seconds() -> u64 {
+    300
+}
+
 impl TradingConfig {
     pub fn load() -> Result<Self> {
         Self::load_from_file("config.json")
@@ -243,20 +1013,261 @@ impl TradingConfig {
 
         let mut config: TradingConfig = serde_json::from_str(&config_str)?;
 
+        // Keep hosts and client IDs out of committed config files
+        config.tws_config.apply_env_overrides()?;
+
         // Update futures contracts with current expiry dates
         config.update_futures_expiries()?;
 
+        config.validate()?;
+
         Ok(config)
     }
 
+    /// Check configuration values are in sensible ranges
+    ///
+    /// Every problem found is reported in a single error rather than stopping
+    /// at the first one.
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+
+        if self.tws_config.host.trim().is_empty() {
+            errors.push("tws_config.host must not be empty".to_string());
+        }
+        if self.tws_config.dry_run && self.tws_config.simulate_fills {
+            errors.push(
+                "tws_config.dry_run and tws_config.simulate_fills are mutually exclusive"
+                    .to_string(),
+            );
+        }
+        if self.tws_config.client_id_attempts == 0 {
+            errors.push("tws_config.client_id_attempts must be positive".to_string());
+        }
+        check_positive(
+            &mut errors,
+            "tws_config.max_orders_per_second",
+            self.tws_config.max_orders_per_second,
+        );
+        check_positive(
+            &mut errors,
+            "tws_config.simulated_starting_cash",
+            self.tws_config.simulated_starting_cash,
+        );
+        if let Err(e) = RealtimeBarSpec::from_seconds(self.tws_config.realtime_bar_seconds) {
+            errors.push(format!("tws_config.realtime_bar_seconds: {}", e));
+        }
+        if u64::from(self.tws_config.realtime_bar_seconds)
+            >= self.strategy_config.max_data_age_seconds
+        {
+            errors.push(format!(
+                "tws_config.realtime_bar_seconds ({}s) must be shorter than strategy_config.max_data_age_seconds ({}s), or data goes stale between bars",
+                self.tws_config.realtime_bar_seconds, self.strategy_config.max_data_age_seconds
+            ));
+        }
+
+        self.strategy_config.collect_errors(&mut errors);
+        self.risk_config.collect_errors(&mut errors);
+        for (name, costs) in [
+            ("stock", &self.cost_model.stock),
+            ("future", &self.cost_model.future),
+            ("forex", &self.cost_model.forex),
+        ] {
+            collect_cost_errors(&mut errors, name, costs);
+        }
+        if let Some(fraction) = self.cost_model.max_commission_fraction
+            && !(fraction > 0.0 && fraction.is_finite())
+        {
+            errors.push(format!(
+                "cost_model.max_commission_fraction must be positive, got {}",
+                fraction
+            ));
+        }
+        if self.journal.enabled && self.journal.max_file_bytes == 0 {
+            errors.push("journal.max_file_bytes must be positive".to_string());
+        }
+        match &self.pairs {
+            Some(pairs) => collect_pairs_errors(&mut errors, pairs, &self.strategy_config),
+            None if self.strategy.eq_ignore_ascii_case("pairs") => {
+                errors.push("pairs must be set when strategy is pairs".to_string());
+            }
+            None => {}
+        }
+        // Either leg may be the short one; an unlisted stock would block every
+        // trade on that side of the spread
+        if let Some(pairs) = &self.pairs
+            && self.strategy.eq_ignore_ascii_case("pairs")
+        {
+            for symbol in [&pairs.symbol_a, &pairs.symbol_b] {
+                let is_stock = self
+                    .strategy_config
+                    .securities
+                    .iter()
+                    .any(|s| &s.symbol == symbol && s.security_type == SecurityType::Stock);
+                if is_stock && !self.risk_config.shortable_symbols.contains(symbol) {
+                    errors.push(format!(
+                        "pairs symbol {:?} must be in risk_config.shortable_symbols",
+                        symbol
+                    ));
+                }
+            }
+        }
+        for (name, allocation) in &self.strategy_allocations {
+            if !(*allocation > 0.0 && *allocation <= 1.0) {
+                errors.push(format!(
+                    "strategy_allocations.{} must be in (0, 1], got {}",
+                    name, allocation
+                ));
+            }
+        }
+        let total_allocation: f64 = self.strategy_allocations.values().sum();
+        if total_allocation > 1.0 + 1e-9 {
+            errors.push(format!(
+                "strategy_allocations must sum to at most 1, got {:.4}",
+                total_allocation
+            ));
+        }
+
+        if !errors.is_empty() {
+            bail!(
+                "Invalid configuration ({} problems):\n  - {}",
+                errors.len(),
+                errors.join("\n  - ")
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Capital fraction for the named strategy
+    ///
+    /// With no allocations configured a lone strategy gets the whole account;
+    /// otherwise strategies left out of the map get nothing.
+    pub fn strategy_allocation(&self, name: &str) -> f64 {
+        if self.strategy_allocations.is_empty() {
+            1.0
+        } else {
+            self.strategy_allocations.get(name).copied().unwrap_or(0.0)
+        }
+    }
+
+    /// Take the live-safe settings from a reloaded config file
+    ///
+    /// Thresholds, weights and risk limits take effect from the next cycle.
+    /// Settings read once at startup (the connection, securities, strategy
+    /// selection and components built from them) keep their running values;
+    /// the names of any that changed are returned so the caller can ask for a
+    /// restart.
+    pub fn apply_reload(&mut self, mut reloaded: TradingConfig) -> Vec<&'static str> {
+        let mut restart_required = Vec::new();
+        let changed = &mut restart_required;
+        keep_running(
+            changed,
+            "tws_config",
+            &self.tws_config,
+            &mut reloaded.tws_config,
+        );
+        keep_running(
+            changed,
+            "cost_model",
+            &self.cost_model,
+            &mut reloaded.cost_model,
+        );
+        keep_running(changed, "journal", &self.journal, &mut reloaded.journal);
+        keep_running(
+            changed,
+            "status_server",
+            &self.status_server,
+            &mut reloaded.status_server,
+        );
+        keep_running(changed, "strategy", &self.strategy, &mut reloaded.strategy);
+        keep_running(changed, "pairs", &self.pairs, &mut reloaded.pairs);
+        keep_running(
+            changed,
+            "strategy_allocations",
+            &self.strategy_allocations,
+            &mut reloaded.strategy_allocations,
+        );
+
+        keep_running(
+            changed,
+            "strategy_config.securities",
+            &self.strategy_config.securities,
+            &mut reloaded.strategy_config.securities,
+        );
+
+        // Baked into the order manager and the cost/inertia filter at startup
+        let (running, risk) = (&self.risk_config, &mut reloaded.risk_config);
+        keep_running(
+            changed,
+            "risk_config.enable_transaction_cost_optimization",
+            &running.enable_transaction_cost_optimization,
+            &mut risk.enable_transaction_cost_optimization,
+        );
+        keep_running(
+            changed,
+            "risk_config.enable_position_inertia",
+            &running.enable_position_inertia,
+            &mut risk.enable_position_inertia,
+        );
+        keep_running(
+            changed,
+            "risk_config.inertia_multiplier",
+            &running.inertia_multiplier,
+            &mut risk.inertia_multiplier,
+        );
+        keep_running(
+            changed,
+            "risk_config.min_position_change_value",
+            &running.min_position_change_value,
+            &mut risk.min_position_change_value,
+        );
+        keep_running(
+            changed,
+            "risk_config.max_position_change_pct",
+            &running.max_position_change_pct,
+            &mut risk.max_position_change_pct,
+        );
+        keep_running(
+            changed,
+            "risk_config.min_holding_period_minutes",
+            &running.min_holding_period_minutes,
+            &mut risk.min_holding_period_minutes,
+        );
+        keep_running(
+            changed,
+            "risk_config.working_order_timeout_seconds",
+            &running.working_order_timeout_seconds,
+            &mut risk.working_order_timeout_seconds,
+        );
+
+        *self = reloaded;
+        restart_required
+    }
+
     fn default_config_json() -> String {
         serde_json::to_string_pretty(&Self::default()).unwrap()
     }
 
     /// Update futures contracts with current front-month expiry dates
+    ///
+    /// Underlyings listed more than once are calendar positions on the
+    /// configured months and are left as written; rolling them would collapse
+    /// every month onto the front one.
     fn update_futures_expiries(&mut self) -> Result<()> {
+        let securities = &self.strategy_config.securities;
+        let calendars: Vec<String> = securities
+            .iter()
+            .filter(|security| {
+                security.security_type == SecurityType::Future
+                    && security_id(securities, security) != security.symbol
+            })
+            .map(|security| security.symbol.clone())
+            .collect();
+
         for security in &mut self.strategy_config.securities {
-            if security.security_type == SecurityType::Future {
+            if security.security_type == SecurityType::Future
+                && !calendars.contains(&security.symbol)
+            {
                 if let Some(futures_specs) = &mut security.futures_specs {
                     match get_front_month_contract(&security.symbol) {
                         Ok((expiry, contract_month)) => {
@@ -285,6 +1296,551 @@ impl TradingConfig {
     }
 }
 
+impl StrategyConfig {
+    /// Lookback for `timeframe`, falling back to `lookback_period`
+    pub fn lookback_for(&self, timeframe: TimeFrame) -> usize {
+        self.lookback_periods
+            .get(&timeframe)
+            .copied()
+            .unwrap_or(self.lookback_period)
+    }
+
+    /// Configured rebalance schedule, or a fixed `rebalance_frequency_minutes` interval
+    pub fn rebalance_schedule(&self) -> Schedule {
+        self.rebalance_schedule
+            .clone()
+            .unwrap_or(Schedule::Interval {
+                minutes: self.rebalance_frequency_minutes,
+            })
+    }
+
+    /// `security_id` of every configured security, in order and without
+    /// duplicates
+    pub fn security_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = Vec::new();
+        for security in &self.securities {
+            let id = security_id(&self.securities, security);
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        ids
+    }
+
+    /// `security_ids` without the data-only securities: the names the
+    /// strategy scores and sizes
+    pub fn tradable_security_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = Vec::new();
+        for security in self.securities.iter().filter(|security| security.trade) {
+            let id = security_id(&self.securities, security);
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        ids
+    }
+
+    /// Bars of history every symbol needs before the trading loop starts,
+    /// by default enough for one `lookback_period` return
+    pub fn min_bars_for_signals(&self) -> usize {
+        self.min_bars_for_signals
+            .unwrap_or(self.lookback_period + 1)
+    }
+
+    fn collect_errors(&self, errors: &mut Vec<String>) {
+        if self.securities.is_empty() {
+            errors.push("strategy_config.securities must not be empty".to_string());
+        } else if !self.securities.iter().any(|security| security.trade) {
+            errors
+                .push("strategy_config.securities has no security with trade enabled".to_string());
+        }
+        for (index, security) in self.securities.iter().enumerate() {
+            let name = if security.symbol.trim().is_empty() {
+                format!("securities[{}]", index)
+            } else {
+                security.symbol.clone()
+            };
+            if security.symbol.trim().is_empty() {
+                errors.push(format!("{}: symbol must not be empty", name));
+            }
+            if security.exchange.trim().is_empty() {
+                errors.push(format!("{}: exchange must not be empty", name));
+            }
+            if security.currency.trim().is_empty() {
+                errors.push(format!("{}: currency must not be empty", name));
+            }
+            if security.security_type == SecurityType::Future && security.futures_specs.is_none() {
+                errors.push(format!("{}: futures security requires futures_specs", name));
+            }
+            if let Some(per_unit) = security.commission_per_unit {
+                check_non_negative(errors, &format!("{}.commission_per_unit", name), per_unit);
+            }
+            if let Some(min) = security.commission_min {
+                check_non_negative(errors, &format!("{}.commission_min", name), min);
+            }
+            if let Some(multiplier) = security.multiplier {
+                check_positive(errors, &format!("{}.multiplier", name), multiplier);
+                if security.security_type != SecurityType::Future {
+                    errors.push(format!("{}: multiplier applies to futures only", name));
+                }
+            }
+        }
+
+        if self.lookback_period < 2 {
+            errors.push(format!(
+                "strategy_config.lookback_period must be at least 2, got {}",
+                self.lookback_period
+            ));
+        }
+        let supported = TimeFrame::carver_momentum_timeframes();
+        let mut timeframes: Vec<_> = self.lookback_periods.iter().collect();
+        timeframes.sort_by_key(|(timeframe, _)| timeframe.to_minutes());
+        for (timeframe, lookback) in timeframes {
+            if !supported.contains(timeframe) {
+                errors.push(format!(
+                    "strategy_config.lookback_periods: unsupported timeframe {:?}",
+                    timeframe
+                ));
+            } else if *lookback < 2 {
+                errors.push(format!(
+                    "strategy_config.lookback_periods[{:?}] must be at least 2, got {}",
+                    timeframe, lookback
+                ));
+            }
+        }
+        if self.rebalance_frequency_minutes == 0 {
+            errors.push("strategy_config.rebalance_frequency_minutes must be positive".to_string());
+        }
+        let weights = &self.signal_weights;
+        for (name, weight) in [
+            ("momentum", weights.momentum),
+            ("breakout", weights.breakout),
+            ("carry", weights.carry),
+            ("mean_reversion", weights.mean_reversion),
+        ] {
+            check_non_negative(
+                errors,
+                &format!("strategy_config.signal_weights.{}", name),
+                weight,
+            );
+        }
+        for (name, weight) in &weights.additional {
+            check_non_negative(
+                errors,
+                &format!("strategy_config.signal_weights.additional.{}", name),
+                *weight,
+            );
+        }
+        if weights.total() <= 0.0 {
+            errors.push("strategy_config.signal_weights must not all be zero".to_string());
+        }
+        if let Some(schedule) = &self.rebalance_schedule
+            && let Err(e) = schedule.validate()
+        {
+            errors.push(format!("strategy_config.rebalance_schedule: {}", e));
+        }
+        check_positive(
+            errors,
+            "strategy_config.target_volatility",
+            self.target_volatility,
+        );
+        check_positive(
+            errors,
+            "strategy_config.volatility_halflife",
+            self.volatility_halflife,
+        );
+        check_fraction(
+            errors,
+            "strategy_config.limit_order_offset",
+            self.limit_order_offset,
+        );
+        for (security_type, threshold) in [
+            ("stock", self.return_outliers.stock),
+            ("future", self.return_outliers.future),
+            ("forex", self.return_outliers.forex),
+        ] {
+            check_positive(
+                errors,
+                &format!("strategy_config.return_outliers.{}", security_type),
+                threshold,
+            );
+        }
+        if let SizingMode::Notional { min_notional } = self.sizing_mode {
+            check_positive(
+                errors,
+                "strategy_config.sizing_mode min_notional",
+                min_notional,
+            );
+        }
+        if self.max_data_age_seconds == 0 {
+            errors.push("strategy_config.max_data_age_seconds must be positive".to_string());
+        }
+        if self.data_gaps.bar_interval_seconds <= 0 {
+            errors.push(
+                "strategy_config.data_gaps.bar_interval_seconds must be positive".to_string(),
+            );
+        }
+        if self.data_gaps.tolerance.is_nan() || self.data_gaps.tolerance < 1.0 {
+            errors.push(format!(
+                "strategy_config.data_gaps.tolerance must be at least 1, got {}",
+                self.data_gaps.tolerance
+            ));
+        }
+        if !(self.signal_smoothing >= 0.0 && self.signal_smoothing < 1.0) {
+            errors.push(format!(
+                "strategy_config.signal_smoothing must be in [0, 1), got {}",
+                self.signal_smoothing
+            ));
+        }
+        check_non_negative(
+            errors,
+            "strategy_config.exit_hysteresis",
+            self.exit_hysteresis,
+        );
+        check_positive(
+            errors,
+            "strategy_config.max_tick_deviation",
+            self.max_tick_deviation,
+        );
+        if let Some(TradingCalendar::Custom(days)) = self.trading_calendar {
+            check_positive(errors, "strategy_config.trading_calendar", days);
+        }
+        if self.max_positions == 0 {
+            errors.push("strategy_config.max_positions must be positive".to_string());
+        }
+        check_non_negative(
+            errors,
+            "strategy_config.rotation_margin",
+            self.rotation_margin,
+        );
+        if let Some(max_spread_bps) = self.max_spread_bps {
+            check_positive(errors, "strategy_config.max_spread_bps", max_spread_bps);
+        }
+        check_non_negative(
+            errors,
+            "strategy_config.min_average_volume",
+            self.min_average_volume,
+        );
+        check_non_negative(
+            errors,
+            "strategy_config.signal_quality_threshold",
+            self.signal_quality_threshold,
+        );
+        if !(0.0..=1.0).contains(&self.signal_consensus_threshold) {
+            errors.push(format!(
+                "strategy_config.signal_consensus_threshold must be in [0, 1], got {}",
+                self.signal_consensus_threshold
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.min_timeframe_consensus) {
+            errors.push(format!(
+                "strategy_config.min_timeframe_consensus must be in [0, 1], got {}",
+                self.min_timeframe_consensus
+            ));
+        }
+        if let Some(brackets) = &self.bracket_orders {
+            for (leg, offset) in [
+                ("take_profit", brackets.take_profit),
+                ("stop_loss", brackets.stop_loss),
+            ] {
+                let (BracketOffset::Fraction(value) | BracketOffset::AtrMultiple(value)) = offset;
+                check_positive(
+                    errors,
+                    &format!("strategy_config.bracket_orders.{}", leg),
+                    value,
+                );
+            }
+            if brackets.atr_period == 0 {
+                errors
+                    .push("strategy_config.bracket_orders.atr_period must be positive".to_string());
+            }
+        }
+        if let Some(chase) = &self.order_chase {
+            if chase.timeout_seconds == 0 || chase.poll_interval_seconds == 0 {
+                errors.push(
+                    "strategy_config.order_chase timeout_seconds and poll_interval_seconds must be positive"
+                        .to_string(),
+                );
+            }
+            check_positive(errors, "strategy_config.order_chase.step", chase.step);
+            check_positive(
+                errors,
+                "strategy_config.order_chase.max_chase",
+                chase.max_chase,
+            );
+        }
+        if self.futures_roll_window_days < 0 {
+            errors.push(format!(
+                "strategy_config.futures_roll_window_days must not be negative, got {}",
+                self.futures_roll_window_days
+            ));
+        }
+        // Same range `calculate_ewma_volatility` accepts; 0 weights only the latest return
+        if let VolatilityEstimator::Ewma { lambda } = self.volatility_estimator
+            && !(0.0..1.0).contains(&lambda)
+        {
+            errors.push(format!(
+                "strategy_config.volatility_estimator lambda must be in [0, 1), got {}",
+                lambda
+            ));
+        }
+    }
+}
+
+impl RiskConfig {
+    fn collect_errors(&self, errors: &mut Vec<String>) {
+        // max_position_size is a percentage of portfolio value (see RiskManager)
+        if !(self.max_position_size > 0.0 && self.max_position_size <= 100.0) {
+            errors.push(format!(
+                "risk_config.max_position_size is a percentage of portfolio and must be in (0, 100], got {}",
+                self.max_position_size
+            ));
+        }
+
+        check_fraction(
+            errors,
+            "risk_config.max_portfolio_exposure",
+            self.max_portfolio_exposure,
+        );
+        check_fraction(
+            errors,
+            "risk_config.stop_loss_percentage",
+            self.stop_loss_percentage,
+        );
+        check_fraction(
+            errors,
+            "risk_config.take_profit_percentage",
+            self.take_profit_percentage,
+        );
+        if let StopLossMethod::Atr { period, multiple } = self.stop_loss_method {
+            if period == 0 {
+                errors.push("risk_config.stop_loss_method ATR period must be positive".to_string());
+            }
+            check_positive(
+                errors,
+                "risk_config.stop_loss_method ATR multiple",
+                multiple,
+            );
+        }
+        check_fraction(
+            errors,
+            "risk_config.max_margin_utilization",
+            self.max_margin_utilization,
+        );
+        check_fraction(
+            errors,
+            "risk_config.margin_call_threshold",
+            self.margin_call_threshold,
+        );
+        check_fraction(
+            errors,
+            "risk_config.margin_buffer_percentage",
+            self.margin_buffer_percentage,
+        );
+        check_fraction(
+            errors,
+            "risk_config.risk_budget_target_volatility",
+            self.risk_budget_target_volatility,
+        );
+        check_fraction(
+            errors,
+            "risk_config.risk_budget_rebalance_threshold",
+            self.risk_budget_rebalance_threshold,
+        );
+        check_fraction(
+            errors,
+            "risk_config.max_correlation_exposure",
+            self.max_correlation_exposure,
+        );
+        check_fraction(
+            errors,
+            "risk_config.max_cluster_exposure",
+            self.max_cluster_exposure,
+        );
+        check_fraction(
+            errors,
+            "risk_config.max_position_change_pct",
+            self.max_position_change_pct,
+        );
+        check_fraction(errors, "risk_config.max_daily_loss", self.max_daily_loss);
+        check_non_negative(
+            errors,
+            "risk_config.max_short_quantity",
+            self.max_short_quantity,
+        );
+        if let Some(scaling) = &self.drawdown_scaling {
+            check_fraction(
+                errors,
+                "risk_config.drawdown_scaling.max_drawdown",
+                scaling.max_drawdown,
+            );
+            if !(0.0..=1.0).contains(&scaling.min_scale) {
+                errors.push(format!(
+                    "risk_config.drawdown_scaling.min_scale must be in [0, 1], got {}",
+                    scaling.min_scale
+                ));
+            }
+        }
+        if let Some(derisking) = &self.drawdown_derisking {
+            check_fraction(
+                errors,
+                "risk_config.drawdown_derisking.trigger_drawdown",
+                derisking.trigger_drawdown,
+            );
+            if !(0.0..derisking.trigger_drawdown).contains(&derisking.reentry_drawdown) {
+                errors.push(format!(
+                    "risk_config.drawdown_derisking.reentry_drawdown must be in [0, trigger_drawdown), got {}",
+                    derisking.reentry_drawdown
+                ));
+            }
+            if !(0.0..=1.0).contains(&derisking.cash_floor) {
+                errors.push(format!(
+                    "risk_config.drawdown_derisking.cash_floor must be in [0, 1], got {}",
+                    derisking.cash_floor
+                ));
+            }
+        }
+        if self.halt_reset_hour_utc > 23 {
+            errors.push(format!(
+                "risk_config.halt_reset_hour_utc must be an hour between 0 and 23, got {}",
+                self.halt_reset_hour_utc
+            ));
+        }
+
+        check_non_negative(
+            errors,
+            "risk_config.min_excess_liquidity",
+            self.min_excess_liquidity,
+        );
+        check_positive(
+            errors,
+            "risk_config.futures_position_limit",
+            self.futures_position_limit,
+        );
+        check_positive(
+            errors,
+            "risk_config.max_acceptable_cost_bps",
+            self.max_acceptable_cost_bps,
+        );
+        check_non_negative(
+            errors,
+            "risk_config.inertia_multiplier",
+            self.inertia_multiplier,
+        );
+        check_non_negative(
+            errors,
+            "risk_config.min_position_change_value",
+            self.min_position_change_value,
+        );
+
+        if self.correlation_lookback_days < 2 {
+            errors.push(format!(
+                "risk_config.correlation_lookback_days must be at least 2, got {}",
+                self.correlation_lookback_days
+            ));
+        }
+        if self.min_positions_for_erc < 2 {
+            errors.push(format!(
+                "risk_config.min_positions_for_erc must be at least 2, got {}",
+                self.min_positions_for_erc
+            ));
+        }
+    }
+}
+
+fn collect_cost_errors(errors: &mut Vec<String>, name: &str, costs: &InstrumentCosts) {
+    let prefix = format!("cost_model.{}", name);
+    check_non_negative(
+        errors,
+        &format!("{}.commission_per_unit", prefix),
+        costs.commission_per_unit,
+    );
+    check_non_negative(
+        errors,
+        &format!("{}.commission_bps", prefix),
+        costs.commission_bps,
+    );
+    check_non_negative(
+        errors,
+        &format!("{}.min_commission", prefix),
+        costs.min_commission,
+    );
+    check_non_negative(
+        errors,
+        &format!("{}.slippage_bps", prefix),
+        costs.slippage_bps,
+    );
+}
+
+fn collect_pairs_errors(errors: &mut Vec<String>, pairs: &PairsConfig, strategy: &StrategyConfig) {
+    for symbol in [&pairs.symbol_a, &pairs.symbol_b] {
+        match strategy.securities.iter().find(|s| &s.symbol == symbol) {
+            None => errors.push(format!(
+                "pairs symbol {:?} is not in strategy_config.securities",
+                symbol
+            )),
+            Some(security) if !security.trade => errors.push(format!(
+                "pairs symbol {:?} is data-only (trade: false)",
+                symbol
+            )),
+            Some(_) => {}
+        }
+    }
+    if pairs.symbol_a == pairs.symbol_b {
+        errors.push("pairs.symbol_a and pairs.symbol_b must differ".to_string());
+    }
+    if pairs.lookback < 2 {
+        errors.push(format!(
+            "pairs.lookback must be at least 2, got {}",
+            pairs.lookback
+        ));
+    }
+    check_positive(errors, "pairs.entry_z", pairs.entry_z);
+    check_non_negative(errors, "pairs.exit_z", pairs.exit_z);
+    if pairs.exit_z >= pairs.entry_z {
+        errors.push(format!(
+            "pairs.exit_z ({}) must be below pairs.entry_z ({})",
+            pairs.exit_z, pairs.entry_z
+        ));
+    }
+    check_positive(errors, "pairs.leg_notional", pairs.leg_notional);
+}
+
+/// Require a fraction in (0, 1]
+fn check_fraction(errors: &mut Vec<String>, field: &str, value: f64) {
+    if !(value > 0.0 && value <= 1.0) {
+        errors.push(format!(
+            "{} must be a fraction in (0, 1], got {}",
+            field, value
+        ));
+    }
+}
+
+/// Put back the running value of a startup-only setting the reload changed
+fn keep_running<T: Clone + Serialize>(
+    changed: &mut Vec<&'static str>,
+    name: &'static str,
+    running: &T,
+    reloaded: &mut T,
+) {
+    if serde_json::to_value(running).ok() != serde_json::to_value(&*reloaded).ok() {
+        changed.push(name);
+        *reloaded = running.clone();
+    }
+}
+
+fn check_positive(errors: &mut Vec<String>, field: &str, value: f64) {
+    if !(value > 0.0 && value.is_finite()) {
+        errors.push(format!("{} must be positive, got {}", field, value));
+    }
+}
+
+fn check_non_negative(errors: &mut Vec<String>, field: &str, value: f64) {
+    if !(value >= 0.0 && value.is_finite()) {
+        errors.push(format!("{} must not be negative, got {}", field, value));
+    }
+}
+
 impl Default for TradingConfig {
     fn default() -> Self {
         Self {
@@ -292,6 +1848,12 @@ impl Default for TradingConfig {
                 host: "127.0.0.1".to_string(),
                 port: 7497,
                 client_id: 1,
+                dry_run: false,
+                simulate_fills: false,
+                simulated_starting_cash: default_simulated_starting_cash(),
+                client_id_attempts: default_client_id_attempts(),
+                max_orders_per_second: default_max_orders_per_second(),
+                realtime_bar_seconds: default_realtime_bar_seconds(),
             },
             strategy_config: StrategyConfig {
                 securities: vec![
@@ -301,6 +1863,13 @@ impl Default for TradingConfig {
                         exchange: "SMART".to_string(),
                         currency: "USD".to_string(),
                         futures_specs: None,
+                        extended_hours_data: false,
+                        outside_rth_orders: false,
+                        return_mode: ReturnMode::Simple,
+                        trade: true,
+                        commission_per_unit: None,
+                        commission_min: None,
+                        multiplier: None,
                     },
                     SecurityConfig {
                         symbol: "MSFT".to_string(),
@@ -308,6 +1877,13 @@ impl Default for TradingConfig {
                         exchange: "SMART".to_string(),
                         currency: "USD".to_string(),
                         futures_specs: None,
+                        extended_hours_data: false,
+                        outside_rth_orders: false,
+                        return_mode: ReturnMode::Simple,
+                        trade: true,
+                        commission_per_unit: None,
+                        commission_min: None,
+                        multiplier: None,
                     },
                     SecurityConfig {
                         symbol: "ES".to_string(),
@@ -321,9 +1897,17 @@ impl Default for TradingConfig {
                             tick_size: 0.25,
                             contract_month: "202403".to_string(),
                         }),
+                        extended_hours_data: false,
+                        outside_rth_orders: false,
+                        return_mode: ReturnMode::Simple,
+                        trade: true,
+                        commission_per_unit: None,
+                        commission_min: None,
+                        multiplier: None,
                     },
                 ],
                 lookback_period: 20,
+                lookback_periods: HashMap::new(),
                 momentum_threshold: 0.02,
                 position_size: 10000.0,
                 rebalance_frequency_minutes: 60,
@@ -331,12 +1915,42 @@ impl Default for TradingConfig {
                 volatility_halflife: default_volatility_halflife(),
                 use_limit_orders: default_use_limit_orders(),
                 limit_order_offset: default_limit_order_offset(),
+                volatility_estimator: VolatilityEstimator::default(),
+                max_data_age_seconds: default_max_data_age_seconds(),
+                data_gaps: DataGapConfig::default(),
+                return_outliers: ReturnOutlierThresholds::default(),
+                signal_smoothing: 0.0,
+                exit_hysteresis: 0.0,
+                futures_roll_window_days: default_futures_roll_window_days(),
+                max_tick_deviation: default_max_tick_deviation(),
+                use_volume_weighted_momentum: false,
+                bracket_orders: None,
+                order_chase: None,
+                max_positions: 5,
+                rotation_margin: 0.0,
+                risk_free_rate: 0.0,
+                trading_calendar: None,
+                signal_quality_threshold: default_signal_quality_threshold(),
+                signal_consensus_threshold: default_signal_consensus_threshold(),
+                min_timeframe_consensus: 0.0,
+                rebalance_schedule: None,
+                signal_weights: default_signal_weights(),
+                fractional_shares: false,
+                share_rounding: ShareRounding::Round,
+                rank_normalization: false,
+                sizing_mode: SizingMode::Lots,
+                min_bars_for_signals: None,
+                max_warmup_seconds: default_max_warmup_seconds(),
+                max_spread_bps: None,
+                min_average_volume: 0.0,
+                aggregation_method: AggregationMethod::default(),
             },
             risk_config: RiskConfig {
-                max_position_size: 50000.0,
+                max_position_size: 50.0, // 50% of portfolio per position
                 max_portfolio_exposure: 0.95,
                 stop_loss_percentage: 0.02,
                 take_profit_percentage: 0.05,
+                stop_loss_method: StopLossMethod::default(),
                 max_margin_utilization: 0.70,
                 min_excess_liquidity: 10000.0,
                 futures_position_limit: 10.0,
@@ -348,17 +1962,623 @@ impl Default for TradingConfig {
                 max_correlation_exposure: default_max_correlation_exposure(),
                 correlation_lookback_days: default_correlation_lookback_days(),
                 min_positions_for_erc: default_min_positions_for_erc(),
+                max_cluster_exposure: default_max_cluster_exposure(),
                 enable_transaction_cost_optimization: default_enable_transaction_cost_optimization(
                 ),
-                stock_commission: default_stock_commission(),
-                futures_commission: default_futures_commission(),
-                forex_commission: default_forex_commission(),
                 max_acceptable_cost_bps: default_max_acceptable_cost_bps(),
                 enable_position_inertia: default_enable_position_inertia(),
                 inertia_multiplier: default_inertia_multiplier(),
                 min_position_change_value: default_min_position_change_value(),
                 max_position_change_pct: default_max_position_change_pct(),
+                max_daily_loss: default_max_daily_loss(),
+                halt_reset_hour_utc: default_halt_reset_hour_utc(),
+                min_holding_period_minutes: 0,
+                reentry_cooldown_minutes: 0,
+                drawdown_scaling: None,
+                drawdown_derisking: None,
+                auto_protective_orders: false,
+                shortable_symbols: Vec::new(),
+                max_short_quantity: 0.0,
+                working_order_timeout_seconds: default_working_order_timeout_seconds(),
             },
+            cost_model: CostModelConfig::default(),
+            journal: JournalConfig::default(),
+            status_server: StatusServerConfig::default(),
+            shutdown: ShutdownConfig::default(),
+            strategy: default_strategy(),
+            pairs: None,
+            strategy_allocations: HashMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validation_error(config: &TradingConfig) -> String {
+        config.validate().unwrap_err().to_string()
+    }
+
+    fn es_specs() -> FuturesSpecs {
+        FuturesSpecs {
+            underlying: "ES".to_string(),
+            expiry: "20240315".to_string(),
+            multiplier: 50.0,
+            tick_size: 0.25,
+            contract_month: "202403".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_security_builder_matches_json() {
+        let json = r#"[
+            {"symbol": "AAPL", "type": "Stock", "exchange": "SMART", "currency": "USD"},
+            {"symbol": "USD.JPY", "type": "Forex", "exchange": "IDEALPRO", "currency": "JPY"},
+            {"symbol": "ES", "type": "Future", "exchange": "CME", "currency": "USD",
+             "futures_specs": {"underlying": "ES", "expiry": "20240315", "multiplier": 50.0,
+                               "tick_size": 0.25, "contract_month": "202403"}}
+        ]"#;
+        let parsed: Vec<SecurityConfig> = serde_json::from_str(json).unwrap();
+        let built = vec![
+            SecurityConfigBuilder::stock("AAPL").build().unwrap(),
+            SecurityConfigBuilder::forex("USD.JPY").build().unwrap(),
+            SecurityConfigBuilder::future("ES")
+                .futures_specs(es_specs())
+                .build()
+                .unwrap(),
+        ];
+        assert_eq!(
+            serde_json::to_value(&built).unwrap(),
+            serde_json::to_value(&parsed).unwrap()
+        );
+
+        let overridden = SecurityConfigBuilder::stock("SAP")
+            .exchange("IBIS")
+            .currency("EUR")
+            .build()
+            .unwrap();
+        assert_eq!(overridden.exchange, "IBIS");
+        assert_eq!(overridden.currency, "EUR");
+    }
+
+    #[test]
+    fn test_security_builder_validates_futures_specs() {
+        let error = SecurityConfigBuilder::future("ES").build().unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("ES: futures security requires futures_specs")
+        );
+
+        let error = SecurityConfigBuilder::stock("AAPL")
+            .futures_specs(es_specs())
+            .build()
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("futures_specs only apply to futures")
+        );
+
+        assert!(SecurityConfigBuilder::forex(" ").build().is_err());
+        assert!(
+            SecurityConfigBuilder::stock("AAPL")
+                .multiplier(10.0)
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(TradingConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_commission_fraction_must_be_positive() {
+        let mut config = TradingConfig::default();
+        config.cost_model.max_commission_fraction = Some(0.0);
+        assert!(validation_error(&config).contains("cost_model.max_commission_fraction"));
+
+        config.cost_model.max_commission_fraction = Some(0.01);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_tws_overrides() {
+        let vars = HashMap::from([
+            (TWS_HOST_ENV, " gateway.internal "),
+            (TWS_PORT_ENV, "4002"),
+            (TWS_CLIENT_ID_ENV, ""),
+        ]);
+        let mut tws = TradingConfig::default().tws_config;
+        let client_id = tws.client_id;
+        tws.apply_overrides(|name| vars.get(name).map(|v| v.to_string()))
+            .unwrap();
+
+        assert_eq!(tws.host, "gateway.internal");
+        assert_eq!(tws.port, 4002);
+        assert_eq!(tws.client_id, client_id); // Empty values are ignored
+
+        for (name, value, message) in [
+            (TWS_PORT_ENV, "70000", "TWS_PORT must be a port number"),
+            (
+                TWS_CLIENT_ID_ENV,
+                "abc",
+                "TWS_CLIENT_ID must be a 32-bit integer",
+            ),
+        ] {
+            let error = tws
+                .apply_overrides(|var| (var == name).then(|| value.to_string()))
+                .unwrap_err();
+            assert!(error.to_string().contains(message), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_calendar_months_survive_loading() {
+        let june = FuturesSpecs {
+            expiry: "20240621".to_string(),
+            contract_month: "202406".to_string(),
+            ..es_specs()
+        };
+        let stale_nq = FuturesSpecs {
+            underlying: "NQ".to_string(),
+            ..es_specs()
+        };
+        let future = |symbol: &str, specs: FuturesSpecs| {
+            SecurityConfigBuilder::future(symbol)
+                .futures_specs(specs)
+                .build()
+                .unwrap()
+        };
+        let mut config = TradingConfig::default();
+        config.strategy_config.securities = vec![
+            future("ES", es_specs()),
+            future("ES", june),
+            future("NQ", stale_nq),
+        ];
+
+        let path = std::env::temp_dir().join(format!("calendar_{}.json", std::process::id()));
+        fs::write(&path, serde_json::to_string(&config).unwrap()).unwrap();
+        let loaded = TradingConfig::load_from_file(path.to_str().unwrap());
+        let _ = fs::remove_file(&path);
+        let loaded = loaded.unwrap().strategy_config;
+
+        // The calendar keeps both months; the lone future rolls to the front month
+        assert_eq!(loaded.security_ids(), ["ES 202403", "ES 202406", "NQ"]);
+        let (_, front_month) = get_front_month_contract("NQ").unwrap();
+        let nq = loaded.securities[2].futures_specs.as_ref().unwrap();
+        assert_eq!(nq.contract_month, front_month);
+    }
+
+    #[test]
+    fn test_shipped_configs_are_valid() {
+        for path in ["config.json", "config-forex.json"] {
+            let config = TradingConfig::load_from_file(path);
+            assert!(config.is_ok(), "{}: {:?}", path, config.err());
+        }
+    }
+
+    #[test]
+    fn test_invalid_risk_fractions_reported() {
+        let mut config = TradingConfig::default();
+        config.risk_config.max_position_size = 1000.0;
+        config.risk_config.stop_loss_percentage = 2.0;
+        config.risk_config.max_margin_utilization = -0.1;
+
+        let message = validation_error(&config);
+        assert!(message.contains("3 problems"), "{}", message);
+        assert!(message.contains("risk_config.max_position_size"));
+        assert!(
+            message
+                .contains("risk_config.stop_loss_percentage must be a fraction in (0, 1], got 2")
+        );
+        assert!(message.contains("risk_config.max_margin_utilization"));
+    }
+
+    #[test]
+    fn test_min_positions_for_erc() {
+        let mut config = TradingConfig::default();
+        config.risk_config.min_positions_for_erc = 1;
+
+        let message = validation_error(&config);
+        assert!(message.contains("risk_config.min_positions_for_erc must be at least 2, got 1"));
+    }
+
+    #[test]
+    fn test_security_fields_required() {
+        let mut config = TradingConfig::default();
+        config.strategy_config.securities[0].exchange = String::new();
+        config.strategy_config.securities[1].symbol = " ".to_string();
+        config.strategy_config.securities[2].futures_specs = None;
+
+        let message = validation_error(&config);
+        assert!(message.contains("AAPL: exchange must not be empty"));
+        assert!(message.contains("securities[1]: symbol must not be empty"));
+        assert!(message.contains("ES: futures security requires futures_specs"));
+    }
+
+    #[test]
+    fn test_lookback_periods_validated() {
+        let mut config = TradingConfig::default();
+        config
+            .strategy_config
+            .lookback_periods
+            .insert(TimeFrame::Days8_32, 30);
+        assert!(config.validate().is_ok());
+        assert_eq!(config.strategy_config.lookback_for(TimeFrame::Days8_32), 30);
+        assert_eq!(config.strategy_config.lookback_for(TimeFrame::Days2_8), 20);
+
+        config
+            .strategy_config
+            .lookback_periods
+            .insert(TimeFrame::Hours1, 10);
+        config
+            .strategy_config
+            .lookback_periods
+            .insert(TimeFrame::Days2_8, 1);
+        let message = validation_error(&config);
+        assert!(
+            message.contains("unsupported timeframe Hours1"),
+            "{}",
+            message
+        );
+        assert!(message.contains("lookback_periods[Days2_8] must be at least 2, got 1"));
+    }
+
+    #[test]
+    fn test_ewma_lambda_bounds() {
+        let with_lambda = |lambda| {
+            let mut config = TradingConfig::default();
+            config.strategy_config.volatility_estimator = VolatilityEstimator::Ewma { lambda };
+            config
+        };
+        assert!(with_lambda(0.0).validate().is_ok());
+        assert!(with_lambda(0.94).validate().is_ok());
+        for lambda in [1.0, -0.1, f64::NAN] {
+            let message = validation_error(&with_lambda(lambda));
+            assert!(message.contains("volatility_estimator lambda must be in [0, 1)"));
+        }
+    }
+
+    #[test]
+    fn test_negative_thresholds_reported() {
+        let mut config = TradingConfig::default();
+        config.cost_model.stock.commission_per_unit = -1.0;
+        config.risk_config.futures_position_limit = 0.0;
+        config.tws_config.simulated_starting_cash = 0.0;
+
+        let message = validation_error(&config);
+        assert!(message.contains("cost_model.stock.commission_per_unit must not be negative"));
+        assert!(message.contains("risk_config.futures_position_limit must be positive"));
+        assert!(message.contains("tws_config.simulated_starting_cash must be positive"));
+    }
+
+    #[test]
+    fn test_bracket_levels_and_validation() {
+        let brackets = BracketConfig {
+            take_profit: BracketOffset::Fraction(0.1),
+            stop_loss: BracketOffset::AtrMultiple(2.0),
+            atr_period: 14,
+        };
+        let long = brackets.levels(100.0, true, Some(1.5)).unwrap();
+        assert!((long.take_profit - 110.0).abs() < 1e-12);
+        assert!((long.stop_loss - 97.0).abs() < 1e-12);
+        let short = brackets.levels(100.0, false, Some(1.5)).unwrap();
+        assert!((short.take_profit - 90.0).abs() < 1e-12);
+        assert!((short.stop_loss - 103.0).abs() < 1e-12);
+        assert!(brackets.levels(100.0, true, None).is_none());
+
+        let mut config = TradingConfig::default();
+        config.strategy_config.bracket_orders = Some(BracketConfig {
+            take_profit: BracketOffset::Fraction(-0.1),
+            atr_period: 0,
+            ..brackets
+        });
+        let message = validation_error(&config);
+        assert!(message.contains("strategy_config.bracket_orders.take_profit must be positive"));
+        assert!(message.contains("strategy_config.bracket_orders.atr_period must be positive"));
+    }
+
+    #[test]
+    fn test_signal_smoothing_validated() {
+        let mut config = TradingConfig::default();
+        config.strategy_config.signal_smoothing = 1.0;
+        config.strategy_config.exit_hysteresis = -0.1;
+
+        let message = validation_error(&config);
+        assert!(message.contains("strategy_config.signal_smoothing must be in [0, 1)"));
+        assert!(message.contains("strategy_config.exit_hysteresis must not be negative"));
+
+        config.strategy_config.signal_smoothing = 0.5;
+        config.strategy_config.exit_hysteresis = 0.1;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_signal_weights_validated() {
+        let mut config = TradingConfig::default();
+        config.strategy_config.signal_weights.breakout = -0.1;
+        let message = validation_error(&config);
+        assert!(message.contains("strategy_config.signal_weights.breakout must not be negative"));
+
+        config.strategy_config.signal_weights = SignalWeights {
+            momentum: 0.0,
+            breakout: 0.0,
+            carry: 0.0,
+            mean_reversion: 0.0,
+            ..SignalWeights::default()
+        };
+        let message = validation_error(&config);
+        assert!(message.contains("strategy_config.signal_weights must not all be zero"));
+
+        // Weights need not sum to one; the strategy normalizes them
+        config.strategy_config.signal_weights.carry = 2.0;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_signal_filter_thresholds_validated() {
+        let mut config = TradingConfig::default();
+        config.strategy_config.signal_quality_threshold = -1.0;
+        config.strategy_config.signal_consensus_threshold = 1.5;
+
+        let message = validation_error(&config);
+        assert!(message.contains("strategy_config.signal_quality_threshold must not be negative"));
+        assert!(message.contains("strategy_config.signal_consensus_threshold must be in [0, 1]"));
+    }
+
+    #[test]
+    fn test_strategy_allocations() {
+        let mut config = TradingConfig::default();
+        assert_eq!(config.strategy_allocation("momentum"), 1.0);
+
+        config
+            .strategy_allocations
+            .insert("momentum".to_string(), 0.6);
+        assert!(config.validate().is_ok());
+        assert_eq!(config.strategy_allocation("momentum"), 0.6);
+        assert_eq!(config.strategy_allocation("reversion"), 0.0);
+
+        config
+            .strategy_allocations
+            .insert("reversion".to_string(), 0.5);
+        let message = validation_error(&config);
+        assert!(message.contains("strategy_allocations must sum to at most 1"));
+
+        config
+            .strategy_allocations
+            .insert("reversion".to_string(), -0.1);
+        let message = validation_error(&config);
+        assert!(message.contains("strategy_allocations.reversion must be in (0, 1]"));
+    }
+
+    #[test]
+    fn test_pairs_config_validated() {
+        let mut config = TradingConfig {
+            strategy: "pairs".to_string(),
+            ..TradingConfig::default()
+        };
+        assert!(validation_error(&config).contains("pairs must be set when strategy is pairs"));
+
+        config.pairs = Some(PairsConfig {
+            symbol_a: "AAPL".to_string(),
+            symbol_b: "MSFT".to_string(),
+            lookback: 60,
+            entry_z: 2.0,
+            exit_z: 0.5,
+            leg_notional: 10_000.0,
+        });
+        let message = validation_error(&config);
+        assert!(message.contains("pairs symbol \"MSFT\" must be in risk_config.shortable_symbols"));
+        config.risk_config.shortable_symbols = vec!["AAPL".to_string(), "MSFT".to_string()];
+        assert!(config.validate().is_ok());
+
+        let pairs = config.pairs.as_mut().unwrap();
+        pairs.symbol_b = "XYZ".to_string();
+        pairs.exit_z = 2.5;
+        let message = validation_error(&config);
+        assert!(message.contains("pairs symbol \"XYZ\" is not in strategy_config.securities"));
+        assert!(message.contains("pairs.exit_z (2.5) must be below pairs.entry_z (2)"));
+    }
+
+    #[test]
+    fn test_strategy_defaults_to_momentum() {
+        let config: TradingConfig =
+            serde_json::from_str(&TradingConfig::default_config_json()).unwrap();
+        assert_eq!(config.strategy, "momentum");
+
+        let mut value = serde_json::to_value(TradingConfig::default()).unwrap();
+        value.as_object_mut().unwrap().remove("strategy");
+        let config: TradingConfig = serde_json::from_value(value).unwrap();
+        assert_eq!(config.strategy, "momentum");
+    }
+
+    #[test]
+    fn test_contract_months_of_one_future_are_tracked_apart() {
+        let june = FuturesSpecs {
+            expiry: "20240621".to_string(),
+            contract_month: "202406".to_string(),
+            ..es_specs()
+        };
+        let future = |specs: FuturesSpecs| {
+            SecurityConfigBuilder::future("ES")
+                .futures_specs(specs)
+                .build()
+                .unwrap()
+        };
+        let mut strategy = TradingConfig::default().strategy_config;
+        strategy.securities = vec![
+            future(es_specs()),
+            future(june.clone()),
+            future(es_specs()), // Duplicate entry, tracked once
+            SecurityConfigBuilder::stock("AAPL").build().unwrap(),
+        ];
+
+        assert_ne!(strategy.securities[0].key(), strategy.securities[1].key());
+        assert_eq!(strategy.securities[0].key(), strategy.securities[2].key());
+        assert_eq!(strategy.security_ids(), ["ES 202403", "ES 202406", "AAPL"]);
+
+        // Market data and positions for the two months do not clobber each other
+        let mut handler = crate::market_data::MarketDataHandler::new();
+        let mut portfolio = crate::portfolio::Portfolio::new(100_000.0);
+        for (req_id, id) in strategy.security_ids().into_iter().enumerate() {
+            handler.register_symbol(req_id as i32, id.clone());
+            let info = crate::security_types::SecurityInfo::new_future(
+                id.clone(),
+                "CME".to_string(),
+                "USD".to_string(),
+                crate::security_types::FuturesContract::default(),
+            );
+            handler.register_security(id.clone(), info.clone());
+            portfolio.register_security(id, info);
+        }
+        let now = time::OffsetDateTime::now_utc();
+        handler.add_historical_price("ES 202403", now, 5_000.0);
+        handler.add_historical_price("ES 202406", now, 5_050.0);
+        portfolio.update_position("ES 202403", 1.0, 5_000.0);
+        portfolio.update_position("ES 202406", -1.0, 5_050.0);
+
+        let price = |id: &str| handler.get_price_history(id).unwrap().prices[0].1;
+        assert_eq!(price("ES 202403"), 5_000.0);
+        assert_eq!(price("ES 202406"), 5_050.0);
+        assert_eq!(portfolio.get_position("ES 202403").unwrap().quantity, 1.0);
+        assert_eq!(portfolio.get_position("ES 202406").unwrap().quantity, -1.0);
+
+        // A lone contract keeps its bare symbol
+        strategy.securities = vec![future(june)];
+        assert_eq!(strategy.security_ids(), ["ES"]);
+    }
+
+    #[test]
+    fn test_reload_applies_thresholds_and_keeps_structure() {
+        let mut config = TradingConfig::default();
+        let mut reloaded = config.clone();
+        reloaded.strategy_config.momentum_threshold = 0.9;
+        reloaded.risk_config.max_position_size = 5.0;
+        assert!(config.apply_reload(reloaded.clone()).is_empty());
+        assert_eq!(config.strategy_config.momentum_threshold, 0.9);
+        assert_eq!(config.risk_config.max_position_size, 5.0);
+
+        let securities = config.strategy_config.securities.len();
+        reloaded.strategy_config.momentum_threshold = 0.7;
+        reloaded
+            .strategy_config
+            .securities
+            .push(SecurityConfigBuilder::stock("NVDA").build().unwrap());
+        reloaded.tws_config.port += 1;
+        reloaded.risk_config.min_holding_period_minutes += 10;
+        assert_eq!(
+            config.apply_reload(reloaded),
+            [
+                "tws_config",
+                "strategy_config.securities",
+                "risk_config.min_holding_period_minutes"
+            ]
+        );
+        assert_eq!(config.strategy_config.momentum_threshold, 0.7);
+        assert_eq!(config.strategy_config.securities.len(), securities);
+        assert_eq!(
+            config.tws_config.port,
+            TradingConfig::default().tws_config.port
+        );
+    }
+
+    #[test]
+    fn test_data_only_securities() {
+        let json = r#"{"symbol": "SPY", "type": "Stock", "exchange": "SMART", "currency": "USD"}"#;
+        let security: SecurityConfig = serde_json::from_str(json).unwrap();
+        assert!(security.trade);
+
+        let mut config = TradingConfig::default();
+        config.strategy_config.securities.push(
+            SecurityConfigBuilder::stock("SPY")
+                .data_only()
+                .build()
+                .unwrap(),
+        );
+        assert!(config.validate().is_ok());
+        assert!(
+            config
+                .strategy_config
+                .security_ids()
+                .contains(&"SPY".to_string())
+        );
+        assert!(
+            !config
+                .strategy_config
+                .tradable_security_ids()
+                .contains(&"SPY".to_string())
+        );
+
+        for security in &mut config.strategy_config.securities {
+            security.trade = false;
         }
+        assert!(
+            validation_error(&config)
+                .contains("strategy_config.securities has no security with trade enabled")
+        );
+    }
+
+    #[test]
+    fn test_security_cost_overrides_validated() {
+        let mut config = TradingConfig::default();
+        let es = &mut config.strategy_config.securities[2];
+        assert_eq!(es.contract_multiplier(), Some(50.0));
+        es.multiplier = Some(5.0);
+        assert_eq!(es.contract_multiplier(), Some(5.0));
+        let contract = es.security_info().contract_specs.unwrap();
+        assert_eq!(contract.multiplier, 5.0);
+        assert!(config.validate().is_ok());
+
+        let aapl = &mut config.strategy_config.securities[0];
+        assert_eq!(aapl.contract_multiplier(), None);
+        aapl.commission_per_unit = Some(-0.01);
+        aapl.commission_min = Some(-1.0);
+        aapl.multiplier = Some(10.0);
+        config.strategy_config.securities[2].multiplier = Some(0.0);
+        let message = validation_error(&config);
+        assert!(message.contains("AAPL.commission_per_unit must not be negative"));
+        assert!(message.contains("AAPL.commission_min must not be negative"));
+        assert!(message.contains("AAPL: multiplier applies to futures only"));
+        assert!(message.contains("ES.multiplier must be positive"));
+    }
+
+    #[test]
+    fn test_realtime_bar_spec_from_seconds() {
+        let spec = |seconds| RealtimeBarSpec::from_seconds(seconds).unwrap();
+        assert_eq!(
+            spec(5),
+            RealtimeBarSpec {
+                bar_size: RealtimeBarSize::Sec5,
+                bars_per_update: 1,
+            }
+        );
+        assert_eq!(spec(60).bar_size, RealtimeBarSize::Sec5);
+        assert_eq!(spec(60).bars_per_update, 12);
+        assert_eq!(spec(3600).bars_per_update, 720);
+
+        // IBKR only streams 5-second bars, so other lengths cannot be built
+        for seconds in [0, 1, 7, 62, 3605] {
+            assert!(
+                RealtimeBarSpec::from_seconds(seconds).is_err(),
+                "{}s",
+                seconds
+            );
+        }
+
+        let mut config = TradingConfig::default();
+        config.tws_config.realtime_bar_seconds = 60;
+        assert!(config.validate().is_ok());
+        config.tws_config.realtime_bar_seconds = 32;
+        assert!(config.validate().is_err());
+
+        // Bars as long as the staleness limit would leave data stale between them
+        config.tws_config.realtime_bar_seconds = 300;
+        assert!(config.validate().is_err());
+        config.strategy_config.max_data_age_seconds = 301;
+        assert!(config.validate().is_ok());
     }
 }
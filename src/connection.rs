@@ -1,47 +1,287 @@
-use crate::config::{SecurityConfig, TwsConfig};
-use crate::market_data::{MarketDataHandler, MarketDataUpdate};
+use crate::config::{RealtimeBarSpec, SecurityConfig, TwsConfig};
+use crate::health::HealthReport;
+use crate::journal::{Journal, JournalEvent};
+use crate::market_data::{MarketDataEvent, MarketDataHandler, MarketDataUpdate};
+use crate::metrics::Metrics;
 use crate::order_types::{EnhancedOrderBuilder, OrderAction, OrderParams};
-use crate::orders::OrderSignal;
-use crate::security_types::SecurityType;
-use anyhow::Result;
-use chrono::Utc;
+use crate::orders::{OrderSignal, OrderStatus};
+use crate::portfolio::Portfolio;
+use crate::security_types::{SecurityType, SpreadContract};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, NaiveTime, TimeZone, Utc};
 use ibapi::Client;
 use ibapi::accounts::{AccountSummaries, AccountSummaryTags, PositionUpdate};
+use ibapi::contracts::ComboLeg;
+use ibapi::contracts::tick_types::TickType;
 use ibapi::market_data::historical::{
     BarSize as HistoricalBarSize, Duration as HistoricalDuration,
     WhatToShow as HistoricalWhatToShow,
 };
 use ibapi::market_data::realtime::{BarSize as RealtimeBarSize, WhatToShow as RealtimeWhatToShow};
+use ibapi::orders::{ExecutionFilter, Executions, Order, Orders};
 use ibapi::prelude::*;
 use log::{debug, error, info, warn};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use tokio::sync::{Mutex, mpsc};
+use tokio::task::JoinHandle;
+
+/// Notification sent by a real-time subscription task whose stream ended unexpectedly
+#[derive(Debug, Clone)]
+pub struct SubscriptionFailure {
+    pub req_id: i32,
+    pub symbol: String,
+    pub reason: String,
+    pub task_id: tokio::task::Id,
+}
+
+/// Exponential backoff schedule for reconnection attempts
+#[derive(Debug, Clone, Copy)]
+pub struct ReconnectPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+}
+
+impl ReconnectPolicy {
+    /// Delay before the given zero-based attempt (base_delay * 2^attempt)
+    pub fn delay_for_attempt(&self, attempt: u32) -> Duration {
+        self.base_delay.saturating_mul(2u32.saturating_pow(attempt))
+    }
+}
+
+/// Connect with the first client id in `ids` that TWS accepts
+///
+/// Ids are tried in order; a failure for which `is_fatal` holds (e.g. nothing
+/// listening on the port) stops the search, since other ids cannot succeed.
+fn first_available_client_id<T, E: Display>(
+    ids: RangeInclusive<i32>,
+    mut connect: impl FnMut(i32) -> std::result::Result<T, E>,
+    is_fatal: impl Fn(&E) -> bool,
+) -> Result<(i32, T)> {
+    let (first, last) = (*ids.start(), *ids.end());
+    let mut last_error = None;
+    for client_id in ids {
+        match connect(client_id) {
+            Ok(connection) => return Ok((client_id, connection)),
+            Err(e) if is_fatal(&e) => {
+                anyhow::bail!("Connecting with client id {} failed: {}", client_id, e)
+            }
+            Err(e) => {
+                warn!("Client id {} unavailable: {}", client_id, e);
+                last_error = Some(e.to_string());
+            }
+        }
+    }
+    match last_error {
+        Some(e) => anyhow::bail!(
+            "No free TWS client id in {}..={} (last error: {})",
+            first,
+            last,
+            e
+        ),
+        None => anyhow::bail!("Empty client id range {}..={}", first, last),
+    }
+}
+
+/// Bookkeeping for a registered real-time data subscription
+struct ActiveSubscription {
+    symbol: String,
+    tx: mpsc::Sender<MarketDataEvent>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl ActiveSubscription {
+    fn is_alive(&self) -> bool {
+        self.task.as_ref().is_some_and(|task| !task.is_finished())
+    }
+}
+
+/// First id handed out to orders in dry-run mode, well clear of real TWS ids
+const DRY_RUN_FIRST_ORDER_ID: i32 = 1_000_000;
+
+/// Symbol whose history the health check requests when none is configured
+const HEALTH_CHECK_SYMBOL: &str = "SPY";
+
+/// Stand-in for order submission when `TwsConfig::dry_run` is set
+///
+/// Hands out synthetic, increasing order ids and logs each fully-formed order
+/// instead of sending it to TWS.
+#[derive(Debug)]
+pub struct DryRunOrders {
+    next_id: AtomicI32,
+}
+
+impl Default for DryRunOrders {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DryRunOrders {
+    pub fn new() -> Self {
+        Self {
+            next_id: AtomicI32::new(DRY_RUN_FIRST_ORDER_ID),
+        }
+    }
+
+    pub fn next_order_id(&self) -> i32 {
+        self.next_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    pub fn record(&self, order_id: i32, contract: &Contract, order: &Order) {
+        info!(
+            "[DRY RUN] Order #{} not submitted: {} {:?} {} {} (type: {}, limit: {:?}, aux: {:?}, parent: {}, transmit: {})",
+            order_id,
+            contract.symbol,
+            order.action,
+            order.total_quantity,
+            contract.security_type,
+            order.order_type,
+            order.limit_price,
+            order.aux_price,
+            order.parent_id,
+            order.transmit
+        );
+    }
+}
+
+/// Token bucket pacing order submissions under IBKR's message rate limit
+///
+/// Holds a single token, so submissions are spaced evenly at the configured
+/// rate rather than released in bursts. Waiters are served in arrival order.
+#[derive(Debug)]
+pub struct OrderRateLimiter {
+    orders_per_second: f64,
+    bucket: Mutex<TokenBucket>,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: tokio::time::Instant,
+}
+
+impl OrderRateLimiter {
+    pub fn new(orders_per_second: f64) -> Self {
+        Self {
+            orders_per_second,
+            bucket: Mutex::new(TokenBucket {
+                tokens: 1.0,
+                last_refill: tokio::time::Instant::now(),
+            }),
+        }
+    }
+
+    /// Wait until an order may be submitted and claim the slot
+    pub async fn acquire(&self) {
+        let mut bucket = self.bucket.lock().await;
+        self.refill(&mut bucket);
+        if bucket.tokens < 1.0 {
+            let wait = Duration::from_secs_f64((1.0 - bucket.tokens) / self.orders_per_second);
+            debug!("Order rate limit reached, waiting {:?}", wait);
+            tokio::time::sleep(wait).await;
+            self.refill(&mut bucket);
+        }
+        bucket.tokens = (bucket.tokens - 1.0).max(0.0);
+    }
+
+    fn refill(&self, bucket: &mut TokenBucket) {
+        let now = tokio::time::Instant::now();
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.orders_per_second).min(1.0);
+        bucket.last_refill = now;
+    }
+}
+
+type SubscriptionRegistry = Arc<Mutex<HashMap<i32, ActiveSubscription>>>;
+type FailureSender = Arc<Mutex<Option<mpsc::UnboundedSender<SubscriptionFailure>>>>;
 
 pub struct TwsClient {
-    client: Arc<Client>,
+    config: TwsConfig,
+    client: Arc<RwLock<Arc<Client>>>,
     pub market_data_handler: Arc<Mutex<MarketDataHandler>>,
     security_configs: Arc<Mutex<HashMap<String, SecurityConfig>>>,
-    active_subscriptions: Arc<Mutex<HashMap<i32, mpsc::Sender<MarketDataUpdate>>>>,
+    active_subscriptions: SubscriptionRegistry,
+    failure_tx: FailureSender,
+    dry_run: Option<DryRunOrders>,
+    order_rate: OrderRateLimiter,
+    journal: Option<Arc<Journal>>,
+    metrics: Option<Arc<Metrics>>,
 }
 
 impl TwsClient {
     pub async fn new(config: TwsConfig) -> Result<Self> {
-        let market_data_handler = Arc::new(Mutex::new(MarketDataHandler::new()));
-
-        let client = Arc::new(Client::connect(
+        let client = Client::connect(
             &format!("{}:{}", config.host, config.port),
             config.client_id,
-        )?);
-
+        )?;
         info!("Connected to TWS at {}:{}", config.host, config.port);
 
-        Ok(Self {
-            client,
+        Ok(Self::from_client(config, client))
+    }
+
+    /// Connect using the first free client id in `client_ids`
+    ///
+    /// Avoids failing when a stale session still holds the configured id.
+    /// Returns the client together with the id it connected as; reconnects
+    /// reuse that id.
+    pub async fn connect_with_auto_client_id(
+        mut config: TwsConfig,
+        client_ids: RangeInclusive<i32>,
+    ) -> Result<(Self, i32)> {
+        let address = format!("{}:{}", config.host, config.port);
+        let (client_id, client) = first_available_client_id(
+            client_ids,
+            |client_id| Client::connect(&address, client_id),
+            |e| {
+                matches!(e, ibapi::Error::Io(io)
+                    if io.kind() == std::io::ErrorKind::ConnectionRefused)
+            },
+        )?;
+        info!("Connected to TWS at {} as client id {}", address, client_id);
+
+        config.client_id = client_id;
+        Ok((Self::from_client(config, client), client_id))
+    }
+
+    fn from_client(config: TwsConfig, client: Client) -> Self {
+        let market_data_handler = Arc::new(Mutex::new(MarketDataHandler::new()));
+        let dry_run = if config.dry_run {
+            warn!("Dry-run mode: orders will be logged but never submitted to TWS");
+            Some(DryRunOrders::new())
+        } else {
+            None
+        };
+
+        let order_rate = OrderRateLimiter::new(config.max_orders_per_second);
+        Self {
+            config,
+            client: Arc::new(RwLock::new(Arc::new(client))),
             market_data_handler,
             security_configs: Arc::new(Mutex::new(HashMap::new())),
             active_subscriptions: Arc::new(Mutex::new(HashMap::new())),
-        })
+            failure_tx: Arc::new(Mutex::new(None)),
+            dry_run,
+            order_rate,
+            journal: None,
+            metrics: None,
+        }
+    }
+
+    /// Record reconnect attempts to `journal`
+    pub fn with_journal(mut self, journal: Arc<Journal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Count reconnect attempts in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
     }
 
     pub async fn connect(&mut self) -> Result<()> {
@@ -54,12 +294,291 @@ impl TwsClient {
         Ok(())
     }
 
+    /// Current TWS client (replaced on reconnect)
+    fn client(&self) -> Arc<Client> {
+        current_client(&self.client)
+    }
+
+    /// Whether orders are only logged rather than sent to TWS
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.is_some()
+    }
+
+    /// Next order id, synthetic in dry-run mode
+    fn next_order_id(&self) -> i32 {
+        match &self.dry_run {
+            Some(dry_run) => dry_run.next_order_id(),
+            None => self.client().next_order_id(),
+        }
+    }
+
+    /// Submit once the order rate limit allows
+    async fn submit_order(&self, order_id: i32, contract: &Contract, order: &Order) -> Result<()> {
+        self.order_rate.acquire().await;
+        self.send_order(order_id, contract, order)
+    }
+
+    /// Send an order to TWS, or just log it in dry-run mode
+    fn send_order(&self, order_id: i32, contract: &Contract, order: &Order) -> Result<()> {
+        match &self.dry_run {
+            Some(dry_run) => dry_run.record(order_id, contract, order),
+            None => self.client().submit_order(order_id, contract, order)?,
+        }
+        Ok(())
+    }
+
+    /// Cancel a working order once the order rate limit allows, or just log
+    /// it in dry-run mode
+    pub async fn cancel_order(&self, order_id: i32) -> Result<()> {
+        self.order_rate.acquire().await;
+        match &self.dry_run {
+            Some(_) => info!("[DRY RUN] Order #{} not cancelled", order_id),
+            None => {
+                self.client().cancel_order(order_id, "")?;
+                info!("Cancelled order #{}", order_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Cancel every working order on the account, or just log it in dry-run mode
+    pub fn cancel_all_orders(&self) -> Result<()> {
+        match &self.dry_run {
+            Some(_) => info!("[DRY RUN] Open orders not cancelled"),
+            None => {
+                self.client().global_cancel()?;
+                warn!("Requested cancellation of all open orders");
+            }
+        }
+        Ok(())
+    }
+
+    /// Close every position in `portfolio` at market
+    ///
+    /// Returns the order IDs placed.
+    pub async fn flatten_all_positions(&self, portfolio: &Portfolio) -> Result<Vec<i32>> {
+        crate::broker::Broker::flatten_all_positions(self, portfolio).await
+    }
+
+    /// Cancel all working orders, then flatten every position
+    pub async fn emergency_stop(&self, portfolio: &Portfolio) -> Result<Vec<i32>> {
+        crate::broker::Broker::emergency_stop(self, portfolio).await
+    }
+
+    /// Cancel a working order and, once TWS confirms the cancel, place
+    /// `signal` in its place
+    ///
+    /// Returns the replacement's order ID, or None when the order filled
+    /// before the cancel took effect. Dry-run orders are never working, so
+    /// the replacement goes straight out.
+    pub async fn replace_order(&self, order_id: i32, signal: &OrderSignal) -> Result<Option<i32>> {
+        if self.is_dry_run() {
+            self.cancel_order(order_id).await?;
+            return self.place_order(signal).await.map(Some);
+        }
+        crate::broker::replace_after_cancel(self, order_id, signal).await
+    }
+
+    /// Current status of an order, looked up among open then completed orders
+    ///
+    /// Dry-run orders never reach TWS, so they always read as submitted. The
+    /// lookup iterates blocking subscriptions, so it runs off the async runtime.
+    pub async fn order_status(&self, order_id: i32) -> Result<OrderStatus> {
+        if self.is_dry_run() {
+            return Ok(OrderStatus::Submitted);
+        }
+
+        let client = self.client();
+        tokio::task::spawn_blocking(move || lookup_order_status(&client, order_id)).await?
+    }
+
+    /// Buy or sell about `notional` worth of `symbol` at its latest price
+    ///
+    /// Returns the order ID; fails without placing anything when there is no
+    /// price for the symbol.
+    pub async fn place_notional_order(
+        &self,
+        symbol: &str,
+        notional: f64,
+        action: OrderAction,
+    ) -> Result<i32> {
+        let signal = {
+            let handler = self.market_data_handler.lock().await;
+            crate::broker::notional_order_signal(&handler, symbol, notional, action)?
+        };
+        self.place_order(&signal).await
+    }
+
+    /// Status of every open and completed order TWS reports, by order ID
+    ///
+    /// Dry-run orders never reach TWS, so the map is empty in dry-run mode.
+    /// The blocking subscriptions are iterated off the async runtime.
+    pub async fn poll_order_statuses(&self) -> Result<HashMap<i32, OrderStatus>> {
+        if self.is_dry_run() {
+            return Ok(HashMap::new());
+        }
+
+        let client = self.client();
+        tokio::task::spawn_blocking(move || poll_tws_order_statuses(&client)).await?
+    }
+
+    /// When each of `positions` was opened, by contract ID
+    ///
+    /// TWS reports only the current day's executions, so a position they do
+    /// not open is dated to the start of the TWS day. Empty in dry-run mode.
+    pub async fn position_entry_times(
+        &self,
+        positions: &[AccountPosition],
+    ) -> Result<HashMap<i32, DateTime<Utc>>> {
+        if self.is_dry_run() {
+            return Ok(HashMap::new());
+        }
+
+        let client = self.client();
+        let (executions, day_start) =
+            tokio::task::spawn_blocking(move || todays_executions(&client)).await??;
+        Ok(positions
+            .iter()
+            .filter(|position| position.position != 0.0)
+            .map(|position| {
+                let contract_id = position.contract.contract_id;
+                let fills = executions.get(&contract_id).map_or(&[][..], Vec::as_slice);
+                (
+                    contract_id,
+                    opening_time(position.position, fills, day_start),
+                )
+            })
+            .collect())
+    }
+
+    /// Start a supervisor that reconnects to TWS and replays subscriptions
+    ///
+    /// Real-time subscription tasks report dropped streams through a shared
+    /// channel. The supervisor reconnects with exponential backoff (up to
+    /// `max_retries` attempts starting at `base_delay`) and re-subscribes every
+    /// registered symbol whose task is no longer running.
+    pub async fn enable_auto_reconnect(&self, max_retries: u32, base_delay: Duration) {
+        let mut failure_tx = self.failure_tx.lock().await;
+        if failure_tx.is_some() {
+            warn!("Auto-reconnect already enabled");
+            return;
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        *failure_tx = Some(tx);
+        drop(failure_tx);
+
+        let policy = ReconnectPolicy {
+            max_retries,
+            base_delay,
+        };
+        let address = format!("{}:{}", self.config.host, self.config.port);
+        let client_id = self.config.client_id;
+        let client_holder = self.client.clone();
+        let context = self.subscription_context();
+
+        let journal = self.journal.clone();
+        let metrics = self.metrics.clone();
+        // The TWS handshake blocks, so each attempt runs off the async runtime
+        let reconnect = move || {
+            let address = address.clone();
+            let client_holder = client_holder.clone();
+            let journal = journal.clone();
+            let metrics = metrics.clone();
+            async move {
+                let connect_address = address.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    Client::connect(&connect_address, client_id)
+                })
+                .await?;
+                if let Some(metrics) = &metrics {
+                    metrics.inc_reconnects();
+                }
+                if let Some(journal) = &journal {
+                    journal.record(JournalEvent::Reconnect {
+                        address: address.clone(),
+                        success: result.is_ok(),
+                        error: result.as_ref().err().map(|e| e.to_string()),
+                    });
+                }
+
+                *client_holder.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(result?);
+                info!("Reconnected to TWS at {}", address);
+                Ok(())
+            }
+        };
+        let resubscribe = move |req_id: i32, symbol: String| {
+            let mut context = context.clone();
+            context.client = current_client(&context.client_holder);
+            context.spawn(symbol, req_id)
+        };
+
+        tokio::spawn(run_reconnect_supervisor(
+            rx,
+            self.active_subscriptions.clone(),
+            policy,
+            reconnect,
+            resubscribe,
+        ));
+
+        info!(
+            "Auto-reconnect enabled (max {} retries, base delay {:?})",
+            max_retries, base_delay
+        );
+    }
+
+    fn subscription_context(&self) -> SubscriptionContext {
+        SubscriptionContext {
+            client: self.client(),
+            client_holder: self.client.clone(),
+            market_data_handler: self.market_data_handler.clone(),
+            security_configs: self.security_configs.clone(),
+            active_subscriptions: self.active_subscriptions.clone(),
+            failure_tx: self.failure_tx.clone(),
+            bar_spec: self.realtime_bar_spec(),
+        }
+    }
+
+    fn realtime_bar_spec(&self) -> RealtimeBarSpec {
+        RealtimeBarSpec::from_seconds(self.config.realtime_bar_seconds).unwrap_or_else(|e| {
+            warn!("Using 5-second real-time bars: {}", e);
+            RealtimeBarSpec {
+                bar_size: RealtimeBarSize::Sec5,
+                bars_per_update: 1,
+            }
+        })
+    }
+
     pub async fn register_security_config(&self, symbol: String, config: SecurityConfig) {
         let mut configs = self.security_configs.lock().await;
         configs.insert(symbol, config);
     }
 
-    fn create_contract(&self, security_config: &SecurityConfig) -> Contract {
+    /// Combo (BAG) contract whose legs carry the spread's ratios
+    fn spread_contract(spread: &SpreadContract) -> Contract {
+        let combo_legs = spread
+            .legs
+            .iter()
+            .map(|leg| ComboLeg {
+                contract_id: leg.contract_id,
+                ratio: leg.ratio.abs(),
+                action: if leg.ratio > 0 { "BUY" } else { "SELL" }.to_string(),
+                exchange: spread.exchange.to_string(),
+                ..ComboLeg::default()
+            })
+            .collect();
+
+        Contract {
+            symbol: spread.symbol.to_string(),
+            security_type: ibapi::contracts::SecurityType::Spread,
+            exchange: spread.exchange.to_string(),
+            currency: spread.currency.to_string(),
+            combo_legs,
+            ..Contract::default()
+        }
+    }
+
+    fn create_contract(security_config: &SecurityConfig) -> Contract {
         match security_config.security_type {
             SecurityType::Stock => {
                 let mut contract = Contract::stock(&security_config.symbol);
@@ -125,14 +644,15 @@ impl TwsClient {
         // Get security config to create appropriate contract
         let configs = self.security_configs.lock().await;
         let contract = if let Some(security_config) = configs.get(symbol) {
-            self.create_contract(security_config)
+            Self::create_contract(security_config)
         } else {
             // Fallback to stock if no config found
             Contract::stock(symbol)
         };
+        let use_rth = data_use_rth(configs.get(symbol));
         drop(configs);
 
-        let client = self.client.clone();
+        let client = self.client();
 
         // Get historical data for momentum calculation
         match client.historical_data(
@@ -141,12 +661,22 @@ impl TwsClient {
             HistoricalDuration::days(30),
             HistoricalBarSize::Day,
             HistoricalWhatToShow::Trades,
-            true, // use RTH
+            use_rth,
         ) {
             Ok(historical_bars) => {
                 let mut handler = self.market_data_handler.lock().await;
                 for bar in historical_bars.bars.iter() {
-                    handler.add_historical_price(symbol, bar.date, bar.close);
+                    handler.add_historical_bar(symbol, bar.date, bar.close, bar.volume);
+                }
+                let filled = handler.fill_small_gaps(symbol);
+                if filled > 0 {
+                    debug!("Forward-filled {} missing bars for {}", filled, symbol);
+                }
+                for (before, after) in handler.detect_gaps(symbol) {
+                    warn!(
+                        "Historical data gap for {}: no bars between {} and {}",
+                        symbol, before, after
+                    );
                 }
                 info!(
                     "Loaded {} days of historical data for {}",
@@ -179,11 +709,12 @@ impl TwsClient {
                 SecurityType::Future => "contracts",
                 SecurityType::Forex => "units",
             };
-            (self.create_contract(security_config), unit)
+            (Self::create_contract(security_config), unit)
         } else {
             // Fallback to stock if no config found
             (Contract::stock(&signal.symbol), "shares")
         };
+        let outside_rth = order_outside_rth(configs.get(&signal.symbol), false);
         drop(configs);
 
         let action = if signal.action == "BUY" {
@@ -193,7 +724,7 @@ impl TwsClient {
         };
 
         // Create order based on type
-        let order = match signal.order_type.as_str() {
+        let mut order = match signal.order_type.as_str() {
             "MKT" => EnhancedOrderBuilder::market_order(action, signal.quantity),
             "LMT" => {
                 if let Some(limit_price) = signal.limit_price {
@@ -214,8 +745,9 @@ impl TwsClient {
                 EnhancedOrderBuilder::market_order(action, signal.quantity)
             }
         };
+        order.outside_rth = outside_rth;
 
-        let order_id = self.client.next_order_id();
+        let order_id = self.next_order_id();
 
         debug!(
             "Submitting TWS order: signal.quantity={:.0}, order.total_quantity={:.0}",
@@ -224,7 +756,7 @@ impl TwsClient {
         );
 
         // Submit order (fire-and-forget)
-        self.client.submit_order(order_id, &contract, &order)?;
+        self.submit_order(order_id, &contract, &order).await?;
 
         let action_str = if signal.action == "BUY" {
             "Buy"
@@ -283,19 +815,23 @@ impl TwsClient {
                 SecurityType::Future => "contracts",
                 SecurityType::Forex => "units",
             };
-            (self.create_contract(security_config), unit)
+            (Self::create_contract(security_config), unit)
         } else {
             // Fallback to stock if no config found
             (Contract::stock(&params.symbol), "shares")
         };
+        let outside_rth = order_outside_rth(configs.get(&params.symbol), params.outside_rth);
         drop(configs);
 
         // Create order from parameters
-        let order = EnhancedOrderBuilder::from_params(params.clone())?;
-        let order_id = self.client.next_order_id();
+        let order = EnhancedOrderBuilder::from_params(OrderParams {
+            outside_rth,
+            ..params.clone()
+        })?;
+        let order_id = self.next_order_id();
 
         // Submit order
-        self.client.submit_order(order_id, &contract, &order)?;
+        self.submit_order(order_id, &contract, &order).await?;
 
         info!(
             "Placed enhanced {:?} order #{} for {} {} of {} (type: {:?})",
@@ -329,11 +865,109 @@ impl TwsClient {
             outside_rth: false,
             hidden: false,
             all_or_none: false,
+            display_size: None,
+        };
+
+        self.place_enhanced_order(params).await
+    }
+
+    /// Place a limit order that expires at `expiry` (good-till-date)
+    pub async fn place_limit_order_gtd(
+        &self,
+        symbol: &str,
+        quantity: f64,
+        limit_price: f64,
+        expiry: DateTime<Utc>,
+    ) -> Result<i32> {
+        use crate::order_types::{OrderAction, OrderType, TimeInForce};
+
+        let action = if quantity > 0.0 {
+            OrderAction::Buy
+        } else {
+            OrderAction::Sell
+        };
+
+        let params = OrderParams {
+            symbol: symbol.to_string(),
+            action,
+            quantity: quantity.abs(),
+            order_type: OrderType::Limit { price: limit_price },
+            time_in_force: TimeInForce::GoodTillDate(expiry),
+            outside_rth: false,
+            hidden: false,
+            all_or_none: false,
+            display_size: None,
+        };
+
+        self.place_enhanced_order(params).await
+    }
+
+    /// Place a limit order that shows only `display_size` on the book
+    /// (iceberg)
+    pub async fn place_iceberg_order(
+        &self,
+        symbol: &str,
+        quantity: f64,
+        limit_price: f64,
+        display_size: f64,
+    ) -> Result<i32> {
+        use crate::order_types::{OrderAction, OrderType, TimeInForce};
+
+        let action = if quantity > 0.0 {
+            OrderAction::Buy
+        } else {
+            OrderAction::Sell
+        };
+
+        let params = OrderParams {
+            symbol: symbol.to_string(),
+            action,
+            quantity: quantity.abs(),
+            order_type: OrderType::Limit { price: limit_price },
+            time_in_force: TimeInForce::Day,
+            outside_rth: false,
+            hidden: false,
+            all_or_none: false,
+            display_size: Some(display_size),
         };
 
         self.place_enhanced_order(params).await
     }
 
+    /// Place a limit order on a futures spread as a single combo order
+    ///
+    /// A positive `quantity` buys the spread (each leg traded as its ratio's
+    /// sign says), a negative one sells it. `limit_price` is the spread price.
+    pub async fn place_spread_order(
+        &self,
+        spread: &SpreadContract,
+        quantity: f64,
+        limit_price: f64,
+    ) -> Result<i32> {
+        let action = if quantity > 0.0 {
+            OrderAction::Buy
+        } else {
+            OrderAction::Sell
+        };
+
+        let contract = Self::spread_contract(spread);
+        let order = EnhancedOrderBuilder::limit_order(action.clone(), quantity.abs(), limit_price);
+        let order_id = self.next_order_id();
+        self.submit_order(order_id, &contract, &order).await?;
+
+        info!(
+            "Placed {:?} spread order #{} for {} of {} ({} legs) @ {}",
+            action,
+            order_id,
+            quantity.abs(),
+            spread.symbol,
+            spread.legs.len(),
+            limit_price
+        );
+
+        Ok(order_id)
+    }
+
     /// Place a stop loss order
     pub async fn place_stop_loss_order(
         &self,
@@ -358,6 +992,45 @@ impl TwsClient {
             outside_rth: false,
             hidden: false,
             all_or_none: false,
+            display_size: None,
+        };
+
+        self.place_enhanced_order(params).await
+    }
+
+    /// Place a stop-limit order: once `stop_price` trades, rest a limit at
+    /// `limit_price` rather than sweeping a thin book
+    ///
+    /// A positive `quantity` buys, a negative one sells. The limit must be at
+    /// or below the stop for a sell, at or above it for a buy.
+    pub async fn place_stop_limit_order(
+        &self,
+        symbol: &str,
+        quantity: f64,
+        stop_price: f64,
+        limit_price: f64,
+    ) -> Result<i32> {
+        use crate::order_types::{OrderAction, OrderType, TimeInForce};
+
+        let action = if quantity > 0.0 {
+            OrderAction::Buy
+        } else {
+            OrderAction::Sell
+        };
+
+        let params = OrderParams {
+            symbol: symbol.to_string(),
+            action,
+            quantity: quantity.abs(),
+            order_type: OrderType::StopLimit {
+                stop_price,
+                limit_price,
+            },
+            time_in_force: TimeInForce::GTC,
+            outside_rth: false,
+            hidden: false,
+            all_or_none: false,
+            display_size: None,
         };
 
         self.place_enhanced_order(params).await
@@ -389,6 +1062,7 @@ impl TwsClient {
             outside_rth: false,
             hidden: false,
             all_or_none: false,
+            display_size: None,
         };
 
         self.place_enhanced_order(params).await
@@ -414,211 +1088,127 @@ impl TwsClient {
         // Get security config to create appropriate contract
         let configs = self.security_configs.lock().await;
         let contract = if let Some(security_config) = configs.get(symbol) {
-            self.create_contract(security_config)
+            Self::create_contract(security_config)
         } else {
             Contract::stock(symbol)
         };
+        let outside_rth = order_outside_rth(configs.get(symbol), false);
         drop(configs);
 
         // Create bracket orders
-        let orders = EnhancedOrderBuilder::bracket_order(
+        let mut orders = EnhancedOrderBuilder::bracket_order(
             action,
             quantity.abs(),
             entry_price,
             profit_target,
             stop_loss,
         );
+        for order in &mut orders {
+            order.outside_rth = outside_rth;
+        }
 
-        let mut order_ids = Vec::new();
+        // Pace the whole bracket up front: its legs must go out back to back
+        for _ in &orders {
+            self.order_rate.acquire().await;
+        }
+        let order_ids = submit_bracket(
+            orders,
+            || self.next_order_id(),
+            |order_id, order| self.send_order(order_id, &contract, order),
+        )?;
 
-        // Submit parent order first
-        let parent_order_id = self.client.next_order_id();
-        let mut parent_order = orders[0].clone();
-        parent_order.transmit = false; // Don't transmit until children are set
+        info!(
+            "Placed bracket order for {} units of {} (Entry: {}, Profit: {}, Stop: {})",
+            quantity, symbol, entry_price, profit_target, stop_loss
+        );
 
-        self.client
-            .submit_order(parent_order_id, &contract, &parent_order)?;
-        order_ids.push(parent_order_id);
+        Ok(order_ids)
+    }
 
-        // Submit child orders
-        for mut child_order in orders.into_iter().skip(1) {
-            let child_order_id = self.client.next_order_id();
-            child_order.parent_id = parent_order_id;
-            child_order.transmit = order_ids.len() == 2; // Transmit on last child
+    /// Rest a take-profit and a stop against an open position as an OCA pair
+    ///
+    /// `quantity` is the signed position being protected. Returns
+    /// `[take_profit, stop_loss]` order IDs.
+    pub async fn place_oco_exit(
+        &self,
+        symbol: &str,
+        quantity: f64,
+        take_profit: f64,
+        stop_loss: f64,
+    ) -> Result<Vec<i32>> {
+        let action = if quantity > 0.0 {
+            OrderAction::Sell
+        } else {
+            OrderAction::Buy
+        };
+
+        let configs = self.security_configs.lock().await;
+        let contract = if let Some(security_config) = configs.get(symbol) {
+            Self::create_contract(security_config)
+        } else {
+            Contract::stock(symbol)
+        };
+        let outside_rth = order_outside_rth(configs.get(symbol), false);
+        drop(configs);
 
-            self.client
-                .submit_order(child_order_id, &contract, &child_order)?;
-            order_ids.push(child_order_id);
+        let order_ids = [self.next_order_id(), self.next_order_id()];
+        let oca_group = format!("protect-{}-{}", symbol, order_ids[0]);
+        let orders = EnhancedOrderBuilder::oco_exit(
+            action,
+            quantity.abs(),
+            take_profit,
+            stop_loss,
+            &oca_group,
+        );
+        for (order_id, mut order) in order_ids.into_iter().zip(orders) {
+            order.outside_rth = outside_rth;
+            self.submit_order(order_id, &contract, &order).await?;
         }
 
         info!(
-            "Placed bracket order for {} units of {} (Entry: {}, Profit: {}, Stop: {})",
-            quantity, symbol, entry_price, profit_target, stop_loss
+            "Placed protective OCA pair for {} units of {} (Profit: {}, Stop: {})",
+            quantity, symbol, take_profit, stop_loss
         );
 
-        Ok(order_ids)
+        Ok(order_ids.to_vec())
     }
 
     pub async fn subscribe_realtime_data(
         &self,
         symbol: &str,
         req_id: i32,
-        tx: mpsc::Sender<MarketDataUpdate>,
+        tx: mpsc::Sender<MarketDataEvent>,
     ) -> Result<()> {
-        // Register the subscription
+        // Register the subscription, refusing to duplicate a live stream
         let mut subscriptions = self.active_subscriptions.lock().await;
-        subscriptions.insert(req_id, tx);
-        drop(subscriptions);
+        if subscriptions
+            .get(&req_id)
+            .is_some_and(|subscription| subscription.is_alive())
+        {
+            warn!(
+                "Subscription {} for {} is already active, ignoring duplicate request",
+                req_id, symbol
+            );
+            return Ok(());
+        }
 
         // Register symbol with handler
         let mut handler = self.market_data_handler.lock().await;
         handler.register_symbol(req_id, symbol.to_string());
         drop(handler);
 
-        // Get security config to create appropriate contract
-        let configs = self.security_configs.lock().await;
-        let (contract, security_type) = if let Some(security_config) = configs.get(symbol) {
-            (
-                self.create_contract(security_config),
-                security_config.security_type.clone(),
-            )
-        } else {
-            // Fallback to stock if no config found
-            (Contract::stock(symbol), SecurityType::Stock)
-        };
-        drop(configs);
-
-        let client = self.client.clone();
-        let symbol_owned = symbol.to_string();
-        let active_subs = self.active_subscriptions.clone();
-        let handler_ref = self.market_data_handler.clone();
-
         // Spawn a task to handle real-time bars data
-        tokio::spawn(async move {
-            info!(
-                "Starting real-time market data subscription for {}",
-                symbol_owned
-            );
-
-            // Determine appropriate WhatToShow based on security type
-            let historical_what_to_show = match &security_type {
-                SecurityType::Forex => HistoricalWhatToShow::MidPoint,
-                _ => HistoricalWhatToShow::Trades,
-            };
-
-            // For paper trading, try to get historical data first
-            match client.historical_data(
-                &contract,
-                None, // end time (None = now)
-                HistoricalDuration::days(1),
-                HistoricalBarSize::Min,
-                historical_what_to_show,
-                true, // use RTH
-            ) {
-                Ok(historical_bars) => {
-                    debug!(
-                        "Got {} historical bars for {}",
-                        historical_bars.bars.len(),
-                        symbol_owned
-                    );
-
-                    // Update handler with historical data
-                    let mut handler = handler_ref.lock().await;
-                    for bar in historical_bars.bars.iter() {
-                        handler.add_historical_price(&symbol_owned, bar.date, bar.close);
-                    }
-                    drop(handler);
-                }
-                Err(e) => {
-                    warn!("Failed to get historical data for {}: {}", symbol_owned, e);
-                }
-            }
-
-            // Determine appropriate WhatToShow for real-time bars
-            let realtime_what_to_show = match &security_type {
-                SecurityType::Forex => RealtimeWhatToShow::MidPoint,
-                _ => RealtimeWhatToShow::Trades,
-            };
-
-            // Subscribe to real-time bars (5 second intervals)
-            match client.realtime_bars(
-                &contract,
-                RealtimeBarSize::Sec5,
-                realtime_what_to_show,
-                false,
-            ) {
-                Ok(subscription) => {
-                    info!(
-                        "Successfully subscribed to real-time bars for {}",
-                        symbol_owned
-                    );
-
-                    // Process incoming bar data
-                    for bar in subscription {
-                        let subs = active_subs.lock().await;
-                        if let Some(tx) = subs.get(&req_id) {
-                            let update = MarketDataUpdate {
-                                symbol: symbol_owned.to_string(),
-                                last_price: bar.close,
-                                bid_price: bar.close - 0.01, // Approximate bid
-                                ask_price: bar.close + 0.01, // Approximate ask
-                                volume: bar.volume as i64,
-                                timestamp: Utc::now(),
-                            };
-
-                            // Update the handler
-                            let mut handler = handler_ref.lock().await;
-                            handler.update_realtime_data(
-                                &symbol_owned,
-                                update.last_price,
-                                update.volume,
-                            );
-                            drop(handler);
-
-                            // Send to channel
-                            if tx.send(update).await.is_err() {
-                                warn!(
-                                    "Failed to send market data update for {}, receiver dropped",
-                                    symbol_owned
-                                );
-                                break;
-                            }
-                        } else {
-                            // Subscription was cancelled
-                            info!("Subscription {} cancelled for {}", req_id, symbol_owned);
-                            break;
-                        }
-                    }
-
-                    info!("Real-time bars stream ended for {}", symbol_owned);
-                }
-                Err(e) => {
-                    error!(
-                        "Failed to subscribe to real-time bars for {}: {}",
-                        symbol_owned, e
-                    );
-
-                    // Try to send an error indicator
-                    let subs = active_subs.lock().await;
-                    if let Some(tx) = subs.get(&req_id) {
-                        // Send a market data update with zero prices to indicate error
-                        let error_update = MarketDataUpdate {
-                            symbol: symbol_owned.to_string(),
-                            last_price: 0.0,
-                            bid_price: 0.0,
-                            ask_price: 0.0,
-                            volume: 0,
-                            timestamp: Utc::now(),
-                        };
-                        let _ = tx.send(error_update).await;
-                    }
-                }
-            }
-
-            // Clean up subscription
-            let mut subs = active_subs.lock().await;
-            subs.remove(&req_id);
-        });
+        let task = self
+            .subscription_context()
+            .spawn(symbol.to_string(), req_id);
+        subscriptions.insert(
+            req_id,
+            ActiveSubscription {
+                symbol: symbol.to_string(),
+                tx,
+                task: Some(task),
+            },
+        );
 
         Ok(())
     }
@@ -631,11 +1221,25 @@ impl TwsClient {
         Ok(())
     }
 
+    /// Stop every real-time subscription for shutdown
+    ///
+    /// Failed streams are no longer resubscribed afterwards. Returns the number
+    /// of subscriptions cancelled.
+    pub async fn cancel_all_subscriptions(&self) -> usize {
+        self.failure_tx.lock().await.take();
+        let cancelled = cancel_subscriptions(&self.active_subscriptions).await;
+        info!("Cancelled {} real-time subscriptions", cancelled);
+        cancelled
+    }
+
     pub async fn get_account_summary(&self) -> Result<HashMap<String, f64>> {
         let mut summary = HashMap::new();
 
         // Request account summary from IBKR
-        match self.client.account_summary("All", AccountSummaryTags::ALL) {
+        match self
+            .client()
+            .account_summary("All", AccountSummaryTags::ALL)
+        {
             Ok(subscription) => {
                 // Process account summary data
                 for update in &subscription {
@@ -718,10 +1322,67 @@ impl TwsClient {
         Ok(summary)
     }
 
+    /// One-shot pre-session self-test; never places orders
+    ///
+    /// Checks that TWS answers, that `security` (SPY when None) returns
+    /// historical bars, and that the account summary reports net liquidation.
+    pub async fn health_check(&self, security: Option<&SecurityConfig>) -> HealthReport {
+        let client = self.client();
+        let mut report = HealthReport::new();
+
+        report.record(
+            "connection",
+            client
+                .server_time()
+                .map(|time| {
+                    format!(
+                        "server version {}, server time {}",
+                        client.server_version(),
+                        time
+                    )
+                })
+                .map_err(Into::into),
+        );
+
+        let (symbol, contract) = match security {
+            Some(security) => (security.symbol.as_str(), Self::create_contract(security)),
+            None => (HEALTH_CHECK_SYMBOL, Contract::stock(HEALTH_CHECK_SYMBOL)),
+        };
+        let what_to_show = match security.map(|security| &security.security_type) {
+            Some(SecurityType::Forex) => HistoricalWhatToShow::MidPoint,
+            _ => HistoricalWhatToShow::Trades,
+        };
+        report.record(
+            "historical_data",
+            client
+                .historical_data(
+                    &contract,
+                    None,
+                    HistoricalDuration::days(5),
+                    HistoricalBarSize::Day,
+                    what_to_show,
+                    data_use_rth(security),
+                )
+                .map_err(Into::into)
+                .and_then(|data| match data.bars.last() {
+                    Some(bar) => Ok(format!(
+                        "{} daily bars for {}, last close {}",
+                        data.bars.len(),
+                        symbol,
+                        bar.close
+                    )),
+                    None => Err(anyhow!("no bars returned for {}", symbol)),
+                }),
+        );
+
+        report.record("account_summary", net_liquidation(&client));
+        report
+    }
+
     pub async fn get_positions(&self) -> Result<Vec<AccountPosition>> {
         let mut positions = Vec::new();
 
-        match self.client.positions() {
+        match self.client().positions() {
             Ok(subscription) => {
                 // Process all position updates
                 while let Some(position_update) = subscription.next() {
@@ -760,6 +1421,678 @@ impl TwsClient {
     }
 }
 
+/// Tell a subscription's consumer that its stream failed
+async fn notify_subscription_error(
+    registry: &SubscriptionRegistry,
+    req_id: i32,
+    symbol: &str,
+    message: &str,
+) {
+    let subs = registry.lock().await;
+    if let Some(subscription) = subs.get(&req_id) {
+        let error_event = MarketDataEvent::Error {
+            symbol: symbol.to_string(),
+            message: message.to_string(),
+        };
+        let _ = subscription.tx.send(error_event).await;
+    }
+}
+
+/// Bid and ask to publish with a bar closing at `close`, and when they were
+/// quoted
+///
+/// The latest top-of-book quote when one is held, otherwise a spread of one
+/// price increment for the security type around the close with no quote
+/// time, so subscribers do not take it for a real quote.
+fn bar_quote(
+    handler: &MarketDataHandler,
+    symbol: &str,
+    close: f64,
+) -> (f64, f64, Option<DateTime<Utc>>) {
+    if let Some(data) = handler.get_market_data(symbol)
+        && let Some((bid, ask)) = data.quote()
+    {
+        return (bid, ask, data.quote_timestamp);
+    }
+    let (bid, ask) = match handler.get_security_info(symbol) {
+        Some(security_info) => security_info.approximate_quote(close),
+        None => (close, close),
+    };
+    (bid, ask, None)
+}
+
+/// Top-of-book subscription feeding bid and ask into the handler
+///
+/// Runs on a blocking thread and stops at the next tick after the handle is
+/// dropped. Without a market data subscription for the instrument the request
+/// fails and bars fall back to an approximate quote.
+struct QuoteStream {
+    stop: Arc<AtomicBool>,
+}
+
+impl QuoteStream {
+    fn spawn(
+        client: Arc<Client>,
+        contract: Contract,
+        symbol: String,
+        handler: Arc<Mutex<MarketDataHandler>>,
+    ) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        tokio::task::spawn_blocking(move || {
+            let subscription = match client.market_data(&contract, &[], false, false) {
+                Ok(subscription) => subscription,
+                Err(e) => {
+                    warn!(
+                        "No top-of-book quotes for {}, approximating the spread: {}",
+                        symbol, e
+                    );
+                    return;
+                }
+            };
+
+            let (mut bid, mut ask) = (0.0, 0.0);
+            for tick in &subscription {
+                if stopped.load(Ordering::Relaxed) {
+                    subscription.cancel();
+                    break;
+                }
+                let (tick_type, price) = match tick {
+                    TickTypes::Price(tick) => (tick.tick_type, tick.price),
+                    TickTypes::PriceSize(tick) => (tick.price_tick_type, tick.price),
+                    _ => continue,
+                };
+                match tick_type {
+                    TickType::Bid | TickType::DelayedBid => bid = price,
+                    TickType::Ask | TickType::DelayedAsk => ask = price,
+                    _ => continue,
+                }
+                if bid > 0.0 && ask > 0.0 {
+                    handler.blocking_lock().update_quote(&symbol, bid, ask);
+                }
+            }
+            debug!("Top-of-book quote stream ended for {}", symbol);
+        });
+        Self { stop }
+    }
+}
+
+impl Drop for QuoteStream {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Shared state needed by a real-time subscription task
+#[derive(Clone)]
+struct SubscriptionContext {
+    client: Arc<Client>,
+    client_holder: Arc<RwLock<Arc<Client>>>,
+    market_data_handler: Arc<Mutex<MarketDataHandler>>,
+    security_configs: Arc<Mutex<HashMap<String, SecurityConfig>>>,
+    active_subscriptions: SubscriptionRegistry,
+    failure_tx: FailureSender,
+    bar_spec: RealtimeBarSpec,
+}
+
+impl SubscriptionContext {
+    fn spawn(self, symbol: String, req_id: i32) -> JoinHandle<()> {
+        tokio::spawn(async move { self.run(symbol, req_id).await })
+    }
+
+    async fn run(self, symbol_owned: String, req_id: i32) {
+        let client = self.client.clone();
+        let active_subs = self.active_subscriptions.clone();
+        let handler_ref = self.market_data_handler.clone();
+
+        // Get security config to create appropriate contract
+        let configs = self.security_configs.lock().await;
+        let (contract, security_type) = if let Some(security_config) = configs.get(&symbol_owned) {
+            (
+                TwsClient::create_contract(security_config),
+                security_config.security_type.clone(),
+            )
+        } else {
+            // Fallback to stock if no config found
+            (Contract::stock(&symbol_owned), SecurityType::Stock)
+        };
+        let use_rth = data_use_rth(configs.get(&symbol_owned));
+        drop(configs);
+
+        info!(
+            "Starting real-time market data subscription for {}",
+            symbol_owned
+        );
+
+        // Determine appropriate WhatToShow based on security type
+        let historical_what_to_show = match &security_type {
+            SecurityType::Forex => HistoricalWhatToShow::MidPoint,
+            _ => HistoricalWhatToShow::Trades,
+        };
+
+        // For paper trading, try to get historical data first
+        match client.historical_data(
+            &contract,
+            None, // end time (None = now)
+            HistoricalDuration::days(1),
+            HistoricalBarSize::Min,
+            historical_what_to_show,
+            use_rth,
+        ) {
+            Ok(historical_bars) => {
+                debug!(
+                    "Got {} historical bars for {}",
+                    historical_bars.bars.len(),
+                    symbol_owned
+                );
+
+                // Update handler with historical data
+                let mut handler = handler_ref.lock().await;
+                for bar in historical_bars.bars.iter() {
+                    handler.add_historical_bar(&symbol_owned, bar.date, bar.close, bar.volume);
+                }
+                drop(handler);
+            }
+            Err(e) => {
+                warn!("Failed to get historical data for {}: {}", symbol_owned, e);
+            }
+        }
+
+        // Stream top-of-book quotes for as long as this task runs
+        let quotes = QuoteStream::spawn(
+            client.clone(),
+            contract.clone(),
+            symbol_owned.clone(),
+            handler_ref.clone(),
+        );
+
+        // Determine appropriate WhatToShow for real-time bars
+        let realtime_what_to_show = match &security_type {
+            SecurityType::Forex => RealtimeWhatToShow::MidPoint,
+            _ => RealtimeWhatToShow::Trades,
+        };
+
+        // Subscribe to real-time bars, merged into bars of the configured length,
+        // over the same hours as the history they extend
+        let failure_reason = match client.realtime_bars(
+            &contract,
+            self.bar_spec.bar_size,
+            realtime_what_to_show,
+            use_rth,
+        ) {
+            Ok(subscription) => {
+                info!(
+                    "Successfully subscribed to real-time bars for {}",
+                    symbol_owned
+                );
+
+                let mut failure_reason = Some("real-time bars stream ended".to_string());
+                let mut merged_bars = 0;
+                let mut merged_volume = 0.0;
+
+                // Process incoming bar data
+                for bar in subscription {
+                    merged_bars += 1;
+                    merged_volume += bar.volume;
+                    if merged_bars < self.bar_spec.bars_per_update {
+                        continue;
+                    }
+                    let volume = std::mem::take(&mut merged_volume) as i64;
+                    merged_bars = 0;
+
+                    let subs = active_subs.lock().await;
+                    let tx = subs
+                        .get(&req_id)
+                        .map(|subscription| subscription.tx.clone());
+                    drop(subs);
+
+                    if let Some(tx) = tx {
+                        // The receiver applies the update; only read the quote here
+                        let handler = handler_ref.lock().await;
+                        let (bid_price, ask_price, quote_timestamp) =
+                            bar_quote(&handler, &symbol_owned, bar.close);
+                        drop(handler);
+
+                        let update = MarketDataUpdate {
+                            symbol: symbol_owned.to_string(),
+                            last_price: bar.close,
+                            bid_price,
+                            ask_price,
+                            quote_timestamp,
+                            volume,
+                            timestamp: Utc::now(),
+                        };
+
+                        // Send to channel
+                        if tx.send(MarketDataEvent::Update(update)).await.is_err() {
+                            warn!(
+                                "Failed to send market data update for {}, receiver dropped",
+                                symbol_owned
+                            );
+                            active_subs.lock().await.remove(&req_id);
+                            failure_reason = None;
+                            break;
+                        }
+                    } else {
+                        // Subscription was cancelled
+                        info!("Subscription {} cancelled for {}", req_id, symbol_owned);
+                        failure_reason = None;
+                        break;
+                    }
+                }
+
+                if failure_reason.is_some() {
+                    info!("Real-time bars stream ended for {}", symbol_owned);
+                }
+                failure_reason
+            }
+            Err(e) => {
+                error!(
+                    "Failed to subscribe to real-time bars for {}: {}",
+                    symbol_owned, e
+                );
+
+                notify_subscription_error(&active_subs, req_id, &symbol_owned, &e.to_string())
+                    .await;
+
+                Some(format!("real-time bars request failed: {}", e))
+            }
+        };
+
+        drop(quotes);
+        if let Some(reason) = failure_reason {
+            self.report_failure(req_id, symbol_owned, reason).await;
+        }
+    }
+
+    /// Hand a dropped stream to the reconnect supervisor, or clean it up if none is running
+    async fn report_failure(&self, req_id: i32, symbol: String, reason: String) {
+        let failure_tx = self.failure_tx.lock().await.clone();
+        let mut subs = self.active_subscriptions.lock().await;
+        if !subs.contains_key(&req_id) {
+            return;
+        }
+
+        let failure = SubscriptionFailure {
+            req_id,
+            symbol,
+            reason,
+            task_id: tokio::task::id(),
+        };
+        let delivered = failure_tx.is_some_and(|tx| tx.send(failure).is_ok());
+        if !delivered {
+            // No supervisor to replay it, so drop the dead subscription
+            subs.remove(&req_id);
+        }
+    }
+}
+
+/// Submit a parent order and its children, transmitting only with the last child
+///
+/// Returns the parent id followed by the child ids.
+pub(crate) fn submit_bracket(
+    orders: Vec<Order>,
+    mut next_order_id: impl FnMut() -> i32,
+    mut submit: impl FnMut(i32, &Order) -> Result<()>,
+) -> Result<Vec<i32>> {
+    let mut order_ids = Vec::new();
+    let child_count = orders.len().saturating_sub(1);
+    let mut orders = orders.into_iter();
+
+    // Submit parent order first
+    let Some(mut parent_order) = orders.next() else {
+        return Ok(order_ids);
+    };
+    let parent_order_id = next_order_id();
+    parent_order.transmit = false; // Don't transmit until children are set
+    submit(parent_order_id, &parent_order)?;
+    order_ids.push(parent_order_id);
+
+    // Submit child orders
+    for (index, mut child_order) in orders.enumerate() {
+        let child_order_id = next_order_id();
+        child_order.parent_id = parent_order_id;
+        child_order.transmit = index + 1 == child_count; // Transmit on last child
+        submit(child_order_id, &child_order)?;
+        order_ids.push(child_order_id);
+    }
+
+    Ok(order_ids)
+}
+
+/// Net liquidation from the account summary, as a health check detail
+fn net_liquidation(client: &Client) -> Result<String> {
+    let subscription = client.account_summary("All", &[AccountSummaryTags::NET_LIQUIDATION])?;
+    let mut net_liquidation = None;
+    for update in &subscription {
+        match update {
+            AccountSummaries::Summary(summary)
+                if summary.tag == AccountSummaryTags::NET_LIQUIDATION =>
+            {
+                net_liquidation = Some(format!(
+                    "net liquidation {} {} for {}",
+                    summary.value, summary.currency, summary.account
+                ));
+            }
+            AccountSummaries::Summary(_) => {}
+            AccountSummaries::End => break,
+        }
+    }
+    subscription.cancel();
+    net_liquidation.ok_or_else(|| anyhow!("account summary reported no net liquidation"))
+}
+
+/// `use_rth` for a symbol's historical and real-time data requests: regular
+/// trading hours only unless its config asks for extended hours
+///
+/// Futures and forex need `extended_hours_data` to stream outside regular hours.
+fn data_use_rth(security_config: Option<&SecurityConfig>) -> bool {
+    security_config.is_none_or(|config| !config.extended_hours_data)
+}
+
+/// `outside_rth` for a symbol's orders: set when either the order asks for it
+/// or the symbol's config allows trading outside regular hours
+fn order_outside_rth(security_config: Option<&SecurityConfig>, requested: bool) -> bool {
+    requested || security_config.is_some_and(|config| config.outside_rth_orders)
+}
+
+fn current_client(holder: &RwLock<Arc<Client>>) -> Arc<Client> {
+    holder.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Abort every registered subscription task and empty the registry
+async fn cancel_subscriptions(subscriptions: &SubscriptionRegistry) -> usize {
+    let mut subscriptions = subscriptions.lock().await;
+    let cancelled = subscriptions.len();
+    for (_, subscription) in subscriptions.drain() {
+        if let Some(task) = subscription.task {
+            task.abort();
+        }
+    }
+    cancelled
+}
+
+/// Reconnect after dropped streams and replay dead subscriptions
+///
+/// Failures arriving together are handled as one outage: a single reconnect
+/// followed by a re-subscribe of every registered subscription whose task has
+/// stopped. Subscriptions with a live task are never duplicated.
+async fn run_reconnect_supervisor<R, F, S>(
+    mut failures: mpsc::UnboundedReceiver<SubscriptionFailure>,
+    subscriptions: SubscriptionRegistry,
+    policy: ReconnectPolicy,
+    mut reconnect: R,
+    mut resubscribe: S,
+) where
+    R: FnMut() -> F,
+    F: Future<Output = Result<()>>,
+    S: FnMut(i32, String) -> JoinHandle<()>,
+{
+    while let Some(failure) = failures.recv().await {
+        let mut outage = vec![failure];
+        while let Ok(failure) = failures.try_recv() {
+            outage.push(failure);
+        }
+
+        // Wait for the reporting tasks to exit so they count as dead
+        for failure in &outage {
+            warn!(
+                "Subscription {} for {} failed: {}",
+                failure.req_id, failure.symbol, failure.reason
+            );
+            let task = subscriptions
+                .lock()
+                .await
+                .get_mut(&failure.req_id)
+                .and_then(|subscription| {
+                    let same_task = subscription
+                        .task
+                        .as_ref()
+                        .is_some_and(|task| task.id() == failure.task_id);
+                    if same_task {
+                        subscription.task.take()
+                    } else {
+                        None
+                    }
+                });
+            if let Some(task) = task {
+                let _ = task.await;
+            }
+        }
+
+        let dead: Vec<(i32, String)> = subscriptions
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, subscription)| !subscription.is_alive())
+            .map(|(req_id, subscription)| (*req_id, subscription.symbol.clone()))
+            .collect();
+        if dead.is_empty() {
+            debug!("No dead subscriptions to replay");
+            continue;
+        }
+
+        let mut reconnected = false;
+        for attempt in 0..policy.max_retries {
+            let delay = policy.delay_for_attempt(attempt);
+            info!(
+                "Reconnect attempt {}/{} in {:?}",
+                attempt + 1,
+                policy.max_retries,
+                delay
+            );
+            tokio::time::sleep(delay).await;
+
+            match reconnect().await {
+                Ok(()) => {
+                    reconnected = true;
+                    break;
+                }
+                Err(e) => warn!("Reconnect attempt {} failed: {}", attempt + 1, e),
+            }
+        }
+
+        let mut subs = subscriptions.lock().await;
+        if !reconnected {
+            error!(
+                "Giving up on TWS reconnection after {} attempts, dropping {} subscriptions",
+                policy.max_retries,
+                dead.len()
+            );
+            for (req_id, _) in &dead {
+                if subs.get(req_id).is_some_and(|s| !s.is_alive()) {
+                    subs.remove(req_id);
+                }
+            }
+            continue;
+        }
+
+        for (req_id, symbol) in dead {
+            if let Some(subscription) = subs.get_mut(&req_id) {
+                if subscription.is_alive() {
+                    continue;
+                }
+                info!("Replaying subscription {} for {}", req_id, symbol);
+                subscription.task = Some(resubscribe(req_id, symbol));
+            }
+        }
+    }
+}
+
+/// Open and completed order statuses, by order ID; blocks on TWS
+fn poll_tws_order_statuses(client: &Client) -> Result<HashMap<i32, OrderStatus>> {
+    let mut statuses = HashMap::new();
+    for update in client.completed_orders(true)? {
+        if let Orders::OrderData(data) = update {
+            statuses.insert(
+                data.order_id,
+                order_status_from_tws(
+                    &data.order_state.status,
+                    data.order.filled_quantity,
+                    data.order.total_quantity - data.order.filled_quantity,
+                ),
+            );
+        }
+    }
+
+    // Open orders are newer; status messages carry the filled quantity
+    for update in client.open_orders()? {
+        match update {
+            Orders::OrderStatus(status) => {
+                statuses.insert(
+                    status.order_id,
+                    order_status_from_tws(&status.status, status.filled, status.remaining),
+                );
+            }
+            Orders::OrderData(data) => {
+                statuses.entry(data.order_id).or_insert_with(|| {
+                    order_status_from_tws(&data.order_state.status, 0.0, data.order.total_quantity)
+                });
+            }
+            _ => {}
+        }
+    }
+
+    debug!("Polled {} order statuses from TWS", statuses.len());
+    Ok(statuses)
+}
+
+/// Executions by contract ID as (signed quantity, time), oldest first
+type ExecutionsByContract = HashMap<i32, Vec<(f64, DateTime<Utc>)>>;
+
+/// Today's executions, with the start of the TWS day
+fn todays_executions(client: &Client) -> Result<(ExecutionsByContract, DateTime<Utc>)> {
+    // Execution times are in the TWS time zone, whose offset the handshake gives
+    let offset = client
+        .connection_time()
+        .and_then(|time| FixedOffset::east_opt(time.offset().whole_seconds()))
+        .unwrap_or_else(|| FixedOffset::east_opt(0).expect("zero offset"));
+    let midnight = Utc::now()
+        .with_timezone(&offset)
+        .date_naive()
+        .and_time(NaiveTime::MIN);
+    let day_start = offset
+        .from_local_datetime(&midnight)
+        .single()
+        .map_or_else(Utc::now, |time| time.with_timezone(&Utc));
+
+    let mut executions = ExecutionsByContract::new();
+    for update in client.executions(ExecutionFilter::default())? {
+        if let Executions::ExecutionData(data) = update {
+            let execution = &data.execution;
+            let Some(time) = parse_execution_time(&execution.time, offset) else {
+                warn!("Unparseable execution time {:?}", execution.time);
+                continue;
+            };
+            let quantity = if execution.side == "SLD" {
+                -execution.shares
+            } else {
+                execution.shares
+            };
+            executions
+                .entry(data.contract.contract_id)
+                .or_default()
+                .push((quantity, time));
+        }
+    }
+    for fills in executions.values_mut() {
+        fills.sort_by_key(|(_, time)| *time);
+    }
+    Ok((executions, day_start))
+}
+
+/// A TWS execution time ("yyyymmdd hh:mm:ss", optionally followed by a zone
+/// name) in the `offset` time zone
+fn parse_execution_time(time: &str, offset: FixedOffset) -> Option<DateTime<Utc>> {
+    let mut parts = time.split_whitespace();
+    let stamp = format!("{} {}", parts.next()?, parts.next()?);
+    let local = NaiveDateTime::parse_from_str(&stamp, "%Y%m%d %H:%M:%S").ok()?;
+    offset
+        .from_local_datetime(&local)
+        .single()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// When a position of `quantity` was opened: walking `fills` (signed
+/// quantity, time, oldest first) back from the newest, the fill it was flat
+/// or on the other side before. A position the fills never open predates
+/// them and is dated `window_start`.
+fn opening_time(
+    quantity: f64,
+    fills: &[(f64, DateTime<Utc>)],
+    window_start: DateTime<Utc>,
+) -> DateTime<Utc> {
+    let mut held = quantity;
+    for (filled, time) in fills.iter().rev() {
+        let before = held - filled;
+        if before.abs() < 1e-9 || before.signum() != quantity.signum() {
+            return *time;
+        }
+        held = before;
+    }
+    window_start
+}
+
+/// Status of `order_id` among TWS's open, then completed, orders
+fn lookup_order_status(client: &Client, order_id: i32) -> Result<OrderStatus> {
+    let open = client.open_orders()?;
+    while let Some(update) = open.next() {
+        match update {
+            Orders::OrderStatus(status) if status.order_id == order_id => {
+                open.cancel();
+                return Ok(order_status_from_tws(
+                    &status.status,
+                    status.filled,
+                    status.remaining,
+                ));
+            }
+            Orders::OrderData(data) if data.order_id == order_id => {
+                open.cancel();
+                return Ok(order_status_from_tws(
+                    &data.order_state.status,
+                    0.0,
+                    data.order.total_quantity,
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    let completed = client.completed_orders(true)?;
+    while let Some(update) = completed.next() {
+        if let Orders::OrderData(data) = update
+            && data.order_id == order_id
+        {
+            completed.cancel();
+            return Ok(order_status_from_tws(
+                &data.order_state.status,
+                data.order.filled_quantity,
+                data.order.total_quantity - data.order.filled_quantity,
+            ));
+        }
+    }
+
+    anyhow::bail!(
+        "Order #{} not found among open or completed orders",
+        order_id
+    )
+}
+
+/// Map a TWS order status string onto the order lifecycle
+fn order_status_from_tws(status: &str, filled: f64, remaining: f64) -> OrderStatus {
+    match status {
+        "Filled" => OrderStatus::Filled,
+        "Cancelled" | "ApiCancelled" => OrderStatus::Cancelled,
+        "Inactive" => OrderStatus::Rejected,
+        "ApiPending" | "PendingSubmit" => OrderStatus::Pending,
+        _ if filled > 0.0 => OrderStatus::PartiallyFilled {
+            filled_qty: filled,
+            remaining_qty: remaining,
+        },
+        _ => OrderStatus::Submitted,
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AccountPosition {
     pub account: String,
@@ -785,6 +2118,12 @@ mod tests {
             host: "127.0.0.1".to_string(),
             port: 7497, // paper trading port
             client_id: 999,
+            dry_run: false,
+            simulate_fills: false,
+            simulated_starting_cash: 100_000.0,
+            client_id_attempts: 1,
+            max_orders_per_second: 40.0,
+            realtime_bar_seconds: 5,
         };
 
         // This test will fail initially (RED phase)
@@ -797,7 +2136,7 @@ mod tests {
 
         // Should receive market data updates
         tokio::select! {
-            Some(data) = rx.recv() => {
+            Some(MarketDataEvent::Update(data)) = rx.recv() => {
                 assert_eq!(data.symbol, "AAPL");
                 assert!(data.last_price > 0.0);
             }
@@ -818,6 +2157,12 @@ mod tests {
             host: "127.0.0.1".to_string(),
             port: 7497,
             client_id: 998,
+            dry_run: false,
+            simulate_fills: false,
+            simulated_starting_cash: 100_000.0,
+            client_id_attempts: 1,
+            max_orders_per_second: 40.0,
+            realtime_bar_seconds: 5,
         };
 
         let client = TwsClient::new(config).await?;
@@ -845,8 +2190,8 @@ mod tests {
             }
 
             tokio::select! {
-                Some(data) = rx.recv() => {
-                    received_symbols.insert(data.symbol);
+                Some(event) = rx.recv() => {
+                    received_symbols.insert(event.symbol().to_string());
                     if received_symbols.len() == 3 {
                         break;
                     }
@@ -871,6 +2216,12 @@ mod tests {
             host: "127.0.0.1".to_string(),
             port: 7497,
             client_id: 997,
+            dry_run: false,
+            simulate_fills: false,
+            simulated_starting_cash: 100_000.0,
+            client_id_attempts: 1,
+            max_orders_per_second: 40.0,
+            realtime_bar_seconds: 5,
         };
 
         let client = TwsClient::new(config).await?;
@@ -895,6 +2246,12 @@ mod tests {
             host: "127.0.0.1".to_string(),
             port: 7497,
             client_id: 996,
+            dry_run: false,
+            simulate_fills: false,
+            simulated_starting_cash: 100_000.0,
+            client_id_attempts: 1,
+            max_orders_per_second: 40.0,
+            realtime_bar_seconds: 5,
         };
 
         let client = TwsClient::new(config).await?;
@@ -910,10 +2267,8 @@ mod tests {
 
         // But we should receive an error message through the channel
         tokio::select! {
-            Some(data) = rx.recv() => {
-                // In real implementation, we'd have an error variant
-                // For now, this test will help drive the design
-                assert_eq!(data.symbol, "INVALID_SYMBOL_12345");
+            Some(MarketDataEvent::Error { symbol, .. }) = rx.recv() => {
+                assert_eq!(symbol, "INVALID_SYMBOL_12345");
             }
             _ = sleep(Duration::from_secs(5)) => {
                 // This is expected for invalid symbols
@@ -922,4 +2277,453 @@ mod tests {
 
         Ok(())
     }
+
+    fn registered(symbol: &str, task: JoinHandle<()>) -> ActiveSubscription {
+        let (tx, _rx) = mpsc::channel(1);
+        ActiveSubscription {
+            symbol: symbol.to_string(),
+            tx,
+            task: Some(task),
+        }
+    }
+
+    fn failure_for(req_id: i32, symbol: &str, task: &JoinHandle<()>) -> SubscriptionFailure {
+        SubscriptionFailure {
+            req_id,
+            symbol: symbol.to_string(),
+            reason: "real-time bars stream ended".to_string(),
+            task_id: task.id(),
+        }
+    }
+
+    fn fast_policy(max_retries: u32) -> ReconnectPolicy {
+        ReconnectPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(1),
+        }
+    }
+
+    #[test]
+    fn test_reconnect_backoff_doubles() {
+        let policy = ReconnectPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(500),
+        };
+
+        assert_eq!(policy.delay_for_attempt(0), Duration::from_millis(500));
+        assert_eq!(policy.delay_for_attempt(1), Duration::from_secs(1));
+        assert_eq!(policy.delay_for_attempt(3), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_client_id_search_skips_ids_in_use() {
+        let mut tried = Vec::new();
+        let (client_id, connection) = first_available_client_id(
+            10..=14,
+            |id| {
+                tried.push(id);
+                if id < 12 {
+                    Err("client id is already in use")
+                } else {
+                    Ok(format!("session {}", id))
+                }
+            },
+            |_| false,
+        )
+        .unwrap();
+
+        assert_eq!(client_id, 12);
+        assert_eq!(connection, "session 12");
+        assert_eq!(tried, vec![10, 11, 12]);
+    }
+
+    #[test]
+    fn test_client_id_search_exhausted_or_fatal() {
+        let mut tried = Vec::new();
+        let error = first_available_client_id(
+            1..=3,
+            |id| {
+                tried.push(id);
+                Err::<(), _>("client id is already in use")
+            },
+            |_| false,
+        )
+        .unwrap_err();
+        assert_eq!(tried, vec![1, 2, 3]);
+        assert!(error.to_string().contains("No free TWS client id in 1..=3"));
+
+        tried.clear();
+        let error = first_available_client_id(
+            1..=3,
+            |id| {
+                tried.push(id);
+                Err::<(), _>("connection refused")
+            },
+            |e| *e == "connection refused",
+        )
+        .unwrap_err();
+        assert_eq!(tried, vec![1]);
+        assert!(error.to_string().contains("connection refused"));
+
+        let empty = RangeInclusive::new(5, 4);
+        let error =
+            first_available_client_id(empty, |_| Ok::<(), &str>(()), |_| false).unwrap_err();
+        assert!(error.to_string().contains("Empty client id range"));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_subscriptions_empties_registry() {
+        let registry: SubscriptionRegistry = Arc::new(Mutex::new(HashMap::new()));
+        let live_task = tokio::spawn(std::future::pending::<()>());
+        let abort_handle = live_task.abort_handle();
+        {
+            let mut subscriptions = registry.lock().await;
+            subscriptions.insert(1, registered("AAPL", live_task));
+            subscriptions.insert(2, registered("MSFT", tokio::spawn(async {})));
+        }
+
+        assert_eq!(cancel_subscriptions(&registry).await, 2);
+
+        assert!(registry.lock().await.is_empty());
+        tokio::task::yield_now().await;
+        assert!(abort_handle.is_finished());
+        assert_eq!(cancel_subscriptions(&registry).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_stream_end_triggers_resubscribe() {
+        let registry: SubscriptionRegistry = Arc::new(Mutex::new(HashMap::new()));
+        let ended_task = tokio::spawn(async {});
+        let failure = failure_for(1, "AAPL", &ended_task);
+        registry
+            .lock()
+            .await
+            .insert(1, registered("AAPL", ended_task));
+
+        let (failure_tx, failure_rx) = mpsc::unbounded_channel();
+        failure_tx.send(failure).unwrap();
+        drop(failure_tx);
+
+        let reconnects = Arc::new(std::sync::Mutex::new(0));
+        let resubscribed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let reconnect_count = reconnects.clone();
+        let resubscribe_log = resubscribed.clone();
+
+        run_reconnect_supervisor(
+            failure_rx,
+            registry.clone(),
+            fast_policy(3),
+            move || {
+                *reconnect_count.lock().unwrap() += 1;
+                std::future::ready(Ok(()))
+            },
+            move |req_id, symbol| {
+                resubscribe_log.lock().unwrap().push((req_id, symbol));
+                tokio::spawn(std::future::pending())
+            },
+        )
+        .await;
+
+        assert_eq!(*reconnects.lock().unwrap(), 1);
+        assert_eq!(*resubscribed.lock().unwrap(), vec![(1, "AAPL".to_string())]);
+        assert!(registry.lock().await.get(&1).unwrap().is_alive());
+    }
+
+    #[tokio::test]
+    async fn test_subscription_error_sends_error_event() {
+        let registry: SubscriptionRegistry = Arc::new(Mutex::new(HashMap::new()));
+        let (tx, mut rx) = mpsc::channel(1);
+        registry.lock().await.insert(
+            7,
+            ActiveSubscription {
+                symbol: "INVALID_SYMBOL_12345".to_string(),
+                tx,
+                task: None,
+            },
+        );
+
+        notify_subscription_error(
+            &registry,
+            7,
+            "INVALID_SYMBOL_12345",
+            "No security definition",
+        )
+        .await;
+
+        match rx.recv().await.unwrap() {
+            MarketDataEvent::Error { symbol, message } => {
+                assert_eq!(symbol, "INVALID_SYMBOL_12345");
+                assert_eq!(message, "No security definition");
+            }
+            MarketDataEvent::Update(update) => {
+                panic!(
+                    "expected an error event, got a tick at {}",
+                    update.last_price
+                )
+            }
+        }
+
+        // Unknown subscriptions are ignored
+        notify_subscription_error(&registry, 8, "MSFT", "gone").await;
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_live_subscription_not_duplicated() {
+        let registry: SubscriptionRegistry = Arc::new(Mutex::new(HashMap::new()));
+        let ended_task = tokio::spawn(async {});
+        let failure = failure_for(1, "AAPL", &ended_task);
+        let mut subs = registry.lock().await;
+        subs.insert(1, registered("AAPL", ended_task));
+        subs.insert(2, registered("MSFT", tokio::spawn(std::future::pending())));
+        drop(subs);
+
+        let (failure_tx, failure_rx) = mpsc::unbounded_channel();
+        failure_tx.send(failure).unwrap();
+        drop(failure_tx);
+
+        let resubscribed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let resubscribe_log = resubscribed.clone();
+
+        run_reconnect_supervisor(
+            failure_rx,
+            registry,
+            fast_policy(3),
+            || std::future::ready(Ok(())),
+            move |req_id, symbol| {
+                resubscribe_log.lock().unwrap().push((req_id, symbol));
+                tokio::spawn(async {})
+            },
+        )
+        .await;
+
+        assert_eq!(*resubscribed.lock().unwrap(), vec![(1, "AAPL".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_gives_up_after_max_retries() {
+        let registry: SubscriptionRegistry = Arc::new(Mutex::new(HashMap::new()));
+        let ended_task = tokio::spawn(async {});
+        let failure = failure_for(1, "AAPL", &ended_task);
+        registry
+            .lock()
+            .await
+            .insert(1, registered("AAPL", ended_task));
+
+        let (failure_tx, failure_rx) = mpsc::unbounded_channel();
+        failure_tx.send(failure).unwrap();
+        drop(failure_tx);
+
+        let attempts = Arc::new(std::sync::Mutex::new(0));
+        let attempt_count = attempts.clone();
+
+        run_reconnect_supervisor(
+            failure_rx,
+            registry.clone(),
+            fast_policy(3),
+            move || {
+                *attempt_count.lock().unwrap() += 1;
+                std::future::ready(Err(anyhow::anyhow!("connection refused")))
+            },
+            |_, _| panic!("must not resubscribe without a connection"),
+        )
+        .await;
+
+        assert_eq!(*attempts.lock().unwrap(), 3);
+        assert!(registry.lock().await.is_empty());
+    }
+
+    #[test]
+    fn test_dry_run_bracket_gets_synthetic_ids() {
+        let dry_run = DryRunOrders::new();
+        let contract = Contract::stock("AAPL");
+        let orders =
+            EnhancedOrderBuilder::bracket_order(OrderAction::Buy, 100.0, 150.0, 160.0, 145.0);
+        let mut submitted = Vec::new();
+
+        let order_ids = submit_bracket(
+            orders,
+            || dry_run.next_order_id(),
+            |order_id, order| {
+                dry_run.record(order_id, &contract, order);
+                submitted.push((order_id, order.parent_id, order.transmit));
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(order_ids.len(), 3);
+        assert!(order_ids.windows(2).all(|ids| ids[0] < ids[1]));
+        assert_eq!(order_ids[0], DRY_RUN_FIRST_ORDER_ID);
+
+        // Children reference the parent and only the last one transmits
+        let parent_id = order_ids[0];
+        assert_eq!(
+            submitted,
+            vec![
+                (parent_id, 0, false),
+                (order_ids[1], parent_id, false),
+                (order_ids[2], parent_id, true),
+            ]
+        );
+
+        // Later orders keep counting up
+        assert_eq!(dry_run.next_order_id(), order_ids[2] + 1);
+    }
+
+    #[test]
+    fn test_calendar_spread_combo_contract() {
+        let spread = SpreadContract::calendar(
+            "ES",
+            "CME",
+            "USD",
+            (495512563, "202503"),
+            (551601561, "202506"),
+        )
+        .unwrap();
+        let contract = TwsClient::spread_contract(&spread);
+
+        assert_eq!(
+            contract.security_type,
+            ibapi::contracts::SecurityType::Spread
+        );
+        assert_eq!(contract.symbol, "ES");
+        assert_eq!(contract.exchange, "CME");
+        assert_eq!(contract.combo_legs.len(), 2);
+
+        let near = &contract.combo_legs[0];
+        assert_eq!(near.contract_id, 495512563);
+        assert_eq!(near.ratio, 1);
+        assert_eq!(near.action, "BUY");
+        assert_eq!(near.exchange, "CME");
+
+        let far = &contract.combo_legs[1];
+        assert_eq!(far.contract_id, 551601561);
+        assert_eq!(far.ratio, 1);
+        assert_eq!(far.action, "SELL");
+    }
+
+    #[test]
+    fn test_opening_time_walks_back_through_executions() {
+        let eastern = FixedOffset::west_opt(5 * 3600).unwrap();
+        let at = |time: &str| parse_execution_time(time, eastern).unwrap();
+        assert_eq!(
+            at("20240304  09:45:00 US/Eastern"),
+            Utc.with_ymd_and_hms(2024, 3, 4, 14, 45, 0).unwrap()
+        );
+        assert!(parse_execution_time("09:45", eastern).is_none());
+
+        let day_start = at("20240304 00:00:00");
+        let fills = [
+            (-50.0, at("20240304 09:35:00")),
+            (150.0, at("20240304 10:00:00")), // Flips the short to 100 long
+            (20.0, at("20240304 11:00:00")),
+            (-20.0, at("20240304 12:00:00")),
+        ];
+        assert_eq!(opening_time(100.0, &fills, day_start), fills[1].1);
+        assert_eq!(opening_time(-50.0, &fills[..1], day_start), fills[0].1);
+        // Trims today leave a position opened before them
+        assert_eq!(opening_time(80.0, &fills[3..], day_start), day_start);
+        assert_eq!(opening_time(100.0, &[], day_start), day_start);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_order_rate_limiter_paces_submissions() {
+        let limiter = OrderRateLimiter::new(100.0);
+        let start = tokio::time::Instant::now();
+
+        // The first order goes straight out; the other 20 wait 10ms each
+        for _ in 0..21 {
+            limiter.acquire().await;
+        }
+        assert_eq!(start.elapsed(), Duration::from_millis(200));
+
+        // An idle spell refills the bucket, but only to a single token
+        tokio::time::advance(Duration::from_secs(1)).await;
+        let resumed = tokio::time::Instant::now();
+        limiter.acquire().await;
+        assert_eq!(resumed.elapsed(), Duration::ZERO);
+        limiter.acquire().await;
+        assert_eq!(resumed.elapsed(), Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_trading_hours_follow_security_config() {
+        use crate::config::SecurityConfigBuilder;
+
+        let regular = SecurityConfigBuilder::stock("AAPL").build().unwrap();
+        let extended = SecurityConfigBuilder::stock("TSLA")
+            .extended_hours_data(true)
+            .outside_rth_orders(true)
+            .build()
+            .unwrap();
+
+        // Data: RTH only unless the symbol opts into extended hours
+        assert!(data_use_rth(None));
+        assert!(data_use_rth(Some(&regular)));
+        assert!(!data_use_rth(Some(&extended)));
+
+        // Orders: outside RTH when the symbol or the order asks for it
+        assert!(!order_outside_rth(None, false));
+        assert!(!order_outside_rth(Some(&regular), false));
+        assert!(order_outside_rth(Some(&regular), true));
+        assert!(order_outside_rth(Some(&extended), false));
+    }
+
+    #[test]
+    fn test_order_status_from_tws() {
+        assert_eq!(
+            order_status_from_tws("Filled", 100.0, 0.0),
+            OrderStatus::Filled
+        );
+        assert_eq!(
+            order_status_from_tws("ApiCancelled", 0.0, 100.0),
+            OrderStatus::Cancelled
+        );
+        assert_eq!(
+            order_status_from_tws("Inactive", 0.0, 100.0),
+            OrderStatus::Rejected
+        );
+        assert_eq!(
+            order_status_from_tws("PendingSubmit", 0.0, 100.0),
+            OrderStatus::Pending
+        );
+        assert_eq!(
+            order_status_from_tws("Submitted", 0.0, 100.0),
+            OrderStatus::Submitted
+        );
+        assert_eq!(
+            order_status_from_tws("Submitted", 40.0, 60.0),
+            OrderStatus::PartiallyFilled {
+                filled_qty: 40.0,
+                remaining_qty: 60.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_bar_quote_marks_approximations() {
+        let mut handler = MarketDataHandler::new();
+        handler.register_security(
+            "EUR.USD".to_string(),
+            crate::security_types::SecurityInfo::new_forex(
+                "EUR.USD".to_string(),
+                "IDEALPRO".to_string(),
+                "USD".to_string(),
+            ),
+        );
+        handler.register_symbol(1, "EUR.USD".to_string());
+
+        // No book yet: a one-pip spread around the close, with no quote time
+        let (bid, ask, quoted_at) = bar_quote(&handler, "EUR.USD", 1.0850);
+        assert!((ask - bid - 0.0001).abs() < 1e-12);
+        assert_eq!(quoted_at, None);
+
+        let now = Utc::now();
+        handler.update_quote_at("EUR.USD", 1.0849, 1.0852, now);
+        assert_eq!(
+            bar_quote(&handler, "EUR.USD", 1.0850),
+            (1.0849, 1.0852, Some(now))
+        );
+    }
 }
@@ -0,0 +1,479 @@
+//! Commission and slippage modeling for simulated and expected fills
+//!
+//! Stocks pay a per-share commission with a minimum, futures a per-contract
+//! commission, and forex a notional (basis point) cost reflecting IBKR's
+//! spread-based pricing. Slippage is modeled in basis points of notional;
+//! simulated fills can sample it around that mean from a seeded `RngSource`.
+
+use crate::config::{SecurityConfig, security_id};
+use crate::rng::RngSource;
+use crate::security_types::SecurityType;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Cost parameters for one security type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstrumentCosts {
+    #[serde(default)]
+    pub commission_per_unit: f64, // Per share or per contract
+    #[serde(default)]
+    pub commission_bps: f64, // Basis points of notional
+    #[serde(default)]
+    pub min_commission: f64, // Minimum charged per order
+    #[serde(default)]
+    pub slippage_bps: f64, // Expected adverse fill in basis points of notional
+    #[serde(default)]
+    pub slippage_std_bps: f64, // Spread of sampled slippage around slippage_bps
+}
+
+/// Per-security-type cost configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostModelConfig {
+    #[serde(default = "default_stock_costs")]
+    pub stock: InstrumentCosts,
+    #[serde(default = "default_future_costs")]
+    pub future: InstrumentCosts,
+    #[serde(default = "default_forex_costs")]
+    pub forex: InstrumentCosts,
+    #[serde(default)]
+    pub max_commission_fraction: Option<f64>, // Skip new trades whose commission exceeds this fraction of notional
+}
+
+impl Default for CostModelConfig {
+    fn default() -> Self {
+        Self {
+            stock: default_stock_costs(),
+            future: default_future_costs(),
+            forex: default_forex_costs(),
+            max_commission_fraction: None,
+        }
+    }
+}
+
+fn default_stock_costs() -> InstrumentCosts {
+    InstrumentCosts {
+        commission_per_unit: 0.005, // IBKR fixed $0.005 per share
+        commission_bps: 0.0,
+        min_commission: 1.00, // $1.00 minimum per order
+        slippage_bps: 5.0,
+        slippage_std_bps: 2.0,
+    }
+}
+
+fn default_future_costs() -> InstrumentCosts {
+    InstrumentCosts {
+        commission_per_unit: 2.50, // $2.50 per contract
+        commission_bps: 0.0,
+        min_commission: 0.0,
+        slippage_bps: 2.0,
+        slippage_std_bps: 1.0,
+    }
+}
+
+fn default_forex_costs() -> InstrumentCosts {
+    InstrumentCosts {
+        commission_per_unit: 0.0,
+        commission_bps: 0.2, // 0.2 bps of notional
+        min_commission: 2.00,
+        slippage_bps: 0.5, // Roughly half the typical major-pair spread
+        slippage_std_bps: 0.25,
+    }
+}
+
+/// Expected execution of an order after costs
+#[derive(Debug, Clone)]
+pub struct ExpectedFill {
+    pub fill_price: f64,
+    pub commission: f64,
+    pub slippage: f64,
+}
+
+impl ExpectedFill {
+    pub fn total_cost(&self) -> f64 {
+        self.commission + self.slippage
+    }
+}
+
+/// One security's commission terms, replacing its type's where set
+#[derive(Debug, Clone, Copy, Default)]
+struct CommissionOverride {
+    per_unit: Option<f64>,
+    min: Option<f64>,
+}
+
+/// Commission and slippage model
+#[derive(Debug, Clone)]
+pub struct CostModel {
+    config: CostModelConfig,
+    multipliers: HashMap<String, f64>, // Contract multipliers for futures notional
+    commission_overrides: HashMap<String, CommissionOverride>,
+    rng: Option<RngSource>, // Samples slippage for simulated fills
+}
+
+impl CostModel {
+    pub fn new(config: CostModelConfig) -> Self {
+        Self {
+            config,
+            multipliers: HashMap::new(),
+            commission_overrides: HashMap::new(),
+            rng: None,
+        }
+    }
+
+    /// Create a cost model using the multipliers and commission overrides of
+    /// the configured securities
+    pub fn for_securities(config: CostModelConfig, securities: &[SecurityConfig]) -> Self {
+        securities
+            .iter()
+            .fold(Self::new(config), |mut model, security| {
+                let id = security_id(securities, security);
+                if let Some(multiplier) = security.contract_multiplier() {
+                    model = model.with_multiplier(&id, multiplier);
+                }
+                if security.commission_per_unit.is_some() || security.commission_min.is_some() {
+                    model = model.with_commission_override(
+                        &id,
+                        security.commission_per_unit,
+                        security.commission_min,
+                    );
+                }
+                model
+            })
+    }
+
+    /// Set the contract multiplier used to compute a symbol's notional
+    pub fn with_multiplier(mut self, symbol: &str, multiplier: f64) -> Self {
+        self.multipliers.insert(symbol.to_string(), multiplier);
+        self
+    }
+
+    /// Charge `symbol` its own per-unit rate and/or minimum; None keeps the
+    /// security type's. Basis point commission still applies.
+    pub fn with_commission_override(
+        mut self,
+        symbol: &str,
+        per_unit: Option<f64>,
+        min: Option<f64>,
+    ) -> Self {
+        self.commission_overrides
+            .insert(symbol.to_string(), CommissionOverride { per_unit, min });
+        self
+    }
+
+    /// Sample simulated slippage from `rng` instead of always using the mean
+    pub fn with_rng(mut self, rng: RngSource) -> Self {
+        self.rng = Some(rng);
+        self
+    }
+
+    fn costs_for(&self, security_type: &SecurityType) -> &InstrumentCosts {
+        match security_type {
+            SecurityType::Stock => &self.config.stock,
+            SecurityType::Future => &self.config.future,
+            SecurityType::Forex => &self.config.forex,
+        }
+    }
+
+    fn notional(&self, symbol: &str, quantity: f64, price: f64) -> f64 {
+        let multiplier = self.multipliers.get(symbol).copied().unwrap_or(1.0);
+        quantity.abs() * price * multiplier
+    }
+
+    /// Commission charged for one order
+    pub fn commission_for(
+        &self,
+        symbol: &str,
+        security_type: &SecurityType,
+        quantity: f64,
+        price: f64,
+    ) -> f64 {
+        if quantity == 0.0 {
+            return 0.0;
+        }
+
+        let costs = self.costs_for(security_type);
+        let terms = self
+            .commission_overrides
+            .get(symbol)
+            .copied()
+            .unwrap_or_default();
+        let per_unit = terms.per_unit.unwrap_or(costs.commission_per_unit);
+        let min_commission = terms.min.unwrap_or(costs.min_commission);
+        let commission = per_unit * quantity.abs()
+            + self.notional(symbol, quantity, price) * costs.commission_bps / 10_000.0;
+        commission.max(min_commission)
+    }
+
+    /// Whether commission would eat more than `max_commission_fraction` of an
+    /// order's notional; always false when no cap is configured
+    pub fn commission_too_high(
+        &self,
+        symbol: &str,
+        security_type: &SecurityType,
+        quantity: f64,
+        price: f64,
+    ) -> bool {
+        let Some(max_fraction) = self.config.max_commission_fraction else {
+            return false;
+        };
+        let commission = self.commission_for(symbol, security_type, quantity, price);
+        commission > max_fraction * self.notional(symbol, quantity, price)
+    }
+
+    /// Expected slippage cost in currency for one order
+    pub fn slippage_for(
+        &self,
+        symbol: &str,
+        security_type: &SecurityType,
+        quantity: f64,
+        price: f64,
+    ) -> f64 {
+        let costs = self.costs_for(security_type);
+        self.notional(symbol, quantity, price) * costs.slippage_bps / 10_000.0
+    }
+
+    /// Expected fill for an order: price moved against us by slippage, plus commission
+    ///
+    /// Positive quantity buys, negative quantity sells.
+    pub fn expected_fill(
+        &self,
+        symbol: &str,
+        security_type: &SecurityType,
+        quantity: f64,
+        price: f64,
+    ) -> ExpectedFill {
+        let slippage_bps = self.costs_for(security_type).slippage_bps;
+        self.fill_with_slippage(symbol, security_type, quantity, price, slippage_bps)
+    }
+
+    /// Slippage in basis points for one simulated fill
+    ///
+    /// Normal around `slippage_bps` with `slippage_std_bps` spread, floored at
+    /// zero; the mean when no `RngSource` is set.
+    pub fn sample_slippage_bps(&self, security_type: &SecurityType) -> f64 {
+        let costs = self.costs_for(security_type);
+        match &self.rng {
+            Some(rng) => {
+                (costs.slippage_bps + costs.slippage_std_bps * rng.standard_normal()).max(0.0)
+            }
+            None => costs.slippage_bps,
+        }
+    }
+
+    /// Fill for a simulated order, with slippage drawn by `sample_slippage_bps`
+    pub fn simulated_fill(
+        &self,
+        symbol: &str,
+        security_type: &SecurityType,
+        quantity: f64,
+        price: f64,
+    ) -> ExpectedFill {
+        let slippage_bps = self.sample_slippage_bps(security_type);
+        self.fill_with_slippage(symbol, security_type, quantity, price, slippage_bps)
+    }
+
+    fn fill_with_slippage(
+        &self,
+        symbol: &str,
+        security_type: &SecurityType,
+        quantity: f64,
+        price: f64,
+        slippage_bps: f64,
+    ) -> ExpectedFill {
+        let slippage_rate = slippage_bps / 10_000.0;
+        let fill_price = if quantity >= 0.0 {
+            price * (1.0 + slippage_rate)
+        } else {
+            price * (1.0 - slippage_rate)
+        };
+
+        ExpectedFill {
+            fill_price,
+            commission: self.commission_for(symbol, security_type, quantity, price),
+            slippage: self.notional(symbol, quantity, price) * slippage_rate,
+        }
+    }
+
+    /// Net P&L of opening at `entry_price` and closing at `exit_price`
+    ///
+    /// Positive quantity is a long round trip, negative a short one. Both legs
+    /// pay commission and slippage.
+    pub fn round_trip_net_pnl(
+        &self,
+        symbol: &str,
+        security_type: &SecurityType,
+        quantity: f64,
+        entry_price: f64,
+        exit_price: f64,
+    ) -> f64 {
+        let entry = self.expected_fill(symbol, security_type, quantity, entry_price);
+        let exit = self.expected_fill(symbol, security_type, -quantity, exit_price);
+        let multiplier = self.multipliers.get(symbol).copied().unwrap_or(1.0);
+
+        (exit.fill_price - entry.fill_price) * quantity * multiplier
+            - entry.commission
+            - exit.commission
+    }
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self::new(CostModelConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_slippage_model() -> CostModel {
+        let mut config = CostModelConfig::default();
+        config.stock.slippage_bps = 0.0;
+        config.future.slippage_bps = 0.0;
+        config.forex.slippage_bps = 0.0;
+        CostModel::new(config)
+    }
+
+    #[test]
+    fn test_round_trip_deducts_both_commissions() {
+        let model = no_slippage_model();
+
+        // 1000 shares: $5.00 per leg
+        let net = model.round_trip_net_pnl("AAPL", &SecurityType::Stock, 1000.0, 100.0, 101.0);
+        assert!((net - (1000.0 - 10.0)).abs() < 1e-9);
+
+        // 2 ES contracts: $5.00 per leg
+        let model = model.with_multiplier("ES", 50.0);
+        let net = model.round_trip_net_pnl("ES", &SecurityType::Future, 2.0, 5000.0, 5010.0);
+        assert!((net - (1000.0 - 10.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stock_minimum_commission() {
+        let model = CostModel::default();
+        assert_eq!(
+            model.commission_for("AAPL", &SecurityType::Stock, 10.0, 150.0),
+            1.0
+        );
+        assert_eq!(
+            model.commission_for("AAPL", &SecurityType::Stock, 0.0, 150.0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_futures_commission_per_contract() {
+        let model = CostModel::default().with_multiplier("ES", 50.0);
+        assert_eq!(
+            model.commission_for("ES", &SecurityType::Future, -4.0, 5000.0),
+            10.0
+        );
+    }
+
+    #[test]
+    fn test_forex_notional_commission() {
+        let model = CostModel::default();
+        // 1,000,000 EUR at 1.10 = $1.1M notional * 0.2 bps = $22
+        let commission = model.commission_for("EUR.USD", &SecurityType::Forex, 1_000_000.0, 1.10);
+        assert!((commission - 22.0).abs() < 1e-9);
+        // Small trades pay the minimum
+        assert_eq!(
+            model.commission_for("EUR.USD", &SecurityType::Forex, 1000.0, 1.10),
+            2.0
+        );
+    }
+
+    #[test]
+    fn test_slippage_moves_fill_against_order() {
+        let model = CostModel::default();
+        let buy = model.expected_fill("AAPL", &SecurityType::Stock, 100.0, 100.0);
+        let sell = model.expected_fill("AAPL", &SecurityType::Stock, -100.0, 100.0);
+
+        assert!(buy.fill_price > 100.0);
+        assert!(sell.fill_price < 100.0);
+        assert!((buy.slippage - 5.0).abs() < 1e-9);
+        assert!((buy.total_cost() - 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_commission_cap() {
+        let capped = CostModel::new(CostModelConfig {
+            max_commission_fraction: Some(0.01),
+            ..CostModelConfig::default()
+        });
+        // $1 minimum commission: 5% of $20, 0.05% of $2,000
+        assert!(capped.commission_too_high("AAPL", &SecurityType::Stock, 1.0, 20.0));
+        assert!(!capped.commission_too_high("AAPL", &SecurityType::Stock, 20.0, 100.0));
+
+        let uncapped = CostModel::new(CostModelConfig::default());
+        assert!(!uncapped.commission_too_high("AAPL", &SecurityType::Stock, 1.0, 20.0));
+    }
+
+    #[test]
+    fn test_same_seed_samples_same_slippage() {
+        let sample = |seed: u64| -> Vec<f64> {
+            let model = CostModel::default().with_rng(RngSource::from_seed(seed));
+            (0..50)
+                .map(|_| {
+                    model
+                        .simulated_fill("AAPL", &SecurityType::Stock, 100.0, 100.0)
+                        .fill_price
+                })
+                .collect()
+        };
+
+        let first = sample(42);
+        assert_eq!(first, sample(42));
+        assert_ne!(first, sample(43));
+        assert!(first.iter().all(|price| *price >= 100.0));
+        assert!(first.windows(2).any(|pair| pair[0] != pair[1]));
+
+        // Without an RngSource, simulated fills use the mean slippage
+        let model = CostModel::default();
+        let simulated = model.simulated_fill("AAPL", &SecurityType::Stock, 100.0, 100.0);
+        let expected = model.expected_fill("AAPL", &SecurityType::Stock, 100.0, 100.0);
+        assert_eq!(simulated.fill_price, expected.fill_price);
+    }
+
+    #[test]
+    fn test_security_overrides_change_round_trip_cost() {
+        use crate::config::{FuturesSpecs, SecurityConfigBuilder};
+
+        let mut config = CostModelConfig::default();
+        config.stock.slippage_bps = 0.0;
+        config.future.slippage_bps = 0.0;
+        let micro_es = FuturesSpecs {
+            underlying: "MES".to_string(),
+            expiry: "20240315".to_string(),
+            multiplier: 50.0,
+            tick_size: 0.25,
+            contract_month: "202403".to_string(),
+        };
+        let securities = vec![
+            SecurityConfigBuilder::stock("AAPL").build().unwrap(),
+            SecurityConfigBuilder::stock("TSLA")
+                .commission(0.01, 2.0)
+                .build()
+                .unwrap(),
+            SecurityConfigBuilder::future("MES")
+                .futures_specs(micro_es)
+                .multiplier(5.0)
+                .build()
+                .unwrap(),
+        ];
+        let model = CostModel::for_securities(config, &securities);
+        let round_trip_cost = |symbol: &str, quantity: f64| {
+            -model.round_trip_net_pnl(symbol, &SecurityType::Stock, quantity, 100.0, 100.0)
+        };
+
+        // $0.005/share with a $1 minimum vs $0.01/share with a $2 minimum
+        assert!((round_trip_cost("AAPL", 1000.0) - 10.0).abs() < 1e-9);
+        assert!((round_trip_cost("TSLA", 1000.0) - 20.0).abs() < 1e-9);
+        assert!((round_trip_cost("AAPL", 100.0) - 2.0).abs() < 1e-9);
+        assert!((round_trip_cost("TSLA", 100.0) - 4.0).abs() < 1e-9);
+
+        // The multiplier override, not the futures spec, scales P&L
+        let net = model.round_trip_net_pnl("MES", &SecurityType::Future, 2.0, 5000.0, 5010.0);
+        assert!((net - (100.0 - 10.0)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,72 @@
+//! What became of each candidate signal
+//!
+//! The strategy and the trading cycle record a `SignalDecision` per symbol:
+//! either the signal was acted on, or the filter that stopped it and why. The
+//! set explains a cycle that placed no orders without digging through debug
+//! logs, and can be written to the journal.
+
+use crate::signals::core::SignalFilter;
+use serde::{Deserialize, Serialize};
+
+/// The filter that stopped a signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilteredBy {
+    Consensus,       // Signal types or momentum timeframes disagreed on direction
+    Quality,         // Combined strength below the quality threshold
+    TransactionCost, // Position inertia or expected trading cost
+    Exposure,        // Portfolio over-exposed, only reductions processed
+    Commission,      // Commission too large a share of notional
+    TradingHalt,     // Daily loss halt allows only risk reduction
+    Drawdown,        // Drawdown de-risking blocks entries until recovery
+    Cooldown,        // Re-entry cooldown after a stop-out
+    ShortBorrow,     // Short sale without a borrow
+    RiskLimit,       // Position limits in RiskManager
+    HoldingPeriod,   // Position younger than the minimum holding period
+    Correlation,     // Diversification score or correlated-cluster cap
+    Margin,          // Margin validation when creating the order
+    Broker,          // The broker refused the order
+    WorkingOrder,    // An earlier order for the symbol is still working
+    Liquidity,       // Spread too wide or volume too thin to enter
+    PairedLeg,       // Another leg of the same pair was filtered
+}
+
+impl FilteredBy {
+    /// The coordinator filter that rejected a combined signal, if any
+    pub fn from_signal_filter(filter: &SignalFilter) -> Option<Self> {
+        match filter {
+            SignalFilter::Passed => None,
+            SignalFilter::NoSignals | SignalFilter::BelowQuality { .. } => Some(Self::Quality),
+            SignalFilter::InsufficientConsensus { .. } => Some(Self::Consensus),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignalDecision {
+    pub symbol: String,
+    pub filtered_by: Option<FilteredBy>, // None when the signal was acted on
+    pub detail: String,
+}
+
+impl SignalDecision {
+    pub fn acted(symbol: &str, detail: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            filtered_by: None,
+            detail: detail.into(),
+        }
+    }
+
+    pub fn filtered(symbol: &str, filtered_by: FilteredBy, detail: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            filtered_by: Some(filtered_by),
+            detail: detail.into(),
+        }
+    }
+
+    pub fn is_acted(&self) -> bool {
+        self.filtered_by.is_none()
+    }
+}
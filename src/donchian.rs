@@ -0,0 +1,209 @@
+//! Classic Donchian channels
+//!
+//! The upper band is the highest close of the `period` bars before the
+//! current one, the lower band the lowest, and the mid-line halfway between.
+//! A close at or beyond a band is a breakout to a new `period`-bar high or
+//! low. Following Carver's breakout rule, the signal is
+//! `40 * (price - mid) / (upper - lower)` capped to -20..+20, so touching a
+//! band gives a full-strength signal.
+
+use crate::market_data::MarketDataHandler;
+
+/// Forecast cap, reached when price touches a band
+const MAX_SIGNAL_STRENGTH: f64 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DonchianChannel {
+    pub upper: f64,  // Highest close over the period
+    pub lower: f64,  // Lowest close over the period
+    pub middle: f64, // Halfway between the bands
+}
+
+impl DonchianChannel {
+    fn from_prices(prices: &[f64]) -> Self {
+        let upper = prices.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let lower = prices.iter().copied().fold(f64::INFINITY, f64::min);
+        Self {
+            upper,
+            lower,
+            middle: (upper + lower) / 2.0,
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.upper - self.lower
+    }
+
+    /// Carver-style signal (-20 to +20) for `price` against this channel
+    pub fn signal_strength(&self, price: f64) -> f64 {
+        if self.width() > 0.0 {
+            (40.0 * (price - self.middle) / self.width())
+                .clamp(-MAX_SIGNAL_STRENGTH, MAX_SIGNAL_STRENGTH)
+        } else if price > self.upper {
+            MAX_SIGNAL_STRENGTH
+        } else if price < self.lower {
+            -MAX_SIGNAL_STRENGTH
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DonchianSignalType {
+    UpperBreakout, // Close at or above the upper band
+    LowerBreakout, // Close at or below the lower band
+    Inside,        // Close within the channel
+}
+
+#[derive(Debug, Clone)]
+pub struct DonchianSignal {
+    pub symbol: String,
+    pub current_price: f64,
+    pub channel: DonchianChannel,
+    pub signal_type: DonchianSignalType,
+    pub signal_strength: f64, // Carver-style -20 to +20
+}
+
+#[derive(Debug, Clone)]
+pub struct DonchianCalculator {
+    pub period: usize, // Bars in the channel (default 20)
+}
+
+impl Default for DonchianCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DonchianCalculator {
+    pub fn new() -> Self {
+        Self { period: 20 }
+    }
+
+    pub fn with_period(period: usize) -> Self {
+        Self { period }
+    }
+
+    /// Channel over the last `period` prices, or None with fewer prices
+    pub fn calculate_channel(&self, prices: &[f64]) -> Option<DonchianChannel> {
+        if self.period == 0 || prices.len() < self.period {
+            return None;
+        }
+        Some(DonchianChannel::from_prices(
+            &prices[prices.len() - self.period..],
+        ))
+    }
+
+    /// Channel each price was compared against, index-aligned with `prices`
+    ///
+    /// Entry `i` covers `prices[i - period..i]`; the first `period` entries
+    /// are None. Intended for plotting the bands alongside the series.
+    pub fn channel_series(&self, prices: &[f64]) -> Vec<Option<DonchianChannel>> {
+        (0..prices.len())
+            .map(|i| self.calculate_channel(&prices[..i]))
+            .collect()
+    }
+
+    /// Signal for the latest close in `symbol`'s price history
+    pub fn calculate_signal(
+        &self,
+        symbol: &str,
+        market_data: &MarketDataHandler,
+    ) -> Option<DonchianSignal> {
+        let history = market_data.get_price_history(symbol)?;
+        let prices: Vec<f64> = history.prices.iter().map(|(_, price)| *price).collect();
+        let (&current_price, previous) = prices.split_last()?;
+        let channel = self.calculate_channel(previous)?;
+
+        let signal_type = if current_price >= channel.upper {
+            DonchianSignalType::UpperBreakout
+        } else if current_price <= channel.lower {
+            DonchianSignalType::LowerBreakout
+        } else {
+            DonchianSignalType::Inside
+        };
+
+        Some(DonchianSignal {
+            symbol: symbol.to_string(),
+            current_price,
+            channel,
+            signal_type,
+            signal_strength: channel.signal_strength(current_price),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market_data(symbol: &str, prices: &[f64]) -> MarketDataHandler {
+        let mut handler = MarketDataHandler::new();
+        handler.register_symbol(1, symbol.to_string());
+        for (day, price) in prices.iter().enumerate() {
+            let timestamp =
+                time::OffsetDateTime::from_unix_timestamp(1_700_000_000 + day as i64 * 86_400)
+                    .unwrap();
+            handler.add_historical_price(symbol, timestamp, *price);
+        }
+        handler
+    }
+
+    /// Oscillates between 90 and 110 for 20 days, then rallies to 112
+    fn series() -> Vec<f64> {
+        let mut prices: Vec<f64> = (0..20)
+            .map(|day| 100.0 + 10.0 * (day as f64 * std::f64::consts::FRAC_PI_2).sin())
+            .collect();
+        prices.push(112.0);
+        prices
+    }
+
+    #[test]
+    fn test_channel_bands() {
+        let calculator = DonchianCalculator::new();
+        let channel = calculator.calculate_channel(&series()[..20]).unwrap();
+        assert!((channel.upper - 110.0).abs() < 1e-9);
+        assert!((channel.lower - 90.0).abs() < 1e-9);
+        assert!((channel.middle - 100.0).abs() < 1e-9);
+        assert!((channel.width() - 20.0).abs() < 1e-9);
+
+        assert!(calculator.calculate_channel(&series()[..19]).is_none());
+
+        let bands = calculator.channel_series(&series());
+        assert_eq!(bands.len(), 21);
+        assert!(bands[..20].iter().all(Option::is_none));
+        assert_eq!(bands[20], Some(channel));
+    }
+
+    #[test]
+    fn test_breakout_at_twenty_day_high() {
+        let calculator = DonchianCalculator::new();
+        let signal = calculator
+            .calculate_signal("ES", &market_data("ES", &series()))
+            .unwrap();
+        assert_eq!(signal.signal_type, DonchianSignalType::UpperBreakout);
+        assert_eq!(signal.signal_strength, 20.0);
+        assert_eq!(signal.current_price, 112.0);
+
+        // Halfway between mid-line and upper band: half strength
+        let mut prices = series();
+        *prices.last_mut().unwrap() = 105.0;
+        let signal = calculator
+            .calculate_signal("ES", &market_data("ES", &prices))
+            .unwrap();
+        assert_eq!(signal.signal_type, DonchianSignalType::Inside);
+        assert!((signal.signal_strength - 10.0).abs() < 1e-9);
+
+        // Touching the lower band is a full-strength short
+        *prices.last_mut().unwrap() = 90.0;
+        let signal = calculator
+            .calculate_signal("ES", &market_data("ES", &prices))
+            .unwrap();
+        assert_eq!(signal.signal_type, DonchianSignalType::LowerBreakout);
+        assert_eq!(signal.signal_strength, -20.0);
+
+        let short = market_data("ES", &series()[..20]);
+        assert!(calculator.calculate_signal("ES", &short).is_none());
+    }
+}
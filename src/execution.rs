@@ -0,0 +1,569 @@
+//! Execution algorithms for working large orders over time
+//!
+//! A single large market order moves the price against us. `TwapExecutor`
+//! splits it into equal child orders spread evenly over a time window, and
+//! stops early if the market runs away from the signal price.
+//!
+//! A passive limit order can sit unfilled while the price runs away.
+//! `OrderChaser` watches a working limit order and, each time it stays
+//! unfilled for a timeout, replaces it one step closer to the market until a
+//! maximum chase distance, optionally finishing with a market order.
+
+use crate::broker::Broker;
+use crate::config::OrderChaseConfig;
+use crate::connection::TwsClient;
+use crate::orders::{OrderSignal, OrderStatus};
+use anyhow::{Context, Result, anyhow};
+use log::{info, warn};
+use std::future::Future;
+use std::time::Duration;
+
+/// Source of delays between child orders, replaceable in tests
+pub trait Sleeper {
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()>;
+}
+
+/// Sleeps on the tokio timer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioSleeper;
+
+impl Sleeper for TokioSleeper {
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()> {
+        tokio::time::sleep(duration)
+    }
+}
+
+/// Time-weighted average price execution
+#[derive(Debug, Clone)]
+pub struct TwapExecutor<S: Sleeper = TokioSleeper> {
+    sleeper: S,
+    price_band: Option<f64>, // Max fractional move from signal price before stopping
+}
+
+impl Default for TwapExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TwapExecutor {
+    pub fn new() -> Self {
+        Self::with_sleeper(TokioSleeper)
+    }
+}
+
+impl<S: Sleeper> TwapExecutor<S> {
+    pub fn with_sleeper(sleeper: S) -> Self {
+        Self {
+            sleeper,
+            price_band: None,
+        }
+    }
+
+    /// Stop placing slices once price moves more than `band` (e.g. 0.01 = 1%)
+    /// away from the signal price
+    pub fn with_price_band(mut self, band: f64) -> Self {
+        self.price_band = Some(band);
+        self
+    }
+
+    /// Split `signal` into `slices` child orders placed evenly over `total_duration`
+    ///
+    /// Returns the TWS order ids of the slices actually placed.
+    pub async fn execute(
+        &self,
+        client: &TwsClient,
+        signal: &OrderSignal,
+        slices: usize,
+        total_duration: Duration,
+    ) -> Result<Vec<i32>> {
+        self.execute_with(
+            signal,
+            slices,
+            total_duration,
+            async |slice: OrderSignal| client.place_order(&slice).await,
+            async || {
+                client
+                    .market_data_handler
+                    .lock()
+                    .await
+                    .get_market_data(&signal.symbol)
+                    .map(|data| data.last_price)
+            },
+        )
+        .await
+    }
+
+    /// TWAP schedule with injectable order submission and price lookup
+    pub async fn execute_with(
+        &self,
+        signal: &OrderSignal,
+        slices: usize,
+        total_duration: Duration,
+        mut submit: impl AsyncFnMut(OrderSignal) -> Result<i32>,
+        mut current_price: impl AsyncFnMut() -> Option<f64>,
+    ) -> Result<Vec<i32>> {
+        let quantities = slice_quantities(signal.quantity, slices);
+        let interval = total_duration / quantities.len().max(1) as u32;
+        let mut order_ids = Vec::with_capacity(quantities.len());
+
+        info!(
+            "TWAP {} {} {} in {} slices every {:?}",
+            signal.action,
+            signal.quantity,
+            signal.symbol,
+            quantities.len(),
+            interval
+        );
+
+        for (index, &quantity) in quantities.iter().enumerate() {
+            if index > 0 {
+                self.sleeper.sleep(interval).await;
+
+                let price = current_price().await;
+                if let Some(price) = price.filter(|&p| self.outside_band(signal.price, p)) {
+                    warn!(
+                        "TWAP for {} stopped after {} of {} slices: price {:.4} moved beyond band from {:.4}",
+                        signal.symbol,
+                        index,
+                        quantities.len(),
+                        price,
+                        signal.price
+                    );
+                    break;
+                }
+            }
+
+            let slice = OrderSignal {
+                quantity,
+                reason: format!(
+                    "{} (TWAP slice {}/{})",
+                    signal.reason,
+                    index + 1,
+                    quantities.len()
+                ),
+                ..signal.clone()
+            };
+            let order_id = submit(slice).await.with_context(|| {
+                format!(
+                    "TWAP slice {} for {} failed after placing orders {:?}",
+                    index + 1,
+                    signal.symbol,
+                    order_ids
+                )
+            })?;
+            order_ids.push(order_id);
+        }
+
+        Ok(order_ids)
+    }
+
+    fn outside_band(&self, reference_price: f64, price: f64) -> bool {
+        match self.price_band {
+            Some(band) if reference_price > 0.0 => (price / reference_price - 1.0).abs() > band,
+            _ => false,
+        }
+    }
+}
+
+/// How patiently and how far to chase an unfilled limit order
+#[derive(Debug, Clone)]
+pub struct ChaseConfig {
+    pub timeout: Duration,       // Time each price gets before repricing
+    pub poll_interval: Duration, // Time between order status checks
+    pub step: f64,               // Reprice step as a fraction of the original limit
+    pub max_chase: f64,          // Max fractional distance from the original limit
+    pub convert_to_market: bool, // Send a market order once the chase is exhausted
+}
+
+impl From<&OrderChaseConfig> for ChaseConfig {
+    fn from(config: &OrderChaseConfig) -> Self {
+        Self {
+            timeout: Duration::from_secs(config.timeout_seconds),
+            poll_interval: Duration::from_secs(config.poll_interval_seconds),
+            step: config.step,
+            max_chase: config.max_chase,
+            convert_to_market: config.convert_to_market,
+        }
+    }
+}
+
+impl Default for ChaseConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            poll_interval: Duration::from_secs(5),
+            step: 0.001,
+            max_chase: 0.005,
+            convert_to_market: false,
+        }
+    }
+}
+
+/// How a chase ended
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChaseOutcome {
+    Filled {
+        order_id: i32,
+    },
+    /// Reached the max chase distance; the last order is left working
+    MaxChaseReached {
+        order_id: i32,
+        limit_price: f64,
+    },
+    ConvertedToMarket {
+        order_id: i32,
+    },
+    /// Cancelled or rejected by the broker
+    Stopped {
+        order_id: i32,
+        status: OrderStatus,
+    },
+}
+
+/// Reprices a working limit order toward the market until it fills
+#[derive(Debug, Clone)]
+pub struct OrderChaser<S: Sleeper = TokioSleeper> {
+    sleeper: S,
+    config: ChaseConfig,
+}
+
+impl OrderChaser {
+    pub fn new(config: ChaseConfig) -> Self {
+        Self::with_sleeper(config, TokioSleeper)
+    }
+}
+
+impl<S: Sleeper> OrderChaser<S> {
+    pub fn with_sleeper(config: ChaseConfig, sleeper: S) -> Self {
+        Self { sleeper, config }
+    }
+
+    /// Chase the working limit order `order_id`, placed from `signal`
+    pub async fn chase<B: Broker>(
+        &self,
+        broker: &B,
+        order_id: i32,
+        signal: &OrderSignal,
+    ) -> Result<ChaseOutcome> {
+        self.chase_with(broker, order_id, signal, |_, _| async {})
+            .await
+    }
+
+    /// `chase`, calling `on_replace(old, new)` with the broker IDs each time
+    /// the order is replaced, so the caller can keep tracking it
+    pub async fn chase_with<B: Broker, F: Future<Output = ()>>(
+        &self,
+        broker: &B,
+        order_id: i32,
+        signal: &OrderSignal,
+        mut on_replace: impl FnMut(i32, i32) -> F,
+    ) -> Result<ChaseOutcome> {
+        let original = signal.limit_price.ok_or_else(|| {
+            anyhow!(
+                "Order #{} for {} has no limit price",
+                order_id,
+                signal.symbol
+            )
+        })?;
+        // Buyers chase up, sellers down
+        let direction = if signal.action == "SELL" { -1.0 } else { 1.0 };
+        let mut order_id = order_id;
+        let mut working = signal.clone();
+        let mut reprices = 0;
+
+        loop {
+            match self.wait_for_fill(broker, order_id).await? {
+                OrderStatus::Filled => return Ok(ChaseOutcome::Filled { order_id }),
+                OrderStatus::PartiallyFilled { remaining_qty, .. } => {
+                    working.quantity = remaining_qty;
+                }
+                status if !status.is_active() => {
+                    warn!("Stopped chasing order #{}: {:?}", order_id, status);
+                    return Ok(ChaseOutcome::Stopped { order_id, status });
+                }
+                _ => {}
+            }
+
+            reprices += 1;
+            let offset = reprices as f64 * self.config.step;
+            if offset > self.config.max_chase + 1e-12 {
+                if !self.config.convert_to_market {
+                    info!(
+                        "Order #{} for {} unfilled at max chase {:.2}%, leaving it working",
+                        order_id,
+                        signal.symbol,
+                        self.config.max_chase * 100.0
+                    );
+                    return Ok(ChaseOutcome::MaxChaseReached {
+                        order_id,
+                        limit_price: working.limit_price.unwrap_or(original),
+                    });
+                }
+
+                let market = OrderSignal {
+                    order_type: "MKT".to_string(),
+                    limit_price: None,
+                    reason: format!("{} (chase to market)", signal.reason),
+                    ..working
+                };
+                let Some(market_id) = broker.replace_order(order_id, &market).await? else {
+                    return Ok(ChaseOutcome::Filled { order_id });
+                };
+                on_replace(order_id, market_id).await;
+                info!(
+                    "Chase for {} converted to market order #{}",
+                    signal.symbol, market_id
+                );
+                return Ok(ChaseOutcome::ConvertedToMarket {
+                    order_id: market_id,
+                });
+            }
+
+            let limit_price = original * (1.0 + direction * offset);
+            working.limit_price = Some(limit_price);
+            working.reason = format!("{} (chase {})", signal.reason, reprices);
+            let Some(replacement) = broker.replace_order(order_id, &working).await? else {
+                return Ok(ChaseOutcome::Filled { order_id });
+            };
+            on_replace(order_id, replacement).await;
+            info!(
+                "Repriced {} order #{} to #{} at {:.4}",
+                signal.symbol, order_id, replacement, limit_price
+            );
+            order_id = replacement;
+        }
+    }
+
+    /// Poll `order_id` until it stops working or the timeout passes, returning
+    /// the last status seen
+    async fn wait_for_fill<B: Broker>(&self, broker: &B, order_id: i32) -> Result<OrderStatus> {
+        let polls = self
+            .config
+            .timeout
+            .as_millis()
+            .div_ceil(self.config.poll_interval.as_millis().max(1))
+            .max(1);
+        let mut status = OrderStatus::Submitted;
+        for _ in 0..polls {
+            self.sleeper.sleep(self.config.poll_interval).await;
+            status = broker.order_status(order_id).await?;
+            if !status.is_active() {
+                break;
+            }
+        }
+        Ok(status)
+    }
+}
+
+/// Split a whole-unit quantity into equal slices, with any remainder in the last
+///
+/// Never produces more slices than units, so every slice trades at least one.
+pub fn slice_quantities(total: f64, slices: usize) -> Vec<f64> {
+    let total = total.abs().floor();
+    if total < 1.0 {
+        return Vec::new();
+    }
+
+    let count = slices.clamp(1, total as usize);
+    let base = (total / count as f64).floor();
+    let mut quantities = vec![base; count];
+    quantities[count - 1] += total - base * count as f64;
+    quantities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::broker::MockBroker;
+    use crate::security_types::SecurityInfo;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    /// Records requested sleeps instead of waiting
+    #[derive(Clone, Default)]
+    struct MockSleeper {
+        sleeps: Arc<Mutex<Vec<Duration>>>,
+    }
+
+    impl Sleeper for MockSleeper {
+        fn sleep(&self, duration: Duration) -> impl Future<Output = ()> {
+            self.sleeps.lock().unwrap().push(duration);
+            std::future::ready(())
+        }
+    }
+
+    fn buy_signal(quantity: f64) -> OrderSignal {
+        OrderSignal {
+            symbol: "AAPL".to_string(),
+            action: "BUY".to_string(),
+            quantity,
+            price: 100.0,
+            order_type: "MKT".to_string(),
+            limit_price: None,
+            reason: "Momentum entry".to_string(),
+            security_info: SecurityInfo::new_stock(
+                "AAPL".to_string(),
+                "SMART".to_string(),
+                "USD".to_string(),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_slice_quantities_puts_remainder_last() {
+        assert_eq!(
+            slice_quantities(1003.0, 4),
+            vec![250.0, 250.0, 250.0, 253.0]
+        );
+        assert_eq!(slice_quantities(3.0, 10), vec![1.0, 1.0, 1.0]);
+        assert!(slice_quantities(0.5, 4).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_twap_slice_count_and_spacing() {
+        let sleeper = MockSleeper::default();
+        let executor = TwapExecutor::with_sleeper(sleeper.clone());
+        let mut submitted = Vec::new();
+        let mut next_id = 1;
+
+        let order_ids = executor
+            .execute_with(
+                &buy_signal(1003.0),
+                4,
+                Duration::from_secs(60),
+                async |slice: OrderSignal| {
+                    submitted.push(slice.quantity);
+                    next_id += 1;
+                    Ok(next_id)
+                },
+                async || Some(100.0),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(order_ids, vec![2, 3, 4, 5]);
+        assert_eq!(submitted, vec![250.0, 250.0, 250.0, 253.0]);
+        assert_eq!(
+            *sleeper.sleeps.lock().unwrap(),
+            vec![Duration::from_secs(15); 3]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_twap_stops_when_price_leaves_band() {
+        let executor = TwapExecutor::with_sleeper(MockSleeper::default()).with_price_band(0.01);
+        let mut prices = vec![100.5, 101.5, 102.0].into_iter();
+        let mut next_id = 0;
+
+        let order_ids = executor
+            .execute_with(
+                &buy_signal(400.0),
+                4,
+                Duration::from_secs(60),
+                async |_slice: OrderSignal| {
+                    next_id += 1;
+                    Ok(next_id)
+                },
+                async || prices.next(),
+            )
+            .await
+            .unwrap();
+
+        // Slice 2 goes out at 100.5; 101.5 is beyond the 1% band
+        assert_eq!(order_ids, vec![1, 2]);
+    }
+
+    fn limit_buy(limit_price: f64) -> OrderSignal {
+        OrderSignal {
+            order_type: "LMT".to_string(),
+            limit_price: Some(limit_price),
+            ..buy_signal(100.0)
+        }
+    }
+
+    fn chase_config(convert_to_market: bool) -> ChaseConfig {
+        ChaseConfig {
+            timeout: Duration::from_secs(30),
+            poll_interval: Duration::from_secs(10),
+            step: 0.002,
+            max_chase: 0.005,
+            convert_to_market,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chase_reprices_until_max_chase() {
+        let broker = MockBroker::new(HashMap::new());
+        let signal = limit_buy(100.0);
+        let order_id = broker.place_order(&signal).await.unwrap();
+        let sleeper = MockSleeper::default();
+        let chaser = OrderChaser::with_sleeper(chase_config(false), sleeper.clone());
+
+        let outcome = chaser.chase(&broker, order_id, &signal).await.unwrap();
+
+        // 100.2 and 100.4 are within 0.5%; 100.6 would not be
+        assert_eq!(
+            outcome,
+            ChaseOutcome::MaxChaseReached {
+                order_id: 3,
+                limit_price: 100.4
+            }
+        );
+        assert_eq!(broker.cancelled_orders(), vec![1, 2]);
+        let limits: Vec<f64> = broker.placed_orders()[1..]
+            .iter()
+            .map(|order| order.limit_price.unwrap())
+            .collect();
+        assert_eq!(limits.len(), 2);
+        assert!((limits[0] - 100.2).abs() < 1e-9);
+        assert!((limits[1] - 100.4).abs() < 1e-9);
+        // Three 10s polls per price
+        assert_eq!(sleeper.sleeps.lock().unwrap().len(), 9);
+    }
+
+    #[tokio::test]
+    async fn test_chase_converts_to_market() {
+        let broker = MockBroker::new(HashMap::new());
+        let signal = OrderSignal {
+            action: "SELL".to_string(),
+            ..limit_buy(100.0)
+        };
+        let order_id = broker.place_order(&signal).await.unwrap();
+        broker.set_order_status(
+            order_id,
+            OrderStatus::PartiallyFilled {
+                filled_qty: 40.0,
+                remaining_qty: 60.0,
+            },
+        );
+        let chaser = OrderChaser::with_sleeper(chase_config(true), MockSleeper::default());
+
+        let outcome = chaser.chase(&broker, order_id, &signal).await.unwrap();
+
+        assert_eq!(outcome, ChaseOutcome::ConvertedToMarket { order_id: 4 });
+        let placed = broker.placed_orders();
+        // Sellers chase down, for the unfilled remainder only
+        assert!((placed[1].limit_price.unwrap() - 99.8).abs() < 1e-9);
+        assert_eq!(placed[1].quantity, 60.0);
+        assert_eq!(placed[3].order_type, "MKT");
+        assert_eq!(placed[3].limit_price, None);
+        assert_eq!(broker.cancelled_orders(), vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_chase_stops_on_fill() {
+        let broker = MockBroker::new(HashMap::new());
+        let signal = limit_buy(100.0);
+        let order_id = broker.place_order(&signal).await.unwrap();
+        // The first replacement fills
+        broker.set_order_status(2, OrderStatus::Filled);
+        let chaser = OrderChaser::with_sleeper(chase_config(true), MockSleeper::default());
+
+        let outcome = chaser.chase(&broker, order_id, &signal).await.unwrap();
+
+        assert_eq!(outcome, ChaseOutcome::Filled { order_id: 2 });
+        assert_eq!(broker.cancelled_orders(), vec![1]);
+        assert_eq!(broker.placed_orders().len(), 2);
+    }
+}
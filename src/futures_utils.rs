@@ -1,5 +1,7 @@
+use crate::orders::OrderSignal;
+use crate::security_types::{FuturesContract, SecurityInfo, SecurityType};
 use anyhow::Result;
-use chrono::{Datelike, Local, NaiveDate, Weekday};
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc, Weekday};
 
 /// Futures contract months and their codes
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -78,9 +80,21 @@ impl ContractMonth {
 /// Calculate the front month contract for a given futures symbol
 pub fn get_front_month_contract(symbol: &str) -> Result<(String, String)> {
     let today = Local::now().date_naive();
+    let (year, month, expiry_date) = front_month_on(symbol, today);
 
+    // Format expiry as YYYYMMDD
+    let expiry = format!("{:04}{:02}{:02}", year, month, expiry_date.day());
+
+    // Format contract month as YYYYMM
+    let contract_month = format!("{:04}{:02}", year, month);
+
+    Ok((expiry, contract_month))
+}
+
+/// First contract for `symbol` expiring strictly after `date`
+fn front_month_on(symbol: &str, today: NaiveDate) -> (i32, u32, NaiveDate) {
     // Get the appropriate expiry based on the symbol
-    let (year, month, expiry_date) = match symbol {
+    match symbol {
         "ES" | "NQ" => {
             // E-mini S&P 500 and Nasdaq-100 futures expire on the third Friday of the contract month
             get_quarterly_expiry(today)
@@ -97,15 +111,121 @@ pub fn get_front_month_contract(symbol: &str) -> Result<(String, String)> {
             // Default to quarterly contracts
             get_quarterly_expiry(today)
         }
-    };
+    }
+}
 
-    // Format expiry as YYYYMMDD
-    let expiry = format!("{:04}{:02}{:02}", year, month, expiry_date.day());
+/// Calendar days from `now` until the contract expires (negative once past)
+///
+/// Reads `expiry` (YYYYMMDD), falling back to the third Friday of
+/// `contract_month` (YYYYMM). Returns `i64::MAX` when neither parses, so an
+/// unknown expiry never triggers a roll.
+pub fn days_to_expiry(contract: &FuturesContract, now: DateTime<Utc>) -> i64 {
+    contract_expiry_date(contract).map_or(i64::MAX, |expiry| (expiry - now.date_naive()).num_days())
+}
 
-    // Format contract month as YYYYMM
-    let contract_month = format!("{:04}{:02}", year, month);
+fn contract_expiry_date(contract: &FuturesContract) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&contract.expiry, "%Y%m%d")
+        .ok()
+        .or_else(|| {
+            let year = contract.contract_month.get(..4)?.parse().ok()?;
+            let month = contract.contract_month.get(4..6)?.parse().ok()?;
+            NaiveDate::from_ymd_opt(year, month, 1)?;
+            Some(get_third_friday(year, month))
+        })
+}
 
-    Ok((expiry, contract_month))
+/// Roll of a futures position from an expiring contract into the next one
+///
+/// Advisory only: the trading loop logs the roll signals as a warning and
+/// never places them, since orders and subscriptions are keyed to the
+/// configured contract month. Rolling means switching the security's
+/// `futures_specs` to `next_contract` and restarting.
+#[derive(Debug, Clone)]
+pub struct RolloverPlan {
+    pub security_info: SecurityInfo,
+    /// Signed position being rolled (negative for shorts)
+    pub quantity: f64,
+    pub next_contract: FuturesContract,
+    pub days_to_expiry: i64,
+}
+
+impl RolloverPlan {
+    /// Plan a roll when a futures position is within `roll_window_days` of
+    /// its contract's expiry
+    pub fn for_position(
+        security_info: &SecurityInfo,
+        quantity: f64,
+        roll_window_days: i64,
+        now: DateTime<Utc>,
+    ) -> Option<Self> {
+        if quantity == 0.0 || security_info.security_type != SecurityType::Future {
+            return None;
+        }
+        let contract = security_info.contract_specs.as_ref()?;
+        let days_to_expiry = days_to_expiry(contract, now);
+        if days_to_expiry > roll_window_days {
+            return None;
+        }
+
+        let underlying = if contract.underlying.is_empty() {
+            &security_info.symbol
+        } else {
+            &contract.underlying
+        };
+        let (year, month, next_expiry) =
+            front_month_on(underlying, contract_expiry_date(contract)?);
+
+        Some(Self {
+            security_info: security_info.clone(),
+            quantity,
+            next_contract: FuturesContract {
+                expiry: next_expiry.format("%Y%m%d").to_string(),
+                contract_month: format!("{:04}{:02}", year, month),
+                ..contract.clone()
+            },
+            days_to_expiry,
+        })
+    }
+
+    /// Close the front contract, then reopen the same position in the next,
+    /// for an operator to place
+    pub fn signals(&self, price: f64) -> [OrderSignal; 2] {
+        let (close_action, open_action) = if self.quantity > 0.0 {
+            ("SELL", "BUY")
+        } else {
+            ("BUY", "SELL")
+        };
+        let front_month = self
+            .security_info
+            .contract_specs
+            .as_ref()
+            .map_or("", |contract| contract.contract_month.as_str());
+        let reason = format!(
+            "Roll {} from {} to {} ({} days to expiry)",
+            self.security_info.symbol,
+            front_month,
+            self.next_contract.contract_month,
+            self.days_to_expiry
+        );
+
+        let mut next_security = self.security_info.clone();
+        next_security.contract_specs = Some(self.next_contract.clone());
+
+        let signal = |action: &str, security_info: SecurityInfo| OrderSignal {
+            symbol: self.security_info.symbol.clone(),
+            action: action.to_string(),
+            quantity: self.quantity.abs(),
+            price,
+            order_type: "MKT".to_string(),
+            limit_price: None,
+            reason: reason.clone(),
+            security_info,
+        };
+        [
+            signal(close_action, self.security_info.clone()),
+            signal(open_action, next_security),
+        ]
+    }
 }
 
 /// Get the next quarterly expiry (Mar, Jun, Sep, Dec)
@@ -310,6 +430,75 @@ mod tests {
         );
     }
 
+    fn es_position(expiry: &str, contract_month: &str) -> SecurityInfo {
+        SecurityInfo::new_future(
+            "ES".to_string(),
+            "CME".to_string(),
+            "USD".to_string(),
+            FuturesContract {
+                underlying: "ES".to_string(),
+                expiry: expiry.to_string(),
+                multiplier: 50.0,
+                tick_size: 0.25,
+                contract_month: contract_month.to_string(),
+            },
+        )
+    }
+
+    fn utc(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(15, 0, 0)
+            .unwrap()
+            .and_utc()
+    }
+
+    #[test]
+    fn test_days_to_expiry() {
+        let security = es_position("20250321", "202503");
+        let contract = security.contract_specs.as_ref().unwrap();
+        assert_eq!(days_to_expiry(contract, utc(2025, 3, 18)), 3);
+        assert_eq!(days_to_expiry(contract, utc(2025, 3, 24)), -3);
+
+        // Contract month alone falls back to its third Friday
+        let month_only = es_position("", "202506");
+        let contract = month_only.contract_specs.as_ref().unwrap();
+        assert_eq!(days_to_expiry(contract, utc(2025, 6, 10)), 10);
+
+        let unknown = es_position("", "");
+        let contract = unknown.contract_specs.as_ref().unwrap();
+        assert_eq!(days_to_expiry(contract, utc(2025, 6, 10)), i64::MAX);
+    }
+
+    #[test]
+    fn test_rollover_plan_inside_window() {
+        let security = es_position("20250321", "202503");
+        let plan = RolloverPlan::for_position(&security, -2.0, 5, utc(2025, 3, 18)).unwrap();
+        assert_eq!(plan.days_to_expiry, 3);
+        assert_eq!(plan.next_contract.contract_month, "202506");
+        assert_eq!(plan.next_contract.expiry, "20250620");
+        assert_eq!(plan.next_contract.multiplier, 50.0);
+
+        let [close, open] = plan.signals(5_700.0);
+        assert_eq!((close.action.as_str(), close.quantity), ("BUY", 2.0));
+        assert_eq!((open.action.as_str(), open.quantity), ("SELL", 2.0));
+        assert_eq!(
+            close.security_info.contract_specs.unwrap().contract_month,
+            "202503"
+        );
+        assert_eq!(
+            open.security_info.contract_specs.unwrap().contract_month,
+            "202506"
+        );
+    }
+
+    #[test]
+    fn test_no_rollover_outside_window() {
+        let security = es_position("20250321", "202503");
+        assert!(RolloverPlan::for_position(&security, 2.0, 5, utc(2025, 3, 11)).is_none());
+        assert!(RolloverPlan::for_position(&security, 0.0, 5, utc(2025, 3, 18)).is_none());
+    }
+
     #[test]
     fn test_get_front_month_contract() {
         // Test ES and NQ futures
@@ -0,0 +1,92 @@
+//! Pre-session self-test results
+//!
+//! `TwsClient::health_check` runs each check and records its outcome here;
+//! `algotrading --check config.json` prints the report and exits non-zero if
+//! anything failed.
+
+use anyhow::Result;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String, // What was observed, or the error
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HealthReport {
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a check: Ok carries what was observed, Err why it failed
+    pub fn record(&mut self, name: &str, outcome: Result<String>) {
+        let (passed, detail) = match outcome {
+            Ok(detail) => (true, detail),
+            Err(e) => (false, e.to_string()),
+        };
+        self.checks.push(HealthCheck {
+            name: name.to_string(),
+            passed,
+            detail,
+        });
+    }
+
+    /// True when at least one check ran and none failed
+    pub fn passed(&self) -> bool {
+        !self.checks.is_empty() && self.checks.iter().all(|check| check.passed)
+    }
+
+    pub fn failures(&self) -> Vec<&HealthCheck> {
+        self.checks.iter().filter(|check| !check.passed).collect()
+    }
+}
+
+impl fmt::Display for HealthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            let status = if check.passed { "PASS" } else { "FAIL" };
+            writeln!(f, "[{}] {}: {}", status, check.name, check.detail)?;
+        }
+        write!(
+            f,
+            "{}/{} checks passed",
+            self.checks.len() - self.failures().len(),
+            self.checks.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_report_fails_if_any_check_fails() {
+        let mut report = HealthReport::new();
+        assert!(!report.passed());
+
+        report.record("connection", Ok("server version 176".to_string()));
+        report.record("account_summary", Ok("net liquidation 100000".to_string()));
+        assert!(report.passed());
+        assert!(report.failures().is_empty());
+
+        report.record("historical_data", Err(anyhow!("no bars for SPY")));
+        assert!(!report.passed());
+        let failures = report.failures();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].name, "historical_data");
+        assert_eq!(failures[0].detail, "no bars for SPY");
+
+        let printed = report.to_string();
+        assert!(printed.contains("[PASS] connection: server version 176"));
+        assert!(printed.contains("[FAIL] historical_data: no bars for SPY"));
+        assert!(printed.ends_with("2/3 checks passed"));
+    }
+}
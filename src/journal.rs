@@ -0,0 +1,382 @@
+//! Structured event journal
+//!
+//! Writes one JSON object per line for the events needed to reconstruct a
+//! trading session (signals, orders, fills, risk halts, reconnects, position
+//! drift), plus optionally why each candidate signal did or did not trade.
+//! The active file is rotated once it reaches `max_file_bytes`,
+//! keeping the most recent `max_files` rotated files as `<path>.1` (newest)
+//! to `<path>.N`.
+
+use crate::decisions::{FilteredBy, SignalDecision};
+use crate::orders::OrderStatus;
+use crate::signals::SignalAttribution;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Journal settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalConfig {
+    #[serde(default = "default_journal_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_journal_path")]
+    pub path: String,
+    #[serde(default = "default_max_file_bytes")]
+    pub max_file_bytes: u64,
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+    #[serde(default)]
+    pub signal_decisions: bool, // Record a SignalDecision event per candidate each cycle
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_journal_enabled(),
+            path: default_journal_path(),
+            max_file_bytes: default_max_file_bytes(),
+            max_files: default_max_files(),
+            signal_decisions: false,
+        }
+    }
+}
+
+fn default_journal_enabled() -> bool {
+    true
+}
+
+fn default_journal_path() -> String {
+    "logs/journal.jsonl".to_string()
+}
+
+fn default_max_file_bytes() -> u64 {
+    10 * 1024 * 1024 // 10 MB per file
+}
+
+fn default_max_files() -> usize {
+    5 // Rotated files kept besides the active one
+}
+
+/// Event recorded in the journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalEvent {
+    SignalGenerated {
+        symbol: String,
+        action: String,
+        quantity: f64,
+        price: f64,
+        reason: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        attribution: Option<SignalAttribution>,
+    },
+    OrderCreated {
+        order_id: i32,
+        symbol: String,
+        action: String,
+        quantity: f64,
+        order_type: String,
+    },
+    OrderSubmitted {
+        order_id: i32,
+        tws_order_id: i32,
+        symbol: String,
+        action: String,
+        quantity: f64,
+    },
+    OrderStatusChanged {
+        order_id: i32,
+        status: OrderStatus,
+    },
+    Fill {
+        order_id: i32,
+        symbol: String,
+        action: String,
+        quantity: f64,
+    },
+    RiskHalt {
+        loss_fraction: f64,
+        max_daily_loss: f64,
+    },
+    Reconnect {
+        address: String,
+        success: bool,
+        error: Option<String>,
+    },
+    PositionMismatch {
+        symbol: String,
+        strategy_quantity: f64,
+        broker_quantity: f64,
+    },
+    SignalDecision {
+        symbol: String,
+        filtered_by: Option<FilteredBy>, // None when the signal was acted on
+        detail: String,
+    },
+}
+
+impl From<SignalDecision> for JournalEvent {
+    fn from(decision: SignalDecision) -> Self {
+        JournalEvent::SignalDecision {
+            symbol: decision.symbol,
+            filtered_by: decision.filtered_by,
+            detail: decision.detail,
+        }
+    }
+}
+
+/// One line of the journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: DateTime<Utc>,
+    pub event: JournalEvent,
+}
+
+struct ActiveFile {
+    file: File,
+    size: u64,
+}
+
+/// Size-rotated newline-delimited JSON event log
+pub struct Journal {
+    path: PathBuf,
+    max_file_bytes: u64,
+    max_files: usize,
+    active: Mutex<ActiveFile>,
+}
+
+impl Journal {
+    pub fn new(path: impl Into<PathBuf>, max_file_bytes: u64, max_files: usize) -> Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let active = open_append(&path)?;
+
+        Ok(Self {
+            path,
+            max_file_bytes,
+            max_files,
+            active: Mutex::new(active),
+        })
+    }
+
+    pub fn from_config(config: &JournalConfig) -> Result<Self> {
+        Self::new(&config.path, config.max_file_bytes, config.max_files)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Force written entries to disk, e.g. before exiting
+    pub fn sync(&self) -> Result<()> {
+        let active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        active.file.sync_all()?;
+        Ok(())
+    }
+
+    /// Append an event, timestamped now
+    ///
+    /// Failures are logged rather than returned so journaling never
+    /// interrupts trading.
+    pub fn record(&self, event: JournalEvent) {
+        let entry = JournalEntry {
+            timestamp: Utc::now(),
+            event,
+        };
+        if let Err(e) = self.write_entry(&entry) {
+            warn!(
+                "Failed to write journal entry to {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+
+    fn write_entry(&self, entry: &JournalEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        if active.size > 0 && active.size + line.len() as u64 > self.max_file_bytes {
+            self.rotate()?;
+            *active = open_append(&self.path)?;
+        }
+
+        active.file.write_all(line.as_bytes())?;
+        active.file.flush()?;
+        active.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    /// Shift `<path>.N-1` to `<path>.N` (dropping the oldest) and move the
+    /// active file to `<path>.1`
+    fn rotate(&self) -> io::Result<()> {
+        if self.max_files == 0 {
+            return fs::remove_file(&self.path);
+        }
+
+        let oldest = self.rotated_path(self.max_files);
+        if oldest.exists() {
+            fs::remove_file(oldest)?;
+        }
+        for index in (1..self.max_files).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(from, self.rotated_path(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))
+    }
+
+    /// Read every entry from a journal file
+    pub fn read_entries(path: &Path) -> Result<Vec<JournalEntry>> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut entries = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                entries.push(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(entries)
+    }
+}
+
+fn open_append(path: &Path) -> Result<ActiveFile> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let size = file.metadata()?.len();
+    Ok(ActiveFile { file, size })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signals::SignalType;
+    use crate::signals::core::SignalContribution;
+
+    fn temp_journal_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("journal_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("events.jsonl")
+    }
+
+    fn sample_events() -> Vec<JournalEvent> {
+        vec![
+            JournalEvent::SignalGenerated {
+                symbol: "AAPL".to_string(),
+                action: "BUY".to_string(),
+                quantity: 100.0,
+                price: 150.25,
+                reason: "Momentum entry".to_string(),
+                attribution: Some(SignalAttribution {
+                    contributions: vec![SignalContribution {
+                        signal_type: SignalType::Momentum,
+                        strength: 6.0,
+                        weight: 1.0,
+                        contribution: 7.5,
+                    }],
+                    adjustment: 1.25,
+                }),
+            },
+            JournalEvent::OrderSubmitted {
+                order_id: 1000,
+                tws_order_id: 42,
+                symbol: "AAPL".to_string(),
+                action: "BUY".to_string(),
+                quantity: 100.0,
+            },
+            JournalEvent::OrderStatusChanged {
+                order_id: 1000,
+                status: OrderStatus::Submitted,
+            },
+            JournalEvent::Fill {
+                order_id: 1000,
+                symbol: "AAPL".to_string(),
+                action: "BUY".to_string(),
+                quantity: 100.0,
+            },
+            JournalEvent::RiskHalt {
+                loss_fraction: 0.035,
+                max_daily_loss: 0.03,
+            },
+            JournalEvent::Reconnect {
+                address: "127.0.0.1:7497".to_string(),
+                success: false,
+                error: Some("connection refused".to_string()),
+            },
+            JournalEvent::PositionMismatch {
+                symbol: "MSFT".to_string(),
+                strategy_quantity: 50.0,
+                broker_quantity: 40.0,
+            },
+            SignalDecision::filtered("XOM", FilteredBy::Correlation, "cluster over 25%").into(),
+        ]
+    }
+
+    #[test]
+    fn test_events_round_trip() {
+        let path = temp_journal_path("round_trip");
+        let journal = Journal::new(&path, 1024 * 1024, 3).unwrap();
+
+        for event in sample_events() {
+            journal.record(event);
+        }
+
+        let entries = Journal::read_entries(&path).unwrap();
+        let events: Vec<JournalEvent> = entries.into_iter().map(|entry| entry.event).collect();
+        assert_eq!(events, sample_events());
+
+        let first_line = fs::read_to_string(&path).unwrap();
+        assert!(first_line.starts_with("{\"timestamp\":"));
+        assert!(first_line.contains("\"type\":\"signal_generated\""));
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_rotation_caps_size_and_file_count() {
+        let path = temp_journal_path("rotation");
+        let journal = Journal::new(&path, 300, 2).unwrap();
+
+        for _ in 0..10 {
+            for event in sample_events() {
+                journal.record(event);
+            }
+        }
+
+        let dir = path.parent().unwrap();
+        let mut files: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec!["events.jsonl", "events.jsonl.1", "events.jsonl.2"]
+        );
+
+        // Every file still parses and stays within the cap (a single oversized
+        // entry may exceed it on its own)
+        for file in &files {
+            let file_path = dir.join(file);
+            let entries = Journal::read_entries(&file_path).unwrap();
+            assert!(!entries.is_empty());
+            assert!(entries.len() == 1 || fs::metadata(&file_path).unwrap().len() <= 300);
+        }
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
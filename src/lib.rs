@@ -1,23 +1,43 @@
+pub mod backtest;
+pub mod benchmark;
 pub mod bollinger;
 pub mod breakout;
+pub mod broker;
 pub mod carry;
+pub mod clock;
 pub mod config;
 pub mod connection;
+pub mod costs;
+pub mod decisions;
+pub mod donchian;
+pub mod execution;
 pub mod futures_utils;
+pub mod health;
+pub mod journal;
 pub mod margin;
 pub mod market_data;
+pub mod metrics;
 pub mod momentum;
 pub mod order_types;
 pub mod orders;
+pub mod pairs;
 pub mod portfolio;
 pub mod position_inertia;
 pub mod position_manager;
+pub mod reconciliation;
 pub mod risk;
 pub mod risk_budgeting;
 pub mod risk_budgeting_inertia;
+pub mod rng;
+pub mod schedule;
 pub mod security_types;
 pub mod signals;
 pub mod stats;
+#[cfg(feature = "status-server")]
+pub mod status_server;
+pub mod strategy;
+pub mod trading_cycle;
 pub mod trading_integration;
 pub mod transaction_cost;
 pub mod volatility;
+pub mod walk_forward;
@@ -1,35 +1,57 @@
+mod backtest;
+mod benchmark;
 mod bollinger;
 mod breakout;
+mod broker;
 mod carry;
+mod clock;
 mod config;
 mod connection;
+mod costs;
+mod decisions;
+mod donchian;
+mod execution;
 mod futures_utils;
+mod health;
+mod journal;
 mod margin;
 mod market_data;
+mod metrics;
 mod momentum;
 mod order_types;
 mod orders;
+mod pairs;
 mod portfolio;
 mod position_inertia;
 mod position_manager;
+mod reconciliation;
 mod risk;
 mod risk_budgeting;
 mod risk_budgeting_inertia;
+mod rng;
+mod schedule;
 mod security_types;
 mod signals;
 mod stats;
+#[cfg(feature = "status-server")]
+mod status_server;
+mod strategy;
+mod trading_cycle;
 mod trading_integration;
 mod transaction_cost;
 mod volatility;
+mod walk_forward;
 
-use market_data::{MarketDataUpdate, TimeFrame};
+use broker::Broker;
+use market_data::{MarketDataEvent, TimeFrame};
+use strategy::Strategy;
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use log::{debug, error, info, warn};
 use std::collections::HashMap;
 use std::env;
 use std::sync::Arc;
-use tokio::sync::{Mutex, mpsc};
+use tokio::sync::{Mutex, mpsc, watch};
 use tokio::time::{Duration, interval, sleep};
 
 #[tokio::main]
@@ -45,6 +67,9 @@ async fn main() -> Result<()> {
 
     // Get config file from command line argument or use default
     let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) == Some("--check") {
+        return run_health_check(args.get(2).map_or("config.json", String::as_str)).await;
+    }
     let config_file = if args.len() > 1 {
         &args[1]
     } else {
@@ -52,20 +77,104 @@ async fn main() -> Result<()> {
     };
 
     info!("Loading configuration from: {}", config_file);
-    let config = config::TradingConfig::load_from_file(config_file)?;
+    let mut config = config::TradingConfig::load_from_file(config_file)?;
+
+    // Structured event journal
+    let journal = if config.journal.enabled {
+        let journal = Arc::new(journal::Journal::from_config(&config.journal)?);
+        info!("Journaling events to {}", journal.path().display());
+        Some(journal)
+    } else {
+        None
+    };
+    // Per-candidate decisions are opt-in: one event per symbol per cycle
+    let decision_journal = journal.clone().filter(|_| config.journal.signal_decisions);
+
+    // Counters and gauges for monitoring
+    let metrics = Arc::new(metrics::Metrics::new());
+
+    // Create TWS client, stepping past client ids held by stale sessions
+    let tws = &config.tws_config;
+    let mut tws_client = if tws.client_id_attempts > 1 {
+        let last_id = tws
+            .client_id
+            .saturating_add(tws.client_id_attempts as i32 - 1);
+        let (client, client_id) = connection::TwsClient::connect_with_auto_client_id(
+            tws.clone(),
+            tws.client_id..=last_id,
+        )
+        .await?;
+        if client_id != tws.client_id {
+            warn!(
+                "Client id {} in use, connected as {}",
+                tws.client_id, client_id
+            );
+        }
+        client
+    } else {
+        connection::TwsClient::new(tws.clone()).await?
+    }
+    .with_metrics(metrics.clone());
+    if let Some(journal) = &journal {
+        tws_client = tws_client.with_journal(journal.clone());
+    }
+    let tws_client = Arc::new(tws_client);
 
-    // Create TWS client
-    let tws_client = Arc::new(connection::TwsClient::new(config.tws_config.clone()).await?);
+    // Reconnect and replay subscriptions if the TWS connection drops
+    tws_client
+        .enable_auto_reconnect(5, Duration::from_secs(2))
+        .await;
 
     // Initialize components
-    let momentum_strategy = Arc::new(Mutex::new(momentum::MomentumStrategy::new(
-        config.strategy_config.clone(),
-    )));
-    let order_manager = Arc::new(Mutex::new(orders::OrderManager::new()));
-    let portfolio = Arc::new(Mutex::new(portfolio::Portfolio::new(100000.0)));
-    let risk_manager = Arc::new(Mutex::new(risk::RiskManager::new(
-        config.risk_config.clone(),
-    )));
+    let mut strategy_manager = strategy::StrategyManager::new();
+    let selected = strategy::build_strategy(&config)?;
+    let allocation = config.strategy_allocation(selected.name());
+    strategy_manager.add(selected, allocation)?;
+    for (name, allocation) in strategy_manager.allocations() {
+        info!(
+            "Strategy {} allocated {:.1}% of capital",
+            name,
+            allocation * 100.0
+        );
+    }
+    let active_strategy: Box<dyn Strategy> = Box::new(strategy_manager);
+    let active_strategy = Arc::new(Mutex::new(active_strategy));
+    let mut order_manager = orders::OrderManager::new()
+        .with_min_holding_period(chrono::Duration::minutes(
+            config.risk_config.min_holding_period_minutes as i64,
+        ))
+        .with_working_order_timeout(chrono::Duration::seconds(
+            config.risk_config.working_order_timeout_seconds as i64,
+        ))
+        .with_metrics(metrics.clone());
+    if let Some(journal) = &journal {
+        order_manager = order_manager.with_journal(journal.clone());
+    }
+    let order_manager = Arc::new(Mutex::new(order_manager));
+    let clock = clock::system_clock();
+    let portfolio = Arc::new(Mutex::new(
+        portfolio::Portfolio::new(100000.0).with_clock(clock.clone()),
+    ));
+    let risk_manager = Arc::new(Mutex::new(
+        risk::RiskManager::new(config.risk_config.clone()).with_clock(clock.clone()),
+    ));
+
+    // Optional HTTP status endpoint
+    if config.status_server.enabled {
+        #[cfg(feature = "status-server")]
+        status_server::spawn_status_server(
+            &config.status_server,
+            status_server::StatusState {
+                portfolio: portfolio.clone(),
+                risk_manager: risk_manager.clone(),
+                metrics: metrics.clone(),
+            },
+        )
+        .await?;
+
+        #[cfg(not(feature = "status-server"))]
+        warn!("status_server is enabled in config but this build lacks the status-server feature");
+    }
 
     // Initialize risk budgeting system
     let risk_budgeter = Arc::new(Mutex::new(risk_budgeting::RiskBudgeter::new(
@@ -73,51 +182,45 @@ async fn main() -> Result<()> {
         config.risk_config.risk_budget_target_volatility,
     )));
 
+    // Expected commissions and slippage, for comparison against actual fills
+    let cost_model = costs::CostModel::for_securities(
+        config.cost_model.clone(),
+        &config.strategy_config.securities,
+    );
+
     // Initialize transaction cost optimization and position inertia system
     let trading_integration = Arc::new(trading_integration::TradingIntegrationLayer::new(
         &config.risk_config,
+        cost_model.clone(),
     ));
 
+    // Route orders to TWS, or fill them locally against live prices
+    let broker = if config.tws_config.simulate_fills {
+        warn!("Simulated fills: orders are filled locally and never sent to TWS");
+        let rng = rng::RngSource::from_entropy();
+        info!("Simulated slippage seed: {}", rng.seed());
+        broker::BrokerHandle::Simulated(Arc::new(broker::SimulatedBroker::new(
+            tws_client.market_data_handler.clone(),
+            cost_model.clone().with_rng(rng),
+            config.tws_config.simulated_starting_cash,
+        )))
+    } else {
+        broker::BrokerHandle::Live(tws_client.clone())
+    };
+
     // Initialize market data handler with TwsClient
-    let handler_guard = tws_client.market_data_handler.lock().await;
+    let mut handler_guard = tws_client.market_data_handler.lock().await;
+    handler_guard.set_clock(clock.clone());
+    handler_guard.apply_strategy_settings(&config.strategy_config);
 
     // Register securities with market data handler and portfolio
     let mut port = portfolio.lock().await;
     for security_cfg in &config.strategy_config.securities {
-        let security_info = match security_cfg.security_type {
-            security_types::SecurityType::Future => {
-                let contract = security_cfg
-                    .futures_specs
-                    .as_ref()
-                    .map(|s| security_types::FuturesContract {
-                        underlying: s.underlying.clone(),
-                        expiry: s.expiry.clone(),
-                        multiplier: s.multiplier,
-                        tick_size: s.tick_size,
-                        contract_month: s.contract_month.clone(),
-                    })
-                    .unwrap_or_default();
-
-                security_types::SecurityInfo::new_future(
-                    security_cfg.symbol.clone(),
-                    security_cfg.exchange.clone(),
-                    security_cfg.currency.clone(),
-                    contract,
-                )
-            }
-            security_types::SecurityType::Forex => security_types::SecurityInfo::new_forex(
-                security_cfg.symbol.clone(),
-                security_cfg.exchange.clone(),
-                security_cfg.currency.clone(),
-            ),
-            _ => security_types::SecurityInfo::new_stock(
-                security_cfg.symbol.clone(),
-                security_cfg.exchange.clone(),
-                security_cfg.currency.clone(),
-            ),
-        };
+        let security_id = config::security_id(&config.strategy_config.securities, security_cfg);
+        let security_info = security_cfg.security_info();
 
-        port.register_security(security_cfg.symbol.clone(), security_info.clone());
+        port.register_security(security_id.clone(), security_info.clone());
+        handler_guard.set_return_mode(&security_id, security_cfg.return_mode);
     }
     drop(port);
     drop(handler_guard);
@@ -129,94 +232,108 @@ async fn main() -> Result<()> {
     );
 
     // Create a channel to receive market data updates
-    let (tx, mut rx) = mpsc::channel::<MarketDataUpdate>(1000);
+    let (tx, mut rx) = mpsc::channel::<MarketDataEvent>(1000);
 
     // Spawn a task to process market data updates
     let market_data_handler = tws_client.market_data_handler.clone();
     tokio::spawn(async move {
-        while let Some(update) = rx.recv().await {
-            let mut handler = market_data_handler.lock().await;
-            handler.update_realtime_data(&update.symbol, update.last_price, update.volume);
+        while let Some(event) = rx.recv().await {
+            market_data_handler.lock().await.apply_event(event);
         }
     });
 
-    // Track subscribed symbols to avoid duplicates
-    let mut subscribed_symbols = std::collections::HashSet::new();
+    // Track subscribed securities to avoid duplicates; futures are keyed by
+    // contract month so calendar positions get a subscription per month
+    let mut subscribed_securities = std::collections::HashSet::new();
 
     for (idx, security_cfg) in config.strategy_config.securities.iter().enumerate() {
-        // Skip duplicate symbols to avoid multiple subscriptions
-        if !subscribed_symbols.insert(security_cfg.symbol.clone()) {
+        // Skip duplicate securities to avoid multiple subscriptions
+        if !subscribed_securities.insert(security_cfg.key()) {
             continue;
         }
+        let security_id = config::security_id(&config.strategy_config.securities, security_cfg);
 
         // Register security config with TwsClient
         tws_client
-            .register_security_config(security_cfg.symbol.clone(), security_cfg.clone())
+            .register_security_config(security_id.clone(), security_cfg.clone())
             .await;
 
         // Register with market data handler
         let mut handler_guard = tws_client.market_data_handler.lock().await;
 
-        let security_info = match security_cfg.security_type {
-            security_types::SecurityType::Future => {
-                let contract = security_cfg
-                    .futures_specs
-                    .as_ref()
-                    .map(|s| security_types::FuturesContract {
-                        underlying: s.underlying.clone(),
-                        expiry: s.expiry.clone(),
-                        multiplier: s.multiplier,
-                        tick_size: s.tick_size,
-                        contract_month: s.contract_month.clone(),
-                    })
-                    .unwrap_or_default();
-
-                security_types::SecurityInfo::new_future(
-                    security_cfg.symbol.clone(),
-                    security_cfg.exchange.clone(),
-                    security_cfg.currency.clone(),
-                    contract,
-                )
-            }
-            security_types::SecurityType::Forex => security_types::SecurityInfo::new_forex(
-                security_cfg.symbol.clone(),
-                security_cfg.exchange.clone(),
-                security_cfg.currency.clone(),
-            ),
-            _ => security_types::SecurityInfo::new_stock(
-                security_cfg.symbol.clone(),
-                security_cfg.exchange.clone(),
-                security_cfg.currency.clone(),
-            ),
-        };
+        let security_info = security_cfg.security_info();
 
-        handler_guard.register_security(security_cfg.symbol.clone(), security_info);
+        handler_guard.register_security(security_id.clone(), security_info);
         drop(handler_guard);
 
         // Subscribe to real-time data instead of request_market_data
         tws_client
-            .subscribe_realtime_data(&security_cfg.symbol, idx as i32, tx.clone())
+            .subscribe_realtime_data(&security_id, idx as i32, tx.clone())
             .await?;
-        info!("Subscribed to real-time data for {}", security_cfg.symbol);
+        info!("Subscribed to real-time data for {}", security_id);
 
         // Small delay between subscriptions to avoid overwhelming the API
         sleep(Duration::from_millis(100)).await;
     }
 
-    // Wait for market data to populate
-    info!("Waiting for market data to populate...");
-    sleep(Duration::from_secs(5)).await;
+    // Pick up the history saved by the last session so warm-up starts ahead
+    if let Some(path) = config
+        .shutdown
+        .price_history_path
+        .as_deref()
+        .filter(|path| std::path::Path::new(path).exists())
+    {
+        let mut handler = tws_client.market_data_handler.lock().await;
+        match handler.load_price_history(std::path::Path::new(path)) {
+            Ok(loaded) => info!(
+                "Loaded saved price history for {} symbols from {}",
+                loaded, path
+            ),
+            Err(e) => warn!("Failed to load price history from {}: {}", path, e),
+        }
+    }
+
+    // Hold the trading loop until every symbol has enough history to signal
+    let min_bars = config.strategy_config.min_bars_for_signals();
+    let max_warmup = Duration::from_secs(config.strategy_config.max_warmup_seconds);
+    let warmup_started = tokio::time::Instant::now();
+    loop {
+        let warming_up = tws_client
+            .market_data_handler
+            .lock()
+            .await
+            .warming_up_symbols(min_bars);
+        if warming_up.is_empty() {
+            info!("Market data warmed up: every symbol has {} bars", min_bars);
+            break;
+        }
+        if warmup_started.elapsed() >= max_warmup {
+            warn!(
+                "Warm-up timed out after {}s, starting with {:?} still below {} bars",
+                max_warmup.as_secs(),
+                warming_up,
+                min_bars
+            );
+            break;
+        }
+        info!(
+            "Warming up {:?}: waiting for {} bars of history",
+            warming_up, min_bars
+        );
+        sleep(Duration::from_secs(2)).await;
+    }
 
     // Get initial account summary
-    match tws_client.get_account_summary().await {
+    match broker.get_account_summary().await {
         Ok(summary) => {
             let net_liq = summary.get("net_liquidation").copied().unwrap_or(0.0);
             let cash = summary.get("cash").copied().unwrap_or(0.0);
             let unrealized_pnl = summary.get("unrealized_pnl").copied().unwrap_or(0.0);
+            let buying_power = summary.get("buying_power").copied().unwrap_or(0.0);
 
             info!(
-                "Account: Net=${:.2} Cash=${:.2} P&L=${:.2}",
-                net_liq, cash, unrealized_pnl
+                "Account: Net=${:.2} Cash=${:.2} P&L=${:.2} Buying power=${:.2}",
+                net_liq, cash, unrealized_pnl, buying_power
             );
 
             // Update portfolio with actual cash balance
@@ -229,17 +346,30 @@ async fn main() -> Result<()> {
     }
 
     // Get current positions and sync with strategy and portfolio
-    match tws_client.get_positions().await {
+    match broker.get_positions().await {
         Ok(positions) => {
             let position_count = positions.len();
             if !positions.is_empty() {
+                // Keep holding periods running across restarts
+                let entry_times = if config.tws_config.simulate_fills {
+                    HashMap::new()
+                } else {
+                    tws_client
+                        .position_entry_times(&positions)
+                        .await
+                        .unwrap_or_else(|e| {
+                            warn!("Failed to date open positions from executions: {}", e);
+                            HashMap::new()
+                        })
+                };
+
                 let total_value: f64 = positions.iter().map(|p| p.position * p.avg_cost).sum();
                 info!(
                     "Positions: {} open, total value ${:.2}",
                     position_count, total_value
                 );
 
-                let mut strategy = momentum_strategy.lock().await;
+                let mut strategy = active_strategy.lock().await;
                 let mut port = portfolio.lock().await;
 
                 // Get current market prices for position valuation
@@ -250,11 +380,15 @@ async fn main() -> Result<()> {
                 // Sync positions with both strategy and portfolio
                 for pos in &positions {
                     // Sync with strategy
-                    strategy.update_position(&pos.symbol, pos.position);
+                    let symbol = port.tws_position_symbol(pos);
+                    strategy.update_position(&symbol, pos.position);
+                    if let Some(opened_at) = entry_times.get(&pos.contract.contract_id) {
+                        port.seed_entry_time(&symbol, pos.position, *opened_at);
+                    }
 
                     // Sync with portfolio using current market price
                     let current_price = current_prices
-                        .get(&pos.symbol)
+                        .get(&symbol)
                         .copied()
                         .unwrap_or(pos.avg_cost);
                     port.sync_position_from_tws(pos, current_price);
@@ -273,14 +407,26 @@ async fn main() -> Result<()> {
 
     info!("Starting trading loop...");
 
-    let mut trading_interval = interval(Duration::from_secs(
-        config.strategy_config.rebalance_frequency_minutes * 60,
-    ));
+    // A plain interval rebalances straight away; other schedules wait for their first slot
+    let mut rebalance_schedule = config.strategy_config.rebalance_schedule();
+    let mut next_rebalance = match rebalance_schedule {
+        schedule::Schedule::Interval { .. } => clock.now(),
+        _ => rebalance_schedule.next_run(clock.now()),
+    };
+    info!("First rebalance at {}", next_rebalance);
     let mut portfolio_update_interval = interval(Duration::from_secs(30)); // Update portfolio every 30 seconds
+    let mut order_status_interval = interval(Duration::from_secs(10)); // Reconcile order statuses with the broker
+    let mut terminate = std::pin::pin!(terminate_signal());
+    let mut emergency = std::pin::pin!(emergency_signal());
+
+    // Pick up threshold and limit changes without losing the warmed-up data
+    let mut config_updates = spawn_config_watcher(config_file.to_string(), config.clone());
 
     loop {
         tokio::select! {
-            _ = trading_interval.tick() => {
+            _ = sleep((next_rebalance - clock.now()).to_std().unwrap_or_default()) => {
+                next_rebalance = rebalance_schedule.next_run(clock.now());
+                debug!("Next rebalance at {}", next_rebalance);
                 info!("=== Running Enhanced Momentum Strategy ===");
 
                 // Get market data handler from TwsClient
@@ -293,16 +439,15 @@ async fn main() -> Result<()> {
                     debug!("  {}: ${:.2}", symbol, price);
                 }
 
+                if let Some(regime) = handler_guard.market_volatility_regime(config.strategy_config.lookback_period) {
+                    info!("Market volatility regime: {:?}", regime);
+                }
+
                 // Show detailed momentum analysis for each security (avoid duplicates)
                 debug!("--- Enhanced Momentum Analysis ---");
-                let mut processed_symbols = std::collections::HashSet::new();
-                for security in &config.strategy_config.securities {
-                    if !processed_symbols.insert(security.symbol.clone()) {
-                        continue; // Skip duplicates
-                    }
-
-                    if let Some(multi_timeframe) = handler_guard.calculate_multi_timeframe_momentum(&security.symbol) {
-                        debug!("{}:", security.symbol);
+                for symbol in config.strategy_config.security_ids() {
+                    if let Some(multi_timeframe) = handler_guard.calculate_multi_timeframe_momentum_with_lookbacks(&symbol, &config.strategy_config.lookback_periods) {
+                        debug!("{}:", symbol);
                         debug!("  Composite Score: {:.4}", multi_timeframe.composite_score);
 
                         if let Some(st) = multi_timeframe.timeframe_metrics.get(&TimeFrame::Days1) {
@@ -318,11 +463,37 @@ async fn main() -> Result<()> {
                                 lt.simple_momentum, lt.risk_adjusted_momentum, lt.volatility * 100.0, lt.sharpe_ratio);
                         }
                     } else {
-                        debug!("{}: Insufficient data for momentum calculation", security.symbol);
+                        debug!("{}: Insufficient data for momentum calculation", symbol);
                     }
                 }
 
-                let mut strategy = momentum_strategy.lock().await;
+                // Refresh ATRs used for volatility-scaled stops
+                risk_manager.lock().await.update_atr_from_market_data(&handler_guard);
+
+                // Warn about futures positions due to roll; the roll itself is manual
+                for position in portfolio.lock().await.positions().values() {
+                    let Some(security_info) = &position.security_info else { continue };
+                    if let Some(plan) = futures_utils::RolloverPlan::for_position(
+                        security_info,
+                        position.quantity,
+                        config.strategy_config.futures_roll_window_days,
+                        clock.now(),
+                    ) {
+                        for signal in plan.signals(position.current_price) {
+                            warn!("Rollover due (not placed automatically): {} {} {} - {}", signal.action, signal.quantity, signal.symbol, signal.reason);
+                        }
+                    }
+                }
+
+                // Refresh correlations and volatilities used for risk budgeting
+                if config.risk_config.enable_risk_budgeting {
+                    let mut budgeter = risk_budgeter.lock().await;
+                    if let Err(e) = budgeter.compute_correlations_from_history(&handler_guard, config.risk_config.correlation_lookback_days) {
+                        warn!("Failed to update correlation matrix: {}", e);
+                    }
+                }
+
+                let mut strategy = active_strategy.lock().await;
 
                 // Show current strategy positions for debugging
                 let current_positions = strategy.get_positions();
@@ -335,7 +506,9 @@ async fn main() -> Result<()> {
                     info!("Strategy has no tracked positions");
                 }
 
+                strategy.set_exposure_scale(risk_manager.lock().await.exposure_scale());
                 let mut signals = strategy.calculate_signals(&handler_guard);
+                record_signal_decisions(&decision_journal, strategy.signal_decisions());
 
                 drop(handler_guard);
 
@@ -344,6 +517,16 @@ async fn main() -> Result<()> {
                     for signal in &signals {
                         debug!("Signal: {} {} {:.0} shares @ ${:.2} - {}",
                             signal.action, signal.symbol, signal.quantity, signal.price, signal.reason);
+                        if let Some(journal) = &journal {
+                            journal.record(journal::JournalEvent::SignalGenerated {
+                                symbol: signal.symbol.clone(),
+                                action: signal.action.clone(),
+                                quantity: signal.quantity,
+                                price: signal.price,
+                                reason: signal.reason.clone(),
+                                attribution: strategy.signal_attribution(&signal.symbol).cloned(),
+                            });
+                        }
                     }
 
                     // Apply position inertia and transaction cost filtering
@@ -352,6 +535,7 @@ async fn main() -> Result<()> {
                     let latest_prices = handler_guard.get_latest_prices();
                     drop(handler_guard);
 
+                    let candidates: Vec<String> = signals.iter().map(|signal| signal.symbol.clone()).collect();
                     let (filtered_signals, filter_result) = trading_integration
                         .filter_signals_with_cost_optimization(
                             signals,
@@ -372,6 +556,15 @@ async fn main() -> Result<()> {
                         });
 
                     drop(port);
+                    let dropped = candidates.iter()
+                        .filter(|symbol| !filtered_signals.iter().any(|kept| &kept.symbol == *symbol))
+                        .map(|symbol| decisions::SignalDecision::filtered(
+                            symbol,
+                            decisions::FilteredBy::TransactionCost,
+                            "dropped by position inertia or transaction cost filtering",
+                        ))
+                        .collect();
+                    record_signal_decisions(&decision_journal, dropped);
                     signals = filtered_signals;
 
                     info!("Signal filtering: {} original → {} final (inertia: {}, cost: {}, estimated costs: ${:.2})",
@@ -423,202 +616,81 @@ async fn main() -> Result<()> {
                         drop(port);
                     }
 
-                    // Get current account summary for margin validation
-                    let account_summary = match tws_client.get_account_summary().await {
-                        Ok(summary) => summary,
-                        Err(e) => {
-                            error!("Failed to get account summary for margin validation: {}", e);
-                            continue;
-                        }
-                    };
-
-                    let mut order_mgr = order_manager.lock().await;
-                    let port = portfolio.lock().await;
-                    let risk_mgr = risk_manager.lock().await;
-
-                    // Check if portfolio exposure is excessive before processing new signals
-                    let current_exposure = port.positions()
-                        .values()
-                        .map(|p| (p.quantity * p.current_price).abs())
-                        .sum::<f64>();
-                    let portfolio_stats = port.get_stats();
-                    let exposure_ratio = current_exposure / portfolio_stats.total_value;
-
-                    if exposure_ratio > risk_mgr.config.max_portfolio_exposure {
-                        warn!("Portfolio exposure {:.1}% exceeds {:.1}% limit - prioritizing risk reduction over new signals",
-                            exposure_ratio * 100.0,
-                            risk_mgr.config.max_portfolio_exposure * 100.0);
-
-                        // Only process SELL signals (position reductions) when over-exposed
-                        let reduction_signals: Vec<_> = signals.into_iter()
-                            .filter(|s| s.action == "SELL")
-                            .collect();
-
-                        if !reduction_signals.is_empty() {
-                            info!("Processing {} position reduction signals due to excessive exposure", reduction_signals.len());
-                        } else {
-                            warn!("No position reduction signals available - portfolio remains over-exposed");
-                            drop(port);
-                            drop(risk_mgr);
-                            drop(order_mgr);
-                            continue;
-                        }
-
-                        // Process only the reduction signals
-                        for signal in reduction_signals {
-                            // Execute reduction signals with minimal validation
-                            info!("Executing risk reduction signal: {} {} {}",
-                                signal.action, signal.quantity, signal.symbol);
-
-                            match order_mgr.validate_and_create_order(
-                                signal.clone(),
-                                &port,
-                                &account_summary,
-                                config.risk_config.max_margin_utilization,
-                            ) {
-                                Ok(order) => {
-                                    info!("Created risk reduction order #{}: {} {} {}",
-                                        order.id, order.action, order.quantity, order.symbol);
-                                    match tws_client.place_order_from_order(&order).await {
-                                        Ok(tws_order_id) => {
-                                            let _ = order_mgr.update_order_status(order.id, orders::OrderStatus::Submitted);
-                                            info!("Risk reduction order submitted to TWS: {} {} {} (TWS ID: {})", order.action, order.quantity, order.symbol, tws_order_id);
-                                        }
-                                        Err(e) => {
-                                            error!("Failed to place risk reduction order: {}", e);
-                                            let _ = order_mgr.update_order_status(order.id, orders::OrderStatus::Rejected);
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    error!("Failed to create risk reduction order: {}", e);
-                                }
-                            }
-                        }
-
-                        drop(port);
-                        drop(risk_mgr);
-                        drop(order_mgr);
-                        continue; // Skip normal signal processing
-                    }
                 }
 
                 if !signals.is_empty() {
-                    // Normal signal processing when exposure is within limits
+                    let bracket_levels = match &config.strategy_config.bracket_orders {
+                        Some(brackets) => {
+                            let handler_guard = tws_client.market_data_handler.lock().await;
+                            trading_cycle::bracket_levels_for(brackets, &signals, &handler_guard)
+                        }
+                        None => HashMap::new(),
+                    };
+
                     let mut port = portfolio.lock().await;
-                    let risk_mgr = risk_manager.lock().await;
+                    let mut risk_mgr = risk_manager.lock().await;
                     let mut order_mgr = order_manager.lock().await;
+                    let budgeter = if config.risk_config.enable_risk_budgeting {
+                        Some(risk_budgeter.lock().await)
+                    } else {
+                        None
+                    };
 
-                    // Get account summary for margin validation
-                    let account_summary = match tws_client.get_account_summary().await {
-                        Ok(summary) => summary,
+                    let cycle = trading_cycle::TradingCycle {
+                        broker: &broker,
+                        strategy: &mut **strategy,
+                        portfolio: &mut port,
+                        risk_manager: &mut risk_mgr,
+                        order_manager: &mut order_mgr,
+                        risk_budgeter: budgeter.as_deref(),
+                        cost_model: Some(&cost_model),
+                        bracket_levels,
+                    };
+                    let report = match trading_cycle::run_cycle(cycle, signals, &latest_prices).await {
+                        Ok(report) => report,
                         Err(e) => {
-                            error!("Failed to get account summary: {}", e);
-                            drop(port);
-                            drop(risk_mgr);
-                            drop(order_mgr);
+                            error!("{:#}", e);
                             continue;
                         }
                     };
-
-                    // Perform risk analysis before executing signals
-                    risk_mgr.log_risk_analysis(&port);
-
-                    for signal in signals {
-                        // Validate position against risk limits
-                        let risk_validation = risk_mgr.validate_new_position(
-                            &port,
-                            &signal.symbol,
-                            signal.quantity,
-                            signal.price
-                        );
-
-                        if let Err(e) = risk_validation {
-                            error!("Risk validation failed for {}: {}", signal.symbol, e);
-                            continue;
+                    drop(budgeter);
+                    record_signal_decisions(&decision_journal, report.decisions.clone());
+
+                    for submitted in &report.submitted {
+                        record_order_submitted(&journal, &submitted.order, submitted.broker_order_id);
+                        if let Some(chase) = &config.strategy_config.order_chase
+                            && submitted.signal.order_type == "LMT"
+                        {
+                            spawn_order_chase(
+                                broker.clone(),
+                                order_manager.clone(),
+                                execution::OrderChaser::new(chase.into()),
+                                submitted,
+                            );
                         }
-
-                        // Additional risk budgeting validation if enabled
-                        if config.risk_config.enable_risk_budgeting {
-                            let budgeter = risk_budgeter.lock().await;
-
-                            // Check correlation risk
-                            let symbols: Vec<String> = port.positions().keys().cloned().collect();
-                            match budgeter.calculate_correlation_risk(&symbols) {
-                                Ok(correlation_risk) => {
-                                    if correlation_risk.diversification_score < (1.0 - config.risk_config.max_correlation_exposure) {
-                                        warn!("Risk budgeting: Diversification score too low for {}: {:.2}% < {:.2}%",
-                                            signal.symbol,
-                                            correlation_risk.diversification_score * 100.0,
-                                            (1.0 - config.risk_config.max_correlation_exposure) * 100.0);
-                                        drop(budgeter);
-                                        continue;
-                                    }
-                                }
-                                Err(e) => {
-                                    warn!("Failed to calculate correlation risk for {}: {}", signal.symbol, e);
-                                }
-                            }
-                            drop(budgeter);
-                        }
-
-                        if !risk_validation.unwrap_or(false) {
-                            info!("Order rejected by risk manager: {} {} {}",
-                                signal.action, signal.quantity, signal.symbol);
-                            continue;
-                        }
-
-                        // Validate margin and create order
-                        match order_mgr.validate_and_create_order(
-                            signal.clone(),
-                            &port,
-                            &account_summary,
-                            config.risk_config.max_margin_utilization,
-                        ) {
-                            Ok(order) => {
-                                debug!(
-                                    "About to place TWS order: signal.quantity={:.0}, order.quantity={:.0}",
-                                    signal.quantity, order.quantity
-                                );
-                                match tws_client.place_order(&signal).await {
-                                    Ok(tws_order_id) => {
-                                        let _ = order_mgr.update_order_status(order.id, orders::OrderStatus::Submitted);
-                                        // NOTE: Don't update portfolio here - wait for TWS position sync
-                                        // Portfolio will be updated when TWS confirms the position change
-                                        info!("Order submitted to TWS: {} {} {} (TWS ID: {})", signal.action, signal.quantity, signal.symbol, tws_order_id);
-                                    }
-                                    Err(e) => {
-                                        error!("Failed to place order: {}", e);
-                                        let _ = order_mgr.update_order_status(order.id, orders::OrderStatus::Rejected);
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                error!("Failed to create order due to margin constraints: {}", e);
+                        if !report.risk_reduction_only {
+                            let signal = &submitted.signal;
+                            // Credit the position's eventual P&L to the signals behind it
+                            if let Some(attribution) = strategy.signal_attribution(&signal.symbol) {
+                                port.tag_signal_attribution(&signal.symbol, attribution.clone());
                             }
+                            let signed_quantity = if signal.action == "BUY" { signal.quantity } else { -signal.quantity };
+                            let expected = cost_model.expected_fill(&signal.symbol, &signal.security_info.security_type, signed_quantity, signal.price);
+                            info!("Expected fill for TWS ID {}: price {:.4}, commission ${:.2}, slippage ${:.2}",
+                                  submitted.broker_order_id, expected.fill_price, expected.commission, expected.slippage);
                         }
                     }
-
-                    // Update portfolio with latest prices after all trading is done
-                    port.update_market_prices(&latest_prices);
-
-                    // IMPORTANT: Force portfolio sync with actual TWS positions
-                    if let Ok(tws_positions) = tws_client.get_positions().await {
-                        port.sync_all_positions_from_tws(&tws_positions, &latest_prices);
-
-                        // Also sync strategy positions with actual TWS positions
-                        // Clear current positions and resync with TWS
-                        for symbol in strategy.get_positions().keys().cloned().collect::<Vec<_>>() {
-                            strategy.update_position(&symbol, 0.0); // Clear position
-                        }
-                        for pos in &tws_positions {
-                            strategy.update_position(&pos.symbol, pos.position);
-                        }
+                    if report.risk_reduction_only {
+                        continue; // Positions resync on the next cycle
                     }
 
                     let stats = port.get_stats();
                     info!("Portfolio: ${:.2} total, {} positions, P&L ${:.2}",
                         stats.total_value, stats.positions_count, stats.total_unrealized_pnl);
+                    if !stats.unconverted_positions.is_empty() {
+                        warn!("Left out of portfolio totals, no {} exchange rate: {}",
+                            port.base_currency(), stats.unconverted_positions.join(", "));
+                    }
 
                     // Show current positions from portfolio (should match TWS now)
                     let positions = port.get_all_positions();
@@ -649,6 +721,32 @@ async fn main() -> Result<()> {
                     }
                 }
             }
+            Ok(()) = config_updates.changed() => {
+                let reloaded = config_updates.borrow_and_update().clone();
+                let restart_required = config.apply_reload(reloaded);
+                if !restart_required.is_empty() {
+                    warn!("Config reload kept running values for {}; restart to apply them",
+                        restart_required.join(", "));
+                }
+                active_strategy.lock().await.update_config(&config.strategy_config);
+                risk_manager.lock().await.config = config.risk_config.clone();
+                risk_budgeter.lock().await.set_risk_config(config.risk_config.clone());
+                tws_client.market_data_handler.lock().await.apply_strategy_settings(&config.strategy_config);
+                let schedule = config.strategy_config.rebalance_schedule();
+                if schedule != rebalance_schedule {
+                    rebalance_schedule = schedule;
+                    next_rebalance = rebalance_schedule.next_run(clock.now());
+                    info!("Rebalance schedule changed, next rebalance at {}", next_rebalance);
+                }
+                info!("Reloaded configuration from {}", config_file);
+            }
+            _ = order_status_interval.tick() => {
+                match trading_cycle::reconcile_order_statuses(&broker, &order_manager).await {
+                    Ok(0) => {}
+                    Ok(updated) => info!("Reconciled {} order statuses with the broker", updated),
+                    Err(e) => warn!("Failed to poll order statuses: {}", e),
+                }
+            }
             _ = portfolio_update_interval.tick() => {
                 // Periodically update portfolio with latest prices
                 let handler_guard = tws_client.market_data_handler.lock().await;
@@ -663,8 +761,27 @@ async fn main() -> Result<()> {
                         stats.total_value, stats.total_unrealized_pnl);
                 }
 
+                let oldest_data_age = tws_client.market_data_handler.lock().await.oldest_data_age();
+                if let Some(age) = oldest_data_age {
+                    metrics.set_data_staleness_seconds(age.num_milliseconds() as f64 / 1000.0);
+                }
+
                 // Also fetch updated account data and positions
-                if let Ok(summary) = tws_client.get_account_summary().await {
+                if let Ok(summary) = broker.get_account_summary().await {
+                    // Daily loss circuit breaker
+                    if let Some(&net_liq) = summary.get("net_liquidation") {
+                        portfolio.lock().await.record_equity(clock.now(), net_liq);
+                        let mut risk_mgr = risk_manager.lock().await;
+                        let was_halted = risk_mgr.is_halted();
+                        risk_mgr.update_daily_pnl(net_liq, clock.now());
+                        if let (false, Some(halt), Some(journal)) = (was_halted, risk_mgr.trading_halt(), &journal) {
+                            journal.record(journal::JournalEvent::RiskHalt {
+                                loss_fraction: halt.loss_fraction,
+                                max_daily_loss: risk_mgr.config.max_daily_loss,
+                            });
+                        }
+                    }
+
                     if let (Some(net_liq), Some(unrealized_pnl)) =
                         (summary.get("net_liquidation"), summary.get("unrealized_pnl")) {
                         info!("Account: ${:.2} net, P&L ${:.2}",
@@ -678,20 +795,40 @@ async fn main() -> Result<()> {
                     }
 
                     // Periodically sync positions from TWS
-                    if let Ok(positions) = tws_client.get_positions().await {
+                    if let Ok(positions) = broker.get_positions().await {
                         let mut port = portfolio.lock().await;
-                        let mut strategy = momentum_strategy.lock().await;
+                        let mut strategy = active_strategy.lock().await;
+
+                        // Report drift before the sync overwrites the strategy's view,
+                        // comparing under the names the strategy tracks (pairs, contract months)
+                        let tracked: Vec<_> = positions
+                            .iter()
+                            .cloned()
+                            .map(|mut pos| {
+                                pos.symbol = port.tws_position_symbol(&pos);
+                                pos
+                            })
+                            .collect();
+                        let report = reconciliation::reconcile(strategy.get_positions(), &tracked);
+                        for mismatch in &report.mismatches {
+                            warn!("Position drift for {} ({:?}): strategy {} vs TWS {}",
+                                mismatch.symbol, mismatch.kind, mismatch.strategy_quantity, mismatch.broker_quantity);
+                            if let Some(journal) = &journal {
+                                journal.record(journal::JournalEvent::PositionMismatch {
+                                    symbol: mismatch.symbol.clone(),
+                                    strategy_quantity: mismatch.strategy_quantity,
+                                    broker_quantity: mismatch.broker_quantity,
+                                });
+                            }
+                        }
+                        debug!("Reconciled {} positions, {} mismatches", report.matched, report.mismatches.len());
 
                         for pos in &positions {
-                            // Sync with strategy
-                            strategy.update_position(&pos.symbol, pos.position);
-
-                            // Sync with portfolio using current market price
-                            let current_price = latest_prices.get(&pos.symbol)
-                                .copied()
-                                .unwrap_or(pos.avg_cost);
-                            port.sync_position_from_tws(pos, current_price);
+                            let symbol = port.tws_position_symbol(pos);
+                            strategy.update_position(&symbol, pos.position);
                         }
+                        // Books fills since the last sync, including closed positions
+                        port.sync_all_positions_from_tws(&positions, &latest_prices);
 
                         // Show current positions summary
                         if !positions.is_empty() {
@@ -700,11 +837,19 @@ async fn main() -> Result<()> {
                         } else {
                             info!("Positions: None");
                         }
+
+                        if config.risk_config.auto_protective_orders {
+                            drop(strategy);
+                            drop(port);
+                            let risk_mgr = risk_manager.lock().await;
+                            let mut order_mgr = order_manager.lock().await;
+                            trading_cycle::sync_protective_orders(&broker, &mut order_mgr, &risk_mgr, &positions).await;
+                        }
                     }
 
                     // Check margin health and update portfolio margin statistics
                     let mut port = portfolio.lock().await;
-                    let risk_mgr = risk_manager.lock().await;
+                    let mut risk_mgr = risk_manager.lock().await;
                     let margin_status = margin::check_margin_health(
                         &port,
                         &summary,
@@ -748,6 +893,11 @@ async fn main() -> Result<()> {
                     }
                     port.recalculate_margin_totals();
 
+                    let stats = port.get_stats();
+                    metrics.set_portfolio_value(stats.total_value);
+                    metrics.set_open_positions(stats.positions_count);
+                    metrics.set_drawdown(risk_mgr.current_drawdown());
+
                     // Generate and execute risk signals
                     let risk_signals = risk_mgr.generate_risk_signals(&port);
                     if !risk_signals.is_empty() {
@@ -785,16 +935,19 @@ async fn main() -> Result<()> {
                             let mut order_mgr = order_manager.lock().await;
 
                             for risk_signal in critical_signals {
-                                // Create risk reduction order
+                                // Trade against the position: shorts are bought back
+                                let is_short = port
+                                    .get_position(&risk_signal.symbol)
+                                    .is_some_and(|p| p.quantity < 0.0);
                                 let reduction_signal = orders::OrderSignal {
                                     symbol: risk_signal.symbol.clone(),
-                                    action: "SELL".to_string(), // Always sell to reduce exposure
+                                    action: if is_short { "BUY" } else { "SELL" }.to_string(),
                                     quantity: risk_signal.quantity,
                                     price: 0.0, // Market order - price will be filled by market
                                     order_type: "MKT".to_string(),
                                     limit_price: None, // Market order - no limit price
                                     reason: format!("RISK REDUCTION: {}", risk_signal.reason),
-                                    security_info: portfolio.lock().await
+                                    security_info: port
                                         .get_position(&risk_signal.symbol)
                                         .and_then(|p| p.security_info.clone())
                                         .unwrap_or_else(|| {
@@ -815,14 +968,18 @@ async fn main() -> Result<()> {
                                 );
 
                                 // Create and execute the risk reduction order
+                                trading_cycle::cancel_protective_legs(&broker, &mut order_mgr, &risk_signal.symbol).await;
                                 let order = order_mgr.create_order(reduction_signal.clone());
                                 info!("Created risk reduction order #{} for {}", order.id, order.symbol);
 
                                 // Execute the order through TWS
-                                match tws_client.place_order_from_order(&order).await {
+                                match broker.place_order_from_order(&order).await {
                                     Ok(tws_order_id) => {
                                         info!("Successfully submitted risk reduction order for {} (TWS ID: {})", order.symbol, tws_order_id);
                                         let _ = order_mgr.update_order_status(order.id, orders::OrderStatus::Submitted);
+                                        order_mgr.record_broker_order_id(order.id, tws_order_id);
+                                        risk_mgr.start_cooldown(&order.symbol);
+                                        record_order_submitted(&journal, &order, tws_order_id);
                                         // NOTE: Don't update portfolio here - wait for TWS position sync
                                         // Portfolio will be updated when TWS confirms the position change
                                     }
@@ -840,10 +997,25 @@ async fn main() -> Result<()> {
                 info!("Shutting down momentum trading bot...");
                 break;
             }
+            _ = &mut terminate => {
+                info!("Received SIGTERM, shutting down momentum trading bot...");
+                break;
+            }
+            _ = &mut emergency => {
+                warn!("Received SIGUSR1: emergency stop");
+                let portfolio_guard = portfolio.lock().await;
+                match broker.emergency_stop(&portfolio_guard).await {
+                    Ok(order_ids) => warn!("Emergency stop placed {} flattening orders", order_ids.len()),
+                    Err(e) => error!("Emergency stop incomplete: {}", e),
+                }
+                break;
+            }
         }
     }
 
     // Cleanup
+    shutdown(&tws_client, &config.shutdown, &journal).await;
+    drop(broker);
     // Try to get exclusive access to disconnect, but don't panic if other refs exist
     if let Ok(mut tws_client_mut) = Arc::try_unwrap(tws_client) {
         tws_client_mut.disconnect().await?;
@@ -855,4 +1027,199 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Poll `path` and publish each change that loads and validates
+///
+/// A file that fails to parse or validate is logged and skipped, leaving the
+/// running config in force.
+fn spawn_config_watcher(
+    path: String,
+    config: config::TradingConfig,
+) -> watch::Receiver<config::TradingConfig> {
+    let (sender, receiver) = watch::channel(config);
+    let modified = |path: &str| {
+        std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+    };
+    tokio::spawn(async move {
+        let mut last_modified = modified(&path);
+        let mut poll = interval(Duration::from_secs(5));
+        loop {
+            poll.tick().await;
+            let current = modified(&path);
+            if current.is_none() || current == last_modified {
+                continue;
+            }
+            last_modified = current;
+            match config::TradingConfig::load_from_file(&path) {
+                Ok(reloaded) => {
+                    if sender.send(reloaded).is_err() {
+                        break; // Trading loop has exited
+                    }
+                }
+                Err(e) => warn!("Ignoring changed {}: {:#}", path, e),
+            }
+        }
+    });
+    receiver
+}
+
+/// `--check`: connect, run the health check and print its report
+///
+/// Verifies against the first configured security; places no orders.
+async fn run_health_check(config_file: &str) -> Result<()> {
+    info!(
+        "Running health check with configuration from: {}",
+        config_file
+    );
+    let config = config::TradingConfig::load_from_file(config_file)?;
+
+    let report = match connection::TwsClient::new(config.tws_config.clone()).await {
+        Ok(tws_client) => {
+            tws_client
+                .health_check(config.strategy_config.securities.first())
+                .await
+        }
+        Err(e) => {
+            let mut report = health::HealthReport::new();
+            report.record("connection", Err(e));
+            report
+        }
+    };
+    println!("{}", report);
+
+    if !report.passed() {
+        bail!(
+            "Health check failed: {} of {} checks failed",
+            report.failures().len(),
+            report.checks.len()
+        );
+    }
+    Ok(())
+}
+
+/// Stop subscriptions, optionally cancel working orders, and persist state
+/// before disconnecting
+///
+/// Each step logs its own failure so a broken one never skips the rest.
+async fn shutdown(
+    tws_client: &connection::TwsClient,
+    config: &config::ShutdownConfig,
+    journal: &Option<Arc<journal::Journal>>,
+) {
+    tws_client.cancel_all_subscriptions().await;
+
+    if config.cancel_orders_on_exit
+        && let Err(e) = tws_client.cancel_all_orders()
+    {
+        error!("Failed to cancel working orders on exit: {}", e);
+    }
+
+    if let Some(path) = &config.price_history_path {
+        let handler = tws_client.market_data_handler.lock().await;
+        match handler.save_price_history(std::path::Path::new(path)) {
+            Ok(saved) => info!("Saved price history for {} symbols to {}", saved, path),
+            Err(e) => error!("Failed to save price history to {}: {}", path, e),
+        }
+    }
+
+    if let Some(journal) = journal
+        && let Err(e) = journal.sync()
+    {
+        error!("Failed to sync journal {}: {}", journal.path().display(), e);
+    }
+}
+
+/// Resolves on SIGTERM, which shuts down gracefully like ctrl-c so service
+/// managers and container stops never flatten the book
+#[cfg(unix)]
+async fn terminate_signal() {
+    unix_signal(tokio::signal::unix::SignalKind::terminate(), "SIGTERM").await;
+}
+
+/// Resolves on SIGUSR1, the explicit request to cancel every working order
+/// and flatten all positions
+#[cfg(unix)]
+async fn emergency_signal() {
+    unix_signal(tokio::signal::unix::SignalKind::user_defined1(), "SIGUSR1").await;
+}
+
+#[cfg(unix)]
+async fn unix_signal(kind: tokio::signal::unix::SignalKind, name: &str) {
+    match tokio::signal::unix::signal(kind) {
+        Ok(mut stream) => {
+            stream.recv().await;
+        }
+        Err(e) => {
+            error!("Failed to listen for {}: {}", name, e);
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn terminate_signal() {
+    std::future::pending::<()>().await;
+}
+
+#[cfg(not(unix))]
+async fn emergency_signal() {
+    std::future::pending::<()>().await;
+}
+
+/// Journal that an order reached TWS
+fn record_order_submitted(
+    journal: &Option<Arc<journal::Journal>>,
+    order: &orders::Order,
+    tws_order_id: i32,
+) {
+    if let Some(journal) = journal {
+        journal.record(journal::JournalEvent::OrderSubmitted {
+            order_id: order.id,
+            tws_order_id,
+            symbol: order.symbol.clone(),
+            action: order.action.clone(),
+            quantity: order.quantity,
+        });
+    }
+}
+
+/// Chase an unfilled limit order in the background, keeping the order manager
+/// pointed at each replacement
+fn spawn_order_chase(
+    broker: broker::BrokerHandle,
+    order_manager: Arc<Mutex<orders::OrderManager>>,
+    chaser: execution::OrderChaser,
+    submitted: &trading_cycle::SubmittedOrder,
+) {
+    let broker_order_id = submitted.broker_order_id;
+    let signal = submitted.signal.clone();
+    tokio::spawn(async move {
+        let outcome = chaser
+            .chase_with(&broker, broker_order_id, &signal, |old, new| {
+                let order_manager = order_manager.clone();
+                async move {
+                    order_manager.lock().await.replace_broker_order_id(old, new);
+                }
+            })
+            .await;
+        match outcome {
+            Ok(outcome) => info!("Chase for {} ended: {:?}", signal.symbol, outcome),
+            Err(e) => warn!("Chase for {} failed: {:#}", signal.symbol, e),
+        }
+    });
+}
+
+/// Journal what became of each candidate signal, when enabled
+fn record_signal_decisions(
+    journal: &Option<Arc<journal::Journal>>,
+    decisions: Vec<decisions::SignalDecision>,
+) {
+    if let Some(journal) = journal {
+        for decision in decisions {
+            journal.record(decision.into());
+        }
+    }
+}
+
 // TwsClient is not cloneable by design to prevent multiple concurrent access
@@ -1,7 +1,25 @@
-use crate::security_types::SecurityInfo;
-use chrono::{DateTime, Duration, Utc};
+use crate::bollinger::VolatilityRegime;
+use crate::clock::{SharedClock, system_clock};
+use crate::config::{StrategyConfig, TradingCalendar};
+use crate::security_types::{SecurityInfo, SecurityType};
+use crate::stats::RollingStats;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use std::collections::HashMap;
 
+/// Default fraction a real-time price may move from the last good price
+pub const DEFAULT_MAX_TICK_DEVIATION: f64 = 0.5;
+
+/// Default largest per-period return kept when computing momentum statistics
+pub const DEFAULT_RETURN_OUTLIER_THRESHOLD: f64 = 0.5;
+
+/// Consecutive out-of-band ticks, each within the deviation limit of the one
+/// before, after which a move is taken as genuine (a gap or limit move) and
+/// the last good price re-anchors to it
+pub const REANCHOR_TICKS: usize = 3;
+
+/// Age after which a top-of-book quote no longer stands for the market
+pub const MAX_QUOTE_AGE_SECONDS: i64 = 60;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum TimeFrame {
     Minutes15,
@@ -38,6 +56,23 @@ impl TimeFrame {
         Duration::minutes(self.to_minutes())
     }
 
+    /// Return periods of this timeframe in a year of `trading_days`
+    pub fn periods_per_year(self, trading_days: f64) -> f64 {
+        match self {
+            TimeFrame::Minutes15 => trading_days * 24.0 * 4.0, // 15-min periods per year
+            TimeFrame::Hours1 => trading_days * 24.0,          // Hours per year
+            TimeFrame::Hours4 => trading_days * 6.0,           // 4-hour periods per year
+            TimeFrame::Days1 => trading_days,                  // Days per year
+            TimeFrame::Days7 => 52.0,                          // Weeks per year
+            TimeFrame::Days14 => 26.0,                         // Bi-weeks per year
+            // For Carver's momentum timeframes, use the midpoint period
+            TimeFrame::Days2_8 => trading_days / 5.0,
+            TimeFrame::Days4_16 => trading_days / 10.0,
+            TimeFrame::Days8_32 => trading_days / 20.0,
+            TimeFrame::Days16_64 => trading_days / 40.0,
+        }
+    }
+
     pub fn label(&self) -> &'static str {
         match self {
             TimeFrame::Minutes15 => "15m",
@@ -95,6 +130,158 @@ impl TimeFrame {
     }
 }
 
+/// Estimator used for the volatility behind momentum risk adjustment
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum VolatilityEstimator {
+    #[default]
+    Simple, // Equal-weighted sample standard deviation
+    Ewma {
+        lambda: f64,
+    }, // RiskMetrics exponentially-weighted estimator
+}
+
+/// How per-timeframe signals are combined into one composite
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregationMethod {
+    #[default]
+    Mean, // Weighted average of the signals
+    ConsensusWeighted, // Mean scaled by the fraction of signals sharing its sign
+    Strongest,         // The largest-magnitude signal, sign kept
+}
+
+impl AggregationMethod {
+    /// Combine `(signal, weight)` pairs; generators without timeframe
+    /// weights pass 1.0. None when there is nothing to combine.
+    pub fn aggregate(self, signals: &[(f64, f64)]) -> Option<f64> {
+        let weight_sum: f64 = signals.iter().map(|(_, weight)| weight).sum();
+        if signals.is_empty() || weight_sum <= 0.0 {
+            return None;
+        }
+        let mean = signals
+            .iter()
+            .map(|(signal, weight)| signal * weight)
+            .sum::<f64>()
+            / weight_sum;
+        match self {
+            AggregationMethod::Mean => Some(mean),
+            AggregationMethod::ConsensusWeighted => {
+                let agreeing = signals
+                    .iter()
+                    .filter(|(signal, _)| *signal != 0.0 && signal.signum() == mean.signum())
+                    .count();
+                Some(mean * agreeing as f64 / signals.len() as f64)
+            }
+            AggregationMethod::Strongest => signals
+                .iter()
+                .map(|(signal, _)| *signal)
+                .max_by(|a, b| a.abs().total_cmp(&b.abs())),
+        }
+    }
+}
+
+/// How a price change is turned into a return in the momentum math
+///
+/// Simple returns divide by the starting price and are meaningless for
+/// instruments that trade at or through zero (spreads, some futures). Those
+/// can use absolute changes in price units instead; every ratio built from
+/// them (risk-adjusted momentum, Sharpe) is still scale-free because the
+/// volatility is measured in the same units.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReturnMode {
+    #[default]
+    Simple, // (end - start) / start, requires a positive start price
+    Log,      // ln(end / start), requires both prices positive
+    Absolute, // end - start, defined for any sign
+}
+
+impl ReturnMode {
+    /// Return from `start` to `end`, or None where the mode is undefined
+    /// (a non-positive denominator, or a log across zero)
+    pub fn period_return(self, start: f64, end: f64) -> Option<f64> {
+        match self {
+            ReturnMode::Simple => (start > 0.0).then(|| (end - start) / start),
+            ReturnMode::Log => (start > 0.0 && end > 0.0).then(|| (end / start).ln()),
+            ReturnMode::Absolute => Some(end - start),
+        }
+    }
+}
+
+/// Handling of gaps in price history
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DataGapConfig {
+    #[serde(default = "default_bar_interval_seconds")]
+    pub bar_interval_seconds: i64,
+    #[serde(default = "default_gap_tolerance")]
+    pub tolerance: f64, // Gaps longer than this many bar intervals are reported
+    #[serde(default)]
+    pub reject_momentum_across_gaps: bool,
+    #[serde(default)]
+    pub max_fill_intervals: usize, // Forward-fill gaps of up to this many missing bars (0 = off)
+}
+
+impl Default for DataGapConfig {
+    fn default() -> Self {
+        Self {
+            bar_interval_seconds: default_bar_interval_seconds(),
+            tolerance: default_gap_tolerance(),
+            reject_momentum_across_gaps: false,
+            max_fill_intervals: 0,
+        }
+    }
+}
+
+fn default_bar_interval_seconds() -> i64 {
+    86_400 // Daily bars
+}
+
+fn default_gap_tolerance() -> f64 {
+    4.0 // Allows weekends and a holiday on daily bars
+}
+
+impl DataGapConfig {
+    pub fn bar_interval(&self) -> Duration {
+        Duration::seconds(self.bar_interval_seconds)
+    }
+}
+
+/// Per-security-type cap on absolute period returns; larger moves are
+/// dropped from return statistics as likely data errors
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ReturnOutlierThresholds {
+    #[serde(default = "default_return_outlier_threshold")]
+    pub stock: f64,
+    #[serde(default = "default_return_outlier_threshold")]
+    pub future: f64,
+    #[serde(default = "default_return_outlier_threshold")]
+    pub forex: f64,
+}
+
+impl Default for ReturnOutlierThresholds {
+    fn default() -> Self {
+        Self {
+            stock: DEFAULT_RETURN_OUTLIER_THRESHOLD,
+            future: DEFAULT_RETURN_OUTLIER_THRESHOLD,
+            forex: DEFAULT_RETURN_OUTLIER_THRESHOLD,
+        }
+    }
+}
+
+fn default_return_outlier_threshold() -> f64 {
+    DEFAULT_RETURN_OUTLIER_THRESHOLD
+}
+
+impl ReturnOutlierThresholds {
+    pub fn for_security_type(&self, security_type: &SecurityType) -> f64 {
+        match security_type {
+            SecurityType::Stock => self.stock,
+            SecurityType::Future => self.future,
+            SecurityType::Forex => self.forex,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct EnhancedMomentumMetrics {
     pub simple_momentum: f64,
@@ -102,8 +289,10 @@ pub struct EnhancedMomentumMetrics {
     pub volatility_normalized_momentum: f64,
     pub momentum_acceleration: f64,
     pub volatility: f64,
-    pub sharpe_ratio: f64,
+    pub sharpe_ratio: f64,      // Lookback return per unit of period volatility
+    pub annualized_sharpe: f64, // (annualized return - risk-free rate) / annualized volatility
     pub timeframe: TimeFrame,
+    pub filtered_returns: usize, // Returns dropped as outliers before computing the statistics
 }
 
 #[derive(Debug, Clone)]
@@ -120,33 +309,98 @@ pub struct MarketDataUpdate {
     pub last_price: f64,
     pub bid_price: f64,
     pub ask_price: f64,
+    pub quote_timestamp: Option<DateTime<Utc>>, // When bid and ask were quoted; None when approximated
     pub volume: i64,
     pub timestamp: DateTime<Utc>,
 }
 
+/// Message delivered to real-time subscribers
+#[derive(Debug, Clone)]
+pub enum MarketDataEvent {
+    Update(MarketDataUpdate),
+    /// The subscription failed; no price accompanies it
+    Error {
+        symbol: String,
+        message: String,
+    },
+}
+
+impl MarketDataEvent {
+    pub fn symbol(&self) -> &str {
+        match self {
+            MarketDataEvent::Update(update) => &update.symbol,
+            MarketDataEvent::Error { symbol, .. } => symbol,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MarketData {
     pub symbol: String,
     pub last_price: f64,
     pub bid_price: f64,
     pub ask_price: f64,
+    pub quote_timestamp: Option<DateTime<Utc>>, // When bid and ask were last quoted
     pub volume: i64,
     pub timestamp: DateTime<Utc>,
     pub security_info: Option<SecurityInfo>,
 }
 
-#[derive(Debug, Clone)]
+impl MarketData {
+    /// Bid and ask, when a usable quote has been received
+    pub fn quote(&self) -> Option<(f64, f64)> {
+        is_valid_quote(self.bid_price, self.ask_price).then_some((self.bid_price, self.ask_price))
+    }
+
+    /// Bid-ask spread relative to the mid price, in basis points
+    pub fn spread_bps(&self) -> Option<f64> {
+        let (bid, ask) = self.quote()?;
+        let mid = (bid + ask) / 2.0;
+        Some((ask - bid) / mid * 10_000.0)
+    }
+}
+
+fn is_valid_quote(bid: f64, ask: f64) -> bool {
+    bid.is_finite() && ask.is_finite() && bid > 0.0 && ask >= bid
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PriceHistory {
     pub symbol: String,
     pub prices: Vec<(DateTime<Utc>, f64)>,
+    /// Bar volume, index-aligned with `prices` (0 when unknown)
+    pub volumes: Vec<f64>,
     pub max_size: usize,
 }
 
+impl PriceHistory {
+    /// Drop the oldest bars beyond `max_size`
+    fn trim(&mut self) {
+        if self.prices.len() > self.max_size {
+            let excess = self.prices.len() - self.max_size;
+            self.prices.drain(0..excess);
+            self.volumes.drain(0..excess);
+        }
+    }
+}
+
 pub struct MarketDataHandler {
     data: HashMap<i32, MarketData>,
     symbol_map: HashMap<i32, String>,
     price_history: HashMap<String, PriceHistory>,
     security_map: HashMap<String, SecurityInfo>,
+    gap_config: DataGapConfig,
+    return_outliers: ReturnOutlierThresholds,
+    max_tick_deviation: f64,
+    risk_free_rate: f64,
+    trading_calendar: Option<TradingCalendar>,
+    return_modes: HashMap<String, ReturnMode>,
+    aggregation: AggregationMethod,
+    rejected_ticks: HashMap<String, u64>,
+    suspect_ticks: HashMap<String, (f64, usize)>, // Latest rejected price and how many agreed in a row
+    /// Per-symbol stats of period returns across the whole price history
+    rolling_returns: HashMap<String, RollingStats>,
+    clock: SharedClock,
 }
 
 impl Default for MarketDataHandler {
@@ -162,7 +416,238 @@ impl MarketDataHandler {
             symbol_map: HashMap::new(),
             price_history: HashMap::new(),
             security_map: HashMap::new(),
+            gap_config: DataGapConfig::default(),
+            return_outliers: ReturnOutlierThresholds::default(),
+            max_tick_deviation: DEFAULT_MAX_TICK_DEVIATION,
+            risk_free_rate: 0.0,
+            trading_calendar: None,
+            return_modes: HashMap::new(),
+            aggregation: AggregationMethod::default(),
+            rejected_ticks: HashMap::new(),
+            suspect_ticks: HashMap::new(),
+            rolling_returns: HashMap::new(),
+            clock: system_clock(),
+        }
+    }
+
+    /// Time source for update timestamps and staleness checks
+    pub fn set_clock(&mut self, clock: SharedClock) {
+        self.clock = clock;
+    }
+
+    /// Strategy settings the handler reads on every calculation
+    pub fn apply_strategy_settings(&mut self, strategy_config: &StrategyConfig) {
+        self.set_gap_config(strategy_config.data_gaps.clone());
+        self.set_return_outlier_thresholds(strategy_config.return_outliers);
+        self.set_max_tick_deviation(strategy_config.max_tick_deviation);
+        self.set_risk_free_rate(strategy_config.risk_free_rate);
+        self.set_aggregation_method(strategy_config.aggregation_method);
+        self.set_trading_calendar(strategy_config.trading_calendar);
+    }
+
+    pub fn set_gap_config(&mut self, gap_config: DataGapConfig) {
+        self.gap_config = gap_config;
+    }
+
+    pub fn set_return_outlier_thresholds(&mut self, thresholds: ReturnOutlierThresholds) {
+        self.return_outliers = thresholds;
+    }
+
+    /// Largest absolute period return kept in `symbol`'s return statistics;
+    /// unregistered symbols use the stock threshold
+    pub fn return_outlier_threshold(&self, symbol: &str) -> f64 {
+        self.security_map
+            .get(symbol)
+            .map_or(self.return_outliers.stock, |info| {
+                self.return_outliers.for_security_type(&info.security_type)
+            })
+    }
+
+    /// Reject real-time prices more than this fraction away from the last
+    /// good price
+    pub fn set_max_tick_deviation(&mut self, max_tick_deviation: f64) {
+        self.max_tick_deviation = max_tick_deviation;
+    }
+
+    /// Annual risk-free rate subtracted in `annualized_sharpe`
+    pub fn set_risk_free_rate(&mut self, risk_free_rate: f64) {
+        self.risk_free_rate = risk_free_rate;
+    }
+
+    /// Use one calendar for every symbol instead of the per-security-type
+    /// default
+    pub fn set_trading_calendar(&mut self, trading_calendar: Option<TradingCalendar>) {
+        self.trading_calendar = trading_calendar;
+    }
+
+    /// Calendar used to annualize `symbol`'s statistics; unregistered
+    /// symbols use the equities calendar
+    pub fn trading_calendar(&self, symbol: &str) -> TradingCalendar {
+        self.trading_calendar.unwrap_or_else(|| {
+            self.security_map
+                .get(symbol)
+                .map_or(TradingCalendar::Equities252, |info| {
+                    TradingCalendar::for_security_type(&info.security_type)
+                })
+        })
+    }
+
+    /// How multi-timeframe momentum combines its timeframes
+    pub fn set_aggregation_method(&mut self, aggregation: AggregationMethod) {
+        self.aggregation = aggregation;
+    }
+
+    pub fn set_return_mode(&mut self, symbol: &str, mode: ReturnMode) {
+        self.return_modes.insert(symbol.to_string(), mode);
+    }
+
+    /// Return mode for `symbol`'s momentum; simple returns unless configured
+    pub fn return_mode(&self, symbol: &str) -> ReturnMode {
+        self.return_modes.get(symbol).copied().unwrap_or_default()
+    }
+
+    /// Outlier threshold for `symbol`'s bar returns under its return mode;
+    /// absolute changes are in price units, so the fractional threshold
+    /// does not apply to them
+    fn bar_outlier_threshold(&self, symbol: &str) -> f64 {
+        match self.return_mode(symbol) {
+            ReturnMode::Absolute => f64::INFINITY,
+            ReturnMode::Simple | ReturnMode::Log => self.return_outlier_threshold(symbol),
+        }
+    }
+
+    /// Real-time updates rejected as bad ticks for a symbol
+    pub fn rejected_tick_count(&self, symbol: &str) -> u64 {
+        self.rejected_ticks.get(symbol).copied().unwrap_or(0)
+    }
+
+    /// Latest accepted price, from real-time data or else history
+    fn last_good_price(&self, symbol: &str) -> Option<f64> {
+        self.get_market_data(symbol)
+            .map(|data| data.last_price)
+            .filter(|price| *price > 0.0)
+            .or_else(|| {
+                self.price_history
+                    .get(symbol)?
+                    .prices
+                    .last()
+                    .map(|(_, p)| *p)
+            })
+            .filter(|price| *price > 0.0)
+    }
+
+    /// Track an out-of-band tick; true once `REANCHOR_TICKS` of them in a row
+    /// agree with each other
+    fn confirms_move(&mut self, symbol: &str, price: f64) -> bool {
+        if !self.is_valid_price(symbol, price) {
+            return false;
+        }
+        let max_deviation = self.max_tick_deviation;
+        let run = self
+            .suspect_ticks
+            .entry(symbol.to_string())
+            .or_insert((price, 0));
+        if (price / run.0 - 1.0).abs() <= max_deviation {
+            run.1 += 1;
+        } else {
+            *run = (price, 1);
+        }
+        run.0 = price;
+        run.1 >= REANCHOR_TICKS
+    }
+
+    /// Finite, and positive unless `symbol` is on absolute returns and so
+    /// may trade at or through zero
+    fn is_valid_price(&self, symbol: &str, price: f64) -> bool {
+        price.is_finite() && (price > 0.0 || self.return_mode(symbol) == ReturnMode::Absolute)
+    }
+
+    /// Why a tick should be rejected, if it should
+    ///
+    /// The deviation check is fractional, so like the return outlier filter
+    /// it does not apply to symbols on absolute returns.
+    fn bad_tick_reason(&self, symbol: &str, price: f64) -> Option<String> {
+        if !self.is_valid_price(symbol, price) {
+            return Some(format!("invalid price {}", price));
+        }
+        if self.return_mode(symbol) == ReturnMode::Absolute {
+            return None;
+        }
+        let last = self.last_good_price(symbol)?;
+        let deviation = (price / last - 1.0).abs();
+        (deviation > self.max_tick_deviation).then(|| {
+            format!(
+                "price {} is {:.1}% away from last good price {}",
+                price,
+                deviation * 100.0,
+                last
+            )
+        })
+    }
+
+    /// Gaps in a symbol's history longer than `tolerance` times the
+    /// configured bar interval, as (last price before, first price after) times
+    pub fn detect_gaps(&self, symbol: &str) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        self.get_price_history(symbol)
+            .map(|history| self.gaps_in(&history.prices, self.gap_config.bar_interval()))
+            .unwrap_or_default()
+    }
+
+    fn gaps_in(
+        &self,
+        prices: &[(DateTime<Utc>, f64)],
+        expected_interval: Duration,
+    ) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let max_seconds = expected_interval.num_seconds() as f64 * self.gap_config.tolerance;
+        prices
+            .windows(2)
+            .filter(|pair| (pair[1].0 - pair[0].0).num_seconds() as f64 > max_seconds)
+            .map(|pair| (pair[0].0, pair[1].0))
+            .collect()
+    }
+
+    /// Forward-fill gaps of up to `max_fill_intervals` missing bars
+    ///
+    /// Inserts bars at each missing interval carrying the last known price.
+    /// Returns the number of bars added; a no-op unless configured.
+    pub fn fill_small_gaps(&mut self, symbol: &str) -> usize {
+        let max_fill = self.gap_config.max_fill_intervals;
+        let interval = self.gap_config.bar_interval();
+        let Some(history) = self.price_history.get_mut(symbol) else {
+            return 0;
+        };
+        if max_fill == 0 || interval <= Duration::zero() {
+            return 0;
+        }
+
+        let mut filled = Vec::with_capacity(history.prices.len());
+        let mut volumes = Vec::with_capacity(history.volumes.len());
+        let mut added = 0;
+        for (i, pair) in history.prices.windows(2).enumerate() {
+            let (start, price) = pair[0];
+            filled.push(pair[0]);
+            volumes.push(history.volumes[i]);
+
+            let missing = ((pair[1].0 - start).num_seconds() / interval.num_seconds() - 1).max(0);
+            if missing as usize <= max_fill {
+                // Filled bars carry the price forward but saw no trading
+                for step in 1..=missing {
+                    filled.push((start + interval * step as i32, price));
+                    volumes.push(0.0);
+                }
+                added += missing as usize;
+            }
+        }
+        filled.extend(history.prices.last().copied());
+        volumes.extend(history.volumes.last().copied());
+
+        history.prices = filled;
+        history.volumes = volumes;
+        history.trim();
+        if added > 0 {
+            self.rebuild_rolling_returns(symbol);
         }
+        added
     }
 
     pub fn register_symbol(&mut self, req_id: i32, symbol: String) {
@@ -174,8 +659,9 @@ impl MarketDataHandler {
                 last_price: 0.0,
                 bid_price: 0.0,
                 ask_price: 0.0,
+                quote_timestamp: None,
                 volume: 0,
-                timestamp: Utc::now(),
+                timestamp: self.clock.now(),
                 security_info: self.security_map.get(&symbol).cloned(),
             },
         );
@@ -185,6 +671,7 @@ impl MarketDataHandler {
                 PriceHistory {
                     symbol,
                     prices: Vec::new(),
+                    volumes: Vec::new(),
                     max_size: 1000,
                 },
             );
@@ -196,27 +683,187 @@ impl MarketDataHandler {
         symbol: &str,
         timestamp: time::OffsetDateTime,
         price: f64,
+    ) {
+        self.add_historical_bar(symbol, timestamp, price, 0.0);
+    }
+
+    /// Add a historical close together with the bar's traded volume
+    pub fn add_historical_bar(
+        &mut self,
+        symbol: &str,
+        timestamp: time::OffsetDateTime,
+        price: f64,
+        volume: f64,
     ) {
         if let Some(history) = self.price_history.get_mut(symbol) {
             // Convert time::OffsetDateTime to chrono::DateTime<Utc>
             let datetime =
                 DateTime::from_timestamp(timestamp.unix_timestamp(), 0).unwrap_or_else(Utc::now);
 
-            history.prices.push((datetime, price));
-
             // Keep prices sorted by time
-            history.prices.sort_by_key(|(dt, _)| *dt);
+            let index = history.prices.partition_point(|(dt, _)| *dt <= datetime);
+            history.prices.insert(index, (datetime, price));
+            history.volumes.insert(index, volume.max(0.0));
+            let appended = index + 1 == history.prices.len();
+
+            history.trim();
+            if appended {
+                self.push_rolling_return(symbol);
+            } else {
+                self.rebuild_rolling_returns(symbol);
+            }
+        }
+    }
+
+    /// Fold the newest history price's return into the symbol's rolling stats
+    fn push_rolling_return(&mut self, symbol: &str) {
+        let Some(history) = self.price_history.get(symbol) else {
+            return;
+        };
+        let stats = self
+            .rolling_returns
+            .entry(symbol.to_string())
+            .or_insert_with(|| RollingStats::new(history.max_size.saturating_sub(1)));
+        if let [.., (_, previous), (_, latest)] = history.prices.as_slice()
+            && *previous > 0.0
+        {
+            stats.push(latest / previous - 1.0);
+        }
+    }
+
+    /// Recompute a symbol's rolling stats after history changed out of order
+    fn rebuild_rolling_returns(&mut self, symbol: &str) {
+        let Some(history) = self.price_history.get(symbol) else {
+            return;
+        };
+        let mut stats = RollingStats::new(history.max_size.saturating_sub(1));
+        for pair in history.prices.windows(2) {
+            if pair[0].1 > 0.0 {
+                stats.push(pair[1].1 / pair[0].1 - 1.0);
+            }
+        }
+        self.rolling_returns.insert(symbol.to_string(), stats);
+    }
+
+    /// Per-period (not annualized) volatility of returns across a symbol's
+    /// price history, maintained incrementally
+    pub fn rolling_volatility(&self, symbol: &str) -> Option<f64> {
+        self.rolling_returns.get(symbol)?.std_dev()
+    }
+
+    /// Where the latest `lookback`-return realized volatility ranks among every
+    /// earlier window in the symbol's history, as a fraction in [0, 1]
+    ///
+    /// Needs at least `MIN_REGIME_WINDOWS` windows to rank against.
+    pub fn volatility_percentile(&self, symbol: &str, lookback: usize) -> Option<f64> {
+        if lookback < 2 {
+            return None;
+        }
+        let history = self.get_price_history(symbol)?;
+        let (returns, _) = filtered_returns(&history.prices, self.return_outlier_threshold(symbol));
+        let vols: Vec<f64> = returns
+            .windows(lookback)
+            .map(|window| sample_variance(window).sqrt())
+            .collect();
+        let (current, past) = vols.split_last()?;
+        if past.len() < MIN_REGIME_WINDOWS {
+            return None;
+        }
+
+        // Mid-rank, so a history of identical windows sits at the median
+        let below = past.iter().filter(|vol| *vol < current).count();
+        let equal = past.iter().filter(|vol| *vol == current).count();
+        Some((below as f64 + equal as f64 / 2.0) / past.len() as f64)
+    }
+
+    /// Regime of a symbol's current realized volatility against its own history
+    ///
+    /// Top tercile is `High`, bottom tercile `Low`.
+    pub fn volatility_regime(&self, symbol: &str, lookback: usize) -> Option<VolatilityRegime> {
+        self.volatility_percentile(symbol, lookback)
+            .map(regime_for_percentile)
+    }
 
-            // Trim to max size if needed
-            if history.prices.len() > history.max_size {
-                let excess = history.prices.len() - history.max_size;
-                history.prices.drain(0..excess);
+    /// Regime of the whole universe from the average volatility percentile of
+    /// every symbol with enough history
+    pub fn market_volatility_regime(&self, lookback: usize) -> Option<VolatilityRegime> {
+        let percentiles: Vec<f64> = self
+            .price_history
+            .keys()
+            .filter_map(|symbol| self.volatility_percentile(symbol, lookback))
+            .collect();
+        if percentiles.is_empty() {
+            return None;
+        }
+        Some(regime_for_percentile(mean(&percentiles)))
+    }
+
+    /// Apply an event from a real-time subscription
+    ///
+    /// Errors are logged and never touch prices. Returns whether a tick was
+    /// accepted.
+    pub fn apply_event(&mut self, event: MarketDataEvent) -> bool {
+        match event {
+            MarketDataEvent::Update(update) => {
+                let accepted = self.update_realtime_data_at(
+                    &update.symbol,
+                    update.last_price,
+                    update.volume,
+                    update.timestamp,
+                );
+                // Approximated bid and ask are for display only, never stored
+                if accepted && let Some(quoted_at) = update.quote_timestamp {
+                    self.update_quote_at(
+                        &update.symbol,
+                        update.bid_price,
+                        update.ask_price,
+                        quoted_at,
+                    );
+                }
+                accepted
+            }
+            MarketDataEvent::Error { symbol, message } => {
+                log::warn!("Market data subscription error for {}: {}", symbol, message);
+                false
             }
         }
     }
 
-    pub fn update_realtime_data(&mut self, symbol: &str, price: f64, volume: i64) {
-        let timestamp = Utc::now();
+    pub fn update_realtime_data(&mut self, symbol: &str, price: f64, volume: i64) -> bool {
+        self.update_realtime_data_at(symbol, price, volume, self.clock.now())
+    }
+
+    /// Record a real-time update received at `timestamp`
+    ///
+    /// Returns false, leaving prices and history untouched, when the price is
+    /// rejected as a bad tick.
+    pub fn update_realtime_data_at(
+        &mut self,
+        symbol: &str,
+        price: f64,
+        volume: i64,
+        timestamp: DateTime<Utc>,
+    ) -> bool {
+        if let Some(reason) = self.bad_tick_reason(symbol, price) {
+            if !self.confirms_move(symbol, price) {
+                let count = self.rejected_ticks.entry(symbol.to_string()).or_insert(0);
+                *count += 1;
+                log::warn!(
+                    "Rejected bad tick for {}: {} ({} rejected so far)",
+                    symbol,
+                    reason,
+                    count
+                );
+                return false;
+            }
+            log::warn!(
+                "Re-anchoring {} after {} consistent ticks: {}",
+                symbol,
+                REANCHOR_TICKS,
+                reason
+            );
+        }
+        self.suspect_ticks.remove(symbol);
 
         // Update current market data
         if let Some(req_id) = self
@@ -229,26 +876,183 @@ impl MarketDataHandler {
                 data.last_price = price;
                 data.volume = volume;
                 data.timestamp = timestamp;
+                // Drop a quote the book has not refreshed, so limit prices and
+                // spread checks fall back to the last price
+                if data.quote_timestamp.is_some_and(|quoted_at| {
+                    timestamp - quoted_at > Duration::seconds(MAX_QUOTE_AGE_SECONDS)
+                }) {
+                    data.bid_price = 0.0;
+                    data.ask_price = 0.0;
+                    data.quote_timestamp = None;
+                }
             }
         }
 
         // Add to price history
         if let Some(history) = self.price_history.get_mut(symbol) {
             history.prices.push((timestamp, price));
-            if history.prices.len() > history.max_size {
-                history.prices.remove(0);
+            history.volumes.push(volume.max(0) as f64);
+            history.trim();
+            self.push_rolling_return(symbol);
+        }
+        true
+    }
+
+    /// Record the latest bid and ask for a subscribed symbol
+    ///
+    /// Crossed, zero or non-finite quotes are ignored. Returns whether the
+    /// quote was stored.
+    pub fn update_quote(&mut self, symbol: &str, bid: f64, ask: f64) -> bool {
+        self.update_quote_at(symbol, bid, ask, self.clock.now())
+    }
+
+    /// Record a bid and ask quoted at `quoted_at`
+    pub fn update_quote_at(
+        &mut self,
+        symbol: &str,
+        bid: f64,
+        ask: f64,
+        quoted_at: DateTime<Utc>,
+    ) -> bool {
+        if !is_valid_quote(bid, ask) {
+            log::debug!("Ignoring quote for {}: bid {} ask {}", symbol, bid, ask);
+            return false;
+        }
+        match self.data.values_mut().find(|d| d.symbol == symbol) {
+            Some(data) => {
+                data.bid_price = bid;
+                data.ask_price = ask;
+                data.quote_timestamp = Some(quoted_at);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Mean daily volume of `symbol` over its last `days` completed sessions
+    ///
+    /// Bars are summed per calendar day so real-time ticks do not dilute the
+    /// daily bars. Today's partial session and days without any recorded
+    /// volume (forward-filled gaps, volume-less ticks) are skipped.
+    pub fn average_volume(&self, symbol: &str, days: usize) -> Option<f64> {
+        let history = self.get_price_history(symbol)?;
+        if days == 0 {
+            return None;
+        }
+        let today = self.clock.now().date_naive();
+        let mut daily: Vec<(NaiveDate, f64)> = Vec::new();
+        for ((timestamp, _), volume) in history.prices.iter().zip(&history.volumes) {
+            let date = timestamp.date_naive();
+            if date >= today {
+                continue;
+            }
+            match daily.last_mut() {
+                Some((last, total)) if *last == date => *total += volume,
+                _ => daily.push((date, *volume)),
             }
         }
+        let recent: Vec<f64> = daily
+            .iter()
+            .rev()
+            .map(|(_, volume)| *volume)
+            .filter(|volume| *volume > 0.0)
+            .take(days)
+            .collect();
+        if recent.is_empty() {
+            return None;
+        }
+        Some(recent.iter().sum::<f64>() / recent.len() as f64)
     }
 
     pub fn get_market_data(&self, symbol: &str) -> Option<&MarketData> {
         self.data.values().find(|d| d.symbol == symbol)
     }
 
+    /// Whether a symbol's last update is older than `max_age` (or missing entirely)
+    pub fn is_stale(&self, symbol: &str, max_age: Duration) -> bool {
+        match self.get_market_data(symbol) {
+            Some(data) => self.clock.now() - data.timestamp > max_age,
+            None => true,
+        }
+    }
+
     pub fn get_price_history(&self, symbol: &str) -> Option<&PriceHistory> {
         self.price_history.get(symbol)
     }
 
+    /// Write every symbol's price history to `path` as a JSON array
+    ///
+    /// Returns the number of histories saved.
+    pub fn save_price_history(&self, path: &std::path::Path) -> anyhow::Result<usize> {
+        let mut histories: Vec<&PriceHistory> = self.price_history.values().collect();
+        histories.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(&histories)?)?;
+        Ok(histories.len())
+    }
+
+    /// Merge histories written by `save_price_history` into registered symbols
+    ///
+    /// Bars already held at the same timestamp win. Returns the number of
+    /// symbols that gained bars.
+    pub fn load_price_history(&mut self, path: &std::path::Path) -> anyhow::Result<usize> {
+        let saved: Vec<PriceHistory> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        let mut loaded = 0;
+        for saved in saved {
+            let Some(history) = self.price_history.get_mut(&saved.symbol) else {
+                continue;
+            };
+            let volumes = saved.volumes.iter().copied().chain(std::iter::repeat(0.0));
+            let mut added = false;
+            for (&(timestamp, price), volume) in saved.prices.iter().zip(volumes) {
+                let index = history.prices.partition_point(|(dt, _)| *dt < timestamp);
+                if history
+                    .prices
+                    .get(index)
+                    .is_some_and(|(dt, _)| *dt == timestamp)
+                {
+                    continue;
+                }
+                history.prices.insert(index, (timestamp, price));
+                history.volumes.insert(index, volume.max(0.0));
+                added = true;
+            }
+            if added {
+                history.trim();
+                self.rebuild_rolling_returns(&saved.symbol);
+                loaded += 1;
+            }
+        }
+        Ok(loaded)
+    }
+
+    /// Wilder's Average True Range over `period` bars
+    ///
+    /// Only closes are stored, so the true range is approximated by the
+    /// absolute close-to-close change. Needs at least `period + 1` prices.
+    pub fn calculate_atr(&self, symbol: &str, period: usize) -> Option<f64> {
+        let history = self.get_price_history(symbol)?;
+        if period == 0 || history.prices.len() < period + 1 {
+            return None;
+        }
+
+        let true_ranges: Vec<f64> = history
+            .prices
+            .windows(2)
+            .map(|pair| (pair[1].1 - pair[0].1).abs())
+            .collect();
+
+        // Seed with a simple average, then apply Wilder's smoothing
+        let seed = true_ranges[..period].iter().sum::<f64>() / period as f64;
+        let atr = true_ranges[period..].iter().fold(seed, |atr, tr| {
+            (atr * (period - 1) as f64 + tr) / period as f64
+        });
+
+        Some(atr)
+    }
+
     pub fn calculate_momentum(&self, symbol: &str, lookback_period: usize) -> Option<f64> {
         let history = self.get_price_history(symbol)?;
 
@@ -272,81 +1076,159 @@ impl MarketDataHandler {
         let start_price = recent_prices.first()?.1;
         let end_price = recent_prices.last()?.1;
 
-        if start_price > 0.0 {
-            let momentum = (end_price - start_price) / start_price;
+        let mode = self.return_mode(symbol);
+        let Some(momentum) = mode.period_return(start_price, end_price) else {
             log::debug!(
-                "Momentum for {}: start={:.4}, end={:.4}, momentum={:.4}",
+                "No {:?} momentum for {}: start={:.4}, end={:.4}",
+                mode,
                 symbol,
                 start_price,
-                end_price,
-                momentum
+                end_price
             );
-            Some(momentum)
-        } else {
-            None
-        }
+            return None;
+        };
+        log::debug!(
+            "Momentum for {}: start={:.4}, end={:.4}, momentum={:.4}",
+            symbol,
+            start_price,
+            end_price,
+            momentum
+        );
+        Some(momentum)
     }
 
-    pub fn calculate_enhanced_momentum(
+    /// Momentum over `lookback_period` prices with each period's return
+    /// weighted by its bar volume relative to the window's average
+    ///
+    /// Equal volumes reduce to the sum of returns. Zero-volume bars (e.g.
+    /// forward-filled gaps) get no weight; if the whole window lacks volume
+    /// the returns are weighted equally.
+    pub fn calculate_volume_weighted_momentum(
         &self,
         symbol: &str,
         lookback_period: usize,
-    ) -> Option<EnhancedMomentumMetrics> {
+    ) -> Option<f64> {
         let history = self.get_price_history(symbol)?;
-
-        if history.prices.len() < lookback_period + 1 {
-            log::debug!(
-                "Insufficient data for enhanced momentum {}: {} < {}",
-                symbol,
-                history.prices.len(),
-                lookback_period + 1
-            );
+        if lookback_period < 2 || history.prices.len() < lookback_period {
             return None;
         }
 
-        let recent_prices = &history.prices[history.prices.len() - lookback_period..];
-        let start_price = recent_prices.first()?.1;
-        let end_price = recent_prices.last()?.1;
+        let start = history.prices.len() - lookback_period;
+        let prices = &history.prices[start..];
+        let volumes = &history.volumes[start + 1..];
+        let returns: Vec<f64> = prices
+            .windows(2)
+            .map(|pair| {
+                if pair[0].1 > 0.0 {
+                    pair[1].1 / pair[0].1 - 1.0
+                } else {
+                    0.0
+                }
+            })
+            .collect();
 
-        if start_price <= 0.0 {
+        let mean_volume = volumes.iter().sum::<f64>() / volumes.len() as f64;
+        let momentum = if mean_volume > 0.0 {
+            returns
+                .iter()
+                .zip(volumes)
+                .map(|(r, v)| r * v / mean_volume)
+                .sum()
+        } else {
+            returns.iter().sum()
+        };
+        Some(momentum)
+    }
+
+    pub fn calculate_enhanced_momentum(
+        &self,
+        symbol: &str,
+        lookback_period: usize,
+    ) -> Option<EnhancedMomentumMetrics> {
+        self.calculate_enhanced_momentum_with_estimator(
+            symbol,
+            lookback_period,
+            VolatilityEstimator::Simple,
+        )
+    }
+
+    /// Enhanced momentum using the given volatility estimator for risk adjustment
+    pub fn calculate_enhanced_momentum_with_estimator(
+        &self,
+        symbol: &str,
+        lookback_period: usize,
+        estimator: VolatilityEstimator,
+    ) -> Option<EnhancedMomentumMetrics> {
+        let history = self.get_price_history(symbol)?;
+
+        if history.prices.len() < lookback_period + 1 {
+            log::debug!(
+                "Insufficient data for enhanced momentum {}: {} < {}",
+                symbol,
+                history.prices.len(),
+                lookback_period + 1
+            );
             return None;
         }
 
-        // Calculate simple momentum
-        let simple_momentum = (end_price - start_price) / start_price;
+        let recent_prices = &history.prices[history.prices.len() - lookback_period..];
+        let start_price = recent_prices.first()?.1;
+        let end_price = recent_prices.last()?.1;
 
-        // Calculate daily returns for volatility and risk adjustment
-        let mut daily_returns = Vec::new();
-        for i in 1..recent_prices.len() {
-            let prev_price = recent_prices[i - 1].1;
-            let curr_price = recent_prices[i].1;
-            if prev_price > 0.0 && curr_price > 0.0 {
-                let return_val = (curr_price - prev_price) / prev_price;
-                // Filter out extreme outliers (>50% moves) which are likely data errors
-                if return_val.abs() < 0.5 {
-                    daily_returns.push(return_val);
-                }
+        let mode = self.return_mode(symbol);
+        let simple_momentum = mode.period_return(start_price, end_price)?;
+
+        if self.gap_config.reject_momentum_across_gaps {
+            let gaps = self.gaps_in(recent_prices, self.gap_config.bar_interval());
+            if let Some((before, after)) = gaps.first() {
+                log::warn!(
+                    "Skipping enhanced momentum for {}: price gap from {} to {}",
+                    symbol,
+                    before,
+                    after
+                );
+                return None;
             }
         }
 
+        // Calculate daily returns for volatility and risk adjustment
+        let (daily_returns, outliers) =
+            filtered_returns_with_mode(recent_prices, mode, self.bar_outlier_threshold(symbol));
+        if outliers > 0 {
+            log::debug!(
+                "Dropped {} outlier returns for {} over {} bars",
+                outliers,
+                symbol,
+                lookback_period
+            );
+        }
+
         if daily_returns.is_empty() || daily_returns.len() < 2 {
             return None;
         }
 
-        // Calculate volatility (standard deviation of returns)
-        let mean_return = daily_returns.iter().sum::<f64>() / daily_returns.len() as f64;
-        let variance = daily_returns
-            .iter()
-            .map(|r| (r - mean_return).powi(2))
-            .sum::<f64>()
-            / (daily_returns.len() - 1) as f64; // Use sample variance (n-1)
-        let volatility = variance.sqrt();
+        // Calculate volatility with the configured estimator
+        let volatility = match estimator {
+            VolatilityEstimator::Simple => sample_variance(&daily_returns).sqrt(),
+            VolatilityEstimator::Ewma { lambda } => ewma_variance(&daily_returns, lambda).sqrt(),
+        };
 
         // Cap volatility at reasonable levels and ensure it's not zero
-        let capped_volatility = volatility.clamp(0.0001, 2.0); // Min 0.01%, Max 200%
+        let capped_volatility = match mode {
+            ReturnMode::Absolute => volatility.max(0.0001), // Price units, no upper cap
+            ReturnMode::Simple | ReturnMode::Log => volatility.clamp(0.0001, 2.0), // Min 0.01%, Max 200%
+        };
 
-        // Calculate annualized volatility (assuming 252 trading days)
-        let annualized_volatility = capped_volatility * (252.0_f64).sqrt();
+        // Annualize over the symbol's trading calendar
+        let periods_per_year =
+            TimeFrame::Days1.periods_per_year(self.trading_calendar(symbol).trading_days());
+        let annualized_volatility = capped_volatility * periods_per_year.sqrt();
+        let annualized_sharpe = annualized_sharpe(
+            mean(&daily_returns),
+            capped_volatility,
+            periods_per_year,
+            self.risk_free_rate,
+        );
 
         // Calculate Sharpe-like ratio (momentum return per unit of volatility)
         let sharpe_ratio = if capped_volatility > 0.0 {
@@ -379,9 +1261,17 @@ impl MarketDataHandler {
                 if let (Some(second_start), Some(second_end)) =
                     (second_half.first(), second_half.last())
                 {
-                    let first_momentum = (first_end.1 - first_start.1) / first_start.1;
-                    let second_momentum = (second_end.1 - second_start.1) / second_start.1;
-                    second_momentum - first_momentum
+                    // A half whose start price the mode cannot divide by
+                    // contributes no acceleration
+                    match (
+                        mode.period_return(first_start.1, first_end.1),
+                        mode.period_return(second_start.1, second_end.1),
+                    ) {
+                        (Some(first_momentum), Some(second_momentum)) => {
+                            second_momentum - first_momentum
+                        }
+                        _ => 0.0,
+                    }
                 } else {
                     0.0
                 }
@@ -410,10 +1300,49 @@ impl MarketDataHandler {
             momentum_acceleration,
             volatility: annualized_volatility,
             sharpe_ratio,
+            annualized_sharpe,
             timeframe: TimeFrame::Days1,
+            filtered_returns: outliers,
         })
     }
 
+    /// RiskMetrics EWMA volatility over the last `lookback` returns, annualized
+    ///
+    /// Uses the recursion sigma^2_t = lambda * sigma^2_(t-1) + (1 - lambda) * r^2_t,
+    /// seeded with the first squared return. Returns past the symbol's outlier
+    /// threshold are dropped as data errors, matching the simple estimator.
+    pub fn calculate_ewma_volatility(
+        &self,
+        symbol: &str,
+        lambda: f64,
+        lookback: usize,
+    ) -> Option<f64> {
+        if !(0.0..1.0).contains(&lambda) {
+            log::warn!("Invalid EWMA lambda {} for {}", lambda, symbol);
+            return None;
+        }
+
+        let history = self.get_price_history(symbol)?;
+        if history.prices.len() < lookback + 1 {
+            log::debug!(
+                "Insufficient data for EWMA volatility {}: {} < {}",
+                symbol,
+                history.prices.len(),
+                lookback + 1
+            );
+            return None;
+        }
+
+        let recent_prices = &history.prices[history.prices.len() - (lookback + 1)..];
+        let (returns, _) = filtered_returns(recent_prices, self.return_outlier_threshold(symbol));
+        if returns.len() < 2 {
+            return None;
+        }
+
+        let daily_volatility = ewma_variance(&returns, lambda).sqrt().clamp(0.0001, 2.0);
+        Some(daily_volatility * self.trading_calendar(symbol).trading_days().sqrt())
+    }
+
     /// Calculate range-based momentum following Carver's approach
     /// This calculates momentum over multiple periods within a range and combines them
     fn calculate_range_based_momentum(
@@ -459,18 +1388,47 @@ impl MarketDataHandler {
         &self,
         symbol: &str,
         timeframe: TimeFrame,
+    ) -> Option<EnhancedMomentumMetrics> {
+        self.timeframe_momentum(symbol, timeframe, None)
+    }
+
+    /// Timeframe momentum measured over the last `lookback` price periods
+    /// instead of the timeframe's natural window
+    pub fn calculate_momentum_for_timeframe_with_lookback(
+        &self,
+        symbol: &str,
+        timeframe: TimeFrame,
+        lookback: usize,
+    ) -> Option<EnhancedMomentumMetrics> {
+        self.timeframe_momentum(symbol, timeframe, Some(lookback))
+    }
+
+    fn timeframe_momentum(
+        &self,
+        symbol: &str,
+        timeframe: TimeFrame,
+        lookback: Option<usize>,
     ) -> Option<EnhancedMomentumMetrics> {
         let history = self.get_price_history(symbol)?;
-        let now = Utc::now();
-        let timeframe_start = now - timeframe.to_duration();
 
-        // Filter prices within the timeframe
-        let timeframe_prices: Vec<(DateTime<Utc>, f64)> = history
-            .prices
-            .iter()
-            .filter(|(timestamp, _)| *timestamp >= timeframe_start)
-            .cloned()
-            .collect();
+        let timeframe_prices: Vec<(DateTime<Utc>, f64)> = match lookback {
+            Some(lookback) => {
+                if history.prices.len() < lookback + 1 {
+                    return None;
+                }
+                history.prices[history.prices.len() - lookback - 1..].to_vec()
+            }
+            None => {
+                // Filter prices within the timeframe
+                let timeframe_start = self.clock.now() - timeframe.to_duration();
+                history
+                    .prices
+                    .iter()
+                    .filter(|(timestamp, _)| *timestamp >= timeframe_start)
+                    .cloned()
+                    .collect()
+            }
+        };
 
         if timeframe_prices.len() < 2 {
             log::debug!(
@@ -488,9 +1446,13 @@ impl MarketDataHandler {
         if start_price <= 0.0 {
             return None;
         }
+        // Timeframe momentum is always in simple returns
+        let mode = ReturnMode::Simple;
 
         // Calculate simple momentum based on timeframe type
         let simple_momentum = match timeframe {
+            // An explicit lookback is a plain return over that many periods
+            _ if lookback.is_some() => (end_price - start_price) / start_price,
             // For Carver's range-based timeframes, calculate momentum over multiple periods
             TimeFrame::Days2_8
             | TimeFrame::Days4_16
@@ -504,50 +1466,29 @@ impl MarketDataHandler {
         };
 
         // Calculate returns for volatility and risk adjustment
-        let mut returns = Vec::new();
-        for i in 1..timeframe_prices.len() {
-            let prev_price = timeframe_prices[i - 1].1;
-            let curr_price = timeframe_prices[i].1;
-            if prev_price > 0.0 && curr_price > 0.0 {
-                let return_val = (curr_price - prev_price) / prev_price;
-                // Filter out extreme outliers (>50% moves) which are likely data errors
-                if return_val.abs() < 0.5 {
-                    returns.push(return_val);
-                }
-            }
-        }
+        let (returns, outliers) =
+            filtered_returns(&timeframe_prices, self.return_outlier_threshold(symbol));
 
         if returns.is_empty() || returns.len() < 2 {
             return None;
         }
 
         // Calculate volatility (standard deviation of returns)
-        let mean_return = returns.iter().sum::<f64>() / returns.len() as f64;
-        let variance = returns
-            .iter()
-            .map(|r| (r - mean_return).powi(2))
-            .sum::<f64>()
-            / (returns.len() - 1) as f64;
-        let volatility = variance.sqrt();
+        let volatility = sample_variance(&returns).sqrt();
 
         // Cap volatility at reasonable levels and ensure it's not zero
         let capped_volatility = volatility.clamp(0.0001, 2.0);
 
         // Scale volatility based on timeframe (annualize it)
-        let scaling_factor = match timeframe {
-            TimeFrame::Minutes15 => (252.0 * 24.0 * 4.0_f64).sqrt(), // 15-min periods per year
-            TimeFrame::Hours1 => (252.0 * 24.0_f64).sqrt(),          // Hours per year
-            TimeFrame::Hours4 => (252.0 * 6.0_f64).sqrt(),           // 4-hour periods per year
-            TimeFrame::Days1 => (252.0_f64).sqrt(),                  // Days per year
-            TimeFrame::Days7 => (52.0_f64).sqrt(),                   // Weeks per year
-            TimeFrame::Days14 => (26.0_f64).sqrt(),                  // Bi-weeks per year
-            // For Carver's momentum timeframes, use appropriate scaling
-            TimeFrame::Days2_8 => (252.0 / 5.0_f64).sqrt(), // ~5-day periods per year
-            TimeFrame::Days4_16 => (252.0 / 10.0_f64).sqrt(), // ~10-day periods per year
-            TimeFrame::Days8_32 => (252.0 / 20.0_f64).sqrt(), // ~20-day periods per year
-            TimeFrame::Days16_64 => (252.0 / 40.0_f64).sqrt(), // ~40-day periods per year
-        };
-        let annualized_volatility = capped_volatility * scaling_factor;
+        let periods_per_year =
+            timeframe.periods_per_year(self.trading_calendar(symbol).trading_days());
+        let annualized_volatility = capped_volatility * periods_per_year.sqrt();
+        let annualized_sharpe = annualized_sharpe(
+            mean(&returns),
+            capped_volatility,
+            periods_per_year,
+            self.risk_free_rate,
+        );
 
         // Calculate Sharpe-like ratio (momentum return per unit of volatility)
         let sharpe_ratio = if capped_volatility > 0.0 {
@@ -580,9 +1521,17 @@ impl MarketDataHandler {
                 if let (Some(second_start), Some(second_end)) =
                     (second_half.first(), second_half.last())
                 {
-                    let first_momentum = (first_end.1 - first_start.1) / first_start.1;
-                    let second_momentum = (second_end.1 - second_start.1) / second_start.1;
-                    second_momentum - first_momentum
+                    // A half whose start price the mode cannot divide by
+                    // contributes no acceleration
+                    match (
+                        mode.period_return(first_start.1, first_end.1),
+                        mode.period_return(second_start.1, second_end.1),
+                    ) {
+                        (Some(first_momentum), Some(second_momentum)) => {
+                            second_momentum - first_momentum
+                        }
+                        _ => 0.0,
+                    }
                 } else {
                     0.0
                 }
@@ -612,20 +1561,34 @@ impl MarketDataHandler {
             momentum_acceleration,
             volatility: annualized_volatility,
             sharpe_ratio,
+            annualized_sharpe,
             timeframe,
+            filtered_returns: outliers,
         })
     }
 
     pub fn calculate_multi_timeframe_momentum(
         &self,
         symbol: &str,
+    ) -> Option<MultiTimeframeMomentum> {
+        self.calculate_multi_timeframe_momentum_with_lookbacks(symbol, &HashMap::new())
+    }
+
+    /// Multi-timeframe momentum with per-timeframe lookback overrides
+    ///
+    /// Timeframes missing from `lookbacks` keep their natural time window.
+    pub fn calculate_multi_timeframe_momentum_with_lookbacks(
+        &self,
+        symbol: &str,
+        lookbacks: &HashMap<TimeFrame, usize>,
     ) -> Option<MultiTimeframeMomentum> {
         let mut timeframe_metrics = HashMap::new();
         let mut available_timeframes = Vec::new();
 
         // Calculate momentum for all timeframes
         for timeframe in TimeFrame::all_timeframes() {
-            if let Some(metrics) = self.calculate_momentum_for_timeframe(symbol, timeframe) {
+            let lookback = lookbacks.get(&timeframe).copied();
+            if let Some(metrics) = self.timeframe_momentum(symbol, timeframe, lookback) {
                 timeframe_metrics.insert(timeframe, metrics);
                 available_timeframes.push(timeframe);
             }
@@ -651,30 +1614,19 @@ impl MarketDataHandler {
             (TimeFrame::Days16_64, 0.30),
         ];
 
-        let mut composite_score = 0.0;
-        let mut weight_sum = 0.0;
-        let mut weighted_score = 0.0;
-        let mut weighted_weight_sum = 0.0;
-
+        let mut risk_adjusted = Vec::new();
+        let mut simple = Vec::new();
         for (timeframe, weight) in weights {
             if let Some(metrics) = timeframe_metrics.get(&timeframe) {
-                // Use risk-adjusted momentum for composite score
-                composite_score += metrics.risk_adjusted_momentum * weight;
-                weight_sum += weight;
-
-                // Use simple momentum for weighted score
-                weighted_score += metrics.simple_momentum * weight;
-                weighted_weight_sum += weight;
+                risk_adjusted.push((metrics.risk_adjusted_momentum, weight));
+                simple.push((metrics.simple_momentum, weight));
             }
         }
 
-        // Normalize scores
-        if weight_sum > 0.0 {
-            composite_score /= weight_sum;
-        }
-        if weighted_weight_sum > 0.0 {
-            weighted_score /= weighted_weight_sum;
-        }
+        // Risk-adjusted momentum for the composite score, simple momentum for
+        // the weighted score
+        let composite_score = self.aggregation.aggregate(&risk_adjusted).unwrap_or(0.0);
+        let weighted_score = self.aggregation.aggregate(&simple).unwrap_or(0.0);
 
         log::debug!(
             "Multi-timeframe momentum for {}: composite={:.4}, weighted={:.4}, timeframes={:?}",
@@ -695,6 +1647,79 @@ impl MarketDataHandler {
         })
     }
 
+    /// Symbols with a price history, sorted for deterministic iteration
+    pub fn tracked_symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self.price_history.keys().cloned().collect();
+        symbols.sort();
+        symbols
+    }
+
+    /// Close-to-close daily returns for the last `lookback_days` days, keyed by date
+    ///
+    /// Intraday prices are collapsed to the last price of each UTC date. Returns
+    /// None when fewer than `lookback_days + 1` daily closes are available.
+    pub fn daily_returns(
+        &self,
+        symbol: &str,
+        lookback_days: usize,
+    ) -> Option<Vec<(NaiveDate, f64)>> {
+        let history = self.get_price_history(symbol)?;
+
+        let mut daily_closes: Vec<(NaiveDate, f64)> = Vec::new();
+        for (timestamp, price) in &history.prices {
+            let date = timestamp.date_naive();
+            match daily_closes.last_mut() {
+                Some((last_date, close)) if *last_date == date => *close = *price,
+                _ => daily_closes.push((date, *price)),
+            }
+        }
+
+        if daily_closes.len() < lookback_days + 1 {
+            return None;
+        }
+
+        let recent = &daily_closes[daily_closes.len() - (lookback_days + 1)..];
+        let threshold = self.return_outlier_threshold(symbol);
+        Some(
+            recent
+                .windows(2)
+                .filter(|w| w[0].1 > 0.0 && w[1].1 > 0.0)
+                .map(|w| (w[1].0, (w[1].1 - w[0].1) / w[0].1))
+                // Filter out extreme outliers which are likely data errors
+                .filter(|(_, r)| r.abs() < threshold)
+                .collect(),
+        )
+    }
+
+    /// Subscribed symbols with fewer than `min_bars` prices of history, sorted
+    pub fn warming_up_symbols(&self, min_bars: usize) -> Vec<String> {
+        let mut symbols: Vec<String> = self
+            .data
+            .values()
+            .filter(|data| {
+                self.price_history
+                    .get(&data.symbol)
+                    .map_or(0, |history| history.prices.len())
+                    < min_bars
+            })
+            .map(|data| data.symbol.clone())
+            .collect();
+        symbols.sort();
+        symbols.dedup();
+        symbols
+    }
+
+    /// Whether every subscribed symbol has at least `min_bars` of history
+    pub fn is_warmed_up(&self, min_bars: usize) -> bool {
+        self.warming_up_symbols(min_bars).is_empty()
+    }
+
+    /// Age of the least recently updated subscription, if any
+    pub fn oldest_data_age(&self) -> Option<Duration> {
+        let now = self.clock.now();
+        self.data.values().map(|data| now - data.timestamp).max()
+    }
+
     pub fn get_latest_prices(&self) -> HashMap<String, f64> {
         let mut prices = HashMap::new();
         for data in self.data.values() {
@@ -713,3 +1738,959 @@ impl MarketDataHandler {
         self.security_map.get(symbol)
     }
 }
+
+/// Simple returns between consecutive prices, skipping non-positive prices
+///
+/// Returns of `outlier_threshold` or more in absolute value are dropped as
+/// likely data errors; their count is returned alongside.
+fn filtered_returns(prices: &[(DateTime<Utc>, f64)], outlier_threshold: f64) -> (Vec<f64>, usize) {
+    let (kept, outliers): (Vec<f64>, Vec<f64>) = prices
+        .windows(2)
+        .filter(|w| w[0].1 > 0.0 && w[1].1 > 0.0)
+        .map(|w| (w[1].1 - w[0].1) / w[0].1)
+        .partition(|r| r.abs() < outlier_threshold);
+    (kept, outliers.len())
+}
+
+/// `filtered_returns` under a return mode; pairs the mode cannot measure
+/// are skipped
+fn filtered_returns_with_mode(
+    prices: &[(DateTime<Utc>, f64)],
+    mode: ReturnMode,
+    outlier_threshold: f64,
+) -> (Vec<f64>, usize) {
+    if mode == ReturnMode::Simple {
+        return filtered_returns(prices, outlier_threshold);
+    }
+    let (kept, outliers): (Vec<f64>, Vec<f64>) = prices
+        .windows(2)
+        .filter_map(|w| mode.period_return(w[0].1, w[1].1))
+        .partition(|r| r.abs() < outlier_threshold);
+    (kept, outliers.len())
+}
+
+/// Fewest earlier volatility windows a regime is judged against
+const MIN_REGIME_WINDOWS: usize = 10;
+
+fn regime_for_percentile(percentile: f64) -> VolatilityRegime {
+    if percentile >= 2.0 / 3.0 {
+        VolatilityRegime::High
+    } else if percentile < 1.0 / 3.0 {
+        VolatilityRegime::Low
+    } else {
+        VolatilityRegime::Normal
+    }
+}
+
+fn mean(returns: &[f64]) -> f64 {
+    returns.iter().sum::<f64>() / returns.len() as f64
+}
+
+/// Sample variance (n-1) of returns
+fn sample_variance(returns: &[f64]) -> f64 {
+    let mean_return = mean(returns);
+    returns
+        .iter()
+        .map(|r| (r - mean_return).powi(2))
+        .sum::<f64>()
+        / (returns.len() - 1) as f64
+}
+
+/// Annualized Sharpe ratio from per-period return statistics
+///
+/// The mean period return is annualized arithmetically (times
+/// `periods_per_year`) and the period volatility by the square root of
+/// time, so both sides use the same calendar (252 for daily equities) and
+/// the ratio is the per-period Sharpe scaled by sqrt(`periods_per_year`).
+/// `risk_free_rate` is annual.
+fn annualized_sharpe(
+    mean_return: f64,
+    period_volatility: f64,
+    periods_per_year: f64,
+    risk_free_rate: f64,
+) -> f64 {
+    let annualized_volatility = period_volatility * periods_per_year.sqrt();
+    if annualized_volatility > 0.0 {
+        (mean_return * periods_per_year - risk_free_rate) / annualized_volatility
+    } else {
+        0.0
+    }
+}
+
+/// RiskMetrics EWMA variance, seeded with the first squared return
+fn ewma_variance(returns: &[f64], lambda: f64) -> f64 {
+    let mut iter = returns.iter();
+    let seed = iter.next().map(|r| r * r).unwrap_or(0.0);
+    iter.fold(seed, |variance, r| {
+        lambda * variance + (1.0 - lambda) * r * r
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use chrono::TimeZone;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_staleness_follows_clock() {
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 3, 4, 14, 30, 0).unwrap());
+        let mut handler = MarketDataHandler::new();
+        handler.set_clock(Arc::new(clock.clone()));
+        handler.register_symbol(1, "AAPL".to_string());
+        assert!(handler.update_realtime_data("AAPL", 150.0, 100));
+
+        clock.advance(Duration::seconds(59));
+        assert!(!handler.is_stale("AAPL", Duration::seconds(60)));
+        assert_eq!(handler.oldest_data_age(), Some(Duration::seconds(59)));
+
+        clock.advance(Duration::seconds(2));
+        assert!(handler.is_stale("AAPL", Duration::seconds(60)));
+
+        // A fresh tick stamped by the clock clears it
+        assert!(handler.update_realtime_data("AAPL", 150.5, 100));
+        assert!(!handler.is_stale("AAPL", Duration::seconds(60)));
+    }
+
+    fn handler_with_prices(symbol: &str, prices: &[f64]) -> MarketDataHandler {
+        let mut handler = MarketDataHandler::new();
+        handler.register_symbol(1, symbol.to_string());
+        let start = time::OffsetDateTime::now_utc() - time::Duration::days(prices.len() as i64);
+        for (i, price) in prices.iter().enumerate() {
+            handler.add_historical_price(symbol, start + time::Duration::days(i as i64), *price);
+        }
+        handler
+    }
+
+    /// Calm series with alternating +/-0.5% moves followed by +/-5% moves
+    fn volatility_spike_series() -> Vec<f64> {
+        let mut prices = vec![100.0];
+        for i in 0..60 {
+            let r = if i % 2 == 0 { 0.005 } else { -0.005 };
+            prices.push(prices[prices.len() - 1] * (1.0 + r));
+        }
+        for i in 0..5 {
+            let r = if i % 2 == 0 { 0.05 } else { -0.05 };
+            prices.push(prices[prices.len() - 1] * (1.0 + r));
+        }
+        prices
+    }
+
+    #[test]
+    fn test_ewma_reacts_faster_to_volatility_spike() {
+        let prices = volatility_spike_series();
+        let lookback = prices.len() - 1;
+        let handler = handler_with_prices("TEST", &prices);
+
+        let simple = handler
+            .calculate_enhanced_momentum("TEST", lookback)
+            .unwrap()
+            .volatility;
+        let ewma = handler
+            .calculate_ewma_volatility("TEST", 0.94, lookback)
+            .unwrap();
+
+        assert!(
+            ewma > simple * 1.3,
+            "EWMA {:.4} should exceed simple {:.4} after a spike",
+            ewma,
+            simple
+        );
+    }
+
+    #[test]
+    fn test_ewma_lambda_bounds() {
+        let prices = volatility_spike_series();
+        let lookback = prices.len() - 1;
+        let handler = handler_with_prices("TEST", &prices);
+
+        // Lambda 0 keeps only the latest squared return
+        let last_return = prices[lookback] / prices[lookback - 1] - 1.0;
+        let latest_only = handler
+            .calculate_ewma_volatility("TEST", 0.0, lookback)
+            .unwrap();
+        let expected = last_return.abs().clamp(0.0001, 2.0) * 252f64.sqrt();
+        assert!((latest_only - expected).abs() < 1e-9, "{}", latest_only);
+
+        assert!(
+            handler
+                .calculate_ewma_volatility("TEST", 1.0, lookback)
+                .is_none()
+        );
+    }
+
+    /// Wild +/-5% moves that calm down to +/-0.5%
+    fn calming_series() -> Vec<f64> {
+        let mut prices = vec![100.0];
+        for i in 0..40 {
+            let r = if i % 2 == 0 { 0.05 } else { -0.05 };
+            prices.push(prices[prices.len() - 1] * (1.0 + r));
+        }
+        for i in 0..10 {
+            let r = if i % 2 == 0 { 0.005 } else { -0.005 };
+            prices.push(prices[prices.len() - 1] * (1.0 + r));
+        }
+        prices
+    }
+
+    #[test]
+    fn test_volatility_regime_against_own_history() {
+        let lookback = 5;
+        let spiking = handler_with_prices("SPIKE", &volatility_spike_series());
+        assert_eq!(
+            spiking.volatility_regime("SPIKE", lookback),
+            Some(VolatilityRegime::High)
+        );
+
+        let calming = handler_with_prices("CALM", &calming_series());
+        assert_eq!(
+            calming.volatility_regime("CALM", lookback),
+            Some(VolatilityRegime::Low)
+        );
+
+        // Not enough history to rank against
+        let short = handler_with_prices("SHORT", &calming_series()[..10]);
+        assert_eq!(short.volatility_regime("SHORT", lookback), None);
+    }
+
+    #[test]
+    fn test_market_volatility_regime_averages_universe() {
+        let lookback = 5;
+        let mut handler = handler_with_prices("SPIKE", &volatility_spike_series());
+        assert_eq!(
+            handler.market_volatility_regime(lookback),
+            Some(VolatilityRegime::High)
+        );
+
+        // One symbol spiking and one calming average out to Normal
+        let prices = calming_series();
+        handler.register_symbol(2, "CALM".to_string());
+        let start = time::OffsetDateTime::now_utc() - time::Duration::days(prices.len() as i64);
+        for (i, price) in prices.iter().enumerate() {
+            handler.add_historical_price("CALM", start + time::Duration::days(i as i64), *price);
+        }
+        assert_eq!(
+            handler.market_volatility_regime(lookback),
+            Some(VolatilityRegime::Normal)
+        );
+        assert_eq!(
+            MarketDataHandler::new().market_volatility_regime(lookback),
+            None
+        );
+    }
+
+    #[test]
+    fn test_ewma_filters_outliers() {
+        let mut prices = vec![100.0, 100.5, 100.0, 100.5, 100.0];
+        let clean = handler_with_prices("CLEAN", &prices)
+            .calculate_ewma_volatility("CLEAN", 0.94, 4)
+            .unwrap();
+
+        // A bad tick doubling the price is dropped as a data error
+        prices.push(250.0);
+        let with_outlier = handler_with_prices("BAD", &prices)
+            .calculate_ewma_volatility("BAD", 0.94, 5)
+            .unwrap();
+
+        assert!((clean - with_outlier).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_enhanced_momentum_uses_configured_estimator() {
+        let prices = volatility_spike_series();
+        let lookback = prices.len() - 1;
+        let handler = handler_with_prices("TEST", &prices);
+
+        let ewma_metrics = handler
+            .calculate_enhanced_momentum_with_estimator(
+                "TEST",
+                lookback,
+                VolatilityEstimator::Ewma { lambda: 0.94 },
+            )
+            .unwrap();
+        let ewma = handler
+            .calculate_ewma_volatility("TEST", 0.94, lookback)
+            .unwrap();
+
+        assert!((ewma_metrics.volatility - ewma).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_tighter_outlier_threshold_filters_spike() {
+        // Calm 1% moves with one 20% jump
+        let mut prices = vec![100.0];
+        for i in 0..30 {
+            let r = match i {
+                20 => 0.20,
+                _ if i % 2 == 0 => 0.01,
+                _ => -0.01,
+            };
+            prices.push(prices[prices.len() - 1] * (1.0 + r));
+        }
+        let mut handler = handler_with_prices("AAPL", &prices);
+
+        let default = handler.calculate_enhanced_momentum("AAPL", 30).unwrap();
+        assert_eq!(default.filtered_returns, 0);
+
+        handler.register_security(
+            "AAPL".to_string(),
+            SecurityInfo::new_stock("AAPL".to_string(), "SMART".to_string(), "USD".to_string()),
+        );
+        handler.set_return_outlier_thresholds(ReturnOutlierThresholds {
+            stock: 0.15,
+            ..ReturnOutlierThresholds::default()
+        });
+        assert_eq!(handler.return_outlier_threshold("AAPL"), 0.15);
+        let tight = handler.calculate_enhanced_momentum("AAPL", 30).unwrap();
+        assert_eq!(tight.filtered_returns, 1);
+        // The spike no longer inflates volatility
+        assert!(tight.volatility < default.volatility / 2.0);
+        assert_eq!(tight.simple_momentum, default.simple_momentum);
+    }
+
+    /// Daily prices at the given day offsets from 60 days ago
+    fn handler_with_dated_prices(symbol: &str, prices: &[(i64, f64)]) -> MarketDataHandler {
+        let mut handler = MarketDataHandler::new();
+        handler.register_symbol(1, symbol.to_string());
+        let start = time::OffsetDateTime::now_utc() - time::Duration::days(60);
+        for (day, price) in prices {
+            handler.add_historical_price(symbol, start + time::Duration::days(*day), *price);
+        }
+        handler
+    }
+
+    /// Ten daily bars, a ten-day outage, ten more daily bars, then a two-day hole
+    fn gapped_series() -> Vec<(i64, f64)> {
+        let mut prices: Vec<(i64, f64)> = (0..10).map(|d| (d, 100.0 + d as f64)).collect();
+        prices.extend((19..29).map(|d| (d, 120.0 + d as f64)));
+        prices.push((31, 150.0));
+        prices
+    }
+
+    #[test]
+    fn test_detect_gaps_brackets_large_gaps() {
+        let mut handler = handler_with_dated_prices("GAP", &gapped_series());
+        let history = handler.get_price_history("GAP").unwrap().clone();
+
+        let gaps = handler.detect_gaps("GAP");
+        assert_eq!(gaps, vec![(history.prices[9].0, history.prices[10].0)]);
+
+        // A shorter bar interval also flags the two-day hole
+        handler.set_gap_config(DataGapConfig {
+            bar_interval_seconds: 12 * 3600,
+            ..DataGapConfig::default()
+        });
+        assert_eq!(handler.detect_gaps("GAP").len(), 2);
+    }
+
+    #[test]
+    fn test_enhanced_momentum_can_refuse_gaps() {
+        let mut handler = handler_with_dated_prices("GAP", &gapped_series());
+        assert!(handler.calculate_enhanced_momentum("GAP", 15).is_some());
+
+        handler.set_gap_config(DataGapConfig {
+            reject_momentum_across_gaps: true,
+            ..DataGapConfig::default()
+        });
+        assert!(handler.calculate_enhanced_momentum("GAP", 15).is_none());
+        // A window after the outage is still computed
+        assert!(handler.calculate_enhanced_momentum("GAP", 8).is_some());
+    }
+
+    #[test]
+    fn test_fill_small_gaps_forward_fills() {
+        let mut handler = handler_with_dated_prices("GAP", &gapped_series());
+        assert_eq!(handler.fill_small_gaps("GAP"), 0); // Disabled by default
+
+        handler.set_gap_config(DataGapConfig {
+            max_fill_intervals: 2,
+            ..DataGapConfig::default()
+        });
+        assert_eq!(handler.fill_small_gaps("GAP"), 2);
+
+        let prices = &handler.get_price_history("GAP").unwrap().prices;
+        assert_eq!(prices.len(), 23);
+        // Days 29 and 30 carry the day-28 price; the ten-day outage is left alone
+        assert_eq!(prices[20].1, 148.0);
+        assert_eq!(prices[21].1, 148.0);
+        assert_eq!(prices[21].0 - prices[19].0, Duration::days(2));
+        assert_eq!(handler.detect_gaps("GAP").len(), 1);
+    }
+
+    #[test]
+    fn test_timeframe_lookbacks_change_momentum() {
+        // Steady rise followed by a pullback over the last few periods
+        let mut prices: Vec<f64> = (0..40).map(|i| 100.0 + i as f64).collect();
+        prices.extend([136.0, 133.0, 130.0]);
+        let handler = handler_with_prices("TEST", &prices);
+
+        let lookbacks = HashMap::from([(TimeFrame::Days2_8, 5), (TimeFrame::Days8_32, 30)]);
+        let mtf = handler
+            .calculate_multi_timeframe_momentum_with_lookbacks("TEST", &lookbacks)
+            .unwrap();
+
+        let short = mtf.timeframe_metrics[&TimeFrame::Days2_8].simple_momentum;
+        let long = mtf.timeframe_metrics[&TimeFrame::Days8_32].simple_momentum;
+        assert!((short - (130.0 - 137.0) / 137.0).abs() < 1e-12);
+        assert!((long - (130.0 - 112.0) / 112.0).abs() < 1e-12);
+        assert!(short < 0.0 && long > 0.0);
+
+        // Same timeframe, same series, different lookback
+        let short_only = handler
+            .calculate_momentum_for_timeframe_with_lookback("TEST", TimeFrame::Days8_32, 5)
+            .unwrap();
+        assert!((short_only.simple_momentum - short).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_rolling_volatility_matches_batch() {
+        let mut handler = handler_with_prices("TEST", &volatility_spike_series());
+        // Shrink the window so real-time updates also evict old returns
+        handler.price_history.get_mut("TEST").unwrap().max_size = 40;
+        handler.rebuild_rolling_returns("TEST");
+        for i in 0..30 {
+            let price = 100.0 + (i % 7) as f64 - (i % 3) as f64 * 1.5;
+            assert!(handler.update_realtime_data("TEST", price, 100));
+        }
+
+        let prices = &handler.get_price_history("TEST").unwrap().prices;
+        let returns: Vec<f64> = prices.windows(2).map(|w| w[1].1 / w[0].1 - 1.0).collect();
+        let batch = sample_variance(&returns).sqrt();
+        let rolling = handler.rolling_volatility("TEST").unwrap();
+        assert!((rolling - batch).abs() < 1e-12, "{} vs {}", rolling, batch);
+        assert!(handler.rolling_volatility("MISSING").is_none());
+    }
+
+    #[test]
+    fn test_bad_ticks_rejected_and_counted() {
+        let mut handler = MarketDataHandler::new();
+        handler.register_symbol(1, "AAPL".to_string());
+
+        assert!(handler.update_realtime_data("AAPL", 150.0, 100));
+        assert!(!handler.update_realtime_data("AAPL", 0.0, 100));
+        assert!(!handler.update_realtime_data("AAPL", 1500.0, 100));
+        assert!(handler.update_realtime_data("AAPL", 151.0, 100));
+
+        assert_eq!(handler.rejected_tick_count("AAPL"), 2);
+        assert_eq!(handler.rejected_tick_count("MSFT"), 0);
+        assert_eq!(handler.get_market_data("AAPL").unwrap().last_price, 151.0);
+        let history: Vec<f64> = handler
+            .get_price_history("AAPL")
+            .unwrap()
+            .prices
+            .iter()
+            .map(|(_, p)| *p)
+            .collect();
+        assert_eq!(history, vec![150.0, 151.0]);
+
+        // Subscription errors are not ticks and are not counted as bad ones
+        assert!(!handler.apply_event(MarketDataEvent::Error {
+            symbol: "AAPL".to_string(),
+            message: "subscription failed".to_string(),
+        }));
+        assert_eq!(handler.get_market_data("AAPL").unwrap().last_price, 151.0);
+        assert_eq!(handler.rejected_tick_count("AAPL"), 2);
+
+        // A tighter threshold rejects smaller jumps
+        handler.set_max_tick_deviation(0.05);
+        assert!(!handler.update_realtime_data("AAPL", 170.0, 100));
+        assert_eq!(handler.rejected_tick_count("AAPL"), 3);
+    }
+
+    #[test]
+    fn test_absolute_return_ticks_may_cross_zero() {
+        let mut handler = MarketDataHandler::new();
+        handler.register_symbol(1, "SPREAD".to_string());
+        handler.set_return_mode("SPREAD", ReturnMode::Absolute);
+
+        assert!(handler.update_realtime_data("SPREAD", 2.0, 100));
+        assert!(handler.update_realtime_data("SPREAD", 0.0, 100));
+        assert!(handler.update_realtime_data("SPREAD", -3.5, 100));
+        assert!(!handler.update_realtime_data("SPREAD", f64::NAN, 100));
+        assert!(!handler.update_realtime_data("SPREAD", f64::INFINITY, 100));
+        assert_eq!(handler.rejected_tick_count("SPREAD"), 2);
+        assert_eq!(handler.get_market_data("SPREAD").unwrap().last_price, -3.5);
+
+        // Non-finite ticks never confirm a move, however many arrive
+        for _ in 0..REANCHOR_TICKS {
+            assert!(!handler.update_realtime_data("SPREAD", f64::NAN, 100));
+        }
+        assert_eq!(handler.get_market_data("SPREAD").unwrap().last_price, -3.5);
+    }
+
+    #[test]
+    fn test_genuine_gap_reanchors() {
+        let mut handler = MarketDataHandler::new();
+        handler.register_symbol(1, "CL".to_string());
+        assert!(handler.update_realtime_data("CL", 100.0, 100));
+
+        // Isolated spikes between good ticks never add up to a re-anchor
+        for _ in 0..REANCHOR_TICKS {
+            assert!(!handler.update_realtime_data("CL", 200.0, 100));
+            assert!(handler.update_realtime_data("CL", 100.5, 100));
+        }
+
+        // A gap the market holds is rejected until it has been confirmed
+        for tick in 1..REANCHOR_TICKS {
+            assert!(!handler.update_realtime_data("CL", 160.0 + tick as f64, 100));
+        }
+        assert!(handler.update_realtime_data("CL", 163.0, 100));
+        assert!(handler.update_realtime_data("CL", 164.0, 100));
+        assert_eq!(handler.get_market_data("CL").unwrap().last_price, 164.0);
+        assert_eq!(
+            handler.rejected_tick_count("CL"),
+            (2 * REANCHOR_TICKS - 1) as u64
+        );
+    }
+
+    #[test]
+    fn test_apply_event_records_quote() {
+        let mut handler = MarketDataHandler::new();
+        handler.register_symbol(1, "EUR.USD".to_string());
+        let update = |last_price, bid_price, ask_price| {
+            MarketDataEvent::Update(MarketDataUpdate {
+                symbol: "EUR.USD".to_string(),
+                last_price,
+                bid_price,
+                ask_price,
+                quote_timestamp: Some(Utc::now()),
+                volume: 0,
+                timestamp: Utc::now(),
+            })
+        };
+        assert_eq!(handler.get_market_data("EUR.USD").unwrap().quote(), None);
+
+        // An approximated bid and ask is not stored as a quote
+        let approximated = MarketDataEvent::Update(MarketDataUpdate {
+            symbol: "EUR.USD".to_string(),
+            last_price: 1.08505,
+            bid_price: 1.08500,
+            ask_price: 1.08510,
+            quote_timestamp: None,
+            volume: 0,
+            timestamp: Utc::now(),
+        });
+        assert!(handler.apply_event(approximated));
+        assert_eq!(handler.get_market_data("EUR.USD").unwrap().quote(), None);
+
+        assert!(handler.apply_event(update(1.08505, 1.0850, 1.0851)));
+        let data = handler.get_market_data("EUR.USD").unwrap();
+        assert_eq!(data.quote(), Some((1.0850, 1.0851)));
+
+        // A crossed quote keeps the last good one
+        assert!(handler.apply_event(update(1.08515, 1.0852, 1.0851)));
+        assert_eq!(
+            handler.get_market_data("EUR.USD").unwrap().quote(),
+            Some((1.0850, 1.0851))
+        );
+        assert!(!handler.update_quote("EUR.USD", 0.0, 1.0851));
+        assert!(!handler.update_quote("GBP.USD", 1.27, 1.2701));
+    }
+
+    #[test]
+    fn test_stale_quote_dropped_on_next_price() {
+        let mut handler = MarketDataHandler::new();
+        handler.register_symbol(1, "EUR.USD".to_string());
+        let quoted_at = Utc::now();
+        assert!(handler.update_quote_at("EUR.USD", 1.0850, 1.0851, quoted_at));
+
+        // Prices within the quote's lifetime keep it
+        let fresh = quoted_at + Duration::seconds(MAX_QUOTE_AGE_SECONDS);
+        assert!(handler.update_realtime_data_at("EUR.USD", 1.0851, 0, fresh));
+        let data = handler.get_market_data("EUR.USD").unwrap();
+        assert_eq!(data.quote(), Some((1.0850, 1.0851)));
+        assert_eq!(data.quote_timestamp, Some(quoted_at));
+
+        // Once the book has gone quiet the quote no longer stands
+        let stale = fresh + Duration::seconds(1);
+        assert!(handler.update_realtime_data_at("EUR.USD", 1.0900, 0, stale));
+        let data = handler.get_market_data("EUR.USD").unwrap();
+        assert_eq!(data.quote(), None);
+        assert_eq!(data.quote_timestamp, None);
+    }
+
+    fn handler_with_bars(symbol: &str, bars: &[(f64, f64)]) -> MarketDataHandler {
+        let mut handler = MarketDataHandler::new();
+        handler.register_symbol(1, symbol.to_string());
+        let start = time::OffsetDateTime::now_utc() - time::Duration::days(bars.len() as i64);
+        for (i, (price, volume)) in bars.iter().enumerate() {
+            let timestamp = start + time::Duration::days(i as i64);
+            handler.add_historical_bar(symbol, timestamp, *price, *volume);
+        }
+        handler
+    }
+
+    #[test]
+    fn test_volume_weighted_momentum_favors_high_volume_moves() {
+        // Same 10% breakout on day 3, traded on heavy or light volume
+        let prices = [100.0, 101.0, 100.0, 110.0, 111.0, 110.0];
+        let bars = |breakout_volume: f64| -> Vec<(f64, f64)> {
+            prices
+                .iter()
+                .enumerate()
+                .map(|(i, p)| (*p, if i == 3 { breakout_volume } else { 1000.0 }))
+                .collect()
+        };
+        let heavy = handler_with_bars("HEAVY", &bars(5000.0));
+        let light = handler_with_bars("LIGHT", &bars(200.0));
+
+        let heavy_score = heavy
+            .calculate_volume_weighted_momentum("HEAVY", 6)
+            .unwrap();
+        let light_score = light
+            .calculate_volume_weighted_momentum("LIGHT", 6)
+            .unwrap();
+        assert!(heavy_score > light_score);
+        assert!(light_score > 0.0);
+
+        // Equal volumes reduce to the plain sum of returns
+        let even = handler_with_bars("EVEN", &bars(1000.0));
+        let sum: f64 = prices.windows(2).map(|w| w[1] / w[0] - 1.0).sum();
+        let even_score = even.calculate_volume_weighted_momentum("EVEN", 6).unwrap();
+        assert!((even_score - sum).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_volume_weighted_momentum_handles_zero_volume() {
+        // No volume at all falls back to equal weights
+        let handler = handler_with_prices("TEST", &[100.0, 105.0, 110.0]);
+        let score = handler
+            .calculate_volume_weighted_momentum("TEST", 3)
+            .unwrap();
+        assert!((score - (0.05 + 110.0 / 105.0 - 1.0)).abs() < 1e-12);
+
+        // A single zero-volume bar is ignored rather than dividing by zero
+        let handler = handler_with_bars("TEST", &[(100.0, 1000.0), (90.0, 0.0), (99.0, 1000.0)]);
+        let score = handler
+            .calculate_volume_weighted_momentum("TEST", 3)
+            .unwrap();
+        assert!(score.is_finite());
+        assert!((score - 0.1 * 2.0).abs() < 1e-12);
+
+        assert!(
+            handler
+                .calculate_volume_weighted_momentum("TEST", 4)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_average_volume_uses_completed_daily_bars() {
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 3, 8, 15, 0, 0).unwrap());
+        let mut handler = MarketDataHandler::new();
+        handler.set_clock(Arc::new(clock.clone()));
+        handler.register_symbol(1, "AAPL".to_string());
+        let day = |d: u32| {
+            let at = Utc.with_ymd_and_hms(2024, 3, d, 0, 0, 0).unwrap();
+            time::OffsetDateTime::from_unix_timestamp(at.timestamp()).unwrap()
+        };
+        handler.add_historical_bar("AAPL", day(4), 100.0, 2_000.0);
+        handler.add_historical_bar("AAPL", day(5), 100.0, 4_000.0);
+        // A forward-filled day with no trading is not a session
+        handler.add_historical_bar("AAPL", day(6), 100.0, 0.0);
+        handler.add_historical_bar("AAPL", day(7), 100.0, 6_000.0);
+
+        // Today's real-time ticks are a partial session and never dilute it
+        for _ in 0..20 {
+            assert!(handler.update_realtime_data("AAPL", 100.0, 10));
+        }
+        assert_eq!(handler.average_volume("AAPL", 2), Some(5_000.0));
+        assert_eq!(handler.average_volume("AAPL", 10), Some(4_000.0));
+
+        // Once the day completes its ticks sum into one daily bar
+        clock.advance(Duration::days(1));
+        assert_eq!(handler.average_volume("AAPL", 1), Some(200.0));
+        assert_eq!(handler.average_volume("AAPL", 0), None);
+    }
+
+    #[test]
+    fn test_annualized_sharpe_scales_both_sides_by_periods() {
+        // 0.1% mean and 1% volatility per day: per-period Sharpe of 0.1
+        let sharpe = annualized_sharpe(0.001, 0.01, 252.0, 0.0);
+        assert!((sharpe - 0.1 * 252.0_f64.sqrt()).abs() < 1e-12);
+        assert!((sharpe - (0.001 * 252.0) / (0.01 * 252.0_f64.sqrt())).abs() < 1e-12);
+
+        // Weekly data uses 52 on both sides
+        let weekly = annualized_sharpe(0.005, 0.02, 52.0, 0.0);
+        assert!((weekly - 0.25 * 52.0_f64.sqrt()).abs() < 1e-12);
+
+        // The annual risk-free rate comes off the annualized return
+        let excess = annualized_sharpe(0.001, 0.01, 252.0, 0.05);
+        assert!((excess - (0.252 - 0.05) / (0.01 * 252.0_f64.sqrt())).abs() < 1e-12);
+
+        assert_eq!(annualized_sharpe(0.001, 0.0, 252.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_enhanced_momentum_annualized_sharpe() {
+        let mut prices = vec![100.0];
+        for i in 0..40 {
+            let r = if i % 2 == 0 { 0.02 } else { -0.01 };
+            prices.push(prices[prices.len() - 1] * (1.0 + r));
+        }
+        let lookback = 30;
+        let mut handler = handler_with_prices("AAPL", &prices);
+
+        let recent = &prices[prices.len() - lookback..];
+        let returns: Vec<f64> = recent.windows(2).map(|w| w[1] / w[0] - 1.0).collect();
+        let mean_return = mean(&returns);
+        let period_volatility = sample_variance(&returns).sqrt();
+
+        let metrics = handler
+            .calculate_enhanced_momentum("AAPL", lookback)
+            .unwrap();
+        let expected = mean_return / period_volatility * 252.0_f64.sqrt();
+        assert!((metrics.annualized_sharpe - expected).abs() < 1e-9);
+        // Consistent with the reported annualized volatility
+        assert!(
+            (metrics.annualized_sharpe * metrics.volatility - mean_return * 252.0).abs() < 1e-9
+        );
+
+        handler.set_risk_free_rate(0.04);
+        let metrics = handler
+            .calculate_enhanced_momentum("AAPL", lookback)
+            .unwrap();
+        assert!(
+            (metrics.annualized_sharpe * metrics.volatility - (mean_return * 252.0 - 0.04)).abs()
+                < 1e-9
+        );
+        assert!(metrics.annualized_sharpe < expected);
+    }
+
+    #[test]
+    fn test_trading_calendar_changes_annualization() {
+        let mut prices = vec![100.0];
+        for i in 0..40 {
+            let r = if i % 2 == 0 { 0.02 } else { -0.01 };
+            prices.push(prices[prices.len() - 1] * (1.0 + r));
+        }
+        let mut handler = handler_with_prices("AAPL", &prices);
+
+        let mut annualize = |calendar| {
+            handler.set_trading_calendar(Some(calendar));
+            let metrics = handler.calculate_enhanced_momentum("AAPL", 30).unwrap();
+            let ewma = handler.calculate_ewma_volatility("AAPL", 0.94, 30).unwrap();
+            (metrics.volatility, metrics.annualized_sharpe, ewma)
+        };
+        let equities = annualize(TradingCalendar::Equities252);
+        let continuous = annualize(TradingCalendar::Continuous365);
+        let custom = annualize(TradingCalendar::Custom(260.0));
+
+        // Volatility and Sharpe both scale with the square root of the days
+        for (other, days) in [(continuous, 365.0), (custom, 260.0)] {
+            let factor = (days / 252.0_f64).sqrt();
+            assert!((other.0 - equities.0 * factor).abs() < 1e-9);
+            assert!((other.1 - equities.1 * factor).abs() < 1e-9);
+            assert!((other.2 - equities.2 * factor).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_trading_calendar_defaults_by_security_type() {
+        let mut handler = MarketDataHandler::new();
+        handler.register_security(
+            "EUR.USD".to_string(),
+            SecurityInfo::new_forex(
+                "EUR.USD".to_string(),
+                "IDEALPRO".to_string(),
+                "USD".to_string(),
+            ),
+        );
+        handler.register_security(
+            "AAPL".to_string(),
+            SecurityInfo::new_stock("AAPL".to_string(), "SMART".to_string(), "USD".to_string()),
+        );
+
+        assert_eq!(
+            handler.trading_calendar("EUR.USD"),
+            TradingCalendar::Continuous365
+        );
+        assert_eq!(
+            handler.trading_calendar("AAPL"),
+            TradingCalendar::Equities252
+        );
+        assert_eq!(
+            handler.trading_calendar("UNKNOWN"),
+            TradingCalendar::Equities252
+        );
+
+        handler.set_trading_calendar(Some(TradingCalendar::Custom(260.0)));
+        assert_eq!(
+            handler.trading_calendar("EUR.USD"),
+            TradingCalendar::Custom(260.0)
+        );
+    }
+
+    #[test]
+    fn test_save_price_history_round_trips() {
+        let mut handler = MarketDataHandler::new();
+        handler.register_symbol(1, "MSFT".to_string());
+        handler.register_symbol(2, "AAPL".to_string());
+        for (i, price) in [150.0, 151.5].into_iter().enumerate() {
+            let timestamp =
+                time::OffsetDateTime::from_unix_timestamp(1_700_000_000 + i as i64 * 60).unwrap();
+            handler.add_historical_bar("AAPL", timestamp, price, 1_000.0);
+        }
+
+        let path = std::env::temp_dir().join(format!("price_history_{}.json", std::process::id()));
+        assert_eq!(handler.save_price_history(&path).unwrap(), 2);
+
+        let saved: Vec<PriceHistory> =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let _ = std::fs::remove_file(path);
+        assert_eq!(saved[0].symbol, "AAPL");
+        assert_eq!(
+            saved[0].prices,
+            handler.get_price_history("AAPL").unwrap().prices
+        );
+        assert_eq!(saved[0].volumes, [1_000.0, 1_000.0]);
+        assert!(saved[1].prices.is_empty());
+    }
+
+    #[test]
+    fn test_load_price_history_merges_saved_bars() {
+        let bar_time = |minute: i64| {
+            time::OffsetDateTime::from_unix_timestamp(1_700_000_000 + minute * 60).unwrap()
+        };
+        let mut previous = MarketDataHandler::new();
+        previous.register_symbol(1, "AAPL".to_string());
+        previous.register_symbol(2, "GONE".to_string());
+        previous.add_historical_bar("AAPL", bar_time(0), 150.0, 1_000.0);
+        previous.add_historical_bar("AAPL", bar_time(1), 151.0, 1_000.0);
+        previous.add_historical_price("GONE", bar_time(0), 10.0);
+        let path =
+            std::env::temp_dir().join(format!("price_history_load_{}.json", std::process::id()));
+        previous.save_price_history(&path).unwrap();
+
+        // The restarted session already fetched the newer bar
+        let mut handler = MarketDataHandler::new();
+        handler.register_symbol(1, "AAPL".to_string());
+        handler.add_historical_bar("AAPL", bar_time(1), 151.5, 2_000.0);
+        handler.add_historical_bar("AAPL", bar_time(2), 152.0, 2_000.0);
+
+        let loaded = handler.load_price_history(&path).unwrap();
+        let _ = std::fs::remove_file(path);
+        assert_eq!(loaded, 1);
+        let history = handler.get_price_history("AAPL").unwrap();
+        let prices: Vec<f64> = history.prices.iter().map(|(_, price)| *price).collect();
+        assert_eq!(prices, [150.0, 151.5, 152.0]);
+        assert_eq!(history.volumes, [1_000.0, 2_000.0, 2_000.0]);
+        assert!(handler.get_price_history("GONE").is_none());
+    }
+
+    #[test]
+    fn test_warm_up_waits_for_every_symbol() {
+        let mut handler = MarketDataHandler::new();
+        assert!(handler.is_warmed_up(3));
+
+        for (req_id, symbol) in ["AAPL", "MSFT", "EUR.USD"].into_iter().enumerate() {
+            handler.register_symbol(req_id as i32, symbol.to_string());
+        }
+        let add_bars = |handler: &mut MarketDataHandler, symbol: &str, bars: i64| {
+            for i in 0..bars {
+                let timestamp =
+                    time::OffsetDateTime::from_unix_timestamp(1_700_000_000 + i * 60).unwrap();
+                handler.add_historical_price(symbol, timestamp, 100.0 + i as f64);
+            }
+        };
+        add_bars(&mut handler, "AAPL", 3);
+        add_bars(&mut handler, "MSFT", 1);
+
+        assert_eq!(handler.warming_up_symbols(3), ["EUR.USD", "MSFT"]);
+        assert!(!handler.is_warmed_up(3));
+        assert!(handler.is_warmed_up(0));
+
+        add_bars(&mut handler, "EUR.USD", 3);
+        assert_eq!(handler.warming_up_symbols(3), ["MSFT"]);
+        assert!(handler.is_warmed_up(1));
+
+        add_bars(&mut handler, "MSFT", 2);
+        assert!(handler.is_warmed_up(3));
+    }
+
+    #[test]
+    fn test_absolute_mode_handles_negative_prices() {
+        // A calendar spread drifting from -2.0 up through zero to +1.0
+        let prices: Vec<f64> = (0..31)
+            .map(|i| -2.0 + 0.1 * i as f64 + if i % 2 == 0 { 0.05 } else { -0.05 })
+            .collect();
+        let mut handler = handler_with_prices("SPREAD", &prices);
+
+        // Simple returns cannot divide by a negative start price
+        assert_eq!(handler.return_mode("SPREAD"), ReturnMode::Simple);
+        assert!(handler.calculate_momentum("SPREAD", 30).is_none());
+        assert!(handler.calculate_enhanced_momentum("SPREAD", 30).is_none());
+
+        handler.set_return_mode("SPREAD", ReturnMode::Log);
+        assert!(handler.calculate_momentum("SPREAD", 30).is_none());
+
+        handler.set_return_mode("SPREAD", ReturnMode::Absolute);
+        let momentum = handler.calculate_momentum("SPREAD", 30).unwrap();
+        assert!((momentum - (prices[30] - prices[1])).abs() < 1e-9);
+        assert!(momentum > 0.0);
+
+        let metrics = handler.calculate_enhanced_momentum("SPREAD", 30).unwrap();
+        assert!((metrics.simple_momentum - momentum).abs() < 1e-9);
+        // Every bar change crosses no denominator, so none is skipped
+        assert_eq!(metrics.filtered_returns, 0);
+        assert!(metrics.risk_adjusted_momentum > 0.0);
+        assert!(metrics.sharpe_ratio.is_finite());
+    }
+
+    #[test]
+    fn test_log_mode_on_positive_prices() {
+        let prices: Vec<f64> = (0..21).map(|i| 100.0 * 1.01f64.powi(i)).collect();
+        let mut handler = handler_with_prices("AAPL", &prices);
+        let simple = handler.calculate_momentum("AAPL", 20).unwrap();
+
+        handler.set_return_mode("AAPL", ReturnMode::Log);
+        let log = handler.calculate_momentum("AAPL", 20).unwrap();
+        assert!((log - (1.0 + simple).ln()).abs() < 1e-12);
+        assert!((log - 19.0 * 1.01f64.ln()).abs() < 1e-12);
+
+        assert_eq!(ReturnMode::Simple.period_return(-1.0, 1.0), None);
+        assert_eq!(ReturnMode::Log.period_return(1.0, -1.0), None);
+        assert_eq!(ReturnMode::Absolute.period_return(-1.0, 1.0), Some(2.0));
+    }
+
+    #[test]
+    fn test_aggregation_methods_differ_on_same_signals() {
+        let signals = [(10.0, 1.0), (4.0, 1.0), (-2.0, 1.0), (12.0, 1.0)];
+        assert_eq!(AggregationMethod::Mean.aggregate(&signals), Some(6.0));
+        // Three of four agree with the positive mean
+        assert_eq!(
+            AggregationMethod::ConsensusWeighted.aggregate(&signals),
+            Some(4.5)
+        );
+        assert_eq!(AggregationMethod::Strongest.aggregate(&signals), Some(12.0));
+        assert_eq!(
+            AggregationMethod::Strongest.aggregate(&[(3.0, 1.0), (-15.0, 1.0)]),
+            Some(-15.0)
+        );
+        assert_eq!(AggregationMethod::Mean.aggregate(&[]), None);
+
+        // Weights shift the mean toward heavier timeframes
+        assert_eq!(
+            AggregationMethod::Mean.aggregate(&[(10.0, 3.0), (-2.0, 1.0)]),
+            Some(7.0)
+        );
+
+        // Multi-timeframe momentum follows the configured method
+        let prices: Vec<f64> = (0..80)
+            .map(|i| 100.0 + i as f64 + if i % 3 == 0 { 2.0 } else { 0.0 })
+            .collect();
+        let mut handler = handler_with_prices("AAPL", &prices);
+        let mean = handler.calculate_multi_timeframe_momentum("AAPL").unwrap();
+        handler.set_aggregation_method(AggregationMethod::Strongest);
+        let strongest = handler.calculate_multi_timeframe_momentum("AAPL").unwrap();
+        let largest = mean
+            .timeframe_metrics
+            .values()
+            .map(|metrics| metrics.risk_adjusted_momentum)
+            .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+            .unwrap();
+        assert_eq!(strongest.composite_score, largest);
+        assert_ne!(strongest.composite_score, mean.composite_score);
+    }
+}
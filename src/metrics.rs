@@ -0,0 +1,225 @@
+//! Operational metrics in the Prometheus text exposition format
+//!
+//! `Metrics` is a lock-free registry shared between the trading loop, the
+//! order manager and the TWS client. Counters only go up; gauges hold the
+//! latest value set. `render_prometheus` produces a scrapeable snapshot.
+
+use serde::Serialize;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const PREFIX: &str = "algotrading";
+
+/// Gauge stored as the bit pattern of an `f64`
+#[derive(Debug, Default)]
+struct Gauge(AtomicU64);
+
+impl Gauge {
+    fn set(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    orders_placed: AtomicU64,
+    orders_rejected: AtomicU64,
+    reconnects: AtomicU64,
+    portfolio_value: Gauge,
+    drawdown: Gauge,
+    open_positions: Gauge,
+    data_staleness_seconds: Gauge,
+}
+
+/// Point-in-time copy of every metric
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct MetricsSnapshot {
+    pub orders_placed: u64,
+    pub orders_rejected: u64,
+    pub reconnects: u64,
+    pub portfolio_value: f64,
+    pub drawdown: f64,
+    pub open_positions: f64,
+    pub data_staleness_seconds: f64, // Age of the oldest market data
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn inc_orders_placed(&self) {
+        self.orders_placed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_orders_rejected(&self) {
+        self.orders_rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_reconnects(&self) {
+        self.reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn set_portfolio_value(&self, value: f64) {
+        self.portfolio_value.set(value);
+    }
+
+    pub fn set_drawdown(&self, drawdown: f64) {
+        self.drawdown.set(drawdown);
+    }
+
+    pub fn set_open_positions(&self, count: usize) {
+        self.open_positions.set(count as f64);
+    }
+
+    pub fn set_data_staleness_seconds(&self, seconds: f64) {
+        self.data_staleness_seconds.set(seconds);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            orders_placed: self.orders_placed.load(Ordering::Relaxed),
+            orders_rejected: self.orders_rejected.load(Ordering::Relaxed),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+            portfolio_value: self.portfolio_value.get(),
+            drawdown: self.drawdown.get(),
+            open_positions: self.open_positions.get(),
+            data_staleness_seconds: self.data_staleness_seconds.get(),
+        }
+    }
+
+    pub fn render_prometheus(&self) -> String {
+        self.snapshot().render_prometheus()
+    }
+}
+
+impl MetricsSnapshot {
+    /// Render as Prometheus text exposition format (version 0.0.4)
+    pub fn render_prometheus(&self) -> String {
+        let counters = [
+            (
+                "orders_placed_total",
+                "Orders accepted by the broker",
+                self.orders_placed,
+            ),
+            (
+                "orders_rejected_total",
+                "Orders rejected at or after submission",
+                self.orders_rejected,
+            ),
+            (
+                "reconnects_total",
+                "Reconnection attempts to TWS",
+                self.reconnects,
+            ),
+        ];
+        let gauges = [
+            (
+                "portfolio_value",
+                "Total portfolio value in account currency",
+                self.portfolio_value,
+            ),
+            (
+                "drawdown",
+                "Current drawdown from peak equity as a fraction",
+                self.drawdown,
+            ),
+            (
+                "open_positions",
+                "Number of open positions",
+                self.open_positions,
+            ),
+            (
+                "data_staleness_seconds",
+                "Age of the oldest market data update",
+                self.data_staleness_seconds,
+            ),
+        ];
+
+        let mut out = String::new();
+        for (name, help, value) in counters {
+            write_metric(&mut out, name, help, "counter", &value.to_string());
+        }
+        for (name, help, value) in gauges {
+            write_metric(&mut out, name, help, "gauge", &format_value(value));
+        }
+        out
+    }
+}
+
+fn write_metric(out: &mut String, name: &str, help: &str, kind: &str, value: &str) {
+    let _ = writeln!(out, "# HELP {}_{} {}", PREFIX, name, help);
+    let _ = writeln!(out, "# TYPE {}_{} {}", PREFIX, name, kind);
+    let _ = writeln!(out, "{}_{} {}", PREFIX, name, value);
+}
+
+/// Prometheus spells non-finite values `NaN`, `+Inf` and `-Inf`
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_snapshot() {
+        let metrics = Metrics::new();
+        metrics.inc_orders_placed();
+        metrics.inc_orders_placed();
+        metrics.inc_orders_rejected();
+        metrics.inc_reconnects();
+        metrics.set_portfolio_value(105_250.5);
+        metrics.set_drawdown(0.035);
+        metrics.set_open_positions(3);
+        metrics.set_data_staleness_seconds(12.0);
+
+        let rendered = metrics.render_prometheus();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 7 * 3);
+
+        for expected in [
+            "# HELP algotrading_orders_placed_total Orders accepted by the broker",
+            "# TYPE algotrading_orders_placed_total counter",
+            "algotrading_orders_placed_total 2",
+            "algotrading_orders_rejected_total 1",
+            "algotrading_reconnects_total 1",
+            "# TYPE algotrading_portfolio_value gauge",
+            "algotrading_portfolio_value 105250.5",
+            "algotrading_drawdown 0.035",
+            "algotrading_open_positions 3",
+            "algotrading_data_staleness_seconds 12",
+        ] {
+            assert!(lines.contains(&expected), "missing line: {}", expected);
+        }
+
+        // Every sample is preceded by its HELP and TYPE headers
+        for chunk in lines.chunks(3) {
+            let name = chunk[2].split_whitespace().next().unwrap();
+            assert!(chunk[0].starts_with(&format!("# HELP {} ", name)));
+            assert!(chunk[1].starts_with(&format!("# TYPE {} ", name)));
+        }
+    }
+
+    #[test]
+    fn test_non_finite_gauges() {
+        let snapshot = MetricsSnapshot {
+            data_staleness_seconds: f64::INFINITY,
+            drawdown: f64::NAN,
+            ..MetricsSnapshot::default()
+        };
+        let rendered = snapshot.render_prometheus();
+        assert!(rendered.contains("algotrading_data_staleness_seconds +Inf\n"));
+        assert!(rendered.contains("algotrading_drawdown NaN\n"));
+    }
+}
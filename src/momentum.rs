@@ -1,18 +1,27 @@
 use crate::bollinger::{BollingerCalculator, BollingerMetrics};
 use crate::breakout::{BreakoutCalculator, BreakoutMetrics};
-use crate::config::{RiskConfig, StrategyConfig};
+use crate::config::{RiskConfig, SizingMode, StrategyConfig};
+use crate::decisions::{FilteredBy, SignalDecision};
 use crate::market_data::{
-    EnhancedMomentumMetrics, MarketDataHandler, MultiTimeframeMomentum, TimeFrame,
+    EnhancedMomentumMetrics, MarketData, MarketDataHandler, MultiTimeframeMomentum, ReturnMode,
+    TimeFrame,
 };
 use crate::orders::OrderSignal;
 use crate::position_manager::PositionManager;
 use crate::security_types::{SecurityInfo, SecurityType};
 use crate::signals::{
-    CoordinatorConfig, SignalCoordinator, SignalCore, SignalQuality, SignalType, SignalWeights,
+    CoordinatorConfig, SignalAttribution, SignalCoordinator, SignalCore, SignalQuality, SignalType,
 };
+use chrono::Duration;
 use log::{debug, info, warn};
 use std::collections::HashMap;
 
+/// Smallest fraction of a share traded when fractional shares are enabled
+const FRACTIONAL_SHARE_INCREMENT: f64 = 0.0001;
+
+/// Fewest names ranked cross-sectionally; smaller universes keep raw scores
+const MIN_RANKED_UNIVERSE: usize = 3;
+
 #[derive(Debug, Clone)]
 pub struct MomentumScore {
     pub symbol: String,
@@ -23,6 +32,39 @@ pub struct MomentumScore {
     pub breakout_metrics: Option<BreakoutMetrics>,
     pub bollinger_metrics: Option<BollingerMetrics>,
     pub composite_score: f64,
+    pub signal_attribution: SignalAttribution, // Breakdown of the unsmoothed composite
+    pub percentile_rank: Option<f64>, // Position in the universe, 0 bottom to 1 top, when rank-normalized
+}
+
+/// Per-symbol EWMA of composite scores carried across cycles
+#[derive(Debug, Clone, Default)]
+pub struct SignalSmoother {
+    smoothing: f64,
+    state: HashMap<String, f64>,
+}
+
+impl SignalSmoother {
+    /// `smoothing` is the weight on the prior value; 0 passes scores through
+    pub fn new(smoothing: f64) -> Self {
+        Self {
+            smoothing,
+            state: HashMap::new(),
+        }
+    }
+
+    /// Blend this cycle's score into the symbol's state and return the result
+    pub fn update(&mut self, symbol: &str, raw: f64) -> f64 {
+        let smoothed = match self.state.get(symbol) {
+            Some(prior) => self.smoothing * prior + (1.0 - self.smoothing) * raw,
+            None => raw,
+        };
+        self.state.insert(symbol.to_string(), smoothed);
+        smoothed
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<f64> {
+        self.state.get(symbol).copied()
+    }
 }
 
 pub struct MomentumStrategy {
@@ -31,6 +73,10 @@ pub struct MomentumStrategy {
     breakout_calculator: BreakoutCalculator,
     bollinger_calculator: BollingerCalculator,
     signal_coordinator: SignalCoordinator,
+    signal_smoother: SignalSmoother,
+    signal_attributions: HashMap<String, SignalAttribution>, // From the latest scoring pass
+    ranked_scores: Vec<MomentumScore>, // From the latest scoring pass, best first
+    signal_decisions: Vec<SignalDecision>, // Candidates filtered in the latest scoring pass
 }
 
 impl MomentumStrategy {
@@ -38,25 +84,12 @@ impl MomentumStrategy {
         // Initialize position manager with default risk config
         let risk_config = RiskConfig::default();
         let position_manager = PositionManager::new(risk_config);
-        let breakout_calculator = BreakoutCalculator::new();
-        let bollinger_calculator = BollingerCalculator::new();
-
-        // Configure SignalCoordinator with current manual weights
-        // 50% momentum, 30% breakout, 0% carry, 20% bollinger (mean_reversion)
-        let coordinator_config = CoordinatorConfig {
-            signal_weights: SignalWeights {
-                momentum: 0.5,
-                breakout: 0.3,
-                carry: 0.0,          // Not used currently
-                mean_reversion: 0.2, // Bollinger signals
-            },
-            consensus_threshold: 0.67, // Match current >66% logic
-            quality_filter_threshold: 1.0,
-            enable_cross_validation: true,
-        };
-
-        let signal_coordinator = SignalCoordinator::with_config(coordinator_config)
-            .expect("Valid signal coordinator configuration");
+        let breakout_calculator =
+            BreakoutCalculator::new().with_aggregation(config.aggregation_method);
+        let bollinger_calculator =
+            BollingerCalculator::new().with_aggregation(config.aggregation_method);
+        let signal_coordinator = Self::signal_coordinator_for(&config);
+        let signal_smoother = SignalSmoother::new(config.signal_smoothing);
 
         Self {
             config,
@@ -64,42 +97,116 @@ impl MomentumStrategy {
             breakout_calculator,
             bollinger_calculator,
             signal_coordinator,
+            signal_smoother,
+            signal_attributions: HashMap::new(),
+            ranked_scores: Vec::new(),
+            signal_decisions: Vec::new(),
         }
     }
 
+    fn signal_coordinator_for(config: &StrategyConfig) -> SignalCoordinator {
+        // Bollinger signals are weighted as mean_reversion
+        let mut signal_weights = config.signal_weights.clone();
+        signal_weights.normalize();
+        let coordinator_config = CoordinatorConfig {
+            signal_weights,
+            consensus_threshold: config.signal_consensus_threshold,
+            quality_filter_threshold: config.signal_quality_threshold,
+            enable_cross_validation: true,
+        };
+
+        SignalCoordinator::with_config(coordinator_config)
+            .expect("Valid signal coordinator configuration")
+    }
+
+    /// Switch to a reloaded config from the next scoring pass
+    ///
+    /// Positions and smoothed scores carry over; thresholds, weights and
+    /// aggregation are taken from `config`.
+    pub fn update_config(&mut self, config: StrategyConfig) {
+        self.breakout_calculator.aggregation = config.aggregation_method;
+        self.bollinger_calculator.aggregation = config.aggregation_method;
+        self.signal_coordinator = Self::signal_coordinator_for(&config);
+        self.signal_smoother.smoothing = config.signal_smoothing;
+        self.config = config;
+    }
+
+    /// Scores from the latest scoring pass, best first
+    pub fn ranked_scores(&self) -> &[MomentumScore] {
+        &self.ranked_scores
+    }
+
+    /// Per-signal breakdown of `symbol`'s composite from the latest scoring pass
+    pub fn signal_attribution(&self, symbol: &str) -> Option<&SignalAttribution> {
+        self.signal_attributions.get(symbol)
+    }
+
+    /// Candidates the latest scoring pass filtered out before sizing, with why
+    pub fn signal_decisions(&self) -> &[SignalDecision] {
+        &self.signal_decisions
+    }
+
     pub fn calculate_signals(&mut self, market_data: &MarketDataHandler) -> Vec<OrderSignal> {
+        let max_data_age = Duration::seconds(self.config.max_data_age_seconds as i64);
+        self.signal_decisions.clear();
+        let security_ids = self.config.tradable_security_ids();
+
         // Update volatility data with current prices
         let mut current_prices = HashMap::new();
-        for security in &self.config.securities {
-            if let Some(market_data_point) = market_data.get_market_data(&security.symbol) {
-                current_prices.insert(security.symbol.clone(), market_data_point.last_price);
+        for symbol in &security_ids {
+            if market_data.is_stale(symbol, max_data_age) {
+                continue;
+            }
+            if let Some(market_data_point) = market_data.get_market_data(symbol) {
+                current_prices.insert(symbol.clone(), market_data_point.last_price);
             }
         }
         self.position_manager.update_prices(&current_prices);
 
         let mut momentum_scores: Vec<MomentumScore> = Vec::new();
 
-        for security in &self.config.securities {
+        for symbol in &security_ids {
+            if market_data.is_stale(symbol, max_data_age) {
+                warn!(
+                    "Skipping {}: market data older than {}s",
+                    symbol, self.config.max_data_age_seconds
+                );
+                continue;
+            }
+
             // Calculate both simple and enhanced momentum
             let simple_momentum =
-                market_data.calculate_momentum(&security.symbol, self.config.lookback_period);
-            let enhanced_metrics = market_data
-                .calculate_enhanced_momentum(&security.symbol, self.config.lookback_period);
-            let multi_timeframe = market_data.calculate_multi_timeframe_momentum(&security.symbol);
+                market_data.calculate_momentum(symbol, self.config.lookback_period);
+            let enhanced_metrics = market_data.calculate_enhanced_momentum_with_estimator(
+                symbol,
+                self.config.lookback_period,
+                self.config.volatility_estimator,
+            );
+            let multi_timeframe = market_data.calculate_multi_timeframe_momentum_with_lookbacks(
+                symbol,
+                &self.config.lookback_periods,
+            );
+            let volume_weighted = if self.config.use_volume_weighted_momentum {
+                market_data.calculate_volume_weighted_momentum(symbol, self.config.lookback_period)
+            } else {
+                None
+            };
 
             // Calculate breakout signals
             let breakout_metrics = self
                 .breakout_calculator
-                .calculate_multi_timeframe_breakout(&security.symbol, market_data);
+                .calculate_multi_timeframe_breakout(symbol, market_data);
 
             // Calculate Bollinger Bands signals
             let bollinger_metrics = self
                 .bollinger_calculator
-                .calculate_multi_timeframe_bollinger(&security.symbol, market_data);
+                .calculate_multi_timeframe_bollinger(symbol, market_data);
 
             if let Some(momentum) = simple_momentum {
                 // Calculate base composite score from momentum
-                let momentum_composite = if let Some(ref mtf) = multi_timeframe {
+                let momentum_composite = if let Some(vw) = volume_weighted {
+                    vw
+                } else if let Some(ref mtf) = multi_timeframe {
                     mtf.composite_score
                 } else if let Some(ref enhanced) = enhanced_metrics {
                     enhanced.risk_adjusted_momentum
@@ -108,38 +215,50 @@ impl MomentumStrategy {
                 };
 
                 // Use SignalCoordinator to combine signals (replaces manual combination)
-                let composite_score = {
+                let combined_signals = {
                     // Convert signals to SignalCore format
                     let momentum_signal =
-                        Self::create_momentum_signal_core(&security.symbol, momentum_composite);
+                        Self::create_momentum_signal_core(symbol, momentum_composite);
 
                     let breakout_signal = breakout_metrics.as_ref().map(|metrics| {
-                        Self::create_breakout_signal_core(
-                            &security.symbol,
-                            metrics.composite_signal,
-                        )
+                        Self::create_breakout_signal_core(symbol, metrics.composite_signal)
                     });
 
                     let bollinger_signal = bollinger_metrics.as_ref().map(|metrics| {
-                        Self::create_bollinger_signal_core(
-                            &security.symbol,
-                            metrics.composite_signal,
-                        )
+                        Self::create_bollinger_signal_core(symbol, metrics.composite_signal)
                     });
 
                     // Combine signals using SignalCoordinator
-                    let combined_signals = self.signal_coordinator.combine_signals(
+                    self.signal_coordinator.combine_signals(
                         Some(momentum_signal),
                         breakout_signal,
                         None, // No carry signal
                         bollinger_signal,
-                    );
-
-                    combined_signals.composite_strength
+                    )
                 };
 
+                // Filtered candidates cannot be entered; held names keep their score
+                // so the exit rules still apply to them
+                if let Some(reason) = combined_signals.filter.reason()
+                    && self.position_manager.get_position(symbol) == 0.0
+                {
+                    debug!("Skipping {}: {}", symbol, reason);
+                    if let Some(filtered_by) =
+                        FilteredBy::from_signal_filter(&combined_signals.filter)
+                    {
+                        self.signal_decisions.push(SignalDecision::filtered(
+                            symbol,
+                            filtered_by,
+                            reason,
+                        ));
+                    }
+                    continue;
+                }
+                let raw_composite = combined_signals.composite_strength;
+                let composite_score = self.signal_smoother.update(symbol, raw_composite);
+
                 momentum_scores.push(MomentumScore {
-                    symbol: security.symbol.clone(),
+                    symbol: symbol.clone(),
                     momentum,
                     rank: 0,
                     enhanced_metrics: enhanced_metrics.clone(),
@@ -147,11 +266,13 @@ impl MomentumStrategy {
                     breakout_metrics: breakout_metrics.clone(),
                     bollinger_metrics: bollinger_metrics.clone(),
                     composite_score,
+                    signal_attribution: combined_signals.attribution.clone(),
+                    percentile_rank: None,
                 });
 
                 debug!(
-                    "Signals for {}: momentum={:.4}, breakout={:.4}, bollinger={:.4}, composite={:.4}",
-                    security.symbol,
+                    "Signals for {}: momentum={:.4}, breakout={:.4}, bollinger={:.4}, composite={:.4} (smoothed {:.4})",
+                    symbol,
                     momentum_composite,
                     breakout_metrics
                         .as_ref()
@@ -159,6 +280,7 @@ impl MomentumStrategy {
                     bollinger_metrics
                         .as_ref()
                         .map_or(0.0, |b| b.composite_signal),
+                    raw_composite,
                     composite_score
                 );
 
@@ -221,35 +343,77 @@ impl MomentumStrategy {
                     }
                 }
             } else {
-                debug!(
-                    "No momentum calculated for {} (insufficient data?)",
-                    security.symbol
-                );
+                debug!("No momentum calculated for {} (insufficient data?)", symbol);
             }
         }
 
+        if self.config.rank_normalization {
+            normalize_to_percentile_ranks(&mut momentum_scores);
+        }
+
         // Sort by composite score instead of simple momentum
         momentum_scores.sort_by(|a, b| b.composite_score.partial_cmp(&a.composite_score).unwrap());
 
         for (i, score) in momentum_scores.iter_mut().enumerate() {
             score.rank = i + 1;
         }
+        self.ranked_scores = momentum_scores.clone();
+        self.signal_attributions = momentum_scores
+            .iter()
+            .map(|score| (score.symbol.clone(), score.signal_attribution.clone()))
+            .collect();
+
+        // Above the entry threshold but too volatile or inconsistent to enter
+        for score in &momentum_scores {
+            if score.composite_score > self.config.momentum_threshold
+                && !passes_quality_filters(score)
+                && self.position_manager.get_position(&score.symbol) == 0.0
+            {
+                let detail = score
+                    .enhanced_metrics
+                    .as_ref()
+                    .map_or_else(String::new, |em| {
+                        format!(
+                            "volatility {:.2}, sharpe {:.2} outside the quality filters",
+                            em.volatility, em.sharpe_ratio
+                        )
+                    });
+                self.signal_decisions.push(SignalDecision::filtered(
+                    &score.symbol,
+                    FilteredBy::Quality,
+                    detail,
+                ));
+            }
+        }
+
+        // Qualifies, but too few timeframes point the same way to enter
+        for score in &momentum_scores {
+            if self.qualifies(score)
+                && !self.has_timeframe_consensus(score)
+                && self.position_manager.get_position(&score.symbol) == 0.0
+            {
+                let detail = match timeframe_consensus(score) {
+                    Some(consensus) => format!(
+                        "{:.0}% of timeframes agree, {:.0}% required",
+                        consensus * 100.0,
+                        self.config.min_timeframe_consensus * 100.0
+                    ),
+                    None => "no multi-timeframe momentum to check consensus".to_string(),
+                };
+                self.signal_decisions.push(SignalDecision::filtered(
+                    &score.symbol,
+                    FilteredBy::Consensus,
+                    detail,
+                ));
+            }
+        }
 
-        // Filter based on composite score and enhanced criteria
-        let top_performers: Vec<&MomentumScore> = momentum_scores
+        // Names to hold this cycle, including held names kept by the exit band
+        let holdings = self.select_holdings(&momentum_scores);
+        let top_performers: Vec<&MomentumScore> = holdings
             .iter()
-            .filter(|s| {
-                // Basic momentum threshold
-                s.composite_score > self.config.momentum_threshold &&
-                // Additional quality filters
-                s.enhanced_metrics.as_ref().is_none_or(|em| {
-                    // Filter out high volatility stocks (risk management)
-                    em.volatility < 0.5 && // Less than 50% annualized volatility
-                    // Ensure momentum has some consistency (positive Sharpe-like ratio)
-                    em.sharpe_ratio > 0.1
-                })
-            })
-            .take(5)
+            .copied()
+            .filter(|s| self.qualifies(s))
             .collect();
 
         debug!(
@@ -265,13 +429,44 @@ impl MomentumStrategy {
 
         let mut signals = Vec::new();
 
+        // Benchmarks are never traded, even if the account holds one
+        let data_only: Vec<String> = self
+            .config
+            .security_ids()
+            .into_iter()
+            .filter(|id| !security_ids.contains(id))
+            .collect();
         for position in self.position_manager.get_positions().keys() {
-            if !top_performers.iter().any(|s| &s.symbol == position) {
+            if data_only.contains(position) {
+                continue;
+            }
+            let held_in_band = holdings
+                .iter()
+                .any(|s| &s.symbol == position && !self.qualifies(s));
+            if held_in_band {
+                debug!(
+                    "Holding {}: smoothed score inside the exit hysteresis band",
+                    position
+                );
+                continue;
+            }
+            if !holdings.iter().any(|s| &s.symbol == position) {
+                if market_data.is_stale(position, max_data_age) {
+                    warn!("Not exiting {}: market data is stale", position);
+                    continue;
+                }
                 if let Some(data) = market_data.get_market_data(position) {
+                    if !is_tradable_price(data.last_price, market_data.return_mode(position)) {
+                        warn!(
+                            "Not exiting {}: price {} is not tradable",
+                            position, data.last_price
+                        );
+                        continue;
+                    }
                     if let Some(security_info) = market_data.get_security_info(position) {
                         let action = "SELL";
                         let order_type = self.get_order_type();
-                        let limit_price = self.calculate_limit_price(action, data.last_price);
+                        let limit_price = self.calculate_limit_price(action, data);
 
                         signals.push(OrderSignal {
                             symbol: position.to_string(),
@@ -290,6 +485,13 @@ impl MomentumStrategy {
 
         for score in top_performers {
             if let Some(data) = market_data.get_market_data(&score.symbol) {
+                if !is_tradable_price(data.last_price, market_data.return_mode(&score.symbol)) {
+                    warn!(
+                        "Skipping {}: price {} is not tradable",
+                        score.symbol, data.last_price
+                    );
+                    continue;
+                }
                 if let Some(security_info) = market_data.get_security_info(&score.symbol) {
                     // Convert momentum score to signal strength in Carver's -20 to +20 scale
                     let signal_strength = self.calculate_signal_strength(score);
@@ -323,6 +525,20 @@ impl MomentumStrategy {
                     };
 
                     if (target_position - current_position).abs() > min_change_threshold {
+                        // Illiquid names may still be reduced, just not entered or added to
+                        if target_position.abs() > current_position.abs()
+                            && let Some(problem) =
+                                self.liquidity_problem(&score.symbol, data, market_data)
+                        {
+                            info!("Skipping entry into {}: {}", score.symbol, problem);
+                            self.signal_decisions.push(SignalDecision::filtered(
+                                &score.symbol,
+                                FilteredBy::Liquidity,
+                                problem,
+                            ));
+                            continue;
+                        }
+
                         let quantity = target_position - current_position;
                         let reason = if let Some(ref bollinger) = score.bollinger_metrics {
                             if let Some(ref breakout) = score.breakout_metrics {
@@ -407,7 +623,7 @@ impl MomentumStrategy {
                         }
 
                         let order_type = self.get_order_type();
-                        let limit_price = self.calculate_limit_price(action, data.last_price);
+                        let limit_price = self.calculate_limit_price(action, data);
 
                         debug!(
                             "Creating OrderSignal for {}: quantity={:.0} (abs={:.0})",
@@ -434,25 +650,137 @@ impl MomentumStrategy {
         signals
     }
 
+    /// Scale new position sizes, e.g. by `RiskManager::exposure_scale`
+    pub fn set_exposure_scale(&mut self, scale: f64) {
+        self.position_manager.set_exposure_scale(scale);
+    }
+
     pub fn update_position(&mut self, symbol: &str, quantity: f64) {
         self.position_manager.update_position(symbol, quantity);
     }
 
+    /// Above the entry threshold and passing the quality filters
+    fn qualifies(&self, score: &MomentumScore) -> bool {
+        score.composite_score > self.config.momentum_threshold && passes_quality_filters(score)
+    }
+
+    /// Why `symbol` is too illiquid to enter, if it is
+    ///
+    /// Checks the quoted spread against `max_spread_bps` and the average daily
+    /// volume over the lookback against `min_average_volume`. Without a real
+    /// quote from the book, or without volume history, the check is skipped.
+    fn liquidity_problem(
+        &self,
+        symbol: &str,
+        data: &MarketData,
+        market_data: &MarketDataHandler,
+    ) -> Option<String> {
+        if let Some(max_spread_bps) = self.config.max_spread_bps
+            && data.quote_timestamp.is_some()
+            && let Some(spread_bps) = data.spread_bps()
+            && spread_bps > max_spread_bps
+        {
+            return Some(format!(
+                "spread {:.1}bps exceeds {:.1}bps",
+                spread_bps, max_spread_bps
+            ));
+        }
+        if self.config.min_average_volume > 0.0
+            && let Some(volume) = market_data.average_volume(symbol, self.config.lookback_period)
+            && volume < self.config.min_average_volume
+        {
+            return Some(format!(
+                "average volume {:.0} below {:.0}",
+                volume, self.config.min_average_volume
+            ));
+        }
+        None
+    }
+
+    /// Whether enough momentum timeframes agree with the composite to open a
+    /// position; held positions are never checked, so exits need no consensus
+    fn has_timeframe_consensus(&self, score: &MomentumScore) -> bool {
+        let required = self.config.min_timeframe_consensus;
+        required <= 0.0 || timeframe_consensus(score).is_some_and(|consensus| consensus >= required)
+    }
+
+    /// Pick up to `max_positions` names from scores ranked best first
+    ///
+    /// Held names that still qualify (or sit in the exit band) keep their
+    /// slots, strongest first. Free slots go to the best new names; once full,
+    /// a new name replaces the weakest holding only if it scores more than
+    /// `rotation_margin` higher. With no margin this is the plain top N.
+    fn select_holdings<'a>(&self, ranked: &'a [MomentumScore]) -> Vec<&'a MomentumScore> {
+        let max_positions = self.config.max_positions;
+        let is_held = |s: &MomentumScore| self.position_manager.get_position(&s.symbol) != 0.0;
+
+        let mut holdings: Vec<&MomentumScore> = ranked
+            .iter()
+            .filter(|s| is_held(s) && (self.qualifies(s) || self.within_exit_band(s)))
+            .take(max_positions)
+            .collect();
+
+        for candidate in ranked
+            .iter()
+            .filter(|s| !is_held(s) && self.qualifies(s) && self.has_timeframe_consensus(s))
+        {
+            if holdings.len() < max_positions {
+                holdings.push(candidate);
+                continue;
+            }
+            let Some((weakest_index, weakest)) = holdings
+                .iter()
+                .enumerate()
+                .min_by(|a, b| a.1.composite_score.total_cmp(&b.1.composite_score))
+            else {
+                break;
+            };
+            if candidate.composite_score <= weakest.composite_score + self.config.rotation_margin {
+                // Later candidates score no higher
+                break;
+            }
+            info!(
+                "Rotating {} out for {} ({:.4} vs {:.4})",
+                weakest.symbol,
+                candidate.symbol,
+                weakest.composite_score,
+                candidate.composite_score
+            );
+            holdings[weakest_index] = candidate;
+        }
+
+        holdings.sort_by(|a, b| b.composite_score.total_cmp(&a.composite_score));
+        holdings
+    }
+
+    /// Whether a held position's score fell below the entry threshold but not
+    /// through the exit band, so it is kept rather than exited
+    fn within_exit_band(&self, score: &MomentumScore) -> bool {
+        let exit_threshold = self.config.momentum_threshold - self.config.exit_hysteresis;
+        score.composite_score > exit_threshold
+            && score.composite_score <= self.config.momentum_threshold
+            && passes_quality_filters(score)
+    }
+
     /// Calculate limit price based on action and configuration
-    fn calculate_limit_price(&self, action: &str, market_price: f64) -> Option<f64> {
+    ///
+    /// Buys are priced off the bid and sells off the ask when a quote is
+    /// known, otherwise off the last price.
+    fn calculate_limit_price(&self, action: &str, data: &MarketData) -> Option<f64> {
         if !self.config.use_limit_orders {
             return None;
         }
 
         let offset = self.config.limit_order_offset;
+        let (bid, ask) = data.quote().unwrap_or((data.last_price, data.last_price));
         match action {
             "BUY" => {
-                // For buy orders, place limit below market price
-                Some(market_price * (1.0 - offset))
+                // For buy orders, place limit below the bid
+                Some(bid - bid.abs() * offset)
             }
             "SELL" => {
-                // For sell orders, place limit above market price
-                Some(market_price * (1.0 + offset))
+                // For sell orders, place limit above the ask
+                Some(ask + ask.abs() * offset)
             }
             _ => None,
         }
@@ -603,14 +931,27 @@ impl MomentumStrategy {
         price: f64,
         portfolio_value: f64,
     ) -> f64 {
-        // Use position manager for volatility-based position sizing
-        let raw_position_size =
-            self.position_manager
-                .calculate_position_size(symbol, signal_strength, price, portfolio_value);
+        // A zero or NaN price would size an infinite or NaN quantity
+        if !(price.is_finite() && price != 0.0) {
+            warn!("Not sizing {}: price {} is not tradable", symbol, price);
+            return 0.0;
+        }
+        // Below zero (absolute-return instruments) size off the magnitude so
+        // the signal alone sets the direction
+        let price = price.abs();
+
+        // Use position manager for volatility-based position sizing, throttled
+        // by the drawdown exposure scale
+        let raw_position_size = self.position_manager.calculate_position_size(
+            symbol,
+            signal_strength,
+            price,
+            portfolio_value,
+        ) * self.position_manager.exposure_scale();
 
         // Apply security-specific adjustments
         let adjusted_size = match security_info.security_type {
-            SecurityType::Stock => raw_position_size.round(),
+            SecurityType::Stock => self.round_shares(raw_position_size),
             SecurityType::Future => {
                 if let Some(specs) = &security_info.contract_specs {
                     let contract_value = price * specs.multiplier;
@@ -636,6 +977,16 @@ impl MomentumStrategy {
                         return 1_000.0; // Return minimum micro lot
                     }
 
+                    if let SizingMode::Notional { min_notional } = self.config.sizing_mode {
+                        let notional = raw_position_size.abs().max(min_notional);
+                        let final_size = (notional / price).round() * raw_position_size.signum();
+                        debug!(
+                            "Notional forex position size for {}: {} units (${:.0} notional, signal_strength={:.2})",
+                            symbol, final_size, notional, signal_strength
+                        );
+                        return final_size;
+                    }
+
                     // Round to appropriate lot size
                     let lot_size = if base_currency_units >= 100_000.0 {
                         100_000.0 // Standard lot
@@ -672,6 +1023,23 @@ impl MomentumStrategy {
         adjusted_size
     }
 
+    /// Snap a stock size to the configured share increment
+    ///
+    /// Without fractional shares, sizes under one share are dropped rather
+    /// than rounded up to a whole share.
+    fn round_shares(&self, size: f64) -> f64 {
+        if self.config.fractional_shares {
+            return self
+                .config
+                .share_rounding
+                .apply(size, FRACTIONAL_SHARE_INCREMENT);
+        }
+        if size.abs() < 1.0 {
+            return 0.0;
+        }
+        self.config.share_rounding.apply(size, 1.0)
+    }
+
     // Helper functions for SignalCore conversion
     fn create_momentum_signal_core(symbol: &str, signal_strength: f64) -> SignalCore {
         SignalCore::new(
@@ -709,3 +1077,649 @@ impl MomentumStrategy {
         )
     }
 }
+
+/// Replace each composite score with its percentile rank in the universe
+///
+/// Ranks map onto [-1, 1], bottom to top with the median at 0, so
+/// `momentum_threshold` still separates above- from below-median names. Tied
+/// scores share their average rank. Universes smaller than
+/// `MIN_RANKED_UNIVERSE` keep their raw scores.
+fn normalize_to_percentile_ranks(scores: &mut [MomentumScore]) {
+    if scores.len() < MIN_RANKED_UNIVERSE {
+        return;
+    }
+    let composites: Vec<f64> = scores.iter().map(|s| s.composite_score).collect();
+    let last_rank = (composites.len() - 1) as f64;
+    for score in scores.iter_mut() {
+        let below = composites
+            .iter()
+            .filter(|c| **c < score.composite_score)
+            .count() as f64;
+        let ties = composites
+            .iter()
+            .filter(|c| **c == score.composite_score)
+            .count() as f64;
+        let percentile = (below + (ties - 1.0) / 2.0) / last_rank;
+        score.percentile_rank = Some(percentile);
+        score.composite_score = 2.0 * percentile - 1.0;
+    }
+}
+
+/// Whether a price is safe to price orders from; instruments on absolute
+/// returns may trade at or below zero
+fn is_tradable_price(price: f64, return_mode: ReturnMode) -> bool {
+    price.is_finite() && (price > 0.0 || return_mode == ReturnMode::Absolute)
+}
+
+/// Volatility and consistency filters applied on top of the score threshold
+fn passes_quality_filters(score: &MomentumScore) -> bool {
+    score.enhanced_metrics.as_ref().is_none_or(|em| {
+        // Filter out high volatility stocks (risk management)
+        em.volatility < 0.5 && // Less than 50% annualized volatility
+        // Ensure momentum has some consistency (positive Sharpe-like ratio)
+        em.sharpe_ratio > 0.1
+    })
+}
+
+/// Fraction of the multi-timeframe momentum periods whose momentum has the
+/// sign of the composite score; None without multi-timeframe metrics
+fn timeframe_consensus(score: &MomentumScore) -> Option<f64> {
+    let mtf = score.multi_timeframe.as_ref()?;
+    if mtf.timeframe_metrics.is_empty() {
+        return None;
+    }
+    let direction = score.composite_score.signum();
+    let agreeing = mtf
+        .timeframe_metrics
+        .values()
+        .filter(|metrics| {
+            metrics.simple_momentum != 0.0 && metrics.simple_momentum.signum() == direction
+        })
+        .count();
+    Some(agreeing as f64 / mtf.timeframe_metrics.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ShareRounding, TradingConfig};
+    use crate::signals::SignalWeights;
+
+    fn strategy(signal_smoothing: f64, exit_hysteresis: f64) -> MomentumStrategy {
+        let mut config = TradingConfig::default().strategy_config;
+        config.momentum_threshold = 0.5;
+        config.signal_smoothing = signal_smoothing;
+        config.exit_hysteresis = exit_hysteresis;
+        MomentumStrategy::new(config)
+    }
+
+    /// Feed one raw score through the smoother and report whether holding
+    /// selection keeps the position this cycle
+    fn cycle(strategy: &mut MomentumStrategy, raw: f64) -> bool {
+        strategy.update_position("AAPL", 10.0);
+        let composite_score = strategy.signal_smoother.update("AAPL", raw);
+        let scores = [MomentumScore {
+            symbol: "AAPL".to_string(),
+            momentum: raw,
+            rank: 1,
+            enhanced_metrics: None,
+            multi_timeframe: None,
+            breakout_metrics: None,
+            bollinger_metrics: None,
+            composite_score,
+            signal_attribution: SignalAttribution::default(),
+            percentile_rank: None,
+        }];
+        !strategy.select_holdings(&scores).is_empty()
+    }
+
+    #[test]
+    fn test_smoother_blends_prior_cycle() {
+        let mut smoother = SignalSmoother::new(0.5);
+        assert_eq!(smoother.update("AAPL", 0.8), 0.8);
+        assert!((smoother.update("AAPL", 0.3) - 0.55).abs() < 1e-12);
+        assert_eq!(smoother.update("MSFT", 0.1), 0.1);
+
+        let mut passthrough = SignalSmoother::new(0.0);
+        passthrough.update("AAPL", 0.8);
+        assert_eq!(passthrough.update("AAPL", 0.3), 0.3);
+        assert_eq!(passthrough.get("AAPL"), Some(0.3));
+    }
+
+    #[test]
+    fn test_one_cycle_dip_does_not_exit_when_smoothed() {
+        let mut smoothed = strategy(0.5, 0.1);
+        assert!(cycle(&mut smoothed, 0.8));
+        // Dips below the 0.5 threshold: smoothed to 0.55, still held
+        assert!(cycle(&mut smoothed, 0.3));
+        // A second weak cycle lands inside the band (0.425 > 0.4), still held
+        assert!(cycle(&mut smoothed, 0.3));
+        // Persisting weakness finally crosses the exit band
+        assert!(!cycle(&mut smoothed, 0.3));
+
+        let mut unsmoothed = strategy(0.0, 0.0);
+        assert!(cycle(&mut unsmoothed, 0.8));
+        assert!(!cycle(&mut unsmoothed, 0.3));
+    }
+
+    fn score(symbol: &str, composite_score: f64) -> MomentumScore {
+        MomentumScore {
+            symbol: symbol.to_string(),
+            momentum: composite_score,
+            rank: 0,
+            enhanced_metrics: None,
+            multi_timeframe: None,
+            breakout_metrics: None,
+            bollinger_metrics: None,
+            composite_score,
+            signal_attribution: SignalAttribution::default(),
+            percentile_rank: None,
+        }
+    }
+
+    fn normalized(composites: &[(&str, f64)]) -> Vec<(String, f64)> {
+        let mut scores: Vec<MomentumScore> = composites
+            .iter()
+            .map(|(symbol, composite)| score(symbol, *composite))
+            .collect();
+        normalize_to_percentile_ranks(&mut scores);
+        scores
+            .into_iter()
+            .map(|s| (s.symbol, s.composite_score))
+            .collect()
+    }
+
+    #[test]
+    fn test_percentile_rank_equalizes_volatile_and_quiet_names() {
+        // A volatile name topping its universe and a quiet one topping its own
+        let volatile = normalized(&[("TSLA", 2.4), ("NVDA", 0.9), ("AMD", 0.3), ("COIN", -0.6)]);
+        let quiet = normalized(&[("KO", 0.04), ("PG", 0.01), ("JNJ", 0.002), ("PEP", -0.005)]);
+
+        assert_eq!(volatile[0], ("TSLA".to_string(), 1.0));
+        assert_eq!(quiet[0], ("KO".to_string(), 1.0));
+        for (v, q) in volatile.iter().zip(&quiet) {
+            assert!((v.1 - q.1).abs() < 1e-12);
+        }
+        assert!((volatile[1].1 - 1.0 / 3.0).abs() < 1e-12);
+        assert_eq!(volatile[3].1, -1.0);
+    }
+
+    #[test]
+    fn test_percentile_rank_ties_and_small_universe() {
+        let tied = normalized(&[("A", 0.5), ("B", 0.5), ("C", 0.1)]);
+        assert_eq!(tied[0].1, tied[1].1);
+        assert!((tied[0].1 - 0.5).abs() < 1e-12);
+        assert_eq!(tied[2].1, -1.0);
+
+        // Two names are too few to rank
+        assert_eq!(
+            normalized(&[("A", 0.3), ("B", -0.2)]),
+            [("A".to_string(), 0.3), ("B".to_string(), -0.2)]
+        );
+    }
+
+    fn rotation_strategy(rotation_margin: f64, held: &[&str]) -> MomentumStrategy {
+        let mut config = TradingConfig::default().strategy_config;
+        config.momentum_threshold = 0.5;
+        config.max_positions = 3;
+        config.rotation_margin = rotation_margin;
+        let mut strategy = MomentumStrategy::new(config);
+        for symbol in held {
+            strategy.update_position(symbol, 100.0);
+        }
+        strategy
+    }
+
+    fn symbols(holdings: &[&MomentumScore]) -> Vec<String> {
+        holdings.iter().map(|s| s.symbol.clone()).collect()
+    }
+
+    #[test]
+    fn test_max_positions_caps_holdings() {
+        let strategy = rotation_strategy(0.0, &[]);
+        let ranked = vec![
+            score("A", 0.95),
+            score("B", 0.9),
+            score("C", 0.8),
+            score("D", 0.7),
+            score("E", 0.6),
+            score("F", 0.4),
+        ];
+        assert_eq!(symbols(&strategy.select_holdings(&ranked)), ["A", "B", "C"]);
+    }
+
+    #[test]
+    fn test_rotation_requires_margin() {
+        let ranked = vec![
+            score("D", 0.75),
+            score("A", 0.72),
+            score("B", 0.7),
+            score("C", 0.65),
+        ];
+
+        // D beats the weakest holding C by 0.10, inside a 0.15 margin
+        let sticky = rotation_strategy(0.15, &["A", "B", "C"]);
+        assert_eq!(symbols(&sticky.select_holdings(&ranked)), ["A", "B", "C"]);
+
+        // With a 0.05 margin D replaces C, so C falls out and will exit
+        let rotating = rotation_strategy(0.05, &["A", "B", "C"]);
+        assert_eq!(symbols(&rotating.select_holdings(&ranked)), ["D", "A", "B"]);
+    }
+
+    #[test]
+    fn test_held_name_below_threshold_frees_slot() {
+        let strategy = rotation_strategy(0.5, &["A", "B", "C"]);
+        let ranked = vec![
+            score("A", 0.9),
+            score("B", 0.8),
+            score("D", 0.6),
+            score("C", 0.3),
+        ];
+        // C no longer qualifies, so D takes its slot without needing the margin
+        assert_eq!(symbols(&strategy.select_holdings(&ranked)), ["A", "B", "D"]);
+    }
+
+    #[test]
+    fn test_exposure_scale_shrinks_new_positions() {
+        let mut strategy = strategy(0.0, 0.0);
+        let security_info =
+            SecurityInfo::new_stock("AAPL".to_string(), "SMART".to_string(), "USD".to_string());
+        let full = strategy.calculate_volatility_based_position_size(
+            "AAPL",
+            10.0,
+            &security_info,
+            100.0,
+            100_000.0,
+        );
+
+        strategy.set_exposure_scale(0.5);
+        let halved = strategy.calculate_volatility_based_position_size(
+            "AAPL",
+            10.0,
+            &security_info,
+            100.0,
+            100_000.0,
+        );
+        assert!(full > 0.0);
+        assert!((halved - full / 2.0).abs() <= 1.0);
+    }
+
+    fn rounding_strategy(
+        fractional_shares: bool,
+        share_rounding: ShareRounding,
+    ) -> MomentumStrategy {
+        let mut config = TradingConfig::default().strategy_config;
+        config.fractional_shares = fractional_shares;
+        config.share_rounding = share_rounding;
+        MomentumStrategy::new(config)
+    }
+
+    #[test]
+    fn test_whole_share_rounding_policies() {
+        let floor = rounding_strategy(false, ShareRounding::Floor);
+        let round = rounding_strategy(false, ShareRounding::Round);
+        let ceil = rounding_strategy(false, ShareRounding::Ceil);
+
+        assert_eq!(floor.round_shares(12.6), 12.0);
+        assert_eq!(round.round_shares(12.6), 13.0);
+        assert_eq!(ceil.round_shares(12.2), 13.0);
+        // Short sizes round by magnitude
+        assert_eq!(floor.round_shares(-12.6), -12.0);
+        assert_eq!(ceil.round_shares(-12.2), -13.0);
+    }
+
+    #[test]
+    fn test_sub_one_share_dropped_without_fractional() {
+        for rounding in [
+            ShareRounding::Floor,
+            ShareRounding::Round,
+            ShareRounding::Ceil,
+        ] {
+            let strategy = rounding_strategy(false, rounding);
+            assert_eq!(strategy.round_shares(0.7), 0.0);
+            assert_eq!(strategy.round_shares(-0.3), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_fractional_shares_keep_fractions() {
+        let floor = rounding_strategy(true, ShareRounding::Floor);
+        assert!((floor.round_shares(0.37259) - 0.3725).abs() < 1e-9);
+        let round = rounding_strategy(true, ShareRounding::Round);
+        assert!((round.round_shares(12.34567) - 12.3457).abs() < 1e-9);
+
+        // The full sizing path keeps the fraction that whole shares drop
+        let security_info =
+            SecurityInfo::new_stock("BRK.A".to_string(), "SMART".to_string(), "USD".to_string());
+        let size = |strategy: &MomentumStrategy| {
+            strategy.calculate_volatility_based_position_size(
+                "BRK.A",
+                10.0,
+                &security_info,
+                20_000.0,
+                100_000.0,
+            )
+        };
+        let fractional = size(&round);
+        let whole = size(&rounding_strategy(false, ShareRounding::Floor));
+        assert!(
+            fractional.fract() > 0.0,
+            "expected a fractional size, got {}",
+            fractional
+        );
+        assert_eq!(whole, fractional.floor());
+    }
+
+    #[test]
+    fn test_notional_sizing_hits_target_instead_of_snapping_to_lots() {
+        let security_info = SecurityInfo::new_forex(
+            "EUR.USD".to_string(),
+            "IDEALPRO".to_string(),
+            "USD".to_string(),
+        );
+        let sized = |sizing_mode, portfolio_value| {
+            let mut config = TradingConfig::default().strategy_config;
+            config.sizing_mode = sizing_mode;
+            let strategy = MomentumStrategy::new(config);
+            let target = strategy.position_manager.calculate_position_size(
+                "EUR.USD",
+                10.0,
+                1.25,
+                portfolio_value,
+            );
+            let size = strategy.calculate_volatility_based_position_size(
+                "EUR.USD",
+                10.0,
+                &security_info,
+                1.25,
+                portfolio_value,
+            );
+            (target, size)
+        };
+        let notional = SizingMode::Notional {
+            min_notional: 100.0,
+        };
+
+        // The same target: lots snap down to a whole lot, notional keeps it
+        let (target, lots) = sized(SizingMode::Lots, 3_000.0);
+        let (_, exact) = sized(notional, 3_000.0);
+        assert_eq!(lots % 1_000.0, 0.0);
+        assert_eq!(exact, (target / 1.25).round());
+        assert!((exact * 1.25 - target).abs() <= 1.25);
+        assert!((lots * 1.25 - target).abs() > (exact * 1.25 - target).abs());
+
+        // A small account is forced up to a whole micro lot, far over target
+        let (small_target, small_lots) = sized(SizingMode::Lots, 40.0);
+        let (_, small_exact) = sized(notional, 40.0);
+        assert_eq!(small_lots, 1_000.0);
+        assert!(small_lots * 1.25 > 2.0 * small_target);
+        assert_eq!(small_exact, (small_target.max(100.0) / 1.25).round());
+
+        // Below the minimum, notional sizing lifts to the minimum
+        let floor = SizingMode::Notional {
+            min_notional: 10_000.0,
+        };
+        assert_eq!(sized(floor, 40.0).1, 8_000.0);
+    }
+
+    #[test]
+    fn test_unusable_prices_size_to_zero() {
+        let strategy = strategy(0.0, 0.0);
+        let stock =
+            SecurityInfo::new_stock("AAPL".to_string(), "SMART".to_string(), "USD".to_string());
+        let forex = SecurityInfo::new_forex(
+            "EUR.USD".to_string(),
+            "IDEALPRO".to_string(),
+            "USD".to_string(),
+        );
+
+        for price in [f64::NAN, 0.0, f64::INFINITY] {
+            for security_info in [&stock, &forex] {
+                let size = strategy.calculate_volatility_based_position_size(
+                    &security_info.symbol,
+                    10.0,
+                    security_info,
+                    price,
+                    100_000.0,
+                );
+                assert_eq!(size, 0.0, "{} at {}", security_info.symbol, price);
+            }
+        }
+
+        // Below zero only the signal sets the direction
+        let size = |price| {
+            strategy.calculate_volatility_based_position_size("CL", 10.0, &stock, price, 100_000.0)
+        };
+        assert!(size(-40.0) > 0.0);
+        assert_eq!(size(-40.0), size(40.0));
+    }
+
+    #[test]
+    fn test_absolute_return_instruments_trade_below_zero() {
+        assert!(!is_tradable_price(-5.0, ReturnMode::Simple));
+        assert!(!is_tradable_price(0.0, ReturnMode::Log));
+        assert!(is_tradable_price(-5.0, ReturnMode::Absolute));
+        assert!(is_tradable_price(0.0, ReturnMode::Absolute));
+        assert!(!is_tradable_price(f64::NAN, ReturnMode::Absolute));
+
+        let mut config = TradingConfig::default().strategy_config;
+        config.use_limit_orders = true;
+        config.limit_order_offset = 0.01;
+        let strategy = MomentumStrategy::new(config);
+        let data = MarketData {
+            symbol: "CL".to_string(),
+            last_price: -10.0,
+            bid_price: 0.0,
+            ask_price: 0.0,
+            quote_timestamp: None,
+            volume: 0,
+            timestamp: chrono::Utc::now(),
+            security_info: None,
+        };
+        // Limits stay on the passive side of a negative price
+        assert_eq!(strategy.calculate_limit_price("BUY", &data), Some(-10.1));
+        assert_eq!(strategy.calculate_limit_price("SELL", &data), Some(-9.9));
+    }
+
+    #[test]
+    fn test_limit_price_uses_quoted_spread() {
+        let mut config = TradingConfig::default().strategy_config;
+        config.use_limit_orders = true;
+        config.limit_order_offset = 0.001;
+        let strategy = MomentumStrategy::new(config);
+        let mut data = MarketData {
+            symbol: "BRK.A".to_string(),
+            last_price: 650_000.0,
+            bid_price: 649_900.0,
+            ask_price: 650_100.0,
+            quote_timestamp: Some(chrono::Utc::now()),
+            volume: 0,
+            timestamp: chrono::Utc::now(),
+            security_info: None,
+        };
+
+        // Buys work off the bid and sells off the ask, not the last price
+        let buy = strategy.calculate_limit_price("BUY", &data).unwrap();
+        let sell = strategy.calculate_limit_price("SELL", &data).unwrap();
+        assert!((buy - 649_900.0 * 0.999).abs() < 1e-6);
+        assert!((sell - 650_100.0 * 1.001).abs() < 1e-6);
+
+        // Without a quote the last price is the reference
+        data.bid_price = 0.0;
+        data.ask_price = 0.0;
+        let buy = strategy.calculate_limit_price("BUY", &data).unwrap();
+        assert!((buy - 650_000.0 * 0.999).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_signal_weights_come_from_config() {
+        let mut config = TradingConfig::default().strategy_config;
+        // Unnormalized: 1:3 momentum to carry
+        config.signal_weights = SignalWeights {
+            momentum: 1.0,
+            breakout: 0.0,
+            carry: 3.0,
+            mean_reversion: 0.0,
+            ..SignalWeights::default()
+        };
+        let weighted = MomentumStrategy::new(config);
+        let default = MomentumStrategy::new(TradingConfig::default().strategy_config);
+
+        let weights = &weighted.signal_coordinator.config().signal_weights;
+        assert!((weights.carry - 0.75).abs() < 1e-12);
+        assert!((weights.momentum - 0.25).abs() < 1e-12);
+
+        let combine = |strategy: &MomentumStrategy| {
+            let momentum = MomentumStrategy::create_momentum_signal_core("EUR", 0.5);
+            let carry = SignalCore::new(
+                "EUR".to_string(),
+                TimeFrame::Days1,
+                -10.0,
+                SignalType::Carry,
+                0.5,
+                -0.5,
+                SignalQuality::High,
+            );
+            strategy
+                .signal_coordinator
+                .combine_signals(Some(momentum), None, Some(carry), None)
+                .composite_strength
+        };
+
+        // Carry outweighs momentum only when configured to
+        assert!((combine(&weighted) + 5.0).abs() < 1e-9);
+        assert!((combine(&default) - 10.0).abs() < 1e-9);
+    }
+
+    /// Score whose Carver timeframes have the given simple momentum
+    fn score_with_timeframes(symbol: &str, composite_score: f64, momenta: &[f64]) -> MomentumScore {
+        let timeframes = [
+            TimeFrame::Days2_8,
+            TimeFrame::Days4_16,
+            TimeFrame::Days8_32,
+            TimeFrame::Days16_64,
+        ];
+        let timeframe_metrics = timeframes
+            .into_iter()
+            .zip(momenta)
+            .map(|(timeframe, &simple_momentum)| {
+                let metrics = EnhancedMomentumMetrics {
+                    simple_momentum,
+                    risk_adjusted_momentum: simple_momentum,
+                    volatility_normalized_momentum: simple_momentum,
+                    momentum_acceleration: 0.0,
+                    volatility: 0.2,
+                    sharpe_ratio: 0.0,
+                    annualized_sharpe: 0.0,
+                    timeframe,
+                    filtered_returns: 0,
+                };
+                (timeframe, metrics)
+            })
+            .collect();
+        MomentumScore {
+            multi_timeframe: Some(MultiTimeframeMomentum {
+                symbol: symbol.to_string(),
+                timeframe_metrics,
+                composite_score,
+                weighted_score: composite_score,
+            }),
+            ..score(symbol, composite_score)
+        }
+    }
+
+    #[test]
+    fn test_entry_requires_timeframe_consensus() {
+        // Short timeframes up, long timeframes down
+        let split = score_with_timeframes("A", 0.9, &[0.05, 0.02, -0.01, -0.03]);
+        assert_eq!(timeframe_consensus(&split), Some(0.5));
+        let ranked = vec![split];
+
+        let mut strict = rotation_strategy(0.0, &[]);
+        strict.config.min_timeframe_consensus = 0.75;
+        assert!(strict.select_holdings(&ranked).is_empty());
+
+        let mut loose = rotation_strategy(0.0, &[]);
+        loose.config.min_timeframe_consensus = 0.5;
+        assert_eq!(symbols(&loose.select_holdings(&ranked)), ["A"]);
+
+        // A held name is kept without consensus, so it is not forced out
+        let mut held = rotation_strategy(0.0, &["A"]);
+        held.config.min_timeframe_consensus = 0.75;
+        assert_eq!(symbols(&held.select_holdings(&ranked)), ["A"]);
+    }
+
+    #[test]
+    fn test_wide_spread_blocks_entry() {
+        let mut config = TradingConfig::default().strategy_config;
+        config.max_spread_bps = Some(50.0);
+        config.min_average_volume = 10_000.0;
+        config.lookback_period = 5;
+        let strategy = MomentumStrategy::new(config);
+
+        let mut market_data = MarketDataHandler::new();
+        for (req_id, symbol) in ["WIDE", "TIGHT"].into_iter().enumerate() {
+            market_data.register_symbol(req_id as i32, symbol.to_string());
+            for day in 0..5 {
+                let timestamp =
+                    time::OffsetDateTime::from_unix_timestamp(1_700_000_000 + day * 86_400)
+                        .unwrap();
+                market_data.add_historical_bar(symbol, timestamp, 100.0, 50_000.0);
+            }
+        }
+        market_data.update_realtime_data("WIDE", 100.0, 0);
+        market_data.update_realtime_data("TIGHT", 100.0, 0);
+        market_data.update_quote("WIDE", 99.5, 100.5); // 100bps
+        market_data.update_quote("TIGHT", 99.99, 100.01); // 2bps
+
+        let problem = |symbol: &str| {
+            let data = market_data.get_market_data(symbol).unwrap();
+            strategy.liquidity_problem(symbol, data, &market_data)
+        };
+        assert_eq!(
+            problem("WIDE").as_deref(),
+            Some("spread 100.0bps exceeds 50.0bps")
+        );
+        assert_eq!(problem("TIGHT"), None);
+
+        // An approximate quote from the bar feed is not checked
+        let mut approximate = market_data.get_market_data("WIDE").unwrap().clone();
+        approximate.quote_timestamp = None;
+        assert_eq!(
+            strategy.liquidity_problem("WIDE", &approximate, &market_data),
+            None
+        );
+
+        // Thin volume blocks even a tight quote; today's volume-less real-time
+        // tick is not a completed day, so the last five daily bars are averaged
+        for day in 5..10 {
+            let timestamp =
+                time::OffsetDateTime::from_unix_timestamp(1_700_000_000 + day * 86_400).unwrap();
+            market_data.add_historical_bar("TIGHT", timestamp, 100.0, 1_000.0);
+        }
+        let data = market_data.get_market_data("TIGHT").unwrap();
+        assert_eq!(
+            strategy
+                .liquidity_problem("TIGHT", data, &market_data)
+                .as_deref(),
+            Some("average volume 1000 below 10000")
+        );
+    }
+
+    #[test]
+    fn test_reloaded_threshold_applies_without_rebuilding() {
+        let mut strategy = rotation_strategy(0.0, &["A"]);
+        let ranked = vec![score("A", 0.9), score("B", 0.7), score("C", 0.6)];
+        assert_eq!(symbols(&strategy.select_holdings(&ranked)), ["A", "B", "C"]);
+
+        let mut config = strategy.config.clone();
+        config.momentum_threshold = 0.8;
+        config.signal_smoothing = 0.5;
+        strategy.update_config(config);
+
+        // The next cycle uses the new threshold; the held position survives
+        assert_eq!(symbols(&strategy.select_holdings(&ranked)), ["A"]);
+        assert_eq!(strategy.get_positions().get("A"), Some(&100.0));
+        assert_eq!(strategy.signal_smoother.smoothing, 0.5);
+    }
+}
@@ -1,4 +1,5 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
 use ibapi::orders::Order;
 use ibapi::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -18,9 +19,10 @@ pub enum OrderType {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TimeInForce {
     Day,
-    GTC, // Good Till Canceled
-    IOC, // Immediate or Cancel
-    FOK, // Fill or Kill
+    GTC,                         // Good Till Canceled
+    IOC,                         // Immediate or Cancel
+    FOK,                         // Fill or Kill
+    GoodTillDate(DateTime<Utc>), // Expires at the given time
 }
 
 /// Enhanced order parameters
@@ -31,9 +33,17 @@ pub struct OrderParams {
     pub quantity: f64,
     pub order_type: OrderType,
     pub time_in_force: TimeInForce,
-    pub outside_rth: bool, // Outside Regular Trading Hours
-    pub hidden: bool,      // Hidden order
-    pub all_or_none: bool, // All or None
+    pub outside_rth: bool,         // Outside Regular Trading Hours
+    pub hidden: bool,              // Hidden order
+    pub all_or_none: bool,         // All or None
+    pub display_size: Option<f64>, // Iceberg: quantity shown on the book
+}
+
+/// Exit prices of a bracket's protective legs
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BracketLevels {
+    pub take_profit: f64,
+    pub stop_loss: f64,
 }
 
 /// Order action enum
@@ -190,6 +200,40 @@ impl EnhancedOrderBuilder {
         orders
     }
 
+    /// Take-profit limit and stop orders closing a position, linked in one
+    /// OCA group so a fill on either cancels the other
+    ///
+    /// `action` is the exit side. Returns `[take_profit, stop_loss]`.
+    pub fn oco_exit(
+        action: OrderAction,
+        quantity: f64,
+        take_profit: f64,
+        stop_loss: f64,
+        oca_group: &str,
+    ) -> Vec<Order> {
+        let take_profit_order = Order {
+            action: action.clone().into(),
+            total_quantity: quantity,
+            order_type: "LMT".to_string(),
+            limit_price: Some(take_profit),
+            tif: "GTC".to_string(),
+            oca_group: oca_group.to_string(),
+            oca_type: 1, // Cancel the rest of the group with block
+            ..Default::default()
+        };
+        let stop_order = Order {
+            action: action.into(),
+            total_quantity: quantity,
+            order_type: "STP".to_string(),
+            aux_price: Some(stop_loss),
+            tif: "GTC".to_string(),
+            oca_group: oca_group.to_string(),
+            oca_type: 1,
+            ..Default::default()
+        };
+        vec![take_profit_order, stop_order]
+    }
+
     /// Create order from parameters
     pub fn from_params(params: OrderParams) -> Result<Order> {
         let mut order = match params.order_type {
@@ -201,7 +245,10 @@ impl EnhancedOrderBuilder {
             OrderType::StopLimit {
                 stop_price,
                 limit_price,
-            } => Self::stop_limit_order(params.action, params.quantity, stop_price, limit_price),
+            } => {
+                validate_stop_limit(&params.action, stop_price, limit_price)?;
+                Self::stop_limit_order(params.action, params.quantity, stop_price, limit_price)
+            }
             OrderType::TrailingStop { trail_amount } => {
                 Self::trailing_stop_order(params.action, params.quantity, trail_amount, false)
             }
@@ -223,6 +270,13 @@ impl EnhancedOrderBuilder {
             TimeInForce::GTC => "GTC",
             TimeInForce::IOC => "IOC",
             TimeInForce::FOK => "FOK",
+            TimeInForce::GoodTillDate(expiry) => {
+                if expiry <= Utc::now() {
+                    bail!("GTD expiry {} is not in the future", expiry);
+                }
+                order.good_till_date = format_good_till_date(expiry);
+                "GTD"
+            }
         }
         .to_string();
 
@@ -230,11 +284,47 @@ impl EnhancedOrderBuilder {
         order.outside_rth = params.outside_rth;
         order.hidden = params.hidden;
         order.all_or_none = params.all_or_none;
+        if let Some(display_size) = params.display_size {
+            // TWS takes whole units; under one would round to a hidden order
+            if display_size < 1.0 || display_size > params.quantity {
+                bail!(
+                    "Display size {} must be at least 1 and at most the order quantity {}",
+                    display_size,
+                    params.quantity
+                );
+            }
+            order.display_size = Some(display_size.round() as i32);
+        }
 
         Ok(order)
     }
 }
 
+/// Check a stop-limit's limit leaves room to fill once the stop triggers
+///
+/// A sell stop-limit fills at or above its limit after the price falls to the
+/// stop, so the limit must be at or below the stop; a buy the reverse.
+pub fn validate_stop_limit(action: &OrderAction, stop_price: f64, limit_price: f64) -> Result<()> {
+    match action {
+        OrderAction::Sell if limit_price > stop_price => bail!(
+            "Sell stop-limit limit {} is above its stop {}",
+            limit_price,
+            stop_price
+        ),
+        OrderAction::Buy if limit_price < stop_price => bail!(
+            "Buy stop-limit limit {} is below its stop {}",
+            limit_price,
+            stop_price
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Format an expiry in IBKR's UTC form, `yyyyMMdd-HH:mm:ss`
+pub fn format_good_till_date(expiry: DateTime<Utc>) -> String {
+    expiry.format("%Y%m%d-%H:%M:%S").to_string()
+}
+
 /// Helper functions for risk management orders
 pub struct RiskOrders;
 
@@ -261,6 +351,7 @@ impl RiskOrders {
             outside_rth: false,
             hidden: false,
             all_or_none: false,
+            display_size: None,
         }
     }
 
@@ -288,6 +379,7 @@ impl RiskOrders {
             outside_rth: false,
             hidden: false,
             all_or_none: false,
+            display_size: None,
         }
     }
 
@@ -313,6 +405,7 @@ impl RiskOrders {
             outside_rth: false,
             hidden: false,
             all_or_none: false,
+            display_size: None,
         }
     }
 }
@@ -373,4 +466,101 @@ mod tests {
         assert_eq!(take_profit.action, OrderAction::Sell);
         assert_eq!(take_profit.quantity, 100.0);
     }
+
+    fn gtd_limit_params(expiry: DateTime<Utc>) -> OrderParams {
+        OrderParams {
+            symbol: "AAPL".to_string(),
+            action: OrderAction::Buy,
+            quantity: 100.0,
+            order_type: OrderType::Limit { price: 150.0 },
+            time_in_force: TimeInForce::GoodTillDate(expiry),
+            outside_rth: false,
+            hidden: false,
+            all_or_none: false,
+            display_size: None,
+        }
+    }
+
+    #[test]
+    fn test_gtd_order_sets_ibkr_date_format() {
+        use chrono::TimeZone;
+
+        let expiry = Utc.with_ymd_and_hms(2099, 1, 2, 21, 5, 9).unwrap();
+        let order = EnhancedOrderBuilder::from_params(gtd_limit_params(expiry)).unwrap();
+
+        assert_eq!(order.tif, "GTD");
+        assert_eq!(order.good_till_date, "20990102-21:05:09");
+        assert_eq!(order.limit_price, Some(150.0));
+    }
+
+    #[test]
+    fn test_gtd_expiry_must_be_in_future() {
+        let expiry = Utc::now() - chrono::Duration::minutes(1);
+        assert!(EnhancedOrderBuilder::from_params(gtd_limit_params(expiry)).is_err());
+    }
+
+    #[test]
+    fn test_iceberg_display_size() {
+        let mut params = gtd_limit_params(Utc::now() + chrono::Duration::days(1));
+        params.display_size = Some(10.0);
+        let order = EnhancedOrderBuilder::from_params(params.clone()).unwrap();
+        assert_eq!(order.display_size, Some(10));
+        assert_eq!(order.total_quantity, 100.0);
+
+        params.display_size = Some(150.0);
+        assert!(EnhancedOrderBuilder::from_params(params.clone()).is_err());
+        params.display_size = Some(0.4);
+        assert!(EnhancedOrderBuilder::from_params(params).is_err());
+    }
+
+    #[test]
+    fn test_oco_exit_orders_share_group() {
+        let orders =
+            EnhancedOrderBuilder::oco_exit(OrderAction::Sell, 100.0, 104.0, 98.0, "protect-AAPL");
+
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].order_type, "LMT");
+        assert_eq!(orders[0].limit_price, Some(104.0));
+        assert_eq!(orders[1].order_type, "STP");
+        assert_eq!(orders[1].aux_price, Some(98.0));
+        for order in &orders {
+            assert_eq!(order.action, Action::Sell);
+            assert_eq!(order.total_quantity, 100.0);
+            assert_eq!(order.tif, "GTC");
+            assert_eq!(order.oca_group, "protect-AAPL");
+            assert_eq!(order.oca_type, 1);
+        }
+    }
+
+    #[test]
+    fn test_stop_limit_sets_both_prices() {
+        let mut params = RiskOrders::stop_loss_for_position("AAPL", 100.0, 140.0, true);
+        params.order_type = OrderType::StopLimit {
+            stop_price: 140.0,
+            limit_price: 139.5,
+        };
+        let order = EnhancedOrderBuilder::from_params(params.clone()).unwrap();
+        assert_eq!(order.order_type, "STP LMT");
+        assert_eq!(order.action, Action::Sell);
+        assert_eq!(order.aux_price, Some(140.0));
+        assert_eq!(order.limit_price, Some(139.5));
+        assert_eq!(order.tif, "GTC");
+
+        // A sell limit above the stop could never fill on the way down
+        params.order_type = OrderType::StopLimit {
+            stop_price: 140.0,
+            limit_price: 140.5,
+        };
+        assert!(EnhancedOrderBuilder::from_params(params.clone()).is_err());
+
+        // Buy stops are the mirror image
+        params.action = OrderAction::Buy;
+        let order = EnhancedOrderBuilder::from_params(params.clone()).unwrap();
+        assert_eq!(order.limit_price, Some(140.5));
+        params.order_type = OrderType::StopLimit {
+            stop_price: 140.0,
+            limit_price: 139.5,
+        };
+        assert!(EnhancedOrderBuilder::from_params(params).is_err());
+    }
 }
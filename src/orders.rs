@@ -1,10 +1,16 @@
+use crate::journal::{Journal, JournalEvent};
 use crate::margin;
+use crate::metrics::Metrics;
 use crate::portfolio::Portfolio;
 use crate::security_types::{SecurityInfo, SecurityType};
 use anyhow::{Result, anyhow};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use log::{info, warn};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub struct OrderSignal {
@@ -28,23 +34,100 @@ pub struct Order {
     pub limit_price: Option<f64>,
     pub stop_price: Option<f64>,
     pub status: OrderStatus,
-    pub timestamp: DateTime<Utc>,
+    pub filled_quantity: f64,
+    pub average_fill_price: Option<f64>,
+    pub timestamp: DateTime<Utc>,  // Creation time
+    pub updated_at: DateTime<Utc>, // Last status change
     pub security_info: SecurityInfo,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum OrderStatus {
     Pending,
     Submitted,
     Filled,
-    PartiallyFilled,
+    PartiallyFilled { filled_qty: f64, remaining_qty: f64 },
     Cancelled,
     Rejected,
 }
 
+impl OrderStatus {
+    /// Whether the order can still trade
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Pending | OrderStatus::Submitted | OrderStatus::PartiallyFilled { .. }
+        )
+    }
+}
+
+/// Flat order record used for trade history exports
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeRecord {
+    pub id: i32,
+    pub symbol: String,
+    pub action: String,
+    pub quantity: f64,
+    pub order_type: String,
+    pub limit_price: Option<f64>,
+    pub stop_price: Option<f64>,
+    pub status: OrderStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<&Order> for TradeRecord {
+    fn from(order: &Order) -> Self {
+        Self {
+            id: order.id,
+            symbol: order.symbol.clone(),
+            action: order.action.clone(),
+            quantity: order.quantity,
+            order_type: order.order_type.clone(),
+            limit_price: order.limit_price,
+            stop_price: order.stop_price,
+            status: order.status.clone(),
+            created_at: order.timestamp,
+            updated_at: order.updated_at,
+        }
+    }
+}
+
+/// CSV header for trade exports; keep stable for downstream scripts
+pub const TRADE_CSV_HEADER: &str =
+    "id,symbol,action,quantity,order_type,limit_price,stop_price,status,created_at,updated_at";
+
+/// Identifier for a one-cancels-other order group
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OcoGroupId(pub u32);
+
+/// Orders linked so that a fill or cancel of one cancels the others
+#[derive(Debug, Clone)]
+pub struct OcoGroup {
+    pub id: OcoGroupId,
+    pub order_ids: Vec<i32>,
+}
+
 pub struct OrderManager {
     orders: Vec<Order>,
     next_order_id: i32,
+    oco_groups: HashMap<OcoGroupId, OcoGroup>,
+    order_to_oco: HashMap<i32, OcoGroupId>,
+    next_oco_id: u32,
+    journal: Option<Arc<Journal>>,
+    metrics: Option<Arc<Metrics>>,
+    min_holding_period: Duration,
+    /// Broker IDs of resting bracket legs protecting each symbol
+    protective_legs: HashMap<String, Vec<i32>>,
+    /// Position size each automatically placed protective pair covers
+    protected_quantities: HashMap<String, f64>,
+    /// Buying power each order took when created, by order id
+    reserved_buying_power: HashMap<i32, f64>,
+    /// Local order id for each broker order id
+    broker_order_ids: HashMap<i32, i32>,
+    /// Latest order created through `validate_and_create_order`, by symbol
+    working_orders: HashMap<String, i32>,
+    working_order_timeout: Duration,
 }
 
 impl Default for OrderManager {
@@ -58,6 +141,238 @@ impl OrderManager {
         Self {
             orders: Vec::new(),
             next_order_id: 1000,
+            oco_groups: HashMap::new(),
+            order_to_oco: HashMap::new(),
+            next_oco_id: 1,
+            journal: None,
+            metrics: None,
+            min_holding_period: Duration::zero(),
+            protective_legs: HashMap::new(),
+            protected_quantities: HashMap::new(),
+            reserved_buying_power: HashMap::new(),
+            broker_order_ids: HashMap::new(),
+            working_orders: HashMap::new(),
+            working_order_timeout: Duration::minutes(5),
+        }
+    }
+
+    /// Remember the broker IDs of bracket legs protecting `symbol`
+    pub fn record_protective_legs(&mut self, symbol: &str, broker_order_ids: &[i32]) {
+        self.protective_legs
+            .entry(symbol.to_string())
+            .or_default()
+            .extend_from_slice(broker_order_ids);
+    }
+
+    /// Forget and return the protective legs for `symbol`, e.g. to cancel
+    /// them before an exit
+    pub fn take_protective_legs(&mut self, symbol: &str) -> Vec<i32> {
+        self.protected_quantities.remove(symbol);
+        self.protective_legs.remove(symbol).unwrap_or_default()
+    }
+
+    /// Drop a protective leg that is no longer working; a symbol left without
+    /// legs is no longer protected
+    fn forget_protective_leg(&mut self, broker_order_id: i32) {
+        let Some(symbol) = self
+            .protective_legs
+            .iter()
+            .find(|(_, legs)| legs.contains(&broker_order_id))
+            .map(|(symbol, _)| symbol.clone())
+        else {
+            return;
+        };
+        let legs = self.protective_legs.entry(symbol.clone()).or_default();
+        legs.retain(|id| *id != broker_order_id);
+        if legs.is_empty() {
+            info!("Protective orders for {} no longer working", symbol);
+            self.take_protective_legs(&symbol);
+        }
+    }
+
+    pub fn has_protective_legs(&self, symbol: &str) -> bool {
+        self.protective_legs
+            .get(symbol)
+            .is_some_and(|legs| !legs.is_empty())
+    }
+
+    /// Note that protective orders now cover a `quantity` position in `symbol`
+    pub fn record_protected_position(&mut self, symbol: &str, quantity: f64) {
+        self.protected_quantities
+            .insert(symbol.to_string(), quantity);
+    }
+
+    /// Position size covered by automatically placed protective orders
+    pub fn protected_quantity(&self, symbol: &str) -> Option<f64> {
+        self.protected_quantities.get(symbol).copied()
+    }
+
+    /// Remember the broker ID an order was submitted under
+    pub fn record_broker_order_id(&mut self, order_id: i32, broker_order_id: i32) {
+        self.broker_order_ids.insert(broker_order_id, order_id);
+    }
+
+    /// Point the order behind `old` at its replacement `new`
+    ///
+    /// The cancelled original's status is ignored from then on, so a repriced
+    /// order is not marked cancelled while its replacement works.
+    pub fn replace_broker_order_id(&mut self, old: i32, new: i32) {
+        if let Some(order_id) = self.broker_order_ids.remove(&old) {
+            self.broker_order_ids.insert(new, order_id);
+        }
+    }
+
+    /// Apply the broker's view of order statuses, keyed by broker order ID
+    ///
+    /// Only active orders are updated, and never back to `Pending`. Broker IDs
+    /// without a local order are ignored, except that protective legs which
+    /// filled or were cancelled are forgotten so the position can be protected
+    /// again. Returns the number of orders updated.
+    pub fn reconcile_broker_statuses(&mut self, statuses: &HashMap<i32, OrderStatus>) -> usize {
+        for (broker_order_id, status) in statuses {
+            if !status.is_active() {
+                self.forget_protective_leg(*broker_order_id);
+            }
+        }
+
+        let mut updates: Vec<(i32, &OrderStatus)> = statuses
+            .iter()
+            .filter(|(_, status)| **status != OrderStatus::Pending)
+            .filter_map(|(broker_order_id, status)| {
+                Some((*self.broker_order_ids.get(broker_order_id)?, status))
+            })
+            .collect();
+        updates.sort_by_key(|(order_id, _)| *order_id);
+
+        let mut updated = 0;
+        for (order_id, status) in updates {
+            // An earlier update may have cancelled this order as an OCO sibling
+            let changed = self
+                .get_order(order_id)
+                .is_some_and(|order| order.status.is_active() && order.status != *status);
+            if changed && self.update_order_status(order_id, status.clone()).is_ok() {
+                updated += 1;
+            }
+        }
+        updated
+    }
+
+    /// Symbols with automatically placed protective orders, sorted
+    pub fn protected_symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self.protected_quantities.keys().cloned().collect();
+        symbols.sort();
+        symbols
+    }
+
+    /// Count placed and rejected orders in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Record order creation and status changes to `journal`
+    pub fn with_journal(mut self, journal: Arc<Journal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Refuse to exit positions opened less than `period` ago
+    pub fn with_min_holding_period(mut self, period: Duration) -> Self {
+        self.min_holding_period = period;
+        self
+    }
+
+    /// Reject an order that would reduce a position still inside the minimum
+    /// holding period
+    ///
+    /// Not applied by `validate_and_create_order`, so callers skip it for
+    /// risk-reduction orders.
+    pub fn check_holding_period(
+        &self,
+        signal: &OrderSignal,
+        portfolio: &Portfolio,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        if self.min_holding_period <= Duration::zero() {
+            return Ok(());
+        }
+        let current_quantity = portfolio
+            .get_position(&signal.symbol)
+            .map_or(0.0, |position| position.quantity);
+        let order_quantity = if signal.action == "SELL" {
+            -signal.quantity
+        } else {
+            signal.quantity
+        };
+        if current_quantity * order_quantity >= 0.0 {
+            return Ok(());
+        }
+
+        if let Some(opened_at) = portfolio.entry_time(&signal.symbol) {
+            let held = now - opened_at;
+            if held < self.min_holding_period {
+                return Err(anyhow!(
+                    "{} {} suppressed: position opened {} minutes ago, minimum holding period is {} minutes",
+                    signal.action,
+                    signal.symbol,
+                    held.num_minutes(),
+                    self.min_holding_period.num_minutes()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Report an unconfirmed order as stale after `timeout`, so a lost status
+    /// update gets it cancelled instead of blocking its symbol forever. Zero
+    /// turns working-order blocking off.
+    pub fn with_working_order_timeout(mut self, timeout: Duration) -> Self {
+        self.working_order_timeout = timeout;
+        self
+    }
+
+    /// Whether an order created for `symbol` by `validate_and_create_order`
+    /// is still working
+    ///
+    /// Callers skip new orders for the symbol meanwhile, so a slow broker
+    /// confirmation does not lead to a duplicate order next cycle. An order
+    /// keeps blocking until it resolves or is cancelled, however old.
+    pub fn has_working_order(&self, symbol: &str) -> bool {
+        self.working_order(symbol).is_some()
+    }
+
+    pub fn working_order(&self, symbol: &str) -> Option<&Order> {
+        if self.working_order_timeout.is_zero() {
+            return None;
+        }
+        let order = self.get_order(*self.working_orders.get(symbol)?)?;
+        order.status.is_active().then_some(order)
+    }
+
+    /// Working orders still unresolved after the working-order timeout
+    ///
+    /// Their status updates were probably lost; they should be cancelled at
+    /// the broker before their symbols trade again.
+    pub fn stale_working_orders(&self) -> Vec<&Order> {
+        let now = Utc::now();
+        self.working_orders
+            .keys()
+            .filter_map(|symbol| self.working_order(symbol))
+            .filter(|order| now - order.timestamp >= self.working_order_timeout)
+            .collect()
+    }
+
+    /// Broker order id `order_id` was submitted under, if any
+    pub fn broker_order_id(&self, order_id: i32) -> Option<i32> {
+        self.broker_order_ids
+            .iter()
+            .find(|(_, id)| **id == order_id)
+            .map(|(broker_order_id, _)| *broker_order_id)
+    }
+
+    fn record(&self, event: JournalEvent) {
+        if let Some(journal) = &self.journal {
+            journal.record(event);
         }
     }
 
@@ -68,7 +383,27 @@ impl OrderManager {
         portfolio: &Portfolio,
         account_summary: &HashMap<String, f64>,
         max_margin_utilization: f64,
+        max_position_size: f64,
     ) -> Result<Order> {
+        check_concentration(&signal, portfolio, account_summary, max_position_size)?;
+        let required_buying_power = required_buying_power(&signal, portfolio);
+        if required_buying_power > 0.0
+            && let Some(&buying_power) = account_summary.get("buying_power")
+        {
+            let reserved = self.reserved_buying_power();
+            let available = buying_power - reserved;
+            if required_buying_power > available {
+                return Err(anyhow!(
+                    "Insufficient buying power for {}: required ${:.2}, available ${:.2} (${:.2} less ${:.2} reserved by open orders)",
+                    signal.symbol,
+                    required_buying_power,
+                    available,
+                    buying_power,
+                    reserved
+                ));
+            }
+        }
+
         // Check margin requirements for futures
         if signal.security_info.security_type == SecurityType::Future {
             let margin_validation = margin::validate_margin_requirements(
@@ -92,10 +427,33 @@ impl OrderManager {
             }
         }
 
-        Ok(self.create_order(signal))
+        let order = self.create_order(signal);
+        self.working_orders.insert(order.symbol.clone(), order.id);
+        if required_buying_power > 0.0 {
+            self.reserved_buying_power
+                .insert(order.id, required_buying_power);
+        }
+        Ok(order)
+    }
+
+    /// Buying power still held by working orders
+    ///
+    /// Each order reserves what it needed when created, released in
+    /// proportion as it fills and entirely once it stops working.
+    pub fn reserved_buying_power(&self) -> f64 {
+        self.orders
+            .iter()
+            .filter(|order| order.status.is_active())
+            .filter_map(|order| {
+                let reserved = self.reserved_buying_power.get(&order.id)?;
+                let unfilled = 1.0 - order.filled_quantity / order.quantity;
+                Some(reserved * unfilled.clamp(0.0, 1.0))
+            })
+            .sum()
     }
 
     pub fn create_order(&mut self, signal: OrderSignal) -> Order {
+        let now = Utc::now();
         let order = Order {
             id: self.next_order_id,
             symbol: signal.symbol,
@@ -105,7 +463,10 @@ impl OrderManager {
             limit_price: None,
             stop_price: None,
             status: OrderStatus::Pending,
-            timestamp: Utc::now(),
+            filled_quantity: 0.0,
+            average_fill_price: None,
+            timestamp: now,
+            updated_at: now,
             security_info: signal.security_info,
         };
 
@@ -122,18 +483,192 @@ impl OrderManager {
             "Created order #{}: {} {} of {} ({})",
             order.id, order.action, quantity_str, order.symbol, signal.reason
         );
+        self.record(JournalEvent::OrderCreated {
+            order_id: order.id,
+            symbol: order.symbol.clone(),
+            action: order.action.clone(),
+            quantity: order.quantity,
+            order_type: order.order_type.clone(),
+        });
 
         order
     }
 
+    /// Create a take-profit and stop-loss pair where a fill on one cancels the other
+    ///
+    /// The take-profit leg uses the signal's limit price (or price) and the
+    /// stop-loss leg uses the signal's price as its stop.
+    pub fn create_oco(&mut self, take_profit: OrderSignal, stop_loss: OrderSignal) -> OcoGroupId {
+        let take_profit_limit = take_profit.limit_price.unwrap_or(take_profit.price);
+        let stop_price = stop_loss.price;
+
+        let take_profit_id = self.create_order(take_profit).id;
+        let stop_loss_id = self.create_order(stop_loss).id;
+
+        if let Some(order) = self.orders.iter_mut().find(|o| o.id == take_profit_id) {
+            order.limit_price = Some(take_profit_limit);
+        }
+        if let Some(order) = self.orders.iter_mut().find(|o| o.id == stop_loss_id) {
+            order.stop_price = Some(stop_price);
+        }
+
+        let group_id = OcoGroupId(self.next_oco_id);
+        self.next_oco_id += 1;
+
+        self.oco_groups.insert(
+            group_id,
+            OcoGroup {
+                id: group_id,
+                order_ids: vec![take_profit_id, stop_loss_id],
+            },
+        );
+        self.order_to_oco.insert(take_profit_id, group_id);
+        self.order_to_oco.insert(stop_loss_id, group_id);
+
+        info!(
+            "Created OCO group {:?}: take-profit #{} / stop-loss #{}",
+            group_id, take_profit_id, stop_loss_id
+        );
+
+        group_id
+    }
+
+    /// Get an OCO group by id
+    pub fn oco_group(&self, group_id: OcoGroupId) -> Option<&OcoGroup> {
+        self.oco_groups.get(&group_id)
+    }
+
+    /// Other orders in the same OCO group as `order_id`
+    pub fn oco_siblings(&self, order_id: i32) -> Vec<i32> {
+        self.order_to_oco
+            .get(&order_id)
+            .and_then(|group_id| self.oco_groups.get(group_id))
+            .map(|group| {
+                group
+                    .order_ids
+                    .iter()
+                    .copied()
+                    .filter(|id| *id != order_id)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     pub fn update_order_status(&mut self, order_id: i32, status: OrderStatus) -> Result<()> {
-        if let Some(order) = self.orders.iter_mut().find(|o| o.id == order_id) {
-            order.status = status;
-            info!("Order #{} status updated to {:?}", order_id, order.status);
-            Ok(())
-        } else {
+        let cancels_siblings = matches!(status, OrderStatus::Filled | OrderStatus::Cancelled);
+
+        let Some(order) = self.orders.iter_mut().find(|o| o.id == order_id) else {
+            anyhow::bail!("Order {} not found", order_id)
+        };
+        order.status = status.clone();
+        order.updated_at = Utc::now();
+        info!("Order #{} status updated to {:?}", order_id, order.status);
+        if let Some(metrics) = &self.metrics {
+            match status {
+                OrderStatus::Submitted => metrics.inc_orders_placed(),
+                OrderStatus::Rejected => metrics.inc_orders_rejected(),
+                _ => {}
+            }
+        }
+
+        // Fills reported only through a broker status count toward the filled
+        // quantity; those from `record_fill` are counted and journaled already
+        let reported_filled = match status {
+            OrderStatus::PartiallyFilled { filled_qty, .. } => Some(filled_qty.min(order.quantity)),
+            OrderStatus::Filled => Some(order.quantity),
+            _ => None,
+        };
+        let fill = reported_filled
+            .filter(|filled| *filled > order.filled_quantity)
+            .map(|filled| {
+                let quantity = filled - order.filled_quantity;
+                order.filled_quantity = filled;
+                JournalEvent::Fill {
+                    order_id,
+                    symbol: order.symbol.clone(),
+                    action: order.action.clone(),
+                    quantity,
+                }
+            });
+        self.record(JournalEvent::OrderStatusChanged { order_id, status });
+        if let Some(fill) = fill {
+            self.record(fill);
+        }
+
+        // A fully filled or cancelled OCO leg cancels its still-working siblings
+        if cancels_siblings {
+            for sibling_id in self.oco_siblings(order_id) {
+                if let Some(sibling) = self
+                    .orders
+                    .iter_mut()
+                    .find(|o| o.id == sibling_id && o.status.is_active())
+                {
+                    sibling.status = OrderStatus::Cancelled;
+                    sibling.updated_at = Utc::now();
+                    info!(
+                        "Order #{} cancelled by OCO sibling #{}",
+                        sibling_id, order_id
+                    );
+                    self.record(JournalEvent::OrderStatusChanged {
+                        order_id: sibling_id,
+                        status: OrderStatus::Cancelled,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Accumulate an execution against an order
+    ///
+    /// The order is `PartiallyFilled` until the fills add up to its quantity,
+    /// then `Filled`.
+    pub fn record_fill(&mut self, order_id: i32, filled_qty: f64, fill_price: f64) -> Result<()> {
+        const EPSILON: f64 = 1e-9;
+
+        let Some(order) = self.orders.iter_mut().find(|o| o.id == order_id) else {
             anyhow::bail!("Order {} not found", order_id)
+        };
+        if !order.status.is_active() {
+            anyhow::bail!("Order {} is {:?} and cannot fill", order_id, order.status);
+        }
+        if filled_qty <= 0.0 {
+            anyhow::bail!("Fill quantity must be positive, got {}", filled_qty);
         }
+        let remaining = order.quantity - order.filled_quantity;
+        if filled_qty > remaining + EPSILON {
+            anyhow::bail!(
+                "Fill of {} exceeds remaining {} on order {}",
+                filled_qty,
+                remaining,
+                order_id
+            );
+        }
+
+        let total = order.filled_quantity + filled_qty;
+        let previous_cost = order.average_fill_price.unwrap_or(0.0) * order.filled_quantity;
+        order.average_fill_price = Some((previous_cost + fill_price * filled_qty) / total);
+        order.filled_quantity = total;
+
+        let fill = JournalEvent::Fill {
+            order_id,
+            symbol: order.symbol.clone(),
+            action: order.action.clone(),
+            quantity: filled_qty,
+        };
+        let remaining_qty = order.quantity - total;
+        self.record(fill);
+
+        let status = if remaining_qty <= EPSILON {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::PartiallyFilled {
+                filled_qty: total,
+                remaining_qty,
+            }
+        };
+        self.update_order_status(order_id, status)
     }
 
     pub fn get_pending_orders(&self) -> Vec<&Order> {
@@ -150,4 +685,633 @@ impl OrderManager {
     pub fn cancel_order(&mut self, order_id: i32) -> Result<()> {
         self.update_order_status(order_id, OrderStatus::Cancelled)
     }
+
+    /// All orders as flat trade records, in creation order
+    pub fn trade_records(&self) -> Vec<TradeRecord> {
+        self.orders.iter().map(TradeRecord::from).collect()
+    }
+
+    /// Write every order to a CSV file with a stable header
+    pub fn export_trades_csv(&self, path: &Path) -> Result<()> {
+        let mut csv = String::from(TRADE_CSV_HEADER);
+        csv.push('\n');
+
+        for record in self.trade_records() {
+            let fields = [
+                record.id.to_string(),
+                csv_field(&record.symbol),
+                csv_field(&record.action),
+                record.quantity.to_string(),
+                csv_field(&record.order_type),
+                record
+                    .limit_price
+                    .map(|p| p.to_string())
+                    .unwrap_or_default(),
+                record.stop_price.map(|p| p.to_string()).unwrap_or_default(),
+                csv_field(&format!("{:?}", record.status)),
+                record.created_at.to_rfc3339(),
+                record.updated_at.to_rfc3339(),
+            ];
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+
+        fs::write(path, csv)?;
+        info!(
+            "Exported {} orders to {}",
+            self.orders.len(),
+            path.display()
+        );
+        Ok(())
+    }
+
+    /// Write every order to a JSON array file
+    pub fn export_trades_json(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.trade_records())?;
+        fs::write(path, json)?;
+        info!(
+            "Exported {} orders to {}",
+            self.orders.len(),
+            path.display()
+        );
+        Ok(())
+    }
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Buying power needed for the part of `signal` that grows the position
+///
+/// Reductions and futures (covered by the margin check) need none.
+fn required_buying_power(signal: &OrderSignal, portfolio: &Portfolio) -> f64 {
+    if signal.security_info.security_type == SecurityType::Future {
+        return 0.0;
+    }
+    let current_quantity = portfolio
+        .get_position(&signal.symbol)
+        .map_or(0.0, |position| position.quantity);
+    let order_quantity = if signal.action == "SELL" {
+        -signal.quantity
+    } else {
+        signal.quantity
+    };
+    let resulting_quantity = current_quantity + order_quantity;
+    let opened_quantity = if current_quantity * order_quantity >= 0.0 {
+        order_quantity.abs()
+    } else if resulting_quantity * order_quantity > 0.0 {
+        resulting_quantity.abs() // Flips through flat: only the new side counts
+    } else {
+        0.0
+    };
+    signal
+        .security_info
+        .get_position_value(signal.price, opened_quantity)
+        .abs()
+}
+
+/// Reject orders that leave a single name above `max_position_size` percent
+/// of portfolio value
+///
+/// Exposure is the absolute value of the position after the order, so shorts
+/// count the same as longs. Orders that shrink exposure are always allowed.
+fn check_concentration(
+    signal: &OrderSignal,
+    portfolio: &Portfolio,
+    account_summary: &HashMap<String, f64>,
+    max_position_size: f64,
+) -> Result<()> {
+    let portfolio_value = account_summary
+        .get("net_liquidation")
+        .copied()
+        .unwrap_or_else(|| portfolio.get_stats().total_value);
+    if portfolio_value <= 0.0 {
+        return Err(anyhow!(
+            "Cannot check concentration for {}: portfolio value is ${:.2}",
+            signal.symbol,
+            portfolio_value
+        ));
+    }
+
+    let current_quantity = portfolio
+        .get_position(&signal.symbol)
+        .map_or(0.0, |position| position.quantity);
+    let order_quantity = if signal.action == "SELL" {
+        -signal.quantity
+    } else {
+        signal.quantity
+    };
+    let resulting_quantity = current_quantity + order_quantity;
+    if resulting_quantity.abs() <= current_quantity.abs() {
+        return Ok(());
+    }
+
+    let resulting_value = signal
+        .security_info
+        .get_position_value(signal.price, resulting_quantity)
+        .abs();
+    let max_value = portfolio_value * (max_position_size / 100.0);
+    if resulting_value > max_value {
+        return Err(anyhow!(
+            "Concentration limit exceeded for {}: resulting position ${:.2} is {:.1}% of portfolio, max {:.1}% (${:.2})",
+            signal.symbol,
+            resulting_value,
+            resulting_value / portfolio_value * 100.0,
+            max_position_size,
+            max_value
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signal(action: &str, order_type: &str, price: f64) -> OrderSignal {
+        OrderSignal {
+            symbol: "AAPL".to_string(),
+            action: action.to_string(),
+            quantity: 100.0,
+            price,
+            order_type: order_type.to_string(),
+            limit_price: None,
+            reason: "test".to_string(),
+            security_info: SecurityInfo::new_stock(
+                "AAPL".to_string(),
+                "SMART".to_string(),
+                "USD".to_string(),
+            ),
+        }
+    }
+
+    fn create_test_oco(manager: &mut OrderManager) -> (i32, i32) {
+        let group_id =
+            manager.create_oco(signal("SELL", "LMT", 110.0), signal("SELL", "STP", 95.0));
+        let group = manager.oco_group(group_id).unwrap();
+        (group.order_ids[0], group.order_ids[1])
+    }
+
+    #[test]
+    fn test_oco_siblings() {
+        let mut manager = OrderManager::new();
+        let (take_profit, stop_loss) = create_test_oco(&mut manager);
+
+        assert_eq!(manager.oco_siblings(take_profit), vec![stop_loss]);
+        assert_eq!(manager.oco_siblings(stop_loss), vec![take_profit]);
+        assert_eq!(
+            manager.get_order(take_profit).unwrap().limit_price,
+            Some(110.0)
+        );
+        assert_eq!(manager.get_order(stop_loss).unwrap().stop_price, Some(95.0));
+
+        let standalone = manager.create_order(signal("BUY", "MKT", 100.0));
+        assert!(manager.oco_siblings(standalone.id).is_empty());
+    }
+
+    #[test]
+    fn test_oco_fill_cancels_sibling() {
+        let mut manager = OrderManager::new();
+        let (take_profit, stop_loss) = create_test_oco(&mut manager);
+        manager
+            .update_order_status(take_profit, OrderStatus::Submitted)
+            .unwrap();
+        manager
+            .update_order_status(stop_loss, OrderStatus::Submitted)
+            .unwrap();
+
+        manager
+            .update_order_status(stop_loss, OrderStatus::Filled)
+            .unwrap();
+
+        assert_eq!(
+            manager.get_order(stop_loss).unwrap().status,
+            OrderStatus::Filled
+        );
+        assert_eq!(
+            manager.get_order(take_profit).unwrap().status,
+            OrderStatus::Cancelled
+        );
+    }
+
+    #[test]
+    fn test_oco_partial_fill_keeps_sibling() {
+        let mut manager = OrderManager::new();
+        let (take_profit, stop_loss) = create_test_oco(&mut manager);
+
+        manager
+            .update_order_status(
+                take_profit,
+                OrderStatus::PartiallyFilled {
+                    filled_qty: 40.0,
+                    remaining_qty: 60.0,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            manager.get_order(stop_loss).unwrap().status,
+            OrderStatus::Pending
+        );
+
+        manager
+            .update_order_status(take_profit, OrderStatus::Filled)
+            .unwrap();
+        assert_eq!(
+            manager.get_order(stop_loss).unwrap().status,
+            OrderStatus::Cancelled
+        );
+    }
+
+    #[test]
+    fn test_oco_cancel_cancels_sibling() {
+        let mut manager = OrderManager::new();
+        let (take_profit, stop_loss) = create_test_oco(&mut manager);
+
+        manager.cancel_order(take_profit).unwrap();
+
+        assert_eq!(
+            manager.get_order(stop_loss).unwrap().status,
+            OrderStatus::Cancelled
+        );
+    }
+
+    #[test]
+    fn test_export_trades_csv_and_json() {
+        let mut manager = OrderManager::new();
+        let filled = manager.create_order(signal("BUY", "MKT", 100.0)).id;
+        let cancelled = manager.create_order(signal("SELL", "LMT", 105.0)).id;
+        manager.create_order(signal("BUY", "MKT", 99.0));
+        manager
+            .update_order_status(filled, OrderStatus::Filled)
+            .unwrap();
+        manager.cancel_order(cancelled).unwrap();
+
+        let dir = std::env::temp_dir();
+        let csv_path = dir.join(format!("trades_{}.csv", std::process::id()));
+        let json_path = dir.join(format!("trades_{}.json", std::process::id()));
+        manager.export_trades_csv(&csv_path).unwrap();
+        manager.export_trades_json(&json_path).unwrap();
+
+        let csv = fs::read_to_string(&csv_path).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(TRADE_CSV_HEADER));
+        let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|row| row.len() == 10));
+        assert_eq!(rows[0][0], filled.to_string());
+        assert_eq!(rows[0][1], "AAPL");
+        assert_eq!(rows[0][2], "BUY");
+        assert_eq!(rows[0][3], "100");
+        assert_eq!(rows[0][7], "Filled");
+        assert_eq!(rows[1][7], "Cancelled");
+        assert_eq!(rows[2][7], "Pending");
+        assert!(chrono::DateTime::parse_from_rfc3339(rows[0][8]).is_ok());
+        assert!(rows[0][9] >= rows[0][8]);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 3);
+        assert_eq!(json[1]["status"], "Cancelled");
+
+        let _ = fs::remove_file(csv_path);
+        let _ = fs::remove_file(json_path);
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("AAPL"), "AAPL");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_concentration_limit_includes_existing_position() {
+        let mut manager = OrderManager::new();
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", 50.0, 100.0);
+        let summary = HashMap::from([("net_liquidation".to_string(), 100_000.0)]);
+
+        // 50 held + 100 bought = 150 * $100 = $15,000 = 15% > 10%
+        let error = manager
+            .validate_and_create_order(signal("BUY", "MKT", 100.0), &portfolio, &summary, 0.5, 10.0)
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("Concentration limit exceeded for AAPL")
+        );
+
+        // 50 held + 100 bought at $66 = $9,900, just under 10%
+        assert!(
+            manager
+                .validate_and_create_order(
+                    signal("BUY", "MKT", 66.0),
+                    &portfolio,
+                    &summary,
+                    0.5,
+                    10.0
+                )
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_buying_power_limit_counts_open_orders() {
+        let mut manager = OrderManager::new();
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", 50.0, 100.0);
+        let summary = HashMap::from([
+            ("net_liquidation".to_string(), 100_000.0),
+            ("buying_power".to_string(), 15_000.0),
+        ]);
+
+        // 100 shares at $200 is $20,000 against $15,000 of buying power
+        let error = manager
+            .validate_and_create_order(
+                signal("BUY", "MKT", 200.0),
+                &portfolio,
+                &summary,
+                0.5,
+                100.0,
+            )
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("Insufficient buying power for AAPL")
+        );
+
+        // $10,000 fits and reserves that much while working
+        let first = manager
+            .validate_and_create_order(
+                signal("BUY", "MKT", 100.0),
+                &portfolio,
+                &summary,
+                0.5,
+                100.0,
+            )
+            .unwrap();
+        assert_eq!(manager.reserved_buying_power(), 10_000.0);
+
+        // Another $10,000 only has $5,000 left
+        let error = manager
+            .validate_and_create_order(
+                signal("BUY", "MKT", 100.0),
+                &portfolio,
+                &summary,
+                0.5,
+                100.0,
+            )
+            .unwrap_err();
+        assert!(error.to_string().contains("available $5000.00"));
+
+        // Selling 100 against 50 held only opens a 50 share short: $5,000
+        manager
+            .validate_and_create_order(
+                signal("SELL", "MKT", 100.0),
+                &portfolio,
+                &summary,
+                0.5,
+                100.0,
+            )
+            .unwrap();
+        assert_eq!(manager.reserved_buying_power(), 15_000.0);
+
+        // A partial fill reported by the broker releases its share
+        manager.record_broker_order_id(first.id, 7);
+        let partial = OrderStatus::PartiallyFilled {
+            filled_qty: 40.0,
+            remaining_qty: 60.0,
+        };
+        assert_eq!(
+            manager.reconcile_broker_statuses(&HashMap::from([(7, partial)])),
+            1
+        );
+        assert_eq!(manager.get_order(first.id).unwrap().filled_quantity, 40.0);
+        assert_eq!(manager.reserved_buying_power(), 11_000.0);
+
+        // Once the first order stops working its reservation is released
+        manager
+            .update_order_status(first.id, OrderStatus::Cancelled)
+            .unwrap();
+        assert_eq!(manager.reserved_buying_power(), 5_000.0);
+        assert!(
+            manager
+                .validate_and_create_order(
+                    signal("BUY", "MKT", 100.0),
+                    &portfolio,
+                    &summary,
+                    0.5,
+                    100.0
+                )
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_concentration_limit_uses_absolute_exposure() {
+        let mut manager = OrderManager::new();
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", -50.0, 100.0);
+        let summary = HashMap::from([("net_liquidation".to_string(), 100_000.0)]);
+
+        // Short 50 + sell 100 = short 150 * $100 = 15%
+        assert!(
+            manager
+                .validate_and_create_order(
+                    signal("SELL", "MKT", 100.0),
+                    &portfolio,
+                    &summary,
+                    0.5,
+                    10.0
+                )
+                .is_err()
+        );
+
+        // Covering reduces exposure and is allowed even above the limit
+        portfolio.update_position("AAPL", -150.0, 100.0);
+        assert!(
+            manager
+                .validate_and_create_order(
+                    signal("BUY", "MKT", 100.0),
+                    &portfolio,
+                    &summary,
+                    0.5,
+                    10.0
+                )
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_min_holding_period_suppresses_early_exit() {
+        let manager = OrderManager::new().with_min_holding_period(Duration::minutes(60));
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", 100.0, 100.0);
+        let opened_at = portfolio.entry_time("AAPL").unwrap();
+
+        // Exiting 10 minutes after entry is churn
+        let error = manager
+            .check_holding_period(
+                &signal("SELL", "MKT", 100.0),
+                &portfolio,
+                opened_at + Duration::minutes(10),
+            )
+            .unwrap_err();
+        assert!(error.to_string().contains("SELL AAPL suppressed"));
+
+        // Adding to the position is not an exit
+        assert!(
+            manager
+                .check_holding_period(
+                    &signal("BUY", "MKT", 100.0),
+                    &portfolio,
+                    opened_at + Duration::minutes(10)
+                )
+                .is_ok()
+        );
+
+        // Once the window has passed the exit goes through
+        assert!(
+            manager
+                .check_holding_period(
+                    &signal("SELL", "MKT", 100.0),
+                    &portfolio,
+                    opened_at + Duration::minutes(61)
+                )
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_partial_fills_sum_to_filled() {
+        let mut manager = OrderManager::new();
+        let mut portfolio = Portfolio::new(100_000.0);
+        let order = manager.create_order(signal("BUY", "LMT", 100.0));
+        manager
+            .update_order_status(order.id, OrderStatus::Submitted)
+            .unwrap();
+
+        manager.record_fill(order.id, 40.0, 100.0).unwrap();
+        portfolio.apply_fill(&order, 40.0, 100.0);
+        assert_eq!(
+            manager.get_order(order.id).unwrap().status,
+            OrderStatus::PartiallyFilled {
+                filled_qty: 40.0,
+                remaining_qty: 60.0
+            }
+        );
+        assert_eq!(portfolio.get_position("AAPL").unwrap().quantity, 40.0);
+
+        manager.record_fill(order.id, 60.0, 101.0).unwrap();
+        portfolio.apply_fill(&order, 60.0, 101.0);
+        let filled = manager.get_order(order.id).unwrap();
+        assert_eq!(filled.status, OrderStatus::Filled);
+        assert_eq!(filled.filled_quantity, 100.0);
+        assert!((filled.average_fill_price.unwrap() - 100.6).abs() < 1e-9);
+        assert_eq!(portfolio.get_position("AAPL").unwrap().quantity, 100.0);
+
+        // Nothing left to fill
+        assert!(manager.record_fill(order.id, 1.0, 101.0).is_err());
+    }
+
+    #[test]
+    fn test_record_fill_rejects_overfill() {
+        let mut manager = OrderManager::new();
+        let id = manager.create_order(signal("SELL", "MKT", 100.0)).id;
+
+        assert!(manager.record_fill(id, 0.0, 100.0).is_err());
+        manager.record_fill(id, 70.0, 100.0).unwrap();
+        assert!(manager.record_fill(id, 40.0, 100.0).is_err());
+        assert_eq!(manager.get_order(id).unwrap().filled_quantity, 70.0);
+    }
+
+    #[test]
+    fn test_working_order_blocks_symbol_until_resolved() {
+        let mut manager = OrderManager::new();
+        let portfolio = Portfolio::new(100_000.0);
+        let summary = HashMap::from([("net_liquidation".to_string(), 100_000.0)]);
+        let create = |manager: &mut OrderManager| {
+            manager
+                .validate_and_create_order(
+                    signal("BUY", "MKT", 100.0),
+                    &portfolio,
+                    &summary,
+                    0.5,
+                    100.0,
+                )
+                .unwrap()
+        };
+
+        assert!(!manager.has_working_order("AAPL"));
+        let first = create(&mut manager);
+        assert!(manager.has_working_order("AAPL"));
+        manager
+            .update_order_status(first.id, OrderStatus::Submitted)
+            .unwrap();
+        assert_eq!(manager.working_order("AAPL").unwrap().id, first.id);
+        assert!(!manager.has_working_order("MSFT"));
+
+        // Resolved: the symbol is free again
+        manager.record_fill(first.id, 100.0, 100.0).unwrap();
+        assert!(!manager.has_working_order("AAPL"));
+
+        // A stuck order keeps blocking but is reported stale once the
+        // timeout passes, until it is cancelled
+        let mut manager = OrderManager::new().with_working_order_timeout(Duration::nanoseconds(1));
+        let stuck = create(&mut manager);
+        manager.record_broker_order_id(stuck.id, 42);
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        assert!(manager.has_working_order("AAPL"));
+        let stale: Vec<i32> = manager
+            .stale_working_orders()
+            .iter()
+            .map(|o| o.id)
+            .collect();
+        assert_eq!(stale, [stuck.id]);
+        assert_eq!(manager.broker_order_id(stuck.id), Some(42));
+        manager.cancel_order(stuck.id).unwrap();
+        assert!(!manager.has_working_order("AAPL"));
+        assert!(manager.stale_working_orders().is_empty());
+
+        // A zero timeout turns blocking off
+        let mut manager = OrderManager::new().with_working_order_timeout(Duration::zero());
+        create(&mut manager);
+        assert!(!manager.has_working_order("AAPL"));
+
+        // Protective legs rest indefinitely and are not tracked
+        let mut manager = OrderManager::new();
+        create_test_oco(&mut manager);
+        assert!(!manager.has_working_order("AAPL"));
+    }
+
+    #[test]
+    fn test_replaced_order_tracks_new_broker_id() {
+        let mut manager = OrderManager::new();
+        let order = manager.create_order(signal("BUY", "LMT", 100.0));
+        manager.record_broker_order_id(order.id, 10);
+        manager.replace_broker_order_id(10, 11);
+
+        // The cancelled original no longer speaks for the order
+        let statuses = HashMap::from([(10, OrderStatus::Cancelled), (11, OrderStatus::Submitted)]);
+        assert_eq!(manager.reconcile_broker_statuses(&statuses), 1);
+        assert_eq!(
+            manager.get_order(order.id).unwrap().status,
+            OrderStatus::Submitted
+        );
+
+        let statuses = HashMap::from([(11, OrderStatus::Filled)]);
+        assert_eq!(manager.reconcile_broker_statuses(&statuses), 1);
+        assert_eq!(
+            manager.get_order(order.id).unwrap().status,
+            OrderStatus::Filled
+        );
+    }
 }
@@ -0,0 +1,351 @@
+//! Mean-reversion trading of a two-symbol spread
+//!
+//! The spread is the log price ratio of `symbol_a` to `symbol_b` over the bars
+//! both symbols have. Its z-score against the last `lookback` bars decides the
+//! trade: above `entry_z` the first symbol is rich, so it is sold and the
+//! second bought (short spread); below `-entry_z` the reverse (long spread).
+//! An open spread closes once the z-score is back within `exit_z` of zero.
+//! Each leg is sized to `leg_notional` dollars.
+
+use crate::config::PairsConfig;
+use crate::market_data::MarketDataHandler;
+use crate::orders::OrderSignal;
+use crate::strategy::Strategy;
+use chrono::{DateTime, Utc};
+use log::debug;
+use statrs::statistics::Statistics;
+use std::collections::HashMap;
+
+/// Spread standard deviations below this are treated as a constant ratio
+const MIN_SPREAD_STD: f64 = 1e-10;
+
+/// Direction of the position held in the spread
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpreadPosition {
+    Flat,
+    Long,  // Long symbol_a, short symbol_b
+    Short, // Short symbol_a, long symbol_b
+    /// Legs that do not form a spread, e.g. after a partial fill
+    Unbalanced,
+}
+
+/// Spread z-score and the prices it was computed from
+struct SpreadSnapshot {
+    zscore: f64,
+    price_a: f64,
+    price_b: f64,
+}
+
+pub struct PairsStrategy {
+    config: PairsConfig,
+    positions: HashMap<String, f64>,
+}
+
+impl PairsStrategy {
+    pub fn new(config: PairsConfig) -> Self {
+        Self {
+            config,
+            positions: HashMap::new(),
+        }
+    }
+
+    /// Z-score of the latest spread against the lookback window
+    ///
+    /// None while fewer than `lookback` bars line up across both symbols, or
+    /// when the ratio has not moved over the window.
+    pub fn spread_zscore(&self, market_data: &MarketDataHandler) -> Option<f64> {
+        self.snapshot(market_data).map(|snapshot| snapshot.zscore)
+    }
+
+    pub fn spread_position(&self) -> SpreadPosition {
+        let quantity_a = self.position(&self.config.symbol_a);
+        let quantity_b = self.position(&self.config.symbol_b);
+        if quantity_a > 0.0 && quantity_b < 0.0 {
+            SpreadPosition::Long
+        } else if quantity_a < 0.0 && quantity_b > 0.0 {
+            SpreadPosition::Short
+        } else if quantity_a == 0.0 && quantity_b == 0.0 {
+            SpreadPosition::Flat
+        } else {
+            SpreadPosition::Unbalanced
+        }
+    }
+
+    fn position(&self, symbol: &str) -> f64 {
+        self.positions.get(symbol).copied().unwrap_or(0.0)
+    }
+
+    fn snapshot(&self, market_data: &MarketDataHandler) -> Option<SpreadSnapshot> {
+        let history_a = market_data.get_price_history(&self.config.symbol_a)?;
+        let history_b = market_data.get_price_history(&self.config.symbol_b)?;
+        let prices_b: HashMap<DateTime<Utc>, f64> = history_b.prices.iter().copied().collect();
+
+        let aligned: Vec<(f64, f64)> = history_a
+            .prices
+            .iter()
+            .filter_map(|(timestamp, price_a)| {
+                let price_b = *prices_b.get(timestamp)?;
+                (*price_a > 0.0 && price_b > 0.0).then_some((*price_a, price_b))
+            })
+            .collect();
+        if aligned.len() < self.config.lookback {
+            debug!(
+                "Pairs {}/{}: {} aligned bars, need {}",
+                self.config.symbol_a,
+                self.config.symbol_b,
+                aligned.len(),
+                self.config.lookback
+            );
+            return None;
+        }
+
+        let window = &aligned[aligned.len() - self.config.lookback..];
+        let spreads: Vec<f64> = window.iter().map(|(a, b)| (a / b).ln()).collect();
+        let mean = spreads.iter().mean();
+        let std_dev = spreads.iter().std_dev();
+        if std_dev.is_nan() || std_dev <= MIN_SPREAD_STD {
+            debug!(
+                "Pairs {}/{}: spread has no variance over {} bars",
+                self.config.symbol_a, self.config.symbol_b, self.config.lookback
+            );
+            return None;
+        }
+
+        let (price_a, price_b) = *window.last()?;
+        Some(SpreadSnapshot {
+            zscore: (spreads[spreads.len() - 1] - mean) / std_dev,
+            price_a,
+            price_b,
+        })
+    }
+
+    /// Where the spread position should be at `zscore`
+    fn target(&self, zscore: f64) -> SpreadPosition {
+        match self.spread_position() {
+            SpreadPosition::Long if zscore < -self.config.exit_z => SpreadPosition::Long,
+            SpreadPosition::Short if zscore > self.config.exit_z => SpreadPosition::Short,
+            _ if zscore > self.config.entry_z => SpreadPosition::Short,
+            _ if zscore < -self.config.entry_z => SpreadPosition::Long,
+            _ => SpreadPosition::Flat,
+        }
+    }
+}
+
+impl Strategy for PairsStrategy {
+    fn name(&self) -> &str {
+        "pairs"
+    }
+
+    /// Orders for both legs when the spread position changes
+    ///
+    /// Legs are only ever traded together: nothing is emitted unless both
+    /// symbols have security info and the entry sizes to at least one share
+    /// per leg.
+    fn calculate_signals(&mut self, market_data: &MarketDataHandler) -> Vec<OrderSignal> {
+        let Some(snapshot) = self.snapshot(market_data) else {
+            return Vec::new();
+        };
+        let current = self.spread_position();
+        let target = self.target(snapshot.zscore);
+        if target == current {
+            return Vec::new();
+        }
+
+        let (Some(security_a), Some(security_b)) = (
+            market_data.get_security_info(&self.config.symbol_a),
+            market_data.get_security_info(&self.config.symbol_b),
+        ) else {
+            debug!(
+                "Pairs {}/{}: missing security info, not trading",
+                self.config.symbol_a, self.config.symbol_b
+            );
+            return Vec::new();
+        };
+
+        let quantity_a = (self.config.leg_notional / snapshot.price_a).floor();
+        let quantity_b = (self.config.leg_notional / snapshot.price_b).floor();
+        let direction = match target {
+            SpreadPosition::Long => 1.0,
+            SpreadPosition::Short => -1.0,
+            SpreadPosition::Flat | SpreadPosition::Unbalanced => 0.0,
+        };
+        if direction != 0.0 && (quantity_a < 1.0 || quantity_b < 1.0) {
+            debug!(
+                "Pairs {}/{}: ${:.0} per leg buys no whole shares",
+                self.config.symbol_a, self.config.symbol_b, self.config.leg_notional
+            );
+            return Vec::new();
+        }
+
+        let reason = format!(
+            "Pairs {}/{} z={:.2}: {:?} -> {:?} spread",
+            self.config.symbol_a, self.config.symbol_b, snapshot.zscore, current, target
+        );
+        [
+            (security_a, direction * quantity_a, snapshot.price_a),
+            (security_b, -direction * quantity_b, snapshot.price_b),
+        ]
+        .into_iter()
+        .filter_map(|(security_info, target_quantity, price)| {
+            let delta = target_quantity - self.position(&security_info.symbol);
+            (delta != 0.0).then(|| OrderSignal {
+                symbol: security_info.symbol.clone(),
+                action: if delta > 0.0 { "BUY" } else { "SELL" }.to_string(),
+                quantity: delta.abs(),
+                price,
+                order_type: "MKT".to_string(),
+                limit_price: None,
+                reason: reason.clone(),
+                security_info: security_info.clone(),
+            })
+        })
+        .collect()
+    }
+
+    fn update_position(&mut self, symbol: &str, quantity: f64) {
+        self.positions.insert(symbol.to_string(), quantity);
+    }
+
+    fn get_positions(&self) -> &HashMap<String, f64> {
+        &self.positions
+    }
+
+    fn linked_legs(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.config.symbol_a.clone(),
+            self.config.symbol_b.clone(),
+        ]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security_types::SecurityInfo;
+
+    fn config() -> PairsConfig {
+        PairsConfig {
+            symbol_a: "KO".to_string(),
+            symbol_b: "PEP".to_string(),
+            lookback: 40,
+            entry_z: 2.0,
+            exit_z: 0.5,
+            leg_notional: 10_000.0,
+        }
+    }
+
+    /// `KO` priced off `PEP` (flat at $100) so the log ratio follows `spreads`
+    fn market_data(spreads: &[f64]) -> MarketDataHandler {
+        let mut handler = MarketDataHandler::new();
+        for (req_id, symbol) in [(1, "KO"), (2, "PEP")] {
+            handler.register_security(
+                symbol.to_string(),
+                SecurityInfo::new_stock(symbol.to_string(), "SMART".to_string(), "USD".to_string()),
+            );
+            handler.register_symbol(req_id, symbol.to_string());
+        }
+        for (i, spread) in spreads.iter().enumerate() {
+            let timestamp =
+                time::OffsetDateTime::from_unix_timestamp(1_700_000_000 + i as i64 * 86_400)
+                    .unwrap();
+            handler.add_historical_price("KO", timestamp, 100.0 * spread.exp());
+            handler.add_historical_price("PEP", timestamp, 100.0);
+        }
+        handler
+    }
+
+    /// A spread oscillating around zero, reverting every 10 bars
+    fn oscillating(bars: usize) -> Vec<f64> {
+        (0..bars)
+            .map(|i| 0.01 * (i as f64 * std::f64::consts::TAU / 10.0).sin())
+            .collect()
+    }
+
+    fn legs(signals: &[OrderSignal]) -> Vec<(&str, &str, f64)> {
+        signals
+            .iter()
+            .map(|s| (s.symbol.as_str(), s.action.as_str(), s.quantity))
+            .collect()
+    }
+
+    #[test]
+    fn test_enters_short_spread_when_first_leg_is_rich() {
+        let mut spreads = oscillating(59);
+        spreads.push(0.08);
+        let handler = market_data(&spreads);
+        let mut strategy = PairsStrategy::new(config());
+
+        assert!(strategy.spread_zscore(&handler).unwrap() > 2.0);
+        let signals = strategy.calculate_signals(&handler);
+
+        // KO at $108.33 sizes to 92 shares, PEP at $100 to 100
+        assert_eq!(
+            legs(&signals),
+            [("KO", "SELL", 92.0), ("PEP", "BUY", 100.0)]
+        );
+    }
+
+    #[test]
+    fn test_enters_long_spread_when_first_leg_is_cheap() {
+        let mut spreads = oscillating(59);
+        spreads.push(-0.08);
+        let mut strategy = PairsStrategy::new(config());
+
+        let signals = strategy.calculate_signals(&market_data(&spreads));
+
+        assert_eq!(
+            legs(&signals),
+            [("KO", "BUY", 108.0), ("PEP", "SELL", 100.0)]
+        );
+    }
+
+    #[test]
+    fn test_exits_when_spread_reverts_to_mean() {
+        let mut spreads = oscillating(59);
+        spreads.push(0.08);
+        let mut strategy = PairsStrategy::new(config());
+        strategy.update_position("KO", -92.0);
+        strategy.update_position("PEP", 100.0);
+        assert_eq!(strategy.spread_position(), SpreadPosition::Short);
+
+        // Still stretched: hold
+        assert!(
+            strategy
+                .calculate_signals(&market_data(&spreads))
+                .is_empty()
+        );
+
+        spreads.push(0.0);
+        let handler = market_data(&spreads);
+        assert!(strategy.spread_zscore(&handler).unwrap().abs() < 0.5);
+        let signals = strategy.calculate_signals(&handler);
+
+        assert_eq!(
+            legs(&signals),
+            [("KO", "BUY", 92.0), ("PEP", "SELL", 100.0)]
+        );
+    }
+
+    #[test]
+    fn test_no_trade_inside_entry_band() {
+        let mut strategy = PairsStrategy::new(config());
+        assert!(
+            strategy
+                .calculate_signals(&market_data(&oscillating(60)))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_insufficient_history_and_constant_spread() {
+        let mut strategy = PairsStrategy::new(config());
+
+        let short = market_data(&oscillating(39));
+        assert_eq!(strategy.spread_zscore(&short), None);
+        assert!(strategy.calculate_signals(&short).is_empty());
+
+        let constant = market_data(&[0.05; 60]);
+        assert_eq!(strategy.spread_zscore(&constant), None);
+        assert!(strategy.calculate_signals(&constant).is_empty());
+    }
+}
@@ -1,7 +1,16 @@
+use crate::clock::{SharedClock, system_clock};
 use crate::connection::AccountPosition;
+use crate::orders::Order;
 use crate::security_types::{SecurityInfo, SecurityType};
+use crate::signals::{SignalAttribution, SignalType};
+use crate::stats;
+use anyhow::{Result, anyhow};
 use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+
+const MAX_EQUITY_POINTS: usize = 100_000; // Oldest samples are dropped beyond this
+const MAX_TRADES: usize = 50_000; // Fills kept for turnover, oldest dropped first
+const MAX_HOLDING_PERIODS: usize = 10_000; // Closed positions kept for the average holding period
 
 #[derive(Debug, Clone)]
 pub struct Position {
@@ -18,6 +27,16 @@ pub struct Position {
     pub margin_utilization: f64,
 }
 
+/// Open quantity bought (or sold short) at a single price
+///
+/// Negative quantity is a short lot.
+#[derive(Debug, Clone)]
+pub struct TaxLot {
+    pub quantity: f64,
+    pub price: f64,
+    pub opened_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone)]
 pub struct PortfolioStats {
     pub total_value: f64,
@@ -25,19 +44,31 @@ pub struct PortfolioStats {
     pub total_unrealized_pnl: f64,
     pub total_realized_pnl: f64,
     pub positions_count: usize,
+    /// Positions left out of the totals for lack of an exchange rate
+    pub unconverted_positions: Vec<String>,
     pub timestamp: DateTime<Utc>,
 }
 
 pub struct Portfolio {
     positions: HashMap<String, Position>,
     cash_balance: f64,
-    total_realized_pnl: f64,
+    tax_lots: HashMap<String, VecDeque<TaxLot>>, // Open lots per symbol, oldest first
+    realized_pnl: HashMap<String, f64>,
+    signal_attributions: HashMap<String, SignalAttribution>, // Signal mix behind each open position
+    pnl_by_signal_type: HashMap<SignalType, f64>, // Realized P&L credited per signal type
     security_map: HashMap<String, SecurityInfo>,
+    equity_curve: VecDeque<(DateTime<Utc>, f64)>,
+    entry_times: HashMap<String, (DateTime<Utc>, bool)>, // When each position was opened, and whether long
+    holding_periods: VecDeque<chrono::Duration>,         // How long each closed position was held
+    trades: VecDeque<(DateTime<Utc>, f64)>,              // Absolute notional of each fill
+    base_currency: String,
+    fx_rates: HashMap<String, f64>, // Base-currency value of one unit of each currency
     // Margin tracking
     pub total_initial_margin: f64,
     pub total_maintenance_margin: f64,
     pub excess_liquidity: f64,
     pub margin_cushion: f64,
+    clock: SharedClock,
 }
 
 impl Portfolio {
@@ -45,42 +76,224 @@ impl Portfolio {
         Self {
             positions: HashMap::new(),
             cash_balance: initial_cash,
-            total_realized_pnl: 0.0,
+            tax_lots: HashMap::new(),
+            realized_pnl: HashMap::new(),
+            signal_attributions: HashMap::new(),
+            pnl_by_signal_type: HashMap::new(),
             security_map: HashMap::new(),
+            equity_curve: VecDeque::new(),
+            entry_times: HashMap::new(),
+            holding_periods: VecDeque::new(),
+            trades: VecDeque::new(),
+            base_currency: "USD".to_string(),
+            fx_rates: HashMap::new(),
             total_initial_margin: 0.0,
             total_maintenance_margin: 0.0,
             excess_liquidity: initial_cash,
             margin_cushion: 1.0,
+            clock: system_clock(),
         }
     }
 
+    /// Time source for fills, entries and turnover windows
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Current time on the portfolio's clock
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
     pub fn register_security(&mut self, symbol: String, security_info: SecurityInfo) {
         self.security_map.insert(symbol, security_info);
     }
 
-    pub fn update_position(&mut self, symbol: &str, quantity: f64, price: f64) {
-        if let Some(position) = self.positions.get_mut(symbol) {
-            if quantity > 0.0 {
-                let total_cost = position.average_cost * position.quantity + price * quantity;
-                position.quantity += quantity;
-                position.average_cost = total_cost / position.quantity;
-            } else {
-                let sell_quantity = quantity.abs();
-                let realized_pnl = sell_quantity * (price - position.average_cost);
-                position.realized_pnl += realized_pnl;
-                self.total_realized_pnl += realized_pnl;
-                position.quantity -= sell_quantity;
-
-                if position.quantity <= 0.0 {
-                    self.positions.remove(symbol);
+    /// Currency that stats are reported in, USD unless set
+    pub fn base_currency(&self) -> &str {
+        &self.base_currency
+    }
+
+    pub fn set_base_currency(&mut self, currency: &str) {
+        self.base_currency = currency.to_string();
+    }
+
+    /// Register how much one unit of `currency` is worth in the base currency
+    pub fn set_fx_rate(&mut self, currency: &str, rate_to_base: f64) {
+        self.fx_rates.insert(currency.to_string(), rate_to_base);
+    }
+
+    /// Refresh FX rates from the quotes of registered forex pairs
+    ///
+    /// Pairs against the base currency price their other currency directly;
+    /// crosses then price any currency reachable through one already known,
+    /// e.g. GBP from EUR.GBP once EUR.USD is quoted in a USD account.
+    fn update_fx_rates(&mut self, prices: &HashMap<String, f64>) {
+        let quotes: Vec<(&str, &str, f64)> = self
+            .security_map
+            .iter()
+            .filter_map(|(symbol, info)| {
+                let pair = info.forex_pair.as_ref()?;
+                let price = prices.get(symbol).copied().filter(|price| *price > 0.0)?;
+                Some((
+                    pair.base_currency.as_str(),
+                    pair.quote_currency.as_str(),
+                    price,
+                ))
+            })
+            .collect();
+
+        let mut rates = HashMap::from([(self.base_currency.clone(), 1.0)]);
+        loop {
+            let before = rates.len();
+            for &(base, quote, price) in &quotes {
+                match (rates.get(base).copied(), rates.get(quote).copied()) {
+                    (Some(base_rate), None) => {
+                        rates.insert(quote.to_string(), base_rate / price);
+                    }
+                    (None, Some(quote_rate)) => {
+                        rates.insert(base.to_string(), quote_rate * price);
+                    }
+                    _ => {}
                 }
             }
-        } else if quantity > 0.0 {
-            self.positions.insert(
-                symbol.to_string(),
-                Position {
+            if rates.len() == before {
+                break;
+            }
+        }
+        rates.remove(&self.base_currency);
+        self.fx_rates.extend(rates);
+    }
+
+    /// Base-currency value of one unit of `currency`, if known
+    pub fn fx_rate(&self, currency: &str) -> Option<f64> {
+        if currency == self.base_currency {
+            return Some(1.0);
+        }
+        self.fx_rates.get(currency).copied()
+    }
+
+    /// Rate converting a position's value and P&L into the base currency
+    ///
+    /// Forex positions are valued in the pair's quote currency, everything
+    /// else in its listing currency. Without a registered rate for the quote
+    /// currency a pair converts at its own current quote, directly when based
+    /// in the base currency (e.g. USD.JPY in a USD account) or through its
+    /// base currency's rate for crosses. Positions without security info are
+    /// taken to be in the base currency.
+    fn position_fx_rate(&self, position: &Position) -> Option<f64> {
+        let Some(security_info) = &position.security_info else {
+            return Some(1.0);
+        };
+        match &security_info.forex_pair {
+            Some(pair) => self
+                .fx_rate(&pair.quote_currency)
+                .or_else(|| pair.quote_to_account_rate(position.current_price, &self.base_currency))
+                .or_else(|| {
+                    let base_rate = self.fx_rate(&pair.base_currency)?;
+                    (position.current_price > 0.0).then(|| base_rate / position.current_price)
+                }),
+            None => self.fx_rate(&security_info.currency),
+        }
+    }
+
+    /// Base-currency value of the position at its current price
+    pub fn position_value(&self, symbol: &str) -> Option<f64> {
+        let position = self.positions.get(symbol)?;
+        let rate = self.position_fx_rate(position)?;
+        let value = match &position.security_info {
+            Some(security_info) => {
+                security_info.get_position_value(position.current_price, position.quantity)
+            }
+            None => position.quantity * position.current_price,
+        };
+        Some(value * rate)
+    }
+
+    /// Apply a fill to the position, closing open lots first-in-first-out
+    ///
+    /// Positive quantity buys, negative sells. Quantity beyond what closes the
+    /// existing lots opens a new lot, so selling more than held goes short.
+    pub fn update_position(&mut self, symbol: &str, quantity: f64, price: f64) {
+        self.update_position_at(symbol, quantity, price, self.clock.now());
+    }
+
+    /// `update_position` for a fill that happened at `timestamp`
+    pub fn update_position_at(
+        &mut self,
+        symbol: &str,
+        quantity: f64,
+        price: f64,
+        timestamp: DateTime<Utc>,
+    ) {
+        self.cash_balance -= self.book_trade(symbol, quantity, price, timestamp);
+    }
+
+    /// Book a trade against `symbol`'s lots, leaving cash alone
+    ///
+    /// Records realized P&L and its signal attribution, entry and holding
+    /// times and traded notional. Returns the trade's value.
+    fn book_trade(
+        &mut self,
+        symbol: &str,
+        quantity: f64,
+        price: f64,
+        timestamp: DateTime<Utc>,
+    ) -> f64 {
+        let multiplier = self.pnl_multiplier(symbol);
+        let lots = self.tax_lots.entry(symbol.to_string()).or_default();
+
+        let mut remaining = quantity;
+        let mut realized = 0.0;
+        while remaining != 0.0 {
+            let Some(lot) = lots.front_mut() else { break };
+            if lot.quantity.signum() == remaining.signum() {
+                break;
+            }
+
+            // Close as much of the oldest lot as this fill covers
+            let closed = remaining.abs().min(lot.quantity.abs()) * lot.quantity.signum();
+            realized += closed * (price - lot.price) * multiplier;
+            lot.quantity -= closed;
+            remaining += closed;
+
+            if lot.quantity == 0.0 {
+                lots.pop_front();
+            }
+        }
+        if remaining != 0.0 {
+            lots.push_back(TaxLot {
+                quantity: remaining,
+                price,
+                opened_at: timestamp,
+            });
+        }
+
+        let open_quantity: f64 = lots.iter().map(|lot| lot.quantity).sum();
+        let average_cost = if open_quantity != 0.0 {
+            lots.iter().map(|lot| lot.quantity * lot.price).sum::<f64>() / open_quantity
+        } else {
+            0.0
+        };
+        if lots.is_empty() {
+            self.tax_lots.remove(symbol);
+        }
+
+        *self.realized_pnl.entry(symbol.to_string()).or_insert(0.0) += realized;
+        self.attribute_realized_pnl(symbol, realized);
+        self.record_entry(symbol, open_quantity, timestamp);
+
+        if open_quantity == 0.0 {
+            self.positions.remove(symbol);
+            self.signal_attributions.remove(symbol);
+        } else {
+            let position = self
+                .positions
+                .entry(symbol.to_string())
+                .or_insert_with(|| Position {
                     symbol: symbol.to_string(),
-                    quantity,
+                    quantity: 0.0,
                     average_cost: price,
                     current_price: price,
                     unrealized_pnl: 0.0,
@@ -89,8 +302,10 @@ impl Portfolio {
                     initial_margin: 0.0,
                     maintenance_margin: 0.0,
                     margin_utilization: 0.0,
-                },
-            );
+                });
+            position.quantity = open_quantity;
+            position.average_cost = average_cost;
+            position.realized_pnl += realized;
         }
 
         let trade_value = if let Some(security_info) = self.security_map.get(symbol) {
@@ -98,10 +313,179 @@ impl Portfolio {
         } else {
             quantity * price
         };
-        self.cash_balance -= trade_value;
+
+        if self.trades.len() == MAX_TRADES {
+            self.trades.pop_front();
+        }
+        self.trades.push_back((timestamp, trade_value.abs()));
+        trade_value
+    }
+
+    /// Apply an execution of `order`, signed by its action
+    pub fn apply_fill(&mut self, order: &Order, quantity: f64, price: f64) {
+        let signed = if order.action == "SELL" {
+            -quantity
+        } else {
+            quantity
+        };
+        self.update_position(&order.symbol, signed, price);
+    }
+
+    /// Realized P&L for a symbol from FIFO lot matching
+    pub fn realized_pnl(&self, symbol: &str) -> f64 {
+        self.realized_pnl.get(symbol).copied().unwrap_or(0.0)
+    }
+
+    /// Realized P&L across all symbols
+    pub fn total_realized_pnl(&self) -> f64 {
+        self.realized_pnl.values().sum()
+    }
+
+    /// Tag `symbol`'s position with the signal mix behind its entry
+    ///
+    /// The first tag sticks until the position is closed, so adds and trims
+    /// do not rewrite why it was opened.
+    pub fn tag_signal_attribution(&mut self, symbol: &str, attribution: SignalAttribution) {
+        self.signal_attributions
+            .entry(symbol.to_string())
+            .or_insert(attribution);
+    }
+
+    /// Signal type with the largest contribution to `symbol`'s entry
+    pub fn dominant_signal_type(&self, symbol: &str) -> Option<&SignalType> {
+        self.signal_attributions
+            .get(symbol)?
+            .contributions
+            .iter()
+            .max_by(|a, b| a.contribution.abs().total_cmp(&b.contribution.abs()))
+            .map(|contribution| &contribution.signal_type)
+    }
+
+    /// Realized P&L credited to each signal type
+    ///
+    /// Only P&L on tagged positions is attributed, so the buckets can sum to
+    /// less than `total_realized_pnl`.
+    pub fn pnl_by_signal_type(&self) -> HashMap<SignalType, f64> {
+        self.pnl_by_signal_type.clone()
+    }
+
+    /// Split `realized` across the signal types of `symbol`'s tag in
+    /// proportion to the size of each one's contribution
+    fn attribute_realized_pnl(&mut self, symbol: &str, realized: f64) {
+        let Some(attribution) = self.signal_attributions.get(symbol) else {
+            return;
+        };
+        let total: f64 = attribution
+            .contributions
+            .iter()
+            .map(|c| c.contribution.abs())
+            .sum();
+        if realized == 0.0 || total <= 0.0 {
+            return;
+        }
+        for contribution in &attribution.contributions {
+            *self
+                .pnl_by_signal_type
+                .entry(contribution.signal_type.clone())
+                .or_insert(0.0) += realized * contribution.contribution.abs() / total;
+        }
+    }
+
+    /// When the current position in `symbol` was opened
+    ///
+    /// Adding to or trimming a position keeps its entry time; going flat or
+    /// flipping direction starts a new one.
+    pub fn entry_time(&self, symbol: &str) -> Option<DateTime<Utc>> {
+        self.entry_times
+            .get(symbol)
+            .map(|(opened_at, _)| *opened_at)
+    }
+
+    /// Date the opening of a held position, e.g. from broker executions after
+    /// a restart; later syncs in the same direction keep it
+    pub fn seed_entry_time(&mut self, symbol: &str, quantity: f64, opened_at: DateTime<Utc>) {
+        if quantity != 0.0 {
+            self.entry_times
+                .insert(symbol.to_string(), (opened_at, quantity > 0.0));
+        }
+    }
+
+    fn record_entry(&mut self, symbol: &str, quantity: f64, timestamp: DateTime<Utc>) {
+        let long = quantity > 0.0;
+        let closed = match self.entry_times.get(symbol) {
+            Some((_, was_long)) => quantity == 0.0 || *was_long != long,
+            None => false,
+        };
+        if closed && let Some((opened_at, _)) = self.entry_times.remove(symbol) {
+            if self.holding_periods.len() == MAX_HOLDING_PERIODS {
+                self.holding_periods.pop_front();
+            }
+            self.holding_periods.push_back(timestamp - opened_at);
+        }
+        if quantity != 0.0 && !self.entry_times.contains_key(symbol) {
+            self.entry_times
+                .insert(symbol.to_string(), (timestamp, long));
+        }
+    }
+
+    /// Mean time from opening to closing a position, over closed positions
+    ///
+    /// A flip from long to short closes one position and opens another.
+    /// None until a position has been closed.
+    pub fn average_holding_period(&self) -> Option<chrono::Duration> {
+        if self.holding_periods.is_empty() {
+            return None;
+        }
+        let total: chrono::Duration = self.holding_periods.iter().sum();
+        Some(total / self.holding_periods.len() as i32)
+    }
+
+    /// Notional traded over the last `window` divided by the average equity
+    /// sampled in it
+    ///
+    /// A ratio of 1.0 means the portfolio turned over its whole value once.
+    /// None without an equity sample in the window.
+    pub fn turnover_ratio(&self, window: chrono::Duration) -> Option<f64> {
+        let since = self.clock.now() - window;
+        let equity: Vec<f64> = self
+            .equity_curve
+            .iter()
+            .filter(|(timestamp, _)| *timestamp >= since)
+            .map(|(_, equity)| *equity)
+            .collect();
+        if equity.is_empty() {
+            return None;
+        }
+        let average_equity = equity.iter().sum::<f64>() / equity.len() as f64;
+        if average_equity <= 0.0 {
+            return None;
+        }
+        let traded: f64 = self
+            .trades
+            .iter()
+            .filter(|(timestamp, _)| *timestamp >= since)
+            .map(|(_, notional)| notional)
+            .sum();
+        Some(traded / average_equity)
+    }
+
+    /// Open tax lots for a symbol, oldest first
+    pub fn tax_lots(&self, symbol: &str) -> Option<&VecDeque<TaxLot>> {
+        self.tax_lots.get(symbol)
+    }
+
+    /// Contract multiplier applied to per-unit price moves
+    fn pnl_multiplier(&self, symbol: &str) -> f64 {
+        self.security_map
+            .get(symbol)
+            .filter(|info| info.security_type == SecurityType::Future)
+            .and_then(|info| info.contract_specs.as_ref())
+            .map(|specs| specs.multiplier)
+            .unwrap_or(1.0)
     }
 
     pub fn update_market_prices(&mut self, prices: &HashMap<String, f64>) {
+        self.update_fx_rates(prices);
         for (symbol, position) in &mut self.positions {
             if let Some(&price) = prices.get(symbol) {
                 position.current_price = price;
@@ -126,29 +510,171 @@ impl Portfolio {
         }
     }
 
+    /// Totals in the base currency
+    ///
+    /// Each position's value and unrealized P&L is converted at its
+    /// currency's registered rate. Positions in a currency without a rate are
+    /// left out of the totals and listed in `unconverted_positions`.
     pub fn get_stats(&self) -> PortfolioStats {
-        let total_position_value: f64 = self
-            .positions
-            .values()
-            .map(|p| {
-                if let Some(security_info) = &p.security_info {
-                    security_info.get_position_value(p.current_price, p.quantity)
-                } else {
-                    p.quantity * p.current_price
-                }
-            })
-            .sum();
-
-        let total_unrealized_pnl: f64 = self.positions.values().map(|p| p.unrealized_pnl).sum();
+        let mut total_position_value = 0.0;
+        let mut total_unrealized_pnl = 0.0;
+        let mut unconverted_positions = Vec::new();
+        for p in self.positions.values() {
+            let (Some(rate), Some(value)) =
+                (self.position_fx_rate(p), self.position_value(&p.symbol))
+            else {
+                unconverted_positions.push(p.symbol.clone());
+                continue;
+            };
+            total_position_value += value;
+            total_unrealized_pnl += p.unrealized_pnl * rate;
+        }
+        unconverted_positions.sort();
 
         PortfolioStats {
             total_value: self.cash_balance + total_position_value,
             cash_balance: self.cash_balance,
             total_unrealized_pnl,
-            total_realized_pnl: self.total_realized_pnl,
+            total_realized_pnl: self.total_realized_pnl(),
             positions_count: self.positions.len(),
-            timestamp: Utc::now(),
+            unconverted_positions,
+            timestamp: self.clock.now(),
+        }
+    }
+
+    /// Append an account equity sample to the equity curve
+    pub fn record_equity(&mut self, timestamp: DateTime<Utc>, equity: f64) {
+        if self.equity_curve.len() == MAX_EQUITY_POINTS {
+            self.equity_curve.pop_front();
         }
+        self.equity_curve.push_back((timestamp, equity));
+    }
+
+    pub fn equity_curve(&self) -> &VecDeque<(DateTime<Utc>, f64)> {
+        &self.equity_curve
+    }
+
+    /// Period-over-period returns between equity samples
+    fn equity_returns(&self) -> Vec<f64> {
+        self.equity_curve
+            .iter()
+            .zip(self.equity_curve.iter().skip(1))
+            .filter(|((_, previous), _)| *previous > 0.0)
+            .map(|((_, previous), (_, current))| current / previous - 1.0)
+            .collect()
+    }
+
+    /// Sortino ratio of per-sample equity returns against `target_return`
+    ///
+    /// Not annualized, since samples need not be evenly spaced. Errors when
+    /// there are no returns or no return falls below the target.
+    pub fn sortino_ratio(&self, target_return: f64) -> Result<f64> {
+        let returns = self.equity_returns();
+        if returns.is_empty() {
+            return Err(anyhow!(
+                "Cannot calculate Sortino ratio: fewer than two equity samples"
+            ));
+        }
+
+        let downside_sum: f64 = returns
+            .iter()
+            .map(|r| (r - target_return).min(0.0).powi(2))
+            .sum();
+        let downside_deviation = (downside_sum / returns.len() as f64).sqrt();
+        if downside_deviation == 0.0 {
+            return Err(anyhow!(
+                "Cannot calculate Sortino ratio: zero downside deviation"
+            ));
+        }
+
+        let mean_return = returns.iter().sum::<f64>() / returns.len() as f64;
+        Ok((mean_return - target_return) / downside_deviation)
+    }
+
+    /// Beta of equity returns to `benchmark_returns`
+    ///
+    /// The series are aligned on their most recent returns when their
+    /// lengths differ.
+    pub fn beta_to(&self, benchmark_returns: &[f64]) -> Result<f64> {
+        Ok(self.regress_on(benchmark_returns)?.1)
+    }
+
+    /// Per-period alpha of equity returns over `benchmark_returns`
+    pub fn alpha_to(&self, benchmark_returns: &[f64]) -> Result<f64> {
+        Ok(self.regress_on(benchmark_returns)?.0)
+    }
+
+    fn regress_on(&self, benchmark_returns: &[f64]) -> Result<(f64, f64)> {
+        let returns = self.equity_returns();
+        let len = returns.len().min(benchmark_returns.len());
+        stats::alpha_beta(
+            &returns[returns.len() - len..],
+            &benchmark_returns[benchmark_returns.len() - len..],
+        )
+    }
+
+    /// Annualized return of the equity curve divided by its maximum drawdown
+    ///
+    /// Errors when the curve spans no time or has no drawdown.
+    pub fn calmar_ratio(&self) -> Result<f64> {
+        let (Some(&(start, first)), Some(&(end, last))) =
+            (self.equity_curve.front(), self.equity_curve.back())
+        else {
+            return Err(anyhow!("Cannot calculate Calmar ratio: empty equity curve"));
+        };
+
+        let years = (end - start).num_seconds() as f64 / (365.25 * 24.0 * 3600.0);
+        if years <= 0.0 || first <= 0.0 {
+            return Err(anyhow!(
+                "Cannot calculate Calmar ratio: equity curve spans no time"
+            ));
+        }
+
+        let values: Vec<f64> = self
+            .equity_curve
+            .iter()
+            .map(|(_, equity)| *equity)
+            .collect();
+        let max_drawdown = stats::max_drawdown(&values)?;
+        if max_drawdown == 0.0 {
+            return Err(anyhow!("Cannot calculate Calmar ratio: zero drawdown"));
+        }
+
+        let annual_return = (last / first).powf(1.0 / years) - 1.0;
+        Ok(annual_return / max_drawdown)
+    }
+
+    /// Quote-to-account conversion rate for a forex position at its current price
+    ///
+    /// Crosses not involving `account_currency` convert through the FX rates
+    /// when it is the base currency.
+    fn forex_account_rate(&self, position: &Position, account_currency: &str) -> Option<f64> {
+        let pair = position.security_info.as_ref()?.forex_pair.as_ref()?;
+        pair.quote_to_account_rate(position.current_price, account_currency)
+            .or_else(|| {
+                (account_currency == self.base_currency)
+                    .then(|| self.position_fx_rate(position))
+                    .flatten()
+            })
+    }
+
+    /// Unrealized P&L of a forex position in `account_currency`
+    ///
+    /// Position P&L is tracked in the pair's quote currency; this converts it
+    /// at the current quote. None for non-forex symbols and crosses without a
+    /// rate.
+    pub fn forex_unrealized_pnl(&self, symbol: &str, account_currency: &str) -> Option<f64> {
+        let position = self.positions.get(symbol)?;
+        let rate = self.forex_account_rate(position, account_currency)?;
+        Some(position.unrealized_pnl * rate)
+    }
+
+    /// Account-currency value of a one-pip move on the held forex position
+    pub fn forex_pip_value(&self, symbol: &str, account_currency: &str) -> Option<f64> {
+        let position = self.positions.get(symbol)?;
+        let rate = self.forex_account_rate(position, account_currency)?;
+        let pair = position.security_info.as_ref()?.forex_pair.as_ref()?;
+        Some(pair.pip_value(position.quantity.abs(), rate))
     }
 
     pub fn get_position(&self, symbol: &str) -> Option<&Position> {
@@ -207,14 +733,96 @@ impl Portfolio {
         self.cash_balance = cash;
     }
 
+    /// Symbol a TWS position is tracked under
+    ///
+    /// TWS reports a forex position under its base currency (symbol "EUR",
+    /// currency "USD") with the quantity in base-currency units. It maps onto
+    /// the registered pair with that base and quote, or "EUR.USD" if none is
+    /// registered. A future maps onto the registered contract of its
+    /// underlying, picking by contract month when several months are
+    /// registered (a calendar position). Other positions keep their symbol.
+    pub fn tws_position_symbol(&self, tws_pos: &AccountPosition) -> String {
+        let contract = &tws_pos.contract;
+        match contract.security_type {
+            ibapi::contracts::SecurityType::ForexPair => self.forex_position_symbol(tws_pos),
+            ibapi::contracts::SecurityType::Future => self.futures_position_symbol(tws_pos),
+            _ => tws_pos.symbol.clone(),
+        }
+    }
+
+    /// Registered id for a TWS futures position, by underlying and month
+    fn futures_position_symbol(&self, tws_pos: &AccountPosition) -> String {
+        let contract = &tws_pos.contract;
+        let month: String = contract
+            .last_trade_date_or_contract_month
+            .chars()
+            .take(6)
+            .collect();
+        let mut candidates: Vec<(&String, &SecurityInfo)> = self
+            .security_map
+            .iter()
+            .filter(|(_, info)| {
+                info.security_type == SecurityType::Future && info.symbol == contract.symbol
+            })
+            .collect();
+        candidates.sort_by_key(|(symbol, _)| *symbol);
+
+        match candidates.as_slice() {
+            [] => tws_pos.symbol.clone(),
+            [(symbol, _)] => symbol.to_string(),
+            _ => candidates
+                .iter()
+                .find(|(_, info)| {
+                    info.contract_specs
+                        .as_ref()
+                        .is_some_and(|specs| specs.contract_month == month)
+                })
+                .map(|(symbol, _)| symbol.to_string())
+                .unwrap_or_else(|| format!("{} {}", contract.symbol, month)),
+        }
+    }
+
+    /// Registered pair for a TWS forex position, by base and quote currency
+    fn forex_position_symbol(&self, tws_pos: &AccountPosition) -> String {
+        let contract = &tws_pos.contract;
+        let base = contract.symbol.to_string();
+        let quote = contract.currency.to_string();
+        self.security_map
+            .iter()
+            .filter(|(_, info)| {
+                info.forex_pair
+                    .as_ref()
+                    .is_some_and(|pair| pair.base_currency == base && pair.quote_currency == quote)
+            })
+            .map(|(symbol, _)| symbol.clone())
+            .min()
+            .unwrap_or_else(|| format!("{}.{}", base, quote))
+    }
+
     /// Sync position from TWS API data
+    ///
+    /// `current_price` is the latest quote for the symbol returned by
+    /// `tws_position_symbol`. Forex quantities stay in base-currency units and
+    /// average cost in quote currency per unit; `position_value` and
+    /// `get_stats` convert to the base currency.
     pub fn sync_position_from_tws(&mut self, tws_pos: &AccountPosition, current_price: f64) {
-        let symbol = &tws_pos.symbol;
+        let symbol = &self.tws_position_symbol(tws_pos);
         let quantity = tws_pos.position;
         let avg_cost = tws_pos.avg_cost;
 
-        // Get security info if available
-        let security_info = self.security_map.get(symbol).cloned();
+        // Get security info if available; unregistered pairs still need it to
+        // be valued in their quote currency
+        let security_info = self.security_map.get(symbol).cloned().or_else(|| {
+            (tws_pos.contract.security_type == ibapi::contracts::SecurityType::ForexPair).then(
+                || {
+                    SecurityInfo::new_forex(
+                        symbol.to_string(),
+                        tws_pos.contract.exchange.to_string(),
+                        tws_pos.contract.currency.to_string(),
+                    )
+                },
+            )
+        });
 
         // Calculate unrealized P&L
         let pnl_per_unit = current_price - avg_cost;
@@ -234,6 +842,30 @@ impl Portfolio {
             quantity * pnl_per_unit
         };
 
+        self.record_entry(symbol, quantity, self.clock.now());
+
+        // TWS only reports the net position, so collapse our lots into one at
+        // its average cost unless they already agree
+        let lot_quantity: f64 = self
+            .tax_lots
+            .get(symbol)
+            .map(|lots| lots.iter().map(|lot| lot.quantity).sum())
+            .unwrap_or(0.0);
+        if (lot_quantity - quantity).abs() > f64::EPSILON {
+            if quantity != 0.0 {
+                self.tax_lots.insert(
+                    symbol.to_string(),
+                    VecDeque::from([TaxLot {
+                        quantity,
+                        price: avg_cost,
+                        opened_at: self.clock.now(),
+                    }]),
+                );
+            } else {
+                self.tax_lots.remove(symbol);
+            }
+        }
+
         // Update or create position
         if quantity != 0.0 {
             let position = Position {
@@ -242,7 +874,7 @@ impl Portfolio {
                 average_cost: avg_cost,
                 current_price,
                 unrealized_pnl,
-                realized_pnl: 0.0, // We don't have realized P&L from TWS positions
+                realized_pnl: self.realized_pnl(symbol),
                 security_info,
                 initial_margin: 0.0,
                 maintenance_margin: 0.0,
@@ -257,18 +889,67 @@ impl Portfolio {
     }
 
     /// Sync all positions from TWS with current market prices
+    ///
+    /// TWS reports positions rather than fills, so any change since the
+    /// tracked lots is booked as a fill at the current price first. Realized
+    /// P&L by signal type, holding periods and turnover then follow broker
+    /// fills, including positions that closed since the last sync. Cash is
+    /// left to the account summary.
     pub fn sync_all_positions_from_tws(
         &mut self,
         tws_positions: &[AccountPosition],
         market_prices: &HashMap<String, f64>,
     ) {
+        let symbols: Vec<String> = tws_positions
+            .iter()
+            .map(|pos| self.tws_position_symbol(pos))
+            .collect();
+
+        let mut changes: Vec<(String, f64, f64)> = tws_positions
+            .iter()
+            .zip(&symbols)
+            .map(|(tws_pos, symbol)| {
+                let price = market_prices
+                    .get(symbol)
+                    .copied()
+                    .unwrap_or(tws_pos.avg_cost);
+                (symbol.clone(), tws_pos.position, price)
+            })
+            .collect();
+        for (symbol, position) in &self.positions {
+            if !symbols.contains(symbol) {
+                let price = market_prices
+                    .get(symbol)
+                    .copied()
+                    .unwrap_or(position.current_price);
+                changes.push((symbol.clone(), 0.0, price));
+            }
+        }
+        let now = self.clock.now();
+        for (symbol, quantity, price) in changes {
+            let tracked: f64 = self
+                .tax_lots
+                .get(&symbol)
+                .map(|lots| lots.iter().map(|lot| lot.quantity).sum())
+                .unwrap_or(0.0);
+            let filled = quantity - tracked;
+            if filled.abs() > f64::EPSILON && price > 0.0 {
+                self.book_trade(&symbol, filled, price, now);
+            }
+        }
+
         // Clear existing positions since we're doing a full sync
         self.positions.clear();
+        self.tax_lots.retain(|symbol, _| symbols.contains(symbol));
+        self.entry_times
+            .retain(|symbol, _| symbols.contains(symbol));
+        self.signal_attributions
+            .retain(|symbol, _| symbols.contains(symbol));
 
-        for tws_pos in tws_positions {
+        for (tws_pos, symbol) in tws_positions.iter().zip(&symbols) {
             // Get current price from market data, fallback to average cost
             let current_price = market_prices
-                .get(&tws_pos.symbol)
+                .get(symbol)
                 .copied()
                 .unwrap_or(tws_pos.avg_cost);
 
@@ -276,3 +957,534 @@ impl Portfolio {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::signals::core::SignalContribution;
+    use chrono::TimeZone;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_fifo_realized_pnl() {
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", 100.0, 10.0);
+        portfolio.update_position("AAPL", 100.0, 12.0);
+        portfolio.update_position("AAPL", -150.0, 15.0);
+
+        // 100 @ 10 -> 15 = 500, then 50 @ 12 -> 15 = 150
+        assert!((portfolio.realized_pnl("AAPL") - 650.0).abs() < 1e-9);
+        assert!((portfolio.total_realized_pnl() - 650.0).abs() < 1e-9);
+
+        // 50 remain from the second lot
+        let position = portfolio.get_position("AAPL").unwrap();
+        assert_eq!(position.quantity, 50.0);
+        assert_eq!(position.average_cost, 12.0);
+        let lots = portfolio.tax_lots("AAPL").unwrap();
+        assert_eq!(lots.len(), 1);
+        assert_eq!(lots[0].quantity, 50.0);
+    }
+
+    #[test]
+    fn test_selling_more_than_held_opens_short_lot() {
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", 100.0, 10.0);
+        portfolio.update_position("AAPL", -150.0, 11.0);
+
+        assert!((portfolio.realized_pnl("AAPL") - 100.0).abs() < 1e-9);
+        let position = portfolio.get_position("AAPL").unwrap();
+        assert_eq!(position.quantity, -50.0);
+        assert_eq!(position.average_cost, 11.0);
+
+        // Covering the short at 9 realizes 50 * 2
+        portfolio.update_position("AAPL", 50.0, 9.0);
+        assert!((portfolio.realized_pnl("AAPL") - 200.0).abs() < 1e-9);
+        assert!(portfolio.get_position("AAPL").is_none());
+        assert!(portfolio.tax_lots("AAPL").is_none());
+    }
+
+    #[test]
+    fn test_forex_pnl_in_account_currency() {
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.register_security(
+            "USD.JPY".to_string(),
+            SecurityInfo::new_forex(
+                "USD.JPY".to_string(),
+                "IDEALPRO".to_string(),
+                "JPY".to_string(),
+            ),
+        );
+        portfolio.update_position("USD.JPY", 100_000.0, 148.0);
+        portfolio.update_market_prices(&HashMap::from([("USD.JPY".to_string(), 150.0)]));
+
+        // 2 yen per dollar on 100k = 200,000 JPY = $1,333.33 at 150
+        let pnl = portfolio.forex_unrealized_pnl("USD.JPY", "USD").unwrap();
+        assert!((pnl - 200_000.0 / 150.0).abs() < 1e-6);
+        let pip_value = portfolio.forex_pip_value("USD.JPY", "USD").unwrap();
+        assert!((pip_value - 1000.0 / 150.0).abs() < 1e-9);
+
+        portfolio.update_position("AAPL", 10.0, 100.0);
+        assert_eq!(portfolio.forex_unrealized_pnl("AAPL", "USD"), None);
+    }
+
+    #[test]
+    fn test_stats_convert_foreign_positions_to_base_currency() {
+        let mut portfolio = Portfolio::new(0.0);
+        portfolio.register_security(
+            "SAP".to_string(),
+            SecurityInfo::new_stock("SAP".to_string(), "IBIS".to_string(), "EUR".to_string()),
+        );
+        portfolio.register_security(
+            "AAPL".to_string(),
+            SecurityInfo::new_stock("AAPL".to_string(), "SMART".to_string(), "USD".to_string()),
+        );
+        portfolio.update_position("SAP", 100.0, 200.0);
+        portfolio.update_position("AAPL", 50.0, 180.0);
+        portfolio.update_market_prices(&HashMap::from([
+            ("SAP".to_string(), 210.0),
+            ("AAPL".to_string(), 190.0),
+        ]));
+        portfolio.update_cash_balance(10_000.0);
+
+        // Without a EUR rate the SAP position is flagged, not counted 1:1
+        let stats = portfolio.get_stats();
+        assert_eq!(stats.unconverted_positions, ["SAP"]);
+        assert!((stats.total_value - (10_000.0 + 50.0 * 190.0)).abs() < 1e-9);
+        assert!((stats.total_unrealized_pnl - 500.0).abs() < 1e-9);
+
+        // EUR 21,000 at 1.10 is $23,100; EUR 1,000 of P&L is $1,100
+        portfolio.set_fx_rate("EUR", 1.10);
+        let stats = portfolio.get_stats();
+        assert!(stats.unconverted_positions.is_empty());
+        assert!((stats.total_value - (10_000.0 + 9_500.0 + 23_100.0)).abs() < 1e-9);
+        assert!((stats.total_unrealized_pnl - (500.0 + 1_100.0)).abs() < 1e-9);
+
+        // Reporting in EUR flips which side needs the rate
+        portfolio.set_base_currency("EUR");
+        portfolio.update_cash_balance(0.0);
+        portfolio.set_fx_rate("USD", 1.0 / 1.10);
+        let stats = portfolio.get_stats();
+        assert!((stats.total_value - (21_000.0 + 9_500.0 / 1.10)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fx_rates_loaded_from_forex_quotes() {
+        let mut portfolio = Portfolio::new(0.0);
+        portfolio.register_security(
+            "SAP".to_string(),
+            SecurityInfo::new_stock("SAP".to_string(), "IBIS".to_string(), "EUR".to_string()),
+        );
+        portfolio.register_security(
+            "EUR.USD".to_string(),
+            SecurityInfo::new_forex(
+                "EUR.USD".to_string(),
+                "IDEALPRO".to_string(),
+                "USD".to_string(),
+            ),
+        );
+        portfolio.update_position("SAP", 100.0, 200.0);
+        portfolio.update_market_prices(&HashMap::from([
+            ("SAP".to_string(), 210.0),
+            ("EUR.USD".to_string(), 1.10),
+        ]));
+
+        assert_eq!(portfolio.fx_rate("EUR"), Some(1.10));
+        let stats = portfolio.get_stats();
+        assert!(stats.unconverted_positions.is_empty());
+        let value = portfolio.position_value("SAP").unwrap();
+        assert!((value - 23_100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_forex_crosses_convert_through_fx_rates() {
+        let mut portfolio = Portfolio::new(0.0);
+        for (symbol, quote) in [("EUR.USD", "USD"), ("EUR.GBP", "GBP")] {
+            portfolio.register_security(
+                symbol.to_string(),
+                SecurityInfo::new_forex(
+                    symbol.to_string(),
+                    "IDEALPRO".to_string(),
+                    quote.to_string(),
+                ),
+            );
+        }
+        portfolio.update_position("EUR.GBP", 10_000.0, 0.84);
+        portfolio.update_market_prices(&HashMap::from([
+            ("EUR.USD".to_string(), 1.10),
+            ("EUR.GBP".to_string(), 0.88),
+        ]));
+
+        // EUR straight off EUR.USD, GBP through the EUR.GBP cross
+        assert_eq!(portfolio.fx_rate("EUR"), Some(1.10));
+        let gbp = portfolio.fx_rate("GBP").unwrap();
+        assert!((gbp - 1.10 / 0.88).abs() < 1e-9);
+
+        // GBP 8,800 of EUR.GBP is EUR 10,000 = $11,000; GBP 400 of P&L is $500
+        let value = portfolio.position_value("EUR.GBP").unwrap();
+        assert!((value - 11_000.0).abs() < 1e-6);
+        let pnl = portfolio.forex_unrealized_pnl("EUR.GBP", "USD").unwrap();
+        assert!((pnl - 500.0).abs() < 1e-6);
+        assert!(portfolio.get_stats().unconverted_positions.is_empty());
+    }
+
+    fn portfolio_with_curve(points: &[(i64, f64)]) -> Portfolio {
+        let start = Utc::now();
+        let mut portfolio = Portfolio::new(100.0);
+        for &(hours, equity) in points {
+            portfolio.record_equity(start + chrono::Duration::hours(hours), equity);
+        }
+        portfolio
+    }
+
+    #[test]
+    fn test_sortino_ratio_from_equity_curve() {
+        // Returns +10%, -5%, +10%, -5%: mean 2.5%, downside deviation sqrt(2 * 0.05^2 / 4)
+        let portfolio = portfolio_with_curve(&[
+            (0, 100.0),
+            (24, 110.0),
+            (48, 104.5),
+            (72, 114.95),
+            (96, 109.2025),
+        ]);
+
+        let downside_deviation = (2.0 * 0.05_f64.powi(2) / 4.0).sqrt();
+        let sortino = portfolio.sortino_ratio(0.0).unwrap();
+        assert!((sortino - 0.025 / downside_deviation).abs() < 1e-9);
+
+        // No return below the target is an error, not infinity
+        let rising = portfolio_with_curve(&[(0, 100.0), (24, 101.0), (48, 102.0)]);
+        assert!(rising.sortino_ratio(0.0).is_err());
+        assert!(Portfolio::new(100.0).sortino_ratio(0.0).is_err());
+    }
+
+    #[test]
+    fn test_calmar_ratio_from_equity_curve() {
+        // One year from 100 to 110 with a 120 -> 90 (25%) drawdown along the way
+        let portfolio =
+            portfolio_with_curve(&[(0, 100.0), (2400, 120.0), (4800, 90.0), (8766, 110.0)]);
+        let calmar = portfolio.calmar_ratio().unwrap();
+        assert!((calmar - 0.4).abs() < 1e-9);
+
+        let no_drawdown = portfolio_with_curve(&[(0, 100.0), (8766, 110.0)]);
+        assert!(no_drawdown.calmar_ratio().is_err());
+    }
+
+    #[test]
+    fn test_beta_and_alpha_to_benchmark() {
+        let benchmark_returns = [0.01, -0.02, 0.015, 0.005, -0.01];
+        let mut equity = 100.0;
+        let mut curve = vec![(0, equity)];
+        for (i, r) in benchmark_returns.iter().enumerate() {
+            equity *= 1.0 + 2.0 * r;
+            curve.push((24 * (i as i64 + 1), equity));
+        }
+        let portfolio = portfolio_with_curve(&curve);
+
+        assert!((portfolio.beta_to(&benchmark_returns).unwrap() - 2.0).abs() < 1e-9);
+        assert!(portfolio.alpha_to(&benchmark_returns).unwrap().abs() < 1e-12);
+
+        // A longer benchmark is aligned on its most recent returns
+        let mut longer = vec![0.3, -0.4];
+        longer.extend_from_slice(&benchmark_returns);
+        assert!((portfolio.beta_to(&longer).unwrap() - 2.0).abs() < 1e-9);
+
+        assert!(portfolio.beta_to(&[0.01, 0.01]).is_err());
+    }
+
+    fn attribution(contributions: &[(SignalType, f64)]) -> SignalAttribution {
+        SignalAttribution {
+            contributions: contributions
+                .iter()
+                .map(|(signal_type, contribution)| SignalContribution {
+                    signal_type: signal_type.clone(),
+                    strength: *contribution,
+                    weight: 1.0,
+                    contribution: *contribution,
+                })
+                .collect(),
+            adjustment: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_momentum_winner_credits_momentum_bucket() {
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", 100.0, 10.0);
+        portfolio.tag_signal_attribution(
+            "AAPL",
+            attribution(&[(SignalType::Momentum, 9.0), (SignalType::Breakout, 1.0)]),
+        );
+        assert_eq!(
+            portfolio.dominant_signal_type("AAPL"),
+            Some(&SignalType::Momentum)
+        );
+
+        // Adding keeps the entry tag; closing realizes 150 * 2 = 300
+        portfolio.update_position("AAPL", 50.0, 10.0);
+        portfolio.tag_signal_attribution("AAPL", attribution(&[(SignalType::Carry, 5.0)]));
+        portfolio.update_position("AAPL", -150.0, 12.0);
+
+        let pnl = portfolio.pnl_by_signal_type();
+        assert!((pnl[&SignalType::Momentum] - 270.0).abs() < 1e-9);
+        assert!((pnl[&SignalType::Breakout] - 30.0).abs() < 1e-9);
+        assert!(!pnl.contains_key(&SignalType::Carry));
+        assert_eq!(portfolio.dominant_signal_type("AAPL"), None);
+
+        // Mixed signals split a loss by contribution size, whatever the sign
+        portfolio.update_position("MSFT", -10.0, 100.0);
+        portfolio.tag_signal_attribution(
+            "MSFT",
+            attribution(&[
+                (SignalType::MeanReversion, -3.0),
+                (SignalType::Momentum, -1.0),
+            ]),
+        );
+        portfolio.update_position("MSFT", 10.0, 110.0);
+        let pnl = portfolio.pnl_by_signal_type();
+        assert!((pnl[&SignalType::MeanReversion] + 75.0).abs() < 1e-9);
+        assert!((pnl[&SignalType::Momentum] - 245.0).abs() < 1e-9);
+
+        // Untagged positions are left out of the buckets
+        portfolio.update_position("TSLA", 1.0, 100.0);
+        portfolio.update_position("TSLA", -1.0, 150.0);
+        let attributed: f64 = portfolio.pnl_by_signal_type().values().sum();
+        assert!((portfolio.total_realized_pnl() - attributed - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_holding_period_and_turnover() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 4, 14, 30, 0).unwrap();
+        let day = |n: i64| start + chrono::Duration::days(n);
+        let clock = MockClock::new(day(10));
+        let mut portfolio = Portfolio::new(100_000.0).with_clock(Arc::new(clock.clone()));
+        assert_eq!(portfolio.average_holding_period(), None);
+
+        // AAPL held 2 days, adding on day 1 does not reset its entry
+        portfolio.update_position_at("AAPL", 100.0, 100.0, day(0));
+        portfolio.update_position_at("AAPL", 100.0, 100.0, day(1));
+        portfolio.update_position_at("AAPL", -200.0, 100.0, day(2));
+        assert_eq!(
+            portfolio.average_holding_period(),
+            Some(chrono::Duration::days(2))
+        );
+
+        // MSFT long for 4 days, then flipped short; the short is still open
+        portfolio.update_position_at("MSFT", 50.0, 200.0, day(3));
+        portfolio.update_position_at("MSFT", -100.0, 200.0, day(7));
+        assert_eq!(portfolio.entry_time("MSFT"), Some(day(7)));
+        assert_eq!(
+            portfolio.average_holding_period(),
+            Some(chrono::Duration::days(3))
+        );
+
+        assert_eq!(portfolio.turnover_ratio(chrono::Duration::days(30)), None);
+        portfolio.record_equity(day(0), 100_000.0);
+        portfolio.record_equity(day(8), 120_000.0);
+
+        // 10k + 10k + 20k + 10k + 20k traded over 110k average equity
+        let turnover = portfolio
+            .turnover_ratio(chrono::Duration::days(30))
+            .unwrap();
+        assert!((turnover - 70_000.0 / 110_000.0).abs() < 1e-9);
+
+        // Only the day-7 flip and the day-8 equity sample fall in the last 3.5 days
+        let recent = portfolio
+            .turnover_ratio(chrono::Duration::hours(84))
+            .unwrap();
+        assert!((recent - 20_000.0 / 120_000.0).abs() < 1e-9);
+
+        // The window trails the portfolio's clock, not the wall clock
+        clock.advance(chrono::Duration::days(30));
+        assert_eq!(portfolio.turnover_ratio(chrono::Duration::days(3)), None);
+    }
+
+    fn forex_position(base: &str, quote: &str, position: f64, avg_cost: f64) -> AccountPosition {
+        let contract = ibapi::contracts::Contract {
+            symbol: base.into(),
+            currency: quote.into(),
+            security_type: ibapi::contracts::SecurityType::ForexPair,
+            exchange: "IDEALPRO".into(),
+            ..Default::default()
+        };
+        AccountPosition {
+            account: "DU123".to_string(),
+            symbol: base.to_string(),
+            position,
+            avg_cost,
+            contract,
+        }
+    }
+
+    #[test]
+    fn test_sync_forex_position_in_base_currency_units() {
+        let mut portfolio = Portfolio::new(0.0);
+        portfolio.register_security(
+            "EUR.USD".to_string(),
+            SecurityInfo::new_forex(
+                "EUR.USD".to_string(),
+                "IDEALPRO".to_string(),
+                "USD".to_string(),
+            ),
+        );
+
+        // TWS reports 10,000 EUR under symbol "EUR", bought at 1.08
+        let eur = forex_position("EUR", "USD", 10_000.0, 1.08);
+        assert_eq!(portfolio.tws_position_symbol(&eur), "EUR.USD");
+        portfolio
+            .sync_all_positions_from_tws(&[eur], &HashMap::from([("EUR.USD".to_string(), 1.10)]));
+
+        assert!(portfolio.get_position("EUR").is_none());
+        let position = portfolio.get_position("EUR.USD").unwrap();
+        assert_eq!(position.quantity, 10_000.0);
+        assert_eq!(position.current_price, 1.10);
+        assert!((position.unrealized_pnl - 200.0).abs() < 1e-9);
+        assert!((portfolio.position_value("EUR.USD").unwrap() - 11_000.0).abs() < 1e-9);
+        assert!((portfolio.get_stats().total_value - 11_000.0).abs() < 1e-9);
+
+        // An unregistered USD.JPY position is valued in yen and converted at
+        // its own quote: 100,000 USD at 150 is 15,000,000 JPY, i.e. $100,000
+        portfolio.sync_position_from_tws(&forex_position("USD", "JPY", 100_000.0, 148.0), 150.0);
+        let position = portfolio.get_position("USD.JPY").unwrap();
+        assert_eq!(position.quantity, 100_000.0);
+        assert!((position.unrealized_pnl - 200_000.0).abs() < 1e-6);
+        assert!((portfolio.position_value("USD.JPY").unwrap() - 100_000.0).abs() < 1e-6);
+        let stats = portfolio.get_stats();
+        assert!(stats.unconverted_positions.is_empty());
+        assert!((stats.total_unrealized_pnl - (200.0 + 200_000.0 / 150.0)).abs() < 1e-6);
+
+        // A full sync without the pair flattens it
+        portfolio.sync_all_positions_from_tws(&[], &HashMap::new());
+        assert!(portfolio.get_position("EUR.USD").is_none());
+        assert!(portfolio.tax_lots("EUR.USD").is_none());
+    }
+
+    #[test]
+    fn test_sync_calendar_futures_positions_by_contract_month() {
+        let mut portfolio = Portfolio::new(0.0);
+        let es = |month: &str| {
+            SecurityInfo::new_future(
+                "ES".to_string(),
+                "CME".to_string(),
+                "USD".to_string(),
+                crate::security_types::FuturesContract {
+                    underlying: "ES".to_string(),
+                    multiplier: 50.0,
+                    contract_month: month.to_string(),
+                    ..Default::default()
+                },
+            )
+        };
+        portfolio.register_security("ES 202403".to_string(), es("202403"));
+        portfolio.register_security("ES 202406".to_string(), es("202406"));
+
+        let future = |month: &str, position: f64, avg_cost: f64| AccountPosition {
+            account: "DU123".to_string(),
+            symbol: "ES".to_string(),
+            position,
+            avg_cost,
+            contract: ibapi::contracts::Contract {
+                symbol: "ES".into(),
+                security_type: ibapi::contracts::SecurityType::Future,
+                last_trade_date_or_contract_month: month.to_string(),
+                ..Default::default()
+            },
+        };
+        let positions = [
+            future("20240315", 1.0, 5_000.0),
+            future("202406", -1.0, 5_050.0),
+        ];
+        assert_eq!(portfolio.tws_position_symbol(&positions[0]), "ES 202403");
+        assert_eq!(portfolio.tws_position_symbol(&positions[1]), "ES 202406");
+
+        portfolio.sync_all_positions_from_tws(&positions, &HashMap::new());
+        assert!(portfolio.get_position("ES").is_none());
+        assert_eq!(portfolio.get_position("ES 202403").unwrap().quantity, 1.0);
+        assert_eq!(portfolio.get_position("ES 202406").unwrap().quantity, -1.0);
+
+        // A month that is not configured is kept apart rather than merged
+        assert_eq!(
+            portfolio.tws_position_symbol(&future("202409", 1.0, 5_100.0)),
+            "ES 202409"
+        );
+
+        // A lone registered contract takes the position whatever month TWS reports
+        let mut lone = Portfolio::new(0.0);
+        lone.register_security("ES".to_string(), es("202403"));
+        assert_eq!(lone.tws_position_symbol(&positions[1]), "ES");
+    }
+
+    #[test]
+    fn test_sync_books_broker_fills() {
+        let stock = |position: f64| AccountPosition {
+            account: "DU123".to_string(),
+            symbol: "AAPL".to_string(),
+            position,
+            avg_cost: 10.0,
+            contract: ibapi::contracts::Contract::stock("AAPL"),
+        };
+        let mut portfolio = Portfolio::new(10_000.0);
+        portfolio.record_equity(Utc::now(), 10_000.0);
+
+        // The broker bought 100, then sold them all before the next sync
+        portfolio.sync_all_positions_from_tws(
+            &[stock(100.0)],
+            &HashMap::from([("AAPL".to_string(), 10.0)]),
+        );
+        portfolio.tag_signal_attribution("AAPL", attribution(&[(SignalType::Momentum, 1.0)]));
+        assert!(portfolio.entry_time("AAPL").is_some());
+        portfolio.sync_all_positions_from_tws(&[], &HashMap::from([("AAPL".to_string(), 12.0)]));
+
+        assert!(portfolio.get_position("AAPL").is_none());
+        assert!((portfolio.realized_pnl("AAPL") - 200.0).abs() < 1e-9);
+        assert!((portfolio.pnl_by_signal_type()[&SignalType::Momentum] - 200.0).abs() < 1e-9);
+        assert!(portfolio.average_holding_period().is_some());
+        // $1,000 in and $1,200 out against $10,000 of equity
+        let turnover = portfolio.turnover_ratio(chrono::Duration::days(1)).unwrap();
+        assert!((turnover - 0.22).abs() < 1e-9);
+        // Cash comes from the account summary, not the booked fills
+        assert_eq!(portfolio.get_stats().cash_balance, 10_000.0);
+
+        // An unchanged position books nothing
+        portfolio.sync_all_positions_from_tws(&[stock(5.0)], &HashMap::new());
+        portfolio.sync_all_positions_from_tws(&[stock(5.0)], &HashMap::new());
+        let turnover = portfolio.turnover_ratio(chrono::Duration::days(1)).unwrap();
+        assert!((turnover - 0.225).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_entry_time_survives_adds_and_resets_on_flip() {
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", 100.0, 100.0);
+        let opened_at = portfolio.entry_time("AAPL").unwrap();
+
+        portfolio.update_position("AAPL", 50.0, 101.0);
+        portfolio.update_position("AAPL", -30.0, 102.0);
+        assert_eq!(portfolio.entry_time("AAPL"), Some(opened_at));
+
+        portfolio.update_position("AAPL", -120.0, 103.0);
+        assert_eq!(portfolio.entry_time("AAPL"), None);
+
+        portfolio.update_position("AAPL", -10.0, 103.0);
+        assert!(portfolio.entry_time("AAPL").unwrap() >= opened_at);
+        assert_eq!(portfolio.get_position("AAPL").unwrap().quantity, -10.0);
+    }
+
+    #[test]
+    fn test_seeded_entry_time_survives_startup_sync() {
+        let position = AccountPosition {
+            account: "DU123".to_string(),
+            symbol: "AAPL".to_string(),
+            position: 100.0,
+            avg_cost: 10.0,
+            contract: ibapi::contracts::Contract::stock("AAPL"),
+        };
+        let opened_at = Utc.with_ymd_and_hms(2024, 3, 4, 14, 30, 0).unwrap();
+        let mut portfolio = Portfolio::new(10_000.0);
+
+        portfolio.seed_entry_time("AAPL", 100.0, opened_at);
+        portfolio.sync_position_from_tws(&position, 10.0);
+        portfolio.sync_all_positions_from_tws(&[position], &HashMap::new());
+        assert_eq!(portfolio.entry_time("AAPL"), Some(opened_at));
+    }
+}
@@ -12,6 +12,8 @@ pub struct PositionManager {
     current_positions: HashMap<String, f64>,
     /// Volatility-based position sizing
     volatility_targeter: VolatilityTargeter,
+    /// Multiplier on new position sizes (drawdown throttling)
+    exposure_scale: f64,
 }
 
 impl PositionManager {
@@ -23,9 +25,19 @@ impl PositionManager {
         Self {
             current_positions: HashMap::new(),
             volatility_targeter,
+            exposure_scale: 1.0,
         }
     }
 
+    /// Set the multiplier applied to new position sizes
+    pub fn set_exposure_scale(&mut self, scale: f64) {
+        self.exposure_scale = scale;
+    }
+
+    pub fn exposure_scale(&self) -> f64 {
+        self.exposure_scale
+    }
+
     /// Update position for a symbol
     pub fn update_position(&mut self, symbol: &str, quantity: f64) {
         self.current_positions.insert(symbol.to_string(), quantity);
@@ -0,0 +1,179 @@
+//! Drift between the positions the strategy tracks and those TWS reports
+
+use crate::connection::AccountPosition;
+use ibapi::contracts::SecurityType;
+use std::collections::{BTreeSet, HashMap};
+
+/// Quantities closer than this are treated as equal
+pub const QUANTITY_TOLERANCE: f64 = 1e-6;
+/// Forex positions are fractional base-currency amounts, so allow a unit
+pub const FOREX_QUANTITY_TOLERANCE: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MismatchKind {
+    QuantityDiffers,
+    MissingAtBroker,   // Strategy holds it, TWS does not
+    MissingInStrategy, // TWS holds it, strategy does not
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionMismatch {
+    pub symbol: String,
+    pub strategy_quantity: f64,
+    pub broker_quantity: f64,
+    pub kind: MismatchKind,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReconciliationReport {
+    pub mismatches: Vec<PositionMismatch>, // Sorted by symbol
+    pub matched: usize,
+}
+
+impl ReconciliationReport {
+    pub fn is_clean(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Compare strategy-tracked quantities with TWS positions symbol by symbol
+///
+/// Flat positions on either side count as absent.
+pub fn reconcile(
+    strategy_positions: &HashMap<String, f64>,
+    tws_positions: &[AccountPosition],
+) -> ReconciliationReport {
+    let mut broker: HashMap<&str, (f64, f64)> = HashMap::new();
+    for position in tws_positions {
+        let tolerance = if position.contract.security_type == SecurityType::ForexPair {
+            FOREX_QUANTITY_TOLERANCE
+        } else {
+            QUANTITY_TOLERANCE
+        };
+        let entry = broker
+            .entry(position.symbol.as_str())
+            .or_insert((0.0, tolerance));
+        entry.0 += position.position;
+    }
+
+    let symbols: BTreeSet<&str> = strategy_positions
+        .keys()
+        .map(String::as_str)
+        .chain(broker.keys().copied())
+        .collect();
+
+    let mut report = ReconciliationReport::default();
+    for symbol in symbols {
+        let strategy_quantity = strategy_positions.get(symbol).copied().unwrap_or(0.0);
+        let (broker_quantity, tolerance) = broker
+            .get(symbol)
+            .copied()
+            .unwrap_or((0.0, QUANTITY_TOLERANCE));
+
+        let strategy_flat = strategy_quantity.abs() <= tolerance;
+        let broker_flat = broker_quantity.abs() <= tolerance;
+        let kind = if strategy_flat && broker_flat {
+            continue;
+        } else if broker_flat {
+            MismatchKind::MissingAtBroker
+        } else if strategy_flat {
+            MismatchKind::MissingInStrategy
+        } else if (strategy_quantity - broker_quantity).abs() > tolerance {
+            MismatchKind::QuantityDiffers
+        } else {
+            report.matched += 1;
+            continue;
+        };
+
+        report.mismatches.push(PositionMismatch {
+            symbol: symbol.to_string(),
+            strategy_quantity,
+            broker_quantity,
+            kind,
+        });
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ibapi::contracts::Contract;
+
+    fn position(symbol: &str, quantity: f64, contract: Contract) -> AccountPosition {
+        AccountPosition {
+            account: "DU123".to_string(),
+            symbol: symbol.to_string(),
+            position: quantity,
+            avg_cost: 100.0,
+            contract,
+        }
+    }
+
+    fn forex(symbol: &str) -> Contract {
+        Contract {
+            symbol: symbol.to_string(),
+            security_type: SecurityType::ForexPair,
+            ..Contract::default()
+        }
+    }
+
+    #[test]
+    fn test_reports_mismatches_and_skips_matches() {
+        let strategy: HashMap<String, f64> = [
+            ("AAPL", 100.0),
+            ("MSFT", 50.0),
+            ("GOOGL", 10.0),
+            ("EUR", 25_000.4),
+            ("TSLA", 0.0),
+        ]
+        .into_iter()
+        .map(|(symbol, quantity)| (symbol.to_string(), quantity))
+        .collect();
+        let broker = vec![
+            position("AAPL", 100.0, Contract::stock("AAPL")),
+            position("MSFT", 40.0, Contract::stock("MSFT")),
+            position("NVDA", 5.0, Contract::stock("NVDA")),
+            // Fractional forex within a unit of the strategy's amount
+            position("EUR", 25_000.0, forex("EUR")),
+        ];
+
+        let report = reconcile(&strategy, &broker);
+
+        assert_eq!(report.matched, 2);
+        assert!(!report.is_clean());
+        assert_eq!(
+            report.mismatches,
+            vec![
+                PositionMismatch {
+                    symbol: "GOOGL".to_string(),
+                    strategy_quantity: 10.0,
+                    broker_quantity: 0.0,
+                    kind: MismatchKind::MissingAtBroker,
+                },
+                PositionMismatch {
+                    symbol: "MSFT".to_string(),
+                    strategy_quantity: 50.0,
+                    broker_quantity: 40.0,
+                    kind: MismatchKind::QuantityDiffers,
+                },
+                PositionMismatch {
+                    symbol: "NVDA".to_string(),
+                    strategy_quantity: 0.0,
+                    broker_quantity: 5.0,
+                    kind: MismatchKind::MissingInStrategy,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_matching_positions_are_clean() {
+        let strategy = HashMap::from([("AAPL".to_string(), 100.0)]);
+        let broker = vec![position("AAPL", 100.0, Contract::stock("AAPL"))];
+
+        let report = reconcile(&strategy, &broker);
+        assert!(report.is_clean());
+        assert_eq!(report.matched, 1);
+    }
+}
@@ -1,7 +1,12 @@
-use crate::config::RiskConfig;
+use crate::clock::{SharedClock, system_clock};
+use crate::config::{RiskConfig, StopLossMethod};
+use crate::market_data::MarketDataHandler;
+use crate::order_types::BracketLevels;
+use crate::orders::OrderSignal;
 use crate::portfolio::{Portfolio, Position};
 use crate::security_types::SecurityType;
-use anyhow::Result;
+use anyhow::{Result, bail};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use log::{error, info, warn};
 use std::collections::HashMap;
 
@@ -55,10 +60,39 @@ pub enum RiskUrgency {
     Low,      // Monitor closely
 }
 
+/// Circuit breaker tripped by the daily loss limit
+///
+/// While set, only risk-reducing orders may be placed.
+#[derive(Debug, Clone)]
+pub struct TradingHalt {
+    pub tripped_at: DateTime<Utc>,
+    pub trading_day: NaiveDate,
+    pub loss_fraction: f64,
+}
+
+/// Hard de-risk tripped by `drawdown_derisking`
+///
+/// While set, positions are cut toward the cash floor and entries are blocked.
+#[derive(Debug, Clone)]
+pub struct Derisked {
+    pub tripped_at: DateTime<Utc>,
+    pub drawdown: f64,      // Drawdown when it tripped
+    pub trough_equity: f64, // Lowest equity since it tripped
+}
+
 pub struct RiskManager {
     pub config: RiskConfig,
     stop_losses: HashMap<String, f64>,
     take_profits: HashMap<String, f64>,
+    atr_values: HashMap<String, f64>,
+    day_start_equity: Option<(NaiveDate, f64)>,
+    halt: Option<TradingHalt>,
+    derisked: Option<Derisked>,
+    peak_equity: f64,
+    last_equity: f64,
+    /// Symbols barred from new entries until the given time
+    cooldowns: HashMap<String, DateTime<Utc>>,
+    clock: SharedClock,
 }
 
 impl RiskManager {
@@ -67,9 +101,207 @@ impl RiskManager {
             config,
             stop_losses: HashMap::new(),
             take_profits: HashMap::new(),
+            atr_values: HashMap::new(),
+            day_start_equity: None,
+            halt: None,
+            derisked: None,
+            peak_equity: 0.0,
+            last_equity: 0.0,
+            cooldowns: HashMap::new(),
+            clock: system_clock(),
         }
     }
 
+    /// Time source for cooldown checks
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Trading day containing `now`, rolling over at `halt_reset_hour_utc`
+    fn trading_day(&self, now: DateTime<Utc>) -> NaiveDate {
+        (now - Duration::hours(self.config.halt_reset_hour_utc as i64)).date_naive()
+    }
+
+    /// Track intraday equity and trip the circuit breaker on the daily loss limit
+    ///
+    /// `equity` is net liquidation value, so the loss covers both realized and
+    /// unrealized P&L. The first update of each trading day records the starting
+    /// equity and clears any halt from the previous day.
+    pub fn update_daily_pnl(&mut self, equity: f64, now: DateTime<Utc>) {
+        self.peak_equity = self.peak_equity.max(equity);
+        self.last_equity = equity;
+        self.update_derisking(now);
+
+        let today = self.trading_day(now);
+
+        let start_equity = match self.day_start_equity {
+            Some((day, start_equity)) if day == today => start_equity,
+            _ => {
+                if self.halt.take().is_some() {
+                    info!("Trading halt reset for new trading day {}", today);
+                }
+                self.day_start_equity = Some((today, equity));
+                equity
+            }
+        };
+
+        if start_equity <= 0.0 || self.halt.is_some() {
+            return;
+        }
+
+        let loss_fraction = (start_equity - equity) / start_equity;
+        if loss_fraction > self.config.max_daily_loss {
+            error!(
+                "TRADING HALT: daily loss {:.2}% exceeds limit {:.2}% (equity ${:.2} from ${:.2})",
+                loss_fraction * 100.0,
+                self.config.max_daily_loss * 100.0,
+                equity,
+                start_equity
+            );
+            self.halt = Some(TradingHalt {
+                tripped_at: now,
+                trading_day: today,
+                loss_fraction,
+            });
+        }
+    }
+
+    /// Trip or clear the drawdown de-risk, with hysteresis between the two
+    /// thresholds
+    ///
+    /// Recovery is measured up from the trough reached while de-risked, not
+    /// down from the old peak: a book mostly in cash could take indefinitely
+    /// to regain the peak. Equity has to climb the band between the trigger
+    /// and re-entry drawdowns off the trough, after which the peak restarts
+    /// from there so the old drawdown does not trip it again.
+    fn update_derisking(&mut self, now: DateTime<Utc>) {
+        let Some(derisking) = self.config.drawdown_derisking else {
+            self.derisked = None;
+            return;
+        };
+        let drawdown = self.current_drawdown();
+        let equity = self.last_equity;
+        match &mut self.derisked {
+            None if drawdown > derisking.trigger_drawdown => {
+                error!(
+                    "DE-RISKING: drawdown {:.2}% exceeds {:.2}%, moving to {:.0}% cash until it recovers below {:.2}%",
+                    drawdown * 100.0,
+                    derisking.trigger_drawdown * 100.0,
+                    derisking.cash_floor * 100.0,
+                    derisking.reentry_drawdown * 100.0
+                );
+                self.derisked = Some(Derisked {
+                    tripped_at: now,
+                    drawdown,
+                    trough_equity: equity,
+                });
+            }
+            Some(derisked) => {
+                derisked.trough_equity = derisked.trough_equity.min(equity);
+                let band = derisking.trigger_drawdown - derisking.reentry_drawdown;
+                let recovery = equity / derisked.trough_equity - 1.0;
+                if derisked.trough_equity > 0.0 && recovery >= band {
+                    info!(
+                        "Equity recovered {:.2}% off its ${:.2} trough, entries resume",
+                        recovery * 100.0,
+                        derisked.trough_equity
+                    );
+                    self.derisked = None;
+                    self.peak_equity = equity;
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Whether the drawdown de-risk is liquidating and blocking entries
+    pub fn is_derisked(&self) -> bool {
+        self.derisked.is_some()
+    }
+
+    pub fn derisked(&self) -> Option<&Derisked> {
+        self.derisked.as_ref()
+    }
+
+    /// Fractional decline of the latest equity from its peak
+    pub fn current_drawdown(&self) -> f64 {
+        if self.peak_equity > 0.0 {
+            (self.peak_equity - self.last_equity) / self.peak_equity
+        } else {
+            0.0
+        }
+    }
+
+    /// Multiplier for new position sizes given the current drawdown
+    ///
+    /// 1.0 unless `drawdown_scaling` is configured.
+    pub fn exposure_scale(&self) -> f64 {
+        self.config
+            .drawdown_scaling
+            .map_or(1.0, |scaling| scaling.scale(self.current_drawdown()))
+    }
+
+    /// Whether the daily loss circuit breaker has tripped
+    pub fn is_halted(&self) -> bool {
+        self.halt.is_some()
+    }
+
+    pub fn trading_halt(&self) -> Option<&TradingHalt> {
+        self.halt.as_ref()
+    }
+
+    /// Whether a signal may be executed given the current halt state
+    ///
+    /// While halted only orders that shrink an existing position are allowed.
+    pub fn allows_signal(&self, signal: &OrderSignal, portfolio: &Portfolio) -> bool {
+        !self.is_halted() || is_risk_reducing(signal, portfolio)
+    }
+
+    /// Bar new entries into `symbol` after a stop-out or risk-reduction exit
+    ///
+    /// A no-op unless `reentry_cooldown_minutes` is configured.
+    pub fn start_cooldown(&mut self, symbol: &str) {
+        self.start_cooldown_at(symbol, self.clock.now());
+    }
+
+    pub fn start_cooldown_at(&mut self, symbol: &str, now: DateTime<Utc>) {
+        if self.config.reentry_cooldown_minutes == 0 {
+            return;
+        }
+        let until = now + Duration::minutes(self.config.reentry_cooldown_minutes as i64);
+        info!("{} in re-entry cooldown until {}", symbol, until);
+        self.cooldowns.insert(symbol.to_string(), until);
+    }
+
+    pub fn in_cooldown(&self, symbol: &str) -> bool {
+        self.in_cooldown_at(symbol, self.clock.now())
+    }
+
+    pub fn in_cooldown_at(&self, symbol: &str, now: DateTime<Utc>) -> bool {
+        self.cooldowns.get(symbol).is_some_and(|until| now < *until)
+    }
+
+    /// Whether a stock may be held `quantity` shares short
+    ///
+    /// The stock must be on `shortable_symbols` and, when `max_short_quantity`
+    /// is set, the short must not exceed it.
+    pub fn can_short(&self, symbol: &str, quantity: f64) -> Result<()> {
+        if !self.config.shortable_symbols.iter().any(|s| s == symbol) {
+            bail!("{} is not on the shortable list", symbol);
+        }
+        let limit = self.config.max_short_quantity;
+        if limit > 0.0 && quantity > limit {
+            bail!(
+                "short of {} {} exceeds the {} share limit",
+                quantity,
+                symbol,
+                limit
+            );
+        }
+        Ok(())
+    }
+
     /// Calculate maximum position size based on portfolio value and risk percentage
     pub fn calculate_max_position_size(
         &self,
@@ -109,13 +341,96 @@ impl RiskManager {
         }
     }
 
+    /// Record the latest ATR for a symbol
+    pub fn update_atr(&mut self, symbol: String, atr: f64) {
+        self.atr_values.insert(symbol, atr);
+    }
+
+    /// Refresh ATRs for all tracked symbols when ATR stops are configured
+    pub fn update_atr_from_market_data(&mut self, market_data: &MarketDataHandler) {
+        let StopLossMethod::Atr { period, .. } = self.config.stop_loss_method else {
+            return;
+        };
+
+        for symbol in market_data.tracked_symbols() {
+            if let Some(atr) = market_data.calculate_atr(&symbol, period) {
+                self.atr_values.insert(symbol, atr);
+            }
+        }
+    }
+
+    /// Stop price `atr_multiple` ATRs beyond entry, against the position
+    ///
+    /// Returns None if no ATR is known for the symbol.
+    pub fn atr_stop_price(
+        &self,
+        symbol: &str,
+        entry_price: f64,
+        is_long: bool,
+        atr_multiple: f64,
+    ) -> Option<f64> {
+        let distance = self.atr_values.get(symbol)? * atr_multiple;
+
+        if is_long {
+            Some(entry_price - distance)
+        } else {
+            Some(entry_price + distance)
+        }
+    }
+
     /// Calculate stop loss price based on position and risk parameters
-    pub fn calculate_stop_loss(
+    ///
+    /// Uses ATR stops when configured and an ATR is available, otherwise the
+    /// fixed percentage.
+    pub fn calculate_stop_loss(&self, position: &Position, entry_price: f64, is_long: bool) -> f64 {
+        self.stop_loss_price(&position.symbol, entry_price, is_long)
+    }
+
+    /// Stop price for the whole position, from its blended cost basis
+    ///
+    /// Averaging into a position moves `average_cost`, so the stop follows
+    /// the combined entry rather than the first order's price.
+    pub fn position_stop_price(&self, position: &Position) -> f64 {
+        self.calculate_stop_loss(position, position.average_cost, position.quantity > 0.0)
+    }
+
+    /// Whether the position's current price has crossed its blended stop
+    pub fn position_stop_breached(&self, position: &Position) -> bool {
+        if position.current_price <= 0.0 {
+            return false; // No price yet
+        }
+        let stop = self.position_stop_price(position);
+        if position.quantity > 0.0 {
+            position.current_price <= stop
+        } else {
+            position.current_price >= stop
+        }
+    }
+
+    /// Stop and take-profit prices protecting a position entered at `entry_price`
+    pub fn protective_levels(
         &self,
-        _position: &Position,
+        symbol: &str,
         entry_price: f64,
         is_long: bool,
-    ) -> f64 {
+    ) -> BracketLevels {
+        BracketLevels {
+            take_profit: self.take_profit_price(entry_price, is_long),
+            stop_loss: self.stop_loss_price(symbol, entry_price, is_long),
+        }
+    }
+
+    fn stop_loss_price(&self, symbol: &str, entry_price: f64, is_long: bool) -> f64 {
+        let atr_stop = match self.config.stop_loss_method {
+            StopLossMethod::Atr { multiple, .. } => {
+                self.atr_stop_price(symbol, entry_price, is_long, multiple)
+            }
+            StopLossMethod::Percentage => None,
+        };
+        if let Some(stop) = atr_stop {
+            return stop;
+        }
+
         let stop_loss_percentage = self.config.stop_loss_percentage;
 
         if is_long {
@@ -134,6 +449,10 @@ impl RiskManager {
         entry_price: f64,
         is_long: bool,
     ) -> f64 {
+        self.take_profit_price(entry_price, is_long)
+    }
+
+    fn take_profit_price(&self, entry_price: f64, is_long: bool) -> f64 {
         let take_profit_percentage = self.config.take_profit_percentage;
 
         if is_long {
@@ -241,22 +560,73 @@ impl RiskManager {
             .sum::<f64>();
         let exposure_ratio = current_exposure / portfolio_value;
 
-        // If total exposure exceeds limit, generate reduction signals
-        if exposure_ratio > self.config.max_portfolio_exposure {
+        // Positions through their blended stop are closed outright, which
+        // supersedes any partial reduction below
+        let mut stopped_out = Vec::new();
+        for (symbol, position) in portfolio.positions() {
+            if self.position_stop_breached(position) {
+                signals.push(RiskSignal {
+                    symbol: symbol.to_string(),
+                    action: RiskAction::ReducePosition,
+                    quantity: position.quantity.abs(),
+                    reason: format!(
+                        "Price ${:.4} breached position stop ${:.4} (average cost ${:.4})",
+                        position.current_price,
+                        self.position_stop_price(position),
+                        position.average_cost
+                    ),
+                    urgency: RiskUrgency::Critical,
+                });
+                stopped_out.push(symbol.as_str());
+            }
+        }
+
+        // Sort positions by size (largest first) for systematic reduction
+        let mut position_sizes: Vec<(&String, &Position, f64)> = portfolio
+            .positions()
+            .iter()
+            .filter(|(symbol, _)| !stopped_out.contains(&symbol.as_str()))
+            .map(|(symbol, position)| {
+                let position_value = (position.quantity * position.current_price).abs();
+                (symbol, position, position_value)
+            })
+            .collect();
+        position_sizes.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap());
+
+        // De-risked: cut the largest positions until the cash floor is met,
+        // superseding the exposure limit below
+        let derisk_exposure = self
+            .config
+            .drawdown_derisking
+            .filter(|_| self.is_derisked())
+            .map(|derisking| 1.0 - derisking.cash_floor);
+        if let Some(max_exposure) = derisk_exposure
+            && exposure_ratio > max_exposure
+        {
+            let mut remaining_excess = current_exposure - portfolio_value * max_exposure;
+            for (symbol, position, position_value) in position_sizes {
+                if remaining_excess <= 0.0 {
+                    break;
+                }
+                let reduction_amount = remaining_excess.min(position_value);
+                signals.push(RiskSignal {
+                    symbol: symbol.to_string(),
+                    action: RiskAction::ReducePosition,
+                    quantity: reduction_amount / position.current_price,
+                    reason: format!(
+                        "Drawdown de-risk: exposure {:.1}% above {:.1}% for the cash floor",
+                        exposure_ratio * 100.0,
+                        max_exposure * 100.0
+                    ),
+                    urgency: RiskUrgency::Critical,
+                });
+                remaining_excess -= reduction_amount;
+            }
+        } else if exposure_ratio > self.config.max_portfolio_exposure {
+            // If total exposure exceeds limit, generate reduction signals
             let excess_exposure =
                 current_exposure - (portfolio_value * self.config.max_portfolio_exposure);
 
-            // Sort positions by size (largest first) for systematic reduction
-            let mut position_sizes: Vec<(&String, &Position, f64)> = portfolio
-                .positions()
-                .iter()
-                .map(|(symbol, position)| {
-                    let position_value = (position.quantity * position.current_price).abs();
-                    (symbol, position, position_value)
-                })
-                .collect();
-            position_sizes.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap());
-
             let mut remaining_excess = excess_exposure;
             for (symbol, position, position_value) in position_sizes {
                 if remaining_excess <= 0.0 {
@@ -287,6 +657,9 @@ impl RiskManager {
         }
 
         for (symbol, position) in portfolio.positions() {
+            if stopped_out.contains(&symbol.as_str()) {
+                continue;
+            }
             if let Some(position_risk) = self.analyze_position_risk(portfolio, symbol) {
                 // Check for position size violations
                 if position_risk.exceeds_position_limit {
@@ -465,8 +838,409 @@ impl RiskManager {
     }
 }
 
+/// Whether an order only reduces (never opens or flips) an existing position
+pub fn is_risk_reducing(signal: &OrderSignal, portfolio: &Portfolio) -> bool {
+    let current = portfolio
+        .get_position(&signal.symbol)
+        .map(|position| position.quantity)
+        .unwrap_or(0.0);
+
+    match signal.action.as_str() {
+        "SELL" => current > 0.0 && signal.quantity <= current,
+        "BUY" => current < 0.0 && signal.quantity <= -current,
+        _ => false,
+    }
+}
+
+/// Size of the short position an order would leave, if it opens or adds to one
+///
+/// Sells that only close (part of) a long return `None`.
+pub fn resulting_short(signal: &OrderSignal, portfolio: &Portfolio) -> Option<f64> {
+    let current = portfolio
+        .get_position(&signal.symbol)
+        .map(|position| position.quantity)
+        .unwrap_or(0.0);
+    let after = current - signal.quantity;
+
+    (signal.action == "SELL" && after < 0.0).then_some(-after)
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::config::{DrawdownDerisking, DrawdownScaling};
+    use crate::security_types::SecurityInfo;
+    use chrono::TimeZone;
+    use std::sync::Arc;
+
+    fn signal(symbol: &str, action: &str, quantity: f64) -> OrderSignal {
+        OrderSignal {
+            symbol: symbol.to_string(),
+            action: action.to_string(),
+            quantity,
+            price: 100.0,
+            order_type: "MKT".to_string(),
+            limit_price: None,
+            reason: "test".to_string(),
+            security_info: SecurityInfo::new_stock(
+                symbol.to_string(),
+                "SMART".to_string(),
+                "USD".to_string(),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_closing_sell_is_not_a_short() {
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", 100.0, 100.0);
+
+        assert_eq!(
+            resulting_short(&signal("AAPL", "SELL", 60.0), &portfolio),
+            None
+        );
+        assert_eq!(
+            resulting_short(&signal("AAPL", "SELL", 100.0), &portfolio),
+            None
+        );
+        assert_eq!(
+            resulting_short(&signal("AAPL", "BUY", 10.0), &portfolio),
+            None
+        );
+        // Selling through the long flips it short
+        assert_eq!(
+            resulting_short(&signal("AAPL", "SELL", 150.0), &portfolio),
+            Some(50.0)
+        );
+        assert_eq!(
+            resulting_short(&signal("MSFT", "SELL", 10.0), &portfolio),
+            Some(10.0)
+        );
+    }
+
+    #[test]
+    fn test_can_short_requires_list_and_limit() {
+        let risk_manager = RiskManager::new(RiskConfig {
+            shortable_symbols: vec!["AAPL".to_string()],
+            max_short_quantity: 100.0,
+            ..RiskConfig::default()
+        });
+
+        assert!(risk_manager.can_short("AAPL", 100.0).is_ok());
+        let too_big = risk_manager.can_short("AAPL", 150.0).unwrap_err();
+        assert!(too_big.to_string().contains("exceeds the 100 share limit"));
+        let not_listed = risk_manager.can_short("GME", 1.0).unwrap_err();
+        assert!(
+            not_listed
+                .to_string()
+                .contains("GME is not on the shortable list")
+        );
+
+        // Nothing is shortable by default
+        assert!(
+            RiskManager::new(RiskConfig::default())
+                .can_short("AAPL", 1.0)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_reentry_cooldown_expires() {
+        let mut risk_manager = RiskManager::new(RiskConfig {
+            reentry_cooldown_minutes: 30,
+            ..RiskConfig::default()
+        });
+        let stop_out = Utc.with_ymd_and_hms(2024, 3, 4, 14, 0, 0).unwrap();
+        risk_manager.start_cooldown_at("AAPL", stop_out);
+
+        assert!(risk_manager.in_cooldown_at("AAPL", stop_out + Duration::minutes(29)));
+        assert!(!risk_manager.in_cooldown_at("AAPL", stop_out + Duration::minutes(30)));
+        assert!(!risk_manager.in_cooldown_at("MSFT", stop_out));
+
+        // Disabled by default
+        let mut risk_manager = RiskManager::new(RiskConfig::default());
+        risk_manager.start_cooldown("AAPL");
+        assert!(!risk_manager.in_cooldown("AAPL"));
+    }
+
+    #[test]
+    fn test_cooldown_expires_on_clock() {
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 3, 4, 14, 0, 0).unwrap());
+        let mut risk_manager = RiskManager::new(RiskConfig {
+            reentry_cooldown_minutes: 30,
+            ..RiskConfig::default()
+        })
+        .with_clock(Arc::new(clock.clone()));
+        risk_manager.start_cooldown("AAPL");
+
+        clock.advance(Duration::minutes(29));
+        assert!(risk_manager.in_cooldown("AAPL"));
+
+        clock.advance(Duration::minutes(1));
+        assert!(!risk_manager.in_cooldown("AAPL"));
+    }
+
+    #[test]
+    fn test_drawdown_scales_exposure() {
+        let mut risk_manager = RiskManager::new(RiskConfig {
+            drawdown_scaling: Some(DrawdownScaling {
+                max_drawdown: 0.20,
+                min_scale: 0.0,
+            }),
+            ..RiskConfig::default()
+        });
+        let now = Utc.with_ymd_and_hms(2024, 3, 4, 14, 0, 0).unwrap();
+        risk_manager.update_daily_pnl(100_000.0, now);
+        assert_eq!(risk_manager.exposure_scale(), 1.0);
+
+        // 10% drawdown is halfway to the 20% floor
+        risk_manager.update_daily_pnl(90_000.0, now);
+        assert!((risk_manager.exposure_scale() - 0.5).abs() < 1e-12);
+
+        // Recovering equity restores exposure
+        risk_manager.update_daily_pnl(95_000.0, now);
+        assert!((risk_manager.exposure_scale() - 0.75).abs() < 1e-12);
+
+        let curve = DrawdownScaling {
+            max_drawdown: 0.20,
+            min_scale: 0.5,
+        };
+        assert!((curve.scale(0.10) - 0.75).abs() < 1e-12);
+        assert_eq!(curve.scale(0.30), 0.5);
+
+        // Off by default
+        let mut unscaled = RiskManager::new(RiskConfig::default());
+        unscaled.update_daily_pnl(100_000.0, now);
+        unscaled.update_daily_pnl(80_000.0, now);
+        assert_eq!(unscaled.exposure_scale(), 1.0);
+    }
+
+    #[test]
+    fn test_drawdown_derisking_moves_to_cash_until_recovery() {
+        let mut risk_manager = RiskManager::new(RiskConfig {
+            drawdown_derisking: Some(DrawdownDerisking {
+                trigger_drawdown: 0.15,
+                reentry_drawdown: 0.05,
+                cash_floor: 0.8,
+            }),
+            ..RiskConfig::default()
+        });
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", 300.0, 100.0);
+        portfolio.update_position("MSFT", 100.0, 100.0);
+        let derisk_signals = |risk_manager: &RiskManager| -> Vec<(String, f64)> {
+            risk_manager
+                .generate_risk_signals(&portfolio)
+                .into_iter()
+                .filter(|s| s.reason.starts_with("Drawdown de-risk"))
+                .map(|s| (s.symbol, s.quantity))
+                .collect()
+        };
+
+        let now = Utc.with_ymd_and_hms(2024, 3, 4, 14, 0, 0).unwrap();
+        risk_manager.update_daily_pnl(100_000.0, now);
+        risk_manager.update_daily_pnl(90_000.0, now);
+        assert!(!risk_manager.is_derisked());
+        assert!(derisk_signals(&risk_manager).is_empty());
+
+        // Past the 15% trigger: cut the largest position until 80% is cash
+        risk_manager.update_daily_pnl(84_000.0, now + Duration::days(1));
+        assert!(risk_manager.is_derisked());
+        assert!((risk_manager.derisked().unwrap().drawdown - 0.16).abs() < 1e-9);
+        let total_value = portfolio.get_stats().total_value;
+        let excess = 40_000.0 - 0.2 * total_value;
+        let signals = derisk_signals(&risk_manager);
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].0, "AAPL");
+        assert!((signals[0].1 - excess / 100.0).abs() < 1e-9);
+
+        // The trough follows equity down; 7.5% off the $80,000 trough is
+        // inside the 10% band between trigger and re-entry
+        risk_manager.update_daily_pnl(80_000.0, now + Duration::days(2));
+        risk_manager.update_daily_pnl(86_000.0, now + Duration::days(3));
+        assert_eq!(risk_manager.derisked().unwrap().trough_equity, 80_000.0);
+        assert!(risk_manager.is_derisked());
+        assert!(!risk_manager.is_halted());
+
+        // 10% off the trough, though still 12% below the old peak
+        risk_manager.update_daily_pnl(88_000.0, now + Duration::days(4));
+        assert!(!risk_manager.is_derisked());
+        assert!(derisk_signals(&risk_manager).is_empty());
+
+        // The peak restarts at recovery, so a small dip does not re-trip
+        risk_manager.update_daily_pnl(86_000.0, now + Duration::days(5));
+        assert!(!risk_manager.is_derisked());
+        assert!(risk_manager.current_drawdown() < 0.05);
+
+        // Off by default
+        let mut unconfigured = RiskManager::new(RiskConfig::default());
+        unconfigured.update_daily_pnl(100_000.0, now);
+        unconfigured.update_daily_pnl(50_000.0, now);
+        assert!(!unconfigured.is_derisked());
+    }
+
+    #[test]
+    fn test_daily_loss_halt_blocks_entries_but_allows_exits() {
+        let mut risk_manager = RiskManager::new(RiskConfig {
+            max_daily_loss: 0.03,
+            halt_reset_hour_utc: 0,
+            ..RiskConfig::default()
+        });
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", 100.0, 100.0);
+
+        let morning = Utc.with_ymd_and_hms(2024, 3, 4, 14, 0, 0).unwrap();
+        risk_manager.update_daily_pnl(100_000.0, morning);
+        risk_manager.update_daily_pnl(98_000.0, morning + Duration::hours(1));
+        assert!(!risk_manager.is_halted());
+
+        risk_manager.update_daily_pnl(96_500.0, morning + Duration::hours(2));
+        assert!(risk_manager.is_halted());
+        assert!((risk_manager.trading_halt().unwrap().loss_fraction - 0.035).abs() < 1e-9);
+
+        // New entries and position increases are blocked
+        assert!(!risk_manager.allows_signal(&signal("MSFT", "BUY", 10.0), &portfolio));
+        assert!(!risk_manager.allows_signal(&signal("AAPL", "BUY", 10.0), &portfolio));
+        assert!(!risk_manager.allows_signal(&signal("AAPL", "SELL", 150.0), &portfolio));
+        // Risk-reducing exits pass
+        assert!(risk_manager.allows_signal(&signal("AAPL", "SELL", 100.0), &portfolio));
+
+        // Recovering intraday does not lift the halt
+        risk_manager.update_daily_pnl(100_000.0, morning + Duration::hours(3));
+        assert!(risk_manager.is_halted());
+    }
+
+    #[test]
+    fn test_trading_halt_resets_at_configured_hour() {
+        let mut risk_manager = RiskManager::new(RiskConfig {
+            max_daily_loss: 0.03,
+            halt_reset_hour_utc: 21,
+            ..RiskConfig::default()
+        });
+
+        let afternoon = Utc.with_ymd_and_hms(2024, 3, 4, 15, 0, 0).unwrap();
+        risk_manager.update_daily_pnl(100_000.0, afternoon);
+        risk_manager.update_daily_pnl(90_000.0, afternoon + Duration::hours(1));
+        assert!(risk_manager.is_halted());
+
+        // Still the same trading day just before the reset hour
+        risk_manager.update_daily_pnl(90_000.0, afternoon + Duration::minutes(5 * 60 + 59));
+        assert!(risk_manager.is_halted());
+
+        // After 21:00 UTC the halt clears and equity is re-baselined
+        risk_manager.update_daily_pnl(90_000.0, afternoon + Duration::hours(6));
+        assert!(!risk_manager.is_halted());
+        risk_manager.update_daily_pnl(88_000.0, afternoon + Duration::hours(7));
+        assert!(!risk_manager.is_halted());
+    }
+
+    fn atr_risk_manager() -> RiskManager {
+        RiskManager::new(RiskConfig {
+            stop_loss_method: StopLossMethod::Atr {
+                period: 3,
+                multiple: 2.0,
+            },
+            ..RiskConfig::default()
+        })
+    }
+
+    fn handler_with_prices(symbol: &str, prices: &[f64]) -> MarketDataHandler {
+        let mut handler = MarketDataHandler::new();
+        handler.register_symbol(1, symbol.to_string());
+        let start = time::OffsetDateTime::now_utc() - time::Duration::days(prices.len() as i64);
+        for (i, &price) in prices.iter().enumerate() {
+            handler.add_historical_price(symbol, start + time::Duration::days(i as i64), price);
+        }
+        handler
+    }
+
+    #[test]
+    fn test_higher_atr_widens_stop() {
+        let mut risk_manager = atr_risk_manager();
+        let calm = handler_with_prices("CALM", &[100.0, 100.5, 100.0, 100.5, 100.0, 100.5]);
+        let wild = handler_with_prices("WILD", &[100.0, 104.0, 100.0, 104.0, 100.0, 104.0]);
+        risk_manager.update_atr_from_market_data(&calm);
+        risk_manager.update_atr_from_market_data(&wild);
+
+        let calm_stop = risk_manager
+            .atr_stop_price("CALM", 100.0, true, 2.0)
+            .unwrap();
+        let wild_stop = risk_manager
+            .atr_stop_price("WILD", 100.0, true, 2.0)
+            .unwrap();
+        assert!((calm_stop - 99.0).abs() < 1e-9);
+        assert!((wild_stop - 92.0).abs() < 1e-9);
+        assert!(100.0 - wild_stop > 100.0 - calm_stop);
+
+        // Shorts stop above entry
+        let short_stop = risk_manager
+            .atr_stop_price("WILD", 100.0, false, 2.0)
+            .unwrap();
+        assert!((short_stop - 108.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stop_loss_falls_back_to_percentage_without_atr() {
+        let risk_manager = atr_risk_manager();
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", 10.0, 100.0);
+        let position = portfolio.get_position("AAPL").unwrap();
+
+        assert!(
+            risk_manager
+                .atr_stop_price("AAPL", 100.0, true, 2.0)
+                .is_none()
+        );
+        let stop = risk_manager.calculate_stop_loss(position, 100.0, true);
+        assert!((stop - 98.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_position_stop_tracks_blended_cost() {
+        let risk_manager = RiskManager::new(RiskConfig::default());
+        let mut portfolio = Portfolio::new(100_000.0);
+
+        portfolio.update_position("AAPL", 10.0, 100.0);
+        let stop = risk_manager.position_stop_price(portfolio.get_position("AAPL").unwrap());
+        assert!((stop - 98.0).abs() < 1e-9);
+
+        // Averaging down to $95 moves the stop to 2% below the blend
+        portfolio.update_position("AAPL", 10.0, 90.0);
+        let position = portfolio.get_position("AAPL").unwrap();
+        assert!((position.average_cost - 95.0).abs() < 1e-9);
+        assert!((risk_manager.position_stop_price(position) - 93.1).abs() < 1e-9);
+
+        // Below the first order's stop but above the blended one: hold
+        portfolio.update_market_prices(&HashMap::from([("AAPL".to_string(), 94.0)]));
+        assert!(!risk_manager.position_stop_breached(portfolio.get_position("AAPL").unwrap()));
+
+        portfolio.update_market_prices(&HashMap::from([("AAPL".to_string(), 93.0)]));
+        let stops: Vec<RiskSignal> = risk_manager
+            .generate_risk_signals(&portfolio)
+            .into_iter()
+            .filter(|s| s.reason.contains("position stop"))
+            .collect();
+        assert_eq!(stops.len(), 1);
+        assert!(matches!(stops[0].action, RiskAction::ReducePosition));
+        assert!(matches!(stops[0].urgency, RiskUrgency::Critical));
+        assert_eq!(stops[0].quantity, 20.0);
+    }
+
+    #[test]
+    fn test_short_position_stop_above_blended_cost() {
+        let risk_manager = RiskManager::new(RiskConfig::default());
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("TSLA", -10.0, 200.0);
+        portfolio.update_position("TSLA", -30.0, 220.0);
+
+        let position = portfolio.get_position("TSLA").unwrap();
+        assert!((position.average_cost - 215.0).abs() < 1e-9);
+        assert!((risk_manager.position_stop_price(position) - 219.3).abs() < 1e-9);
+
+        portfolio.update_market_prices(&HashMap::from([("TSLA".to_string(), 219.5)]));
+        assert!(risk_manager.position_stop_breached(portfolio.get_position("TSLA").unwrap()));
+    }
 
     #[test]
     fn test_position_size_calculation() {
@@ -1,6 +1,14 @@
 use crate::config::RiskConfig;
+use crate::market_data::MarketDataHandler;
+use crate::orders::OrderSignal;
 use crate::portfolio::Portfolio;
+use crate::risk::is_risk_reducing;
+use crate::security_types::SecurityInfo;
+use crate::stats::rolling_correlation;
 use anyhow::Result;
+use chrono::NaiveDate;
+use log::{debug, info, warn};
+use statrs::statistics::Statistics;
 use std::collections::HashMap;
 
 /// Portfolio risk budgeting system following Carver's risk parity principles
@@ -13,7 +21,6 @@ use std::collections::HashMap;
 /// - Concentration risk monitoring
 #[derive(Debug, Clone)]
 pub struct RiskBudgeter {
-    #[allow(dead_code)]
     risk_config: RiskConfig,
     correlation_matrix: HashMap<(String, String), f64>,
     volatilities: HashMap<String, f64>,
@@ -54,6 +61,14 @@ pub struct ERCAllocation {
     pub risk_contribution_target: f64,
 }
 
+/// Orders that move the portfolio towards its ERC target weights
+#[derive(Debug, Clone)]
+pub struct RebalancePlan {
+    pub signals: Vec<OrderSignal>,
+    pub total_turnover: f64,    // Notional traded across all signals
+    pub turnover_fraction: f64, // Turnover as a fraction of portfolio value
+}
+
 /// Correlation-based risk metrics
 #[derive(Debug, Clone)]
 pub struct CorrelationRisk {
@@ -74,6 +89,12 @@ impl RiskBudgeter {
         }
     }
 
+    /// Take up a reloaded risk config; correlations and volatilities are kept
+    pub fn set_risk_config(&mut self, risk_config: RiskConfig) {
+        self.target_portfolio_volatility = risk_config.risk_budget_target_volatility;
+        self.risk_config = risk_config;
+    }
+
     /// Update correlation matrix between instruments
     pub fn update_correlation(
         &mut self,
@@ -111,6 +132,84 @@ impl RiskBudgeter {
         Ok(())
     }
 
+    /// Populate correlations and volatilities from daily returns in price history
+    ///
+    /// Computes pairwise Pearson correlations over the dates both symbols have
+    /// returns for, and annualized volatilities from the same returns. Symbols
+    /// with fewer than `lookback_days` days of history are skipped.
+    pub fn compute_correlations_from_history(
+        &mut self,
+        handler: &MarketDataHandler,
+        lookback_days: usize,
+    ) -> Result<()> {
+        if lookback_days < 2 {
+            return Err(anyhow::anyhow!(
+                "Correlation lookback must be at least 2 days, got {}",
+                lookback_days
+            ));
+        }
+
+        let mut returns_by_symbol: Vec<(String, HashMap<NaiveDate, f64>)> = Vec::new();
+        for symbol in handler.tracked_symbols() {
+            let Some(returns) = handler.daily_returns(&symbol, lookback_days) else {
+                warn!(
+                    "Skipping {} in correlation matrix: fewer than {} days of history",
+                    symbol, lookback_days
+                );
+                continue;
+            };
+            if returns.len() < 2 {
+                warn!(
+                    "Skipping {} in correlation matrix: too few valid returns",
+                    symbol
+                );
+                continue;
+            }
+
+            let values: Vec<f64> = returns.iter().map(|(_, r)| *r).collect();
+            let annualized_volatility = values.as_slice().std_dev() * (252.0_f64).sqrt();
+            self.update_volatility(&symbol, annualized_volatility)?;
+
+            returns_by_symbol.push((symbol, returns.into_iter().collect()));
+        }
+
+        for (i, (symbol1, returns1)) in returns_by_symbol.iter().enumerate() {
+            for (symbol2, returns2) in &returns_by_symbol[i + 1..] {
+                let mut dates: Vec<&NaiveDate> = returns1
+                    .keys()
+                    .filter(|d| returns2.contains_key(d))
+                    .collect();
+                dates.sort();
+                if dates.len() < 2 {
+                    warn!(
+                        "Skipping correlation {}/{}: no overlapping return dates",
+                        symbol1, symbol2
+                    );
+                    continue;
+                }
+
+                let series1: Vec<f64> = dates.iter().map(|d| returns1[*d]).collect();
+                let series2: Vec<f64> = dates.iter().map(|d| returns2[*d]).collect();
+                let correlation = rolling_correlation(&series1, &series2, dates.len())?
+                    .first()
+                    .copied()
+                    .unwrap_or(0.0)
+                    .clamp(-1.0, 1.0);
+
+                debug!(
+                    "Correlation {}/{} over {} days: {:.3}",
+                    symbol1,
+                    symbol2,
+                    dates.len(),
+                    correlation
+                );
+                self.update_correlation(symbol1, symbol2, correlation)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Calculate risk contribution for each position in portfolio
     pub fn calculate_risk_contributions(&self, portfolio: &Portfolio) -> Result<RiskAttribution> {
         let positions = portfolio.positions();
@@ -411,6 +510,62 @@ impl RiskBudgeter {
         })
     }
 
+    /// Reject an order that would lift its correlation cluster above
+    /// `max_cluster_exposure` of portfolio value
+    ///
+    /// Clusters come from `calculate_correlation_risk` over the held symbols
+    /// plus `symbol`. Orders that shrink the cluster are always allowed.
+    pub fn check_cluster_exposure(
+        &self,
+        portfolio: &Portfolio,
+        symbol: &str,
+        signed_quantity: f64,
+        price: f64,
+    ) -> Result<()> {
+        let total_value = portfolio.get_stats().total_value;
+        if total_value <= 0.0 {
+            return Ok(());
+        }
+
+        let mut symbols: Vec<String> = portfolio.positions().keys().cloned().collect();
+        if !symbols.iter().any(|s| s == symbol) {
+            symbols.push(symbol.to_string());
+        }
+        let correlation_risk = self.calculate_correlation_risk(&symbols)?;
+        let Some(cluster) = correlation_risk
+            .correlation_clusters
+            .iter()
+            .find(|cluster| cluster.iter().any(|s| s == symbol))
+        else {
+            return Ok(());
+        };
+
+        let exposure = |member: &String, with_order: bool| {
+            let (quantity, member_price) = portfolio
+                .get_position(member)
+                .map_or((0.0, price), |p| (p.quantity, p.current_price));
+            if with_order && member == symbol {
+                ((quantity + signed_quantity) * price).abs()
+            } else {
+                (quantity * member_price).abs()
+            }
+        };
+        let current: f64 = cluster.iter().map(|m| exposure(m, false)).sum();
+        let proposed: f64 = cluster.iter().map(|m| exposure(m, true)).sum();
+
+        let max_exposure = self.risk_config.max_cluster_exposure;
+        if proposed > current && proposed / total_value > max_exposure {
+            return Err(anyhow::anyhow!(
+                "Correlation cluster {:?} would reach {:.1}% of portfolio (limit {:.1}%) after order for {}",
+                cluster,
+                proposed / total_value * 100.0,
+                max_exposure * 100.0,
+                symbol
+            ));
+        }
+        Ok(())
+    }
+
     /// Get correlation between two instruments (returns 0.0 if not found)
     pub fn get_correlation(&self, symbol1: &str, symbol2: &str) -> f64 {
         self.correlation_matrix
@@ -502,6 +657,122 @@ impl RiskBudgeter {
         Ok(portfolio_variance.sqrt())
     }
 
+    /// Scale new entries uniformly so the projected portfolio volatility
+    /// hits `target_portfolio_volatility` (Carver's volatility target)
+    ///
+    /// The projection starts from the current positions with the
+    /// risk-reducing signals applied; those always go through unscaled.
+    /// Entries are scaled by the factor that puts `held + factor * entries`
+    /// on target, never below zero, so a book already over target drops its
+    /// entries. Whole-unit quantities stay whole, fractional ones are scaled
+    /// as is, and entries that scale to nothing are dropped. Signals come back
+    /// unchanged when a symbol has no volatility.
+    pub fn scale_to_target_volatility(
+        &self,
+        portfolio: &Portfolio,
+        signals: &[OrderSignal],
+    ) -> Vec<OrderSignal> {
+        let total_value = portfolio.get_stats().total_value;
+        if signals.is_empty() || total_value <= 0.0 {
+            return signals.to_vec();
+        }
+
+        // Base-currency weight of each signal's trade
+        let weight = |signal: &OrderSignal| {
+            let direction = if signal.action == "BUY" { 1.0 } else { -1.0 };
+            let rate = portfolio
+                .fx_rate(&signal.security_info.currency)
+                .unwrap_or(1.0);
+            let value = signal
+                .security_info
+                .get_position_value(signal.price, direction * signal.quantity);
+            value * rate / total_value
+        };
+        let mut held: HashMap<String, f64> = portfolio
+            .positions()
+            .keys()
+            .filter_map(|symbol| {
+                let value = portfolio.position_value(symbol)?;
+                Some((symbol.clone(), value / total_value))
+            })
+            .collect();
+        let mut added: HashMap<String, f64> = HashMap::new();
+        for signal in signals {
+            let weights = if is_risk_reducing(signal, portfolio) {
+                &mut held
+            } else {
+                &mut added
+            };
+            *weights.entry(signal.symbol.clone()).or_insert(0.0) += weight(signal);
+        }
+        held.retain(|_, weight| *weight != 0.0);
+
+        let scale = match self.entry_scale(&held, &added) {
+            Ok(Some(scale)) => scale,
+            Ok(None) => return signals.to_vec(),
+            Err(e) => {
+                warn!("Signals not scaled to target volatility: {}", e);
+                return signals.to_vec();
+            }
+        };
+        info!(
+            "Scaling entries by {:.2} to target {:.1}% portfolio volatility",
+            scale,
+            self.target_portfolio_volatility * 100.0
+        );
+
+        signals
+            .iter()
+            .filter_map(|signal| {
+                if is_risk_reducing(signal, portfolio) {
+                    return Some(signal.clone());
+                }
+                let quantity = signal.quantity * scale;
+                let quantity = if signal.quantity.fract() == 0.0 {
+                    quantity.round()
+                } else {
+                    quantity
+                };
+                (quantity > 0.0).then(|| OrderSignal {
+                    quantity,
+                    ..signal.clone()
+                })
+            })
+            .collect()
+    }
+
+    /// Factor on `added` that puts the volatility of `held + factor * added`
+    /// on target
+    ///
+    /// The variance is quadratic in the factor k: a k^2 + 2b k + c with
+    /// a = added'Σadded, b = held'Σadded and c = held'Σheld. When no factor
+    /// reaches the target the variance-minimizing one is used. None when the
+    /// entries carry no risk.
+    fn entry_scale(
+        &self,
+        held: &HashMap<String, f64>,
+        added: &HashMap<String, f64>,
+    ) -> Result<Option<f64>> {
+        let a = self.calculate_portfolio_volatility(added)?.powi(2);
+        if a <= 0.0 {
+            return Ok(None);
+        }
+        let c = self.calculate_portfolio_volatility(held)?.powi(2);
+        let mut combined = held.clone();
+        for (symbol, weight) in added {
+            *combined.entry(symbol.clone()).or_insert(0.0) += weight;
+        }
+        let b = (self.calculate_portfolio_volatility(&combined)?.powi(2) - a - c) / 2.0;
+
+        let discriminant = b * b - a * (c - self.target_portfolio_volatility.powi(2));
+        let scale = if discriminant >= 0.0 {
+            (-b + discriminant.sqrt()) / a
+        } else {
+            -b / a
+        };
+        Ok(Some(scale.max(0.0)))
+    }
+
     /// Generate rebalancing recommendations to achieve ERC
     pub fn generate_rebalancing_recommendations(
         &self,
@@ -548,12 +819,100 @@ impl RiskBudgeter {
 
         Ok(recommendations)
     }
+
+    /// Turn rebalancing recommendations into orders
+    ///
+    /// Weight adjustments smaller than `risk_budget_rebalance_threshold` are
+    /// dropped. Quantities are whole shares, contracts or currency units and
+    /// use contract multipliers for futures. Symbols without a price are skipped.
+    pub fn create_rebalancing_orders(
+        &self,
+        recommendations: &[ERCAllocation],
+        portfolio: &Portfolio,
+        prices: &HashMap<String, f64>,
+        portfolio_value: f64,
+    ) -> RebalancePlan {
+        let threshold = self.risk_config.risk_budget_rebalance_threshold;
+        let mut signals = Vec::new();
+        let mut total_turnover = 0.0;
+
+        for allocation in recommendations {
+            if allocation.adjustment_needed.abs() < threshold {
+                debug!(
+                    "Skipping rebalance of {}: adjustment {:.2}% below threshold",
+                    allocation.symbol,
+                    allocation.adjustment_needed * 100.0
+                );
+                continue;
+            }
+
+            let Some(&price) = prices.get(&allocation.symbol).filter(|p| **p > 0.0) else {
+                warn!("No price for {}, skipping rebalance", allocation.symbol);
+                continue;
+            };
+
+            // Fall back to stock if the position carries no security info
+            let security_info = portfolio
+                .get_position(&allocation.symbol)
+                .and_then(|position| position.security_info.clone())
+                .unwrap_or_else(|| {
+                    SecurityInfo::new_stock(
+                        allocation.symbol.clone(),
+                        "SMART".to_string(),
+                        "USD".to_string(),
+                    )
+                });
+
+            let value_change = allocation.adjustment_needed * portfolio_value;
+            let quantity = (value_change / security_info.get_contract_value(price))
+                .abs()
+                .trunc();
+            if quantity == 0.0 {
+                continue;
+            }
+
+            total_turnover += security_info.get_position_value(price, quantity);
+            signals.push(OrderSignal {
+                symbol: allocation.symbol.clone(),
+                action: if value_change > 0.0 { "BUY" } else { "SELL" }.to_string(),
+                quantity,
+                price,
+                order_type: "MKT".to_string(),
+                limit_price: None,
+                reason: format!(
+                    "ERC rebalance: weight {:.1}% -> {:.1}%",
+                    allocation.current_weight * 100.0,
+                    allocation.target_weight * 100.0
+                ),
+                security_info,
+            });
+        }
+
+        let turnover_fraction = if portfolio_value > 0.0 {
+            total_turnover / portfolio_value
+        } else {
+            0.0
+        };
+
+        info!(
+            "Rebalance plan: {} orders, turnover ${:.0} ({:.1}% of portfolio)",
+            signals.len(),
+            total_turnover,
+            turnover_fraction * 100.0
+        );
+
+        RebalancePlan {
+            signals,
+            total_turnover,
+            turnover_fraction,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::RiskConfig;
+    use crate::config::{RiskConfig, StopLossMethod};
     use crate::portfolio::Portfolio;
     use crate::security_types::SecurityInfo;
 
@@ -563,6 +922,7 @@ mod tests {
             max_portfolio_exposure: 0.95,
             stop_loss_percentage: 0.02,
             take_profit_percentage: 0.04,
+            stop_loss_method: StopLossMethod::Percentage,
             max_margin_utilization: 0.70,
             min_excess_liquidity: 10000.0,
             futures_position_limit: 10.0,
@@ -574,17 +934,25 @@ mod tests {
             max_correlation_exposure: 0.60,
             correlation_lookback_days: 63,
             min_positions_for_erc: 3,
+            max_cluster_exposure: 0.40,
             // Transaction Cost Configuration
             enable_transaction_cost_optimization: true,
-            stock_commission: 1.0,
-            futures_commission: 2.5,
-            forex_commission: 2.0,
             max_acceptable_cost_bps: 15.0,
             // Position Inertia Configuration
             enable_position_inertia: true,
             inertia_multiplier: 2.0,
             min_position_change_value: 100.0,
             max_position_change_pct: 0.20,
+            max_daily_loss: 0.03,
+            halt_reset_hour_utc: 0,
+            min_holding_period_minutes: 0,
+            reentry_cooldown_minutes: 0,
+            drawdown_scaling: None,
+            drawdown_derisking: None,
+            auto_protective_orders: false,
+            shortable_symbols: Vec::new(),
+            max_short_quantity: 0.0,
+            working_order_timeout_seconds: 300,
         }
     }
 
@@ -762,6 +1130,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cluster_exposure_cap_rejects_correlated_buy() {
+        let mut budgeter = RiskBudgeter::new(
+            RiskConfig {
+                max_cluster_exposure: 0.25,
+                ..create_test_risk_config()
+            },
+            0.15,
+        );
+        budgeter.update_correlation("AAPL", "MSFT", 0.90).unwrap();
+        budgeter.update_correlation("AAPL", "XOM", 0.10).unwrap();
+
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", 100.0, 150.0); // 15% of portfolio
+        let total_value = portfolio.get_stats().total_value;
+        let msft_budget = 0.25 * total_value - 15_000.0;
+
+        // Second correlated name breaches the 25% cluster cap
+        let error = budgeter
+            .check_cluster_exposure(&portfolio, "MSFT", 50.0, 300.0)
+            .unwrap_err();
+        assert!(error.to_string().contains("limit 25.0%"));
+
+        // Smaller buys, uncorrelated names and reductions pass
+        let within_cap = (msft_budget / 300.0).floor() - 1.0;
+        assert!(
+            budgeter
+                .check_cluster_exposure(&portfolio, "MSFT", within_cap, 300.0)
+                .is_ok()
+        );
+        assert!(
+            budgeter
+                .check_cluster_exposure(&portfolio, "XOM", 100.0, 100.0)
+                .is_ok()
+        );
+        portfolio.update_position("MSFT", 50.0, 300.0);
+        assert!(
+            budgeter
+                .check_cluster_exposure(&portfolio, "MSFT", -10.0, 300.0)
+                .is_ok()
+        );
+    }
+
     #[test]
     fn test_correlation_risk_analysis() {
         let mut budgeter = RiskBudgeter::new(create_test_risk_config(), 0.15);
@@ -826,6 +1237,90 @@ mod tests {
         );
     }
 
+    fn buy(symbol: &str, quantity: f64, price: f64) -> OrderSignal {
+        OrderSignal {
+            symbol: symbol.to_string(),
+            action: "BUY".to_string(),
+            quantity,
+            price,
+            order_type: "MKT".to_string(),
+            limit_price: None,
+            reason: "test".to_string(),
+            security_info: SecurityInfo::new_stock(
+                symbol.to_string(),
+                "SMART".to_string(),
+                "USD".to_string(),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_scale_to_target_volatility_halves_sizes() {
+        let mut budgeter = RiskBudgeter::new(create_test_risk_config(), 0.15);
+        budgeter.update_volatility("AAPL", 0.50).unwrap();
+        budgeter.update_volatility("MSFT", 0.50).unwrap();
+        budgeter.update_correlation("AAPL", "MSFT", 1.0).unwrap();
+        let portfolio = Portfolio::new(100_000.0);
+
+        // 30% of the portfolio in each, perfectly correlated: 30% projected vol
+        let signals = [buy("AAPL", 200.0, 150.0), buy("MSFT", 100.0, 300.0)];
+        let weights = HashMap::from([("AAPL".to_string(), 0.3), ("MSFT".to_string(), 0.3)]);
+        let projected = budgeter.calculate_portfolio_volatility(&weights).unwrap();
+        assert!((projected - 0.30).abs() < 1e-12);
+
+        let scaled = budgeter.scale_to_target_volatility(&portfolio, &signals);
+        let sizes: Vec<(&str, f64)> = scaled
+            .iter()
+            .map(|s| (s.symbol.as_str(), s.quantity))
+            .collect();
+        assert_eq!(sizes, [("AAPL", 100.0), ("MSFT", 50.0)]);
+
+        // Below target the sizes grow instead
+        let half = [buy("AAPL", 50.0, 150.0), buy("MSFT", 25.0, 300.0)];
+        let scaled = budgeter.scale_to_target_volatility(&portfolio, &half);
+        assert_eq!(scaled[0].quantity, 100.0);
+        assert_eq!(scaled[1].quantity, 50.0);
+
+        // Unknown volatility leaves the signals alone
+        let unknown = [buy("XOM", 100.0, 100.0)];
+        let unscaled = budgeter.scale_to_target_volatility(&portfolio, &unknown);
+        assert_eq!(unscaled[0].quantity, 100.0);
+    }
+
+    #[test]
+    fn test_scale_to_target_volatility_counts_existing_positions() {
+        let mut budgeter = RiskBudgeter::new(create_test_risk_config(), 0.25);
+        budgeter.update_volatility("AAPL", 0.50).unwrap();
+        budgeter.update_volatility("MSFT", 0.50).unwrap();
+        budgeter.update_correlation("AAPL", "MSFT", 0.0).unwrap();
+        // $30,000 of AAPL in a $100,000 account
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", 200.0, 150.0);
+
+        // Trimming AAPL to 22.5% leaves 11.25% vol; the MSFT entry is sized
+        // so the uncorrelated total lands on 25%
+        let mut trim = buy("AAPL", 50.0, 150.0);
+        trim.action = "SELL".to_string();
+        let signals = [trim, buy("MSFT", 100.0, 300.0), buy("MSFT", 0.5, 300.0)];
+        let scaled = budgeter.scale_to_target_volatility(&portfolio, &signals);
+
+        let held_variance = (0.225_f64 * 0.5).powi(2);
+        let entry_weight: f64 = 100.5 * 300.0 / 100_000.0;
+        let scale = ((0.25_f64.powi(2) - held_variance) / (entry_weight * 0.5).powi(2)).sqrt();
+        assert_eq!(scaled[0].quantity, 50.0); // Reductions are never scaled
+        assert_eq!(scaled[1].quantity, (100.0 * scale).round());
+        assert!((scaled[2].quantity - 0.5 * scale).abs() < 1e-9); // Fractional stays fractional
+
+        // Already over a 10% target: entries are dropped, reductions kept
+        let budgeter = RiskBudgeter {
+            target_portfolio_volatility: 0.10,
+            ..budgeter
+        };
+        let scaled = budgeter.scale_to_target_volatility(&portfolio, &signals[..2]);
+        assert_eq!(scaled.len(), 1);
+        assert_eq!(scaled[0].symbol, "AAPL");
+    }
+
     #[test]
     fn test_risk_budget_violation_detection() {
         let budgeter = RiskBudgeter::new(create_test_risk_config(), 0.15);
@@ -905,4 +1400,165 @@ mod tests {
             }
         );
     }
+
+    fn allocation(symbol: &str, current_weight: f64, target_weight: f64) -> ERCAllocation {
+        ERCAllocation {
+            symbol: symbol.to_string(),
+            current_weight,
+            target_weight,
+            adjustment_needed: target_weight - current_weight,
+            risk_contribution_current: 0.0,
+            risk_contribution_target: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_overweight_high_vol_position_generates_sell() {
+        let mut budgeter = RiskBudgeter::new(create_test_risk_config(), 0.15);
+        let portfolio = create_test_portfolio();
+        budgeter.update_volatility("AAPL", 0.60).unwrap(); // Very high vol
+        budgeter.update_volatility("SPY", 0.12).unwrap();
+        budgeter.update_volatility("QQQ", 0.15).unwrap();
+
+        let recommendations = budgeter
+            .generate_rebalancing_recommendations(&portfolio)
+            .unwrap();
+        let aapl = recommendations
+            .iter()
+            .find(|rec| rec.symbol == "AAPL")
+            .unwrap();
+        assert!(aapl.adjustment_needed < 0.0);
+
+        let prices = HashMap::from([
+            ("AAPL".to_string(), 150.0),
+            ("SPY".to_string(), 400.0),
+            ("QQQ".to_string(), 300.0),
+        ]);
+        let portfolio_value = 100.0 * 150.0 + 50.0 * 400.0 + 75.0 * 300.0;
+        let plan = budgeter.create_rebalancing_orders(
+            &recommendations,
+            &portfolio,
+            &prices,
+            portfolio_value,
+        );
+
+        let sell = plan.signals.iter().find(|s| s.symbol == "AAPL").unwrap();
+        assert_eq!(sell.action, "SELL");
+        let expected_shares = (aapl.adjustment_needed.abs() * portfolio_value / 150.0).trunc();
+        assert_eq!(sell.quantity, expected_shares);
+        assert!(plan.total_turnover >= expected_shares * 150.0);
+    }
+
+    #[test]
+    fn test_rebalancing_orders_respect_threshold_and_units() {
+        let budgeter = RiskBudgeter::new(create_test_risk_config(), 0.15);
+        let mut portfolio = Portfolio::new(0.0);
+        portfolio.register_security(
+            "ES".to_string(),
+            SecurityInfo::new_future(
+                "ES".to_string(),
+                "CME".to_string(),
+                "USD".to_string(),
+                crate::security_types::FuturesContract {
+                    underlying: "ES".to_string(),
+                    expiry: "20240315".to_string(),
+                    multiplier: 50.0,
+                    tick_size: 0.25,
+                    contract_month: "202403".to_string(),
+                },
+            ),
+        );
+        portfolio.update_position("ES", 2.0, 5000.0);
+
+        let recommendations = vec![
+            allocation("ES", 0.50, 0.80),  // +30% of $1M = 1.2 contracts
+            allocation("SPY", 0.20, 0.18), // Below the 5% threshold
+        ];
+        let prices = HashMap::from([("ES".to_string(), 5000.0), ("SPY".to_string(), 400.0)]);
+
+        let plan =
+            budgeter.create_rebalancing_orders(&recommendations, &portfolio, &prices, 1_000_000.0);
+
+        assert_eq!(plan.signals.len(), 1);
+        assert_eq!(plan.signals[0].action, "BUY");
+        assert_eq!(plan.signals[0].quantity, 1.0);
+        assert!((plan.total_turnover - 250_000.0).abs() < 1e-9);
+        assert!((plan.turnover_fraction - 0.25).abs() < 1e-9);
+    }
+
+    fn handler_with_returns(series: &[(&str, Vec<f64>)]) -> MarketDataHandler {
+        let mut handler = MarketDataHandler::new();
+        let start = time::OffsetDateTime::now_utc() - time::Duration::days(100);
+        for (req_id, (symbol, returns)) in series.iter().enumerate() {
+            handler.register_symbol(req_id as i32, symbol.to_string());
+            let mut price = 100.0;
+            handler.add_historical_price(symbol, start, price);
+            for (day, r) in returns.iter().enumerate() {
+                price *= 1.0 + r;
+                handler.add_historical_price(
+                    symbol,
+                    start + time::Duration::days(day as i64 + 1),
+                    price,
+                );
+            }
+        }
+        handler
+    }
+
+    fn synthetic_returns(days: usize) -> Vec<f64> {
+        (0..days)
+            .map(|i| 0.01 * ((i as f64) * 0.7).sin() + 0.002 * ((i % 3) as f64 - 1.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_correlations_from_history_perfectly_correlated() {
+        let returns = synthetic_returns(30);
+        let handler = handler_with_returns(&[("AAA", returns.clone()), ("BBB", returns)]);
+
+        let mut budgeter = RiskBudgeter::new(create_test_risk_config(), 0.15);
+        budgeter
+            .compute_correlations_from_history(&handler, 20)
+            .unwrap();
+
+        assert!((budgeter.get_correlation("AAA", "BBB") - 1.0).abs() < 1e-9);
+        assert!((budgeter.get_correlation("BBB", "AAA") - 1.0).abs() < 1e-9);
+        assert!(budgeter.volatilities.get("AAA").unwrap() > &0.0);
+    }
+
+    #[test]
+    fn test_correlations_from_history_anti_correlated() {
+        let returns = synthetic_returns(30);
+        let inverse: Vec<f64> = returns.iter().map(|r| -r).collect();
+        let handler = handler_with_returns(&[("AAA", returns), ("BBB", inverse)]);
+
+        let mut budgeter = RiskBudgeter::new(create_test_risk_config(), 0.15);
+        budgeter
+            .compute_correlations_from_history(&handler, 20)
+            .unwrap();
+
+        assert!((budgeter.get_correlation("AAA", "BBB") + 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_correlations_from_history_skips_short_history() {
+        let handler = handler_with_returns(&[
+            ("AAA", synthetic_returns(30)),
+            ("BBB", synthetic_returns(30)),
+            ("NEW", synthetic_returns(5)),
+        ]);
+
+        let mut budgeter = RiskBudgeter::new(create_test_risk_config(), 0.15);
+        budgeter
+            .compute_correlations_from_history(&handler, 20)
+            .unwrap();
+
+        assert!(!budgeter.volatilities.contains_key("NEW"));
+        assert!(
+            !budgeter
+                .correlation_matrix
+                .contains_key(&("AAA".to_string(), "NEW".to_string()))
+        );
+        assert!(budgeter.volatilities.contains_key("AAA"));
+    }
 }
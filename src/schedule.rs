@@ -0,0 +1,275 @@
+//! When the strategy rebalances
+//!
+//! A `Schedule` is either a fixed interval, a list of UTC times of day, or
+//! runs tied to a market session (e.g. 10 minutes after the open, then every
+//! hour until the close). All times are UTC; sessions do not follow daylight
+//! saving changes, so adjust `open`/`close` when the exchange's offset shifts.
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// Days searched ahead for the next run; covers a weekend plus holidays
+const SEARCH_DAYS: i64 = 8;
+
+/// Trading hours of a market in UTC
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketSession {
+    pub open: NaiveTime,
+    pub close: NaiveTime, // At or before `open` means the session ends the next day
+    #[serde(default = "default_weekdays_only")]
+    pub weekdays_only: bool,
+}
+
+fn default_weekdays_only() -> bool {
+    true
+}
+
+impl MarketSession {
+    /// NYSE/Nasdaq regular hours, 09:30-16:00 New York during daylight saving
+    pub fn us_equities() -> Self {
+        Self {
+            open: NaiveTime::from_hms_opt(13, 30, 0).unwrap(),
+            close: NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+            weekdays_only: true,
+        }
+    }
+
+    /// Open and close of the session starting on `date`, if it trades that day
+    fn on(&self, date: NaiveDate) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        if self.weekdays_only && is_weekend(date) {
+            return None;
+        }
+        let open = date.and_time(self.open).and_utc();
+        let mut close = date.and_time(self.close).and_utc();
+        if close <= open {
+            close += Duration::days(1);
+        }
+        Some((open, close))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Schedule {
+    /// Every `minutes`, around the clock
+    Interval { minutes: u64 },
+    /// At each UTC time of day, optionally skipping weekends
+    TimesOfDay {
+        times: Vec<NaiveTime>,
+        #[serde(default = "default_weekdays_only")]
+        weekdays_only: bool,
+    },
+    /// `after_open_minutes` into each session, then every `every_minutes`
+    /// (if set) until the close
+    Session {
+        #[serde(default = "MarketSession::us_equities")]
+        session: MarketSession,
+        #[serde(default)]
+        after_open_minutes: u64,
+        #[serde(default)]
+        every_minutes: Option<u64>,
+    },
+}
+
+impl Schedule {
+    /// First run strictly after `now`
+    pub fn next_run(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Schedule::Interval { minutes } => now + Duration::minutes(*minutes as i64),
+            Schedule::TimesOfDay {
+                times,
+                weekdays_only,
+            } => {
+                let mut times = times.clone();
+                times.sort();
+                (0..=SEARCH_DAYS)
+                    .map(|offset| now.date_naive() + Duration::days(offset))
+                    .filter(|date| !(*weekdays_only && is_weekend(*date)))
+                    .flat_map(|date| times.iter().map(move |time| date.and_time(*time).and_utc()))
+                    .find(|run| *run > now)
+                    .unwrap_or_else(|| now + Duration::days(1))
+            }
+            Schedule::Session {
+                session,
+                after_open_minutes,
+                every_minutes,
+            } => (-1..=SEARCH_DAYS)
+                .filter_map(|offset| session.on(now.date_naive() + Duration::days(offset)))
+                .find_map(|(open, close)| {
+                    let first = open + Duration::minutes(*after_open_minutes as i64);
+                    if first > close {
+                        return None;
+                    }
+                    if first > now {
+                        return Some(first);
+                    }
+                    let every = Duration::minutes((*every_minutes)? as i64);
+                    let elapsed = (now - first).num_seconds() / every.num_seconds();
+                    let run = first + every * (elapsed as i32 + 1);
+                    (run <= close).then_some(run)
+                })
+                .unwrap_or_else(|| now + Duration::days(1)),
+        }
+    }
+
+    /// Check the schedule can produce runs
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Schedule::Interval { minutes } => {
+                if *minutes == 0 {
+                    bail!("interval minutes must be positive");
+                }
+            }
+            Schedule::TimesOfDay { times, .. } => {
+                if times.is_empty() {
+                    bail!("times_of_day needs at least one time");
+                }
+            }
+            Schedule::Session {
+                session,
+                after_open_minutes,
+                every_minutes,
+            } => {
+                if every_minutes == &Some(0) {
+                    bail!("session every_minutes must be positive");
+                }
+                // Any date will do: every session has the same length
+                let monday = NaiveDate::from_isoywd_opt(2024, 1, Weekday::Mon).unwrap();
+                let (open, close) = session.on(monday).unwrap();
+                if open + Duration::minutes(*after_open_minutes as i64) > close {
+                    bail!(
+                        "session after_open_minutes {} is past the close",
+                        after_open_minutes
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_weekend(date: NaiveDate) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        // January 2024: the 1st is a Monday, the 6th and 7th a weekend
+        Utc.with_ymd_and_hms(2024, 1, day, hour, minute, 0).unwrap()
+    }
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_time_of_day_rolls_over_day_boundary() {
+        let schedule = Schedule::TimesOfDay {
+            times: vec![time(20, 0), time(14, 0)],
+            weekdays_only: false,
+        };
+
+        assert_eq!(schedule.next_run(at(2, 9, 0)), at(2, 14, 0));
+        assert_eq!(schedule.next_run(at(2, 14, 0)), at(2, 20, 0));
+        // After the last time of the day: first time tomorrow
+        assert_eq!(schedule.next_run(at(2, 21, 30)), at(3, 14, 0));
+        assert_eq!(
+            schedule.next_run(at(31, 23, 59)),
+            Utc.with_ymd_and_hms(2024, 2, 1, 14, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_time_of_day_skips_weekend() {
+        let schedule = Schedule::TimesOfDay {
+            times: vec![time(14, 0)],
+            weekdays_only: true,
+        };
+
+        // Friday evening -> Monday
+        assert_eq!(schedule.next_run(at(5, 15, 0)), at(8, 14, 0));
+    }
+
+    #[test]
+    fn test_session_runs_after_open_then_every_interval() {
+        let schedule = Schedule::Session {
+            session: MarketSession::us_equities(),
+            after_open_minutes: 10,
+            every_minutes: Some(60),
+        };
+
+        assert_eq!(schedule.next_run(at(2, 8, 0)), at(2, 13, 40));
+        assert_eq!(schedule.next_run(at(2, 13, 40)), at(2, 14, 40));
+        assert_eq!(schedule.next_run(at(2, 19, 45)), at(3, 13, 40));
+        // Friday after the last run -> Monday
+        assert_eq!(schedule.next_run(at(5, 19, 50)), at(8, 13, 40));
+    }
+
+    #[test]
+    fn test_overnight_session() {
+        let session = MarketSession {
+            open: time(22, 0),
+            close: time(21, 0),
+            weekdays_only: false,
+        };
+        let schedule = Schedule::Session {
+            session,
+            after_open_minutes: 0,
+            every_minutes: Some(240),
+        };
+        // Session opened 22:00 on the 2nd; runs at 02:00, 06:00, ...
+        assert_eq!(schedule.next_run(at(3, 3, 0)), at(3, 6, 0));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Schedule::Interval { minutes: 0 }.validate().is_err());
+        assert!(
+            Schedule::TimesOfDay {
+                times: vec![],
+                weekdays_only: true
+            }
+            .validate()
+            .is_err()
+        );
+        let late = Schedule::Session {
+            session: MarketSession::us_equities(),
+            after_open_minutes: 500,
+            every_minutes: None,
+        };
+        assert!(late.validate().is_err());
+        assert!(Schedule::Interval { minutes: 60 }.validate().is_ok());
+    }
+
+    #[test]
+    fn test_session_defaults_to_us_equities() {
+        let schedule: Schedule =
+            serde_json::from_str(r#"{"type": "session", "after_open_minutes": 10}"#).unwrap();
+        assert_eq!(
+            schedule,
+            Schedule::Session {
+                session: MarketSession::us_equities(),
+                after_open_minutes: 10,
+                every_minutes: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_deserialize_times_of_day() {
+        let schedule: Schedule =
+            serde_json::from_str(r#"{"type": "times_of_day", "times": ["13:40:00"]}"#).unwrap();
+        assert_eq!(
+            schedule,
+            Schedule::TimesOfDay {
+                times: vec![time(13, 40)],
+                weekdays_only: true
+            }
+        );
+    }
+}
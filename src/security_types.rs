@@ -1,4 +1,6 @@
+use anyhow::{Result, bail};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum SecurityType {
@@ -24,6 +26,37 @@ pub struct ForexPair {
     pub pair_symbol: String, // e.g., "EUR.USD"
 }
 
+impl ForexPair {
+    /// Price increment of one pip: 0.01 for JPY-quoted pairs, 0.0001 otherwise
+    pub fn pip_size(&self) -> f64 {
+        if self.quote_currency == "JPY" {
+            0.01
+        } else {
+            0.0001
+        }
+    }
+
+    /// Value of a one-pip move on `notional_units` of the base currency,
+    /// converted to the account currency
+    pub fn pip_value(&self, notional_units: f64, quote_to_account_rate: f64) -> f64 {
+        notional_units * self.pip_size() * quote_to_account_rate
+    }
+
+    /// Rate converting quote-currency amounts into `account_currency`
+    ///
+    /// `price` is the pair's quote (quote currency per base unit). Crosses
+    /// not involving the account currency need a separate rate and return None.
+    pub fn quote_to_account_rate(&self, price: f64, account_currency: &str) -> Option<f64> {
+        if self.quote_currency == account_currency {
+            Some(1.0)
+        } else if self.base_currency == account_currency && price > 0.0 {
+            Some(1.0 / price)
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct FuturesContract {
     pub underlying: String,
@@ -93,6 +126,29 @@ impl SecurityInfo {
         }
     }
 
+    /// Smallest price step: a cent for stocks, a pip for forex, the contract
+    /// tick for futures
+    pub fn price_increment(&self) -> f64 {
+        match &self.security_type {
+            SecurityType::Stock => 0.01,
+            SecurityType::Forex => self.forex_pair.as_ref().map_or(0.0001, ForexPair::pip_size),
+            SecurityType::Future => self
+                .contract_specs
+                .as_ref()
+                .map(|contract| contract.tick_size)
+                .filter(|tick_size| *tick_size > 0.0)
+                .unwrap_or(0.01),
+        }
+    }
+
+    /// Bid and ask one price increment apart around `price`
+    ///
+    /// Stands in for a quote when only trade or midpoint prices are known.
+    pub fn approximate_quote(&self, price: f64) -> (f64, f64) {
+        let half_spread = self.price_increment() / 2.0;
+        (price - half_spread, price + half_spread)
+    }
+
     pub fn get_contract_value(&self, price: f64) -> f64 {
         match &self.security_type {
             SecurityType::Stock => price,
@@ -107,6 +163,11 @@ impl SecurityInfo {
         }
     }
 
+    /// Whole shares, contracts or base currency units worth at most `notional`
+    pub fn quantity_for_notional(&self, notional: f64, price: f64) -> f64 {
+        (notional.abs() / self.get_contract_value(price)).floor()
+    }
+
     pub fn get_position_value(&self, price: f64, quantity: f64) -> f64 {
         match &self.security_type {
             SecurityType::Stock => price * quantity,
@@ -136,3 +197,176 @@ impl SecurityInfo {
         })
     }
 }
+
+/// One leg of a futures spread
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpreadLeg {
+    pub contract_id: i32, // IB contract id of the leg's futures contract
+    pub contract_month: String,
+    pub ratio: i32, // Positive legs are bought, negative legs sold
+}
+
+/// Combo (BAG) contract trading several futures legs as one order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpreadContract {
+    pub symbol: String,
+    pub exchange: String,
+    pub currency: String,
+    pub legs: Vec<SpreadLeg>,
+}
+
+impl SpreadContract {
+    /// Validate the legs: at least two distinct contracts with non-zero
+    /// ratios, bought on one side and sold on the other
+    pub fn new(symbol: &str, exchange: &str, currency: &str, legs: Vec<SpreadLeg>) -> Result<Self> {
+        if legs.len() < 2 {
+            bail!(
+                "Spread {} needs at least two legs, got {}",
+                symbol,
+                legs.len()
+            );
+        }
+        let mut contract_ids = HashSet::new();
+        for leg in &legs {
+            if leg.ratio == 0 {
+                bail!(
+                    "Spread {} leg {} has a zero ratio",
+                    symbol,
+                    leg.contract_month
+                );
+            }
+            if leg.contract_id <= 0 {
+                bail!(
+                    "Spread {} leg {} has no contract id",
+                    symbol,
+                    leg.contract_month
+                );
+            }
+            if !contract_ids.insert(leg.contract_id) {
+                bail!(
+                    "Spread {} lists contract {} more than once",
+                    symbol,
+                    leg.contract_id
+                );
+            }
+        }
+        if !legs.iter().any(|leg| leg.ratio > 0) || !legs.iter().any(|leg| leg.ratio < 0) {
+            bail!("Spread {} needs both a long and a short leg", symbol);
+        }
+
+        Ok(Self {
+            symbol: symbol.to_string(),
+            exchange: exchange.to_string(),
+            currency: currency.to_string(),
+            legs,
+        })
+    }
+
+    /// 1:-1 calendar spread: long the `long` month, short the `short` month
+    pub fn calendar(
+        symbol: &str,
+        exchange: &str,
+        currency: &str,
+        long: (i32, &str),
+        short: (i32, &str),
+    ) -> Result<Self> {
+        let leg = |(contract_id, contract_month): (i32, &str), ratio| SpreadLeg {
+            contract_id,
+            contract_month: contract_month.to_string(),
+            ratio,
+        };
+        Self::new(
+            symbol,
+            exchange,
+            currency,
+            vec![leg(long, 1), leg(short, -1)],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(symbol: &str) -> ForexPair {
+        SecurityInfo::new_forex(
+            symbol.to_string(),
+            "IDEALPRO".to_string(),
+            "USD".to_string(),
+        )
+        .forex_pair
+        .unwrap()
+    }
+
+    #[test]
+    fn test_eurusd_pip_value() {
+        let eurusd = pair("EUR.USD");
+        assert_eq!(eurusd.pip_size(), 0.0001);
+
+        // 100k EUR: one pip is $10 regardless of the EUR.USD rate
+        let rate = eurusd.quote_to_account_rate(1.0850, "USD").unwrap();
+        assert!((eurusd.pip_value(100_000.0, rate) - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_usdjpy_pip_value() {
+        let usdjpy = pair("USD.JPY");
+        assert_eq!(usdjpy.pip_size(), 0.01);
+
+        // 100k USD at 150.00: one pip is 1,000 JPY = $6.67
+        let rate = usdjpy.quote_to_account_rate(150.0, "USD").unwrap();
+        assert!((usdjpy.pip_value(100_000.0, rate) - 1000.0 / 150.0).abs() < 1e-9);
+
+        // A JPY account needs no conversion; a GBP account needs a cross rate
+        assert!((usdjpy.pip_value(100_000.0, 1.0) - 1000.0).abs() < 1e-9);
+        assert_eq!(usdjpy.quote_to_account_rate(150.0, "GBP"), None);
+    }
+
+    #[test]
+    fn test_approximate_quote_spans_one_price_increment() {
+        let spread = |info: SecurityInfo, price: f64| {
+            let (bid, ask) = info.approximate_quote(price);
+            assert!((bid + ask - 2.0 * price).abs() < 1e-9);
+            ask - bid
+        };
+        let stock =
+            SecurityInfo::new_stock("BRK.A".to_string(), "SMART".to_string(), "USD".to_string());
+        let forex = |symbol: &str| {
+            SecurityInfo::new_forex(
+                symbol.to_string(),
+                "IDEALPRO".to_string(),
+                "USD".to_string(),
+            )
+        };
+        let future = SecurityInfo::new_future(
+            "ES".to_string(),
+            "CME".to_string(),
+            "USD".to_string(),
+            FuturesContract {
+                tick_size: 0.25,
+                ..FuturesContract::default()
+            },
+        );
+
+        assert!((spread(stock, 650_000.0) - 0.01).abs() < 1e-9);
+        assert!((spread(forex("EUR.USD"), 1.085) - 0.0001).abs() < 1e-12);
+        assert!((spread(forex("USD.JPY"), 150.0) - 0.01).abs() < 1e-9);
+        assert!((spread(future, 5000.0) - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_spread_leg_validation() {
+        let leg = |contract_id, ratio| SpreadLeg {
+            contract_id,
+            contract_month: format!("2025{:02}", contract_id),
+            ratio,
+        };
+
+        assert!(SpreadContract::new("ES", "CME", "USD", vec![leg(3, 1)]).is_err());
+        assert!(SpreadContract::new("ES", "CME", "USD", vec![leg(3, 1), leg(6, 0)]).is_err());
+        assert!(SpreadContract::new("ES", "CME", "USD", vec![leg(3, 1), leg(6, 2)]).is_err());
+        assert!(SpreadContract::new("ES", "CME", "USD", vec![leg(3, 1), leg(3, -1)]).is_err());
+        assert!(SpreadContract::new("ES", "CME", "USD", vec![leg(0, 1), leg(6, -1)]).is_err());
+        assert!(SpreadContract::new("ES", "CME", "USD", vec![leg(3, 2), leg(6, -1)]).is_ok());
+    }
+}
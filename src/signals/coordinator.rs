@@ -4,18 +4,25 @@
 //! in momentum.rs. Provides centralized signal weighting and combination following
 //! Carver's systematic trading framework.
 
-use super::core::{CombinedSignals, SignalCore, SignalGenerator, SignalType, SignalWeights};
+use super::core::{
+    CombinedSignals, SignalAttribution, SignalContribution, SignalCore, SignalFilter,
+    SignalGenerator, SignalType, SignalWeights,
+};
 use super::utils::SignalUtils;
 use crate::market_data::{MarketDataHandler, TimeFrame};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+/// Slack on the consensus threshold so e.g. two of three signals (0.666...)
+/// meet a threshold written as 2/3
+const CONSENSUS_TOLERANCE: f64 = 1e-9;
+
 /// Configuration for signal coordination
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CoordinatorConfig {
     pub signal_weights: SignalWeights,
-    pub consensus_threshold: f64, // Minimum consensus for signal boosting
-    pub quality_filter_threshold: f64, // Minimum quality for signal inclusion
+    pub consensus_threshold: f64, // Minimum agreement for boosting and for trading
+    pub quality_filter_threshold: f64, // Minimum strength for a signal, and the composite, to count
     pub enable_cross_validation: bool, // Whether to validate signals against each other
 }
 
@@ -23,7 +30,7 @@ impl Default for CoordinatorConfig {
     fn default() -> Self {
         Self {
             signal_weights: SignalWeights::default(),
-            consensus_threshold: 0.67,
+            consensus_threshold: 2.0 / 3.0,
             quality_filter_threshold: 1.0,
             enable_cross_validation: true,
         }
@@ -75,43 +82,89 @@ impl SignalCoordinator {
         carry_signal: Option<SignalCore>,
         mean_reversion_signal: Option<SignalCore>,
     ) -> CombinedSignals {
+        let signals: Vec<(SignalType, SignalCore)> = [
+            (SignalType::Momentum, momentum_signal),
+            (SignalType::Breakout, breakout_signal),
+            (SignalType::Carry, carry_signal),
+            (SignalType::MeanReversion, mean_reversion_signal),
+        ]
+        .into_iter()
+        .filter_map(|(signal_type, signal)| signal.map(|s| (signal_type, s)))
+        .collect();
+
+        self.combine_signals_from(&signals)
+    }
+
+    /// Combine an arbitrary list of signals into unified signal
+    ///
+    /// Each signal is weighted by the configured `SignalWeights` entry for its
+    /// `SignalType`. Core types populate the matching `CombinedSignals` field;
+    /// any other signals are collected in `additional`.
+    ///
+    /// The result's `filter` records whether it may be traded: the composite
+    /// must reach `quality_filter_threshold` in absolute strength and the
+    /// agreement score must reach `consensus_threshold`.
+    ///
+    /// # Arguments
+    /// * `signals` - Signal cores tagged with the type used for weighting
+    ///
+    /// # Returns
+    /// * `CombinedSignals` - Unified signal combination
+    pub fn combine_signals_from(&self, signals: &[(SignalType, SignalCore)]) -> CombinedSignals {
         // Filter signals based on quality threshold
-        let momentum = self.filter_signal_quality(momentum_signal);
-        let breakout = self.filter_signal_quality(breakout_signal);
-        let carry = self.filter_signal_quality(carry_signal);
-        let mean_reversion = self.filter_signal_quality(mean_reversion_signal);
+        let active: Vec<&(SignalType, SignalCore)> = signals
+            .iter()
+            .filter(|(_, s)| s.signal_strength.abs() >= self.config.quality_filter_threshold)
+            .collect();
 
         // Calculate weighted composite signal
-        let composite_strength =
-            self.calculate_weighted_composite(&momentum, &breakout, &carry, &mean_reversion);
+        let composite_strength = self.calculate_weighted_composite(&active);
 
         // Determine dominant signal type
-        let dominant_signal =
-            self.find_dominant_signal(&momentum, &breakout, &carry, &mean_reversion);
+        let dominant_signal = self.find_dominant_signal(&active);
 
         // Calculate cross-signal agreement
         let agreement_score = if self.config.enable_cross_validation {
-            self.calculate_agreement_score(&momentum, &breakout, &carry, &mean_reversion)
+            self.calculate_agreement_score(&active)
         } else {
             1.0 // No cross-validation, assume perfect agreement
         };
 
         // Apply consensus boost if agreement is strong
-        let final_composite = if agreement_score >= self.config.consensus_threshold {
+        let final_composite = if self.meets_consensus(agreement_score) {
             SignalUtils::apply_consensus_boost(composite_strength, agreement_score)
         } else {
             composite_strength
         };
 
-        CombinedSignals {
-            momentum,
-            breakout,
-            carry,
-            mean_reversion,
-            composite_strength: SignalUtils::clamp_to_carver_range(final_composite),
-            dominant_signal,
-            agreement_score,
+        let composite = SignalUtils::clamp_to_carver_range(final_composite);
+        let attribution = self.attribute(&active, composite_strength, composite);
+        let has_signals = !active.is_empty();
+        let mut combined = CombinedSignals::empty();
+        for (signal_type, signal) in active {
+            let slot = match signal_type {
+                SignalType::Momentum => &mut combined.momentum,
+                SignalType::Breakout => &mut combined.breakout,
+                SignalType::Carry => &mut combined.carry,
+                SignalType::MeanReversion => &mut combined.mean_reversion,
+                SignalType::Custom(_) => {
+                    combined.additional.push(signal.clone());
+                    continue;
+                }
+            };
+            if slot.is_none() {
+                *slot = Some(signal.clone());
+            } else {
+                combined.additional.push(signal.clone());
+            }
         }
+
+        combined.composite_strength = composite;
+        combined.attribution = attribution;
+        combined.dominant_signal = dominant_signal;
+        combined.agreement_score = agreement_score;
+        combined.filter = self.filter_decision(&combined, has_signals);
+        combined
     }
 
     /// Calculate signals for all generators and combine them
@@ -185,45 +238,39 @@ impl SignalCoordinator {
 
     // Private helper methods
 
-    /// Filter signal based on quality threshold
-    fn filter_signal_quality(&self, signal: Option<SignalCore>) -> Option<SignalCore> {
-        signal.filter(|s| s.signal_strength.abs() >= self.config.quality_filter_threshold)
+    /// Decide whether a combination clears the quality and consensus thresholds
+    fn meets_consensus(&self, agreement_score: f64) -> bool {
+        agreement_score + CONSENSUS_TOLERANCE >= self.config.consensus_threshold
+    }
+
+    fn filter_decision(&self, combined: &CombinedSignals, has_signals: bool) -> SignalFilter {
+        if !has_signals {
+            SignalFilter::NoSignals
+        } else if combined.composite_strength.abs() < self.config.quality_filter_threshold {
+            SignalFilter::BelowQuality {
+                strength: combined.composite_strength,
+                threshold: self.config.quality_filter_threshold,
+            }
+        } else if !self.meets_consensus(combined.agreement_score) {
+            SignalFilter::InsufficientConsensus {
+                agreement: combined.agreement_score,
+                threshold: self.config.consensus_threshold,
+            }
+        } else {
+            SignalFilter::Passed
+        }
     }
 
     /// Calculate weighted composite signal strength
-    fn calculate_weighted_composite(
-        &self,
-        momentum: &Option<SignalCore>,
-        breakout: &Option<SignalCore>,
-        carry: &Option<SignalCore>,
-        mean_reversion: &Option<SignalCore>,
-    ) -> f64 {
+    fn calculate_weighted_composite(&self, signals: &[&(SignalType, SignalCore)]) -> f64 {
         let weights = &self.config.signal_weights;
         let mut weighted_sum = 0.0;
         let mut total_weight = 0.0;
 
-        // Add momentum component
-        if let Some(signal) = momentum {
-            weighted_sum += signal.signal_strength * weights.momentum;
-            total_weight += weights.momentum;
-        }
-
-        // Add breakout component
-        if let Some(signal) = breakout {
-            weighted_sum += signal.signal_strength * weights.breakout;
-            total_weight += weights.breakout;
-        }
-
-        // Add carry component
-        if let Some(signal) = carry {
-            weighted_sum += signal.signal_strength * weights.carry;
-            total_weight += weights.carry;
-        }
-
-        // Add mean reversion component
-        if let Some(signal) = mean_reversion {
-            weighted_sum += signal.signal_strength * weights.mean_reversion;
-            total_weight += weights.mean_reversion;
+        for (signal_type, signal) in signals {
+            let weight = weights.weight_for(signal_type);
+            weighted_sum += signal.signal_strength * weight;
+            total_weight += weight;
         }
 
         // Normalize by actual weight used (in case some signals are missing)
@@ -234,49 +281,63 @@ impl SignalCoordinator {
         }
     }
 
-    /// Find the signal type with strongest absolute strength
-    fn find_dominant_signal(
+    /// Split a composite into each signal's weighted contribution
+    ///
+    /// `weighted` is the plain weighted average and `composite` the final
+    /// strength after the consensus boost and clamping; the ratio between them
+    /// scales every contribution so they sum to `composite`.
+    fn attribute(
         &self,
-        momentum: &Option<SignalCore>,
-        breakout: &Option<SignalCore>,
-        carry: &Option<SignalCore>,
-        mean_reversion: &Option<SignalCore>,
-    ) -> Option<SignalType> {
-        let mut max_strength = 0.0;
-        let mut dominant_type = None;
+        signals: &[&(SignalType, SignalCore)],
+        weighted: f64,
+        composite: f64,
+    ) -> SignalAttribution {
+        let weights = &self.config.signal_weights;
+        let total_weight: f64 = signals
+            .iter()
+            .map(|(signal_type, _)| weights.weight_for(signal_type))
+            .sum();
+        let adjustment = if weighted != 0.0 {
+            composite / weighted
+        } else {
+            1.0
+        };
 
-        // Check momentum
-        if let Some(signal) = momentum {
-            let abs_strength = signal.signal_strength.abs();
-            if abs_strength > max_strength {
-                max_strength = abs_strength;
-                dominant_type = Some(SignalType::Momentum);
-            }
-        }
+        let contributions = signals
+            .iter()
+            .map(|(signal_type, signal)| {
+                let weight = if total_weight > 0.0 {
+                    weights.weight_for(signal_type) / total_weight
+                } else {
+                    0.0
+                };
+                SignalContribution {
+                    signal_type: signal_type.clone(),
+                    strength: signal.signal_strength,
+                    weight,
+                    contribution: signal.signal_strength * weight * adjustment,
+                }
+            })
+            .collect();
 
-        // Check breakout
-        if let Some(signal) = breakout {
-            let abs_strength = signal.signal_strength.abs();
-            if abs_strength > max_strength {
-                max_strength = abs_strength;
-                dominant_type = Some(SignalType::Breakout);
-            }
+        SignalAttribution {
+            contributions,
+            adjustment,
         }
+    }
 
-        // Check carry
-        if let Some(signal) = carry {
-            let abs_strength = signal.signal_strength.abs();
-            if abs_strength > max_strength {
-                max_strength = abs_strength;
-                dominant_type = Some(SignalType::Carry);
-            }
-        }
+    /// Find the signal type with strongest absolute strength
+    ///
+    /// Ties resolve to the earliest signal in the list.
+    fn find_dominant_signal(&self, signals: &[&(SignalType, SignalCore)]) -> Option<SignalType> {
+        let mut max_strength = 0.0;
+        let mut dominant_type = None;
 
-        // Check mean reversion
-        if let Some(signal) = mean_reversion {
+        for (signal_type, signal) in signals {
             let abs_strength = signal.signal_strength.abs();
             if abs_strength > max_strength {
-                dominant_type = Some(SignalType::MeanReversion);
+                max_strength = abs_strength;
+                dominant_type = Some(signal_type.clone());
             }
         }
 
@@ -284,17 +345,10 @@ impl SignalCoordinator {
     }
 
     /// Calculate agreement score across all active signals
-    fn calculate_agreement_score(
-        &self,
-        momentum: &Option<SignalCore>,
-        breakout: &Option<SignalCore>,
-        carry: &Option<SignalCore>,
-        mean_reversion: &Option<SignalCore>,
-    ) -> f64 {
-        let signal_strengths: Vec<f64> = [momentum, breakout, carry, mean_reversion]
+    fn calculate_agreement_score(&self, signals: &[&(SignalType, SignalCore)]) -> f64 {
+        let signal_strengths: Vec<f64> = signals
             .iter()
-            .filter_map(|opt| opt.as_ref())
-            .map(|signal| signal.signal_strength)
+            .map(|(_, signal)| signal.signal_strength)
             .collect();
 
         if signal_strengths.len() < 2 {
@@ -382,7 +436,7 @@ impl Default for CoordinatorBuilder {
 mod tests {
     use super::*;
     use crate::market_data::TimeFrame;
-    use crate::signals::core::{SignalQuality, SignalType};
+    use crate::signals::core::{SignalFilter, SignalQuality, SignalType};
 
     fn create_test_signal(strength: f64, signal_type: SignalType) -> SignalCore {
         SignalCore::new(
@@ -430,6 +484,88 @@ mod tests {
         assert_eq!(combined.dominant_signal, Some(SignalType::Breakout));
     }
 
+    #[test]
+    fn test_low_quality_composite_is_filtered() {
+        let coordinator = CoordinatorBuilder::new()
+            .with_quality_threshold(3.0)
+            .build()
+            .unwrap();
+
+        // Each signal clears the per-signal threshold but they cancel out
+        let combined = coordinator.combine_signals(
+            Some(create_test_signal(4.0, SignalType::Momentum)),
+            Some(create_test_signal(-4.0, SignalType::Breakout)),
+            None,
+            None,
+        );
+
+        assert!(!combined.is_tradeable());
+        assert!(matches!(
+            combined.filter,
+            SignalFilter::BelowQuality { threshold, .. } if threshold == 3.0
+        ));
+        assert!(
+            combined
+                .filter
+                .reason()
+                .unwrap()
+                .contains("quality threshold")
+        );
+
+        let nothing = coordinator.combine_signals(
+            Some(create_test_signal(1.0, SignalType::Momentum)),
+            None,
+            None,
+            None,
+        );
+        assert_eq!(nothing.filter, SignalFilter::NoSignals);
+    }
+
+    #[test]
+    fn test_weak_consensus_is_filtered() {
+        let coordinator = SignalCoordinator::new();
+
+        // Two of four agree: short of the two-thirds default
+        let combined = coordinator.combine_signals(
+            Some(create_test_signal(12.0, SignalType::Momentum)),
+            Some(create_test_signal(8.0, SignalType::Breakout)),
+            Some(create_test_signal(-2.0, SignalType::Carry)),
+            Some(create_test_signal(-3.0, SignalType::MeanReversion)),
+        );
+
+        assert!(combined.composite_strength.abs() >= 1.0);
+        assert!(matches!(
+            combined.filter,
+            SignalFilter::InsufficientConsensus { .. }
+        ));
+        assert!(combined.filter.reason().unwrap().contains("consensus"));
+
+        // Two of three meet it exactly
+        let combined = coordinator.combine_signals(
+            Some(create_test_signal(12.0, SignalType::Momentum)),
+            Some(create_test_signal(8.0, SignalType::Breakout)),
+            Some(create_test_signal(-2.0, SignalType::Carry)),
+            None,
+        );
+        assert_eq!(combined.filter, SignalFilter::Passed);
+    }
+
+    #[test]
+    fn test_high_quality_signal_passes() {
+        let coordinator = SignalCoordinator::new();
+
+        let combined = coordinator.combine_signals(
+            Some(create_test_signal(10.0, SignalType::Momentum)),
+            Some(create_test_signal(6.0, SignalType::Breakout)),
+            None,
+            None,
+        );
+
+        assert!(combined.is_tradeable());
+        assert_eq!(combined.filter, SignalFilter::Passed);
+        assert_eq!(combined.filter.reason(), None);
+    }
+
     #[test]
     fn test_consensus_boost() {
         let coordinator = SignalCoordinator::new();
@@ -454,6 +590,7 @@ mod tests {
             breakout: 0.3,
             carry: 0.1,
             mean_reversion: 0.0,
+            ..SignalWeights::default()
         };
 
         let coordinator = CoordinatorBuilder::new()
@@ -467,4 +604,173 @@ mod tests {
         assert_eq!(coordinator.config().consensus_threshold, 0.8);
         assert_eq!(coordinator.config().quality_filter_threshold, 2.0);
     }
+
+    #[test]
+    fn test_combine_signals_from_matches_fixed_arguments() {
+        let coordinator = SignalCoordinator::new();
+
+        let momentum = create_test_signal(10.0, SignalType::Momentum);
+        let breakout = create_test_signal(-3.0, SignalType::Breakout);
+        let mean_reversion = create_test_signal(4.0, SignalType::MeanReversion);
+
+        let fixed = coordinator.combine_signals(
+            Some(momentum.clone()),
+            Some(breakout.clone()),
+            None,
+            Some(mean_reversion.clone()),
+        );
+        let from_list = coordinator.combine_signals_from(&[
+            (SignalType::Momentum, momentum),
+            (SignalType::Breakout, breakout),
+            (SignalType::MeanReversion, mean_reversion),
+        ]);
+
+        assert!((fixed.composite_strength - from_list.composite_strength).abs() < 1e-12);
+        assert!((fixed.agreement_score - from_list.agreement_score).abs() < 1e-12);
+        assert_eq!(fixed.dominant_signal, from_list.dominant_signal);
+        assert!(from_list.momentum.is_some());
+        assert!(from_list.breakout.is_some());
+        assert!(from_list.carry.is_none());
+        assert!(from_list.mean_reversion.is_some());
+        assert!(from_list.additional.is_empty());
+    }
+
+    #[test]
+    fn test_combine_signals_from_equal_strength_tie() {
+        let coordinator = SignalCoordinator::new();
+
+        let momentum = create_test_signal(6.0, SignalType::Momentum);
+        let carry = create_test_signal(6.0, SignalType::Carry);
+
+        let fixed =
+            coordinator.combine_signals(Some(momentum.clone()), None, Some(carry.clone()), None);
+        let from_list = coordinator
+            .combine_signals_from(&[(SignalType::Momentum, momentum), (SignalType::Carry, carry)]);
+
+        assert_eq!(fixed.dominant_signal, Some(SignalType::Momentum));
+        assert_eq!(from_list.dominant_signal, Some(SignalType::Momentum));
+        assert!((fixed.composite_strength - from_list.composite_strength).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_combine_signals_from_custom_type() {
+        let rsi = SignalType::Custom("RSI".to_string());
+        let mut weights = SignalWeights {
+            momentum: 0.5,
+            breakout: 0.0,
+            carry: 0.0,
+            mean_reversion: 0.0,
+            ..SignalWeights::default()
+        };
+        weights.set_weight(rsi.clone(), 0.5);
+        assert!(weights.validate().is_ok());
+
+        let coordinator = CoordinatorBuilder::new()
+            .with_weights(weights)
+            .with_cross_validation(false)
+            .build()
+            .unwrap();
+
+        let combined = coordinator.combine_signals_from(&[
+            (
+                SignalType::Momentum,
+                create_test_signal(4.0, SignalType::Momentum),
+            ),
+            (rsi.clone(), create_test_signal(-8.0, rsi.clone())),
+        ]);
+
+        assert_eq!(combined.additional.len(), 1);
+        assert_eq!(combined.dominant_signal, Some(rsi));
+        assert!(combined.composite_strength < 0.0);
+        assert_eq!(combined.max_signal_strength(), 8.0);
+    }
+
+    #[test]
+    fn test_signal_weights_normalize_includes_custom() {
+        let mut weights = SignalWeights::default();
+        weights.set_weight(SignalType::Custom("MACD".to_string()), 1.0);
+        assert!(weights.validate().is_err());
+
+        weights.normalize();
+        assert!(weights.validate().is_ok());
+        assert!((weights.weight_for(&SignalType::Custom("MACD".to_string())) - 0.5).abs() < 1e-12);
+        assert!((weights.weight_for(&SignalType::Momentum) - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_signal_weights_json_round_trip_with_custom() {
+        let mut weights = SignalWeights::default();
+        weights.set_weight(SignalType::Custom("MACD".to_string()), 0.2);
+        weights.normalize();
+
+        let json = serde_json::to_string(&weights).unwrap();
+        assert!(json.contains(r#""additional":{"MACD":"#));
+        let parsed: SignalWeights = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            parsed.weight_for(&SignalType::Custom("MACD".to_string())),
+            weights.weight_for(&SignalType::Custom("MACD".to_string()))
+        );
+        assert!(parsed.validate().is_ok());
+    }
+
+    #[test]
+    fn test_attribution_sums_to_boosted_composite() {
+        let coordinator = SignalCoordinator::new();
+
+        let combined = coordinator.combine_signals(
+            Some(create_test_signal(8.0, SignalType::Momentum)),
+            Some(create_test_signal(6.0, SignalType::Breakout)),
+            Some(create_test_signal(4.0, SignalType::Carry)),
+            None,
+        );
+
+        let attribution = &combined.attribution;
+        assert_eq!(attribution.contributions.len(), 3);
+        assert!((attribution.total() - combined.composite_strength).abs() < 1e-9);
+        // Agreeing signals get the consensus boost
+        assert!((attribution.adjustment - 1.25).abs() < 1e-9);
+        assert!(attribution.contributions.iter().all(|c| c.sign() == 1));
+    }
+
+    #[test]
+    fn test_attribution_respects_weights() {
+        let weights = SignalWeights {
+            momentum: 0.75,
+            breakout: 0.25,
+            carry: 0.0,
+            mean_reversion: 0.0,
+            ..SignalWeights::default()
+        };
+        let coordinator = CoordinatorBuilder::new()
+            .with_weights(weights)
+            .with_consensus_threshold(1.0)
+            .build()
+            .unwrap();
+
+        // Disagreeing signals: no boost, so contributions are weight x strength
+        let combined = coordinator.combine_signals(
+            Some(create_test_signal(8.0, SignalType::Momentum)),
+            Some(create_test_signal(-4.0, SignalType::Breakout)),
+            None,
+            None,
+        );
+
+        let attribution = &combined.attribution;
+        let momentum = attribution.get(&SignalType::Momentum).unwrap();
+        let breakout = attribution.get(&SignalType::Breakout).unwrap();
+        assert_eq!(momentum.weight, 0.75);
+        assert_eq!(breakout.weight, 0.25);
+        assert!((momentum.contribution - 6.0).abs() < 1e-9);
+        assert!((breakout.contribution + 1.0).abs() < 1e-9);
+        assert_eq!(breakout.sign(), -1);
+        assert!((combined.composite_strength - 5.0).abs() < 1e-9);
+        assert!((attribution.total() - combined.composite_strength).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_attribution_empty_without_signals() {
+        let combined = SignalCoordinator::new().combine_signals(None, None, None, None);
+        assert!(combined.attribution.contributions.is_empty());
+        assert_eq!(combined.attribution.total(), 0.0);
+    }
 }
@@ -17,12 +17,13 @@ pub enum SignalQuality {
 }
 
 /// Signal type classification for combination weighting
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum SignalType {
-    Momentum,      // Trend-following signals
-    Breakout,      // Price breakout signals
-    Carry,         // Interest rate differential signals
-    MeanReversion, // Bollinger, RSI signals
+    Momentum,       // Trend-following signals
+    Breakout,       // Price breakout signals
+    Carry,          // Interest rate differential signals
+    MeanReversion,  // Bollinger, RSI signals
+    Custom(String), // Additional signal families (e.g. "RSI", "MACD")
 }
 
 /// Core signal data shared by all signal types
@@ -123,6 +124,95 @@ where
     }
 }
 
+/// Whether the coordinator cleared a combined signal for trading
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub enum SignalFilter {
+    #[default]
+    Passed,
+    NoSignals,
+    BelowQuality {
+        strength: f64,
+        threshold: f64,
+    },
+    InsufficientConsensus {
+        agreement: f64,
+        threshold: f64,
+    },
+}
+
+impl SignalFilter {
+    pub fn passed(&self) -> bool {
+        *self == SignalFilter::Passed
+    }
+
+    /// Why the signal was filtered, or `None` if it passed
+    pub fn reason(&self) -> Option<String> {
+        match self {
+            SignalFilter::Passed => None,
+            SignalFilter::NoSignals => Some("no signal passed the quality filter".to_string()),
+            SignalFilter::BelowQuality {
+                strength,
+                threshold,
+            } => Some(format!(
+                "composite strength {:.4} below quality threshold {:.4}",
+                strength, threshold
+            )),
+            SignalFilter::InsufficientConsensus {
+                agreement,
+                threshold,
+            } => Some(format!(
+                "agreement {:.2} below consensus threshold {:.2}",
+                agreement, threshold
+            )),
+        }
+    }
+}
+
+/// One signal's weighted share of a composite
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignalContribution {
+    pub signal_type: SignalType,
+    pub strength: f64,     // The signal's own strength
+    pub weight: f64,       // Weight normalized over the signals that passed the quality filter
+    pub contribution: f64, // Signed share of the composite strength
+}
+
+impl SignalContribution {
+    /// 1 if this signal pushes the composite long, -1 if short, 0 if neither
+    pub fn sign(&self) -> i8 {
+        if self.contribution > 0.0 {
+            1
+        } else if self.contribution < 0.0 {
+            -1
+        } else {
+            0
+        }
+    }
+}
+
+/// How much each signal drove a composite
+///
+/// Contributions include the consensus boost and clamping, so they sum to the
+/// composite strength.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SignalAttribution {
+    pub contributions: Vec<SignalContribution>,
+    pub adjustment: f64, // Composite over the plain weighted average (boost and clamping)
+}
+
+impl SignalAttribution {
+    /// Sum of the contributions, equal to the composite strength
+    pub fn total(&self) -> f64 {
+        self.contributions.iter().map(|c| c.contribution).sum()
+    }
+
+    pub fn get(&self, signal_type: &SignalType) -> Option<&SignalContribution> {
+        self.contributions
+            .iter()
+            .find(|c| &c.signal_type == signal_type)
+    }
+}
+
 /// Combined signals from all signal generators
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CombinedSignals {
@@ -130,9 +220,15 @@ pub struct CombinedSignals {
     pub breakout: Option<SignalCore>,
     pub carry: Option<SignalCore>,
     pub mean_reversion: Option<SignalCore>,
+    #[serde(default)]
+    pub additional: Vec<SignalCore>, // Signals beyond the four core types
     pub composite_strength: f64, // Final combined signal strength
     pub dominant_signal: Option<SignalType>, // Strongest contributing signal type
     pub agreement_score: f64,    // Cross-signal agreement level
+    #[serde(default)]
+    pub filter: SignalFilter, // Whether the combination may be traded
+    #[serde(default)]
+    pub attribution: SignalAttribution, // Per-signal contributions to the composite
 }
 
 impl CombinedSignals {
@@ -143,38 +239,44 @@ impl CombinedSignals {
             breakout: None,
             carry: None,
             mean_reversion: None,
+            additional: Vec::new(),
             composite_strength: 0.0,
             dominant_signal: None,
             agreement_score: 0.0,
+            filter: SignalFilter::NoSignals,
+            attribution: SignalAttribution::default(),
         }
     }
 
-    /// Check if any signals are actionable
-    pub fn has_actionable_signals(&self) -> bool {
+    /// Iterate over every signal present, core types first
+    pub fn all_signals(&self) -> impl Iterator<Item = &SignalCore> {
         [
             &self.momentum,
             &self.breakout,
             &self.carry,
             &self.mean_reversion,
         ]
-        .iter()
+        .into_iter()
         .filter_map(|opt| opt.as_ref())
-        .any(|signal| signal.is_actionable())
+        .chain(self.additional.iter())
+    }
+
+    /// Whether the coordinator's quality and consensus filters passed
+    pub fn is_tradeable(&self) -> bool {
+        self.filter.passed()
+    }
+
+    /// Check if any signals are actionable
+    pub fn has_actionable_signals(&self) -> bool {
+        self.all_signals().any(|signal| signal.is_actionable())
     }
 
     /// Get strongest signal strength
     pub fn max_signal_strength(&self) -> f64 {
-        [
-            &self.momentum,
-            &self.breakout,
-            &self.carry,
-            &self.mean_reversion,
-        ]
-        .iter()
-        .filter_map(|opt| opt.as_ref())
-        .map(|signal| signal.signal_strength.abs())
-        .max_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap_or(0.0)
+        self.all_signals()
+            .map(|signal| signal.signal_strength.abs())
+            .max_by(|a, b| a.partial_cmp(b).unwrap())
+            .unwrap_or(0.0)
     }
 }
 
@@ -242,6 +344,8 @@ pub struct SignalWeights {
     pub breakout: f64,
     pub carry: f64,
     pub mean_reversion: f64,
+    #[serde(default)]
+    pub additional: HashMap<String, f64>, // Weights for SignalType::Custom signals, by name
 }
 
 impl Default for SignalWeights {
@@ -251,14 +355,48 @@ impl Default for SignalWeights {
             breakout: 0.3,        // Secondary confirmation
             carry: 0.15,          // Fundamental factor
             mean_reversion: 0.05, // Counter-trend filter
+            additional: HashMap::new(),
         }
     }
 }
 
 impl SignalWeights {
+    /// Get the configured weight for a signal type (0.0 if unweighted)
+    pub fn weight_for(&self, signal_type: &SignalType) -> f64 {
+        match signal_type {
+            SignalType::Momentum => self.momentum,
+            SignalType::Breakout => self.breakout,
+            SignalType::Carry => self.carry,
+            SignalType::MeanReversion => self.mean_reversion,
+            SignalType::Custom(name) => self.additional.get(name).copied().unwrap_or(0.0),
+        }
+    }
+
+    /// Set the weight for a signal type, storing custom types in `additional`
+    pub fn set_weight(&mut self, signal_type: SignalType, weight: f64) {
+        match signal_type {
+            SignalType::Momentum => self.momentum = weight,
+            SignalType::Breakout => self.breakout = weight,
+            SignalType::Carry => self.carry = weight,
+            SignalType::MeanReversion => self.mean_reversion = weight,
+            SignalType::Custom(name) => {
+                self.additional.insert(name, weight);
+            }
+        }
+    }
+
+    /// Sum of all configured weights
+    pub fn total(&self) -> f64 {
+        self.momentum
+            + self.breakout
+            + self.carry
+            + self.mean_reversion
+            + self.additional.values().sum::<f64>()
+    }
+
     /// Validate weights sum to 1.0
     pub fn validate(&self) -> Result<()> {
-        let total = self.momentum + self.breakout + self.carry + self.mean_reversion;
+        let total = self.total();
         if (total - 1.0).abs() > 0.001 {
             return Err(anyhow::anyhow!(
                 "Signal weights must sum to 1.0, got: {:.3}",
@@ -270,12 +408,15 @@ impl SignalWeights {
 
     /// Normalize weights to sum to 1.0
     pub fn normalize(&mut self) {
-        let total = self.momentum + self.breakout + self.carry + self.mean_reversion;
+        let total = self.total();
         if total > 0.0 {
             self.momentum /= total;
             self.breakout /= total;
             self.carry /= total;
             self.mean_reversion /= total;
+            for weight in self.additional.values_mut() {
+                *weight /= total;
+            }
         }
     }
 }
@@ -22,6 +22,7 @@ impl SignalExample {
             breakout: 0.3,
             carry: 0.25,
             mean_reversion: 0.05,
+            ..SignalWeights::default()
         };
 
         let coordinator = CoordinatorBuilder::new()
@@ -143,6 +144,7 @@ impl SignalExample {
                 breakout: 0.25,       // Secondary confirmation
                 carry: 0.1,           // Minor fundamental factor
                 mean_reversion: 0.05, // Counter-trend filter
+                ..SignalWeights::default()
             })
             .with_consensus_threshold(0.8) // Require strong agreement
             .with_quality_threshold(5.0) // Only high-quality signals
@@ -170,6 +172,7 @@ impl SignalExample {
                 breakout: 0.4, // Equal weight trend signals
                 carry: 0.15,
                 mean_reversion: 0.05,
+                ..SignalWeights::default()
             })
             .with_consensus_threshold(0.5) // Lower consensus requirement
             .with_quality_threshold(1.0) // Accept weaker signals
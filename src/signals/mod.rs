@@ -15,7 +15,9 @@ pub mod utils;
 // Re-export core types for easy access
 pub use carry::CarrySignalGenerator;
 pub use coordinator::{CoordinatorBuilder, CoordinatorConfig, SignalCoordinator};
-pub use core::{SignalCore, SignalGenerator, SignalQuality, SignalType, SignalWeights};
+pub use core::{
+    SignalAttribution, SignalCore, SignalGenerator, SignalQuality, SignalType, SignalWeights,
+};
 
 // Note: Other signal generators and utilities are available but not re-exported to reduce warnings
 // Use directly from their modules when needed:
@@ -9,6 +9,7 @@ use crate::market_data::{
 use crate::signals::core::{SignalCore, SignalGenerator, SignalQuality, SignalType};
 use crate::signals::utils::SignalUtils;
 use anyhow::Result;
+use std::collections::HashMap;
 
 /// Momentum signal structure containing all momentum-related data
 #[derive(Debug, Clone)]
@@ -36,19 +37,35 @@ pub struct MomentumMetrics {
 #[derive(Debug, Clone)]
 pub struct MomentumSignalGenerator {
     lookback_period: usize,
+    timeframe_lookbacks: HashMap<TimeFrame, usize>,
 }
 
 impl MomentumSignalGenerator {
     /// Create new momentum signal generator with default settings
     pub fn new() -> Self {
-        Self {
-            lookback_period: 20, // Default 20-day momentum
-        }
+        Self::with_lookback(20) // Default 20-day momentum
     }
 
     /// Create momentum signal generator with custom lookback period
     pub fn with_lookback(lookback_period: usize) -> Self {
-        Self { lookback_period }
+        Self {
+            lookback_period,
+            timeframe_lookbacks: HashMap::new(),
+        }
+    }
+
+    /// Override the lookback for individual timeframes
+    pub fn with_timeframe_lookbacks(mut self, lookbacks: HashMap<TimeFrame, usize>) -> Self {
+        self.timeframe_lookbacks = lookbacks;
+        self
+    }
+
+    /// Lookback for `timeframe`, falling back to the scalar lookback
+    pub fn lookback_for(&self, timeframe: TimeFrame) -> usize {
+        self.timeframe_lookbacks
+            .get(&timeframe)
+            .copied()
+            .unwrap_or(self.lookback_period)
     }
 }
 
@@ -63,15 +80,18 @@ impl SignalGenerator for MomentumSignalGenerator {
         market_data: &MarketDataHandler,
     ) -> Result<Option<Self::Signal>> {
         // Calculate basic momentum using market data handler
-        let simple_momentum = market_data.calculate_momentum(symbol, self.lookback_period);
+        let lookback = self.lookback_for(timeframe);
+        let simple_momentum = market_data.calculate_momentum(symbol, lookback);
 
         if let Some(momentum) = simple_momentum {
             // Get enhanced metrics if available
-            let enhanced_metrics =
-                market_data.calculate_enhanced_momentum(symbol, self.lookback_period);
+            let enhanced_metrics = market_data.calculate_enhanced_momentum(symbol, lookback);
 
             // Get multi-timeframe data if available
-            let multi_timeframe = market_data.calculate_multi_timeframe_momentum(symbol);
+            let multi_timeframe = market_data.calculate_multi_timeframe_momentum_with_lookbacks(
+                symbol,
+                &self.timeframe_lookbacks,
+            );
 
             // Calculate signal strength using enhanced metrics or simple momentum
             let signal_strength = if let Some(ref enhanced) = enhanced_metrics {
@@ -99,7 +119,8 @@ impl SignalGenerator for MomentumSignalGenerator {
         market_data: &MarketDataHandler,
     ) -> Result<Option<Self::Metrics>> {
         // Get multi-timeframe momentum data
-        let multi_timeframe = market_data.calculate_multi_timeframe_momentum(symbol);
+        let multi_timeframe = market_data
+            .calculate_multi_timeframe_momentum_with_lookbacks(symbol, &self.timeframe_lookbacks);
 
         if let Some(mtf_data) = multi_timeframe {
             // Calculate signals for each timeframe
@@ -562,7 +583,9 @@ mod tests {
             momentum_acceleration: 0.05,
             volatility: 0.25,
             sharpe_ratio: 0.8,
+            annualized_sharpe: 0.8,
             timeframe: TimeFrame::Days1,
+            filtered_returns: 0,
         };
 
         let test_signal = MomentumSignal {
@@ -618,7 +641,9 @@ mod tests {
                 momentum_acceleration: 0.08,
                 volatility: 0.20,  // Moderate volatility
                 sharpe_ratio: 1.2, // High Sharpe ratio
+                annualized_sharpe: 1.2,
                 timeframe: TimeFrame::Days1,
+                filtered_returns: 0,
             }),
             multi_timeframe: None,
         };
@@ -643,7 +668,9 @@ mod tests {
                 momentum_acceleration: 0.01,
                 volatility: 0.35,  // High volatility
                 sharpe_ratio: 0.2, // Low Sharpe ratio
+                annualized_sharpe: 0.2,
                 timeframe: TimeFrame::Days1,
+                filtered_returns: 0,
             }),
             multi_timeframe: None,
         };
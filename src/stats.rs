@@ -1,5 +1,6 @@
 use anyhow::{Result, anyhow};
 use statrs::statistics::Statistics;
+use std::collections::VecDeque;
 
 /// Calculate the Sharpe ratio for a series of returns
 ///
@@ -179,6 +180,37 @@ pub fn information_ratio(returns: &[f64], benchmark_returns: &[f64]) -> Result<f
     Ok(mean_active_return / tracking_error)
 }
 
+/// OLS regression of returns on benchmark returns, as `(alpha, beta)`
+///
+/// Alpha is the per-period intercept, not annualized.
+pub fn alpha_beta(returns: &[f64], benchmark_returns: &[f64]) -> Result<(f64, f64)> {
+    if returns.len() != benchmark_returns.len() {
+        return Err(anyhow!("Returns and benchmark must have same length"));
+    }
+    if returns.len() < 2 {
+        return Err(anyhow!(
+            "Need at least two returns to regress on a benchmark"
+        ));
+    }
+
+    let mean_return = returns.mean();
+    let mean_benchmark = benchmark_returns.mean();
+    let (covariance, benchmark_variance) =
+        returns
+            .iter()
+            .zip(benchmark_returns)
+            .fold((0.0, 0.0), |(cov, var), (r, b)| {
+                let diff = b - mean_benchmark;
+                (cov + (r - mean_return) * diff, var + diff * diff)
+            });
+    if benchmark_variance == 0.0 {
+        return Err(anyhow!("Benchmark returns have zero variance"));
+    }
+
+    let beta = covariance / benchmark_variance;
+    Ok((mean_return - beta * mean_benchmark, beta))
+}
+
 /// Calculate rolling correlation between two series
 pub fn rolling_correlation(series1: &[f64], series2: &[f64], window: usize) -> Result<Vec<f64>> {
     if series1.len() != series2.len() {
@@ -222,6 +254,72 @@ pub fn rolling_correlation(series1: &[f64], series2: &[f64], window: usize) -> R
     Ok(correlations)
 }
 
+/// Mean and sample variance over the last `window` values, updated in O(1)
+///
+/// Uses Welford's algorithm, extended to drop the oldest value once the
+/// window is full.
+#[derive(Debug, Clone)]
+pub struct RollingStats {
+    window: usize,
+    values: VecDeque<f64>,
+    mean: f64,
+    m2: f64,
+}
+
+impl RollingStats {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            values: VecDeque::with_capacity(window),
+            mean: 0.0,
+            m2: 0.0,
+        }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        if self.values.len() == self.window {
+            let oldest = self.values.pop_front().unwrap();
+            let n = self.values.len() as f64;
+            if n == 0.0 {
+                self.mean = 0.0;
+                self.m2 = 0.0;
+            } else {
+                let old_mean = self.mean;
+                self.mean -= (oldest - old_mean) / n;
+                self.m2 -= (oldest - old_mean) * (oldest - self.mean);
+            }
+        }
+
+        self.values.push_back(value);
+        let n = self.values.len() as f64;
+        let delta = value - self.mean;
+        self.mean += delta / n;
+        // Guard against drift below zero from repeated removals
+        self.m2 = (self.m2 + delta * (value - self.mean)).max(0.0);
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (!self.values.is_empty()).then_some(self.mean)
+    }
+
+    /// Sample (n-1) variance; needs at least two values
+    pub fn variance(&self) -> Option<f64> {
+        (self.values.len() >= 2).then(|| self.m2 / (self.values.len() - 1) as f64)
+    }
+
+    pub fn std_dev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+}
+
 /// Portfolio statistics calculator
 pub struct PortfolioStats {
     pub total_return: f64,
@@ -285,6 +383,36 @@ mod tests {
         assert!(sharpe > 0.0);
     }
 
+    /// Deterministic pseudo-random returns in [-0.05, 0.05)
+    fn random_series(len: usize, mut seed: u64) -> Vec<f64> {
+        (0..len)
+            .map(|_| {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                ((seed >> 11) as f64 / (1u64 << 53) as f64 - 0.5) * 0.1
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rolling_stats_match_batch() {
+        let series = random_series(500, 42);
+        let window = 50;
+        let mut rolling = RollingStats::new(window);
+        assert_eq!(rolling.variance(), None);
+
+        for (i, value) in series.iter().enumerate() {
+            rolling.push(*value);
+            let batch = &series[(i + 1).saturating_sub(window)..=i];
+            assert_eq!(rolling.len(), batch.len());
+            assert!((rolling.mean().unwrap() - batch.mean()).abs() < 1e-12);
+            if batch.len() >= 2 {
+                assert!((rolling.std_dev().unwrap() - batch.std_dev()).abs() < 1e-10);
+            }
+        }
+    }
+
     #[test]
     fn test_max_drawdown() {
         let values = vec![100.0, 110.0, 95.0, 105.0, 90.0, 100.0];
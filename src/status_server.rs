@@ -0,0 +1,219 @@
+//! Read-only HTTP status endpoint for monitoring a headless bot
+//!
+//! Serves `GET /status` (portfolio stats, positions, drawdown, halt state)
+//! and `GET /positions` as JSON, plus `GET /metrics` in the Prometheus text
+//! format. Each request copies what it needs while
+//! briefly holding the shared locks, then serializes outside them so the
+//! trading loop is never held up by a slow client.
+
+use crate::config::StatusServerConfig;
+use crate::metrics::Metrics;
+use crate::portfolio::Portfolio;
+use crate::risk::RiskManager;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Shared trading state the server reads from
+#[derive(Clone)]
+pub struct StatusState {
+    pub portfolio: Arc<Mutex<Portfolio>>,
+    pub risk_manager: Arc<Mutex<RiskManager>>,
+    pub metrics: Arc<Metrics>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionSnapshot {
+    pub symbol: String,
+    pub quantity: f64,
+    pub average_cost: f64,
+    pub current_price: f64,
+    pub unrealized_pnl: f64,
+    pub realized_pnl: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PortfolioSnapshot {
+    pub total_value: f64,
+    pub cash_balance: f64,
+    pub total_unrealized_pnl: f64,
+    pub total_realized_pnl: f64,
+    pub positions_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusSnapshot {
+    pub timestamp: DateTime<Utc>,
+    pub portfolio: PortfolioSnapshot,
+    pub positions: Vec<PositionSnapshot>,
+    pub drawdown: f64,
+    pub trading_halted: bool,
+}
+
+impl StatusState {
+    async fn positions(&self) -> Vec<PositionSnapshot> {
+        let portfolio = self.portfolio.lock().await;
+        position_snapshots(&portfolio)
+    }
+
+    async fn status(&self) -> StatusSnapshot {
+        let (portfolio, positions) = {
+            let portfolio = self.portfolio.lock().await;
+            let stats = portfolio.get_stats();
+            (
+                PortfolioSnapshot {
+                    total_value: stats.total_value,
+                    cash_balance: stats.cash_balance,
+                    total_unrealized_pnl: stats.total_unrealized_pnl,
+                    total_realized_pnl: stats.total_realized_pnl,
+                    positions_count: stats.positions_count,
+                },
+                position_snapshots(&portfolio),
+            )
+        };
+        let (drawdown, trading_halted) = {
+            let risk_manager = self.risk_manager.lock().await;
+            (risk_manager.current_drawdown(), risk_manager.is_halted())
+        };
+
+        StatusSnapshot {
+            timestamp: Utc::now(),
+            portfolio,
+            positions,
+            drawdown,
+            trading_halted,
+        }
+    }
+}
+
+fn position_snapshots(portfolio: &Portfolio) -> Vec<PositionSnapshot> {
+    let mut positions: Vec<PositionSnapshot> = portfolio
+        .positions()
+        .values()
+        .map(|position| PositionSnapshot {
+            symbol: position.symbol.clone(),
+            quantity: position.quantity,
+            average_cost: position.average_cost,
+            current_price: position.current_price,
+            unrealized_pnl: position.unrealized_pnl,
+            realized_pnl: position.realized_pnl,
+        })
+        .collect();
+    positions.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    positions
+}
+
+/// Bind the configured address and serve in a background task
+///
+/// Returns the bound address (useful when configured with port 0).
+pub async fn spawn_status_server(
+    config: &StatusServerConfig,
+    state: StatusState,
+) -> Result<SocketAddr> {
+    let listener = TcpListener::bind((config.bind_address.as_str(), config.port)).await?;
+    let address = listener.local_addr()?;
+    info!("Status server listening on http://{}", address);
+
+    tokio::spawn(serve(listener, state));
+    Ok(address)
+}
+
+/// Accept connections until the listener fails
+pub async fn serve(listener: TcpListener, state: StatusState) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                let state = state.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, &state).await {
+                        debug!("Status request from {} failed: {}", peer, e);
+                    }
+                });
+            }
+            Err(e) => {
+                warn!("Status server stopped accepting connections: {}", e);
+                return;
+            }
+        }
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, state: &StatusState) -> Result<()> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+        if request.len() > MAX_REQUEST_BYTES {
+            return write_response(&mut stream, "413 Payload Too Large", "{}").await;
+        }
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line.next().unwrap_or_default();
+
+    if method != "GET" {
+        return write_response(
+            &mut stream,
+            "405 Method Not Allowed",
+            r#"{"error":"method not allowed"}"#,
+        )
+        .await;
+    }
+
+    match path {
+        "/status" => {
+            let body = serde_json::to_string(&state.status().await)?;
+            write_response(&mut stream, "200 OK", &body).await
+        }
+        "/positions" => {
+            let body = serde_json::to_string(&state.positions().await)?;
+            write_response(&mut stream, "200 OK", &body).await
+        }
+        "/metrics" => {
+            let body = state.metrics.render_prometheus();
+            write_body(&mut stream, "200 OK", PROMETHEUS_CONTENT_TYPE, &body).await
+        }
+        _ => write_response(&mut stream, "404 Not Found", r#"{"error":"not found"}"#).await,
+    }
+}
+
+async fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> Result<()> {
+    write_body(stream, status, "application/json", body).await
+}
+
+async fn write_body(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,521 @@
+//! Several strategies sharing one account
+//!
+//! Each `Strategy` sizes its signals as if it ran the whole account.
+//! `StrategyManager` scales every strategy's signals by its capital
+//! allocation and nets them per symbol, so the order path sees one signal per
+//! symbol. Account positions cannot be attributed to the strategy that opened
+//! them, so every strategy is told the account position divided by the total
+//! allocation: exact when one strategy trades a symbol, a proportional split
+//! when several do.
+
+use crate::config::{StrategyConfig, TradingConfig};
+use crate::decisions::SignalDecision;
+use crate::market_data::MarketDataHandler;
+use crate::momentum::MomentumStrategy;
+use crate::orders::OrderSignal;
+use crate::pairs::PairsStrategy;
+use crate::signals::SignalAttribution;
+use anyhow::{Context, Result, bail};
+use std::collections::HashMap;
+
+/// Slack on the total allocation for floating-point sums like 0.1 + 0.2 + 0.7
+const ALLOCATION_TOLERANCE: f64 = 1e-9;
+
+/// A signal source the trading loop can run
+pub trait Strategy: Send {
+    fn name(&self) -> &str;
+
+    /// Orders moving this strategy's positions to its targets
+    fn calculate_signals(&mut self, market_data: &MarketDataHandler) -> Vec<OrderSignal>;
+
+    fn update_position(&mut self, symbol: &str, quantity: f64);
+
+    fn get_positions(&self) -> &HashMap<String, f64>;
+
+    /// Scale new position sizes, e.g. by `RiskManager::exposure_scale`
+    fn set_exposure_scale(&mut self, _scale: f64) {}
+
+    /// Per-signal breakdown behind `symbol`'s latest signal, if tracked
+    fn signal_attribution(&self, _symbol: &str) -> Option<&SignalAttribution> {
+        None
+    }
+
+    /// Candidates the latest `calculate_signals` filtered out, with why
+    fn signal_decisions(&self) -> Vec<SignalDecision> {
+        Vec::new()
+    }
+
+    /// Take up thresholds and weights from a reloaded config
+    fn update_config(&mut self, _config: &StrategyConfig) {}
+
+    /// Groups of symbols whose signals must all trade or none of them
+    fn linked_legs(&self) -> Vec<Vec<String>> {
+        Vec::new()
+    }
+}
+
+impl Strategy for MomentumStrategy {
+    fn name(&self) -> &str {
+        "momentum"
+    }
+
+    fn calculate_signals(&mut self, market_data: &MarketDataHandler) -> Vec<OrderSignal> {
+        MomentumStrategy::calculate_signals(self, market_data)
+    }
+
+    fn update_position(&mut self, symbol: &str, quantity: f64) {
+        MomentumStrategy::update_position(self, symbol, quantity)
+    }
+
+    fn get_positions(&self) -> &HashMap<String, f64> {
+        MomentumStrategy::get_positions(self)
+    }
+
+    fn set_exposure_scale(&mut self, scale: f64) {
+        MomentumStrategy::set_exposure_scale(self, scale)
+    }
+
+    fn signal_attribution(&self, symbol: &str) -> Option<&SignalAttribution> {
+        MomentumStrategy::signal_attribution(self, symbol)
+    }
+
+    fn signal_decisions(&self) -> Vec<SignalDecision> {
+        MomentumStrategy::signal_decisions(self).to_vec()
+    }
+
+    fn update_config(&mut self, config: &StrategyConfig) {
+        MomentumStrategy::update_config(self, config.clone())
+    }
+}
+
+/// Builds a strategy from the trading config
+pub type StrategyConstructor = fn(&TradingConfig) -> Result<Box<dyn Strategy>>;
+
+/// Strategy constructors by name, the value of `config.strategy`
+///
+/// Names are matched case-insensitively. The default registry holds the
+/// built-in strategies; new ones only need a `register` call.
+pub struct StrategyRegistry {
+    constructors: HashMap<String, StrategyConstructor>,
+}
+
+impl Default for StrategyRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            constructors: HashMap::new(),
+        };
+        registry.register("momentum", build_momentum);
+        registry.register("pairs", build_pairs);
+        registry
+    }
+}
+
+impl StrategyRegistry {
+    /// Make `name` selectable, replacing any constructor registered under it
+    pub fn register(&mut self, name: &str, constructor: StrategyConstructor) {
+        self.constructors
+            .insert(name.to_ascii_lowercase(), constructor);
+    }
+
+    /// Registered names, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<_> = self.constructors.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Build the strategy registered as `name`
+    pub fn build(&self, name: &str, config: &TradingConfig) -> Result<Box<dyn Strategy>> {
+        let constructor = self
+            .constructors
+            .get(&name.to_ascii_lowercase())
+            .with_context(|| {
+                format!(
+                    "Unknown strategy {:?}; registered: {}",
+                    name,
+                    self.names().join(", ")
+                )
+            })?;
+        constructor(config)
+    }
+}
+
+fn build_momentum(config: &TradingConfig) -> Result<Box<dyn Strategy>> {
+    Ok(Box::new(MomentumStrategy::new(
+        config.strategy_config.clone(),
+    )))
+}
+
+fn build_pairs(config: &TradingConfig) -> Result<Box<dyn Strategy>> {
+    let pairs = config
+        .pairs
+        .clone()
+        .context("strategy pairs requires a pairs section")?;
+    Ok(Box::new(PairsStrategy::new(pairs)))
+}
+
+/// Build the strategy selected by `config.strategy` from the built-in registry
+pub fn build_strategy(config: &TradingConfig) -> Result<Box<dyn Strategy>> {
+    StrategyRegistry::default().build(&config.strategy, config)
+}
+
+struct AllocatedStrategy {
+    strategy: Box<dyn Strategy>,
+    allocation: f64, // Fraction of account capital
+}
+
+/// Runs several strategies on fractions of the account and nets their signals
+#[derive(Default)]
+pub struct StrategyManager {
+    strategies: Vec<AllocatedStrategy>,
+    positions: HashMap<String, f64>, // Account positions, as last synced
+}
+
+impl StrategyManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `strategy` on `allocation` of the account's capital
+    ///
+    /// Fails if the allocation is outside (0, 1] or would take the total
+    /// above 1.
+    pub fn add(&mut self, strategy: Box<dyn Strategy>, allocation: f64) -> Result<()> {
+        if !(allocation > 0.0 && allocation <= 1.0) {
+            bail!(
+                "{} allocation must be in (0, 1], got {}",
+                strategy.name(),
+                allocation
+            );
+        }
+        let total = self.total_allocation() + allocation;
+        if total > 1.0 + ALLOCATION_TOLERANCE {
+            bail!(
+                "Adding {} at {} brings total allocation to {:.4}, above 1",
+                strategy.name(),
+                allocation,
+                total
+            );
+        }
+        self.strategies.push(AllocatedStrategy {
+            strategy,
+            allocation,
+        });
+        Ok(())
+    }
+
+    pub fn total_allocation(&self) -> f64 {
+        self.strategies.iter().map(|s| s.allocation).sum()
+    }
+
+    /// `(name, allocation)` of every strategy, in the order added
+    pub fn allocations(&self) -> Vec<(&str, f64)> {
+        self.strategies
+            .iter()
+            .map(|s| (s.strategy.name(), s.allocation))
+            .collect()
+    }
+}
+
+impl Strategy for StrategyManager {
+    fn name(&self) -> &str {
+        "portfolio"
+    }
+
+    /// Every strategy's signals scaled by its allocation and netted to at
+    /// most one signal per symbol
+    fn calculate_signals(&mut self, market_data: &MarketDataHandler) -> Vec<OrderSignal> {
+        let scaled = self
+            .strategies
+            .iter_mut()
+            .flat_map(|allocated| {
+                let name = allocated.strategy.name().to_string();
+                let allocation = allocated.allocation;
+                allocated
+                    .strategy
+                    .calculate_signals(market_data)
+                    .into_iter()
+                    .map(move |signal| (name.clone(), allocation, signal))
+            })
+            .collect();
+        net_signals(scaled)
+    }
+
+    /// Record the account position and pass each strategy its share, in its
+    /// own full-account terms
+    fn update_position(&mut self, symbol: &str, quantity: f64) {
+        self.positions.insert(symbol.to_string(), quantity);
+        let total = self.total_allocation();
+        if total <= 0.0 {
+            return;
+        }
+        for allocated in &mut self.strategies {
+            allocated.strategy.update_position(symbol, quantity / total);
+        }
+    }
+
+    fn get_positions(&self) -> &HashMap<String, f64> {
+        &self.positions
+    }
+
+    fn set_exposure_scale(&mut self, scale: f64) {
+        for allocated in &mut self.strategies {
+            allocated.strategy.set_exposure_scale(scale);
+        }
+    }
+
+    fn signal_attribution(&self, symbol: &str) -> Option<&SignalAttribution> {
+        self.strategies
+            .iter()
+            .find_map(|allocated| allocated.strategy.signal_attribution(symbol))
+    }
+
+    fn signal_decisions(&self) -> Vec<SignalDecision> {
+        self.strategies
+            .iter()
+            .flat_map(|allocated| allocated.strategy.signal_decisions())
+            .collect()
+    }
+
+    fn update_config(&mut self, config: &StrategyConfig) {
+        for allocated in &mut self.strategies {
+            allocated.strategy.update_config(config);
+        }
+    }
+
+    fn linked_legs(&self) -> Vec<Vec<String>> {
+        self.strategies
+            .iter()
+            .flat_map(|allocated| allocated.strategy.linked_legs())
+            .collect()
+    }
+}
+
+fn signed_quantity(signal: &OrderSignal) -> f64 {
+    if signal.action == "SELL" {
+        -signal.quantity
+    } else {
+        signal.quantity
+    }
+}
+
+/// Scale each `(strategy, allocation, signal)` and net the results per symbol
+///
+/// The netted signal takes its price and order type from the first signal on
+/// the winning side. Quantities stay whole when every contributing signal was
+/// whole; symbols that net to nothing are dropped.
+fn net_signals(scaled: Vec<(String, f64, OrderSignal)>) -> Vec<OrderSignal> {
+    let mut order: Vec<String> = Vec::new();
+    let mut by_symbol: HashMap<String, Vec<(String, f64, OrderSignal)>> = HashMap::new();
+    for (name, allocation, signal) in scaled {
+        let entries = by_symbol.entry(signal.symbol.clone()).or_default();
+        if entries.is_empty() {
+            order.push(signal.symbol.clone());
+        }
+        entries.push((name, allocation, signal));
+    }
+
+    order
+        .into_iter()
+        .filter_map(|symbol| {
+            let entries = &by_symbol[&symbol];
+            let mut net: f64 = entries
+                .iter()
+                .map(|(_, allocation, signal)| signed_quantity(signal) * allocation)
+                .sum();
+            if entries.iter().all(|(_, _, s)| s.quantity.fract() == 0.0) {
+                net = net.round();
+            }
+            if net.abs() < f64::EPSILON {
+                return None;
+            }
+
+            let action = if net > 0.0 { "BUY" } else { "SELL" };
+            let template = entries
+                .iter()
+                .map(|(_, _, signal)| signal)
+                .find(|signal| signal.action == action)?;
+            let reason = entries
+                .iter()
+                .map(|(name, allocation, signal)| {
+                    format!("{} x{:.2}: {}", name, allocation, signal.reason)
+                })
+                .collect::<Vec<_>>()
+                .join("; ");
+
+            Some(OrderSignal {
+                quantity: net.abs(),
+                reason,
+                ..template.clone()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security_types::SecurityInfo;
+
+    /// Emits fixed signals and records the positions it is told about
+    struct FixedStrategy {
+        name: &'static str,
+        signals: Vec<(&'static str, &'static str, f64)>,
+        positions: HashMap<String, f64>,
+    }
+
+    impl FixedStrategy {
+        fn boxed(
+            name: &'static str,
+            signals: Vec<(&'static str, &'static str, f64)>,
+        ) -> Box<dyn Strategy> {
+            Box::new(Self {
+                name,
+                signals,
+                positions: HashMap::new(),
+            })
+        }
+    }
+
+    impl Strategy for FixedStrategy {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn calculate_signals(&mut self, _market_data: &MarketDataHandler) -> Vec<OrderSignal> {
+            self.signals
+                .iter()
+                .map(|(symbol, action, quantity)| OrderSignal {
+                    symbol: symbol.to_string(),
+                    action: action.to_string(),
+                    quantity: *quantity,
+                    price: 100.0,
+                    order_type: "MKT".to_string(),
+                    limit_price: None,
+                    reason: "fixed".to_string(),
+                    security_info: SecurityInfo::new_stock(
+                        symbol.to_string(),
+                        "SMART".to_string(),
+                        "USD".to_string(),
+                    ),
+                })
+                .collect()
+        }
+
+        fn update_position(&mut self, symbol: &str, quantity: f64) {
+            self.positions.insert(symbol.to_string(), quantity);
+        }
+
+        fn get_positions(&self) -> &HashMap<String, f64> {
+            &self.positions
+        }
+    }
+
+    fn quantities(signals: &[OrderSignal]) -> Vec<(&str, &str, f64)> {
+        signals
+            .iter()
+            .map(|s| (s.symbol.as_str(), s.action.as_str(), s.quantity))
+            .collect()
+    }
+
+    #[test]
+    fn test_signals_scaled_by_allocation_and_netted() {
+        let mut manager = StrategyManager::new();
+        manager
+            .add(
+                FixedStrategy::boxed("trend", vec![("AAPL", "BUY", 100.0), ("MSFT", "BUY", 50.0)]),
+                0.6,
+            )
+            .unwrap();
+        manager
+            .add(
+                FixedStrategy::boxed(
+                    "reversion",
+                    vec![("AAPL", "SELL", 100.0), ("TSLA", "SELL", 30.0)],
+                ),
+                0.4,
+            )
+            .unwrap();
+
+        let signals = manager.calculate_signals(&MarketDataHandler::new());
+
+        // AAPL: 60 bought against 40 sold nets to a 20 share buy
+        assert_eq!(
+            quantities(&signals),
+            [
+                ("AAPL", "BUY", 20.0),
+                ("MSFT", "BUY", 30.0),
+                ("TSLA", "SELL", 12.0)
+            ]
+        );
+        assert_eq!(
+            signals[0].reason,
+            "trend x0.60: fixed; reversion x0.40: fixed"
+        );
+    }
+
+    #[test]
+    fn test_offsetting_signals_cancel_out() {
+        let mut manager = StrategyManager::new();
+        manager
+            .add(FixedStrategy::boxed("a", vec![("AAPL", "BUY", 50.0)]), 0.5)
+            .unwrap();
+        manager
+            .add(FixedStrategy::boxed("b", vec![("AAPL", "SELL", 50.0)]), 0.5)
+            .unwrap();
+
+        assert!(
+            manager
+                .calculate_signals(&MarketDataHandler::new())
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_allocations_cannot_exceed_one() {
+        let mut manager = StrategyManager::new();
+        manager.add(FixedStrategy::boxed("a", vec![]), 0.7).unwrap();
+        assert!(manager.add(FixedStrategy::boxed("b", vec![]), 0.4).is_err());
+        assert!(manager.add(FixedStrategy::boxed("c", vec![]), 0.0).is_err());
+        manager.add(FixedStrategy::boxed("d", vec![]), 0.3).unwrap();
+        assert_eq!(manager.allocations(), [("a", 0.7), ("d", 0.3)]);
+    }
+
+    #[test]
+    fn test_positions_shared_by_total_allocation() {
+        let mut manager = StrategyManager::new();
+        manager.add(FixedStrategy::boxed("a", vec![]), 0.5).unwrap();
+
+        manager.update_position("AAPL", 40.0);
+
+        assert_eq!(manager.get_positions()["AAPL"], 40.0);
+        // A half-allocated strategy holding 40 shares sized for 80
+        let strategy = &manager.strategies[0].strategy;
+        assert_eq!(strategy.get_positions()["AAPL"], 80.0);
+    }
+
+    #[test]
+    fn test_registry_builds_strategies_by_name() {
+        let config = TradingConfig::default();
+        let mut registry = StrategyRegistry::default();
+        assert_eq!(registry.names(), ["momentum", "pairs"]);
+        assert_eq!(
+            registry.build("Momentum", &config).unwrap().name(),
+            "momentum"
+        );
+        // Pairs needs its own config section
+        assert!(registry.build("pairs", &config).is_err());
+
+        registry.register("fixed", |_| Ok(FixedStrategy::boxed("fixed", vec![])));
+        assert_eq!(registry.build("fixed", &config).unwrap().name(), "fixed");
+
+        let Err(error) = registry.build("reversion", &config) else {
+            panic!("unregistered strategy built");
+        };
+        assert!(
+            error
+                .to_string()
+                .contains("registered: fixed, momentum, pairs")
+        );
+    }
+}
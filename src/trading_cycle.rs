@@ -0,0 +1,1662 @@
+//! Execution half of a rebalance, generic over the order route
+//!
+//! `run_cycle` takes the already filtered signals for one rebalance, applies
+//! the exposure, halt, risk and margin checks, places the surviving orders
+//! through a `Broker` and resyncs portfolio and strategy positions from the
+//! broker afterwards. Legs the strategy links together, like the two sides
+//! of a pair, are placed together or not at all. Keeping it free of `TwsClient` lets the core loop run
+//! against `MockBroker` in tests. Every signal leaves a `SignalDecision` in
+//! the report saying whether it was placed or which check stopped it.
+
+use crate::broker::Broker;
+use crate::config::BracketConfig;
+use crate::connection::AccountPosition;
+use crate::costs::CostModel;
+use crate::decisions::{FilteredBy, SignalDecision};
+use crate::market_data::MarketDataHandler;
+use crate::order_types::BracketLevels;
+use crate::orders::{Order, OrderManager, OrderSignal, OrderStatus};
+use crate::portfolio::Portfolio;
+use crate::risk::{RiskManager, is_risk_reducing, resulting_short};
+use crate::risk_budgeting::RiskBudgeter;
+use crate::security_types::SecurityType;
+use crate::strategy::Strategy;
+use anyhow::{Context, Result};
+use log::{debug, error, info, warn};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+/// State a cycle reads and updates, borrowed from the trading loop
+pub struct TradingCycle<'a, B: Broker> {
+    pub broker: &'a B,
+    pub strategy: &'a mut dyn Strategy,
+    pub portfolio: &'a mut Portfolio,
+    pub risk_manager: &'a mut RiskManager,
+    pub order_manager: &'a mut OrderManager,
+    /// Present only when risk budgeting is enabled
+    pub risk_budgeter: Option<&'a RiskBudgeter>,
+    /// Drops new trades too small to be worth their commission, if set
+    pub cost_model: Option<&'a CostModel>,
+    /// Protective legs for entries, by symbol; entries without levels go in
+    /// as plain orders
+    pub bracket_levels: HashMap<String, BracketLevels>,
+}
+
+/// Bracket leg prices for each signal that can be priced
+///
+/// Signals whose ATR offset cannot be computed yet are left out and enter
+/// without protective legs.
+pub fn bracket_levels_for(
+    brackets: &BracketConfig,
+    signals: &[OrderSignal],
+    market_data: &MarketDataHandler,
+) -> HashMap<String, BracketLevels> {
+    signals
+        .iter()
+        .filter_map(|signal| {
+            let atr = market_data.calculate_atr(&signal.symbol, brackets.atr_period);
+            let entry = signal.limit_price.unwrap_or(signal.price);
+            let levels = brackets.levels(entry, signal.action == "BUY", atr)?;
+            Some((signal.symbol.clone(), levels))
+        })
+        .collect()
+}
+
+fn working_order_detail(order: &Order) -> String {
+    format!(
+        "order #{} ({} {}) is still {:?}",
+        order.id, order.action, order.quantity, order.status
+    )
+}
+
+/// The first leg linked to `symbol` whose signal was filtered this cycle
+fn filtered_partner<'a>(
+    symbol: &str,
+    linked_legs: &'a [Vec<String>],
+    report: &CycleReport,
+) -> Option<&'a String> {
+    linked_legs
+        .iter()
+        .filter(|group| group.iter().any(|leg| leg == symbol))
+        .flatten()
+        .find(|leg| {
+            *leg != symbol
+                && report
+                    .decision(leg)
+                    .is_some_and(|decision| !decision.is_acted())
+        })
+}
+
+/// Whether `signal` can still trade alongside its linked legs
+///
+/// One leg of a pair on its own is an unhedged position, so a leg whose
+/// partner was filtered is filtered too.
+fn keep_with_partners(
+    signal: &OrderSignal,
+    linked_legs: &[Vec<String>],
+    report: &mut CycleReport,
+) -> bool {
+    let Some(partner) = filtered_partner(&signal.symbol, linked_legs, report) else {
+        return true;
+    };
+    info!(
+        "Skipping {} {} {}: linked leg {} was filtered",
+        signal.action, signal.quantity, signal.symbol, partner
+    );
+    let detail = format!("linked leg {} was filtered", partner);
+    report.filtered(signal, FilteredBy::PairedLeg, detail);
+    false
+}
+
+/// Pull back legs already placed alongside `signal` after the broker refused it
+async fn cancel_placed_partners<B: Broker>(
+    broker: &B,
+    order_manager: &mut OrderManager,
+    signal: &OrderSignal,
+    linked_legs: &[Vec<String>],
+    report: &mut CycleReport,
+) {
+    let partners: Vec<&String> = linked_legs
+        .iter()
+        .filter(|group| group.contains(&signal.symbol))
+        .flatten()
+        .filter(|leg| **leg != signal.symbol)
+        .collect();
+    let (cancelled, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut report.submitted)
+        .into_iter()
+        .partition(|submitted| partners.contains(&&submitted.signal.symbol));
+    report.submitted = kept;
+    for submitted in cancelled {
+        warn!(
+            "Cancelling {} {} {}: linked leg {} was refused by the broker",
+            submitted.signal.action,
+            submitted.signal.quantity,
+            submitted.signal.symbol,
+            signal.symbol
+        );
+        if let Err(e) = broker.cancel_order(submitted.broker_order_id).await {
+            error!(
+                "Failed to cancel broker order {} for {}: {}",
+                submitted.broker_order_id, submitted.signal.symbol, e
+            );
+        }
+        let _ = order_manager.cancel_order(submitted.order.id);
+        report
+            .decisions
+            .retain(|decision| decision.symbol != submitted.signal.symbol);
+        report.filtered(
+            &submitted.signal,
+            FilteredBy::PairedLeg,
+            format!("linked leg {} was refused by the broker", signal.symbol),
+        );
+    }
+}
+
+/// Cancel a working order at the broker, then locally
+///
+/// Orders never submitted to the broker are only cancelled locally.
+async fn cancel_working_order<B: Broker>(
+    broker: &B,
+    order_manager: &mut OrderManager,
+    order_id: i32,
+) -> Result<()> {
+    if let Some(broker_order_id) = order_manager.broker_order_id(order_id) {
+        broker.cancel_order(broker_order_id).await?;
+    }
+    order_manager.cancel_order(order_id)
+}
+
+/// Cancel working orders still unresolved past the working-order timeout
+///
+/// Their symbols stay blocked until the cancel goes through, so a lost
+/// status update cannot leave a live order behind a duplicate.
+pub async fn cancel_stale_orders<B: Broker>(broker: &B, order_manager: &mut OrderManager) {
+    let stale: Vec<(i32, String)> = order_manager
+        .stale_working_orders()
+        .into_iter()
+        .map(|order| (order.id, order.symbol.clone()))
+        .collect();
+    for (order_id, symbol) in stale {
+        warn!(
+            "Order #{} for {} unresolved past the working-order timeout, cancelling",
+            order_id, symbol
+        );
+        if let Err(e) = cancel_working_order(broker, order_manager, order_id).await {
+            error!(
+                "Failed to cancel stale order #{} for {}: {}",
+                order_id, symbol, e
+            );
+        }
+    }
+}
+
+/// Cancel any resting bracket legs protecting `symbol` before it is reduced
+pub async fn cancel_protective_legs<B: Broker>(
+    broker: &B,
+    order_manager: &mut OrderManager,
+    symbol: &str,
+) {
+    for broker_order_id in order_manager.take_protective_legs(symbol) {
+        if let Err(e) = broker.cancel_order(broker_order_id).await {
+            warn!(
+                "Failed to cancel protective order {} for {}: {}",
+                broker_order_id, symbol, e
+            );
+        }
+    }
+}
+
+/// Keep an OCO stop/take-profit pair resting behind every open position
+///
+/// Protection is replaced when a position is resized or flips side and
+/// cancelled once it closes. Positions already covered by bracket legs from
+/// their entry order are left alone.
+pub async fn sync_protective_orders<B: Broker>(
+    broker: &B,
+    order_manager: &mut OrderManager,
+    risk_manager: &RiskManager,
+    positions: &[AccountPosition],
+) {
+    for symbol in order_manager.protected_symbols() {
+        let still_open = positions
+            .iter()
+            .any(|p| p.symbol == symbol && p.position != 0.0);
+        if !still_open {
+            info!(
+                "Position in {} closed, cancelling protective orders",
+                symbol
+            );
+            cancel_protective_legs(broker, order_manager, &symbol).await;
+        }
+    }
+
+    for position in positions.iter().filter(|p| p.position != 0.0) {
+        let symbol = &position.symbol;
+        match order_manager.protected_quantity(symbol) {
+            Some(quantity) if quantity == position.position => continue,
+            Some(quantity) => {
+                info!(
+                    "Position in {} changed from {} to {}, replacing protective orders",
+                    symbol, quantity, position.position
+                );
+                cancel_protective_legs(broker, order_manager, symbol).await;
+            }
+            None if order_manager.has_protective_legs(symbol) => continue,
+            None => {}
+        }
+
+        // TWS reports average cost per contract, including the multiplier
+        let multiplier = position
+            .contract
+            .multiplier
+            .parse::<f64>()
+            .ok()
+            .filter(|m| *m > 0.0)
+            .unwrap_or(1.0);
+        let entry_price = position.avg_cost / multiplier;
+        let levels = risk_manager.protective_levels(symbol, entry_price, position.position > 0.0);
+
+        match broker
+            .place_protective_orders(symbol, position.position, &levels)
+            .await
+        {
+            Ok(ids) => {
+                info!(
+                    "Protecting {} {}: take profit {:.4}, stop loss {:.4}",
+                    position.position, symbol, levels.take_profit, levels.stop_loss
+                );
+                order_manager.record_protective_legs(symbol, &ids);
+                order_manager.record_protected_position(symbol, position.position);
+            }
+            Err(e) => error!("Failed to place protective orders for {}: {}", symbol, e),
+        }
+    }
+}
+
+/// Bring `order_manager` in line with the broker's view of its orders
+///
+/// Picks up fills, cancellations and rejections that happened at the broker
+/// after submission. Returns the number of orders updated. The order manager
+/// is locked only to apply the results, not while the broker is polled.
+pub async fn reconcile_order_statuses<B: Broker>(
+    broker: &B,
+    order_manager: &Mutex<OrderManager>,
+) -> Result<usize> {
+    let statuses = broker.poll_order_statuses().await?;
+    Ok(order_manager
+        .lock()
+        .await
+        .reconcile_broker_statuses(&statuses))
+}
+
+/// An order accepted by the broker during a cycle
+#[derive(Debug, Clone)]
+pub struct SubmittedOrder {
+    pub order: Order,
+    pub signal: OrderSignal,
+    pub broker_order_id: i32,
+}
+
+/// What a cycle did
+#[derive(Debug, Default)]
+pub struct CycleReport {
+    pub submitted: Vec<SubmittedOrder>,
+    /// Exposure was over the limit, so only SELL signals were processed
+    pub risk_reduction_only: bool,
+    /// One per signal, in the order they were processed
+    pub decisions: Vec<SignalDecision>,
+}
+
+impl CycleReport {
+    /// What happened to `symbol`'s signal this cycle
+    pub fn decision(&self, symbol: &str) -> Option<&SignalDecision> {
+        self.decisions
+            .iter()
+            .find(|decision| decision.symbol == symbol)
+    }
+
+    fn filtered(&mut self, signal: &OrderSignal, filtered_by: FilteredBy, detail: String) {
+        self.decisions.push(SignalDecision::filtered(
+            &signal.symbol,
+            filtered_by,
+            detail,
+        ));
+    }
+
+    fn acted(&mut self, signal: &OrderSignal, broker_order_id: i32) {
+        self.decisions.push(SignalDecision::acted(
+            &signal.symbol,
+            format!(
+                "{} {} submitted as broker order {}",
+                signal.action, signal.quantity, broker_order_id
+            ),
+        ));
+    }
+}
+
+/// Execute one rebalance worth of signals
+///
+/// Fails only when the account summary needed for margin checks cannot be
+/// fetched; individual order failures are logged and skipped.
+pub async fn run_cycle<B: Broker>(
+    cycle: TradingCycle<'_, B>,
+    signals: Vec<OrderSignal>,
+    latest_prices: &HashMap<String, f64>,
+) -> Result<CycleReport> {
+    let TradingCycle {
+        broker,
+        strategy,
+        portfolio,
+        risk_manager,
+        order_manager,
+        risk_budgeter,
+        cost_model,
+        bracket_levels,
+    } = cycle;
+    let config = risk_manager.config.clone();
+    let mut report = CycleReport::default();
+    cancel_stale_orders(broker, order_manager).await;
+
+    if signals.is_empty() {
+        return Ok(report);
+    }
+
+    let account_summary = broker
+        .get_account_summary()
+        .await
+        .context("Failed to get account summary for margin validation")?;
+
+    // Check if portfolio exposure is excessive before processing new signals
+    let current_exposure = portfolio
+        .positions()
+        .values()
+        .map(|p| (p.quantity * p.current_price).abs())
+        .sum::<f64>();
+    let exposure_ratio = current_exposure / portfolio.get_stats().total_value;
+
+    // Size entries to the portfolio volatility target
+    let signals = match risk_budgeter {
+        Some(budgeter) => {
+            let scaled = budgeter.scale_to_target_volatility(portfolio, &signals);
+            for signal in &signals {
+                if !scaled.iter().any(|s| s.symbol == signal.symbol) {
+                    report.filtered(
+                        signal,
+                        FilteredBy::RiskLimit,
+                        "scaled to nothing by the portfolio volatility target".to_string(),
+                    );
+                }
+            }
+            scaled
+        }
+        None => signals,
+    };
+
+    let linked_legs = strategy.linked_legs();
+    let mut checked = Vec::new();
+    if exposure_ratio > config.max_portfolio_exposure {
+        warn!(
+            "Portfolio exposure {:.1}% exceeds {:.1}% limit - prioritizing risk reduction over new signals",
+            exposure_ratio * 100.0,
+            config.max_portfolio_exposure * 100.0
+        );
+        report.risk_reduction_only = true;
+
+        // Only process genuine reductions when over-exposed; a SELL that opens
+        // or adds to a short is new exposure and still needs the short checks
+        let (reduction_signals, blocked): (Vec<_>, Vec<_>) = signals
+            .into_iter()
+            .partition(|s| is_risk_reducing(s, portfolio));
+        for signal in &blocked {
+            report.filtered(
+                signal,
+                FilteredBy::Exposure,
+                format!(
+                    "exposure {:.1}% over the {:.1}% limit",
+                    exposure_ratio * 100.0,
+                    config.max_portfolio_exposure * 100.0
+                ),
+            );
+        }
+        if reduction_signals.is_empty() {
+            warn!("No position reduction signals available - portfolio remains over-exposed");
+            return Ok(report);
+        }
+        info!(
+            "Processing {} position reduction signals due to excessive exposure",
+            reduction_signals.len()
+        );
+
+        for signal in reduction_signals {
+            info!(
+                "Executing risk reduction signal: {} {} {}",
+                signal.action, signal.quantity, signal.symbol
+            );
+
+            // The reduction takes priority over whatever is still working
+            if let Some(working) = order_manager.working_order(&signal.symbol) {
+                let order_id = working.id;
+                let detail = working_order_detail(working);
+                info!(
+                    "Cancelling working order for {} ahead of risk reduction: {}",
+                    signal.symbol, detail
+                );
+                if let Err(e) = cancel_working_order(broker, order_manager, order_id).await {
+                    warn!("Skipping risk reduction for {}: {}", signal.symbol, e);
+                    report.filtered(
+                        &signal,
+                        FilteredBy::WorkingOrder,
+                        format!("{}, cancel failed: {}", detail, e),
+                    );
+                    continue;
+                }
+            }
+            checked.push(signal);
+        }
+    } else {
+        // Perform risk analysis before executing signals
+        risk_manager.log_risk_analysis(portfolio);
+
+        for signal in signals {
+            // Don't duplicate an order the broker has not confirmed yet
+            if let Some(working) = order_manager.working_order(&signal.symbol) {
+                let detail = working_order_detail(working);
+                info!("Skipping {} {}: {}", signal.action, signal.symbol, detail);
+                report.filtered(&signal, FilteredBy::WorkingOrder, detail);
+                continue;
+            }
+
+            // Tiny new trades cost more in commission than they are worth;
+            // reductions always go through
+            if let Some(cost_model) = cost_model
+                && !is_risk_reducing(&signal, portfolio)
+                && cost_model.commission_too_high(
+                    &signal.symbol,
+                    &signal.security_info.security_type,
+                    signal.quantity,
+                    signal.price,
+                )
+            {
+                info!(
+                    "Skipping {} {} {}: commission exceeds the cap on ${:.2} notional",
+                    signal.action,
+                    signal.quantity,
+                    signal.symbol,
+                    signal.quantity * signal.price
+                );
+                report.filtered(
+                    &signal,
+                    FilteredBy::Commission,
+                    format!(
+                        "commission exceeds the cap on ${:.2} notional",
+                        signal.quantity * signal.price
+                    ),
+                );
+                continue;
+            }
+
+            // Only risk-reducing orders while the daily loss halt is active
+            if !risk_manager.allows_signal(&signal, portfolio) {
+                warn!(
+                    "Trading halted: skipping {} {} {}",
+                    signal.action, signal.quantity, signal.symbol
+                );
+                report.filtered(
+                    &signal,
+                    FilteredBy::TradingHalt,
+                    "daily loss halt allows only risk reduction".to_string(),
+                );
+                continue;
+            }
+
+            // No new exposure until a drawdown de-risk has recovered
+            if risk_manager.is_derisked() && !is_risk_reducing(&signal, portfolio) {
+                info!(
+                    "De-risked on drawdown: skipping {} {} {}",
+                    signal.action, signal.quantity, signal.symbol
+                );
+                report.filtered(
+                    &signal,
+                    FilteredBy::Drawdown,
+                    "drawdown de-risking blocks entries until recovery".to_string(),
+                );
+                continue;
+            }
+
+            // No re-entry, long or short, into names recently stopped out
+            if !is_risk_reducing(&signal, portfolio) && risk_manager.in_cooldown(&signal.symbol) {
+                info!(
+                    "{} in re-entry cooldown: skipping {} {}",
+                    signal.symbol, signal.action, signal.quantity
+                );
+                report.filtered(
+                    &signal,
+                    FilteredBy::Cooldown,
+                    "in re-entry cooldown".to_string(),
+                );
+                continue;
+            }
+
+            // Shorting a stock needs a borrow; futures and forex do not
+            if signal.security_info.security_type == SecurityType::Stock
+                && let Some(short) = resulting_short(&signal, portfolio)
+                && let Err(e) = risk_manager.can_short(&signal.symbol, short)
+            {
+                info!(
+                    "Short rejected: {} {} {}: {}",
+                    signal.action, signal.quantity, signal.symbol, e
+                );
+                report.filtered(&signal, FilteredBy::ShortBorrow, e.to_string());
+                continue;
+            }
+
+            // Validate position against risk limits
+            match risk_manager.validate_new_position(
+                portfolio,
+                &signal.symbol,
+                signal.quantity,
+                signal.price,
+            ) {
+                Ok(true) => {}
+                Ok(false) => {
+                    info!(
+                        "Order rejected by risk manager: {} {} {}",
+                        signal.action, signal.quantity, signal.symbol
+                    );
+                    report.filtered(
+                        &signal,
+                        FilteredBy::RiskLimit,
+                        "rejected by position limits".to_string(),
+                    );
+                    continue;
+                }
+                Err(e) => {
+                    error!("Risk validation failed for {}: {}", signal.symbol, e);
+                    report.filtered(&signal, FilteredBy::RiskLimit, e.to_string());
+                    continue;
+                }
+            }
+
+            // Anti-churn: don't exit young positions unless the halt is forcing
+            // risk reduction
+            if !risk_manager.is_halted()
+                && let Err(e) =
+                    order_manager.check_holding_period(&signal, portfolio, portfolio.now())
+            {
+                info!("{}", e);
+                report.filtered(&signal, FilteredBy::HoldingPeriod, e.to_string());
+                continue;
+            }
+
+            // Additional risk budgeting validation if enabled
+            if let Some(budgeter) = risk_budgeter {
+                let symbols: Vec<String> = portfolio.positions().keys().cloned().collect();
+                match budgeter.calculate_correlation_risk(&symbols) {
+                    Ok(correlation_risk) => {
+                        let min_score = 1.0 - config.max_correlation_exposure;
+                        if correlation_risk.diversification_score < min_score {
+                            warn!(
+                                "Risk budgeting: Diversification score too low for {}: {:.2}% < {:.2}%",
+                                signal.symbol,
+                                correlation_risk.diversification_score * 100.0,
+                                min_score * 100.0
+                            );
+                            report.filtered(
+                                &signal,
+                                FilteredBy::Correlation,
+                                format!(
+                                    "diversification score {:.2}% below {:.2}%",
+                                    correlation_risk.diversification_score * 100.0,
+                                    min_score * 100.0
+                                ),
+                            );
+                            continue;
+                        }
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Failed to calculate correlation risk for {}: {}",
+                            signal.symbol, e
+                        );
+                    }
+                }
+
+                let signed_quantity = if signal.action == "SELL" {
+                    -signal.quantity
+                } else {
+                    signal.quantity
+                };
+                if let Err(e) = budgeter.check_cluster_exposure(
+                    portfolio,
+                    &signal.symbol,
+                    signed_quantity,
+                    signal.price,
+                ) {
+                    warn!("Risk budgeting: {}", e);
+                    report.filtered(&signal, FilteredBy::Correlation, e.to_string());
+                    continue;
+                }
+            }
+
+            checked.push(signal);
+        }
+    }
+
+    // Pair legs trade together or not at all
+    let checked: Vec<OrderSignal> = checked
+        .into_iter()
+        .filter(|signal| keep_with_partners(signal, &linked_legs, &mut report))
+        .collect();
+
+    // Create every order before placing any, so a leg failing margin
+    // validation stops its partner too
+    let mut created = Vec::new();
+    for signal in checked {
+        match order_manager.validate_and_create_order(
+            signal.clone(),
+            portfolio,
+            &account_summary,
+            config.max_margin_utilization,
+            config.max_position_size,
+        ) {
+            Ok(order) => created.push((signal, order)),
+            Err(e) => {
+                error!("Failed to create order due to margin constraints: {}", e);
+                report.filtered(&signal, FilteredBy::Margin, e.to_string());
+            }
+        }
+    }
+
+    for (signal, order) in created {
+        if !keep_with_partners(&signal, &linked_legs, &mut report) {
+            let _ = order_manager.cancel_order(order.id);
+            continue;
+        }
+        debug!(
+            "About to place order: signal.quantity={:.0}, order.quantity={:.0}",
+            signal.quantity, order.quantity
+        );
+
+        // Exits drop the old protection; entries bring their own
+        let reducing = is_risk_reducing(&signal, portfolio);
+        let brackets = if report.risk_reduction_only || reducing {
+            cancel_protective_legs(broker, order_manager, &signal.symbol).await;
+            None
+        } else {
+            bracket_levels.get(&signal.symbol)
+        };
+        let placed = match brackets {
+            Some(levels) => broker
+                .place_bracket_order(&signal, levels)
+                .await
+                .map(|ids| {
+                    info!(
+                        "Bracket for {}: take-profit {:.4}, stop {:.4}",
+                        signal.symbol, levels.take_profit, levels.stop_loss
+                    );
+                    order_manager.record_protective_legs(&signal.symbol, &ids[1..]);
+                    ids[0]
+                }),
+            None => broker.place_order(&signal).await,
+        };
+
+        match placed {
+            Ok(broker_order_id) => {
+                let _ = order_manager.update_order_status(order.id, OrderStatus::Submitted);
+                order_manager.record_broker_order_id(order.id, broker_order_id);
+                if report.risk_reduction_only && reducing {
+                    risk_manager.start_cooldown(&order.symbol);
+                }
+                // Portfolio is updated from the broker's positions below rather
+                // than assuming the fill
+                info!(
+                    "Order submitted: {} {} {} (broker ID: {})",
+                    signal.action, signal.quantity, signal.symbol, broker_order_id
+                );
+                report.acted(&signal, broker_order_id);
+                report.submitted.push(SubmittedOrder {
+                    order,
+                    signal,
+                    broker_order_id,
+                });
+            }
+            Err(e) => {
+                error!("Failed to place order: {}", e);
+                let _ = order_manager.update_order_status(order.id, OrderStatus::Rejected);
+                report.filtered(&signal, FilteredBy::Broker, e.to_string());
+                cancel_placed_partners(broker, order_manager, &signal, &linked_legs, &mut report)
+                    .await;
+            }
+        }
+    }
+
+    // Update portfolio with latest prices after all trading is done
+    portfolio.update_market_prices(latest_prices);
+
+    // Force portfolio and strategy sync with the broker's actual positions
+    if let Ok(positions) = broker.get_positions().await {
+        portfolio.sync_all_positions_from_tws(&positions, latest_prices);
+
+        for symbol in strategy.get_positions().keys().cloned().collect::<Vec<_>>() {
+            strategy.update_position(&symbol, 0.0);
+        }
+        for pos in &positions {
+            strategy.update_position(&portfolio.tws_position_symbol(pos), pos.position);
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::broker::MockBroker;
+    use crate::config::{BracketOffset, RiskConfig, TradingConfig};
+    use crate::connection::AccountPosition;
+    use crate::costs::CostModelConfig;
+    use crate::momentum::MomentumStrategy;
+    use crate::security_types::SecurityInfo;
+    use chrono::Utc;
+    use ibapi::contracts::Contract;
+
+    fn signal(symbol: &str, action: &str, quantity: f64, price: f64) -> OrderSignal {
+        OrderSignal {
+            symbol: symbol.to_string(),
+            action: action.to_string(),
+            quantity,
+            price,
+            order_type: "MKT".to_string(),
+            limit_price: None,
+            reason: "test".to_string(),
+            security_info: SecurityInfo::new_stock(
+                symbol.to_string(),
+                "SMART".to_string(),
+                "USD".to_string(),
+            ),
+        }
+    }
+
+    fn summary(net_liquidation: f64) -> HashMap<String, f64> {
+        HashMap::from([
+            ("net_liquidation".to_string(), net_liquidation),
+            ("available_funds".to_string(), net_liquidation),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_cycle_places_only_valid_orders_and_syncs_positions() {
+        let broker = MockBroker::new(summary(100_000.0));
+        broker.set_positions(vec![AccountPosition {
+            account: "MOCK".to_string(),
+            symbol: "AAPL".to_string(),
+            position: 3.0,
+            avg_cost: 150.0,
+            contract: Contract::stock("AAPL"),
+        }]);
+
+        let mut strategy = MomentumStrategy::new(TradingConfig::default().strategy_config);
+        strategy.update_position("OLD", 10.0);
+        let mut portfolio = Portfolio::new(100_000.0);
+        // 0.5% position limit: $500 per position
+        let mut risk_manager = RiskManager::new(RiskConfig::default());
+        let mut order_manager = OrderManager::new();
+
+        let signals = vec![
+            signal("AAPL", "BUY", 3.0, 150.0),
+            signal("MSFT", "BUY", 10.0, 300.0), // $3,000 exceeds the limit
+        ];
+        let prices = HashMap::from([("AAPL".to_string(), 150.0), ("MSFT".to_string(), 300.0)]);
+
+        let report = run_cycle(
+            TradingCycle {
+                broker: &broker,
+                strategy: &mut strategy,
+                portfolio: &mut portfolio,
+                risk_manager: &mut risk_manager,
+                order_manager: &mut order_manager,
+                risk_budgeter: None,
+                cost_model: None,
+                bracket_levels: HashMap::new(),
+            },
+            signals,
+            &prices,
+        )
+        .await
+        .unwrap();
+
+        let placed = broker.placed_orders();
+        assert_eq!(placed.len(), 1);
+        assert_eq!(placed[0].symbol, "AAPL");
+        assert_eq!(placed[0].action, "BUY");
+        assert_eq!(placed[0].quantity, 3.0);
+
+        assert!(!report.risk_reduction_only);
+        assert_eq!(report.submitted.len(), 1);
+        let submitted = &report.submitted[0];
+        assert_eq!(submitted.broker_order_id, 1);
+        assert_eq!(
+            order_manager.get_order(submitted.order.id).unwrap().status,
+            OrderStatus::Submitted
+        );
+
+        // Strategy and portfolio now mirror the broker
+        assert_eq!(strategy.get_positions().get("AAPL"), Some(&3.0));
+        assert_eq!(strategy.get_positions().get("OLD"), Some(&0.0));
+        assert_eq!(portfolio.positions()["AAPL"].quantity, 3.0);
+    }
+
+    /// Buys a fixed quantity of every symbol it has no position in
+    struct BuyOnce {
+        symbols: Vec<String>,
+        positions: HashMap<String, f64>,
+    }
+
+    impl Strategy for BuyOnce {
+        fn name(&self) -> &str {
+            "buy_once"
+        }
+
+        fn calculate_signals(&mut self, _market_data: &MarketDataHandler) -> Vec<OrderSignal> {
+            self.symbols
+                .iter()
+                .filter(|symbol| self.positions.get(*symbol).copied().unwrap_or(0.0) == 0.0)
+                .map(|symbol| signal(symbol, "BUY", 2.0, 100.0))
+                .collect()
+        }
+
+        fn update_position(&mut self, symbol: &str, quantity: f64) {
+            self.positions.insert(symbol.to_string(), quantity);
+        }
+
+        fn get_positions(&self) -> &HashMap<String, f64> {
+            &self.positions
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cycle_drives_any_strategy() {
+        let broker = MockBroker::new(summary(100_000.0));
+        let mut strategy: Box<dyn Strategy> = Box::new(BuyOnce {
+            symbols: vec!["AAPL".to_string()],
+            positions: HashMap::new(),
+        });
+        let mut portfolio = Portfolio::new(100_000.0);
+        let mut risk_manager = RiskManager::new(RiskConfig::default());
+        let mut order_manager = OrderManager::new();
+
+        let signals = strategy.calculate_signals(&MarketDataHandler::new());
+        assert_eq!(signals.len(), 1);
+        broker.set_positions(vec![AccountPosition {
+            account: "MOCK".to_string(),
+            symbol: "AAPL".to_string(),
+            position: 2.0,
+            avg_cost: 100.0,
+            contract: Contract::stock("AAPL"),
+        }]);
+        let prices = HashMap::from([("AAPL".to_string(), 100.0)]);
+
+        let report = run_cycle(
+            TradingCycle {
+                broker: &broker,
+                strategy: strategy.as_mut(),
+                portfolio: &mut portfolio,
+                risk_manager: &mut risk_manager,
+                order_manager: &mut order_manager,
+                risk_budgeter: None,
+                cost_model: None,
+                bracket_levels: HashMap::new(),
+            },
+            signals,
+            &prices,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.submitted.len(), 1);
+        assert_eq!(broker.placed_orders()[0].symbol, "AAPL");
+        // The resync fed the fill back, so the next cycle has nothing to do
+        assert_eq!(strategy.get_positions().get("AAPL"), Some(&2.0));
+        assert!(
+            strategy
+                .calculate_signals(&MarketDataHandler::new())
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_commission_cap_drops_tiny_entries_but_not_reductions() {
+        let broker = MockBroker::new(summary(1_000_000.0));
+        let mut strategy = MomentumStrategy::new(TradingConfig::default().strategy_config);
+        let mut portfolio = Portfolio::new(1_000_000.0);
+        portfolio.update_position("OLD", 1.0, 20.0);
+        let mut risk_manager = RiskManager::new(RiskConfig::default());
+        let mut order_manager = OrderManager::new();
+        // $1 minimum stock commission against a 1% cap
+        let cost_model = CostModel::new(CostModelConfig {
+            max_commission_fraction: Some(0.01),
+            ..CostModelConfig::default()
+        });
+
+        run_cycle(
+            TradingCycle {
+                broker: &broker,
+                strategy: &mut strategy,
+                portfolio: &mut portfolio,
+                risk_manager: &mut risk_manager,
+                order_manager: &mut order_manager,
+                risk_budgeter: None,
+                cost_model: Some(&cost_model),
+                bracket_levels: HashMap::new(),
+            },
+            vec![
+                signal("AAPL", "BUY", 1.0, 20.0),   // $20: $1 is 5%
+                signal("MSFT", "BUY", 20.0, 100.0), // $2,000: $1 is 0.05%
+                signal("OLD", "SELL", 1.0, 20.0),   // $20 but closes a position
+            ],
+            &HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        let placed: Vec<String> = broker
+            .placed_orders()
+            .into_iter()
+            .map(|order| order.symbol)
+            .collect();
+        assert_eq!(placed, ["MSFT", "OLD"]);
+    }
+
+    #[tokio::test]
+    async fn test_over_exposed_cycle_only_sells() {
+        let broker = MockBroker::new(summary(1_000.0));
+        let mut strategy = MomentumStrategy::new(TradingConfig::default().strategy_config);
+        let mut portfolio = Portfolio::new(1_000.0);
+        portfolio.update_position("AAPL", 10.0, 150.0);
+        let mut risk_manager = RiskManager::new(RiskConfig::default());
+        let mut order_manager = OrderManager::new();
+
+        let report = run_cycle(
+            TradingCycle {
+                broker: &broker,
+                strategy: &mut strategy,
+                portfolio: &mut portfolio,
+                risk_manager: &mut risk_manager,
+                order_manager: &mut order_manager,
+                risk_budgeter: None,
+                cost_model: None,
+                bracket_levels: HashMap::new(),
+            },
+            vec![
+                signal("MSFT", "BUY", 1.0, 300.0),
+                signal("AAPL", "SELL", 2.0, 150.0),
+            ],
+            &HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        assert!(report.risk_reduction_only);
+        let placed = broker.placed_orders();
+        assert_eq!(placed.len(), 1);
+        assert_eq!(placed[0].symbol, "AAPL");
+        assert_eq!(placed[0].action, "SELL");
+    }
+
+    #[tokio::test]
+    async fn test_over_exposed_cycle_filters_short_opening_sells() {
+        let broker = MockBroker::new(summary(1_000.0));
+        let mut strategy = MomentumStrategy::new(TradingConfig::default().strategy_config);
+        let mut portfolio = Portfolio::new(1_000.0);
+        portfolio.update_position("AAPL", 10.0, 150.0);
+        let mut risk_manager = RiskManager::new(RiskConfig::default());
+        let mut order_manager = OrderManager::new();
+        assert!(risk_manager.can_short("TSLA", 5.0).is_err());
+
+        let report = run_cycle(
+            TradingCycle {
+                broker: &broker,
+                strategy: &mut strategy,
+                portfolio: &mut portfolio,
+                risk_manager: &mut risk_manager,
+                order_manager: &mut order_manager,
+                risk_budgeter: None,
+                cost_model: None,
+                bracket_levels: HashMap::new(),
+            },
+            vec![
+                signal("TSLA", "SELL", 5.0, 200.0),  // opens a short
+                signal("AAPL", "SELL", 12.0, 150.0), // flips the long short
+                signal("AAPL", "SELL", 2.0, 150.0),
+            ],
+            &HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        assert!(report.risk_reduction_only);
+        assert!(!report.decision("TSLA").unwrap().is_acted());
+        let placed = broker.placed_orders();
+        assert_eq!(placed.len(), 1);
+        assert_eq!(
+            (placed[0].symbol.as_str(), placed[0].quantity),
+            ("AAPL", 2.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_over_exposed_cycle_keeps_cooldown_on_short_entries() {
+        let broker = MockBroker::new(summary(1_000.0));
+        let mut strategy = MomentumStrategy::new(TradingConfig::default().strategy_config);
+        let mut portfolio = Portfolio::new(1_000.0);
+        portfolio.update_position("AAPL", 10.0, 150.0);
+        let mut risk_manager = RiskManager::new(RiskConfig {
+            shortable_symbols: vec!["TSLA".to_string(), "NFLX".to_string()],
+            reentry_cooldown_minutes: 30,
+            ..RiskConfig::default()
+        });
+        risk_manager.start_cooldown("TSLA");
+        let mut order_manager = OrderManager::new();
+
+        let report = run_cycle(
+            TradingCycle {
+                broker: &broker,
+                strategy: &mut strategy,
+                portfolio: &mut portfolio,
+                risk_manager: &mut risk_manager,
+                order_manager: &mut order_manager,
+                risk_budgeter: None,
+                cost_model: None,
+                bracket_levels: HashMap::new(),
+            },
+            vec![
+                signal("TSLA", "SELL", 1.0, 200.0),
+                signal("NFLX", "SELL", 1.0, 400.0),
+                signal("AAPL", "SELL", 2.0, 150.0),
+            ],
+            &HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        assert!(report.risk_reduction_only);
+        let placed: Vec<String> = broker
+            .placed_orders()
+            .into_iter()
+            .map(|order| order.symbol)
+            .collect();
+        assert_eq!(placed, ["AAPL"]);
+        assert!(risk_manager.in_cooldown("AAPL"));
+        assert!(!risk_manager.in_cooldown("NFLX"));
+    }
+
+    #[tokio::test]
+    async fn test_bracketed_entry_and_exit_cancels_legs() {
+        let broker = MockBroker::new(summary(100_000.0));
+        let mut strategy = MomentumStrategy::new(TradingConfig::default().strategy_config);
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("MSFT", 1.0, 300.0);
+        let mut risk_manager = RiskManager::new(RiskConfig::default());
+        let mut order_manager = OrderManager::new();
+        order_manager.record_protective_legs("MSFT", &[41, 42]);
+
+        let brackets = BracketConfig {
+            take_profit: BracketOffset::Fraction(0.1),
+            stop_loss: BracketOffset::Fraction(0.05),
+            atr_period: 14,
+        };
+        let signals = vec![
+            signal("AAPL", "BUY", 2.0, 150.0),
+            signal("MSFT", "SELL", 1.0, 300.0),
+        ];
+        let bracket_levels = bracket_levels_for(&brackets, &signals, &MarketDataHandler::new());
+
+        let report = run_cycle(
+            TradingCycle {
+                broker: &broker,
+                strategy: &mut strategy,
+                portfolio: &mut portfolio,
+                risk_manager: &mut risk_manager,
+                order_manager: &mut order_manager,
+                risk_budgeter: None,
+                cost_model: None,
+                bracket_levels,
+            },
+            signals,
+            &HashMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.submitted.len(), 2);
+
+        // Entry: parent limit plus two linked exits, transmitted on the last
+        let legs = broker.bracket_orders();
+        assert_eq!(legs.len(), 3);
+        let (parent_id, parent) = &legs[0];
+        assert_eq!(parent.order_type, "LMT");
+        assert_eq!(parent.limit_price, Some(150.0));
+        assert_eq!(parent.total_quantity, 2.0);
+        assert!(!parent.transmit);
+        let (_, take_profit) = &legs[1];
+        assert_eq!(take_profit.order_type, "LMT");
+        assert!((take_profit.limit_price.unwrap() - 165.0).abs() < 1e-9);
+        assert_eq!(take_profit.parent_id, *parent_id);
+        let (_, stop) = &legs[2];
+        assert_eq!(stop.order_type, "STP");
+        assert!((stop.aux_price.unwrap() - 142.5).abs() < 1e-9);
+        assert_eq!(stop.parent_id, *parent_id);
+        assert!(stop.transmit);
+        assert_eq!(
+            order_manager.take_protective_legs("AAPL"),
+            vec![legs[1].0, legs[2].0]
+        );
+
+        // Exit: plain order, old protection cancelled
+        let placed = broker.placed_orders();
+        assert_eq!(placed.len(), 1);
+        assert_eq!(placed[0].symbol, "MSFT");
+        assert_eq!(broker.cancelled_orders(), vec![41, 42]);
+        assert!(order_manager.take_protective_legs("MSFT").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_entry_blocked_during_reentry_cooldown() {
+        let broker = MockBroker::new(summary(100_000.0));
+        let mut strategy = MomentumStrategy::new(TradingConfig::default().strategy_config);
+        let mut portfolio = Portfolio::new(100_000.0);
+        let mut risk_manager = RiskManager::new(RiskConfig {
+            reentry_cooldown_minutes: 60,
+            ..RiskConfig::default()
+        });
+        risk_manager.start_cooldown("AAPL");
+        risk_manager.start_cooldown("TSLA");
+        risk_manager.start_cooldown("NFLX");
+        portfolio.update_position("NFLX", 1.0, 400.0);
+        let mut order_manager = OrderManager::new();
+
+        let report = run_cycle(
+            TradingCycle {
+                broker: &broker,
+                strategy: &mut strategy,
+                portfolio: &mut portfolio,
+                risk_manager: &mut risk_manager,
+                order_manager: &mut order_manager,
+                risk_budgeter: None,
+                cost_model: None,
+                bracket_levels: HashMap::new(),
+            },
+            vec![
+                signal("AAPL", "BUY", 2.0, 150.0),
+                signal("MSFT", "BUY", 1.0, 300.0),
+                signal("TSLA", "SELL", 3.0, 200.0),
+                signal("NFLX", "SELL", 1.0, 400.0),
+            ],
+            &HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        // A short entry is blocked like a long one; closing a holding is not
+        let placed = broker.placed_orders();
+        assert_eq!(report.submitted.len(), 2);
+        let symbols: Vec<&str> = placed.iter().map(|order| order.symbol.as_str()).collect();
+        assert_eq!(symbols, ["MSFT", "NFLX"]);
+        assert_eq!(
+            report.decision("TSLA").unwrap().filtered_by,
+            Some(FilteredBy::Cooldown)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_entries_blocked_while_derisked() {
+        let broker = MockBroker::new(summary(100_000.0));
+        let mut strategy = MomentumStrategy::new(TradingConfig::default().strategy_config);
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("MSFT", 10.0, 300.0);
+        let mut risk_manager = RiskManager::new(RiskConfig {
+            drawdown_derisking: Some(crate::config::DrawdownDerisking {
+                trigger_drawdown: 0.15,
+                reentry_drawdown: 0.05,
+                cash_floor: 0.5,
+            }),
+            ..RiskConfig::default()
+        });
+        // A drawdown across days, so the daily loss halt stays off
+        risk_manager.update_daily_pnl(100_000.0, Utc::now() - chrono::Duration::days(2));
+        risk_manager.update_daily_pnl(80_000.0, Utc::now());
+        assert!(!risk_manager.is_halted());
+        let mut order_manager = OrderManager::new();
+
+        let report = run_cycle(
+            TradingCycle {
+                broker: &broker,
+                strategy: &mut strategy,
+                portfolio: &mut portfolio,
+                risk_manager: &mut risk_manager,
+                order_manager: &mut order_manager,
+                risk_budgeter: None,
+                cost_model: None,
+                bracket_levels: HashMap::new(),
+            },
+            vec![
+                signal("AAPL", "BUY", 2.0, 150.0),
+                signal("MSFT", "SELL", 1.0, 300.0),
+            ],
+            &HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        let placed = broker.placed_orders();
+        assert_eq!(placed.len(), 1);
+        assert_eq!(placed[0].symbol, "MSFT");
+        assert_eq!(
+            report.decision("AAPL").unwrap().filtered_by,
+            Some(FilteredBy::Drawdown)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_short_entries_need_shortable_stock() {
+        let broker = MockBroker::new(summary(100_000.0));
+        let mut strategy = MomentumStrategy::new(TradingConfig::default().strategy_config);
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", 2.0, 150.0);
+        let mut risk_manager = RiskManager::new(RiskConfig {
+            shortable_symbols: vec!["TSLA".to_string()],
+            ..RiskConfig::default()
+        });
+        let mut order_manager = OrderManager::new();
+
+        run_cycle(
+            TradingCycle {
+                broker: &broker,
+                strategy: &mut strategy,
+                portfolio: &mut portfolio,
+                risk_manager: &mut risk_manager,
+                order_manager: &mut order_manager,
+                risk_budgeter: None,
+                cost_model: None,
+                bracket_levels: HashMap::new(),
+            },
+            vec![
+                signal("AAPL", "SELL", 2.0, 150.0), // Closes the long
+                signal("MSFT", "SELL", 1.0, 300.0), // Opens a short, not shortable
+                signal("TSLA", "SELL", 1.0, 200.0), // Opens a short, shortable
+            ],
+            &HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        let placed: Vec<String> = broker
+            .placed_orders()
+            .into_iter()
+            .map(|signal| signal.symbol)
+            .collect();
+        assert_eq!(placed, vec!["AAPL", "TSLA"]);
+    }
+
+    #[tokio::test]
+    async fn test_sync_protects_new_positions_and_follows_changes() {
+        let broker = MockBroker::new(summary(100_000.0));
+        let risk_manager = RiskManager::new(RiskConfig::default());
+        let mut order_manager = OrderManager::new();
+        let long = |quantity: f64| AccountPosition {
+            account: "MOCK".to_string(),
+            symbol: "AAPL".to_string(),
+            position: quantity,
+            avg_cost: 100.0,
+            contract: Contract::stock("AAPL"),
+        };
+
+        sync_protective_orders(&broker, &mut order_manager, &risk_manager, &[long(100.0)]).await;
+        let protective = broker.protective_orders();
+        assert_eq!(protective.len(), 1);
+        let (symbol, quantity, levels) = &protective[0];
+        assert_eq!(symbol, "AAPL");
+        assert_eq!(*quantity, 100.0);
+        assert!((levels.take_profit - 104.0).abs() < 1e-9);
+        assert!((levels.stop_loss - 98.0).abs() < 1e-9);
+        assert_eq!(order_manager.protected_quantity("AAPL"), Some(100.0));
+
+        // Unchanged position: nothing new
+        sync_protective_orders(&broker, &mut order_manager, &risk_manager, &[long(100.0)]).await;
+        assert_eq!(broker.protective_orders().len(), 1);
+        assert!(broker.cancelled_orders().is_empty());
+
+        // Flipped short: old pair cancelled, new pair on the other side
+        sync_protective_orders(&broker, &mut order_manager, &risk_manager, &[long(-50.0)]).await;
+        assert_eq!(broker.cancelled_orders().len(), 2);
+        let protective = broker.protective_orders();
+        assert_eq!(protective.len(), 2);
+        assert!((protective[1].2.take_profit - 96.0).abs() < 1e-9);
+        assert!((protective[1].2.stop_loss - 102.0).abs() < 1e-9);
+
+        // Closed: remaining pair cancelled
+        sync_protective_orders(&broker, &mut order_manager, &risk_manager, &[]).await;
+        assert_eq!(broker.cancelled_orders().len(), 4);
+        assert!(order_manager.protected_symbols().is_empty());
+        assert!(!order_manager.has_protective_legs("AAPL"));
+    }
+
+    #[tokio::test]
+    async fn test_sync_leaves_bracketed_positions_alone() {
+        let broker = MockBroker::new(summary(100_000.0));
+        let risk_manager = RiskManager::new(RiskConfig::default());
+        let mut order_manager = OrderManager::new();
+        order_manager.record_protective_legs("MSFT", &[41, 42]);
+
+        let positions = vec![AccountPosition {
+            account: "MOCK".to_string(),
+            symbol: "MSFT".to_string(),
+            position: 10.0,
+            avg_cost: 300.0,
+            contract: Contract::stock("MSFT"),
+        }];
+        sync_protective_orders(&broker, &mut order_manager, &risk_manager, &positions).await;
+
+        assert!(broker.protective_orders().is_empty());
+        assert!(broker.cancelled_orders().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_finished_protective_legs_are_replaced() {
+        let broker = MockBroker::new(summary(100_000.0));
+        let risk_manager = RiskManager::new(RiskConfig::default());
+        let order_manager = Mutex::new(OrderManager::new());
+        order_manager
+            .lock()
+            .await
+            .record_protective_legs("MSFT", &[41, 42]);
+        let positions = vec![AccountPosition {
+            account: "MOCK".to_string(),
+            symbol: "MSFT".to_string(),
+            position: 10.0,
+            avg_cost: 300.0,
+            contract: Contract::stock("MSFT"),
+        }];
+
+        // One leg cancelled at the broker still leaves the other working
+        broker.set_order_status(41, OrderStatus::Cancelled);
+        reconcile_order_statuses(&broker, &order_manager)
+            .await
+            .unwrap();
+        assert!(order_manager.lock().await.has_protective_legs("MSFT"));
+
+        // Once neither leg works the position is protected afresh
+        broker.set_order_status(42, OrderStatus::Cancelled);
+        reconcile_order_statuses(&broker, &order_manager)
+            .await
+            .unwrap();
+        let mut order_manager = order_manager.into_inner();
+        assert!(!order_manager.has_protective_legs("MSFT"));
+        sync_protective_orders(&broker, &mut order_manager, &risk_manager, &positions).await;
+        assert_eq!(broker.protective_orders().len(), 1);
+        assert!(order_manager.has_protective_legs("MSFT"));
+    }
+
+    #[tokio::test]
+    async fn test_broker_side_cancel_reaches_order_manager() {
+        let broker = MockBroker::new(summary(100_000.0));
+        let mut strategy = BuyOnce {
+            symbols: vec!["AAPL".to_string(), "MSFT".to_string()],
+            positions: HashMap::new(),
+        };
+        let mut portfolio = Portfolio::new(100_000.0);
+        let mut risk_manager = RiskManager::new(RiskConfig::default());
+        let mut order_manager = OrderManager::new();
+        let signals = strategy.calculate_signals(&MarketDataHandler::new());
+        let prices = HashMap::from([("AAPL".to_string(), 100.0), ("MSFT".to_string(), 100.0)]);
+
+        let report = run_cycle(
+            TradingCycle {
+                broker: &broker,
+                strategy: &mut strategy,
+                portfolio: &mut portfolio,
+                risk_manager: &mut risk_manager,
+                order_manager: &mut order_manager,
+                risk_budgeter: None,
+                cost_model: None,
+                bracket_levels: HashMap::new(),
+            },
+            signals,
+            &prices,
+        )
+        .await
+        .unwrap();
+        let [cancelled, partial] = [0, 1].map(|i| &report.submitted[i]);
+        let order_manager = Mutex::new(order_manager);
+
+        // Nothing has changed at the broker yet
+        assert_eq!(
+            reconcile_order_statuses(&broker, &order_manager)
+                .await
+                .unwrap(),
+            0
+        );
+
+        let partial_fill = OrderStatus::PartiallyFilled {
+            filled_qty: 1.0,
+            remaining_qty: 1.0,
+        };
+        broker.set_order_status(cancelled.broker_order_id, OrderStatus::Cancelled);
+        broker.set_order_status(partial.broker_order_id, partial_fill.clone());
+        broker.set_order_status(99, OrderStatus::Filled); // Placed outside this session
+
+        let updated = reconcile_order_statuses(&broker, &order_manager)
+            .await
+            .unwrap();
+
+        assert_eq!(updated, 2);
+        {
+            let order_manager = order_manager.lock().await;
+            let status = |id: i32| order_manager.get_order(id).unwrap().status.clone();
+            assert_eq!(status(cancelled.order.id), OrderStatus::Cancelled);
+            assert_eq!(status(partial.order.id), partial_fill);
+        }
+        assert_eq!(
+            reconcile_order_statuses(&broker, &order_manager)
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_correlated_buy_records_correlation_decision() {
+        let broker = MockBroker::new(summary(100_000.0));
+        let mut strategy = MomentumStrategy::new(TradingConfig::default().strategy_config);
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.update_position("AAPL", 100.0, 150.0); // 15% of portfolio
+        let config = RiskConfig {
+            max_position_size: 20.0, // Percent of portfolio
+            max_cluster_exposure: 0.25,
+            ..RiskConfig::default()
+        };
+        let mut risk_manager = RiskManager::new(config.clone());
+        let mut order_manager = OrderManager::new();
+        let mut budgeter = RiskBudgeter::new(config, 0.15);
+        budgeter.update_correlation("AAPL", "MSFT", 0.90).unwrap();
+        budgeter.update_correlation("AAPL", "XOM", 0.10).unwrap();
+
+        let report = run_cycle(
+            TradingCycle {
+                broker: &broker,
+                strategy: &mut strategy,
+                portfolio: &mut portfolio,
+                risk_manager: &mut risk_manager,
+                order_manager: &mut order_manager,
+                risk_budgeter: Some(&budgeter),
+                cost_model: None,
+                bracket_levels: HashMap::new(),
+            },
+            vec![
+                signal("MSFT", "BUY", 50.0, 300.0), // Takes the AAPL cluster past 25%
+                signal("XOM", "BUY", 100.0, 100.0),
+            ],
+            &HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.decisions.len(), 2);
+        let msft = report.decision("MSFT").unwrap();
+        assert_eq!(msft.filtered_by, Some(FilteredBy::Correlation));
+        assert!(msft.detail.contains("limit 25.0%"));
+        assert!(report.decision("XOM").unwrap().is_acted());
+        let placed = broker.placed_orders();
+        assert_eq!(placed.len(), 1);
+        assert_eq!(placed[0].symbol, "XOM");
+    }
+
+    #[tokio::test]
+    async fn test_unconfirmed_order_blocks_duplicate_next_cycle() {
+        let broker = MockBroker::new(summary(100_000.0));
+        let mut strategy = MomentumStrategy::new(TradingConfig::default().strategy_config);
+        let mut portfolio = Portfolio::new(100_000.0);
+        let mut risk_manager = RiskManager::new(RiskConfig {
+            max_position_size: 20.0, // Percent of portfolio
+            ..RiskConfig::default()
+        });
+        let mut order_manager = OrderManager::new();
+
+        let mut cycle = async |order_manager: &mut OrderManager| {
+            run_cycle(
+                TradingCycle {
+                    broker: &broker,
+                    strategy: &mut strategy,
+                    portfolio: &mut portfolio,
+                    risk_manager: &mut risk_manager,
+                    order_manager,
+                    risk_budgeter: None,
+                    cost_model: None,
+                    bracket_levels: HashMap::new(),
+                },
+                vec![signal("AAPL", "BUY", 10.0, 150.0)],
+                &HashMap::new(),
+            )
+            .await
+            .unwrap()
+        };
+
+        let first = cycle(&mut order_manager).await;
+        let order_id = first.submitted[0].order.id;
+        assert!(first.decision("AAPL").unwrap().is_acted());
+
+        // TWS has not confirmed the fill: the repeat signal is held back
+        let second = cycle(&mut order_manager).await;
+        let decision = second.decision("AAPL").unwrap();
+        assert_eq!(decision.filtered_by, Some(FilteredBy::WorkingOrder));
+        assert!(decision.detail.contains(&format!("order #{}", order_id)));
+        assert!(second.submitted.is_empty());
+
+        order_manager.record_fill(order_id, 10.0, 150.0).unwrap();
+        let third = cycle(&mut order_manager).await;
+        assert!(third.decision("AAPL").unwrap().is_acted());
+        assert_eq!(broker.placed_orders().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pair_legs_dropped_together() {
+        let broker = MockBroker::new(summary(100_000.0));
+        let mut strategy = crate::pairs::PairsStrategy::new(crate::config::PairsConfig {
+            symbol_a: "KO".to_string(),
+            symbol_b: "PEP".to_string(),
+            lookback: 40,
+            entry_z: 2.0,
+            exit_z: 0.5,
+            leg_notional: 10_000.0,
+        });
+        let mut portfolio = Portfolio::new(100_000.0);
+        let mut risk_manager = RiskManager::new(RiskConfig {
+            shortable_symbols: vec!["KO".to_string()],
+            ..RiskConfig::default()
+        });
+        let mut order_manager = OrderManager::new();
+
+        let report = run_cycle(
+            TradingCycle {
+                broker: &broker,
+                strategy: &mut strategy,
+                portfolio: &mut portfolio,
+                risk_manager: &mut risk_manager,
+                order_manager: &mut order_manager,
+                risk_budgeter: None,
+                cost_model: None,
+                bracket_levels: HashMap::new(),
+            },
+            vec![
+                signal("KO", "BUY", 1.0, 60.0),
+                signal("PEP", "SELL", 1.0, 170.0), // Short without a borrow
+                signal("AAPL", "BUY", 1.0, 150.0), // Not part of the pair
+            ],
+            &HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        let placed: Vec<String> = broker
+            .placed_orders()
+            .into_iter()
+            .map(|signal| signal.symbol)
+            .collect();
+        assert_eq!(placed, vec!["AAPL"]);
+        assert_eq!(
+            report.decision("PEP").unwrap().filtered_by,
+            Some(FilteredBy::ShortBorrow)
+        );
+        assert_eq!(
+            report.decision("KO").unwrap().filtered_by,
+            Some(FilteredBy::PairedLeg)
+        );
+        assert!(order_manager.working_order("KO").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_risk_reduction_cancels_working_order() {
+        let broker = MockBroker::new(summary(1_000.0));
+        let mut strategy = MomentumStrategy::new(TradingConfig::default().strategy_config);
+        let mut portfolio = Portfolio::new(1_000.0);
+        portfolio.update_position("AAPL", 10.0, 150.0);
+        let mut risk_manager = RiskManager::new(RiskConfig::default());
+        let mut order_manager = OrderManager::new();
+        let working = order_manager
+            .validate_and_create_order(
+                signal("AAPL", "BUY", 1.0, 150.0),
+                &portfolio,
+                &summary(1_000_000.0),
+                1.0,
+                1.0,
+            )
+            .unwrap()
+            .id;
+        order_manager.record_broker_order_id(working, 77);
+
+        let report = run_cycle(
+            TradingCycle {
+                broker: &broker,
+                strategy: &mut strategy,
+                portfolio: &mut portfolio,
+                risk_manager: &mut risk_manager,
+                order_manager: &mut order_manager,
+                risk_budgeter: None,
+                cost_model: None,
+                bracket_levels: HashMap::new(),
+            },
+            vec![signal("AAPL", "SELL", 2.0, 150.0)],
+            &HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        assert!(report.risk_reduction_only);
+        assert!(broker.cancelled_orders().contains(&77));
+        assert_eq!(
+            order_manager.get_order(working).unwrap().status,
+            OrderStatus::Cancelled
+        );
+        assert_eq!(broker.placed_orders()[0].action, "SELL");
+        assert!(report.decision("AAPL").unwrap().is_acted());
+    }
+}
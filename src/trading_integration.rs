@@ -5,14 +5,15 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use crate::config::RiskConfig;
+use crate::costs::CostModel;
 use crate::orders::OrderSignal;
 use crate::portfolio::Portfolio;
 use crate::position_inertia::{InertiaConfig, InertiaDecision, PositionInertiaCalculator};
-use crate::security_types::SecurityType;
 use crate::transaction_cost::{TransactionCostCalculator, TransactionCostConfig};
 
 pub struct TradingIntegrationLayer {
     transaction_cost_calculator: Arc<Mutex<TransactionCostCalculator>>,
+    cost_model: CostModel, // Commissions, shared with fill cost estimates
     position_inertia_calculator: Arc<Mutex<PositionInertiaCalculator>>,
     enable_transaction_cost_optimization: bool,
     enable_position_inertia: bool,
@@ -28,20 +29,17 @@ pub struct SignalFilterResult {
 }
 
 impl TradingIntegrationLayer {
-    pub fn new(risk_config: &RiskConfig) -> Self {
+    /// Commissions come from `cost_model`; spread and market impact from
+    /// the transaction cost calculator
+    pub fn new(risk_config: &RiskConfig, cost_model: CostModel) -> Self {
         // Create transaction cost configuration
         let mut bid_ask_spreads = HashMap::new();
         bid_ask_spreads.insert("DEFAULT".to_string(), 0.0010); // Default 0.10% spread
 
-        let mut commission_rates = HashMap::new();
-        commission_rates.insert(SecurityType::Stock, risk_config.stock_commission);
-        commission_rates.insert(SecurityType::Future, risk_config.futures_commission);
-        commission_rates.insert(SecurityType::Forex, risk_config.forex_commission);
-
         let transaction_cost_config = TransactionCostConfig {
             bid_ask_spreads,
-            commission_rates,
-            market_impact_threshold: 0.01, // 1% of daily volume
+            commission_rates: HashMap::new(), // Unused: the cost model charges commissions
+            market_impact_threshold: 0.01,    // 1% of daily volume
             market_impact_coefficient: 0.5,
         };
 
@@ -62,6 +60,7 @@ impl TradingIntegrationLayer {
 
         Self {
             transaction_cost_calculator,
+            cost_model,
             position_inertia_calculator,
             enable_transaction_cost_optimization: risk_config.enable_transaction_cost_optimization,
             enable_position_inertia: risk_config.enable_position_inertia,
@@ -269,22 +268,31 @@ impl TradingIntegrationLayer {
     ) -> Result<f64> {
         let cost_calculator = self.transaction_cost_calculator.lock().await;
 
-        let security_type = match signal.symbol.contains("USD") || signal.symbol.contains(".") {
-            true => SecurityType::Forex,
-            false => SecurityType::Stock, // Default to stock for now
-        };
+        let security_type = &signal.security_info.security_type;
+        let quantity = signal.quantity.abs();
+        if quantity == 0.0 {
+            return Ok(0.0);
+        }
 
         let daily_volume = 1_000_000.0; // Default daily volume - should be fetched from market data
 
-        let total_cost = cost_calculator.calculate_total_cost(
+        let spread_cost = cost_calculator.calculate_spread_cost(
+            &signal.symbol,
+            security_type,
+            quantity,
+            *current_price,
+        )?;
+        let market_impact_cost = cost_calculator.calculate_market_impact_cost(
             &signal.symbol,
-            &security_type,
-            signal.quantity.abs(),
+            quantity,
             *current_price,
             daily_volume,
         )?;
+        let commission_cost =
+            self.cost_model
+                .commission_for(&signal.symbol, security_type, quantity, *current_price);
 
-        Ok(total_cost)
+        Ok(spread_cost + commission_cost + market_impact_cost)
     }
 
     /// Validate final order before execution
@@ -0,0 +1,546 @@
+//! Walk-forward optimization of strategy parameters
+//!
+//! Each window of a `SplitSchedule` backtests every `ParameterSet` of a
+//! `ParameterGrid` on the in-sample bars, keeps the one with the best Sharpe
+//! ratio and reports how it does on the following out-of-sample bars. The
+//! backtest is any function from parameters and a bar range to period
+//! returns; `MomentumBacktest` replays bars through `MomentumStrategy`.
+
+use crate::backtest::{BacktestResult, BacktestTrade};
+use crate::clock::MockClock;
+use crate::config::{StrategyConfig, security_id};
+use crate::market_data::MarketDataHandler;
+use crate::momentum::MomentumStrategy;
+use crate::orders::OrderSignal;
+use crate::signals::SignalWeights;
+use crate::stats::sharpe_ratio;
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use log::{debug, info};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Equity `MomentumBacktest::returns` starts from; the strategy sizes
+/// positions off a $100,000 portfolio
+const INITIAL_EQUITY: f64 = 100_000.0;
+
+/// One candidate configuration
+#[derive(Debug, Clone)]
+pub struct ParameterSet {
+    pub lookback_period: usize,
+    pub momentum_threshold: f64,
+    pub signal_weights: SignalWeights,
+}
+
+impl ParameterSet {
+    /// `config` with this set's values in place of its own
+    pub fn apply(&self, config: &StrategyConfig) -> StrategyConfig {
+        StrategyConfig {
+            lookback_period: self.lookback_period,
+            momentum_threshold: self.momentum_threshold,
+            signal_weights: self.signal_weights.clone(),
+            ..config.clone()
+        }
+    }
+}
+
+/// Values to sweep; every combination is tried
+#[derive(Debug, Clone)]
+pub struct ParameterGrid {
+    pub lookback_periods: Vec<usize>,
+    pub momentum_thresholds: Vec<f64>,
+    pub signal_weights: Vec<SignalWeights>,
+}
+
+impl ParameterGrid {
+    pub fn combinations(&self) -> Vec<ParameterSet> {
+        let mut sets = Vec::new();
+        for &lookback_period in &self.lookback_periods {
+            for &momentum_threshold in &self.momentum_thresholds {
+                for signal_weights in &self.signal_weights {
+                    sets.push(ParameterSet {
+                        lookback_period,
+                        momentum_threshold,
+                        signal_weights: signal_weights.clone(),
+                    });
+                }
+            }
+        }
+        sets
+    }
+}
+
+/// Rolling train/test split over bar indices
+#[derive(Debug, Clone, Copy)]
+pub struct SplitSchedule {
+    pub train_bars: usize,
+    pub test_bars: usize,
+    pub step_bars: usize, // Distance between window starts
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitWindow {
+    pub train: Range<usize>,
+    pub test: Range<usize>,
+}
+
+impl SplitSchedule {
+    /// Windows whose test range fits within `total_bars`
+    pub fn windows(&self, total_bars: usize) -> Vec<SplitWindow> {
+        let mut windows = Vec::new();
+        if self.train_bars == 0 || self.test_bars == 0 || self.step_bars == 0 {
+            return windows;
+        }
+        let mut start = 0;
+        while start + self.train_bars + self.test_bars <= total_bars {
+            let split = start + self.train_bars;
+            windows.push(SplitWindow {
+                train: start..split,
+                test: split..split + self.test_bars,
+            });
+            start += self.step_bars;
+        }
+        windows
+    }
+}
+
+/// Out-of-sample performance of one window's chosen parameters
+#[derive(Debug, Clone)]
+pub struct OutOfSampleMetrics {
+    pub sharpe_ratio: f64,
+    pub total_return: f64,
+    pub periods: usize,
+}
+
+impl OutOfSampleMetrics {
+    fn from_returns(returns: &[f64], periods_per_year: f64) -> Self {
+        Self {
+            sharpe_ratio: sharpe_or_zero(returns, periods_per_year),
+            total_return: returns.iter().fold(1.0, |acc, r| acc * (1.0 + r)) - 1.0,
+            periods: returns.len(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WindowResult {
+    pub window: SplitWindow,
+    pub params: ParameterSet,
+    pub in_sample_sharpe: f64,
+    pub out_of_sample: OutOfSampleMetrics,
+}
+
+#[derive(Debug, Clone)]
+pub struct WalkForwardReport {
+    pub windows: Vec<WindowResult>,
+    /// All out-of-sample windows stitched together
+    pub out_of_sample: OutOfSampleMetrics,
+}
+
+pub struct WalkForward {
+    pub grid: ParameterGrid,
+    pub schedule: SplitSchedule,
+    pub periods_per_year: f64,
+}
+
+impl WalkForward {
+    pub fn new(grid: ParameterGrid, schedule: SplitSchedule) -> Self {
+        Self {
+            grid,
+            schedule,
+            periods_per_year: 252.0,
+        }
+    }
+
+    /// Optimize on each in-sample window and evaluate out of sample
+    ///
+    /// `backtest` returns the strategy's period returns over a bar range.
+    pub fn run<F>(&self, total_bars: usize, backtest: F) -> Result<WalkForwardReport>
+    where
+        F: Fn(&ParameterSet, Range<usize>) -> Result<Vec<f64>>,
+    {
+        let candidates = self.grid.combinations();
+        if candidates.is_empty() {
+            bail!("Walk-forward parameter grid is empty");
+        }
+        let splits = self.schedule.windows(total_bars);
+        if splits.is_empty() {
+            bail!(
+                "{} bars is too short for a {}-bar train / {}-bar test split",
+                total_bars,
+                self.schedule.train_bars,
+                self.schedule.test_bars
+            );
+        }
+
+        let mut windows = Vec::with_capacity(splits.len());
+        let mut stitched = Vec::new();
+        for window in splits {
+            let mut best: Option<(f64, &ParameterSet)> = None;
+            for params in &candidates {
+                let returns = backtest(params, window.train.clone())?;
+                let sharpe = sharpe_or_zero(&returns, self.periods_per_year);
+                debug!(
+                    "In-sample {:?} {:?}: Sharpe {:.3}",
+                    window.train, params, sharpe
+                );
+                if best.is_none_or(|(best_sharpe, _)| sharpe > best_sharpe) {
+                    best = Some((sharpe, params));
+                }
+            }
+            let (in_sample_sharpe, params) = best.expect("grid is not empty");
+
+            let returns = backtest(params, window.test.clone())?;
+            let out_of_sample = OutOfSampleMetrics::from_returns(&returns, self.periods_per_year);
+            info!(
+                "Walk-forward window {:?}: lookback {}, threshold {:.4}, IS Sharpe {:.3}, OOS Sharpe {:.3}",
+                window.test,
+                params.lookback_period,
+                params.momentum_threshold,
+                in_sample_sharpe,
+                out_of_sample.sharpe_ratio
+            );
+            stitched.extend(returns);
+            windows.push(WindowResult {
+                window,
+                params: params.clone(),
+                in_sample_sharpe,
+                out_of_sample,
+            });
+        }
+
+        Ok(WalkForwardReport {
+            windows,
+            out_of_sample: OutOfSampleMetrics::from_returns(&stitched, self.periods_per_year),
+        })
+    }
+}
+
+/// Sharpe ratio with no risk-free rate; undefined cases (no returns, no
+/// variance) score zero so a strategy that never trades ranks as neutral
+fn sharpe_or_zero(returns: &[f64], periods_per_year: f64) -> f64 {
+    sharpe_ratio(returns, 0.0, periods_per_year).unwrap_or(0.0)
+}
+
+/// Replay of aligned bars through `MomentumStrategy::calculate_signals`
+///
+/// Each `ParameterSet` overrides the threshold, lookback and signal weights
+/// of `config`. Bars before the tested range are loaded as warm-up history,
+/// as at startup; each bar in the range then arrives as a real-time tick,
+/// the strategy's signals fill in full at that close, and positions are
+/// marked to market at the next one.
+pub struct MomentumBacktest {
+    pub config: StrategyConfig,
+    pub timestamps: Vec<DateTime<Utc>>,
+    /// Closes by security id, index-aligned with `timestamps`
+    pub closes: HashMap<String, Vec<f64>>,
+}
+
+impl MomentumBacktest {
+    pub fn new(
+        config: StrategyConfig,
+        timestamps: Vec<DateTime<Utc>>,
+        closes: HashMap<String, Vec<f64>>,
+    ) -> Result<Self> {
+        for symbol in config.tradable_security_ids() {
+            match closes.get(&symbol) {
+                Some(series) if series.len() == timestamps.len() => {}
+                Some(series) => bail!(
+                    "{} closes for {} but {} timestamps",
+                    series.len(),
+                    symbol,
+                    timestamps.len()
+                ),
+                None => bail!("No closes for {}", symbol),
+            }
+        }
+        Ok(Self {
+            config,
+            timestamps,
+            closes,
+        })
+    }
+
+    /// Strategy returns for the bars in `bars`, using only prices up to each
+    /// decision point
+    pub fn returns(&self, params: &ParameterSet, bars: Range<usize>) -> Result<Vec<f64>> {
+        Ok(self.run(params, bars, INITIAL_EQUITY)?.returns())
+    }
+
+    /// Equity curve and round trips over `bars`, starting from `initial_equity`
+    ///
+    /// Trades enter and exit at bar closes; one still open at the end of the
+    /// range is closed on its last bar.
+    pub fn run(
+        &self,
+        params: &ParameterSet,
+        bars: Range<usize>,
+        initial_equity: f64,
+    ) -> Result<BacktestResult> {
+        if bars.end > self.timestamps.len() {
+            bail!(
+                "Bar range {:?} exceeds {} bars",
+                bars,
+                self.timestamps.len()
+            );
+        }
+        let mut result = BacktestResult::new();
+        if bars.start >= bars.end {
+            return Ok(result);
+        }
+
+        let config = params.apply(&self.config);
+        let clock = MockClock::new(self.timestamps[bars.start]);
+        let mut market_data = self.market_data(&config, &clock, bars.start)?;
+        let mut strategy = MomentumStrategy::new(config);
+
+        let mut equity = initial_equity;
+        let mut positions: HashMap<String, f64> = HashMap::new();
+        let mut open: HashMap<String, OpenTrade> = HashMap::new();
+        for t in bars.clone() {
+            let timestamp = self.timestamps[t];
+            clock.set(timestamp);
+            if t > bars.start {
+                for (symbol, &quantity) in &positions {
+                    let pnl = self.mark_to_market(&market_data, symbol, quantity, t);
+                    equity += pnl;
+                    if let Some(trade) = open.get_mut(symbol) {
+                        trade.pnl += pnl;
+                    }
+                }
+            }
+            result.record_equity(timestamp, equity);
+
+            for (symbol, closes) in &self.closes {
+                market_data.update_realtime_data_at(symbol, closes[t], 0, timestamp);
+            }
+            if t + 1 == bars.end {
+                break;
+            }
+            for signal in strategy.calculate_signals(&market_data) {
+                let held = positions.get(&signal.symbol).copied().unwrap_or(0.0);
+                let target = held + signed_quantity(&signal);
+                strategy.update_position(&signal.symbol, target);
+                if target == 0.0 {
+                    positions.remove(&signal.symbol);
+                    if let Some(trade) = open.remove(&signal.symbol) {
+                        result.record_trade(self.trade(&signal.symbol, trade, t));
+                    }
+                } else {
+                    positions.insert(signal.symbol.clone(), target);
+                    open.entry(signal.symbol.clone()).or_insert(OpenTrade {
+                        entry_bar: t,
+                        pnl: 0.0,
+                    });
+                }
+            }
+        }
+        for (symbol, trade) in open {
+            result.record_trade(self.trade(&symbol, trade, bars.end - 1));
+        }
+        Ok(result)
+    }
+
+    /// Handler set up as at startup, with the bars before `warm_up_bars` as
+    /// history
+    fn market_data(
+        &self,
+        config: &StrategyConfig,
+        clock: &MockClock,
+        warm_up_bars: usize,
+    ) -> Result<MarketDataHandler> {
+        let mut market_data = MarketDataHandler::new();
+        market_data.set_clock(Arc::new(clock.clone()));
+        market_data.apply_strategy_settings(config);
+        for (req_id, security) in config.securities.iter().enumerate() {
+            let symbol = security_id(&config.securities, security);
+            market_data.register_security(symbol.clone(), security.security_info());
+            market_data.set_return_mode(&symbol, security.return_mode);
+            market_data.register_symbol(req_id as i32, symbol);
+        }
+        for (symbol, closes) in &self.closes {
+            for (timestamp, &close) in self.timestamps.iter().zip(closes).take(warm_up_bars) {
+                let timestamp = time::OffsetDateTime::from_unix_timestamp(timestamp.timestamp())?;
+                market_data.add_historical_price(symbol, timestamp, close);
+            }
+        }
+        Ok(market_data)
+    }
+
+    /// Change in `quantity`'s value from bar `t - 1` to bar `t`
+    fn mark_to_market(
+        &self,
+        market_data: &MarketDataHandler,
+        symbol: &str,
+        quantity: f64,
+        t: usize,
+    ) -> f64 {
+        let closes = &self.closes[symbol];
+        match market_data.get_security_info(symbol) {
+            Some(info) => {
+                info.get_position_value(closes[t], quantity)
+                    - info.get_position_value(closes[t - 1], quantity)
+            }
+            None => quantity * (closes[t] - closes[t - 1]),
+        }
+    }
+
+    fn trade(&self, symbol: &str, trade: OpenTrade, exit_bar: usize) -> BacktestTrade {
+        let closes = &self.closes[symbol];
+        BacktestTrade {
+            entry_time: self.timestamps[trade.entry_bar],
+            exit_time: self.timestamps[exit_bar],
+            entry_price: closes[trade.entry_bar],
+            exit_price: closes[exit_bar],
+            pnl: trade.pnl,
+        }
+    }
+}
+
+/// Position opened at `entry_bar` with its P&L so far
+struct OpenTrade {
+    entry_bar: usize,
+    pnl: f64,
+}
+
+fn signed_quantity(signal: &OrderSignal) -> f64 {
+    if signal.action == "SELL" {
+        -signal.quantity
+    } else {
+        signal.quantity
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::config::{SecurityConfigBuilder, TradingConfig};
+    use chrono::{Duration, TimeZone};
+
+    /// Uptrend with alternating +1.5% / -0.5% bars
+    fn trending_prices(len: usize) -> Vec<f64> {
+        let mut prices = vec![100.0];
+        for i in 1..len {
+            let r = if i % 2 == 0 { 0.015 } else { -0.005 };
+            prices.push(prices[i - 1] * (1.0 + r));
+        }
+        prices
+    }
+
+    /// One stock replayed over daily `prices`
+    pub(crate) fn daily_backtest(prices: Vec<f64>) -> MomentumBacktest {
+        let mut config = TradingConfig::default().strategy_config;
+        config.securities = vec![SecurityConfigBuilder::stock("TEST").build().unwrap()];
+        // A steady trend looks overbought to Bollinger; trade on momentum alone
+        config.signal_consensus_threshold = 0.0;
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 21, 0, 0).unwrap();
+        let timestamps = (0..prices.len())
+            .map(|day| start + Duration::days(day as i64))
+            .collect();
+        let closes = HashMap::from([("TEST".to_string(), prices)]);
+        MomentumBacktest::new(config, timestamps, closes).unwrap()
+    }
+
+    fn momentum_only() -> SignalWeights {
+        SignalWeights {
+            momentum: 1.0,
+            breakout: 0.0,
+            ..SignalWeights::default()
+        }
+    }
+
+    #[test]
+    fn test_split_schedule_windows() {
+        let schedule = SplitSchedule {
+            train_bars: 50,
+            test_bars: 20,
+            step_bars: 20,
+        };
+        let windows = schedule.windows(120);
+        assert_eq!(
+            windows,
+            vec![
+                SplitWindow {
+                    train: 0..50,
+                    test: 50..70
+                },
+                SplitWindow {
+                    train: 20..70,
+                    test: 70..90
+                },
+                SplitWindow {
+                    train: 40..90,
+                    test: 90..110
+                },
+            ]
+        );
+        assert!(schedule.windows(60).is_empty());
+    }
+
+    #[test]
+    fn test_walk_forward_picks_obviously_best_threshold() {
+        let backtest = daily_backtest(trending_prices(200));
+        let grid = ParameterGrid {
+            lookback_periods: vec![10],
+            // Composites are capped at 20, so a threshold of 25 never trades
+            momentum_thresholds: vec![25.0, 0.0],
+            signal_weights: vec![momentum_only()],
+        };
+        let walk_forward = WalkForward::new(
+            grid,
+            SplitSchedule {
+                train_bars: 80,
+                test_bars: 40,
+                step_bars: 40,
+            },
+        );
+
+        let report = walk_forward
+            .run(backtest.timestamps.len(), |params, bars| {
+                backtest.returns(params, bars)
+            })
+            .unwrap();
+
+        assert_eq!(report.windows.len(), 3);
+        for window in &report.windows {
+            assert_eq!(window.params.momentum_threshold, 0.0);
+            assert!(window.in_sample_sharpe > 0.0);
+            assert!(window.out_of_sample.total_return > 0.0);
+            assert_eq!(window.out_of_sample.periods, 39);
+        }
+        assert_eq!(report.out_of_sample.periods, 3 * 39);
+        assert!(report.out_of_sample.sharpe_ratio > 0.0);
+    }
+
+    #[test]
+    fn test_walk_forward_rejects_empty_inputs() {
+        let empty = ParameterGrid {
+            lookback_periods: vec![10],
+            momentum_thresholds: vec![],
+            signal_weights: vec![momentum_only()],
+        };
+        let schedule = SplitSchedule {
+            train_bars: 80,
+            test_bars: 40,
+            step_bars: 40,
+        };
+        let no_returns = |_: &ParameterSet, _: Range<usize>| Ok(Vec::new());
+        assert!(
+            WalkForward::new(empty, schedule)
+                .run(200, no_returns)
+                .is_err()
+        );
+
+        let grid = ParameterGrid {
+            lookback_periods: vec![10],
+            momentum_thresholds: vec![0.0],
+            signal_weights: vec![momentum_only()],
+        };
+        assert!(
+            WalkForward::new(grid, schedule)
+                .run(100, no_returns)
+                .is_err()
+        );
+    }
+}
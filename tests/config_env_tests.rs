@@ -0,0 +1,46 @@
+// Environment overrides for TWS connection settings
+//
+// Kept in its own test binary with a single test: environment variables are
+// process-wide, so setting them must not race other config loads.
+
+use algotrading::config::TradingConfig;
+
+#[test]
+fn test_env_overrides_file_values() {
+    let mut config = TradingConfig::default();
+    config.tws_config.host = "file-host".to_string();
+    config.tws_config.port = 7497;
+    config.tws_config.client_id = 1;
+
+    let path = std::env::temp_dir().join(format!("config_env_{}.json", std::process::id()));
+    std::fs::write(&path, serde_json::to_string(&config).unwrap()).unwrap();
+    let path_str = path.to_str().unwrap();
+
+    let loaded = TradingConfig::load_from_file(path_str).unwrap();
+    assert_eq!(loaded.tws_config.host, "file-host");
+    assert_eq!(loaded.tws_config.port, 7497);
+    assert_eq!(loaded.tws_config.client_id, 1);
+
+    unsafe {
+        std::env::set_var("TWS_HOST", "10.0.0.5");
+        std::env::set_var("TWS_PORT", "4001");
+        std::env::set_var("TWS_CLIENT_ID", "42");
+    }
+    let loaded = TradingConfig::load_from_file(path_str).unwrap();
+    assert_eq!(loaded.tws_config.host, "10.0.0.5");
+    assert_eq!(loaded.tws_config.port, 4001);
+    assert_eq!(loaded.tws_config.client_id, 42);
+
+    unsafe {
+        std::env::set_var("TWS_PORT", "not-a-port");
+    }
+    let error = TradingConfig::load_from_file(path_str).unwrap_err();
+    assert!(error.to_string().contains("TWS_PORT"), "{}", error);
+
+    unsafe {
+        std::env::remove_var("TWS_HOST");
+        std::env::remove_var("TWS_PORT");
+        std::env::remove_var("TWS_CLIENT_ID");
+    }
+    std::fs::remove_file(&path).unwrap();
+}
@@ -2,15 +2,24 @@
 // These tests capture current momentum.rs behavior before refactoring
 
 use anyhow::Result;
+use std::collections::HashMap;
 
-use algotrading::config::{SecurityConfig, StrategyConfig};
-use algotrading::market_data::MarketDataHandler;
+use algotrading::config::{SecurityConfig, ShareRounding, SizingMode, StrategyConfig};
+use algotrading::market_data::{
+    AggregationMethod, DataGapConfig, MarketDataHandler, ReturnMode, ReturnOutlierThresholds,
+    VolatilityEstimator,
+};
 use algotrading::momentum::MomentumStrategy;
 use algotrading::security_types::SecurityType;
+use algotrading::signals::SignalWeights;
 
 // Test helper for creating test strategy
 fn create_test_strategy() -> MomentumStrategy {
-    let strategy_config = StrategyConfig {
+    MomentumStrategy::new(create_test_strategy_config())
+}
+
+fn create_test_strategy_config() -> StrategyConfig {
+    StrategyConfig {
         securities: vec![
             SecurityConfig {
                 symbol: "AAPL".to_string(),
@@ -18,6 +27,13 @@ fn create_test_strategy() -> MomentumStrategy {
                 exchange: "NASDAQ".to_string(),
                 currency: "USD".to_string(),
                 futures_specs: None,
+                extended_hours_data: false,
+                outside_rth_orders: false,
+                return_mode: ReturnMode::Simple,
+                trade: true,
+                commission_per_unit: None,
+                commission_min: None,
+                multiplier: None,
             },
             SecurityConfig {
                 symbol: "GOOGL".to_string(),
@@ -25,6 +41,13 @@ fn create_test_strategy() -> MomentumStrategy {
                 exchange: "NASDAQ".to_string(),
                 currency: "USD".to_string(),
                 futures_specs: None,
+                extended_hours_data: false,
+                outside_rth_orders: false,
+                return_mode: ReturnMode::Simple,
+                trade: true,
+                commission_per_unit: None,
+                commission_min: None,
+                multiplier: None,
             },
             SecurityConfig {
                 symbol: "EURUSD".to_string(),
@@ -32,9 +55,17 @@ fn create_test_strategy() -> MomentumStrategy {
                 exchange: "IDEALPRO".to_string(),
                 currency: "USD".to_string(),
                 futures_specs: None,
+                extended_hours_data: false,
+                outside_rth_orders: false,
+                return_mode: ReturnMode::Simple,
+                trade: true,
+                commission_per_unit: None,
+                commission_min: None,
+                multiplier: None,
             },
         ],
         lookback_period: 20,
+        lookback_periods: HashMap::new(),
         momentum_threshold: 0.5,
         position_size: 1000.0,
         rebalance_frequency_minutes: 60,
@@ -42,9 +73,42 @@ fn create_test_strategy() -> MomentumStrategy {
         volatility_halflife: 32.0,
         use_limit_orders: true,
         limit_order_offset: 0.01,
-    };
-
-    MomentumStrategy::new(strategy_config)
+        volatility_estimator: VolatilityEstimator::Simple,
+        max_data_age_seconds: 300,
+        data_gaps: DataGapConfig::default(),
+        return_outliers: ReturnOutlierThresholds::default(),
+        signal_smoothing: 0.0,
+        exit_hysteresis: 0.0,
+        futures_roll_window_days: 5,
+        max_tick_deviation: 0.5,
+        use_volume_weighted_momentum: false,
+        bracket_orders: None,
+        order_chase: None,
+        max_positions: 5,
+        rotation_margin: 0.0,
+        risk_free_rate: 0.0,
+        trading_calendar: None,
+        signal_quality_threshold: 1.0,
+        signal_consensus_threshold: 0.67,
+        min_timeframe_consensus: 0.0,
+        rebalance_schedule: None,
+        signal_weights: SignalWeights {
+            momentum: 0.5,
+            breakout: 0.3,
+            carry: 0.0,
+            mean_reversion: 0.2,
+            ..SignalWeights::default()
+        },
+        fractional_shares: false,
+        share_rounding: ShareRounding::Round,
+        rank_normalization: false,
+        sizing_mode: SizingMode::Lots,
+        min_bars_for_signals: None,
+        max_warmup_seconds: 60,
+        max_spread_bps: None,
+        min_average_volume: 0.0,
+        aggregation_method: AggregationMethod::default(),
+    }
 }
 
 // Test helper for creating test market data handler with realistic data
@@ -466,4 +530,78 @@ mod order_generation_tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_stale_market_data_generates_no_signals() -> Result<()> {
+        use chrono::{Duration, Utc};
+
+        let mut strategy = create_test_strategy();
+        let mut market_data = create_test_market_data();
+
+        // Last updates arrived 10 minutes ago, beyond the 5 minute max age
+        let stale_time = Utc::now() - Duration::minutes(10);
+        for symbol in ["AAPL", "GOOGL", "EURUSD"] {
+            let price = market_data.get_market_data(symbol).unwrap().last_price;
+            market_data.update_realtime_data_at(symbol, price, 1000, stale_time);
+        }
+        strategy.update_position("AAPL", 100.0);
+
+        let signals = strategy.calculate_signals(&market_data);
+        assert!(
+            signals.is_empty(),
+            "Should not trade or exit on stale data, got {:?}",
+            signals
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_price_generates_no_signal() -> Result<()> {
+        let mut strategy = create_test_strategy();
+        let mut market_data = create_test_market_data();
+
+        // Re-registering resets AAPL to the warm-up state: history but no live price
+        market_data.register_symbol(0, "AAPL".to_string());
+        assert_eq!(market_data.get_market_data("AAPL").unwrap().last_price, 0.0);
+        // A NaN tick is rejected before it reaches the strategy
+        assert!(!market_data.update_realtime_data("AAPL", f64::NAN, 1000));
+        strategy.update_position("AAPL", 100.0);
+
+        let signals = strategy.calculate_signals(&market_data);
+        assert!(
+            signals.iter().all(|s| s.symbol != "AAPL"),
+            "Should not trade AAPL without a price, got {:?}",
+            signals
+        );
+        assert!(
+            signals
+                .iter()
+                .all(|s| s.quantity.is_finite() && s.price.is_finite())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_data_only_security_never_signals() -> Result<()> {
+        let market_data = create_test_market_data();
+        let googl_signals = |config: StrategyConfig| {
+            let mut strategy = MomentumStrategy::new(config);
+            strategy.update_position("GOOGL", 10.0);
+            let signals = strategy.calculate_signals(&market_data);
+            signals.iter().filter(|s| s.symbol == "GOOGL").count()
+        };
+        assert_eq!(googl_signals(create_test_strategy_config()), 1);
+
+        // As a benchmark GOOGL keeps its data but is never scored or sized
+        let mut config = create_test_strategy_config();
+        config.securities[1].trade = false;
+        assert_eq!(config.tradable_security_ids(), ["AAPL", "EURUSD"]);
+        assert_eq!(googl_signals(config), 0);
+        assert!(market_data.get_market_data("GOOGL").is_some());
+        assert!(market_data.get_price_history("GOOGL").is_some());
+
+        Ok(())
+    }
 }
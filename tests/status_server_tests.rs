@@ -0,0 +1,120 @@
+// Status endpoint integration tests (run with --features status-server)
+#![cfg(feature = "status-server")]
+
+use algotrading::config::{RiskConfig, StatusServerConfig};
+use algotrading::metrics::Metrics;
+use algotrading::portfolio::Portfolio;
+use algotrading::risk::RiskManager;
+use algotrading::status_server::{StatusState, spawn_status_server};
+use chrono::Utc;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+async fn start_seeded_server() -> SocketAddr {
+    let mut portfolio = Portfolio::new(100_000.0);
+    portfolio.update_position("AAPL", 100.0, 150.0);
+    portfolio.update_position("MSFT", 50.0, 300.0);
+    portfolio.update_market_prices(&HashMap::from([
+        ("AAPL".to_string(), 155.0),
+        ("MSFT".to_string(), 290.0),
+    ]));
+
+    let mut risk_manager = RiskManager::new(RiskConfig::default());
+    risk_manager.update_daily_pnl(100_000.0, Utc::now());
+    risk_manager.update_daily_pnl(99_000.0, Utc::now());
+
+    let metrics = Metrics::new();
+    metrics.inc_orders_placed();
+    metrics.set_open_positions(2);
+
+    let state = StatusState {
+        portfolio: Arc::new(Mutex::new(portfolio)),
+        risk_manager: Arc::new(Mutex::new(risk_manager)),
+        metrics: Arc::new(metrics),
+    };
+    let config = StatusServerConfig {
+        enabled: true,
+        bind_address: "127.0.0.1".to_string(),
+        port: 0,
+    };
+
+    spawn_status_server(&config, state).await.unwrap()
+}
+
+async fn get(address: SocketAddr, path: &str) -> (String, String) {
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    stream
+        .write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).as_bytes())
+        .await
+        .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    (head.lines().next().unwrap().to_string(), body.to_string())
+}
+
+#[tokio::test]
+async fn test_status_endpoint_json_shape() {
+    let address = start_seeded_server().await;
+
+    let (status_line, body) = get(address, "/status").await;
+    assert_eq!(status_line, "HTTP/1.1 200 OK");
+
+    let status: Value = serde_json::from_str(&body).unwrap();
+    assert!(status["timestamp"].is_string());
+    assert_eq!(status["trading_halted"], Value::Bool(false));
+    assert!((status["drawdown"].as_f64().unwrap() - 0.01).abs() < 1e-9);
+
+    let portfolio = &status["portfolio"];
+    for field in [
+        "total_value",
+        "cash_balance",
+        "total_unrealized_pnl",
+        "total_realized_pnl",
+    ] {
+        assert!(portfolio[field].is_number(), "missing {}", field);
+    }
+    assert_eq!(portfolio["positions_count"], 2);
+
+    let positions = status["positions"].as_array().unwrap();
+    assert_eq!(positions.len(), 2);
+    assert_eq!(positions[0]["symbol"], "AAPL");
+    assert_eq!(positions[0]["unrealized_pnl"], 500.0);
+}
+
+#[tokio::test]
+async fn test_positions_endpoint_and_unknown_path() {
+    let address = start_seeded_server().await;
+
+    let (status_line, body) = get(address, "/positions").await;
+    assert_eq!(status_line, "HTTP/1.1 200 OK");
+    let positions: Value = serde_json::from_str(&body).unwrap();
+    let symbols: Vec<&str> = positions
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["symbol"].as_str().unwrap())
+        .collect();
+    assert_eq!(symbols, vec!["AAPL", "MSFT"]);
+    assert_eq!(positions[1]["quantity"], 50.0);
+
+    let (status_line, _) = get(address, "/orders").await;
+    assert_eq!(status_line, "HTTP/1.1 404 Not Found");
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint_prometheus_text() {
+    let address = start_seeded_server().await;
+
+    let (status_line, body) = get(address, "/metrics").await;
+    assert_eq!(status_line, "HTTP/1.1 200 OK");
+    assert!(body.contains("# TYPE algotrading_orders_placed_total counter\n"));
+    assert!(body.contains("algotrading_orders_placed_total 1\n"));
+    assert!(body.contains("algotrading_open_positions 2\n"));
+}
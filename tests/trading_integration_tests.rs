@@ -1,4 +1,5 @@
-use algotrading::config::RiskConfig;
+use algotrading::config::{RiskConfig, StopLossMethod};
+use algotrading::costs::{CostModel, CostModelConfig};
 use algotrading::orders::OrderSignal;
 use algotrading::portfolio::{Portfolio, Position};
 use algotrading::security_types::SecurityInfo;
@@ -16,6 +17,7 @@ mod trading_integration_tests {
             max_portfolio_exposure: 1.0,
             stop_loss_percentage: 0.02,
             take_profit_percentage: 0.04,
+            stop_loss_method: StopLossMethod::Percentage,
             max_margin_utilization: 0.70,
             min_excess_liquidity: 10000.0,
             futures_position_limit: 10.0,
@@ -27,20 +29,32 @@ mod trading_integration_tests {
             max_correlation_exposure: 0.60,
             correlation_lookback_days: 63,
             min_positions_for_erc: 3,
+            max_cluster_exposure: 0.40,
             // Transaction cost configuration
             enable_transaction_cost_optimization: true,
-            stock_commission: 1.00,
-            futures_commission: 2.50,
-            forex_commission: 0.50,
             max_acceptable_cost_bps: 50.0,
             // Position inertia configuration
             enable_position_inertia: true,
             inertia_multiplier: 2.0,
             min_position_change_value: 100.0,
             max_position_change_pct: 0.50,
+            max_daily_loss: 0.03,
+            halt_reset_hour_utc: 0,
+            min_holding_period_minutes: 0,
+            reentry_cooldown_minutes: 0,
+            drawdown_scaling: None,
+            drawdown_derisking: None,
+            auto_protective_orders: false,
+            shortable_symbols: Vec::new(),
+            max_short_quantity: 0.0,
+            working_order_timeout_seconds: 300,
         }
     }
 
+    fn test_cost_model() -> CostModel {
+        CostModel::new(CostModelConfig::default())
+    }
+
     fn create_test_signal(
         symbol: &str,
         quantity: f64,
@@ -86,7 +100,7 @@ mod trading_integration_tests {
     #[tokio::test]
     async fn test_signal_filtering_with_inertia_enabled() -> Result<()> {
         let risk_config = setup_test_risk_config();
-        let integration_layer = TradingIntegrationLayer::new(&risk_config);
+        let integration_layer = TradingIntegrationLayer::new(&risk_config, test_cost_model());
         let portfolio = setup_test_portfolio();
 
         let mut latest_prices = HashMap::new();
@@ -133,7 +147,7 @@ mod trading_integration_tests {
     #[tokio::test]
     async fn test_signal_filtering_with_strong_signal_override() -> Result<()> {
         let risk_config = setup_test_risk_config();
-        let integration_layer = TradingIntegrationLayer::new(&risk_config);
+        let integration_layer = TradingIntegrationLayer::new(&risk_config, test_cost_model());
         let portfolio = setup_test_portfolio();
 
         let mut latest_prices = HashMap::new();
@@ -161,7 +175,7 @@ mod trading_integration_tests {
         let mut risk_config = setup_test_risk_config();
         risk_config.enable_position_inertia = false; // Disable inertia to focus on cost filtering
 
-        let integration_layer = TradingIntegrationLayer::new(&risk_config);
+        let integration_layer = TradingIntegrationLayer::new(&risk_config, test_cost_model());
         let portfolio = Portfolio::new(50000.0); // Empty portfolio with $50k cash
 
         let mut latest_prices = HashMap::new();
@@ -195,7 +209,7 @@ mod trading_integration_tests {
     #[tokio::test]
     async fn test_position_reversal_always_executes() -> Result<()> {
         let risk_config = setup_test_risk_config();
-        let integration_layer = TradingIntegrationLayer::new(&risk_config);
+        let integration_layer = TradingIntegrationLayer::new(&risk_config, test_cost_model());
 
         // Create portfolio with long AAPL position
         let mut portfolio = Portfolio::new(50000.0);
@@ -229,7 +243,7 @@ mod trading_integration_tests {
     async fn test_maximum_position_change_limiting() -> Result<()> {
         let mut risk_config = setup_test_risk_config();
         risk_config.enable_transaction_cost_optimization = false; // Disable cost filtering to test position limiting
-        let integration_layer = TradingIntegrationLayer::new(&risk_config);
+        let integration_layer = TradingIntegrationLayer::new(&risk_config, test_cost_model());
         let portfolio = setup_test_portfolio();
 
         let mut latest_prices = HashMap::new();
@@ -261,7 +275,7 @@ mod trading_integration_tests {
         risk_config.enable_position_inertia = false;
         risk_config.enable_transaction_cost_optimization = false;
 
-        let integration_layer = TradingIntegrationLayer::new(&risk_config);
+        let integration_layer = TradingIntegrationLayer::new(&risk_config, test_cost_model());
         let portfolio = setup_test_portfolio();
 
         let mut latest_prices = HashMap::new();
@@ -288,7 +302,7 @@ mod trading_integration_tests {
     #[tokio::test]
     async fn test_order_cost_validation() -> Result<()> {
         let risk_config = setup_test_risk_config();
-        let integration_layer = TradingIntegrationLayer::new(&risk_config);
+        let integration_layer = TradingIntegrationLayer::new(&risk_config, test_cost_model());
 
         // Test normal cost order
         let normal_signal = create_test_signal("AAPL", 100.0, 155.0, 10.0);
@@ -310,7 +324,7 @@ mod trading_integration_tests {
     #[tokio::test]
     async fn test_transaction_cost_estimation() -> Result<()> {
         let risk_config = setup_test_risk_config();
-        let integration_layer = TradingIntegrationLayer::new(&risk_config);
+        let integration_layer = TradingIntegrationLayer::new(&risk_config, test_cost_model());
 
         let signal = create_test_signal("AAPL", 100.0, 155.0, 10.0);
         let estimated_cost = integration_layer
@@ -330,7 +344,7 @@ mod trading_integration_tests {
     #[tokio::test]
     async fn test_filter_result_metrics() -> Result<()> {
         let risk_config = setup_test_risk_config();
-        let integration_layer = TradingIntegrationLayer::new(&risk_config);
+        let integration_layer = TradingIntegrationLayer::new(&risk_config, test_cost_model());
         let portfolio = setup_test_portfolio();
 
         let mut latest_prices = HashMap::new();